@@ -1,17 +1,13 @@
 use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use std::collections::HashMap;
+use vectordb_rust::filter::parse_filter;
+use vectordb_rust::testing::BlobConfig;
+use vectordb_rust::vector::MetadataValue;
 use vectordb_rust::Collection;
-use rand::Rng;
 
+// nuages gaussiens reproductibles, un par tranche de 100 vecteurs
 fn generate_vectors(n: usize, dim: usize) -> Vec<Vec<f32>> {
-    let mut rng = rand::thread_rng();
-    (0..n)
-        .map(|_| {
-            let vec: Vec<f32> = (0..dim).map(|_| rng.gen::<f32>()).collect();
-            // normaliser
-            let norm: f32 = vec.iter().map(|x| x * x).sum::<f32>().sqrt();
-            vec.iter().map(|x| x / norm).collect()
-        })
-        .collect()
+    BlobConfig::new(n, dim, (n / 100).max(1)).with_spread(0.1).generate().embeddings
 }
 
 fn bench_linear_search(c: &mut Criterion) {
@@ -79,15 +75,49 @@ fn bench_ivf_search(c: &mut Criterion) {
     group.finish();
 }
 
+fn bench_filtered_count(c: &mut Criterion) {
+    let sizes = vec![10000, 100000];
+    let filter = parse_filter(&serde_json::json!({"lang": "fr", "year": {"$gte": 2010}})).unwrap();
+
+    let mut group = c.benchmark_group("filtered_count");
+
+    for size in sizes {
+        let ids: Vec<String> = (0..size).map(|i| format!("vec_{}", i)).collect();
+        let metadatas: Vec<HashMap<String, MetadataValue>> = (0..size)
+            .map(|i| {
+                HashMap::from([
+                    ("lang".to_string(), MetadataValue::from(["en", "fr", "de"][i % 3])),
+                    ("year".to_string(), MetadataValue::Int(2000 + (i % 25) as i64)),
+                ])
+            })
+            .collect();
+
+        let mut coll = Collection::new("test".to_string(), 4);
+        coll.add(ids, vec![vec![1.0, 0.0, 0.0, 0.0]; size], Some(metadatas)).unwrap();
+
+        group.bench_with_input(
+            BenchmarkId::from_parameter(size),
+            &size,
+            |b, _| {
+                b.iter(|| {
+                    coll.count_where(black_box(&filter))
+                });
+            },
+        );
+    }
+
+    group.finish();
+}
+
 fn bench_dot_product(c: &mut Criterion) {
-    use vectordb_rust::distance::dot_product;
+    use vectordb_rust::distance::{dot_product, dot_product_with, SimdKernel};
 
     let dims = vec![128, 512, 1280, 2048];
     let mut group = c.benchmark_group("dot_product");
 
     for dim in dims {
-        let a = generate_vectors(1, dim)[0].clone();
-        let b = generate_vectors(1, dim)[0].clone();
+        let pair = generate_vectors(2, dim);
+        let (a, b) = (pair[0].clone(), pair[1].clone());
 
         group.bench_with_input(
             BenchmarkId::from_parameter(dim),
@@ -98,10 +128,46 @@ fn bench_dot_product(c: &mut Criterion) {
                 });
             },
         );
+
+        // chaque noyau disponible sur la machine
+        for kernel in SimdKernel::available() {
+            group.bench_with_input(
+                BenchmarkId::new(kernel.name(), dim),
+                &dim,
+                |bench, _| {
+                    bench.iter(|| {
+                        dot_product_with(kernel, black_box(&a), black_box(&b))
+                    });
+                },
+            );
+        }
+    }
+
+    group.finish();
+
+    // une requête contre 1000 vecteurs, un par un puis par lots
+    use vectordb_rust::distance::dot_products;
+    let mut group = c.benchmark_group("dot_products_1000");
+    for dim in [128, 1280] {
+        let vectors = generate_vectors(1001, dim);
+        let query = &vectors[0];
+        let refs: Vec<&[f32]> = vectors[1..].iter().map(Vec::as_slice).collect();
+        let mut out = vec![0.0; refs.len()];
+
+        group.bench_with_input(BenchmarkId::new("single", dim), &dim, |bench, _| {
+            bench.iter(|| {
+                for (v, o) in refs.iter().zip(out.iter_mut()) {
+                    *o = dot_product(black_box(query), v);
+                }
+            });
+        });
+        group.bench_with_input(BenchmarkId::new("batched", dim), &dim, |bench, _| {
+            bench.iter(|| dot_products(black_box(query), &refs, &mut out));
+        });
     }
 
     group.finish();
 }
 
-criterion_group!(benches, bench_linear_search, bench_ivf_search, bench_dot_product);
+criterion_group!(benches, bench_linear_search, bench_ivf_search, bench_filtered_count, bench_dot_product);
 criterion_main!(benches);
@@ -0,0 +1,36 @@
+fn main() {
+    // liens spécifiques à la plateforme pour le module natif Node
+    #[cfg(feature = "node")]
+    napi_build::setup();
+
+    // en-tête C de l'interface ffi, généré dans OUT_DIR : le build ne modifie jamais
+    // l'arbre source. La copie versionnée include/vectordb.h est régénérée à la main
+    // (cbindgen --config cbindgen.toml --output include/vectordb.h), un avertissement
+    // signale qu'elle n'est plus à jour
+    #[cfg(feature = "ffi")]
+    {
+        println!("cargo:rerun-if-changed=src/ffi.rs");
+        println!("cargo:rerun-if-changed=cbindgen.toml");
+        println!("cargo:rerun-if-changed=include/vectordb.h");
+        let crate_dir = std::env::var("CARGO_MANIFEST_DIR").unwrap();
+        let out_dir = std::env::var("OUT_DIR").unwrap();
+        let config = cbindgen::Config::from_file(format!("{}/cbindgen.toml", crate_dir))
+            .expect("invalid cbindgen.toml");
+        let generated = format!("{}/vectordb.h", out_dir);
+        cbindgen::generate_with_config(&crate_dir, config)
+            .expect("unable to generate the C header")
+            .write_to_file(&generated);
+        let checked_in = std::fs::read(format!("{}/include/vectordb.h", crate_dir)).ok();
+        if checked_in != std::fs::read(&generated).ok() {
+            println!(
+                "cargo:warning=include/vectordb.h is out of date, regenerate it with \
+                 cbindgen --config cbindgen.toml --output include/vectordb.h (generated copy: {})",
+                generated
+            );
+        }
+    }
+
+    // scaffolding UniFFI depuis l'interface UDL
+    #[cfg(feature = "mobile")]
+    uniffi::generate_scaffolding("src/vectordb.udl").expect("invalid src/vectordb.udl");
+}
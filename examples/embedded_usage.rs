@@ -0,0 +1,84 @@
+// usage embarqué, sans serveur : Collection en mémoire avec index IVF, requêtes par
+// QueryBuilder, puis persistance par VectorDbClient et rechargement
+//
+//   cargo run --example embedded_usage
+use std::collections::HashMap;
+use vectordb_rust::collection::{CollectionConfig, IndexParams};
+use vectordb_rust::vector::MetadataValue;
+use vectordb_rust::{Collection, FilterBuilder, Result, SearchHit, VectorDbClient};
+
+const DIMENSION: usize = 16;
+
+type Metadata = HashMap<String, MetadataValue>;
+
+// vecteurs déterministes répartis autour de 4 directions, "group" = direction
+fn dataset(n: usize) -> (Vec<String>, Vec<Vec<f32>>, Vec<Metadata>) {
+    let mut ids = Vec::with_capacity(n);
+    let mut embeddings = Vec::with_capacity(n);
+    let mut metadatas = Vec::with_capacity(n);
+    for i in 0..n {
+        let group = i % 4;
+        let embedding = (0..DIMENSION)
+            .map(|d| if d % 4 == group { 1.0 } else { 0.0 } + ((i * 7919 + d * 104_729) % 1009) as f32 / 5000.0)
+            .collect();
+        ids.push(format!("item-{}", i));
+        embeddings.push(embedding);
+        metadatas.push(HashMap::from([
+            ("group".to_string(), MetadataValue::Int(group as i64)),
+            ("rank".to_string(), MetadataValue::Int(i as i64)),
+        ]));
+    }
+    (ids, embeddings, metadatas)
+}
+
+fn main() -> Result<()> {
+    // 1. collection seule, en mémoire
+    let config = CollectionConfig::new("items".to_string(), DIMENSION)
+        .with_ivf(8)
+        .with_index_params(IndexParams { n_probe: Some(2), ..Default::default() });
+    let mut coll = Collection::from_config(config.clone());
+    let (ids, embeddings, metadatas) = dataset(400);
+    coll.add(ids, embeddings.clone(), Some(metadatas.clone()))?;
+    coll.rebuild_index();
+    println!("{} vectors, index: {:?}", coll.count(), coll.index_status());
+
+    // item-249 est quasi identique à item-5 (distance ~4e-8, sous la précision f32) :
+    // leur ordre dépend de l'ordre de sommation du noyau SIMD
+    let is_top = |hits: &[SearchHit]| hits.iter().take(2).any(|h| h.id == "item-5");
+    let hits = coll.search(&embeddings[5]).k(5).run()?;
+    assert!(is_top(&hits));
+    // index contre recherche exhaustive, même requête
+    let exact = coll.search(&embeddings[5]).k(5).exact().run()?;
+    println!("top 5 via IVF: {:?}", hits.iter().map(|h| h.id.as_str()).collect::<Vec<_>>());
+    println!("top 5 exact:   {:?}", exact.iter().map(|h| h.id.as_str()).collect::<Vec<_>>());
+
+    let filter = FilterBuilder::new().eq("group", 2i64).lt("rank", 100i64).build()?;
+    let filtered = coll.search(&embeddings[6]).k(50).filter(filter).exact().run()?;
+    assert_eq!(filtered.len(), 25);
+
+    // 2. même données, persistées : survivent au client
+    let dir = std::env::temp_dir().join(format!("vectordb-example-embedded-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    {
+        let client = VectorDbClient::new(&dir)?;
+        client.create_collection_with_config(config)?;
+        let (ids, embeddings, metadatas) = dataset(400);
+        client.with_collection_mut("items", |coll| coll.add(ids, embeddings, Some(metadatas)))?;
+    }
+    let client = VectorDbClient::new(&dir)?;
+    let stats = client.stats("items")?;
+    println!("reloaded: {} vectors, ~{} bytes in memory", stats.count, stats.estimated_memory_bytes);
+    assert_eq!(stats.count, 400);
+    let hits = client.with_collection_mut("items", |coll| coll.search(&embeddings[5]).k(2).run())?;
+    assert!(is_top(&hits));
+
+    drop(client);
+    std::fs::remove_dir_all(&dir)?;
+    Ok(())
+}
+
+// exécuté par cargo test (test = true dans Cargo.toml)
+#[test]
+fn example_runs() {
+    main().unwrap();
+}
@@ -0,0 +1,92 @@
+// recherche hybride : candidats par mots-clés (index inversé tenu par l'application)
+// re-classés par similarité vectorielle avec Collection::score_ids, fusionnés avec
+// les plus proches voisins par reciprocal rank fusion. Un CollectionHandle évite la
+// recherche par nom à chaque appel
+//
+//   cargo run --example hybrid_search
+use std::collections::{HashMap, HashSet};
+use vectordb_rust::vector::MetadataValue;
+use vectordb_rust::{HashEmbedding, Result, VectorDbClient};
+
+const DIMENSION: usize = 256;
+// constante usuelle de la reciprocal rank fusion
+const RRF_K: f32 = 60.0;
+
+const DOCUMENTS: &[(&str, &str)] = &[
+    ("err-404", "HTTP 404 not found returned by the collections endpoint"),
+    ("err-409", "HTTP 409 conflict when an index rebuild is already running"),
+    ("ivf-tuning", "Tune n_probe to trade recall against query latency"),
+    ("disk-lists", "Keep inverted lists on disk for collections larger than memory"),
+    ("filters", "Metadata filters restrict the search to matching documents"),
+    ("rebuild", "Rebuild the index after many writes to refresh the centroids"),
+];
+
+fn words(text: &str) -> impl Iterator<Item = String> + '_ {
+    text.split(|c: char| !c.is_alphanumeric()).filter(|w| !w.is_empty()).map(str::to_lowercase)
+}
+
+fn main() -> Result<()> {
+    let dir = std::env::temp_dir().join(format!("vectordb-example-hybrid-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    let client = VectorDbClient::new(&dir)?;
+    client.create_collection("support".to_string(), DIMENSION)?;
+    let support = client.collection("support")?;
+
+    let provider = HashEmbedding::new(DIMENSION);
+    let ids: Vec<String> = DOCUMENTS.iter().map(|(id, _)| id.to_string()).collect();
+    let embeddings = DOCUMENTS.iter().map(|(_, text)| provider.embed_text(text)).collect();
+    let metadatas = DOCUMENTS
+        .iter()
+        .map(|(_, text)| HashMap::from([("text".to_string(), MetadataValue::from(*text))]))
+        .collect();
+    support.add(ids.clone(), embeddings, Some(metadatas))?;
+
+    // index inversé mot -> ids, côté application
+    let mut keyword_index: HashMap<String, Vec<String>> = HashMap::new();
+    for (id, text) in DOCUMENTS {
+        for word in words(text).collect::<HashSet<_>>() {
+            keyword_index.entry(word).or_default().push(id.to_string());
+        }
+    }
+
+    let question = "index rebuild conflict 409";
+    let query = provider.embed_text(question);
+
+    // 1. plus proches voisins
+    let semantic = support.query(&query, 3, None)?;
+    // 2. documents contenant un mot de la question, classés par similarité
+    let keyword_ids: Vec<String> = words(question)
+        .filter_map(|w| keyword_index.get(&w))
+        .flatten()
+        .cloned()
+        .collect();
+    let lexical = support.read(|coll| coll.score_ids(&query, &keyword_ids))??;
+
+    // 3. fusion : somme des 1 / (RRF_K + rang) sur les deux listes
+    let mut fused: HashMap<&str, f32> = HashMap::new();
+    for results in [&semantic, &lexical] {
+        for (rank, result) in results.iter().enumerate() {
+            *fused.entry(result.id.as_str()).or_default() += 1.0 / (RRF_K + rank as f32 + 1.0);
+        }
+    }
+    let mut ranking: Vec<(&str, f32)> = fused.into_iter().collect();
+    ranking.sort_by(|a, b| b.1.total_cmp(&a.1).then(a.0.cmp(b.0)));
+
+    println!("{}:", question);
+    for (id, score) in &ranking {
+        println!("  {:<11} {:.4}", id, score);
+    }
+    assert_eq!(ranking[0].0, "err-409");
+    assert!(lexical.iter().all(|r| keyword_ids.contains(&r.id)));
+
+    drop(support);
+    drop(client);
+    std::fs::remove_dir_all(&dir)?;
+    Ok(())
+}
+
+// exécuté par cargo test (test = true dans Cargo.toml)
+#[test]
+fn example_runs() {
+    main().unwrap();
+}
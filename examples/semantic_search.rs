@@ -0,0 +1,81 @@
+// recherche sémantique de bout en bout : collection persistée, documents embarqués
+// par un EmbeddingProvider, requête avec filtre et seuil de similarité.
+// HashEmbedding (proximité lexicale, sans API) tient lieu de vrai modèle ici :
+// remplacer par un fournisseur branché sur l'API de son choix
+//
+//   cargo run --example semantic_search
+use std::collections::HashMap;
+use vectordb_rust::embedding_model::HASH_EMBEDDING_MODEL;
+use vectordb_rust::vector::MetadataValue;
+use vectordb_rust::{CollectionConfig, EmbeddingProvider, FilterBuilder, HashEmbedding, Result, VectorDbClient};
+
+const DIMENSION: usize = 256;
+
+const DOCUMENTS: &[(&str, &str, &str)] = &[
+    ("rust-book", "docs", "The Rust programming language book: ownership, borrowing and lifetimes"),
+    ("tokio-guide", "docs", "Asynchronous programming in Rust with the tokio runtime"),
+    ("pasta", "recipes", "Fresh pasta with tomato sauce and basil"),
+    ("risotto", "recipes", "Mushroom risotto slowly cooked with parmesan"),
+    ("hnsw-paper", "papers", "Approximate nearest neighbor search with hierarchical navigable small world graphs"),
+    ("ivf-notes", "papers", "Inverted file index for nearest neighbor search over vector embeddings"),
+];
+
+fn main() -> Result<()> {
+    let dir = std::env::temp_dir().join(format!("vectordb-example-semantic-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    let client = VectorDbClient::new(&dir)?;
+
+    // le modèle est enregistré dans la config : un embedding d'un autre modèle est refusé
+    let provider = HashEmbedding::new(DIMENSION);
+    let config = CollectionConfig::new("articles".to_string(), DIMENSION).with_embedding_model(HASH_EMBEDDING_MODEL.to_string());
+    client.create_collection_with_config(config)?;
+
+    let texts: Vec<String> = DOCUMENTS.iter().map(|(_, _, text)| text.to_string()).collect();
+    let embeddings = provider.embed(&texts)?;
+    let ids = DOCUMENTS.iter().map(|(id, _, _)| id.to_string()).collect();
+    let metadatas = DOCUMENTS
+        .iter()
+        .map(|(_, category, text)| {
+            HashMap::from([
+                ("category".to_string(), MetadataValue::from(*category)),
+                ("text".to_string(), MetadataValue::from(*text)),
+            ])
+        })
+        .collect();
+    client.with_collection_mut("articles", |coll| coll.add(ids, embeddings, Some(metadatas)))?;
+
+    let query = provider.embed_text("nearest neighbor search index");
+    let hits = client.with_collection_mut("articles", |coll| coll.search(&query).k(3).run())?;
+    println!("nearest neighbor search index:");
+    for hit in &hits {
+        println!("  {:<12} {:.3}", hit.id, hit.score);
+    }
+    assert!(hits[0].id == "ivf-notes" || hits[0].id == "hnsw-paper");
+
+    // mêmes données, restreintes à une catégorie et à une similarité minimale
+    let filter = FilterBuilder::new().eq("category", "docs").build()?;
+    let query = provider.embed_text("rust programming");
+    let hits = client.with_collection_mut("articles", |coll| {
+        coll.search(&query).k(5).filter(filter).score_threshold(0.1).run()
+    })?;
+    println!("rust programming (category = docs):");
+    for hit in &hits {
+        println!("  {:<12} {:.3}", hit.id, hit.score);
+    }
+    assert!(!hits.is_empty());
+    assert!(hits.iter().all(|hit| hit.metadata.as_ref().unwrap()["category"] == MetadataValue::from("docs")));
+
+    // la collection est persistée : un nouveau client la relit depuis le disque
+    drop(client);
+    let client = VectorDbClient::new(&dir)?;
+    assert_eq!(client.count("articles", None)?, DOCUMENTS.len());
+
+    std::fs::remove_dir_all(&dir)?;
+    Ok(())
+}
+
+// exécuté par cargo test (test = true dans Cargo.toml)
+#[test]
+fn example_runs() {
+    main().unwrap();
+}
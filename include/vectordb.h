@@ -0,0 +1,159 @@
+/* vectordb - interface C (src/ffi.rs). Fichier généré par cbindgen, ne pas modifier. */
+
+#ifndef VECTORDB_H
+#define VECTORDB_H
+
+
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+/**
+ * Codes de retour de toutes les fonctions `vdb_*`.
+ */
+typedef enum VdbStatus {
+  VDB_STATUS_OK = 0,
+  VDB_STATUS_NULL_POINTER = 1,
+  VDB_STATUS_INVALID_ARGUMENT = 2,
+  VDB_STATUS_NOT_FOUND = 3,
+  VDB_STATUS_ALREADY_EXISTS = 4,
+  VDB_STATUS_DIMENSION_MISMATCH = 5,
+  VDB_STATUS_READ_ONLY = 6,
+  VDB_STATUS_LOCKED = 7,
+  VDB_STATUS_IO = 8,
+  VDB_STATUS_BUFFER_TOO_SMALL = 9,
+  VDB_STATUS_INTERNAL = 10,
+} VdbStatus;
+
+/**
+ * Client opaque, créé par `vdb_client_open` et libéré par `vdb_client_free`.
+ */
+typedef struct VdbClient VdbClient;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+/**
+ * Ouvre (ou crée) une base en répertoire et écrit le client dans `*out_client`.
+ *
+ * # Safety
+ * `path` doit être une chaîne C terminée par NUL et `out_client` un pointeur valide.
+ */
+enum VdbStatus vdb_client_open(const char *path, struct VdbClient **out_client);
+
+/**
+ * Comme `vdb_client_open`, en lecture seule à côté d'un processus écrivain.
+ *
+ * # Safety
+ * Mêmes contraintes que `vdb_client_open`.
+ */
+enum VdbStatus vdb_client_open_read_only(const char *path, struct VdbClient **out_client);
+
+/**
+ * Sauvegarde et libère le client ; `NULL` est accepté.
+ *
+ * # Safety
+ * `client` doit provenir de `vdb_client_open*` et ne plus être utilisé ensuite.
+ */
+void vdb_client_free(struct VdbClient *client);
+
+/**
+ * Copie le message de la dernière erreur du thread courant dans `buf` (tronqué, terminé
+ * par NUL) et renvoie sa longueur complète hors NUL.
+ *
+ * # Safety
+ * `buf` doit pointer sur au moins `buf_len` octets, ou être `NULL` avec `buf_len == 0`.
+ */
+size_t vdb_last_error(char *buf, size_t buf_len);
+
+/**
+ * Crée une collection ; `n_clusters == 0` pour une recherche linéaire sans IVF.
+ *
+ * # Safety
+ * `client` doit être valide et `name` une chaîne C terminée par NUL.
+ */
+enum VdbStatus vdb_collection_create(const struct VdbClient *client,
+                                     const char *name,
+                                     size_t dimension,
+                                     size_t n_clusters);
+
+/**
+ * Supprime une collection et ses fichiers.
+ *
+ * # Safety
+ * `client` doit être valide et `name` une chaîne C terminée par NUL.
+ */
+enum VdbStatus vdb_collection_delete(const struct VdbClient *client, const char *name);
+
+/**
+ * Ajoute `n_ids` vecteurs. `embeddings` contient `embeddings_len` flottants, soit
+ * `n_ids × dimension` à la suite ; `metadatas_json` (optionnel) contient `n_ids` objets
+ * JSON, un élément `NULL` valant une métadonnée vide.
+ *
+ * # Safety
+ * `ids` (et `metadatas_json` s'il est non nul) doivent pointer sur `n_ids` chaînes C,
+ * `embeddings` sur `embeddings_len` flottants.
+ */
+enum VdbStatus vdb_add(const struct VdbClient *client,
+                       const char *collection,
+                       const char *const *ids,
+                       size_t n_ids,
+                       const float *embeddings,
+                       size_t embeddings_len,
+                       const char *const *metadatas_json);
+
+/**
+ * Recherche les `n_results` plus proches voisins de `query` (`dimension` flottants),
+ * avec un filtre JSON optionnel (`where_json`, syntaxe de l'API REST).
+ *
+ * Les résultats sont écrits dans les tampons de l'appelant : `out_distances` reçoit
+ * `n_results` flottants, et `out_ids` `n_results` emplacements de `id_stride` octets
+ * contenant chacun un id terminé par NUL. `*out_count` reçoit le nombre de résultats.
+ * Renvoie `VDB_STATUS_BUFFER_TOO_SMALL` si un id ne tient pas dans `id_stride` octets.
+ *
+ * # Safety
+ * `query` doit pointer sur `dimension` flottants, `out_distances` sur `n_results`
+ * flottants, `out_ids` sur `n_results * id_stride` octets, `out_count` être valide.
+ */
+enum VdbStatus vdb_query(const struct VdbClient *client,
+                         const char *collection,
+                         const float *query,
+                         size_t dimension,
+                         size_t n_results,
+                         const char *where_json,
+                         char *out_ids,
+                         size_t id_stride,
+                         float *out_distances,
+                         size_t *out_count);
+
+/**
+ * Nombre de vecteurs de la collection, éventuellement filtré (`where_json` nul sinon).
+ *
+ * # Safety
+ * `client` et `out_count` doivent être valides, les chaînes terminées par NUL.
+ */
+enum VdbStatus vdb_count(const struct VdbClient *client,
+                         const char *collection,
+                         const char *where_json,
+                         size_t *out_count);
+
+/**
+ * Supprime `n_ids` vecteurs.
+ *
+ * # Safety
+ * `ids` doit pointer sur `n_ids` chaînes C terminées par NUL.
+ */
+enum VdbStatus vdb_delete(const struct VdbClient *client,
+                          const char *collection,
+                          const char *const *ids,
+                          size_t n_ids);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* VECTORDB_H */
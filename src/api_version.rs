@@ -0,0 +1,129 @@
+// versions de l'API HTTP : routes servies sous /api/v{n}/, anciens chemins sans
+// préfixe gardés comme alias dépréciés. La version retenue pour une requête est
+// transmise aux handlers, qui peuvent faire évoluer leurs payloads par version
+use crate::error::{Result, VectorDbError};
+
+// version servie par défaut sous /api/ et annoncée comme successeur
+pub const CURRENT_API_VERSION: u32 = 1;
+// version des chemins sans préfixe, figée pour ne pas casser les anciens clients
+pub const LEGACY_API_VERSION: u32 = 1;
+pub const SUPPORTED_API_VERSIONS: &[u32] = &[1];
+// chemins sans préfixe dépréciés depuis l'arrivée de /api/v1 (secondes Unix)
+pub const LEGACY_PATHS_DEPRECATED_AT: i64 = 1_792_108_800;
+// versions encore servies mais dépréciées : date de dépréciation (secondes Unix)
+// et date de retrait (HTTP-date)
+pub const DEPRECATED_API_VERSIONS: &[(u32, i64, &str)] = &[];
+
+// en-tête de requête pour choisir la version sur un chemin sans préfixe, renvoyé
+// dans chaque réponse avec la version effectivement servie
+pub const API_VERSION_HEADER: &str = "x-vectordb-api-version";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ApiVersion(pub u32);
+
+// en-têtes Deprecation / Sunset / Link à ajouter à la réponse
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Deprecation {
+    // secondes Unix, envoyé en "@<secondes>" (RFC 9745)
+    pub since: i64,
+    pub sunset: Option<&'static str>,
+    // chemin équivalent dans la version courante
+    pub successor: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Negotiated {
+    pub version: ApiVersion,
+    pub deprecation: Option<Deprecation>,
+}
+
+pub fn prefix(version: u32) -> String {
+    format!("/api/v{}", version)
+}
+
+// "/api/v1/collections" -> (1, "/collections") ; None pour un chemin sans préfixe
+pub fn split_path(path: &str) -> Option<(u32, &str)> {
+    let rest = path.strip_prefix("/api/v")?;
+    let end = rest.find('/').unwrap_or(rest.len());
+    let version = rest[..end].parse().ok()?;
+    Some((version, &rest[end..]))
+}
+
+// version servie pour `path`, d'après son préfixe ou, sans préfixe, l'en-tête
+// `requested` ("1" ou "v1", LEGACY_API_VERSION en son absence)
+pub fn negotiate(path: &str, requested: Option<&str>) -> Result<Negotiated> {
+    let requested = requested.map(parse_version).transpose()?;
+    let (version, successor) = match split_path(path) {
+        Some((version, rest)) => {
+            if let Some(header) = requested.filter(|&v| v != version) {
+                return Err(VectorDbError::UnsupportedApiVersion(format!(
+                    "{} header asks for version {} on a /api/v{} path",
+                    API_VERSION_HEADER, header, version
+                )));
+            }
+            (version, format!("{}{}", prefix(CURRENT_API_VERSION), rest))
+        }
+        None => {
+            let version = requested.unwrap_or(LEGACY_API_VERSION);
+            (version, format!("{}{}", prefix(version), path))
+        }
+    };
+    if !SUPPORTED_API_VERSIONS.contains(&version) {
+        return Err(VectorDbError::UnsupportedApiVersion(format!(
+            "version {} (supported: {:?})",
+            version, SUPPORTED_API_VERSIONS
+        )));
+    }
+
+    let legacy_path = split_path(path).is_none();
+    let deprecation = match DEPRECATED_API_VERSIONS.iter().find(|(v, _, _)| *v == version) {
+        Some(&(_, since, sunset)) => Some(Deprecation { since, sunset: Some(sunset), successor }),
+        None if legacy_path => Some(Deprecation { since: LEGACY_PATHS_DEPRECATED_AT, sunset: None, successor }),
+        None => None,
+    };
+    Ok(Negotiated { version: ApiVersion(version), deprecation })
+}
+
+fn parse_version(value: &str) -> Result<u32> {
+    let value = value.trim();
+    value
+        .strip_prefix('v')
+        .unwrap_or(value)
+        .parse()
+        .map_err(|_| VectorDbError::UnsupportedApiVersion(format!("invalid {} header: {}", API_VERSION_HEADER, value)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_negotiate_versions_and_legacy_paths() {
+        assert_eq!(split_path("/api/v1/collections/docs"), Some((1, "/collections/docs")));
+        assert_eq!(split_path("/api/v1"), Some((1, "")));
+        assert_eq!(split_path("/api/vx/collections"), None);
+        assert_eq!(split_path("/collections"), None);
+
+        // préfixé : version du chemin, pas de dépréciation
+        let versioned = negotiate("/api/v1/collections", None).unwrap();
+        assert_eq!(versioned, Negotiated { version: ApiVersion(1), deprecation: None });
+        assert!(negotiate("/api/v1/collections", Some("v1")).is_ok());
+        assert!(negotiate("/api/v1/collections", Some("2")).is_err());
+        assert!(negotiate("/api/v9/collections", None).is_err());
+
+        // sans préfixe : alias déprécié vers le chemin versionné
+        let legacy = negotiate("/collections/docs/query", None).unwrap();
+        assert_eq!(legacy.version, ApiVersion(LEGACY_API_VERSION));
+        assert_eq!(
+            legacy.deprecation,
+            Some(Deprecation {
+                since: LEGACY_PATHS_DEPRECATED_AT,
+                sunset: None,
+                successor: "/api/v1/collections/docs/query".to_string(),
+            })
+        );
+        assert_eq!(negotiate("/health", Some(" 1 ")).unwrap().version, ApiVersion(1));
+        assert!(negotiate("/health", Some("latest")).is_err());
+        assert!(negotiate("/health", Some("7")).is_err());
+    }
+}
@@ -0,0 +1,466 @@
+// réglage automatique de l'index IVF (POST /admin/autotune) : sur un jeu de requêtes
+// fourni, ou tiré des vecteurs stockés, cherche le couple n_clusters / n_probe qui
+// atteint le rappel visé avec la latence la plus faible, en grille ou par recuit
+// simulé. Les index candidats sont entraînés hors verrou sur une copie des vecteurs ;
+// la collection n'est modifiée que si `apply`
+use crate::client::VectorDbClient;
+use crate::distance::{dot_product, normalized_l2};
+use crate::error::{Result, VectorDbError};
+use crate::ivf::{trained_clusters, IVFIndex};
+use crate::topk::top_k_by;
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::time::Instant;
+
+// pénalité du recuit par point de rappel manquant, en ms de latence moyenne
+const RECALL_PENALTY_MS: f64 = 10.0;
+
+// bruit ajouté aux vecteurs tirés comme requêtes synthétiques, par composante
+const SYNTHETIC_JITTER: f32 = 0.05;
+
+fn default_n_queries() -> usize {
+    100
+}
+
+fn default_n_results() -> usize {
+    10
+}
+
+fn default_target_recall() -> f64 {
+    0.95
+}
+
+fn default_max_trials() -> usize {
+    24
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TuneStrategy {
+    // toutes les combinaisons, n_probe croissant jusqu'au premier qui atteint le rappel
+    #[default]
+    Grid,
+    // recuit simulé sur la grille, au plus max_trials évaluations
+    Annealing,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AutotuneConfig {
+    pub collection: String,
+    // requêtes d'échantillon ; absentes : n_queries vecteurs stockés, légèrement bruités
+    #[serde(default)]
+    pub queries: Option<Vec<Vec<f32>>>,
+    #[serde(default = "default_n_queries")]
+    pub n_queries: usize,
+    #[serde(default = "default_n_results")]
+    pub n_results: usize,
+    #[serde(default = "default_target_recall")]
+    pub target_recall: f64,
+    // valeurs essayées ; vides : √n / 2, √n et 2√n clusters, puissances de 2 pour
+    // n_probe, plus la configuration courante. n_clusters ramené au plus à n / 10,
+    // nombre de clusters que l'index entraîne réellement (voir ivf::trained_clusters)
+    #[serde(default)]
+    pub n_clusters: Vec<usize>,
+    #[serde(default)]
+    pub n_probe: Vec<usize>,
+    #[serde(default)]
+    pub strategy: TuneStrategy,
+    #[serde(default = "default_max_trials")]
+    pub max_trials: usize,
+    // graine des tirages (requêtes synthétiques, recuit) ; aléatoire sinon
+    #[serde(default)]
+    pub seed: Option<u64>,
+    // applique la meilleure configuration : n_probe tout de suite, rebuild si
+    // n_clusters change
+    #[serde(default)]
+    pub apply: bool,
+}
+
+impl AutotuneConfig {
+    pub fn new(collection: String) -> Self {
+        Self {
+            collection,
+            queries: None,
+            n_queries: default_n_queries(),
+            n_results: default_n_results(),
+            target_recall: default_target_recall(),
+            n_clusters: Vec::new(),
+            n_probe: Vec::new(),
+            strategy: TuneStrategy::default(),
+            max_trials: default_max_trials(),
+            seed: None,
+            apply: false,
+        }
+    }
+
+    fn validate(&self, dimension: usize) -> Result<()> {
+        let invalid = |msg: &str| Err(VectorDbError::InvalidConfig(format!("autotune: {}", msg)));
+        if !(self.target_recall > 0.0 && self.target_recall <= 1.0) {
+            return invalid("target_recall must be in (0, 1]");
+        }
+        if self.n_results == 0 || self.n_queries == 0 || self.max_trials == 0 {
+            return invalid("n_results, n_queries and max_trials must be > 0");
+        }
+        if self.n_clusters.contains(&0) || self.n_probe.contains(&0) {
+            return invalid("n_clusters and n_probe values must be > 0");
+        }
+        if let Some(ref queries) = self.queries {
+            if queries.is_empty() {
+                return invalid("queries must not be empty");
+            }
+            if let Some(q) = queries.iter().find(|q| q.len() != dimension) {
+                return Err(VectorDbError::DimensionMismatch { expected: dimension, actual: q.len() });
+            }
+        }
+        Ok(())
+    }
+}
+
+// une configuration évaluée
+#[derive(Debug, Clone, Serialize)]
+pub struct TuneTrial {
+    pub n_clusters: usize,
+    // centroïdes de l'index entraîné
+    pub centroids: usize,
+    pub n_probe: usize,
+    pub recall: f64,
+    pub mean_latency_ms: f64,
+    // vecteurs comparés par requête, en moyenne
+    pub mean_candidates: f64,
+}
+
+impl TuneTrial {
+    fn meets(&self, target: f64) -> bool {
+        self.recall >= target
+    }
+
+    // énergie du recuit : latence, pénalisée sous le rappel visé
+    fn energy(&self, target: f64) -> f64 {
+        self.mean_latency_ms + RECALL_PENALTY_MS * 100.0 * (target - self.recall).max(0.0)
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AutotuneReport {
+    pub collection: String,
+    pub vectors: usize,
+    pub queries: usize,
+    pub target_recall: f64,
+    pub strategy: TuneStrategy,
+    // configuration de la collection avant le réglage
+    pub current: TuneTrial,
+    // dans l'ordre d'évaluation
+    pub trials: Vec<TuneTrial>,
+    // la plus rapide atteignant le rappel visé ; None si aucune
+    pub best: Option<TuneTrial>,
+    pub applied: bool,
+    pub elapsed_secs: f64,
+}
+
+// index entraînés par n_clusters et vérité terrain exhaustive des requêtes
+struct Evaluator<'a> {
+    data: &'a [(String, Vec<f32>)],
+    embeddings: HashMap<&'a str, &'a [f32]>,
+    queries: Vec<Vec<f32>>,
+    truth: Vec<HashSet<&'a str>>,
+    n_results: usize,
+    coarse_groups: Option<usize>,
+    indexes: HashMap<usize, IVFIndex>,
+    trials: Vec<TuneTrial>,
+    seen: HashMap<(usize, usize), usize>,
+}
+
+impl<'a> Evaluator<'a> {
+    fn new(data: &'a [(String, Vec<f32>)], queries: Vec<Vec<f32>>, n_results: usize, coarse_groups: Option<usize>) -> Self {
+        let truth = queries
+            .par_iter()
+            .map(|q| {
+                let scored = data.iter().map(|(id, v)| (id.as_str(), dot_product(q, v)));
+                top_k_by(scored, n_results, |a, b| b.1.total_cmp(&a.1)).into_iter().map(|(id, _)| id).collect()
+            })
+            .collect();
+        Self {
+            data,
+            embeddings: data.iter().map(|(id, v)| (id.as_str(), v.as_slice())).collect(),
+            queries,
+            truth,
+            n_results,
+            coarse_groups,
+            indexes: HashMap::new(),
+            trials: Vec::new(),
+            seen: HashMap::new(),
+        }
+    }
+
+    // mesure séquentielle, pour que les latences ne dépendent pas du parallélisme
+    fn evaluate(&mut self, n_clusters: usize, n_probe: usize) -> TuneTrial {
+        let n_clusters = trained_clusters(n_clusters, self.data.len());
+        let (data, coarse_groups) = (self.data, self.coarse_groups);
+        let index = self.indexes.entry(n_clusters).or_insert_with(|| {
+            let mut index = IVFIndex::new(n_clusters).with_coarse_groups(coarse_groups);
+            index.build(data);
+            index
+        });
+        let centroids = index.centroids.len();
+        let n_probe = n_probe.min(centroids);
+        if let Some(&i) = self.seen.get(&(n_clusters, n_probe)) {
+            return self.trials[i].clone();
+        }
+        index.n_probe = n_probe;
+
+        let (mut found, mut expected, mut candidates) = (0, 0, 0);
+        let start = Instant::now();
+        for (query, truth) in self.queries.iter().zip(&self.truth) {
+            let ids = index.search_candidates(query);
+            candidates += ids.len();
+            let scored = ids.iter().filter_map(|id| self.embeddings.get(id.as_str()).map(|v| (id, dot_product(query, v))));
+            let hits = top_k_by(scored, self.n_results, |a, b| b.1.total_cmp(&a.1));
+            found += hits.iter().filter(|(id, _)| truth.contains(id.as_str())).count();
+            expected += truth.len();
+        }
+        let n = self.queries.len() as f64;
+        let trial = TuneTrial {
+            n_clusters,
+            centroids,
+            n_probe,
+            recall: if expected == 0 { 1.0 } else { found as f64 / expected as f64 },
+            mean_latency_ms: start.elapsed().as_secs_f64() * 1000.0 / n,
+            mean_candidates: candidates as f64 / n,
+        };
+        self.seen.insert((n_clusters, n_probe), self.trials.len());
+        self.trials.push(trial.clone());
+        trial
+    }
+
+    // n_probe croissant : au-delà du premier qui atteint le rappel, seulement plus lent
+    fn grid(&mut self, clusters: &[usize], probes: &[usize], target: f64) {
+        for &n_clusters in clusters {
+            for &n_probe in probes.iter().filter(|&&p| p <= n_clusters) {
+                if self.evaluate(n_clusters, n_probe).meets(target) {
+                    break;
+                }
+            }
+        }
+    }
+
+    // voisin : un pas sur l'un des deux axes de la grille ; température décroissante
+    // géométriquement jusqu'au centième de l'énergie de départ
+    fn anneal(&mut self, clusters: &[usize], probes: &[usize], target: f64, max_trials: usize, rng: &mut StdRng) {
+        let mut state = (clusters.len() / 2, probes.len() / 2);
+        let mut energy = self.evaluate(clusters[state.0], probes[state.1]).energy(target);
+        let mut temperature = energy.max(f64::EPSILON);
+        let cooling = 0.01f64.powf(1.0 / max_trials as f64);
+
+        // les configurations déjà vues ne comptent pas dans max_trials
+        for _ in 0..max_trials * 4 {
+            if self.trials.len() >= max_trials {
+                break;
+            }
+            let step = |i: usize, len: usize, rng: &mut StdRng| {
+                if rng.gen_bool(0.5) { (i + 1).min(len - 1) } else { i.saturating_sub(1) }
+            };
+            let next = if rng.gen_bool(0.5) {
+                (step(state.0, clusters.len(), rng), state.1)
+            } else {
+                (state.0, step(state.1, probes.len(), rng))
+            };
+            let candidate = self.evaluate(clusters[next.0], probes[next.1]).energy(target);
+            if candidate <= energy || rng.gen::<f64>() < ((energy - candidate) / temperature).exp() {
+                state = next;
+                energy = candidate;
+            }
+            temperature *= cooling;
+        }
+    }
+
+    fn best(&self, target: f64) -> Option<TuneTrial> {
+        self.trials
+            .iter()
+            .filter(|t| t.meets(target))
+            .min_by(|a, b| {
+                a.mean_latency_ms.total_cmp(&b.mean_latency_ms).then(a.mean_candidates.total_cmp(&b.mean_candidates))
+            })
+            .cloned()
+    }
+}
+
+// valeurs essayées, triées et sans doublon ; `current` toujours comprise
+fn candidates(given: &[usize], defaults: impl IntoIterator<Item = usize>, current: usize, max: usize) -> Vec<usize> {
+    let mut values: Vec<usize> = if given.is_empty() {
+        defaults.into_iter().map(|v| v.clamp(1, max)).collect()
+    } else {
+        given.iter().map(|v| (*v).clamp(1, max)).collect()
+    };
+    values.push(current.clamp(1, max));
+    values.sort_unstable();
+    values.dedup();
+    values
+}
+
+pub fn run_autotune(client: &VectorDbClient, config: AutotuneConfig) -> Result<AutotuneReport> {
+    let start = Instant::now();
+    if config.apply && client.is_read_only() {
+        return Err(VectorDbError::ReadOnly(config.collection.clone()));
+    }
+    let (data, dimension, current_clusters, current_probe, coarse_groups) =
+        client.with_collection(&config.collection, |coll| {
+            let n_probe = match coll.stats().index_info {
+                Some(info) if coll.config.use_ivf => info.n_probe,
+                _ => return Err(VectorDbError::InvalidConfig("Collection does not use IVF index".to_string())),
+            };
+            Ok((
+                coll.index_data()?,
+                coll.config.dimension,
+                coll.config.n_clusters,
+                n_probe,
+                coll.config.index_params.coarse_groups,
+            ))
+        })??;
+    config.validate(dimension)?;
+    if data.is_empty() {
+        return Err(VectorDbError::InvalidConfig(format!(
+            "autotune: collection '{}' is empty",
+            config.collection
+        )));
+    }
+
+    let mut rng = match config.seed {
+        Some(seed) => StdRng::seed_from_u64(seed),
+        None => StdRng::from_entropy(),
+    };
+    let queries: Vec<Vec<f32>> = match config.queries {
+        Some(ref queries) => queries.iter().map(|q| normalized_l2(q)).collect(),
+        None => data
+            .choose_multiple(&mut rng, config.n_queries)
+            .map(|(_, v)| {
+                let noisy: Vec<f32> =
+                    v.iter().map(|x| x + rng.gen_range(-SYNTHETIC_JITTER..SYNTHETIC_JITTER)).collect();
+                normalized_l2(&noisy)
+            })
+            .collect(),
+    };
+
+    let root = (data.len() as f64).sqrt().round() as usize;
+    let max_trained = trained_clusters(usize::MAX, data.len());
+    let clusters = candidates(&config.n_clusters, [root / 2, root, root * 2], current_clusters, max_trained);
+    let max_clusters = clusters.last().copied().unwrap_or(1);
+    let powers = std::iter::successors(Some(1usize), |p| p.checked_mul(2)).take_while(|&p| p <= max_clusters);
+    let probes = candidates(&config.n_probe, powers, current_probe, max_clusters);
+
+    let mut evaluator = Evaluator::new(&data, queries, config.n_results, coarse_groups);
+    let current = evaluator.evaluate(current_clusters, current_probe);
+    match config.strategy {
+        TuneStrategy::Grid => evaluator.grid(&clusters, &probes, config.target_recall),
+        TuneStrategy::Annealing => {
+            evaluator.anneal(&clusters, &probes, config.target_recall, config.max_trials, &mut rng)
+        }
+    }
+    let best = evaluator.best(config.target_recall);
+    tracing::info!(
+        collection = %config.collection,
+        trials = evaluator.trials.len(),
+        best = ?best.as_ref().map(|b| (b.n_clusters, b.n_probe)),
+        "Autotune finished"
+    );
+
+    let mut applied = false;
+    if let (true, Some(best)) = (config.apply, best.as_ref()) {
+        let name = &config.collection;
+        if client.with_collection_mut(name, |coll| coll.set_n_clusters(best.n_clusters))? {
+            client.rebuild_index_background(name)?;
+        }
+        client.with_collection_mut(name, |coll| {
+            let mut params = coll.config.index_params.clone();
+            params.n_probe = Some(best.n_probe);
+            coll.set_index_params(params)
+        })?;
+        applied = true;
+    }
+
+    Ok(AutotuneReport {
+        collection: config.collection,
+        vectors: data.len(),
+        queries: evaluator.queries.len(),
+        target_recall: config.target_recall,
+        strategy: config.strategy,
+        current,
+        trials: evaluator.trials,
+        best,
+        applied,
+        elapsed_secs: start.elapsed().as_secs_f64(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{BlobConfig, TempDir};
+
+    #[test]
+    fn test_autotune_finds_and_applies_config() {
+        let dir = TempDir::new("autotune");
+        let client = VectorDbClient::new(&dir).unwrap();
+        client
+            .create_collection_with_config(crate::CollectionConfig::new("docs".into(), 16).with_ivf(4))
+            .unwrap();
+        let dataset = BlobConfig::new(600, 16, 12).with_spread(0.1).generate();
+        client.with_collection_mut("docs", |coll| dataset.populate(coll)).unwrap();
+
+        let mut config = AutotuneConfig::new("docs".into());
+        config.n_queries = 40;
+        config.target_recall = 0.9;
+        config.seed = Some(7);
+        let report = run_autotune(&client, config.clone()).unwrap();
+        let best = report.best.clone().unwrap();
+        assert!(best.recall >= 0.9);
+        assert!(report.trials.iter().all(|t| t.n_probe <= t.centroids && t.centroids == t.n_clusters));
+        // sonder tous les clusters retrouve les voisins exacts
+        assert!(report.trials.iter().filter(|t| t.n_probe == t.centroids).all(|t| t.recall == 1.0));
+        assert!(!report.applied);
+        assert_eq!(client.with_collection("docs", |c| c.config.n_clusters).unwrap(), 4);
+
+        config.strategy = TuneStrategy::Annealing;
+        config.max_trials = 6;
+        config.apply = true;
+        let report = run_autotune(&client, config).unwrap();
+        assert!(report.trials.len() <= 6);
+        let best = report.best.unwrap();
+        assert!(report.applied);
+        let (n_clusters, n_probe) =
+            client.with_collection("docs", |c| (c.config.n_clusters, c.config.index_params.n_probe)).unwrap();
+        assert_eq!((n_clusters, n_probe), (best.n_clusters, Some(best.n_probe)));
+
+        let mut invalid = AutotuneConfig::new("docs".into());
+        invalid.queries = Some(vec![vec![1.0; 3]]);
+        assert!(matches!(run_autotune(&client, invalid), Err(VectorDbError::DimensionMismatch { .. })));
+    }
+
+    // petite collection : pas plus de clusters que l'index n'en entraîne (n / 10)
+    #[test]
+    fn test_autotune_clamps_to_trained_clusters() {
+        let dir = TempDir::new("autotune-small");
+        let client = VectorDbClient::new(&dir).unwrap();
+        client
+            .create_collection_with_config(crate::CollectionConfig::new("small".into(), 8).with_ivf(4))
+            .unwrap();
+        let dataset = BlobConfig::new(150, 8, 5).generate();
+        client.with_collection_mut("small", |coll| dataset.populate(coll)).unwrap();
+
+        let mut config = AutotuneConfig::new("small".into());
+        config.n_clusters = vec![4, 64, 500];
+        config.n_probe = vec![1, 32];
+        config.n_queries = 20;
+        config.seed = Some(3);
+        config.apply = true;
+        let report = run_autotune(&client, config).unwrap();
+        assert!(report.trials.iter().all(|t| t.n_clusters <= 15 && t.centroids == t.n_clusters));
+        assert!(report.trials.iter().all(|t| t.n_probe <= t.centroids));
+        let best = report.best.unwrap();
+        assert!(best.n_clusters <= 15);
+        assert_eq!(client.with_collection("small", |c| c.config.n_clusters).unwrap(), best.n_clusters);
+    }
+}
@@ -0,0 +1,300 @@
+use crate::client::VectorDbClient;
+use crate::error::{Result, VectorDbError};
+use crate::vector::MetadataValue;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, SyncSender, TrySendError};
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+// micro-batching des ajouts : les producteurs envoient des lignes une par une, un
+// thread les regroupe par collection et les écrit en un seul appel quand le lot
+// atteint max_rows ou que sa plus vieille ligne a attendu max_delay. La file entre
+// producteurs et thread est bornée : add bloque quand elle est pleine
+
+// les lots sont écrits dans les collections du VectorDbClient local ; pas de
+// destination distante tant que le serveur n'a pas de client Rust
+
+#[derive(Default)]
+struct AddBatch {
+    ids: Vec<String>,
+    embeddings: Vec<Vec<f32>>,
+    metadatas: Vec<HashMap<String, MetadataValue>>,
+}
+
+impl AddBatch {
+    fn write(self, client: &VectorDbClient, collection: &str) -> Result<()> {
+        client.with_collection_mut(collection, |coll| {
+            coll.add_with_source(self.ids, self.embeddings, Some(self.metadatas), "batcher")
+        })
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct BatcherConfig {
+    // lignes par lot, toutes collections confondues
+    pub max_rows: usize,
+    // attente maximale d'une ligne avant envoi de son lot
+    pub max_delay: Duration,
+    // lignes en file avant que add ne bloque (try_add échoue)
+    pub queue_capacity: usize,
+}
+
+impl Default for BatcherConfig {
+    fn default() -> Self {
+        Self {
+            max_rows: 256,
+            max_delay: Duration::from_millis(20),
+            queue_capacity: 4096,
+        }
+    }
+}
+
+impl BatcherConfig {
+    pub fn with_max_rows(mut self, max_rows: usize) -> Self {
+        self.max_rows = max_rows.max(1);
+        self
+    }
+
+    pub fn with_max_delay(mut self, max_delay: Duration) -> Self {
+        self.max_delay = max_delay;
+        self
+    }
+
+    pub fn with_queue_capacity(mut self, queue_capacity: usize) -> Self {
+        self.queue_capacity = queue_capacity.max(1);
+        self
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct BatcherStats {
+    pub rows_sent: usize,
+    pub batches: usize,
+    // lignes des lots refusés par la collection
+    pub rows_failed: usize,
+}
+
+struct Row {
+    collection: String,
+    id: String,
+    embedding: Vec<f32>,
+    metadata: HashMap<String, MetadataValue>,
+}
+
+enum Message {
+    Row(Row),
+    // envoie tout ce qui est en attente puis répond avec la première erreur depuis
+    // le flush précédent
+    Flush(mpsc::Sender<Result<()>>),
+}
+
+pub struct AddBatcher {
+    sender: Option<SyncSender<Message>>,
+    handle: Option<JoinHandle<()>>,
+    stats: Arc<Mutex<BatcherStats>>,
+}
+
+impl AddBatcher {
+    pub fn spawn(client: Arc<VectorDbClient>, config: BatcherConfig) -> Self {
+        let (sender, receiver) = mpsc::sync_channel(config.queue_capacity);
+        let stats = Arc::new(Mutex::new(BatcherStats::default()));
+        let worker_stats = stats.clone();
+        let handle = std::thread::spawn(move || run(&client, &config, receiver, &worker_stats));
+        Self { sender: Some(sender), handle: Some(handle), stats }
+    }
+
+    // bloque tant que la file est pleine
+    pub fn add(
+        &self,
+        collection: &str,
+        id: String,
+        embedding: Vec<f32>,
+        metadata: HashMap<String, MetadataValue>,
+    ) -> Result<()> {
+        let row = Row { collection: collection.to_string(), id, embedding, metadata };
+        self.sender().send(Message::Row(row)).map_err(|_| stopped())
+    }
+
+    // comme add, sans attendre : LimitExceeded si la file est pleine
+    pub fn try_add(
+        &self,
+        collection: &str,
+        id: String,
+        embedding: Vec<f32>,
+        metadata: HashMap<String, MetadataValue>,
+    ) -> Result<()> {
+        let row = Row { collection: collection.to_string(), id, embedding, metadata };
+        match self.sender().try_send(Message::Row(row)) {
+            Ok(()) => Ok(()),
+            Err(TrySendError::Full(_)) => Err(VectorDbError::LimitExceeded("batcher queue is full".to_string())),
+            Err(TrySendError::Disconnected(_)) => Err(stopped()),
+        }
+    }
+
+    // envoie les lignes en attente et renvoie la première erreur d'envoi survenue
+    // depuis le flush précédent
+    pub fn flush(&self) -> Result<()> {
+        let (reply, response) = mpsc::channel();
+        self.sender().send(Message::Flush(reply)).map_err(|_| stopped())?;
+        response.recv().map_err(|_| stopped())?
+    }
+
+    pub fn stats(&self) -> BatcherStats {
+        self.stats.lock().unwrap().clone()
+    }
+
+    // flush puis arrêt du thread
+    pub fn close(mut self) -> Result<BatcherStats> {
+        let flushed = self.flush();
+        self.shutdown();
+        flushed.map(|_| self.stats())
+    }
+
+    fn sender(&self) -> &SyncSender<Message> {
+        self.sender.as_ref().expect("batcher is running until dropped")
+    }
+
+    fn shutdown(&mut self) {
+        self.sender.take();
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+// les lignes encore en file sont envoyées avant l'arrêt
+impl Drop for AddBatcher {
+    fn drop(&mut self) {
+        self.shutdown();
+    }
+}
+
+fn stopped() -> VectorDbError {
+    VectorDbError::Io(std::io::Error::other("batcher thread stopped"))
+}
+
+fn run(client: &VectorDbClient, config: &BatcherConfig, receiver: Receiver<Message>, stats: &Mutex<BatcherStats>) {
+    let mut pending: HashMap<String, AddBatch> = HashMap::new();
+    let mut n_pending = 0;
+    // arrivée de la plus vieille ligne en attente
+    let mut oldest: Option<Instant> = None;
+    let mut first_error: Option<VectorDbError> = None;
+
+    let send_all = |pending: &mut HashMap<String, AddBatch>, first_error: &mut Option<VectorDbError>| {
+        for (collection, batch) in pending.drain() {
+            let n = batch.ids.len();
+            let result = batch.write(client, &collection);
+            let mut stats = stats.lock().unwrap();
+            match result {
+                Ok(()) => {
+                    stats.rows_sent += n;
+                    stats.batches += 1;
+                }
+                Err(e) => {
+                    tracing::warn!(collection = %collection, rows = n, error = %e, "Batched add failed");
+                    stats.rows_failed += n;
+                    first_error.get_or_insert(e);
+                }
+            }
+        }
+    };
+
+    loop {
+        let message = match oldest {
+            Some(since) => match receiver.recv_timeout(config.max_delay.saturating_sub(since.elapsed())) {
+                Ok(message) => Some(message),
+                Err(RecvTimeoutError::Timeout) => None,
+                Err(RecvTimeoutError::Disconnected) => break,
+            },
+            None => match receiver.recv() {
+                Ok(message) => Some(message),
+                Err(_) => break,
+            },
+        };
+
+        match message {
+            Some(Message::Row(row)) => {
+                let batch = pending.entry(row.collection).or_default();
+                batch.ids.push(row.id);
+                batch.embeddings.push(row.embedding);
+                batch.metadatas.push(row.metadata);
+                n_pending += 1;
+                oldest.get_or_insert_with(Instant::now);
+                if n_pending < config.max_rows {
+                    continue;
+                }
+            }
+            Some(Message::Flush(reply)) => {
+                send_all(&mut pending, &mut first_error);
+                (n_pending, oldest) = (0, None);
+                let _ = reply.send(first_error.take().map_or(Ok(()), Err));
+                continue;
+            }
+            // max_delay écoulé
+            None => {}
+        }
+        send_all(&mut pending, &mut first_error);
+        (n_pending, oldest) = (0, None);
+    }
+    send_all(&mut pending, &mut first_error);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::TempDir;
+
+    #[test]
+    fn test_batches_by_size_delay_and_flush() {
+        let dir = TempDir::new("batcher_flush");
+        let client = Arc::new(VectorDbClient::new(&dir).unwrap());
+        client.create_collection("docs".to_string(), 2).unwrap();
+        let config = BatcherConfig::default()
+            .with_max_rows(4)
+            .with_max_delay(Duration::from_millis(50))
+            .with_queue_capacity(2);
+        let batcher = AddBatcher::spawn(client.clone(), config);
+        let add = |i: usize| batcher.add("docs", format!("v{}", i), vec![i as f32, 1.0], HashMap::new());
+
+        // max_rows atteint : un lot de 4 sans attendre le délai
+        (0..4).try_for_each(add).unwrap();
+        batcher.flush().unwrap();
+        assert_eq!(batcher.stats().batches, 1);
+        assert_eq!(client.count("docs", None).unwrap(), 4);
+
+        // délai écoulé : lot partiel envoyé sans flush
+        add(4).unwrap();
+        std::thread::sleep(Duration::from_millis(200));
+        assert_eq!(batcher.stats().batches, 2);
+
+        // collection absente : erreur rendue au flush suivant, puis oubliée
+        batcher.add("missing", "x".to_string(), vec![1.0, 0.0], HashMap::new()).unwrap();
+        assert!(batcher.flush().is_err());
+        assert!(batcher.flush().is_ok());
+
+        add(5).unwrap();
+        let stats = batcher.close().unwrap();
+        assert_eq!((stats.rows_sent, stats.batches, stats.rows_failed), (6, 3, 1));
+        assert_eq!(client.count("docs", None).unwrap(), 6);
+    }
+
+    #[test]
+    fn test_batcher_writes_to_client() {
+        let dir = TempDir::new("batcher");
+        let client = Arc::new(VectorDbClient::new(&dir).unwrap());
+        client.create_collection("docs".to_string(), 2).unwrap();
+
+        let batcher = AddBatcher::spawn(client.clone(), BatcherConfig::default().with_max_rows(10));
+        for i in 0..25 {
+            batcher.add("docs", format!("v{}", i), vec![1.0, i as f32], HashMap::new()).unwrap();
+        }
+        let stats = batcher.close().unwrap();
+        assert_eq!(stats.rows_sent, 25);
+        assert!(stats.batches >= 3);
+        assert_eq!(client.count("docs", None).unwrap(), 25);
+
+        drop(client);
+    }
+}
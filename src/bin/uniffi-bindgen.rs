@@ -0,0 +1,4 @@
+// cargo run --features uniffi-bindgen --bin uniffi-bindgen -- generate --library <lib> --language kotlin --out-dir out
+fn main() {
+    uniffi::uniffi_bindgen_main()
+}
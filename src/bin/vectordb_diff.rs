@@ -0,0 +1,119 @@
+use std::process::ExitCode;
+use vectordb_rust::diff::{DiffOptions, DiffSource};
+use vectordb_rust::VectorDbClient;
+
+const USAGE: &str = "Usage:
+  vectordb_diff <gauche> <droite> [--db CHEMIN] [--tolerance T] [--max-ids N] [--json]
+
+Compare deux collections de même dimension : ids ajoutés, supprimés et modifiés, et
+dérive de l'embedding de chaque id modifié (distance cosinus). Chaque côté est un nom
+de collection de la base, ou NOM@FICHIER.vdb pour la collection d'un instantané.
+Exemple, avant de basculer sur un ré-embedding :
+  vectordb_diff docs@/mnt/snapshots/snapshot-20240501T120000000Z.vdb docs_v2
+--tolerance : distance sous laquelle deux embeddings sont identiques (1e-6) ;
+--max-ids : ids détaillés par liste (20 par défaut, 0 pour tous) ; --json : rapport
+complet en JSON. --db vaut par défaut $VECTORDB_PATH ou ./vector_db, ouvert en
+lecture seule : le serveur peut tourner.";
+
+// ids modifiés affichés sans --json ni --max-ids
+const DEFAULT_MAX_IDS: usize = 20;
+
+struct Args {
+    left: DiffSource,
+    right: DiffSource,
+    db: String,
+    options: DiffOptions,
+    json: bool,
+}
+
+fn parse_source(arg: &str) -> DiffSource {
+    match arg.split_once('@') {
+        Some((name, path)) => DiffSource::snapshot(path, name),
+        None => DiffSource::collection(arg),
+    }
+}
+
+fn parse_args() -> Result<Args, String> {
+    let mut args = std::env::args().skip(1);
+    let left = parse_source(&args.next().ok_or("missing left collection")?);
+    let right = parse_source(&args.next().ok_or("missing right collection")?);
+
+    let mut parsed = Args {
+        left,
+        right,
+        db: std::env::var("VECTORDB_PATH").unwrap_or("./vector_db".into()),
+        options: DiffOptions { max_ids: DEFAULT_MAX_IDS, ..DiffOptions::default() },
+        json: false,
+    };
+
+    while let Some(flag) = args.next() {
+        if flag == "--json" {
+            parsed.json = true;
+            continue;
+        }
+        let value = args.next().ok_or(format!("missing value for {}", flag))?;
+        match flag.as_str() {
+            "--db" => parsed.db = value,
+            "--tolerance" => {
+                parsed.options.tolerance = value.parse().map_err(|_| format!("invalid --tolerance {}", value))?;
+            }
+            "--max-ids" => {
+                parsed.options.max_ids = value.parse().map_err(|_| format!("invalid --max-ids {}", value))?;
+            }
+            other => return Err(format!("unknown option {}", other)),
+        }
+    }
+
+    Ok(parsed)
+}
+
+fn run(args: Args) -> Result<String, String> {
+    let client = VectorDbClient::open_read_only(&args.db).map_err(|e| e.to_string())?;
+    let diff = client.diff_collections(&args.left, &args.right, &args.options).map_err(|e| e.to_string())?;
+    if args.json {
+        return serde_json::to_string_pretty(&diff).map_err(|e| e.to_string());
+    }
+
+    let mut summary = format!(
+        "{} -> {} (dimension {})\n{} added, {} removed, {} changed, {} unchanged",
+        diff.left, diff.right, diff.dimension, diff.total_added, diff.total_removed, diff.total_changed, diff.unchanged
+    );
+    if let Some(drift) = diff.drift {
+        summary.push_str(&format!(
+            "\nembedding drift: mean {:.4}, p50 {:.4}, p95 {:.4}, max {:.4}",
+            drift.mean, drift.p50, drift.p95, drift.max
+        ));
+    }
+    for changed in &diff.changed {
+        let metadata = if changed.metadata_changed { " (metadata changed)" } else { "" };
+        summary.push_str(&format!("\n  ~ {} {:.4}{}", changed.id, changed.distance, metadata));
+    }
+    for id in &diff.added {
+        summary.push_str(&format!("\n  + {}", id));
+    }
+    for id in &diff.removed {
+        summary.push_str(&format!("\n  - {}", id));
+    }
+    Ok(summary)
+}
+
+fn main() -> ExitCode {
+    let args = match parse_args() {
+        Ok(args) => args,
+        Err(e) => {
+            eprintln!("{}\n\n{}", e, USAGE);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    match run(args) {
+        Ok(summary) => {
+            println!("{}", summary);
+            ExitCode::SUCCESS
+        }
+        Err(e) => {
+            eprintln!("Diff failed: {}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
@@ -0,0 +1,99 @@
+use std::path::PathBuf;
+use std::process::ExitCode;
+use vectordb_rust::VectorDbClient;
+
+const USAGE: &str = "Usage:
+  vectordb_import npy <fichier.npy|.npz> --collection NOM [--ids FICHIER] [--array NOM] [--db CHEMIN]
+  vectordb_import faiss <fichier.index> --collection NOM [--db CHEMIN]
+  vectordb_import chroma <répertoire chroma> [--db CHEMIN]
+
+--db vaut par défaut $VECTORDB_PATH ou ./vector_db ; le serveur doit être arrêté
+(un seul processus écrivain par répertoire).";
+
+struct Args {
+    command: String,
+    source: PathBuf,
+    collection: Option<String>,
+    ids: Option<PathBuf>,
+    array: Option<String>,
+    db: String,
+}
+
+fn parse_args() -> Result<Args, String> {
+    let mut args = std::env::args().skip(1);
+    let command = args.next().ok_or("missing command")?;
+    let source = PathBuf::from(args.next().ok_or("missing source path")?);
+
+    let mut parsed = Args {
+        command,
+        source,
+        collection: None,
+        ids: None,
+        array: None,
+        db: std::env::var("VECTORDB_PATH").unwrap_or("./vector_db".into()),
+    };
+
+    while let Some(flag) = args.next() {
+        let value = args.next().ok_or(format!("missing value for {}", flag))?;
+        match flag.as_str() {
+            "--collection" => parsed.collection = Some(value),
+            "--ids" => parsed.ids = Some(PathBuf::from(value)),
+            "--array" => parsed.array = Some(value),
+            "--db" => parsed.db = value,
+            other => return Err(format!("unknown option {}", other)),
+        }
+    }
+
+    Ok(parsed)
+}
+
+fn run(args: Args) -> Result<String, String> {
+    let client = VectorDbClient::new(&args.db).map_err(|e| e.to_string())?;
+    let collection = || args.collection.clone().ok_or("--collection is required".to_string());
+
+    match args.command.as_str() {
+        "npy" => {
+            let name = collection()?;
+            let count = client
+                .import_npy(&name, &args.source, args.ids.as_deref(), args.array.as_deref())
+                .map_err(|e| e.to_string())?;
+            Ok(format!("{} embeddings imported into '{}'", count, name))
+        }
+        "faiss" => {
+            let name = collection()?;
+            let count = client.import_faiss(&args.source, &name).map_err(|e| e.to_string())?;
+            Ok(format!("{} vectors imported into '{}'", count, name))
+        }
+        #[cfg(feature = "chroma-import")]
+        "chroma" => {
+            let imported = client.import_chroma(&args.source).map_err(|e| e.to_string())?;
+            Ok(imported
+                .iter()
+                .map(|(name, count)| format!("{}: {} vectors", name, count))
+                .collect::<Vec<_>>()
+                .join("\n"))
+        }
+        other => Err(format!("unknown command {}", other)),
+    }
+}
+
+fn main() -> ExitCode {
+    let args = match parse_args() {
+        Ok(args) => args,
+        Err(e) => {
+            eprintln!("{}\n\n{}", e, USAGE);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    match run(args) {
+        Ok(summary) => {
+            println!("{}", summary);
+            ExitCode::SUCCESS
+        }
+        Err(e) => {
+            eprintln!("Import failed: {}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
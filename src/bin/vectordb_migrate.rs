@@ -0,0 +1,76 @@
+use std::process::ExitCode;
+use vectordb_rust::storage::CURRENT_FORMAT_VERSION;
+use vectordb_rust::VectorDbClient;
+
+const USAGE: &str = "Usage:
+  vectordb_migrate [--db CHEMIN] [--dry-run]
+
+Convertit en une passe toutes les collections d'un répertoire au format de stockage
+courant, y compris les anciens data.json / data.bin : chaque collection convertie est
+relue et comparée à l'ancien fichier avant qu'il soit supprimé. --dry-run liste les
+étapes sans rien modifier. --db vaut par défaut $VECTORDB_PATH ou ./vector_db ; le
+serveur doit être arrêté (un seul processus écrivain par répertoire).";
+
+struct Args {
+    db: String,
+    dry_run: bool,
+}
+
+fn parse_args() -> Result<Args, String> {
+    let mut args = std::env::args().skip(1);
+    let mut parsed = Args {
+        db: std::env::var("VECTORDB_PATH").unwrap_or("./vector_db".into()),
+        dry_run: false,
+    };
+
+    while let Some(flag) = args.next() {
+        match flag.as_str() {
+            "--dry-run" => parsed.dry_run = true,
+            "--db" => parsed.db = args.next().ok_or("missing value for --db")?,
+            other => return Err(format!("unknown option {}", other)),
+        }
+    }
+
+    Ok(parsed)
+}
+
+fn run(args: Args) -> Result<String, String> {
+    let client = VectorDbClient::new(&args.db).map_err(|e| e.to_string())?;
+    let reports = client.migrate_storage(args.dry_run).map_err(|e| e.to_string())?;
+
+    let mut summary = String::new();
+    for report in &reports {
+        summary.push_str(&format!("{}: format {}\n", report.collection, report.from_version));
+        for step in &report.steps {
+            summary.push_str(&format!("  {}\n", step));
+        }
+    }
+    summary.push_str(&format!(
+        "{} collection(s) {} to storage format {}",
+        reports.len(),
+        if args.dry_run { "to migrate" } else { "migrated" },
+        CURRENT_FORMAT_VERSION
+    ));
+    Ok(summary)
+}
+
+fn main() -> ExitCode {
+    let args = match parse_args() {
+        Ok(args) => args,
+        Err(e) => {
+            eprintln!("{}\n\n{}", e, USAGE);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    match run(args) {
+        Ok(summary) => {
+            println!("{}", summary);
+            ExitCode::SUCCESS
+        }
+        Err(e) => {
+            eprintln!("Migration failed: {}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
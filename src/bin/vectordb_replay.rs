@@ -0,0 +1,92 @@
+use std::path::PathBuf;
+use std::process::ExitCode;
+use vectordb_rust::querylog::{replay, ReplayOptions};
+use vectordb_rust::VectorDbClient;
+
+const USAGE: &str = "Usage:
+  vectordb_replay <journal> [--collection NOM] [--recall] [--limit N] [--db CHEMIN]
+
+Rejoue les requêtes d'un journal (VECTORDB_QUERY_LOG, écrit avec
+VECTORDB_QUERY_LOG_EMBEDDINGS=full) et compare les latences à celles journalisées ;
+--recall mesure aussi le rappel de chaque requête par rapport à la recherche exhaustive.
+--db vaut par défaut $VECTORDB_PATH ou ./vector_db ; le serveur doit être arrêté
+(un seul processus écrivain par répertoire).";
+
+struct Args {
+    log: PathBuf,
+    options: ReplayOptions,
+    db: String,
+}
+
+fn parse_args() -> Result<Args, String> {
+    let mut args = std::env::args().skip(1);
+    let log = PathBuf::from(args.next().ok_or("missing query log path")?);
+
+    let mut parsed = Args {
+        log,
+        options: ReplayOptions::default(),
+        db: std::env::var("VECTORDB_PATH").unwrap_or("./vector_db".into()),
+    };
+
+    while let Some(flag) = args.next() {
+        if flag == "--recall" {
+            parsed.options.check_recall = true;
+            continue;
+        }
+        let value = args.next().ok_or(format!("missing value for {}", flag))?;
+        match flag.as_str() {
+            "--collection" => parsed.options.collection = Some(value),
+            "--limit" => {
+                let limit = value.parse().map_err(|_| format!("invalid --limit {}", value))?;
+                parsed.options.limit = Some(limit);
+            }
+            "--db" => parsed.db = value,
+            other => return Err(format!("unknown option {}", other)),
+        }
+    }
+
+    Ok(parsed)
+}
+
+fn run(args: Args) -> Result<String, String> {
+    let client = VectorDbClient::new(&args.db).map_err(|e| e.to_string())?;
+    let report = replay(&client, &args.log, &args.options).map_err(|e| e.to_string())?;
+
+    let mut summary = format!(
+        "{} queries replayed, {} skipped (hash only), {} failed\n\
+         logged latency: p50 {:.3} ms, p95 {:.3} ms\n\
+         replay latency: p50 {:.3} ms, p95 {:.3} ms",
+        report.replayed,
+        report.skipped,
+        report.failed,
+        report.logged_p50_ms,
+        report.logged_p95_ms,
+        report.replay_p50_ms,
+        report.replay_p95_ms,
+    );
+    if let Some(recall) = report.mean_recall {
+        summary.push_str(&format!("\nmean recall vs exact search: {:.4}", recall));
+    }
+    Ok(summary)
+}
+
+fn main() -> ExitCode {
+    let args = match parse_args() {
+        Ok(args) => args,
+        Err(e) => {
+            eprintln!("{}\n\n{}", e, USAGE);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    match run(args) {
+        Ok(summary) => {
+            println!("{}", summary);
+            ExitCode::SUCCESS
+        }
+        Err(e) => {
+            eprintln!("Replay failed: {}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
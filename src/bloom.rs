@@ -0,0 +1,83 @@
+// filtre de Bloom sur les ids d'un segment : une recherche par id (get, delete,
+// remplacement) écarte sans lecture les segments qui ne contiennent pas l'id. Pas de
+// faux négatif ; environ 1 % de faux positifs à BITS_PER_KEY bits par id. Persisté
+// dans l'index du segment, les hachages doivent rester stables d'une version à l'autre
+use serde::{Deserialize, Serialize};
+
+pub const BITS_PER_KEY: usize = 10;
+// optimum ln 2 * BITS_PER_KEY
+const N_HASHES: u32 = 7;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BloomFilter {
+    bits: Vec<u64>,
+    n_hashes: u32,
+}
+
+impl BloomFilter {
+    pub fn with_capacity(n_keys: usize) -> Self {
+        let n_words = (n_keys * BITS_PER_KEY).div_ceil(64).max(1);
+        Self { bits: vec![0; n_words], n_hashes: N_HASHES }
+    }
+
+    pub fn insert(&mut self, key: &str) {
+        let n_bits = self.n_bits();
+        for bit in probes(key, self.n_hashes, n_bits) {
+            self.bits[bit / 64] |= 1 << (bit % 64);
+        }
+    }
+
+    // faux : l'id n'a sûrement pas été inséré
+    pub fn may_contain(&self, key: &str) -> bool {
+        let n_bits = self.n_bits();
+        probes(key, self.n_hashes, n_bits).all(|bit| self.bits[bit / 64] & (1 << (bit % 64)) != 0)
+    }
+
+    pub fn bytes(&self) -> usize {
+        self.bits.len() * 8
+    }
+
+    fn n_bits(&self) -> u64 {
+        self.bits.len() as u64 * 64
+    }
+}
+
+// double hachage (Kirsch-Mitzenmacher) : n_hashes positions tirées de deux hachages
+fn probes(key: &str, n_hashes: u32, n_bits: u64) -> impl Iterator<Item = usize> {
+    let h1 = mix(fnv1a(key.as_bytes()));
+    let h2 = mix(h1 ^ 0x9e37_79b9_7f4a_7c15) | 1;
+    (0..n_hashes as u64).map(move |i| (h1.wrapping_add(i.wrapping_mul(h2)) % n_bits) as usize)
+}
+
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, b| (hash ^ *b as u64).wrapping_mul(0x0000_0100_0000_01b3))
+}
+
+// finaliseur de splitmix64 : FNV seul répartit mal les ids qui ne diffèrent que
+// par leurs derniers caractères
+fn mix(mut x: u64) -> u64 {
+    x = (x ^ (x >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    x ^ (x >> 31)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_no_false_negatives_and_few_false_positives() {
+        let mut bloom = BloomFilter::with_capacity(10_000);
+        for i in 0..10_000 {
+            bloom.insert(&format!("doc-{}", i));
+        }
+        assert!((0..10_000).all(|i| bloom.may_contain(&format!("doc-{}", i))));
+
+        let false_positives = (10_000..20_000).filter(|i| bloom.may_contain(&format!("doc-{}", i))).count();
+        assert!(false_positives < 300, "{} false positives", false_positives);
+
+        // relu tel quel : mêmes positions après sérialisation
+        let reloaded: BloomFilter = bincode::deserialize(&bincode::serialize(&bloom).unwrap()).unwrap();
+        assert!(reloaded.may_contain("doc-42"));
+    }
+}
@@ -0,0 +1,450 @@
+use crate::error::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CachePolicy {
+    // éviction de la collection la moins récemment utilisée
+    Lru,
+    // éviction selon la valeur (fréquence x coût de chargement), avec refus
+    // d'admission si l'entrée coûterait plus cher à évincer qu'elle ne rapporte
+    CostAware,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CacheConfig {
+    pub max_cached: usize,
+    // budget mémoire estimé, 0 = illimité
+    pub max_bytes: usize,
+    pub policy: CachePolicy,
+}
+
+impl Default for CacheConfig {
+    fn default() -> Self {
+        Self {
+            max_cached: 20,
+            max_bytes: 0,
+            policy: CachePolicy::Lru,
+        }
+    }
+}
+
+impl CacheConfig {
+    pub fn from_env() -> Self {
+        let default = Self::default();
+        let env_usize = |key: &str, fallback: usize| {
+            std::env::var(key)
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(fallback)
+        };
+
+        let policy = match std::env::var("VECTORDB_CACHE_POLICY").as_deref() {
+            Ok("cost_aware") => CachePolicy::CostAware,
+            _ => default.policy,
+        };
+
+        Self {
+            max_cached: env_usize("VECTORDB_MAX_CACHED", default.max_cached),
+            max_bytes: env_usize("VECTORDB_CACHE_MAX_BYTES", default.max_bytes),
+            policy,
+        }
+    }
+
+    // victimes à évincer pour admettre une entrée de `incoming_size` octets,
+    // None si l'entrée ne doit pas être admise
+    pub fn plan_admission<'a, I>(
+        &self,
+        entries: I,
+        incoming_size: usize,
+        incoming_value: f64,
+        force: bool,
+        now_ms: u64,
+    ) -> Option<Vec<String>>
+    where
+        I: Iterator<Item = (&'a String, &'a CacheEntryStats)>,
+    {
+        let mut candidates: Vec<(&String, &CacheEntryStats)> = entries.collect();
+        let mut count = candidates.len();
+        let mut bytes: usize = candidates.iter().map(|(_, e)| e.size_bytes()).sum();
+
+        match self.policy {
+            CachePolicy::Lru => candidates.sort_by_key(|(_, e)| e.last_access()),
+            CachePolicy::CostAware => candidates.sort_by(|a, b| {
+                a.1.value(now_ms).total_cmp(&b.1.value(now_ms))
+            }),
+        }
+
+        let over_budget = |count: usize, bytes: usize| {
+            count >= self.max_cached
+                || (self.max_bytes > 0 && bytes + incoming_size > self.max_bytes)
+        };
+
+        let mut victims = Vec::new();
+        let mut evicted_value = 0.0;
+        for (name, entry) in candidates {
+            if !over_budget(count, bytes) {
+                break;
+            }
+            // tenue par un CollectionHandle : jamais évincée pour faire de la place
+            if entry.is_pinned() {
+                continue;
+            }
+            victims.push(name.clone());
+            evicted_value += entry.value(now_ms);
+            count -= 1;
+            bytes -= entry.size_bytes();
+        }
+
+        if force || self.policy == CachePolicy::Lru {
+            return Some(victims);
+        }
+
+        if over_budget(count, bytes) || evicted_value > incoming_value {
+            return None;
+        }
+
+        Some(victims)
+    }
+}
+
+// informations de coût d'une entrée en cache, mises à jour sans verrou en écriture
+#[derive(Debug)]
+pub struct CacheEntryStats {
+    size_bytes: AtomicUsize,
+    pub load_cost_ms: f64,
+    last_access_ms: AtomicU64,
+    hits: AtomicU64,
+    // nombre de CollectionHandle ouverts sur l'entrée
+    pins: AtomicUsize,
+    // retirée du cache : les handles qui la tiennent doivent relire la collection
+    evicted: AtomicBool,
+}
+
+impl CacheEntryStats {
+    pub fn new(size_bytes: usize, load_cost_ms: f64, now_ms: u64) -> Self {
+        Self {
+            size_bytes: AtomicUsize::new(size_bytes),
+            load_cost_ms,
+            last_access_ms: AtomicU64::new(now_ms),
+            hits: AtomicU64::new(1),
+            pins: AtomicUsize::new(0),
+            evicted: AtomicBool::new(false),
+        }
+    }
+
+    pub fn size_bytes(&self) -> usize {
+        self.size_bytes.load(Ordering::Relaxed)
+    }
+
+    pub fn set_size_bytes(&self, size_bytes: usize) {
+        self.size_bytes.store(size_bytes, Ordering::Relaxed);
+    }
+
+    pub fn pin(&self) {
+        self.pins.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn unpin(&self) {
+        self.pins.fetch_sub(1, Ordering::Relaxed);
+    }
+
+    pub fn is_pinned(&self) -> bool {
+        self.pins.load(Ordering::Relaxed) > 0
+    }
+
+    pub fn mark_evicted(&self) {
+        self.evicted.store(true, Ordering::Release);
+    }
+
+    pub fn is_evicted(&self) -> bool {
+        self.evicted.load(Ordering::Acquire)
+    }
+
+    pub fn touch(&self, now_ms: u64) {
+        self.last_access_ms.store(now_ms, Ordering::Relaxed);
+        self.hits.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn last_access(&self) -> u64 {
+        self.last_access_ms.load(Ordering::Relaxed)
+    }
+
+    pub fn hits(&self) -> u64 {
+        self.hits.load(Ordering::Relaxed)
+    }
+
+    // valeur de l'entrée : ce que coûterait son rechargement, pondéré par son
+    // utilisation et amorti par le temps écoulé depuis le dernier accès
+    pub fn value(&self, now_ms: u64) -> f64 {
+        let idle_secs = now_ms.saturating_sub(self.last_access()) as f64 / 1000.0;
+        self.hits() as f64 * self.load_cost_ms.max(1.0) / (idle_secs + 1.0)
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct CacheCounters {
+    pub hits: AtomicU64,
+    pub misses: AtomicU64,
+    pub evictions: AtomicU64,
+    pub rejected_admissions: AtomicU64,
+}
+
+impl CacheCounters {
+    pub fn incr(counter: &AtomicU64, n: u64) {
+        counter.fetch_add(n, Ordering::Relaxed);
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct CacheEntryMetrics {
+    pub name: String,
+    pub size_bytes: usize,
+    pub load_cost_ms: f64,
+    pub hits: u64,
+    pub idle_ms: u64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CacheMetrics {
+    pub config: CacheConfig,
+    pub cached_collections: usize,
+    pub cached_bytes: usize,
+    pub hits: u64,
+    pub misses: u64,
+    pub evictions: u64,
+    pub rejected_admissions: u64,
+    pub entries: Vec<CacheEntryMetrics>,
+}
+
+// embeddings des textes de requête déjà calculés : les mêmes questions reviennent
+// souvent (applications de chat), l'appel au fournisseur est alors évité.
+// Clé (modèle, dimension, texte), éviction LRU et expiration après `ttl`
+#[derive(Debug)]
+pub struct EmbeddingCache {
+    // 0 = cache désactivé
+    max_entries: usize,
+    ttl: Duration,
+    inner: Mutex<EmbeddingCacheInner>,
+    counters: EmbeddingCacheCounters,
+}
+
+type EmbeddingKey = (String, usize, String);
+
+#[derive(Debug, Default)]
+struct EmbeddingCacheInner {
+    entries: HashMap<EmbeddingKey, CachedEmbedding>,
+    // ordre d'utilisation : tick -> clé, le plus ancien en tête
+    recency: BTreeMap<u64, EmbeddingKey>,
+    tick: u64,
+}
+
+#[derive(Debug)]
+struct CachedEmbedding {
+    embedding: Vec<f32>,
+    inserted: Instant,
+    tick: u64,
+}
+
+#[derive(Debug, Default)]
+struct EmbeddingCacheCounters {
+    hits: AtomicU64,
+    misses: AtomicU64,
+    expired: AtomicU64,
+    evictions: AtomicU64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct EmbeddingCacheMetrics {
+    pub max_entries: usize,
+    pub ttl_secs: u64,
+    pub entries: usize,
+    pub hits: u64,
+    pub misses: u64,
+    // entrées trouvées mais trop anciennes, comptées aussi dans misses
+    pub expired: u64,
+    pub evictions: u64,
+}
+
+pub const DEFAULT_EMBEDDING_CACHE_ENTRIES: usize = 10_000;
+pub const DEFAULT_EMBEDDING_CACHE_TTL: Duration = Duration::from_secs(3600);
+
+impl Default for EmbeddingCache {
+    fn default() -> Self {
+        Self::new(DEFAULT_EMBEDDING_CACHE_ENTRIES, DEFAULT_EMBEDDING_CACHE_TTL)
+    }
+}
+
+impl EmbeddingCache {
+    pub fn new(max_entries: usize, ttl: Duration) -> Self {
+        Self {
+            max_entries,
+            ttl,
+            inner: Mutex::default(),
+            counters: EmbeddingCacheCounters::default(),
+        }
+    }
+
+    // embedding en cache, sinon calculé par `embed` (verrou relâché pendant l'appel)
+    // puis mis en cache ; une erreur de `embed` n'est pas mise en cache
+    pub fn get_or_embed<F>(&self, model: &str, dimension: usize, text: &str, embed: F) -> Result<Vec<f32>>
+    where
+        F: FnOnce() -> Result<Vec<f32>>,
+    {
+        if self.max_entries == 0 {
+            return embed();
+        }
+        let key = (model.to_string(), dimension, text.to_string());
+        if let Some(embedding) = self.lookup(&key) {
+            CacheCounters::incr(&self.counters.hits, 1);
+            return Ok(embedding);
+        }
+        CacheCounters::incr(&self.counters.misses, 1);
+        let embedding = embed()?;
+        self.insert(key, embedding.clone());
+        Ok(embedding)
+    }
+
+    fn lookup(&self, key: &EmbeddingKey) -> Option<Vec<f32>> {
+        let mut inner = self.inner.lock().unwrap();
+        let inner = &mut *inner;
+        let entry = inner.entries.get_mut(key)?;
+        if entry.inserted.elapsed() >= self.ttl {
+            let tick = entry.tick;
+            inner.entries.remove(key);
+            inner.recency.remove(&tick);
+            CacheCounters::incr(&self.counters.expired, 1);
+            return None;
+        }
+        inner.tick += 1;
+        inner.recency.remove(&entry.tick);
+        entry.tick = inner.tick;
+        inner.recency.insert(entry.tick, key.clone());
+        Some(entry.embedding.clone())
+    }
+
+    fn insert(&self, key: EmbeddingKey, embedding: Vec<f32>) {
+        let mut inner = self.inner.lock().unwrap();
+        inner.tick += 1;
+        let tick = inner.tick;
+        let entry = CachedEmbedding { embedding, inserted: Instant::now(), tick };
+        // deux requêtes concurrentes sur le même texte : la dernière remplace l'autre
+        if let Some(previous) = inner.entries.insert(key.clone(), entry) {
+            inner.recency.remove(&previous.tick);
+        }
+        inner.recency.insert(tick, key);
+        while inner.entries.len() > self.max_entries {
+            let Some((_, oldest)) = inner.recency.pop_first() else { break };
+            inner.entries.remove(&oldest);
+            CacheCounters::incr(&self.counters.evictions, 1);
+        }
+    }
+
+    // vide le cache (changement de modèle derrière un même nom...) ; renvoie le
+    // nombre d'entrées supprimées
+    pub fn flush(&self) -> usize {
+        let mut inner = self.inner.lock().unwrap();
+        let flushed = inner.entries.len();
+        inner.entries.clear();
+        inner.recency.clear();
+        flushed
+    }
+
+    pub fn metrics(&self) -> EmbeddingCacheMetrics {
+        EmbeddingCacheMetrics {
+            max_entries: self.max_entries,
+            ttl_secs: self.ttl.as_secs(),
+            entries: self.inner.lock().unwrap().entries.len(),
+            hits: self.counters.hits.load(Ordering::Relaxed),
+            misses: self.counters.misses.load(Ordering::Relaxed),
+            expired: self.counters.expired.load(Ordering::Relaxed),
+            evictions: self.counters.evictions.load(Ordering::Relaxed),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(size: usize, cost_ms: f64, hits: u64) -> CacheEntryStats {
+        let e = CacheEntryStats::new(size, cost_ms, 1_000);
+        for _ in 1..hits {
+            e.touch(1_000);
+        }
+        e
+    }
+
+    #[test]
+    fn test_lru_evicts_oldest() {
+        let config = CacheConfig { max_cached: 2, ..Default::default() };
+        let a = CacheEntryStats::new(10, 1.0, 100);
+        let b = CacheEntryStats::new(10, 1.0, 200);
+        let names = ["a".to_string(), "b".to_string()];
+        let entries = vec![(&names[0], &a), (&names[1], &b)];
+
+        let victims = config.plan_admission(entries.clone().into_iter(), 10, 1.0, false, 300);
+        assert_eq!(victims, Some(vec!["a".to_string()]));
+
+        // "a" tenue par un handle : la suivante est évincée à sa place
+        a.pin();
+        let victims = config.plan_admission(entries.into_iter(), 10, 1.0, false, 300);
+        assert_eq!(victims, Some(vec!["b".to_string()]));
+    }
+
+    #[test]
+    fn test_cost_aware_rejects_cheap_newcomer() {
+        let config = CacheConfig {
+            max_cached: 10,
+            max_bytes: 6_000,
+            policy: CachePolicy::CostAware,
+        };
+        // grosse collection longue à charger et souvent utilisée
+        let big = entry(5_000, 30_000.0, 20);
+        let name = "big".to_string();
+
+        let victims = config.plan_admission(vec![(&name, &big)].into_iter(), 2_000, 5.0, false, 1_000);
+        assert_eq!(victims, None);
+
+        // un chargement explicite est toujours admis
+        let victims = config.plan_admission(vec![(&name, &big)].into_iter(), 2_000, 5.0, true, 1_000);
+        assert_eq!(victims, Some(vec!["big".to_string()]));
+    }
+
+    #[test]
+    fn test_embedding_cache_lru_and_ttl() {
+        let cache = EmbeddingCache::new(2, Duration::from_secs(60));
+        let calls = AtomicUsize::new(0);
+        let embed = |text: &str| {
+            cache.get_or_embed("m", 2, text, || {
+                calls.fetch_add(1, Ordering::Relaxed);
+                Ok(vec![text.len() as f32, 0.0])
+            })
+        };
+        embed("a").unwrap();
+        embed("bb").unwrap();
+        assert_eq!(embed("a").unwrap(), [1.0, 0.0]);
+        assert_eq!(calls.load(Ordering::Relaxed), 2);
+        // "bb", le moins récemment utilisé, est évincé
+        embed("ccc").unwrap();
+        embed("a").unwrap();
+        embed("bb").unwrap();
+        assert_eq!(calls.load(Ordering::Relaxed), 4);
+
+        // une erreur du fournisseur n'est pas mise en cache
+        let failed = cache.get_or_embed("m", 2, "x", || Err(crate::error::VectorDbError::InvalidConfig("down".into())));
+        assert!(failed.is_err());
+        let metrics = cache.metrics();
+        assert_eq!((metrics.entries, metrics.hits, metrics.misses, metrics.evictions), (2, 2, 5, 2));
+        assert_eq!(cache.flush(), 2);
+
+        let expiring = EmbeddingCache::new(10, Duration::ZERO);
+        expiring.get_or_embed("m", 2, "a", || Ok(vec![1.0, 0.0])).unwrap();
+        expiring.get_or_embed("m", 2, "a", || Ok(vec![1.0, 0.0])).unwrap();
+        assert_eq!(expiring.metrics().expired, 1);
+    }
+}
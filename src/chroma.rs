@@ -0,0 +1,375 @@
+use crate::error::{Result, VectorDbError};
+use crate::metadata::{estimated_size, MetadataLimits};
+use crate::vector::MetadataValue;
+use rusqlite::{Connection, OpenFlags};
+use std::collections::HashMap;
+use std::fs::File;
+use std::path::Path;
+
+// clé sous laquelle Chroma range le document dans les métadonnées
+const CHROMA_DOCUMENT_KEY: &str = "chroma:document";
+// clé du document côté vectordb ; repli sur la clé Chroma si déjà utilisée
+pub const DOCUMENT_KEY: &str = "document";
+
+// opérations du journal embeddings_queue
+const OP_ADD: i64 = 0;
+const OP_UPDATE: i64 = 1;
+const OP_UPSERT: i64 = 2;
+const OP_DELETE: i64 = 3;
+
+#[derive(Debug, Clone)]
+pub struct ChromaCollection {
+    pub name: String,
+    pub dimension: usize,
+    pub ids: Vec<String>,
+    pub embeddings: Vec<Vec<f32>>,
+    pub metadatas: Vec<HashMap<String, MetadataValue>>,
+}
+
+impl ChromaCollection {
+    // limites par défaut, élargies pour accueillir les données importées
+    // (les documents Chroma dépassent souvent max_string_len)
+    pub fn metadata_limits(&self) -> MetadataLimits {
+        let mut limits = MetadataLimits::default();
+        for metadata in &self.metadatas {
+            limits.max_keys = limits.max_keys.max(metadata.len());
+            let mut total = 0;
+            for (key, value) in metadata {
+                if let MetadataValue::String(s) = value {
+                    limits.max_string_len = limits.max_string_len.max(s.len());
+                }
+                total += key.len() + estimated_size(value);
+            }
+            limits.max_total_bytes = limits.max_total_bytes.max(total);
+        }
+        limits
+    }
+}
+
+// lit un répertoire persistant Chroma : chroma.sqlite3 (Chroma >= 0.4) ou
+// chroma-collections.parquet / chroma-embeddings.parquet (Chroma 0.3, duckdb+parquet)
+pub fn read_chroma(path: &Path) -> Result<Vec<ChromaCollection>> {
+    let sqlite_path = path.join("chroma.sqlite3");
+    if sqlite_path.exists() {
+        return read_sqlite(&sqlite_path);
+    }
+
+    let parquet_path = path.join("chroma-embeddings.parquet");
+    if parquet_path.exists() {
+        return read_parquet(path);
+    }
+
+    Err(VectorDbError::InvalidConfig(format!(
+        "{} is not a Chroma persistent directory (no chroma.sqlite3 or chroma-embeddings.parquet)",
+        path.display()
+    )))
+}
+
+fn read_sqlite(path: &Path) -> Result<Vec<ChromaCollection>> {
+    let conn = Connection::open_with_flags(path, OpenFlags::SQLITE_OPEN_READ_ONLY)?;
+
+    let mut stmt = conn.prepare("SELECT id, name, dimension FROM collections ORDER BY name")?;
+    let collections: Vec<(String, String, Option<i64>)> = stmt
+        .query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))?
+        .collect::<std::result::Result<_, _>>()?;
+
+    let vectors = read_queue_vectors(&conn)?;
+    let has_bool = column_exists(&conn, "embedding_metadata", "bool_value")?;
+
+    let mut result = Vec::with_capacity(collections.len());
+    for (coll_id, name, dimension) in collections {
+        // ids et métadonnées courants : segment METADATA de la collection
+        let mut stmt = conn.prepare(
+            "SELECT e.id, e.embedding_id FROM embeddings e
+             JOIN segments s ON e.segment_id = s.id
+             WHERE s.collection = ?1 AND s.scope = 'METADATA'
+             ORDER BY e.id",
+        )?;
+        let rows: Vec<(i64, String)> = stmt
+            .query_map([&coll_id], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<std::result::Result<_, _>>()?;
+
+        let coll_vectors = vectors
+            .iter()
+            .find(|(topic, _)| **topic == coll_id || topic.ends_with(&format!("/{}", coll_id)))
+            .map(|(_, v)| v);
+
+        let mut ids = Vec::with_capacity(rows.len());
+        let mut embeddings = Vec::with_capacity(rows.len());
+        let mut metadatas = Vec::with_capacity(rows.len());
+        let mut missing = 0;
+
+        for (row_id, embedding_id) in rows {
+            match coll_vectors.and_then(|v| v.get(&embedding_id)) {
+                Some(embedding) => {
+                    metadatas.push(read_sqlite_metadata(&conn, row_id, has_bool)?);
+                    embeddings.push(embedding.clone());
+                    ids.push(embedding_id);
+                }
+                None => missing += 1,
+            }
+        }
+
+        // le journal a été purgé : les vecteurs ne sont plus que dans l'index HNSW
+        if missing > 0 {
+            return Err(VectorDbError::Serialization(format!(
+                "Chroma collection '{}': {} embeddings are no longer in the write-ahead log; \
+                 export them with migrate_from_chromadb.py instead",
+                name, missing
+            )));
+        }
+
+        let dimension = dimension
+            .map(|d| d as usize)
+            .or_else(|| embeddings.first().map(|e| e.len()))
+            .unwrap_or(0);
+        result.push(ChromaCollection { name, dimension, ids, embeddings, metadatas });
+    }
+
+    Ok(result)
+}
+
+fn column_exists(conn: &Connection, table: &str, column: &str) -> Result<bool> {
+    let mut stmt = conn.prepare(&format!("PRAGMA table_info({})", table))?;
+    let names: Vec<String> = stmt
+        .query_map([], |row| row.get(1))?
+        .collect::<std::result::Result<_, _>>()?;
+    Ok(names.iter().any(|n| n == column))
+}
+
+// topic du journal -> (id -> vecteur)
+type TopicVectors = HashMap<String, HashMap<String, Vec<f32>>>;
+
+// rejoue le journal pour obtenir le dernier vecteur de chaque id, par topic
+fn read_queue_vectors(conn: &Connection) -> Result<TopicVectors> {
+    let mut by_topic: TopicVectors = HashMap::new();
+
+    let mut stmt = conn.prepare(
+        "SELECT operation, topic, id, vector, encoding FROM embeddings_queue ORDER BY seq_id",
+    )?;
+    let mut rows = stmt.query([])?;
+    while let Some(row) = rows.next()? {
+        let operation: i64 = row.get(0)?;
+        let topic: String = row.get(1)?;
+        let id: String = row.get(2)?;
+        let vector: Option<Vec<u8>> = row.get(3)?;
+        let encoding: Option<String> = row.get(4)?;
+
+        let vectors = by_topic.entry(topic).or_default();
+        match operation {
+            OP_DELETE => {
+                vectors.remove(&id);
+            }
+            OP_ADD | OP_UPDATE | OP_UPSERT => {
+                // une mise à jour sans vecteur ne touche que les métadonnées
+                if let Some(bytes) = vector {
+                    vectors.insert(id, decode_vector(&bytes, encoding.as_deref())?);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    Ok(by_topic)
+}
+
+fn decode_vector(bytes: &[u8], encoding: Option<&str>) -> Result<Vec<f32>> {
+    match encoding {
+        Some("FLOAT32") | None if bytes.len().is_multiple_of(4) => Ok(bytes
+            .chunks_exact(4)
+            .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+            .collect()),
+        Some("INT32") if bytes.len().is_multiple_of(4) => Ok(bytes
+            .chunks_exact(4)
+            .map(|b| i32::from_le_bytes([b[0], b[1], b[2], b[3]]) as f32)
+            .collect()),
+        other => Err(VectorDbError::Serialization(format!(
+            "unsupported Chroma vector encoding {:?} ({} bytes)",
+            other,
+            bytes.len()
+        ))),
+    }
+}
+
+fn read_sqlite_metadata(
+    conn: &Connection,
+    row_id: i64,
+    has_bool: bool,
+) -> Result<HashMap<String, MetadataValue>> {
+    let sql = if has_bool {
+        "SELECT key, string_value, int_value, float_value, bool_value
+         FROM embedding_metadata WHERE id = ?1"
+    } else {
+        "SELECT key, string_value, int_value, float_value, NULL
+         FROM embedding_metadata WHERE id = ?1"
+    };
+
+    let mut stmt = conn.prepare_cached(sql)?;
+    let mut rows = stmt.query([row_id])?;
+    let mut metadata = HashMap::new();
+    while let Some(row) = rows.next()? {
+        let key: String = row.get(0)?;
+        let value = if let Some(s) = row.get::<_, Option<String>>(1)? {
+            MetadataValue::String(s.into())
+        } else if let Some(b) = row.get::<_, Option<bool>>(4)? {
+            MetadataValue::Bool(b)
+        } else if let Some(i) = row.get::<_, Option<i64>>(2)? {
+            MetadataValue::Int(i)
+        } else if let Some(f) = row.get::<_, Option<f64>>(3)? {
+            MetadataValue::Float(f)
+        } else {
+            MetadataValue::Null
+        };
+        metadata.insert(key, value);
+    }
+
+    Ok(rename_document(metadata))
+}
+
+fn rename_document(mut metadata: HashMap<String, MetadataValue>) -> HashMap<String, MetadataValue> {
+    if !metadata.contains_key(DOCUMENT_KEY) {
+        if let Some(document) = metadata.remove(CHROMA_DOCUMENT_KEY) {
+            metadata.insert(DOCUMENT_KEY.to_string(), document);
+        }
+    }
+    metadata
+}
+
+fn read_parquet(path: &Path) -> Result<Vec<ChromaCollection>> {
+    use parquet::file::reader::{FileReader, SerializedFileReader};
+    use parquet::record::Field;
+
+    // nom des collections par uuid
+    let mut names: HashMap<String, String> = HashMap::new();
+    let reader = SerializedFileReader::new(File::open(path.join("chroma-collections.parquet"))?)?;
+    for row in reader.get_row_iter(None)? {
+        let row = row?;
+        let mut uuid = None;
+        let mut name = None;
+        for (column, field) in row.get_column_iter() {
+            match (column.as_str(), field) {
+                ("uuid", Field::Str(s)) => uuid = Some(s.clone()),
+                ("name", Field::Str(s)) => name = Some(s.clone()),
+                _ => {}
+            }
+        }
+        if let (Some(uuid), Some(name)) = (uuid, name) {
+            names.insert(uuid, name);
+        }
+    }
+
+    let mut collections: HashMap<String, ChromaCollection> = HashMap::new();
+    let reader = SerializedFileReader::new(File::open(path.join("chroma-embeddings.parquet"))?)?;
+    for row in reader.get_row_iter(None)? {
+        let row = row?;
+        let mut collection_uuid = None;
+        let mut id = None;
+        let mut embedding = Vec::new();
+        let mut document = None;
+        let mut metadata = HashMap::new();
+
+        for (column, field) in row.get_column_iter() {
+            match (column.as_str(), field) {
+                ("collection_uuid", Field::Str(s)) => collection_uuid = Some(s.clone()),
+                ("id", Field::Str(s)) => id = Some(s.clone()),
+                ("document", Field::Str(s)) => document = Some(s.clone()),
+                ("embedding", Field::ListInternal(list)) => {
+                    embedding = list
+                        .elements()
+                        .iter()
+                        .filter_map(|f| match f {
+                            Field::Double(d) => Some(*d as f32),
+                            Field::Float(f) => Some(*f),
+                            _ => None,
+                        })
+                        .collect();
+                }
+                ("metadata", Field::Str(s)) => {
+                    let json: serde_json::Value = serde_json::from_str(s)?;
+                    if let serde_json::Value::Object(map) = json {
+                        metadata = map
+                            .into_iter()
+                            .map(|(k, v)| (k, MetadataValue::from_json(v)))
+                            .collect();
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        let (Some(collection_uuid), Some(id)) = (collection_uuid, id) else {
+            continue;
+        };
+        if let Some(document) = document {
+            metadata.insert(CHROMA_DOCUMENT_KEY.to_string(), MetadataValue::String(document.into()));
+        }
+
+        let name = names.get(&collection_uuid).cloned().unwrap_or_else(|| collection_uuid.clone());
+        let coll = collections.entry(collection_uuid).or_insert_with(|| ChromaCollection {
+            name,
+            dimension: embedding.len(),
+            ids: Vec::new(),
+            embeddings: Vec::new(),
+            metadatas: Vec::new(),
+        });
+        coll.ids.push(id);
+        coll.embeddings.push(embedding);
+        coll.metadatas.push(rename_document(metadata));
+    }
+
+    let mut result: Vec<ChromaCollection> = collections.into_values().collect();
+    result.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::TempDir;
+
+    // schéma minimal de chroma.sqlite3 (0.5)
+    fn chroma_fixture(dir: &Path) {
+        let conn = Connection::open(dir.join("chroma.sqlite3")).unwrap();
+        conn.execute_batch(
+            "CREATE TABLE collections (id TEXT, name TEXT, dimension INTEGER);
+             CREATE TABLE segments (id TEXT, type TEXT, scope TEXT, collection TEXT);
+             CREATE TABLE embeddings (id INTEGER PRIMARY KEY, segment_id TEXT, embedding_id TEXT, seq_id BLOB);
+             CREATE TABLE embedding_metadata (id INTEGER, key TEXT, string_value TEXT,
+                 int_value INTEGER, float_value REAL, bool_value INTEGER);
+             CREATE TABLE embeddings_queue (seq_id INTEGER, created_at TEXT, operation INTEGER,
+                 topic TEXT, id TEXT, vector BLOB, encoding TEXT, metadata TEXT);
+             INSERT INTO collections VALUES ('c1', 'docs', 2);
+             INSERT INTO segments VALUES ('s1', 'sqlite', 'METADATA', 'c1');
+             INSERT INTO embeddings VALUES (1, 's1', 'a', 1);
+             INSERT INTO embedding_metadata VALUES (1, 'chroma:document', 'hello', NULL, NULL, NULL);
+             INSERT INTO embedding_metadata VALUES (1, 'page', NULL, 3, NULL, NULL);",
+        )
+        .unwrap();
+
+        let vector = |v: [f32; 2]| v.iter().flat_map(|x| x.to_le_bytes()).collect::<Vec<u8>>();
+        let mut insert = conn
+            .prepare("INSERT INTO embeddings_queue VALUES (?1, '', ?2, 'persistent://default/default/c1', ?3, ?4, 'FLOAT32', NULL)")
+            .unwrap();
+        insert.execute(rusqlite::params![1, OP_ADD, "a", vector([1.0, 0.0])]).unwrap();
+        insert.execute(rusqlite::params![2, OP_ADD, "b", vector([0.0, 1.0])]).unwrap();
+        insert.execute(rusqlite::params![3, OP_UPDATE, "a", vector([0.5, 0.5])]).unwrap();
+        insert.execute(rusqlite::params![4, OP_DELETE, "b", Option::<Vec<u8>>::None]).unwrap();
+    }
+
+    #[test]
+    fn test_read_sqlite_store() {
+        let dir = TempDir::new("chroma");
+        chroma_fixture(&dir);
+
+        let collections = read_chroma(&dir).unwrap();
+
+        assert_eq!(collections.len(), 1);
+        let docs = &collections[0];
+        assert_eq!(docs.name, "docs");
+        assert_eq!(docs.dimension, 2);
+        assert_eq!(docs.ids, vec!["a".to_string()]);
+        assert_eq!(docs.embeddings[0], vec![0.5, 0.5]);
+        assert_eq!(docs.metadatas[0].get(DOCUMENT_KEY), Some(&MetadataValue::String("hello".into())));
+        assert_eq!(docs.metadatas[0].get("page"), Some(&MetadataValue::Int(3)));
+    }
+}
@@ -1,76 +1,625 @@
-use crate::collection::Collection;
+use crate::cache::{
+    CacheConfig, CacheCounters, CacheEntryMetrics, CacheEntryStats, CacheMetrics, EmbeddingCache,
+};
+use crate::collection::{
+    recall, Collection, CollectionConfig, CollectionStats, GetResult, IndexStatus, QueryOptions, SearchResult,
+};
+use crate::cursor::{CursorKey, PageCursor};
+use crate::diff::{diff_collections, CollectionDiff, DiffOptions, DiffSource};
+use crate::embedding_model::ModelRegistry;
 use crate::error::{Result, VectorDbError};
-use crate::storage::Storage;
-use std::collections::HashMap;
-use std::path::Path;
-use std::sync::{Arc, RwLock};
-use std::time::{SystemTime, UNIX_EPOCH};
+use crate::faiss;
+use crate::hooks::{HookSet, Hooks};
+use crate::filter::{check_filter_types, matches_filter, observed_types, validate_filter, WhereFilter};
+use crate::ivf::IVFIndex;
+use crate::limits::{LimitUsage, Limits};
+use crate::metrics::{CollectionOpReport, CollectionOpStats, StatsHistory, StatsSnapshot};
+use crate::npy;
+use crate::profiles::{CollectionProfile, ProfileRegistry};
+use crate::querylog::{concerns_erasure, QueryLog};
+use crate::readview::{ConcurrencyMode, PublishedView, ReadView};
+use crate::rerank::{Reranker, RerankerRegistry};
+use crate::segment::SegmentInfo;
+use crate::snapshot::{PruneReport, ShippedSnapshot, SnapshotInfo, SnapshotShipper};
+use crate::tombstones::TombstonePolicy;
+use crate::storage::{CollectionHeader, MigrationReport, ScanReport, Storage, CURRENT_FORMAT_VERSION};
+use crate::vector::{metadata_to_json, MetadataValue};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs::{File, OpenOptions, TryLockError};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, PoisonError, RwLock, RwLockReadGuard, RwLockWriteGuard};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
+// 6 h d'historique avec un instantané par minute
+pub const DEFAULT_STATS_HISTORY_LEN: usize = 360;
+
+// résultat d'un effacement sur toutes les collections, tel qu'écrit dans audit.log
+#[derive(Debug, Clone, Serialize)]
+pub struct ScrubReport {
+    pub at: String,
+    pub filter: WhereFilter,
+    // référence fournie par l'appelant (numéro de demande, ticket)
+    pub reason: Option<String>,
+    pub deleted: BTreeMap<String, usize>,
+    pub total_deleted: usize,
+    // segments réécrits sans les lignes effacées
+    pub segments_rewritten: usize,
+    // enregistrements retirés du journal des requêtes (querylog::concerns_erasure)
+    pub query_log_purged: usize,
+    // instantanés de VECTORDB_SNAPSHOT_DIR antérieurs à l'effacement : ils ne sont pas
+    // réécrits et gardent les données effacées jusqu'à leur suppression par la rétention
+    pub snapshots_not_scrubbed: Vec<String>,
+    // collections non traitées (filtre incompatible, erreur de stockage) ; "(query log)"
+    // et "(snapshots)" pour le journal des requêtes et la liste des instantanés
+    pub errors: BTreeMap<String, String>,
+}
+
+// une entrée de get_multi : ids d'une collection et champs à renvoyer
+#[derive(Debug, Clone, Deserialize)]
+pub struct MultiGetRequest {
+    pub collection: String,
+    pub ids: Vec<String>,
+    #[serde(default)]
+    pub include: Option<Vec<String>>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct MultiGetResult {
+    pub collection: String,
+    #[serde(flatten)]
+    pub result: GetResult,
+}
+
+// page d'un get sans ids (voir VectorDbClient::scroll) : limit / offset, et curseur de
+// la page précédente pour parcourir toute la collection
+#[derive(Debug, Clone, Default)]
+pub struct ScrollRequest {
+    pub filter: Option<WhereFilter>,
+    // None : jusqu'à la fin (plafonné par VECTORDB_MAX_GET_RESULTS)
+    pub limit: Option<usize>,
+    pub offset: usize,
+    // next_cursor de la page précédente, None pour la première
+    pub cursor: Option<String>,
+    pub include: Option<Vec<String>>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ScrollPage {
+    #[serde(flatten)]
+    pub result: GetResult,
+    // jeton de la page suivante, None sur la dernière
+    pub next_cursor: Option<String>,
+}
+
+// embeddings sérialisés par morceau d'un GetStream
+pub const GET_STREAM_CHUNK: usize = 1024;
+
+// morceaux JSON successifs d'un get (voir VectorDbClient::get_stream) : ids et
+// métadonnées sont lus d'un coup, les embeddings par blocs de GET_STREAM_CHUNK sous
+// un verrou de lecture repris à chaque bloc, pour ne pas bloquer les écritures
+// pendant tout l'envoi. Un vecteur supprimé entre-temps garde sa place avec un
+// embedding null
+pub struct GetStream<'a> {
+    client: &'a VectorDbClient,
+    name: String,
+    ids: Vec<String>,
+    metadatas: Option<Vec<HashMap<String, MetadataValue>>>,
+    embeddings: bool,
+    part: GetStreamPart,
+}
+
+enum GetStreamPart {
+    Head,
+    // position du prochain bloc d'embeddings
+    Embeddings(usize),
+    Tail,
+    Done,
+}
+
+impl GetStream<'_> {
+    pub fn len(&self) -> usize {
+        self.ids.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ids.is_empty()
+    }
+
+    fn next_chunk(&mut self) -> Result<Vec<u8>> {
+        let mut out = Vec::new();
+        match self.part {
+            GetStreamPart::Head => {
+                out.extend_from_slice(b"{\"ids\":");
+                serde_json::to_writer(&mut out, &self.ids)?;
+                out.extend_from_slice(b",\"embeddings\":");
+                if self.embeddings {
+                    out.push(b'[');
+                    self.part = GetStreamPart::Embeddings(0);
+                } else {
+                    out.extend_from_slice(b"null");
+                    self.part = GetStreamPart::Tail;
+                }
+            }
+            GetStreamPart::Embeddings(start) => {
+                let end = (start + GET_STREAM_CHUNK).min(self.ids.len());
+                let ids = &self.ids[start..end];
+                let embeddings = self.client.with_collection(&self.name, |coll| {
+                    ids.iter().map(|id| coll.embedding(id)).collect::<Result<Vec<_>>>()
+                })??;
+                for (i, embedding) in embeddings.iter().enumerate() {
+                    if start + i > 0 {
+                        out.push(b',');
+                    }
+                    serde_json::to_writer(&mut out, embedding)?;
+                }
+                if end == self.ids.len() {
+                    out.push(b']');
+                    self.part = GetStreamPart::Tail;
+                } else {
+                    self.part = GetStreamPart::Embeddings(end);
+                }
+            }
+            GetStreamPart::Tail => {
+                out.extend_from_slice(b",\"metadatas\":");
+                match self.metadatas.take() {
+                    Some(metadatas) => {
+                        out.push(b'[');
+                        for (i, metadata) in metadatas.iter().enumerate() {
+                            if i > 0 {
+                                out.push(b',');
+                            }
+                            serde_json::to_writer(&mut out, &metadata_to_json(metadata))?;
+                        }
+                        out.push(b']');
+                    }
+                    None => out.extend_from_slice(b"null"),
+                }
+                out.push(b'}');
+                self.part = GetStreamPart::Done;
+            }
+            GetStreamPart::Done => {}
+        }
+        Ok(out)
+    }
+}
+
+impl Iterator for GetStream<'_> {
+    type Item = Result<Vec<u8>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if matches!(self.part, GetStreamPart::Done) {
+            return None;
+        }
+        let chunk = self.next_chunk();
+        if chunk.is_err() {
+            self.part = GetStreamPart::Done;
+        }
+        Some(chunk)
+    }
+}
+
+// partagée avec les CollectionHandle : verrou par collection, pris après le verrou
+// global du cache quand les deux sont nécessaires
+#[derive(Clone)]
 struct CachedCollection {
-    collection: Collection,
-    last_access: u64,
+    collection: Arc<RwLock<Collection>>,
+    stats: Arc<CacheEntryStats>,
+    // en lecture seule : date de l'en-tête chargé, pour détecter les sauvegardes de l'écrivain
+    disk_stamp: Option<SystemTime>,
+}
+
+impl CachedCollection {
+    fn read(&self) -> RwLockReadGuard<'_, Collection> {
+        self.collection.read().unwrap()
+    }
+
+    fn write(&self) -> RwLockWriteGuard<'_, Collection> {
+        self.collection.write().unwrap()
+    }
+
+    // marquée sous son verrou : un handle qui la tient ne peut pas écrire dans
+    // une collection supprimée ou rechargée entre-temps
+    fn detach(&self) {
+        let _guard = self.write();
+        self.stats.mark_evicted();
+    }
 }
 
 pub struct VectorDbClient {
     storage: Storage,
     collections: Arc<RwLock<HashMap<String, CachedCollection>>>,
-    max_cached: usize,
+    cache_config: CacheConfig,
+    cache_counters: CacheCounters,
+    // nombre de chargements refusés par collection, augmente sa valeur d'admission
+    miss_counts: Mutex<HashMap<String, u64>>,
+    op_stats: Mutex<HashMap<String, CollectionOpStats>>,
+    stats_history: Mutex<HashMap<String, StatsHistory>>,
+    // nombre d'instantanés gardés par collection
+    stats_history_len: usize,
+    created_at: Instant,
+    limits: Limits,
+    models: RwLock<ModelRegistry>,
+    profiles: RwLock<ProfileRegistry>,
+    // rebuild IVF hors du chemin des requêtes (voir rebuild_index_background)
+    deferred_rebuild: bool,
+    query_log: Option<QueryLog>,
+    // part des requêtes IVF rejouées en exhaustif pour mesurer le rappel
+    recall_sample_rate: f64,
+    // budget du cache de listes de chaque collection aux listes IVF sur disque
+    list_cache_bytes: usize,
+    // taille de memtable déclenchant un vidage en segment (compact_segments), 0 = jamais
+    memtable_max_vectors: usize,
+    // expédition d'instantanés vers un répertoire secondaire (voir snapshot)
+    snapshots: Option<SnapshotShipper>,
+    // rappels transmis à chaque collection chargée (voir hooks)
+    hooks: HookSet,
+    rerankers: RwLock<RerankerRegistry>,
+    // embeddings des textes de requête calculés côté serveur
+    embedding_cache: EmbeddingCache,
+    // durée de garde et maximum des ids supprimés de chaque collection
+    tombstone_policy: TombstonePolicy,
+    // instantanés des collections en cache en mode snapshot, hors du verrou global
+    // que les écritures tiennent (voir query_snapshot)
+    views: RwLock<HashMap<String, Arc<PublishedView>>>,
+    // signature des curseurs de pagination (voir cursor)
+    cursor_key: CursorKey,
+    packed: Option<PackedFile>,
+}
+
+// base ouverte depuis un fichier unique : les collections vivent dans un
+// répertoire de travail temporaire, réécrit dans le fichier par sync_file
+struct PackedFile {
+    path: PathBuf,
+    work_dir: PathBuf,
+    // verrou exclusif sur le fichier .vdb
+    _lock: File,
+}
+
+// collection chargée depuis le disque mais non admise dans le cache
+enum Loaded {
+    Cached,
+    Transient(Box<Collection>),
 }
 
 impl VectorDbClient {
     pub fn new<P: AsRef<Path>>(path: P) -> Result<Self> {
-        let storage = Storage::new(path)?;
-        let max_cached = std::env::var("VECTORDB_MAX_CACHED")
-            .ok()
-            .and_then(|v| v.parse().ok())
-            .unwrap_or(20);
+        Self::with_cache_config(path, CacheConfig::from_env())
+    }
 
-        Ok(Self {
+    // VECTORDB_STARTUP_SCAN=true : scan de cohérence (voir scan_storage) à l'ouverture,
+    // plutôt qu'une erreur au premier accès à une collection à moitié écrite
+    pub fn with_cache_config<P: AsRef<Path>>(path: P, cache_config: CacheConfig) -> Result<Self> {
+        let client = Self::with_storage(Storage::new(path)?, cache_config);
+        let startup_scan = std::env::var("VECTORDB_STARTUP_SCAN")
+            .map(|v| v == "true" || v == "1")
+            .unwrap_or(false);
+        if startup_scan {
+            client.scan_storage(false)?;
+        }
+        Ok(client)
+    }
+
+    // lecteur d'un répertoire dont un autre processus détient l'écriture ;
+    // les collections en cache sont rechargées après chaque sauvegarde de l'écrivain
+    pub fn open_read_only<P: AsRef<Path>>(path: P) -> Result<Self> {
+        Ok(Self::with_storage(Storage::open_read_only(path)?, CacheConfig::from_env()))
+    }
+
+    // base entière dans un seul fichier (créé s'il n'existe pas), pratique pour
+    // distribuer un jeu de données préparé ; sync_file ou le drop du client
+    // réécrivent le fichier
+    pub fn open_file<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let lock = OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .read(true)
+            .write(true)
+            .open(&path)?;
+        match lock.try_lock() {
+            Ok(()) => {}
+            Err(TryLockError::WouldBlock) => {
+                return Err(VectorDbError::StorageLocked(path.display().to_string()));
+            }
+            Err(TryLockError::Error(e)) => return Err(e.into()),
+        }
+
+        let work_dir = std::env::temp_dir().join(format!(
+            "vectordb-{}-{}",
+            std::process::id(),
+            SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_nanos()
+        ));
+        let storage = Storage::new(&work_dir)?;
+        // fichier tout juste créé : base vide
+        if lock.metadata()?.len() > 0 {
+            storage.import_packed(&path)?;
+        }
+
+        let mut client = Self::with_storage(storage, CacheConfig::from_env());
+        client.packed = Some(PackedFile {
+            path,
+            work_dir,
+            _lock: lock,
+        });
+        Ok(client)
+    }
+
+    // réécrire le fichier .vdb à partir de l'état courant
+    pub fn sync_file(&self) -> Result<()> {
+        match self.packed {
+            Some(ref packed) => {
+                // verrou en écriture : pas de sauvegarde en cours pendant l'export
+                let _colls = self.collections.write().unwrap();
+                self.storage.export_packed(&packed.path)?;
+                Ok(())
+            }
+            None => Err(VectorDbError::InvalidConfig(
+                "Client was not opened from a single file".to_string(),
+            )),
+        }
+    }
+
+    // exporter toutes les collections dans un fichier unique, quel que soit le mode d'ouverture
+    pub fn export_file<P: AsRef<Path>>(&self, path: P) -> Result<usize> {
+        let _colls = self.collections.write().unwrap();
+        self.storage.export_packed(path.as_ref())
+    }
+
+    fn with_storage(storage: Storage, cache_config: CacheConfig) -> Self {
+        Self {
             storage,
             collections: Arc::new(RwLock::new(HashMap::new())),
-            max_cached,
-        })
+            cache_config,
+            cache_counters: CacheCounters::default(),
+            miss_counts: Mutex::new(HashMap::new()),
+            op_stats: Mutex::new(HashMap::new()),
+            stats_history: Mutex::new(HashMap::new()),
+            stats_history_len: DEFAULT_STATS_HISTORY_LEN,
+            created_at: Instant::now(),
+            limits: Limits::from_env(),
+            models: RwLock::new(ModelRegistry::default()),
+            profiles: RwLock::new(ProfileRegistry::default()),
+            deferred_rebuild: false,
+            query_log: None,
+            recall_sample_rate: 0.0,
+            list_cache_bytes: 0,
+            memtable_max_vectors: 0,
+            snapshots: None,
+            hooks: HookSet::default(),
+            rerankers: RwLock::new(RerankerRegistry::default()),
+            embedding_cache: EmbeddingCache::default(),
+            tombstone_policy: TombstonePolicy::default(),
+            views: RwLock::new(HashMap::new()),
+            cursor_key: CursorKey::random(),
+            packed: None,
+        }
     }
 
-    fn now() -> u64 {
+    pub fn is_read_only(&self) -> bool {
+        self.storage.is_read_only()
+    }
+
+    // l'écrivain a sauvegardé la collection depuis son chargement
+    fn is_stale(&self, name: &str, cached: &CachedCollection) -> bool {
+        self.storage.is_read_only() && self.storage.header_stamp(name) != cached.disk_stamp
+    }
+
+    fn evict_if_stale(&self, colls: &mut HashMap<String, CachedCollection>, name: &str) {
+        if colls.get(name).is_some_and(|c| self.is_stale(name, c)) {
+            self.evict(colls, name);
+        }
+    }
+
+    // retrait du cache ; les CollectionHandle qui la tiennent la relisent
+    fn evict(&self, colls: &mut HashMap<String, CachedCollection>, name: &str) {
+        if let Some(cached) = colls.remove(name) {
+            cached.detach();
+        }
+        self.views.write().unwrap().remove(name);
+    }
+
+    fn now_ms() -> u64 {
         SystemTime::now()
             .duration_since(UNIX_EPOCH)
             .unwrap()
-            .as_secs()
+            .as_millis() as u64
+    }
+
+    // insérer dans le cache selon la politique configurée ; si l'admission est
+    // refusée la collection est rendue à l'appelant pour un usage ponctuel
+    fn admit(
+        &self,
+        colls: &mut HashMap<String, CachedCollection>,
+        name: &str,
+        mut collection: Collection,
+        load_cost_ms: f64,
+        force: bool,
+    ) -> Loaded {
+        collection.set_deferred_rebuild(self.deferred_rebuild);
+        collection.set_read_only(self.storage.is_read_only());
+        let max_vectors = self.limits.max_vectors_per_collection;
+        collection.set_vector_limit(max_vectors, self.limits.soft_limit(max_vectors));
+        collection.set_list_cache_bytes(self.list_cache_bytes);
+        collection.set_hooks(self.hooks.clone());
+        collection.set_tombstone_policy(self.tombstone_policy);
+        let disk_stamp = self.storage.header_stamp(name);
+        let now = Self::now_ms();
+        let size = collection.stats().estimated_memory_bytes;
+        let misses = {
+            let mut miss_counts = self.miss_counts.lock().unwrap();
+            let n = miss_counts.entry(name.to_string()).or_insert(0);
+            *n += 1;
+            *n
+        };
+        let incoming_value = misses as f64 * load_cost_ms.max(1.0);
+
+        let plan = self.cache_config.plan_admission(
+            colls.iter().map(|(n, c)| (n, c.stats.as_ref())),
+            size,
+            incoming_value,
+            force,
+            now,
+        );
+
+        match plan {
+            Some(victims) => {
+                for victim in &victims {
+                    self.evict(colls, victim);
+                }
+                CacheCounters::incr(&self.cache_counters.evictions, victims.len() as u64);
+                self.miss_counts.lock().unwrap().remove(name);
+                self.open_view(name, &mut collection);
+                colls.insert(name.to_string(), CachedCollection {
+                    collection: Arc::new(RwLock::new(collection)),
+                    stats: Arc::new(CacheEntryStats::new(size, load_cost_ms, now)),
+                    disk_stamp,
+                });
+                Loaded::Cached
+            }
+            None => {
+                CacheCounters::incr(&self.cache_counters.rejected_admissions, 1);
+                tracing::debug!(collection = %name, size_bytes = size, "Cache admission rejected");
+                Loaded::Transient(Box::new(collection))
+            }
+        }
+    }
+
+    // premier instantané d'une collection en mode snapshot qui entre dans le cache.
+    // Pas d'instantané pour un lecteur : l'écrivain est un autre processus et seul le
+    // chemin verrouillé détecte qu'il a sauvegardé depuis le chargement
+    fn open_view(&self, name: &str, collection: &mut Collection) {
+        if collection.config.concurrency != ConcurrencyMode::Snapshot || self.storage.is_read_only() {
+            return;
+        }
+        match collection.read_view() {
+            Ok(view) => {
+                self.views.write().unwrap().insert(name.to_string(), Arc::new(PublishedView::new(view)));
+            }
+            Err(e) => {
+                tracing::warn!(collection = %name, error = %e, "Snapshot view unavailable, queries stay locked");
+            }
+        }
     }
 
-    fn evict_lru(&self, colls: &mut HashMap<String, CachedCollection>) {
-        if colls.len() < self.max_cached {
+    // publie les écritures suivies depuis la publication précédente ; en cas d'échec
+    // l'instantané est retiré et les requêtes repassent par le verrou
+    fn publish_view(&self, collection: &mut Collection) {
+        let name = collection.config.name.clone();
+        let Some(published) = self.views.read().unwrap().get(&name).cloned() else {
             return;
+        };
+        match collection.take_view_changes() {
+            Ok(changes) if changes.is_empty() => {}
+            Ok(changes) => published.publish(published.load().apply(&collection.config, changes)),
+            Err(e) => {
+                tracing::warn!(collection = %name, error = %e, "Snapshot view dropped");
+                self.views.write().unwrap().remove(&name);
+            }
         }
+    }
+
+    // dernier instantané publié d'une collection en mode snapshot, None si elle n'est
+    // pas en cache : l'appelant passe alors par with_collection
+    pub fn read_view(&self, name: &str) -> Option<Arc<ReadView>> {
+        let published = self.views.read().unwrap().get(name).cloned()?;
+        Some(published.load())
+    }
 
-        // trouver la collection la moins récemment utilisée
-        if let Some((oldest_name, _)) = colls
+    // requête sur l'instantané publié, sans verrou de collection ni verrou global du
+    // cache ; None si aucun instantané n'est disponible
+    pub fn query_snapshot(
+        &self,
+        name: &str,
+        query_embedding: &[f32],
+        n_results: usize,
+        where_filter: Option<&WhereFilter>,
+        options: &QueryOptions,
+        embedding_model: Option<&str>,
+    ) -> Result<Option<Vec<SearchResult>>> {
+        let Some(view) = self.read_view(name) else {
+            return Ok(None);
+        };
+        self.record_op(name, |s| s.reads += 1);
+        view.config().check_embedding_model(embedding_model)?;
+        view.query(query_embedding, n_results, where_filter, options).map(Some)
+    }
+
+    // à appeler sous verrou en écriture, après avoir vérifié l'absence du cache
+    fn load_into_cache(
+        &self,
+        colls: &mut HashMap<String, CachedCollection>,
+        name: &str,
+        force: bool,
+    ) -> Result<Loaded> {
+        CacheCounters::incr(&self.cache_counters.misses, 1);
+        let start = Instant::now();
+        let collection = self.storage.load_collection(name)?;
+        let elapsed = start.elapsed();
+        self.record_op(name, |s| s.loads.record(elapsed));
+        let load_cost_ms = elapsed.as_secs_f64() * 1000.0;
+        Ok(self.admit(colls, name, collection, load_cost_ms, force))
+    }
+
+    pub fn cache_metrics(&self) -> CacheMetrics {
+        use std::sync::atomic::Ordering::Relaxed;
+
+        let colls = self.collections.read().unwrap();
+        let now = Self::now_ms();
+        let entries: Vec<CacheEntryMetrics> = colls
             .iter()
-            .min_by_key(|(_, cached)| cached.last_access)
-        {
-            let name = oldest_name.clone();
-            colls.remove(&name);
+            .map(|(name, c)| CacheEntryMetrics {
+                name: name.clone(),
+                size_bytes: c.stats.size_bytes(),
+                load_cost_ms: c.stats.load_cost_ms,
+                hits: c.stats.hits(),
+                idle_ms: now.saturating_sub(c.stats.last_access()),
+            })
+            .collect();
+
+        CacheMetrics {
+            config: self.cache_config.clone(),
+            cached_collections: entries.len(),
+            cached_bytes: entries.iter().map(|e| e.size_bytes).sum(),
+            hits: self.cache_counters.hits.load(Relaxed),
+            misses: self.cache_counters.misses.load(Relaxed),
+            evictions: self.cache_counters.evictions.load(Relaxed),
+            rejected_admissions: self.cache_counters.rejected_admissions.load(Relaxed),
+            entries,
         }
     }
 
-    pub fn create_collection(&self, name: String, dimension: usize) -> Result<()> {
-        let mut colls = self.collections.write().unwrap();
+    pub fn register_embedding_model(&self, name: String, dimension: usize) {
+        self.models.write().unwrap().register(name, dimension);
+    }
 
-        if colls.contains_key(&name) || self.storage.collection_exists(&name) {
-            return Err(VectorDbError::CollectionAlreadyExists(name));
+    // le profil est validé comme une config de collection (limites, modèle connu)
+    pub fn register_profile(&self, name: String, profile: CollectionProfile) -> Result<()> {
+        let config = profile.config(&name, name.clone());
+        self.limits.validate_config(&config)?;
+        config.rebuild_policy.validate()?;
+        if let Some(ref model) = config.embedding_model {
+            self.models.read().unwrap().check(model, config.dimension)?;
         }
+        self.profiles.write().unwrap().register(name, profile);
+        Ok(())
+    }
 
-        let coll = Collection::new(name.clone(), dimension);
-        self.storage.save_collection(&coll)?;
+    pub fn profile(&self, name: &str) -> Result<CollectionProfile> {
+        self.profiles.read().unwrap().get(name).cloned()
+    }
 
-        self.evict_lru(&mut colls);
-        colls.insert(name, CachedCollection {
-            collection: coll,
-            last_access: Self::now(),
-        });
+    pub fn list_profiles(&self) -> Vec<(String, CollectionProfile)> {
+        self.profiles.read().unwrap().list()
+    }
 
-        Ok(())
+    pub fn create_collection_from_profile(&self, name: String, profile: &str) -> Result<()> {
+        let config = self.profile(profile)?.config(profile, name);
+        self.create_collection_with_config(config)
+    }
+
+    pub fn create_collection(&self, name: String, dimension: usize) -> Result<()> {
+        self.create_collection_with_config(CollectionConfig::new(name, dimension))
     }
 
     pub fn create_collection_with_ivf(
@@ -79,20 +628,343 @@ impl VectorDbClient {
         dimension: usize,
         n_clusters: usize,
     ) -> Result<()> {
+        self.create_collection_with_config(CollectionConfig::new(name, dimension).with_ivf(n_clusters))
+    }
+
+    pub fn set_limits(&mut self, limits: Limits) {
+        self.limits = limits;
+    }
+
+    pub fn limits(&self) -> &Limits {
+        &self.limits
+    }
+
+    // en mode différé, les requêtes ne déclenchent plus de rebuild sous verrou ;
+    // l'appelant lance rebuild_index_background quand rebuild_due() le signale
+    pub fn set_deferred_rebuild(&mut self, deferred: bool) {
+        self.deferred_rebuild = deferred;
+    }
+
+    pub fn set_query_log(&mut self, log: QueryLog) {
+        self.query_log = Some(log);
+    }
+
+    // journalise une requête servie (voir querylog) ; un échec d'écriture est
+    // signalé sans faire échouer la requête
+    pub fn log_query(
+        &self,
+        collection: &str,
+        query_embedding: &[f32],
+        where_filter: Option<&serde_json::Value>,
+        n_results: usize,
+        exact: bool,
+        latency: Duration,
+    ) {
+        if let Some(ref log) = self.query_log {
+            let entry = log.entry(collection, query_embedding, where_filter, n_results, exact, latency);
+            if let Err(e) = log.record(&entry) {
+                tracing::warn!(path = %log.path().display(), error = %e, "Failed to write query log");
+            }
+        }
+    }
+
+    // collections déjà en cache non concernées
+    pub fn set_list_cache_bytes(&mut self, bytes: usize) {
+        self.list_cache_bytes = bytes;
+    }
+
+    // rappels sur ajouts, suppressions, requêtes et rebuilds de toutes les collections,
+    // y compris celles déjà en cache
+    pub fn add_hooks(&mut self, hooks: Arc<dyn Hooks>) {
+        self.hooks.push(hooks);
+        for cached in self.collections.read().unwrap().values() {
+            cached.write().set_hooks(self.hooks.clone());
+        }
+    }
+
+    // clé partagée : sans elle, les curseurs émis avant un redémarrage sont refusés
+    pub fn set_cursor_key(&mut self, key: CursorKey) {
+        self.cursor_key = key;
+    }
+
+    pub fn cursor_key(&self) -> &CursorKey {
+        &self.cursor_key
+    }
+
+    pub fn set_tombstone_policy(&mut self, policy: TombstonePolicy) {
+        self.tombstone_policy = policy;
+    }
+
+    // purge selon la politique des collections en cache qui en ont besoin, les autres
+    // étant purgées à leur chargement ; renvoie le nombre purgé par collection
+    pub fn purge_tombstones(&self) -> Result<BTreeMap<String, usize>> {
+        let names: Vec<String> = self.collections.read().unwrap().keys().cloned().collect();
+        let mut purged = BTreeMap::new();
+        for name in names {
+            match self.with_collection(&name, |c| c.tombstones_need_purge()) {
+                Ok(true) => {}
+                Ok(false) | Err(VectorDbError::CollectionNotFound(_)) => continue,
+                Err(e) => return Err(e),
+            }
+            let n = self.with_collection_mut(&name, |c| Ok(c.purge_tombstones()))?;
+            if n > 0 {
+                purged.insert(name, n);
+            }
+        }
+        Ok(purged)
+    }
+
+    pub fn set_embedding_cache(&mut self, cache: EmbeddingCache) {
+        self.embedding_cache = cache;
+    }
+
+    pub fn embedding_cache(&self) -> &EmbeddingCache {
+        &self.embedding_cache
+    }
+
+    // remplace un reranker existant, "default" compris
+    pub fn register_reranker(&self, name: String, reranker: Arc<dyn Reranker>) {
+        self.rerankers.write().unwrap().register(name, reranker);
+    }
+
+    pub fn reranker(&self, name: &str) -> Result<Arc<dyn Reranker>> {
+        self.rerankers.read().unwrap().get(name)
+    }
+
+    pub fn set_snapshot_shipper(&mut self, shipper: SnapshotShipper) {
+        self.snapshots = Some(shipper);
+    }
+
+    fn snapshot_shipper(&self) -> Result<&SnapshotShipper> {
+        self.snapshots.as_ref().ok_or_else(|| {
+            VectorDbError::InvalidConfig("snapshot shipping is not configured (VECTORDB_SNAPSHOT_DIR)".to_string())
+        })
+    }
+
+    // nouvel instantané de toutes les collections dans le répertoire configuré
+    pub fn ship_snapshot(&self) -> Result<ShippedSnapshot> {
+        self.snapshot_shipper()?.ship(self)
+    }
+
+    pub fn list_snapshots(&self) -> Result<Vec<SnapshotInfo>> {
+        self.snapshot_shipper()?.list()
+    }
+
+    pub fn prune_snapshots(&self, dry_run: bool) -> Result<PruneReport> {
+        self.snapshot_shipper()?.prune(dry_run)
+    }
+
+    pub fn set_recall_sample_rate(&mut self, rate: f64) {
+        self.recall_sample_rate = rate.clamp(0.0, 1.0);
+    }
+
+    // tirage : cette requête doit-elle être vérifiée par check_recall ?
+    pub fn sample_recall(&self) -> bool {
+        self.recall_sample_rate > 0.0 && rand::random::<f64>() < self.recall_sample_rate
+    }
+
+    // rejoue une requête servie par l'index en exhaustif et comptabilise le rappel
+    // (recall de /debug/collections). Les écritures reçues entre-temps peuvent
+    // légèrement fausser la mesure
+    pub fn check_recall(
+        &self,
+        name: &str,
+        query_embedding: &[f32],
+        n_results: usize,
+        where_filter: Option<&WhereFilter>,
+        options: &QueryOptions,
+        results: &[SearchResult],
+    ) -> Result<f64> {
+        let truth = self.with_collection(name, |coll| {
+            coll.exact_results(query_embedding, n_results, where_filter, options)
+        })??;
+        let recall = recall(results, &truth);
+        self.record_op(name, |s| s.recall.record(recall));
+        Ok(recall)
+    }
+
+    pub fn set_stats_history_len(&mut self, len: usize) {
+        self.stats_history_len = len.max(1);
+    }
+
+    // instantané (taille, mémoire, QPS, latence p95) de chaque collection chargée,
+    // à appeler périodiquement ; les collections hors cache ne sont pas échantillonnées
+    pub fn snapshot_stats(&self) {
+        let colls = self.collections.read().unwrap();
+        let mut history = self.stats_history.lock().unwrap();
+        for (name, cached) in colls.iter() {
+            let collection = cached.read();
+            let stats = collection.stats();
+            let latencies = collection.take_latencies();
+            history
+                .entry(name.clone())
+                .or_insert_with(|| StatsHistory::new(self.created_at))
+                .push(stats.count, stats.estimated_memory_bytes, latencies, self.stats_history_len);
+        }
+    }
+
+    pub fn stats_history(&self, name: &str) -> Result<Vec<StatsSnapshot>> {
+        if !self.storage.collection_exists(name) {
+            return Err(VectorDbError::CollectionNotFound(name.to_string()));
+        }
+        Ok(self
+            .stats_history
+            .lock()
+            .unwrap()
+            .get(name)
+            .map(|h| h.snapshots.iter().cloned().collect())
+            .unwrap_or_default())
+    }
+
+    // rebuild IVF sans bloquer la collection pendant le k-means : capture sous
+    // verrou, entraînement hors verrou, puis échange de l'index sous verrou.
+    // Les écritures concurrentes sont acceptées et rejouées sur le nouvel index ;
+    // un second rebuild simultané est refusé (RebuildInProgress)
+    pub fn rebuild_index_background(&self, name: &str) -> Result<CollectionStats> {
+        // listes sur disque : le rebuild réécrit le fichier, verrou tenu
+        if self.with_collection(name, |coll| coll.is_offloaded())? {
+            return self.offload_collection(name);
+        }
+        let job = self.with_collection_mut(name, |coll| coll.begin_rebuild())?;
+        let index = job.run();
+
+        self.with_collection_mut(name, |coll| {
+            if !coll.finish_rebuild(index) {
+                // collection rechargée entre-temps : son index est déjà reconstruit
+                tracing::debug!(collection = %name, "Discarding stale rebuilt index");
+            }
+            Ok(coll.stats())
+        })
+    }
+
+    // listes IVF rangées sur disque (lists.dat dans le répertoire de la collection) :
+    // seuls centroïdes, ids et métadonnées restent en mémoire. Sur une collection
+    // déjà sur disque, réentraîne l'index et réécrit le fichier
+    pub fn offload_collection(&self, name: &str) -> Result<CollectionStats> {
+        let dir = self.storage.collection_path(name);
+        self.with_collection_mut(name, |coll| {
+            coll.offload_to_disk(&dir)?;
+            Ok(coll.stats())
+        })
+    }
+
+    // collection figée dans un segment immuable (dans le répertoire de la collection),
+    // projeté en mémoire au chargement ; écritures refusées (Frozen)
+    pub fn freeze_collection(&self, name: &str) -> Result<CollectionStats> {
+        let dir = self.storage.collection_path(name);
+        self.with_collection_mut(name, |coll| {
+            coll.freeze(&dir)?;
+            Ok(coll.stats())
+        })
+    }
+
+    // embeddings recopiés en mémoire, segments retirés à la sauvegarde
+    pub fn thaw_collection(&self, name: &str) -> Result<CollectionStats> {
+        self.with_collection_mut(name, |coll| {
+            coll.thaw()?;
+            Ok(coll.stats())
+        })
+    }
+
+    // contrôle complet des sommes de contrôle des segments d'une collection
+    pub fn verify_segments(&self, name: &str) -> Result<Vec<SegmentInfo>> {
+        self.with_collection(name, |coll| coll.verify_segments())
+    }
+
+    // memtable écrite dans un nouveau segment ; le premier vidage range toute la
+    // collection. None si la memtable était vide
+    pub fn flush_collection(&self, name: &str) -> Result<Option<SegmentInfo>> {
+        let dir = self.storage.collection_path(name);
+        self.with_collection_mut(name, |coll| coll.flush_memtable(&dir))
+    }
+
+    // une fusion de segments (voir segment::merge_candidates), écrite hors verrou
+    // comme un rebuild ; None s'il n'y avait rien à fusionner ou si la collection a
+    // changé entre-temps
+    pub fn merge_segments(&self, name: &str) -> Result<Option<SegmentInfo>> {
+        let dir = self.storage.collection_path(name);
+        let Some(job) = self.with_collection_mut(name, |coll| Ok(coll.plan_merge(&dir)))? else {
+            return Ok(None);
+        };
+        let seq = job.seq;
+        let merged = match job.run() {
+            Ok(merged) => merged,
+            Err(e) => {
+                self.with_collection_mut(name, |coll| {
+                    coll.abort_merge();
+                    Ok(())
+                })?;
+                return Err(e);
+            }
+        };
+        self.with_collection_mut(name, |coll| {
+            if !coll.finish_merge(merged) {
+                tracing::debug!(collection = %name, "Discarding stale merged segment");
+                return Ok(None);
+            }
+            Ok(coll.segment_infos().into_iter().find(|info| info.seq == seq))
+        })
+    }
+
+    // 0 = pas de vidage automatique
+    pub fn set_memtable_max_vectors(&mut self, max_vectors: usize) {
+        self.memtable_max_vectors = max_vectors;
+    }
+
+    // tâche de fond des collections en cache : vidage des memtables pleines
+    // (set_memtable_max_vectors), puis fusion des segments qui le demandent ;
+    // renvoie le nombre de segments écrits par collection
+    pub fn compact_segments(&self) -> Result<BTreeMap<String, usize>> {
+        let names: Vec<String> = self.collections.read().unwrap().keys().cloned().collect();
+        let mut written = BTreeMap::new();
+        for name in names {
+            let max_vectors = self.memtable_max_vectors;
+            let (flush, segmented) = match self.with_collection(&name, |c| {
+                let writable = !c.is_frozen() && !c.is_offloaded() && !c.rebuild_in_progress();
+                (writable && max_vectors > 0 && c.memtable_len() >= max_vectors, c.is_segmented())
+            }) {
+                Ok(state) => state,
+                Err(VectorDbError::CollectionNotFound(_)) => continue,
+                Err(e) => return Err(e),
+            };
+            let mut n = 0;
+            if flush && self.flush_collection(&name)?.is_some() {
+                n += 1;
+            }
+            if (segmented || n > 0) && self.merge_segments(&name)?.is_some() {
+                n += 1;
+            }
+            if n > 0 {
+                written.insert(name, n);
+            }
+        }
+        Ok(written)
+    }
+
+    pub fn create_collection_with_config(&self, config: CollectionConfig) -> Result<()> {
+        self.limits.validate_config(&config)?;
+        config.rebuild_policy.validate()?;
+        if let Some(ref model) = config.embedding_model {
+            self.models.read().unwrap().check(model, config.dimension)?;
+        }
+
         let mut colls = self.collections.write().unwrap();
+        let name = config.name.clone();
 
         if colls.contains_key(&name) || self.storage.collection_exists(&name) {
             return Err(VectorDbError::CollectionAlreadyExists(name));
         }
+        let existing = self.storage.list_collections()?.len();
+        self.limits.check_collection_count(existing)?;
+        if let Some(usage) = self.limits.usage(existing + 1, self.limits.max_collections) {
+            if usage.soft_exceeded {
+                tracing::warn!(collections = usage.used, max_collections = usage.max, "Approaching the collection limit");
+            }
+        }
 
-        let coll = Collection::new_with_ivf(name.clone(), dimension, n_clusters);
+        let coll = Collection::from_config(config);
         self.storage.save_collection(&coll)?;
-
-        self.evict_lru(&mut colls);
-        colls.insert(name, CachedCollection {
-            collection: coll,
-            last_access: Self::now(),
-        });
+        self.admit(&mut colls, &name, coll, 0.0, true);
 
         Ok(())
     }
@@ -100,27 +972,55 @@ impl VectorDbClient {
     pub fn get_collection(&self, name: &str) -> Result<()> {
         let mut collections = self.collections.write().unwrap();
 
-        if !collections.contains_key(name) {
-            let collection = self.storage.load_collection(name)?;
-            self.evict_lru(&mut collections);
-            collections.insert(name.to_string(), CachedCollection {
-                collection,
-                last_access: Self::now(),
-            });
-        } else {
-            // update access time
-            if let Some(cached) = collections.get_mut(name) {
-                cached.last_access = Self::now();
+        // chargement explicite : toujours admis dans le cache
+        match collections.get(name) {
+            Some(cached) => cached.stats.touch(Self::now_ms()),
+            None => {
+                self.load_into_cache(&mut collections, name, true)?;
             }
         }
 
         Ok(())
     }
 
+    // vide le cache : les collections sont relues depuis le disque au prochain accès.
+    // Tout est déjà persisté après chaque écriture, rien n'est perdu
+    pub fn clear_cache(&self) -> Vec<String> {
+        let mut collections = self.collections.write().unwrap();
+        let mut evicted: Vec<String> = collections
+            .drain()
+            .map(|(name, cached)| {
+                cached.detach();
+                name
+            })
+            .collect();
+        evicted.sort();
+        self.views.write().unwrap().clear();
+        self.miss_counts.lock().unwrap().clear();
+        CacheCounters::incr(&self.cache_counters.evictions, evicted.len() as u64);
+        tracing::info!(evicted = evicted.len(), "Cache cleared");
+        evicted
+    }
+
+    // remplace l'état en mémoire par celui du disque (après une restauration de fichiers)
+    pub fn reload_collection(&self, name: &str) -> Result<CollectionStats> {
+        let mut collections = self.collections.write().unwrap();
+        if !self.storage.collection_exists(name) {
+            return Err(VectorDbError::CollectionNotFound(name.to_string()));
+        }
+        self.evict(&mut collections, name);
+        self.load_into_cache(&mut collections, name, true)?;
+        tracing::info!(collection = %name, "Collection reloaded from storage");
+        let stats = collections[name].read().stats();
+        Ok(stats)
+    }
+
     pub fn delete_collection(&self, name: &str) -> Result<()> {
         let mut collections = self.collections.write().unwrap();
-        collections.remove(name);
         self.storage.delete_collection(name)?;
+        self.evict(&mut collections, name);
+        self.op_stats.lock().unwrap().remove(name);
+        self.stats_history.lock().unwrap().remove(name);
         Ok(())
     }
 
@@ -128,59 +1028,952 @@ impl VectorDbClient {
         self.storage.list_collections()
     }
 
+    // config et nombre de vecteurs, sans charger la collection dans le cache
+    pub fn collection_info(&self, name: &str) -> Result<CollectionHeader> {
+        {
+            let colls = self.collections.read().unwrap();
+            if let Some(cached) = colls.get(name) {
+                let coll = cached.read();
+                return Ok(CollectionHeader {
+                    format_version: self.storage.format_version(name).unwrap_or(CURRENT_FORMAT_VERSION),
+                    ..CollectionHeader::of(&coll)
+                });
+            }
+        }
+
+        self.storage.load_collection_meta(name)
+    }
+
+    pub fn list_collections_details(&self) -> Result<Vec<CollectionHeader>> {
+        self.list_collections()?
+            .iter()
+            .filter(|name| self.storage.collection_exists(name))
+            .map(|name| self.collection_info(name))
+            .collect()
+    }
+
+    // migration des collections vers le format courant ; les collections en cache
+    // sont retirées pour être relues depuis les fichiers migrés
+    pub fn migrate_storage(&self, dry_run: bool) -> Result<Vec<MigrationReport>> {
+        let mut colls = self.collections.write().unwrap();
+        let reports = self.storage.migrate_all(dry_run)?;
+        if !dry_run {
+            for report in &reports {
+                self.evict(&mut colls, &report.collection);
+            }
+        }
+        Ok(reports)
+    }
+
+    // comparaison complète de deux collections (voir diff.rs). `left` est relu depuis
+    // le disque ou l'instantané, sans passer par le cache ; `right` est lu en mémoire
+    // s'il s'agit d'une collection de la base
+    pub fn diff_collections(&self, left: &DiffSource, right: &DiffSource, options: &DiffOptions) -> Result<CollectionDiff> {
+        let reference = self.load_diff_source(left)?;
+        let mut diff = match right.snapshot {
+            Some(_) => diff_collections(&reference, &self.load_diff_source(right)?, options)?,
+            None => self.with_collection(&right.collection, |coll| diff_collections(&reference, coll, options))??,
+        };
+        diff.left = left.label();
+        diff.right = right.label();
+        Ok(diff)
+    }
+
+    fn load_diff_source(&self, source: &DiffSource) -> Result<Collection> {
+        match source.snapshot {
+            Some(ref path) => self.storage.load_packed_collection(path, &source.collection),
+            None if self.storage.collection_exists(&source.collection) => {
+                self.storage.load_collection(&source.collection)
+            }
+            None => Err(VectorDbError::CollectionNotFound(source.collection.clone())),
+        }
+    }
+
+    // suppression des vecteurs correspondant au filtre dans toutes les collections
+    // (droit à l'effacement, voir Collection::erase_where) : memtable, segments
+    // réécrits et pierres tombales compris, puis les requêtes du journal qui les
+    // concernent. Les instantanés expédiés (VECTORDB_SNAPSHOT_DIR) ne sont PAS réécrits :
+    // ils sont listés dans snapshots_not_scrubbed. Une collection en erreur
+    // n'interrompt pas les autres et l'opération est toujours consignée dans
+    // audit.log, même partielle
+    pub fn scrub(&self, filter: &WhereFilter, reason: Option<String>) -> Result<ScrubReport> {
+        if self.storage.is_read_only() {
+            return Err(VectorDbError::ReadOnly("scrub requires a writable storage".to_string()));
+        }
+        validate_filter(filter)?;
+
+        let mut report = ScrubReport {
+            at: chrono::Utc::now().to_rfc3339(),
+            filter: filter.clone(),
+            reason,
+            deleted: BTreeMap::new(),
+            total_deleted: 0,
+            segments_rewritten: 0,
+            query_log_purged: 0,
+            snapshots_not_scrubbed: Vec::new(),
+            errors: BTreeMap::new(),
+        };
+        let mut content_hashes = HashSet::new();
+        let mut names = self.list_collections()?;
+        names.sort();
+        for name in names.into_iter().filter(|n| self.storage.collection_exists(n)) {
+            let dir = self.storage.collection_path(&name);
+            match self.with_collection_mut(&name, |coll| coll.erase_where(filter, &dir)) {
+                Ok(erasure) => {
+                    report.total_deleted += erasure.deleted;
+                    report.segments_rewritten += erasure.segments_rewritten;
+                    report.deleted.insert(name, erasure.deleted);
+                    content_hashes.extend(erasure.content_hashes);
+                }
+                Err(e) => {
+                    report.errors.insert(name, e.to_string());
+                }
+            }
+        }
+        if let Some(ref log) = self.query_log {
+            match log.purge(|entry| concerns_erasure(entry, filter, &content_hashes)) {
+                Ok(n) => report.query_log_purged = n,
+                Err(e) => {
+                    report.errors.insert("(query log)".to_string(), e.to_string());
+                }
+            }
+        }
+        if let Some(ref snapshots) = self.snapshots {
+            match snapshots.list() {
+                Ok(list) => report.snapshots_not_scrubbed = list.into_iter().map(|s| s.name).collect(),
+                Err(e) => {
+                    report.errors.insert("(snapshots)".to_string(), e.to_string());
+                }
+            }
+        }
+
+        self.storage.append_audit(&report)?;
+        tracing::info!(
+            total_deleted = report.total_deleted,
+            collections = report.deleted.len(),
+            segments_rewritten = report.segments_rewritten,
+            query_log_purged = report.query_log_purged,
+            snapshots_not_scrubbed = report.snapshots_not_scrubbed.len(),
+            errors = report.errors.len(),
+            "Scrub completed"
+        );
+        Ok(report)
+    }
+
+    // fichiers temporaires et répertoires vides supprimés, collections incomplètes
+    // ou illisibles déplacées sous corrupted/ ; le rapport est aussi journalisé
+    pub fn scan_storage(&self, dry_run: bool) -> Result<ScanReport> {
+        let mut colls = self.collections.write().unwrap();
+        let report = self.storage.scan_consistency(dry_run)?;
+        for temp_file in &report.removed_temp_files {
+            tracing::warn!(file = %temp_file, dry_run, "Leftover temporary file from an interrupted write");
+        }
+        for q in &report.quarantined {
+            if !dry_run {
+                self.evict(&mut colls, &q.collection);
+            }
+            tracing::warn!(
+                collection = %q.collection,
+                reason = %q.reason,
+                moved_to = q.moved_to.as_deref().unwrap_or("-"),
+                dry_run,
+                "Inconsistent collection quarantined"
+            );
+        }
+        tracing::info!(
+            scanned = report.scanned,
+            temp_files = report.removed_temp_files.len(),
+            empty_dirs = report.removed_empty_dirs.len(),
+            quarantined = report.quarantined.len(),
+            dry_run,
+            "Storage consistency scan finished"
+        );
+        Ok(report)
+    }
+
+    // import d'un répertoire persistant Chroma ; aucune collection n'est créée
+    // si l'une d'elles existe déjà
+    #[cfg(feature = "chroma-import")]
+    pub fn import_chroma<P: AsRef<Path>>(&self, path: P) -> Result<Vec<(String, usize)>> {
+        let collections = crate::chroma::read_chroma(path.as_ref())?;
+
+        if let Some(existing) = collections
+            .iter()
+            .find(|c| self.storage.collection_exists(&c.name))
+        {
+            return Err(VectorDbError::CollectionAlreadyExists(existing.name.clone()));
+        }
+
+        let mut imported = Vec::with_capacity(collections.len());
+        for chroma in collections {
+            // collection Chroma jamais alimentée : dimension inconnue
+            if chroma.dimension == 0 {
+                tracing::warn!(collection = %chroma.name, "Skipping empty Chroma collection");
+                continue;
+            }
+            let config = CollectionConfig::new(chroma.name.clone(), chroma.dimension)
+                .with_metadata_limits(chroma.metadata_limits());
+            self.create_collection_with_config(config)?;
+
+            let count = chroma.ids.len();
+            self.with_collection_mut(&chroma.name, |coll| {
+                coll.add_with_source(chroma.ids, chroma.embeddings, Some(chroma.metadatas), "chroma")
+            })?;
+            tracing::info!(collection = %chroma.name, count = count, "Imported Chroma collection");
+            imported.push((chroma.name, count));
+        }
+
+        Ok(imported)
+    }
+
+    // export au format faiss : IndexIVFFlat si l'index IVF est construit, sinon
+    // IndexIDMap(IndexFlatIP) ; les ids sont écrits dans <path>.ids.json
+    pub fn export_faiss<P: AsRef<Path>>(&self, name: &str, path: P) -> Result<usize> {
+        let path = path.as_ref();
+        self.with_collection(name, |coll| {
+            let entries: Vec<_> = coll.entries().collect();
+            let ids: Vec<&String> = entries.iter().map(|e| &e.id).collect();
+            let embeddings = entries.iter().map(|e| coll.embedding_of(e)).collect::<Result<Vec<_>>>()?;
+            let vectors: Vec<&[f32]> = embeddings.iter().map(|e| e.as_ref()).collect();
+
+            match coll.ivf_index() {
+                Some(ivf) => {
+                    let centroids: Vec<&[f32]> = ivf.centroids.iter().map(|c| c.as_slice()).collect();
+                    // affectation recalculée : les listes peuvent contenir des ids supprimés
+                    let assignments: Vec<usize> = vectors.iter().map(|v| ivf.nearest_centroid(v)).collect();
+                    faiss::write_ivf_flat(path, coll.config.dimension, &centroids, ivf.n_probe, &vectors, &assignments)?;
+                }
+                None => faiss::write_flat(path, coll.config.dimension, &vectors)?,
+            }
+
+            let ids_file = File::create(faiss::ids_path(path))?;
+            serde_json::to_writer(std::io::BufWriter::new(ids_file), &ids)?;
+            Ok(ids.len())
+        })?
+    }
+
+    // import d'un index faiss plat ou IVF-Flat dans une nouvelle collection ;
+    // les centroïdes et listes d'un index IVF sont repris tels quels
+    pub fn import_faiss<P: AsRef<Path>>(&self, path: P, name: &str) -> Result<usize> {
+        let path = path.as_ref();
+        let index = faiss::read_index(path)?;
+
+        // ids vectordb depuis le fichier compagnon, sinon le label faiss
+        let ids_path = faiss::ids_path(path);
+        let known_ids: Option<Vec<String>> = if ids_path.exists() {
+            Some(serde_json::from_reader(std::io::BufReader::new(File::open(ids_path)?))?)
+        } else {
+            None
+        };
+        let id_of = |label: i64| -> Result<String> {
+            match known_ids {
+                Some(ref ids) => ids.get(label as usize).cloned().ok_or_else(|| {
+                    VectorDbError::Serialization(format!("Faiss label {} has no entry in the ids file", label))
+                }),
+                None => Ok(label.to_string()),
+            }
+        };
+        let ids: Vec<String> = index.labels.iter().map(|&l| id_of(l)).collect::<Result<_>>()?;
+
+        let mut config = CollectionConfig::new(name.to_string(), index.dimension);
+        if let Some(ref ivf) = index.ivf {
+            config = config.with_ivf(ivf.centroids.len());
+        }
+        self.create_collection_with_config(config)?;
+
+        let imported_ivf = match index.ivf {
+            Some(ivf) => {
+                let lists = ivf.lists
+                    .iter()
+                    .map(|list| list.iter().map(|&l| id_of(l)).collect::<Result<Vec<_>>>())
+                    .collect::<Result<Vec<_>>>()?;
+                Some(IVFIndex::from_trained(ivf.centroids, lists, ivf.n_probe))
+            }
+            None => None,
+        };
+
+        let count = ids.len();
+        self.with_collection_mut(name, |coll| {
+            coll.add_with_source(ids, index.vectors, None, "faiss")?;
+            if let Some(ivf) = imported_ivf {
+                coll.install_ivf_index(ivf);
+            }
+            Ok(())
+        })?;
+
+        Ok(count)
+    }
+
+    // import d'embeddings numpy (.npy ou .npz) sans passer par JSON ; la collection
+    // est créée si besoin avec la dimension du tableau. Ids : tableau "ids" du .npz,
+    // sinon `ids_file`, sinon générés (<nom du fichier>-<ligne>)
+    pub fn import_npy<P: AsRef<Path>>(
+        &self,
+        name: &str,
+        path: P,
+        ids_file: Option<&Path>,
+        array: Option<&str>,
+    ) -> Result<usize> {
+        let path = path.as_ref();
+        let data = npy::read_embeddings(path, array)?;
+
+        let ids = match (data.ids, ids_file) {
+            (_, Some(file)) => npy::read_ids_file(file)?,
+            (Some(ids), None) => ids,
+            (None, None) => {
+                let stem = path.file_stem().and_then(|s| s.to_str()).unwrap_or("npy");
+                (0..data.embeddings.len()).map(|i| format!("{}-{}", stem, i)).collect()
+            }
+        };
+        if ids.len() != data.embeddings.len() {
+            return Err(VectorDbError::InvalidConfig(format!(
+                "{} ids for {} embeddings",
+                ids.len(),
+                data.embeddings.len()
+            )));
+        }
+
+        if !self.storage.collection_exists(name) && !self.is_loaded(name) {
+            self.create_collection_with_config(CollectionConfig::new(name.to_string(), data.dimension))?;
+        }
+
+        let count = ids.len();
+        self.with_collection_mut(name, |coll| {
+            coll.add_with_source(ids, data.embeddings, None, "npy")
+        })?;
+        Ok(count)
+    }
+
+    pub fn is_loaded(&self, name: &str) -> bool {
+        self.collections.read().unwrap().contains_key(name)
+    }
+
+    pub fn stats(&self, name: &str) -> Result<CollectionStats> {
+        {
+            let colls = self.collections.read().unwrap();
+            if let Some(cached) = colls.get(name) {
+                return Ok(cached.read().stats());
+            }
+        }
+
+        let header = self.storage.load_collection_meta(name)?;
+        let mut stats = CollectionStats::unloaded(&header.config, header.count, header.modifications_count);
+        stats.vector_limit = self.limits.usage(header.count, self.limits.max_vectors_per_collection);
+        Ok(stats)
+    }
+
+    // nombre de collections rapporté à max_collections, None sans limite
+    pub fn collection_limit_usage(&self) -> Result<Option<LimitUsage>> {
+        let existing = self.storage.list_collections()?.len();
+        Ok(self.limits.usage(existing, self.limits.max_collections))
+    }
+
+    // état de l'index sans charger la collection (en-tête seul si elle n'est pas en cache)
+    pub fn index_status(&self, name: &str) -> Result<IndexStatus> {
+        {
+            let colls = self.collections.read().unwrap();
+            if let Some(cached) = colls.get(name) {
+                return Ok(cached.read().index_status());
+            }
+        }
+
+        let header = self.storage.load_collection_meta(name)?;
+        Ok(IndexStatus::unloaded(&header.config, header.modifications_count))
+    }
+
+    // include absent : métadonnées et embeddings ; sans "embeddings", lecture des
+    // seules métadonnées (voir get_metadata_only)
+    pub fn get(&self, name: &str, ids: Option<Vec<String>>, include: Option<Vec<String>>) -> Result<GetResult> {
+        let wants_embeddings = include
+            .as_ref()
+            .is_none_or(|inc| inc.iter().any(|i| i == "embeddings"));
+        if wants_embeddings {
+            return self.with_collection(name, |coll| {
+                self.limits.check_get_results(ids.as_ref().map_or(coll.count(), Vec::len))?;
+                coll.get(ids, include)
+            })?;
+        }
+
+        if let Some(ref ids) = ids {
+            self.limits.check_get_results(ids.len())?;
+        }
+        let mut result = self.get_metadata_only(name, ids)?;
+        self.limits.check_get_results(result.ids.len())?;
+        if !include.is_some_and(|inc| inc.iter().any(|i| i == "metadatas")) {
+            result.metadatas = None;
+        }
+        Ok(result)
+    }
+
+    // get sérialisé en JSON par morceaux, pour les réponses trop grosses pour être
+    // construites en mémoire ; le document produit est celui de GetResult
+    pub fn get_stream(
+        &self,
+        name: &str,
+        ids: Option<Vec<String>>,
+        include: Option<Vec<String>>,
+    ) -> Result<GetStream<'_>> {
+        let include = include.unwrap_or_else(|| vec!["metadatas".to_string(), "embeddings".to_string()]);
+        let snapshot = self.with_collection(name, |coll| {
+            self.limits.check_get_results(ids.as_ref().map_or(coll.count(), Vec::len))?;
+            coll.get(ids, Some(vec!["metadatas".to_string()]))
+        })??;
+        Ok(GetStream {
+            client: self,
+            name: name.to_string(),
+            ids: snapshot.ids,
+            metadatas: snapshot.metadatas.filter(|_| include.iter().any(|i| i == "metadatas")),
+            embeddings: include.iter().any(|i| i == "embeddings"),
+            part: GetStreamPart::Head,
+        })
+    }
+
+    // get par pages dans l'ordre croissant des ids (voir Collection::page_ids) : la
+    // position est portée par un curseur signé, lié au filtre et à la version des
+    // données ; une écriture dans la collection entre deux pages le rend périmé
+    // (StaleCursor) plutôt que de sauter ou répéter des vecteurs
+    pub fn scroll(&self, name: &str, request: ScrollRequest) -> Result<ScrollPage> {
+        if request.limit == Some(0) {
+            return Err(VectorDbError::InvalidConfig("limit must be at least 1".to_string()));
+        }
+        self.with_collection(name, |coll| {
+            let filter = request.filter.as_ref();
+            let version = coll.data_version();
+            let after = match request.cursor {
+                Some(ref token) => Some(self.cursor_key.open(token, name, filter, version)?.after),
+                None => None,
+            };
+            let (ids, has_more) = coll.page_ids(filter, after.as_deref(), request.offset, request.limit)?;
+            self.limits.check_get_results(ids.len())?;
+            let next_cursor = match ids.last() {
+                Some(last) if has_more => {
+                    Some(self.cursor_key.sign(&PageCursor::new(name, last.clone(), filter, version)))
+                }
+                _ => None,
+            };
+            let result = coll.get(Some(ids), request.include)?;
+            Ok(ScrollPage { result, next_cursor })
+        })?
+    }
+
+    // plusieurs get en un appel, dans l'ordre des requêtes ; une collection
+    // inexistante fait échouer l'ensemble
+    pub fn get_multi(&self, requests: Vec<MultiGetRequest>) -> Result<Vec<MultiGetResult>> {
+        requests
+            .into_iter()
+            .map(|req| {
+                let result = self.get(&req.collection, Some(req.ids), req.include)?;
+                Ok(MultiGetResult { collection: req.collection, result })
+            })
+            .collect()
+    }
+
+    // get sans embeddings : si la collection n'est pas en cache, seules les
+    // métadonnées sont lues sur disque et rien n'est ajouté au cache
+    pub fn get_metadata_only(&self, name: &str, ids: Option<Vec<String>>) -> Result<GetResult> {
+        {
+            let colls = self.collections.read().unwrap();
+            if let Some(cached) = colls.get(name) {
+                return cached.read().get(ids, Some(vec!["metadatas".to_string()]));
+            }
+        }
+
+        let stored = self.storage.load_metadata(name)?;
+        let (ids, metadatas): (Vec<String>, Vec<_>) = match ids {
+            Some(id_list) => {
+                let mut by_id: HashMap<String, _> = stored.into_iter().collect();
+                id_list
+                    .into_iter()
+                    .filter_map(|id| by_id.remove(&id).map(|m| (id, m)))
+                    .unzip()
+            }
+            None => stored.into_iter().unzip(),
+        };
+
+        Ok(GetResult {
+            ids,
+            embeddings: None,
+            metadatas: Some(metadatas),
+        })
+    }
+
+    pub fn count(&self, name: &str, filter: Option<&WhereFilter>) -> Result<usize> {
+        {
+            let colls = self.collections.read().unwrap();
+            if let Some(cached) = colls.get(name) {
+                let coll = cached.read();
+                return match filter {
+                    Some(f) => {
+                        coll.validate_filter(f)?;
+                        Ok(coll.count_where(f))
+                    }
+                    None => Ok(coll.count()),
+                };
+            }
+        }
+
+        let stored = self.storage.load_metadata(name)?;
+        Ok(match filter {
+            Some(f) => {
+                validate_filter(f)?;
+                let keys: Vec<&str> = f.keys().map(String::as_str).collect();
+                check_filter_types(f, &observed_types(&keys, stored.iter().map(|(_, m)| m)))?;
+                stored.iter().filter(|(_, m)| matches_filter(m, f)).count()
+            }
+            None => stored.len(),
+        })
+    }
+
+    fn record_op(&self, name: &str, update: impl FnOnce(&mut CollectionOpStats)) {
+        let mut op_stats = self.op_stats.lock().unwrap();
+        update(op_stats.entry(name.to_string()).or_default());
+    }
+
+    fn save_timed(&self, collection: &Collection) -> Result<()> {
+        // lecteur : seul l'état en mémoire (index, compteurs) a pu changer,
+        // les écritures de données sont refusées par la collection
+        if self.storage.is_read_only() {
+            return Ok(());
+        }
+        let start = Instant::now();
+        let with_embeddings = collection.embeddings_dirty();
+        self.storage.save_collection_parts(collection, with_embeddings)?;
+        let elapsed = start.elapsed();
+        self.record_op(&collection.config.name, |s| s.saves.record(elapsed));
+        Ok(())
+    }
+
+    // attente sur le verrou global (partagé par toutes les collections),
+    // comptabilisée pour la collection demandée
+    pub fn collection_op_stats(&self) -> Vec<CollectionOpReport> {
+        let loaded: Vec<String> = self.collections.read().unwrap().keys().cloned().collect();
+        let op_stats = self.op_stats.lock().unwrap();
+        let mut reports: Vec<CollectionOpReport> = op_stats
+            .iter()
+            .map(|(name, stats)| {
+                CollectionOpReport::new(name.clone(), loaded.contains(name), stats.clone())
+            })
+            .collect();
+        reports.sort_by(|a, b| a.name.cmp(&b.name));
+        reports
+    }
+
     pub fn with_collection<F, R>(&self, name: &str, f: F) -> Result<R>
     where
         F: FnOnce(&Collection) -> R,
     {
         // try read lock first
         {
+            let wait_start = Instant::now();
             let colls = self.collections.read().unwrap();
-            if let Some(cached) = colls.get(name) {
-                return Ok(f(&cached.collection));
+            let wait = wait_start.elapsed();
+            if let Some(cached) = colls.get(name).filter(|c| !self.is_stale(name, c)) {
+                self.record_op(name, |s| {
+                    s.reads += 1;
+                    s.read_lock_wait.record(wait);
+                });
+                cached.stats.touch(Self::now_ms());
+                CacheCounters::incr(&self.cache_counters.hits, 1);
+                return Ok(f(&cached.read()));
             }
         }
 
         // not in cache, need to load with write lock
+        let wait_start = Instant::now();
         let mut colls = self.collections.write().unwrap();
+        let wait = wait_start.elapsed();
+        self.record_op(name, |s| {
+            s.reads += 1;
+            s.write_lock_wait.record(wait);
+        });
 
         // double-check in case another thread loaded it
+        self.evict_if_stale(&mut colls, name);
         if !colls.contains_key(name) {
-            let collection = self.storage.load_collection(name)?;
-            self.evict_lru(&mut colls);
-            colls.insert(name.to_string(), CachedCollection {
-                collection,
-                last_access: Self::now(),
-            });
+            if let Loaded::Transient(collection) = self.load_into_cache(&mut colls, name, false)? {
+                drop(colls);
+                return Ok(f(&collection));
+            }
         }
 
-        let cached = colls.get_mut(name).unwrap();
-        cached.last_access = Self::now();
-        Ok(f(&cached.collection))
+        let cached = colls.get(name).unwrap();
+        cached.stats.touch(Self::now_ms());
+        let res = f(&cached.read());
+        Ok(res)
     }
 
     pub fn with_collection_mut<F, R>(&self, name: &str, f: F) -> Result<R>
     where
         F: FnOnce(&mut Collection) -> Result<R>,
     {
+        let wait_start = Instant::now();
         let mut colls = self.collections.write().unwrap();
+        let wait = wait_start.elapsed();
+        self.record_op(name, |s| {
+            s.writes += 1;
+            s.write_lock_wait.record(wait);
+        });
 
         // auto-load if not present
-        if !colls.contains_key(name) {
-            let collection = self.storage.load_collection(name)?;
-            self.evict_lru(&mut colls);
-            colls.insert(name.to_string(), CachedCollection {
-                collection,
-                last_access: Self::now(),
-            });
+        self.evict_if_stale(&mut colls, name);
+        if colls.contains_key(name) {
+            CacheCounters::incr(&self.cache_counters.hits, 1);
+        } else if let Loaded::Transient(mut collection) =
+            self.load_into_cache(&mut colls, name, false)?
+        {
+            // le verrou reste tenu : deux écritures ponctuelles ne doivent pas se croiser
+            let res = f(&mut collection)?;
+            self.save_timed(&collection)?;
+            return Ok(res);
         }
 
         let cached = colls
-            .get_mut(name)
+            .get(name)
             .ok_or_else(|| VectorDbError::CollectionNotFound(name.to_string()))?;
 
-        cached.last_access = Self::now();
-        let res = f(&mut cached.collection)?;
-        self.storage.save_collection(&cached.collection)?;
+        cached.stats.touch(Self::now_ms());
+        let res = self.write_locked(&cached.stats, &mut cached.write(), f);
+        res
+    }
+
+    // écriture dans une collection en cache, verrou de la collection tenu, puis sauvegarde
+    fn write_locked<F, R>(&self, stats: &CacheEntryStats, collection: &mut Collection, f: F) -> Result<R>
+    where
+        F: FnOnce(&mut Collection) -> Result<R>,
+    {
+        // publié même si f échoue après une partie de ses modifications
+        let res = f(collection);
+        self.publish_view(collection);
+        let res = res?;
+
+        let with_embeddings = collection.embeddings_dirty();
+        self.save_timed(collection)?;
+        collection.mark_persisted();
+        if with_embeddings {
+            stats.set_size_bytes(collection.stats().estimated_memory_bytes);
+        }
         Ok(res)
     }
+
+    // handle durable sur une collection (chargée si besoin) : voir CollectionHandle
+    pub fn collection(&self, name: &str) -> Result<CollectionHandle<'_>> {
+        Ok(CollectionHandle {
+            client: self,
+            name: name.to_string(),
+            entry: Mutex::new(self.pin(name)?),
+        })
+    }
+
+    // entrée en cache marquée comme tenue par un handle, sous verrou global pour
+    // ne pas croiser une éviction
+    fn pin(&self, name: &str) -> Result<CachedCollection> {
+        let mut colls = self.collections.write().unwrap();
+        self.evict_if_stale(&mut colls, name);
+        if !colls.contains_key(name) {
+            // admission forcée : un handle n'a pas de sens sur une copie ponctuelle
+            self.load_into_cache(&mut colls, name, true)?;
+        }
+        let cached = colls
+            .get(name)
+            .cloned()
+            .ok_or_else(|| VectorDbError::CollectionNotFound(name.to_string()))?;
+        cached.stats.pin();
+        Ok(cached)
+    }
+}
+
+// référence à une collection gardée d'un appel à l'autre par un utilisateur
+// embarqué : les opérations prennent directement le verrou de la collection, sans
+// recherche par nom ni verrou global du cache. Tant qu'un handle existe la
+// collection n'est pas évincée ; supprimée, rechargée ou migrée, elle est relue
+// au prochain appel (CollectionNotFound si elle n'existe plus).
+// Partageable entre threads (&CollectionHandle)
+pub struct CollectionHandle<'a> {
+    client: &'a VectorDbClient,
+    name: String,
+    entry: Mutex<CachedCollection>,
+}
+
+impl CollectionHandle<'_> {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn entry(&self) -> Result<CachedCollection> {
+        let mut entry = self.entry.lock().unwrap();
+        if entry.stats.is_evicted() || self.client.is_stale(&self.name, &entry) {
+            let fresh = self.client.pin(&self.name)?;
+            entry.stats.unpin();
+            *entry = fresh;
+        }
+        Ok(entry.clone())
+    }
+
+    pub fn read<F, R>(&self, f: F) -> Result<R>
+    where
+        F: FnOnce(&Collection) -> R,
+    {
+        loop {
+            let cached = self.entry()?;
+            let collection = cached.read();
+            // retirée du cache entre entry() et la prise du verrou
+            if cached.stats.is_evicted() {
+                continue;
+            }
+            cached.stats.touch(VectorDbClient::now_ms());
+            self.client.record_op(&self.name, |s| s.reads += 1);
+            return Ok(f(&collection));
+        }
+    }
+
+    // l'écriture est sauvegardée avant de rendre la main, comme with_collection_mut
+    pub fn write<F, R>(&self, f: F) -> Result<R>
+    where
+        F: FnOnce(&mut Collection) -> Result<R>,
+    {
+        loop {
+            let cached = self.entry()?;
+            let mut collection = cached.write();
+            if cached.stats.is_evicted() {
+                continue;
+            }
+            cached.stats.touch(VectorDbClient::now_ms());
+            self.client.record_op(&self.name, |s| s.writes += 1);
+            return self.client.write_locked(&cached.stats, &mut collection, f);
+        }
+    }
+
+    pub fn add(
+        &self,
+        ids: Vec<String>,
+        embeddings: Vec<Vec<f32>>,
+        metadatas: Option<Vec<HashMap<String, MetadataValue>>>,
+    ) -> Result<()> {
+        self.write(|coll| coll.add(ids, embeddings, metadatas))
+    }
+
+    pub fn delete(&self, ids: Vec<String>) -> Result<()> {
+        self.write(|coll| coll.delete(ids))
+    }
+
+    pub fn query(
+        &self,
+        query_embedding: &[f32],
+        n_results: usize,
+        where_filter: Option<&WhereFilter>,
+    ) -> Result<Vec<SearchResult>> {
+        self.write(|coll| coll.query(query_embedding, n_results, where_filter))
+    }
+
+    pub fn get(&self, ids: Option<Vec<String>>, include: Option<Vec<String>>) -> Result<GetResult> {
+        self.read(|coll| coll.get(ids, include))?
+    }
+
+    pub fn count(&self) -> Result<usize> {
+        self.read(|coll| coll.count())
+    }
+
+    pub fn stats(&self) -> Result<CollectionStats> {
+        self.read(|coll| coll.stats())
+    }
+}
+
+impl Drop for CollectionHandle<'_> {
+    fn drop(&mut self) {
+        // sans paniquer dans drop si un thread a paniqué sous le verrou
+        self.entry.get_mut().unwrap_or_else(PoisonError::into_inner).stats.unpin();
+    }
+}
+
+impl Drop for VectorDbClient {
+    fn drop(&mut self) {
+        if let Some(ref packed) = self.packed {
+            if let Err(e) = self.storage.export_packed(&packed.path) {
+                tracing::error!(path = %packed.path.display(), error = %e, "Failed to write database file");
+                // garder le répertoire de travail pour ne rien perdre
+                return;
+            }
+            let _ = std::fs::remove_dir_all(&packed.work_dir);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::filter::FilterBuilder;
+    use crate::querylog::{QueryLogMode, QueryLogReader};
+    use crate::testing::TempDir;
+
+    #[test]
+    fn test_scrub() {
+        let dir = TempDir::new("scrub");
+        let mut client = VectorDbClient::new(&dir).unwrap();
+        let log_path = dir.join("queries.log");
+        client.set_query_log(QueryLog::open(&log_path, QueryLogMode::Full).unwrap());
+        // pierres tombales des ids effacés retirées elles aussi
+        client.create_collection_with_config(CollectionConfig::new("docs".into(), 2).with_tombstones()).unwrap();
+        for name in ["notes", "ints"] {
+            client.create_collection(name.into(), 2).unwrap();
+        }
+        let user = |u: &str| HashMap::from([("user_id".to_string(), MetadataValue::from(u))]);
+        let ids = vec!["a".to_string(), "b".to_string(), "c".to_string()];
+        let embeddings = vec![vec![1.0, 0.0]; 3];
+        client.with_collection_mut("docs", |c| c.add(ids.clone(), embeddings.clone(), Some(vec![user("u1"), user("u2"), user("u1")]))).unwrap();
+        client.with_collection_mut("notes", |c| c.add(ids.clone(), embeddings.clone(), Some(vec![user("u2"); 3]))).unwrap();
+        // user_id entier : filtre incompatible, collection rapportée en erreur
+        let ints = vec![HashMap::from([("user_id".to_string(), MetadataValue::Int(1))]); 3];
+        client.with_collection_mut("ints", |c| c.add(ids, embeddings, Some(ints))).unwrap();
+        // docs rangée dans un segment : les lignes effacées doivent quitter le fichier
+        client.flush_collection("docs").unwrap();
+        for user in ["u1", "u2"] {
+            let filter = serde_json::json!({"user_id": {"$in": [user]}});
+            client.log_query("docs", &[1.0, 0.0], Some(&filter), 1, false, Duration::ZERO);
+        }
+        client.log_query("docs", &[1.0, 0.0], None, 1, false, Duration::ZERO);
+
+        let filter = FilterBuilder::new().eq("user_id", "u1").build().unwrap();
+        let report = client.scrub(&filter, Some("ticket-42".to_string())).unwrap();
+        assert_eq!(report.total_deleted, 2);
+        assert_eq!(report.deleted, BTreeMap::from([("docs".to_string(), 2), ("notes".to_string(), 0)]));
+        assert_eq!(report.errors.keys().collect::<Vec<_>>(), ["ints"]);
+        assert_eq!((report.segments_rewritten, report.query_log_purged), (1, 1));
+        assert!(report.snapshots_not_scrubbed.is_empty());
+
+        // suppression persistée et consignée
+        client.clear_cache();
+        assert_eq!(client.with_collection("docs", |c| c.get(None, None)).unwrap().unwrap().ids, ["b"]);
+        let segments = client.with_collection("docs", |c| c.segment_infos()).unwrap();
+        assert_eq!((segments.len(), segments[0].vectors, segments[0].deleted), (1, 1, 0));
+        assert_eq!(client.with_collection("docs", |c| c.tombstone_stats().count).unwrap(), 0);
+        let logged: Vec<_> = QueryLogReader::open(&log_path).unwrap().map(|e| e.unwrap().where_filter).collect();
+        assert_eq!(logged, [Some(r#"{"user_id":{"$in":["u2"]}}"#.to_string()), None]);
+        let audit = std::fs::read_to_string(dir.join("audit.log")).unwrap();
+        let record: serde_json::Value = serde_json::from_str(audit.lines().next().unwrap()).unwrap();
+        assert_eq!((record["reason"].as_str(), record["total_deleted"].as_u64()), (Some("ticket-42"), Some(2)));
+
+        drop(client);
+        let reader = VectorDbClient::open_read_only(&dir).unwrap();
+        assert!(matches!(reader.scrub(&filter, None), Err(VectorDbError::ReadOnly(_))));
+    }
+
+    #[test]
+    fn test_get_stream_matches_get() {
+        let dir = TempDir::new("getstream");
+        let mut client = VectorDbClient::new(&dir).unwrap();
+        client.create_collection("docs".into(), 4).unwrap();
+        // plus de deux blocs d'embeddings
+        let n = 2 * GET_STREAM_CHUNK + 10;
+        let ids: Vec<String> = (0..n).map(|i| format!("v{}", i)).collect();
+        let embeddings = (0..n).map(|i| vec![1.0, i as f32, 0.5, -1.0]).collect();
+        let metas = (0..n).map(|i| HashMap::from([("i".to_string(), MetadataValue::Int(i as i64))])).collect();
+        client.with_collection_mut("docs", |c| c.add(ids, embeddings, Some(metas))).unwrap();
+
+        let collect = |stream: GetStream| -> GetResult {
+            let bytes: Vec<u8> = stream.flat_map(Result::unwrap).collect();
+            serde_json::from_slice(&bytes).unwrap()
+        };
+        let expected = client.get("docs", None, None).unwrap();
+        let streamed = collect(client.get_stream("docs", None, None).unwrap());
+        assert_eq!(streamed.ids, expected.ids);
+        assert_eq!(streamed.embeddings, expected.embeddings);
+        assert_eq!(streamed.metadatas, expected.metadatas);
+
+        let ids = Some(vec!["v3".to_string(), "missing".to_string(), "v1".to_string()]);
+        let include = Some(vec!["embeddings".to_string()]);
+        let expected = client.get("docs", ids.clone(), include.clone()).unwrap();
+        let streamed = collect(client.get_stream("docs", ids, include).unwrap());
+        assert_eq!(streamed.ids, ["v3", "v1"]);
+        assert_eq!(streamed.embeddings, expected.embeddings);
+        assert!(streamed.metadatas.is_none());
+
+        // plafond appliqué avant toute lecture, au get classique comme au flux
+        client.set_limits(Limits { max_get_results: 100, ..Limits::default() });
+        assert!(matches!(client.get("docs", None, None), Err(VectorDbError::LimitExceeded(_))));
+        assert!(matches!(client.get_stream("docs", None, None), Err(VectorDbError::LimitExceeded(_))));
+        assert!(client.get("docs", Some(vec!["v1".to_string()]), None).is_ok());
+    }
+
+    #[test]
+    fn test_scroll_pages() {
+        let dir = TempDir::new("scroll");
+        let client = VectorDbClient::new(&dir).unwrap();
+        client.create_collection("docs".into(), 2).unwrap();
+        let n = 25;
+        let ids: Vec<String> = (0..n).map(|i| format!("v{:02}", i)).collect();
+        let embeddings = (0..n).map(|i| vec![1.0, i as f32]).collect();
+        let metas = (0..n).map(|i| HashMap::from([("even".to_string(), MetadataValue::Bool(i % 2 == 0))])).collect();
+        client.with_collection_mut("docs", |c| c.add(ids.clone(), embeddings, Some(metas))).unwrap();
+
+        // parcours complet par pages de 10 : ordre des ids, sans trou ni doublon
+        let mut seen = Vec::new();
+        let mut cursor = None;
+        loop {
+            let request = ScrollRequest { limit: Some(10), cursor, ..ScrollRequest::default() };
+            let page = client.scroll("docs", request).unwrap();
+            assert_eq!(page.result.embeddings.as_ref().unwrap().len(), page.result.ids.len());
+            seen.extend(page.result.ids);
+            cursor = page.next_cursor;
+            if cursor.is_none() {
+                break;
+            }
+        }
+        assert_eq!(seen, ids);
+
+        let page = client
+            .scroll("docs", ScrollRequest { limit: Some(3), offset: 5, ..ScrollRequest::default() })
+            .unwrap();
+        assert_eq!(page.result.ids, ["v05", "v06", "v07"]);
+
+        // filtre lié au curseur
+        let filter = crate::filter::FilterBuilder::new().eq("even", true).build().unwrap();
+        let request = ScrollRequest { filter: Some(filter.clone()), limit: Some(12), ..ScrollRequest::default() };
+        let page = client.scroll("docs", request).unwrap();
+        assert_eq!(page.result.ids.len(), 12);
+        let token = page.next_cursor.unwrap();
+        let request = ScrollRequest { cursor: Some(token.clone()), ..ScrollRequest::default() };
+        assert!(matches!(client.scroll("docs", request), Err(VectorDbError::InvalidCursor(_))));
+        let request = ScrollRequest { filter: Some(filter.clone()), cursor: Some(token.clone()), ..ScrollRequest::default() };
+        let page = client.scroll("docs", request).unwrap();
+        assert_eq!(page.result.ids, ["v24"]);
+        assert!(page.next_cursor.is_none());
+
+        // écriture entre deux pages : curseur périmé
+        client.with_collection_mut("docs", |c| c.delete(vec!["v00".to_string()])).unwrap();
+        let request = ScrollRequest { filter: Some(filter), cursor: Some(token), ..ScrollRequest::default() };
+        assert!(matches!(client.scroll("docs", request), Err(VectorDbError::StaleCursor(_))));
+    }
+
+    #[test]
+    fn test_scroll_survives_reload() {
+        let dir = TempDir::new("scroll_reload");
+        let key = CursorKey::from_secret("shared");
+        let mut client = VectorDbClient::new(&dir).unwrap();
+        client.set_cursor_key(key.clone());
+        client.create_collection("docs".into(), 2).unwrap();
+        let ids: Vec<String> = (0..10).map(|i| format!("v{:02}", i)).collect();
+        let embeddings = (0..10).map(|i| vec![1.0, i as f32]).collect();
+        client.with_collection_mut("docs", |c| c.add(ids.clone(), embeddings, None)).unwrap();
+        let scroll = |client: &VectorDbClient, cursor: Option<String>| {
+            client.scroll("docs", ScrollRequest { limit: Some(3), cursor, ..ScrollRequest::default() }).unwrap()
+        };
+
+        // la version des données est relue de l'en-tête : le curseur reste valable
+        // après un vidage du cache, un rechargement ou un redémarrage
+        let mut page = scroll(&client, None);
+        let mut seen = page.result.ids;
+        client.clear_cache();
+        page = scroll(&client, page.next_cursor);
+        seen.extend(page.result.ids);
+        client.reload_collection("docs").unwrap();
+        page = scroll(&client, page.next_cursor);
+        seen.extend(page.result.ids);
+        drop(client);
+        let mut restarted = VectorDbClient::new(&dir).unwrap();
+        restarted.set_cursor_key(key);
+        page = scroll(&restarted, page.next_cursor);
+        seen.extend(page.result.ids);
+        assert!(page.next_cursor.is_none());
+        assert_eq!(seen, ids);
+    }
 }
@@ -1,11 +1,38 @@
-use crate::distance::{cosine_distance, normalize_l2};
+use crate::columns::MetadataColumns;
+use crate::dictionary::{DictionaryStats, MetadataDictionary};
+use crate::disk_ivf::{DiskLists, DiskListsWriter, ListCacheStats, DISK_LISTS_FILE};
+use crate::distance::{cosine_distance, cosine_distances, dot_product, normalize_l2};
+use crate::drift::{BatchSample, DriftMonitor, DriftReport, DEFAULT_DRIFT_THRESHOLD};
 use crate::error::{Result, VectorDbError};
-use crate::filter::{matches_filter, WhereFilter};
+use crate::filter::{self, compare_values, matches_filter, ObservedTypes, WhereFilter};
+use crate::half::{HalfVector, Precision};
+use crate::hooks::{AddEvent, DeleteEvent, HookSet, QueryEvent, RebuildEvent, RebuildKind};
 use crate::ivf::IVFIndex;
-use crate::vector::{MetadataValue, VectorEntry};
+use crate::late_interaction::{max_sim, normalize_tokens, pool, TokenMatrix, VectorAggregation};
+use crate::metadata::{self, MetadataLimits, MetadataStats};
+use crate::limits::LimitUsage;
+use crate::metrics::LatencyWindow;
+use crate::query::QueryBuilder;
+use crate::readview::{ConcurrencyMode, ReadView, ViewChange};
+use crate::sq::{Int8Vector, ScalarQuantization};
+use crate::segment::{
+    merge_candidates, segment_file, write_segment, LiveSegment, MergeJob, MergedSegment, Segment, SegmentInfo,
+    SegmentManifest,
+};
+use crate::tombstones::{TombstonePolicy, TombstoneSet, TombstoneStats};
+use crate::topk::{top_k_by, TopK};
+use crate::vector::{
+    embedding_hash, is_reserved_key, BinaryVector, MetadataValue, VectorEntry, VectorType,
+    SYSTEM_CONTENT_HASH, SYSTEM_CREATED_AT, SYSTEM_SOURCE, SYSTEM_UPDATED_AT,
+};
 use rayon::prelude::*;
-use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use serde::{Deserialize, Deserializer, Serialize};
+use std::borrow::Cow;
+use std::cmp::Ordering;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CollectionConfig {
@@ -13,6 +40,508 @@ pub struct CollectionConfig {
     pub dimension: usize,
     pub use_ivf: bool,
     pub n_clusters: usize,
+    #[serde(default)]
+    pub embedding_model: Option<String>,
+    #[serde(default)]
+    pub metadata_limits: MetadataLimits,
+    // taille attendue, utilisée pour valider n_clusters à la création
+    #[serde(default)]
+    pub expected_size: Option<usize>,
+    // calcule _system.content_hash à l'ajout quand le client n'en fournit pas
+    #[serde(default)]
+    pub content_hash: bool,
+    // seuil d'alerte du score de dérive par lot, DEFAULT_DRIFT_THRESHOLD si absent
+    #[serde(default)]
+    pub drift_threshold: Option<f32>,
+    // profil ayant servi à la création (voir profiles.rs)
+    #[serde(default)]
+    pub profile: Option<String>,
+    #[serde(default)]
+    pub rebuild_policy: RebuildPolicy,
+    #[serde(default)]
+    pub index_params: IndexParams,
+    // entrées porteuses d'une matrice d'embeddings de tokens (voir late_interaction)
+    #[serde(default)]
+    pub late_interaction: bool,
+    // IVF-PQ : vecteurs des listes encodés sur pq_subspaces octets, candidats classés
+    // par distance approchée (voir pq) ; None : distances exactes
+    #[serde(default)]
+    pub pq_subspaces: Option<usize>,
+    // embeddings stockés en int8 (voir sq), recherche en distance approchée ; None : f32
+    #[serde(default)]
+    pub scalar_quantization: Option<ScalarQuantization>,
+    // binary : `dimension` bits par vecteur, distance de Hamming, recherche exhaustive
+    #[serde(default)]
+    pub vector_type: VectorType,
+    // vecteurs gardés tels qu'ajoutés (rendus par get) à côté de leur copie normalisée
+    #[serde(default)]
+    pub store_raw: bool,
+    // f16 / bf16 : embeddings normalisés stockés sur 2 octets par composante (voir half)
+    #[serde(default)]
+    pub precision: Precision,
+    // snapshot : requêtes servies par un instantané copié à l'écriture, sans verrou
+    // de collection (voir readview)
+    #[serde(default)]
+    pub concurrency: ConcurrencyMode,
+    // suppressions suivies par des pierres tombales (voir tombstones) : un ajout
+    // répliqué antérieur à la suppression ne ressuscite pas l'id. Désactivé par défaut
+    #[serde(default)]
+    pub tombstones: bool,
+}
+
+impl CollectionConfig {
+    pub fn new(name: String, dimension: usize) -> Self {
+        Self {
+            name,
+            dimension,
+            use_ivf: false,
+            n_clusters: 0,
+            embedding_model: None,
+            metadata_limits: MetadataLimits::default(),
+            expected_size: None,
+            content_hash: false,
+            drift_threshold: None,
+            profile: None,
+            rebuild_policy: RebuildPolicy::default(),
+            index_params: IndexParams::default(),
+            late_interaction: false,
+            pq_subspaces: None,
+            scalar_quantization: None,
+            vector_type: VectorType::Float,
+            store_raw: false,
+            precision: Precision::F32,
+            concurrency: ConcurrencyMode::Locked,
+            tombstones: false,
+        }
+    }
+
+    pub fn with_ivf(mut self, n_clusters: usize) -> Self {
+        self.use_ivf = true;
+        self.n_clusters = n_clusters;
+        self
+    }
+
+    pub fn with_embedding_model(mut self, model: String) -> Self {
+        self.embedding_model = Some(model);
+        self
+    }
+
+    pub fn with_metadata_limits(mut self, limits: MetadataLimits) -> Self {
+        self.metadata_limits = limits;
+        self
+    }
+
+    pub fn with_content_hash(mut self) -> Self {
+        self.content_hash = true;
+        self
+    }
+
+    pub fn with_drift_threshold(mut self, threshold: f32) -> Self {
+        self.drift_threshold = Some(threshold);
+        self
+    }
+
+    pub fn with_rebuild_policy(mut self, policy: RebuildPolicy) -> Self {
+        self.rebuild_policy = policy;
+        self
+    }
+
+    pub fn with_index_params(mut self, params: IndexParams) -> Self {
+        self.index_params = params;
+        self
+    }
+
+    pub fn with_late_interaction(mut self) -> Self {
+        self.late_interaction = true;
+        self
+    }
+
+    pub fn with_pq(mut self, subspaces: usize) -> Self {
+        self.pq_subspaces = Some(subspaces);
+        self
+    }
+
+    pub fn with_scalar_quantization(mut self, rescore: Option<usize>) -> Self {
+        self.scalar_quantization = Some(ScalarQuantization { rescore });
+        self
+    }
+
+    pub fn with_binary(mut self) -> Self {
+        self.vector_type = VectorType::Binary;
+        self
+    }
+
+    pub fn is_binary(&self) -> bool {
+        self.vector_type == VectorType::Binary
+    }
+
+    pub fn with_store_raw(mut self) -> Self {
+        self.store_raw = true;
+        self
+    }
+
+    pub fn with_precision(mut self, precision: Precision) -> Self {
+        self.precision = precision;
+        self
+    }
+
+    pub fn with_concurrency(mut self, concurrency: ConcurrencyMode) -> Self {
+        self.concurrency = concurrency;
+        self
+    }
+
+    pub fn with_tombstones(mut self) -> Self {
+        self.tombstones = true;
+        self
+    }
+
+    pub fn check_embedding_model(&self, model: Option<&str>) -> Result<()> {
+        match (&self.embedding_model, model) {
+            (Some(expected), Some(actual)) if expected != actual => {
+                Err(VectorDbError::EmbeddingModelMismatch {
+                    expected: expected.clone(),
+                    actual: actual.to_string(),
+                })
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+// paramètres de recherche IVF modifiables sans rebuild
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct IndexParams {
+    // clusters sondés par requête ; None : valeur de l'index (4 par défaut)
+    pub n_probe: Option<usize>,
+    // sondage élargi aux clusters suivants, du plus proche au plus lointain, tant que
+    // moins de n_results × refine_factor candidats passent le filtre ; None : désactivé
+    pub refine_factor: Option<usize>,
+    // quantificateur à deux niveaux : centroïdes regroupés en coarse_groups groupes
+    // (≈ √n_clusters), pour les très grandes collections ; None : centroïdes parcourus
+    // en entier
+    pub coarse_groups: Option<usize>,
+    // IVF-PQ : les n_results × pq_rerank meilleurs candidats par distance approchée
+    // sont reclassés en distance exacte ; None : distances approchées renvoyées telles quelles
+    pub pq_rerank: Option<usize>,
+}
+
+impl IndexParams {
+    pub fn validate(&self) -> Result<()> {
+        if self.n_probe == Some(0)
+            || self.refine_factor == Some(0)
+            || self.coarse_groups == Some(0)
+            || self.pq_rerank == Some(0)
+        {
+            return Err(VectorDbError::InvalidConfig(
+                "n_probe, refine_factor, coarse_groups and pq_rerank must be > 0".to_string(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+// PATCH /collections/{name}/index : champs absents inchangés, null remet la
+// valeur automatique
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct IndexParamsUpdate {
+    #[serde(default, deserialize_with = "present_or_null")]
+    pub n_probe: Option<Option<usize>>,
+    #[serde(default, deserialize_with = "present_or_null")]
+    pub refine_factor: Option<Option<usize>>,
+    #[serde(default, deserialize_with = "present_or_null")]
+    pub coarse_groups: Option<Option<usize>>,
+    #[serde(default, deserialize_with = "present_or_null")]
+    pub pq_rerank: Option<Option<usize>>,
+    pub rebuild_policy: Option<RebuildPolicyUpdate>,
+}
+
+// champ présent, même à null : Some(valeur) ; absent : None via #[serde(default)]
+fn present_or_null<'de, D, T>(deserializer: D) -> std::result::Result<Option<Option<T>>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    Option::<T>::deserialize(deserializer).map(Some)
+}
+
+impl IndexParamsUpdate {
+    pub fn apply(&self, params: &IndexParams) -> IndexParams {
+        IndexParams {
+            n_probe: self.n_probe.unwrap_or(params.n_probe),
+            refine_factor: self.refine_factor.unwrap_or(params.refine_factor),
+            coarse_groups: self.coarse_groups.unwrap_or(params.coarse_groups),
+            pq_rerank: self.pq_rerank.unwrap_or(params.pq_rerank),
+        }
+    }
+}
+
+// quand reconstruire automatiquement l'index IVF après des modifications
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RebuildPolicy {
+    // false : seul un rebuild explicite (POST /rebuild, rebuild_index) reconstruit l'index
+    pub auto: bool,
+    // part des vecteurs modifiés, en pourcentage, à partir de laquelle le rebuild est dû
+    pub threshold_percent: f64,
+    // plancher du seuil, pour éviter des rebuilds à répétition sur une petite collection
+    pub min_modifications: usize,
+}
+
+impl Default for RebuildPolicy {
+    fn default() -> Self {
+        Self {
+            auto: true,
+            threshold_percent: 10.0,
+            min_modifications: 10,
+        }
+    }
+}
+
+impl RebuildPolicy {
+    pub fn validate(&self) -> Result<()> {
+        if !(self.threshold_percent > 0.0 && self.threshold_percent <= 100.0) {
+            return Err(VectorDbError::InvalidConfig(format!(
+                "rebuild threshold_percent must be in (0, 100], got {}",
+                self.threshold_percent
+            )));
+        }
+        Ok(())
+    }
+
+    // nombre de modifications déclenchant le rebuild pour `total` vecteurs
+    pub fn threshold(&self, total: usize) -> usize {
+        ((total as f64 * self.threshold_percent / 100.0) as usize).max(self.min_modifications)
+    }
+}
+
+// modification partielle d'une RebuildPolicy (PATCH), champs absents inchangés
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct RebuildPolicyUpdate {
+    pub auto: Option<bool>,
+    pub threshold_percent: Option<f64>,
+    pub min_modifications: Option<usize>,
+}
+
+impl RebuildPolicyUpdate {
+    pub fn apply(&self, policy: &RebuildPolicy) -> RebuildPolicy {
+        RebuildPolicy {
+            auto: self.auto.unwrap_or(policy.auto),
+            threshold_percent: self.threshold_percent.unwrap_or(policy.threshold_percent),
+            min_modifications: self.min_modifications.unwrap_or(policy.min_modifications),
+        }
+    }
+}
+
+// comportement de add pour un id déjà présent
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OnConflict {
+    Skip,
+    #[default]
+    Overwrite,
+    // embedding remplacé, métadonnées existantes complétées ou remplacées clé par clé
+    Merge,
+    Error,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct AddOutcome {
+    pub added: usize,
+    pub overwritten: usize,
+    pub skipped: usize,
+    // ids supprimés après l'écriture d'origine (AddOptions::written_at), non ajoutés
+    pub tombstoned: usize,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct UpsertOutcome {
+    pub inserted: usize,
+    pub updated: usize,
+}
+
+// résultat de Collection::range_search
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct RangeResults {
+    pub results: Vec<SearchResult>,
+    // d'autres vecteurs étaient dans le rayon au-delà de max_results
+    pub truncated: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct AddOptions {
+    // valeur de _system.source
+    pub source: String,
+    pub on_conflict: OnConflict,
+    // empreintes fournies par le client (hash du document source par exemple), une par id
+    pub content_hashes: Option<Vec<String>>,
+    // matrices de tokens d'une collection late_interaction, une par id
+    pub token_embeddings: Option<Vec<TokenMatrix>>,
+    // date d'écriture d'origine d'un ajout répliqué (epoch en microsecondes) : les
+    // ids supprimés depuis sont écartés au lieu d'être ressuscités
+    pub written_at: Option<i64>,
+}
+
+impl AddOptions {
+    pub fn new(source: &str) -> Self {
+        Self {
+            source: source.to_string(),
+            on_conflict: OnConflict::default(),
+            content_hashes: None,
+            token_embeddings: None,
+            written_at: None,
+        }
+    }
+
+    pub fn with_on_conflict(mut self, on_conflict: OnConflict) -> Self {
+        self.on_conflict = on_conflict;
+        self
+    }
+
+    pub fn with_content_hashes(mut self, hashes: Vec<String>) -> Self {
+        self.content_hashes = Some(hashes);
+        self
+    }
+
+    pub fn with_token_embeddings(mut self, tokens: Vec<TokenMatrix>) -> Self {
+        self.token_embeddings = Some(tokens);
+        self
+    }
+
+    pub fn with_written_at(mut self, written_at: i64) -> Self {
+        self.written_at = Some(written_at);
+        self
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SortDirection {
+    #[default]
+    Asc,
+    Desc,
+}
+
+// clé de métadonnée départageant les résultats de même distance
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SortKey {
+    pub key: String,
+    #[serde(default)]
+    pub direction: SortDirection,
+}
+
+impl SortKey {
+    pub fn new(key: &str, direction: SortDirection) -> Self {
+        Self { key: key.to_string(), direction }
+    }
+
+    // valeurs absentes en dernier quelle que soit la direction ; types non
+    // comparables entre eux considérés égaux
+    fn compare(&self, a: &HashMap<String, MetadataValue>, b: &HashMap<String, MetadataValue>) -> Ordering {
+        match (a.get(&self.key), b.get(&self.key)) {
+            (Some(x), Some(y)) => {
+                let ord = compare_values(x, y).unwrap_or(Ordering::Equal);
+                match self.direction {
+                    SortDirection::Asc => ord,
+                    SortDirection::Desc => ord.reverse(),
+                }
+            }
+            (Some(_), None) => Ordering::Less,
+            (None, Some(_)) => Ordering::Greater,
+            (None, None) => Ordering::Equal,
+        }
+    }
+}
+
+// dénombrement des vecteurs d'un filtre rendu avec les résultats : balayage complet
+// des colonnes, ou extrapolation depuis un échantillon pour les grandes collections
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CountMode {
+    Exact,
+    Estimate,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct MatchCount {
+    pub total_matched: usize,
+    // faux si extrapolé d'un échantillon
+    pub exact: bool,
+}
+
+// options de recherche de query_with_options
+#[derive(Debug, Clone, Default)]
+pub struct QueryOptions {
+    // recherche exhaustive même si l'index IVF est construit (vérité terrain pour
+    // mesurer le rappel, petits ensembles filtrés)
+    pub exact: bool,
+    // renseigne SearchResult::explain pour chaque résultat
+    pub explain_hits: bool,
+    // ordre de la distance : Asc = plus proches d'abord ; Desc = plus lointains
+    // d'abord, toujours en recherche exhaustive (l'IVF ne sonde que les clusters proches)
+    pub direction: SortDirection,
+    // départage des distances égales, dans l'ordre
+    pub sort_by: Vec<SortKey>,
+    // résultats plus lointains écartés avant de garder les n_results : une requête
+    // sans voisin assez proche renvoie une liste vide. Bits différents pour les
+    // vecteurs binaires
+    pub max_distance: Option<f32>,
+}
+
+impl QueryOptions {
+    pub fn exact(mut self) -> Self {
+        self.exact = true;
+        self
+    }
+
+    pub fn explain_hits(mut self) -> Self {
+        self.explain_hits = true;
+        self
+    }
+
+    pub fn direction(mut self, direction: SortDirection) -> Self {
+        self.direction = direction;
+        self
+    }
+
+    pub fn then_by(mut self, key: &str, direction: SortDirection) -> Self {
+        self.sort_by.push(SortKey::new(key, direction));
+        self
+    }
+
+    pub fn max_distance(mut self, max_distance: f32) -> Self {
+        self.max_distance = Some(max_distance);
+        self
+    }
+
+    // similarité cosinus minimale, soit une distance d'au plus 1 - threshold
+    pub fn score_threshold(self, threshold: f32) -> Self {
+        self.max_distance(1.0 - threshold)
+    }
+
+    fn within(&self, distance: f32) -> bool {
+        self.max_distance.is_none_or(|max| distance <= max)
+    }
+
+    // ordre des résultats : distance selon la direction, puis clés de départage
+    fn compare(&self, a: (f32, &HashMap<String, MetadataValue>), b: (f32, &HashMap<String, MetadataValue>)) -> Ordering {
+        let primary = a.0.partial_cmp(&b.0).unwrap_or(Ordering::Equal);
+        let primary = match self.direction {
+            SortDirection::Asc => primary,
+            SortDirection::Desc => primary.reverse(),
+        };
+        self.sort_by
+            .iter()
+            .fold(primary, |ord, key| ord.then_with(|| key.compare(a.1, b.1)))
+    }
+}
+
+// résultat de Collection::diff, ids triés
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct DiffResult {
+    pub new: Vec<String>,
+    pub changed: Vec<String>,
+    pub unchanged: Vec<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -29,451 +558,4164 @@ pub struct Collection {
     last_query_time_ms: f64,
     #[serde(skip)]
     total_queries: usize,
+    #[serde(skip)]
+    embeddings_dirty: bool,
+    #[serde(skip)]
+    deferred_rebuild: bool,
+    #[serde(skip)]
+    pending_rebuild: Option<PendingRebuild>,
+    #[serde(skip)]
+    read_only: bool,
+    // types observés par clé de filtre, vidé à chaque écriture
+    #[serde(skip)]
+    key_types: Mutex<ObservedTypes>,
+    // statistiques des lots ajoutés depuis le chargement
+    #[serde(skip)]
+    drift: DriftMonitor,
+    // latences depuis le dernier instantané de VectorDbClient::snapshot_stats
+    #[serde(skip)]
+    latencies: Mutex<LatencyWindow>,
+    // limite dure et seuil d'avertissement du nombre de vecteurs, 0 = illimité
+    #[serde(skip)]
+    max_vectors: usize,
+    #[serde(skip)]
+    soft_max_vectors: usize,
+    // listes IVF sur disque (offload_to_disk) : les vecteurs qui y sont rangés ont
+    // un embedding vide en mémoire, relu depuis le fichier à la demande
+    #[serde(skip)]
+    disk: Option<DiskLists>,
+    // budget du cache des listes sur disque les plus sondées, 0 = sans cache
+    #[serde(skip)]
+    list_cache_bytes: usize,
+    // rappels du client (VectorDbClient::add_hooks)
+    #[serde(skip)]
+    hooks: HookSet,
+    // matrices de tokens normalisées par id (config.late_interaction)
+    #[serde(skip)]
+    tokens: HashMap<String, TokenMatrix>,
+    // embeddings quantifiés par id (config.scalar_quantization) ; l'embedding f32 de
+    // l'entrée est vide sauf pour reclasser (rescore)
+    #[serde(skip)]
+    quantized: HashMap<String, Int8Vector>,
+    // vecteurs des collections binaires par id, embedding f32 de l'entrée vide
+    #[serde(skip)]
+    binary: HashMap<String, BinaryVector>,
+    // embeddings en demi-précision par id (config.precision), embedding f32 vide
+    #[serde(skip)]
+    half: HashMap<String, HalfVector>,
+    // collection store_raw : vecteurs tels qu'ajoutés, persistés dans raw.bin
+    #[serde(skip)]
+    raw: HashMap<String, Vec<f32>>,
+    // ids supprimés, persistés dans tombstones.bin (voir tombstones)
+    #[serde(skip)]
+    tombstones: TombstoneSet,
+    #[serde(skip)]
+    tombstone_policy: TombstonePolicy,
+    // chaînes de métadonnées partagées entre les vecteurs (voir dictionary)
+    #[serde(skip)]
+    dictionary: MetadataDictionary,
+    // métadonnées en colonnes pour les filtres des parcours complets (voir columns)
+    #[serde(skip)]
+    columns: MetadataColumns,
+    // segments immuables (voir segment), du plus ancien au plus récent : leurs
+    // entrées ont un embedding vide en mémoire, lu dans le fichier projeté
+    #[serde(skip)]
+    segments: Vec<LiveSegment>,
+    // ids écrits depuis le dernier vidage d'une collection segmentée
+    #[serde(skip)]
+    memtable: HashSet<String>,
+    #[serde(skip)]
+    next_segment_seq: u64,
+    // numéro réservé par plan_merge jusqu'à finish_merge ou abort_merge
+    #[serde(skip)]
+    pending_merge: Option<u64>,
+    // un seul segment, écritures refusées jusqu'à thaw
+    #[serde(skip)]
+    frozen: bool,
+    // config.concurrency = snapshot : ids écrits depuis la dernière publication de
+    // l'instantané de lecture (voir readview) ; None tant qu'aucun n'est publié
+    #[serde(skip)]
+    view_changes: Option<HashSet<String>>,
+    // version des données, avancée à chaque écriture de vecteurs et gardée dans
+    // l'en-tête : un curseur reste valable après un rechargement (voir cursor)
+    #[serde(skip)]
+    data_version: u64,
+}
+
+// modifications reçues pendant un rebuild en arrière-plan
+#[derive(Debug)]
+struct PendingRebuild {
+    added: HashSet<String>,
+    removed: HashSet<String>,
+    started: Instant,
+}
+
+// données capturées pour entraîner un nouvel index hors verrou
+pub struct RebuildJob {
+    data: Vec<(String, Vec<f32>)>,
+    n_clusters: usize,
+    n_probe: Option<usize>,
+    coarse_groups: Option<usize>,
+    pq_subspaces: Option<usize>,
+}
+
+impl RebuildJob {
+    pub fn run(self) -> IVFIndex {
+        let mut index =
+            IVFIndex::new(self.n_clusters).with_coarse_groups(self.coarse_groups).with_pq(self.pq_subspaces);
+        if let Some(n_probe) = self.n_probe {
+            index = index.with_n_probe(n_probe);
+        }
+        index.build(&self.data);
+        index
+    }
 }
 
 impl Collection {
     pub fn new(name: String, dimension: usize) -> Self {
-        Self {
-            config: CollectionConfig {
-                name,
-                dimension,
-                use_ivf: false,
-                n_clusters: 0,
-            },
-            vectors: HashMap::new(),
-            ivf_index: None,
-            needs_rebuild: false,
-            batch_mode: false,
-            modifications_count: 0,
-            last_query_time_ms: 0.0,
-            total_queries: 0,
-        }
+        Self::from_config(CollectionConfig::new(name, dimension))
     }
 
     pub fn new_with_ivf(name: String, dimension: usize, n_clusters: usize) -> Self {
+        Self::from_config(CollectionConfig::new(name, dimension).with_ivf(n_clusters))
+    }
+
+    pub fn from_config(config: CollectionConfig) -> Self {
+        let ivf_index = if config.use_ivf {
+            Some(
+                IVFIndex::new(config.n_clusters)
+                    .with_coarse_groups(config.index_params.coarse_groups)
+                    .with_pq(config.pq_subspaces),
+            )
+        } else {
+            None
+        };
+
         Self {
-            config: CollectionConfig {
-                name,
-                dimension,
-                use_ivf: true,
-                n_clusters,
-            },
+            needs_rebuild: config.use_ivf,
+            config,
             vectors: HashMap::new(),
-            ivf_index: Some(IVFIndex::new(n_clusters)),
-            needs_rebuild: true,
+            ivf_index,
             batch_mode: false,
             modifications_count: 0,
             last_query_time_ms: 0.0,
             total_queries: 0,
+            embeddings_dirty: true,
+            deferred_rebuild: false,
+            pending_rebuild: None,
+            read_only: false,
+            key_types: Mutex::default(),
+            drift: DriftMonitor::default(),
+            latencies: Mutex::default(),
+            max_vectors: 0,
+            soft_max_vectors: 0,
+            disk: None,
+            list_cache_bytes: 0,
+            hooks: HookSet::default(),
+            tokens: HashMap::new(),
+            quantized: HashMap::new(),
+            binary: HashMap::new(),
+            half: HashMap::new(),
+            raw: HashMap::new(),
+            tombstones: TombstoneSet::default(),
+            tombstone_policy: TombstonePolicy::default(),
+            dictionary: MetadataDictionary::default(),
+            columns: MetadataColumns::default(),
+            segments: Vec::new(),
+            memtable: HashSet::new(),
+            next_segment_seq: 1,
+            pending_merge: None,
+            frozen: false,
+            view_changes: None,
+            data_version: 0,
         }
     }
 
-    pub fn begin_batch(&mut self) {
-        self.batch_mode = true;
+    // reconstruire une collection depuis les fichiers séparés du stockage ; avec des
+    // listes sur disque, les vecteurs absents de `embeddings` y sont lus et l'index
+    // IVF est repris du fichier, sans k-means. Collection quantifiée : les vecteurs
+    // absents de `encoded.quantized` (import, migration) sont quantifiés ici ; de même
+    // pour `binary`, empaquetés depuis leurs composantes 0/1, et `half`
+    pub(crate) fn from_parts(
+        config: CollectionConfig,
+        modifications_count: usize,
+        data_version: u64,
+        metadatas: Vec<(String, HashMap<String, MetadataValue>)>,
+        embeddings: Vec<(String, Vec<f32>)>,
+        encoded: EncodedVectors,
+        disk: Option<DiskLists>,
+    ) -> Result<Self> {
+        let mut coll = Self::from_config(config);
+        coll.modifications_count = modifications_count;
+        coll.data_version = data_version;
+
+        let mut embeddings: HashMap<String, Vec<f32>> = embeddings.into_iter().collect();
+        let mut quantized: HashMap<String, Int8Vector> = encoded.quantized.into_iter().collect();
+        let mut binary: HashMap<String, BinaryVector> = encoded.binary.into_iter().collect();
+        let mut half: HashMap<String, HalfVector> = encoded.half.into_iter().collect();
+        let precision = coll.config.precision;
+        let sq = coll.config.scalar_quantization;
+        let is_binary = coll.config.is_binary();
+        coll.vectors.reserve(metadatas.len());
+        for (id, mut metadata) in metadatas {
+            coll.dictionary.intern(&mut metadata);
+            coll.columns.upsert(&id, &metadata);
+            if is_binary {
+                let vector = match (binary.remove(&id), embeddings.remove(&id)) {
+                    (Some(vector), _) => Some(vector),
+                    (None, Some(embedding)) => BinaryVector::from_bits(&embedding),
+                    (None, None) => None,
+                };
+                let Some(vector) = vector else {
+                    return Err(VectorDbError::Serialization(format!("missing binary vector for '{}'", id)));
+                };
+                coll.binary.insert(id.clone(), vector);
+                coll.vectors.insert(id.clone(), VectorEntry { id, embedding: Vec::new(), metadata });
+                continue;
+            }
+            if precision.is_half() {
+                let vector = match (half.remove(&id), embeddings.remove(&id)) {
+                    (Some(vector), _) => vector,
+                    (None, Some(embedding)) => HalfVector::encode(&embedding, precision),
+                    (None, None) => {
+                        return Err(VectorDbError::Serialization(format!("missing embedding for vector '{}'", id)))
+                    }
+                };
+                coll.half.insert(id.clone(), vector);
+                coll.vectors.insert(id.clone(), VectorEntry { id, embedding: Vec::new(), metadata });
+                continue;
+            }
+            let mut embedding = match embeddings.remove(&id) {
+                Some(embedding) => embedding,
+                None if disk.as_ref().is_some_and(|d| d.contains(&id)) => Vec::new(),
+                None if quantized.contains_key(&id) && sq.is_some() => Vec::new(),
+                None => {
+                    return Err(VectorDbError::Serialization(format!("missing embedding for vector '{}'", id)))
+                }
+            };
+            if let Some(sq) = sq {
+                let vector = match quantized.remove(&id) {
+                    Some(vector) => vector,
+                    None => Int8Vector::quantize(&embedding),
+                };
+                coll.quantized.insert(id.clone(), vector);
+                if sq.rescore.is_none() {
+                    embedding = Vec::new();
+                }
+            }
+            coll.vectors.insert(id.clone(), VectorEntry { id, embedding, metadata });
+        }
+
+        if let Some(disk) = disk {
+            coll.attach_disk_lists(disk);
+        }
+        coll.embeddings_dirty = false;
+        Ok(coll)
     }
 
-    pub fn end_batch(&mut self) {
-        self.batch_mode = false;
-        if self.config.use_ivf && self.modifications_count > 0 {
+    // index IVF repris des listes sur disque : ids encore présents et non réécrits
+    // depuis l'offload, puis vecteurs en mémoire placés dans le cluster le plus proche
+    fn attach_disk_lists(&mut self, disk: DiskLists) {
+        let lists = (0..disk.n_lists())
+            .map(|cluster| {
+                disk.ids(cluster)
+                    .iter()
+                    .filter(|id| self.vectors.get(*id).is_some_and(|e| e.embedding.is_empty()))
+                    .cloned()
+                    .collect()
+            })
+            .collect();
+        let mut ivf = IVFIndex::new(self.config.n_clusters).with_pq(self.config.pq_subspaces);
+        if let Some(n_probe) = self.ivf_index.as_ref().map(|ivf| ivf.n_probe) {
+            ivf = ivf.with_n_probe(n_probe);
+        }
+        ivf.centroids = disk.centroids().to_vec();
+        ivf.inverted_lists = lists;
+        // codes PQ repris du fichier pour les mêmes entrées ; sans quantificateur dans
+        // le fichier (offload antérieur à IVF-PQ), distances exactes jusqu'au prochain offload
+        if let Some(pq) = disk.pq().filter(|_| self.config.pq_subspaces.is_some()) {
+            let m = pq.code_len();
+            ivf.pq_codes = (0..disk.n_lists())
+                .map(|cluster| {
+                    disk.ids(cluster)
+                        .iter()
+                        .zip(disk.codes(cluster).chunks_exact(m))
+                        .filter(|(id, _)| self.vectors.get(*id).is_some_and(|e| e.embedding.is_empty()))
+                        .flat_map(|(_, code)| code.iter().copied())
+                        .collect()
+                })
+                .collect();
+            ivf.pq = Some(pq.clone());
+        }
+        ivf.set_coarse_groups(self.config.index_params.coarse_groups);
+        for entry in self.vectors.values().filter(|e| !e.embedding.is_empty()) {
+            ivf.insert(entry.id.clone(), &entry.embedding);
+        }
+        self.ivf_index = Some(ivf);
+        self.needs_rebuild = false;
+        disk.set_cache_capacity(self.list_cache_bytes);
+        self.disk = Some(disk);
+    }
+
+    // segments relus du stockage, après la memtable (from_parts) : les lignes
+    // supprimées ou réécrites depuis dans la memtable ne sont pas chargées
+    pub(crate) fn attach_segments(&mut self, segments: Vec<(u64, Segment, HashSet<String>)>, frozen: bool) -> Result<()> {
+        for (seq, mut segment, mut deleted) in segments {
+            if segment.dimension() != self.config.dimension {
+                return Err(VectorDbError::CorruptData {
+                    path: segment.path().display().to_string(),
+                    reason: format!("dimension {} in a collection of dimension {}", segment.dimension(), self.config.dimension),
+                });
+            }
+            let mut tokens: HashMap<String, TokenMatrix> = segment.take_tokens().into_iter().collect();
+            for (id, mut metadata) in segment.take_metadata() {
+                if deleted.contains(&id) {
+                    continue;
+                }
+                if self.vectors.contains_key(&id) {
+                    deleted.insert(id);
+                    continue;
+                }
+                self.dictionary.intern(&mut metadata);
+                self.columns.upsert(&id, &metadata);
+                if let Some(matrix) = tokens.remove(&id) {
+                    self.tokens.insert(id.clone(), matrix);
+                }
+                self.vectors.insert(id.clone(), VectorEntry { id, embedding: Vec::new(), metadata });
+            }
+            self.next_segment_seq = self.next_segment_seq.max(seq + 1);
+            self.segments.push(LiveSegment { seq, segment: Arc::new(segment), deleted });
+        }
+        if self.is_segmented() {
+            self.memtable = self.vectors.values().filter(|e| !e.embedding.is_empty()).map(|e| e.id.clone()).collect();
+            self.frozen = frozen;
+            self.enter_segmented_mode();
+            self.columns.compact();
+        }
+        Ok(())
+    }
+
+    // chaque segment porte son propre index : pas d'index global à reconstruire
+    fn enter_segmented_mode(&mut self) {
+        self.ivf_index = None;
+        self.needs_rebuild = false;
+        self.modifications_count = 0;
+    }
+
+    // les collections chargées depuis l'ancien format n'ont pas d'index en mémoire
+    pub(crate) fn restore_after_load(&mut self) {
+        if self.config.use_ivf {
+            if self.ivf_index.is_none() {
+                self.ivf_index = Some(
+                    IVFIndex::new(self.config.n_clusters)
+                        .with_coarse_groups(self.config.index_params.coarse_groups)
+                        .with_pq(self.config.pq_subspaces),
+                );
+            }
             self.needs_rebuild = true;
         }
+        for entry in self.vectors.values_mut() {
+            self.dictionary.intern(&mut entry.metadata);
+            self.columns.upsert(&entry.id, &entry.metadata);
+        }
+        self.embeddings_dirty = true;
     }
 
-    pub fn add(
-        &mut self,
-        ids: Vec<String>,
-        embeddings: Vec<Vec<f32>>,
-        metadatas: Option<Vec<HashMap<String, MetadataValue>>>,
-    ) -> Result<()> {
-        let n = ids.len();
-        if n != embeddings.len() {
-            return Err(VectorDbError::InvalidConfig(
-                "ids and embeddings must have the same length".to_string(),
-            ));
+    pub(crate) fn entries(&self) -> impl Iterator<Item = &VectorEntry> {
+        self.vectors.values()
+    }
+
+    pub(crate) fn entry(&self, id: &str) -> Option<&VectorEntry> {
+        self.vectors.get(id)
+    }
+
+    // instantané de lecture complet (embeddings normalisés décodés) ; les écritures
+    // suivantes sont suivies pour take_view_changes
+    pub(crate) fn read_view(&mut self) -> Result<ReadView> {
+        let entries = self
+            .vectors
+            .values()
+            .map(|entry| self.view_entry(entry))
+            .collect::<Result<Vec<_>>>()?;
+        self.view_changes = Some(HashSet::new());
+        Ok(ReadView::new(&self.config, entries))
+    }
+
+    // ids écrits depuis le dernier appel, dans leur état courant
+    pub(crate) fn take_view_changes(&mut self) -> Result<Vec<ViewChange>> {
+        let Some(changed) = self.view_changes.as_mut().map(std::mem::take) else {
+            return Ok(Vec::new());
+        };
+        changed
+            .into_iter()
+            .map(|id| {
+                let entry = self.vectors.get(&id).map(|entry| self.view_entry(entry)).transpose()?;
+                Ok((id, entry))
+            })
+            .collect()
+    }
+
+    fn view_entry(&self, entry: &VectorEntry) -> Result<VectorEntry> {
+        Ok(VectorEntry::new(entry.id.clone(), self.embedding_of(entry)?.into_owned(), entry.metadata.clone()))
+    }
+
+    // vecteurs ajoutés, modifiés ou supprimés : nouvelle version des données
+    fn record_writes<'a>(&mut self, ids: impl IntoIterator<Item = &'a String>) {
+        let mut ids = ids.into_iter().peekable();
+        if ids.peek().is_none() {
+            return;
+        }
+        self.data_version += 1;
+        if let Some(ref mut changed) = self.view_changes {
+            changed.extend(ids.cloned());
         }
+    }
+
+    // change à chaque écriture de vecteurs ; les curseurs de pagination la portent
+    pub fn data_version(&self) -> u64 {
+        self.data_version
+    }
+
+    // embedding normalisé d'une entrée, lu dans son segment ou relu dans les listes
+    // sur disque s'il n'est plus en mémoire
+    pub(crate) fn embedding_of<'a>(&'a self, entry: &'a VectorEntry) -> Result<Cow<'a, [f32]>> {
+        if let Some(vector) = self.binary.get(&entry.id) {
+            return Ok(Cow::Owned(vector.to_floats(self.config.dimension)));
+        }
+        if let Some(vector) = self.half.get(&entry.id) {
+            return Ok(Cow::Owned(vector.decode()));
+        }
+        if let Some(vector) = self.quantized.get(&entry.id).filter(|_| entry.embedding.is_empty()) {
+            return Ok(Cow::Owned(vector.dequantize()));
+        }
+        if self.is_segmented() && entry.embedding.is_empty() {
+            return match self.segment_row(&entry.id) {
+                Some((segment, row)) => Ok(Cow::Borrowed(segment.vector(row))),
+                None => Err(VectorDbError::CorruptData {
+                    path: self.config.name.clone(),
+                    reason: format!("vector '{}' is in no segment", entry.id),
+                }),
+            };
+        }
+        match self.disk {
+            Some(ref disk) if entry.embedding.is_empty() => {
+                disk.read_vector(&entry.id)?.map(Cow::Owned).ok_or_else(|| VectorDbError::CorruptData {
+                    path: disk.path().display().to_string(),
+                    reason: format!("missing vector '{}'", entry.id),
+                })
+            }
+            _ => Ok(Cow::Borrowed(&entry.embedding)),
+        }
+    }
+
+    pub(crate) fn quantized_vectors(&self) -> &HashMap<String, Int8Vector> {
+        &self.quantized
+    }
+
+    pub(crate) fn binary_vectors(&self) -> &HashMap<String, BinaryVector> {
+        &self.binary
+    }
+
+    pub(crate) fn half_vectors(&self) -> &HashMap<String, HalfVector> {
+        &self.half
+    }
+
+    pub(crate) fn raw_embeddings(&self) -> &HashMap<String, Vec<f32>> {
+        &self.raw
+    }
+
+    // vecteurs relus du stockage, ids inconnus ignorés
+    pub(crate) fn set_raw_embeddings(&mut self, raw: Vec<(String, Vec<f32>)>) {
+        self.raw = raw.into_iter().filter(|(id, _)| self.vectors.contains_key(id)).collect();
+    }
+
+    // embedding rendu à l'utilisateur (get, include_embeddings) : le vecteur ajouté
+    // pour une collection store_raw, sinon sa forme normalisée
+    fn stored_embedding_of(&self, entry: &VectorEntry) -> Result<Vec<f32>> {
+        match self.raw.get(&entry.id) {
+            Some(raw) => Ok(raw.clone()),
+            None => self.embedding_of(entry).map(Cow::into_owned),
+        }
+    }
+
+    // vrai si les listes IVF sont sur disque (offload_to_disk)
+    pub fn is_offloaded(&self) -> bool {
+        self.disk.is_some()
+    }
+
+    pub(crate) fn modifications_count(&self) -> usize {
+        self.modifications_count
+    }
+
+    pub(crate) fn ivf_index(&self) -> Option<&IVFIndex> {
+        self.ivf_index.as_ref().filter(|ivf| ivf.is_built())
+    }
+
+    // index entraîné ailleurs (import faiss) : remplace l'index courant sans k-means
+    pub(crate) fn install_ivf_index(&mut self, mut index: IVFIndex) {
+        index.set_coarse_groups(self.config.index_params.coarse_groups);
+        self.ivf_index = Some(index);
+        self.modifications_count = 0;
+        self.needs_rebuild = false;
+    }
+
+    // vrai si les embeddings ont changé depuis la dernière sauvegarde
+    pub fn embeddings_dirty(&self) -> bool {
+        self.embeddings_dirty
+    }
+
+    pub fn mark_persisted(&mut self) {
+        self.embeddings_dirty = false;
+    }
+
+    // métadonnées utilisateur des ids présents, complétées par celles reçues ; les clés
+    // _system sont recalculées par add
+    fn merge_metadatas(
+        &self,
+        ids: &[String],
+        metadatas: Option<Vec<HashMap<String, MetadataValue>>>,
+    ) -> Vec<HashMap<String, MetadataValue>> {
+        let mut metadatas = metadatas.unwrap_or_else(|| vec![HashMap::new(); ids.len()]);
+        for (id, metadata) in ids.iter().zip(metadatas.iter_mut()) {
+            if let Some(existing) = self.vectors.get(id) {
+                for (key, value) in existing.metadata.iter().filter(|(k, _)| !is_reserved_key(k)) {
+                    metadata.entry(key.clone()).or_insert_with(|| value.clone());
+                }
+            }
+        }
+        metadatas
+    }
+
+    // refuser un embedding issu d'un autre modèle, même de dimension identique
+    pub fn check_embedding_model(&self, model: Option<&str>) -> Result<()> {
+        self.config.check_embedding_model(model)
+    }
+
+    pub fn begin_batch(&mut self) {
+        self.batch_mode = true;
+    }
+
+    pub fn end_batch(&mut self) {
+        self.batch_mode = false;
+        if self.config.use_ivf && self.modifications_count > 0 {
+            self.needs_rebuild = true;
+        }
+    }
+
+    pub fn add(
+        &mut self,
+        ids: Vec<String>,
+        embeddings: Vec<Vec<f32>>,
+        metadatas: Option<Vec<HashMap<String, MetadataValue>>>,
+    ) -> Result<()> {
+        self.add_with_source(ids, embeddings, metadatas, "library")
+    }
+
+    // comme add, en renseignant _system.source avec l'origine de l'ingestion
+    pub fn add_with_source(
+        &mut self,
+        ids: Vec<String>,
+        embeddings: Vec<Vec<f32>>,
+        metadatas: Option<Vec<HashMap<String, MetadataValue>>>,
+        source: &str,
+    ) -> Result<()> {
+        self.add_with_options(ids, embeddings, metadatas, AddOptions::new(source))
+            .map(|_| ())
+    }
+
+    // ids nouveaux ajoutés ; ids présents : embedding remplacé et métadonnées fusionnées
+    // (OnConflict::Merge), les clés absentes de `metadatas` sont gardées
+    pub fn upsert(
+        &mut self,
+        ids: Vec<String>,
+        embeddings: Vec<Vec<f32>>,
+        metadatas: Option<Vec<HashMap<String, MetadataValue>>>,
+    ) -> Result<UpsertOutcome> {
+        let options = AddOptions::new("library").with_on_conflict(OnConflict::Merge);
+        let outcome = self.add_with_options(ids, embeddings, metadatas, options)?;
+        Ok(UpsertOutcome { inserted: outcome.added, updated: outcome.overwritten })
+    }
+
+    // ajout avec politique pour les ids déjà présents ; les ids ignorés ne modifient
+    // ni les données ni l'index
+    pub fn add_with_options(
+        &mut self,
+        ids: Vec<String>,
+        embeddings: Vec<Vec<f32>>,
+        metadatas: Option<Vec<HashMap<String, MetadataValue>>>,
+        mut options: AddOptions,
+    ) -> Result<AddOutcome> {
+        let start = Instant::now();
+        self.ensure_writable()?;
+        let n = ids.len();
+
+        // une matrice de tokens par id, dont la moyenne sert d'embedding quand
+        // `embeddings` est vide
+        let mut tokens = match options.token_embeddings.take() {
+            Some(tokens) if self.config.late_interaction => {
+                if tokens.len() != n {
+                    return Err(VectorDbError::InvalidConfig(
+                        "token_embeddings must have the same length as ids".to_string(),
+                    ));
+                }
+                let dimension = self.config.dimension;
+                Some(tokens.into_iter().map(|t| normalize_tokens(t, dimension)).collect::<Result<Vec<_>>>()?)
+            }
+            Some(_) => {
+                return Err(VectorDbError::InvalidConfig(format!(
+                    "collection '{}' does not use late interaction",
+                    self.config.name
+                )))
+            }
+            None if self.config.late_interaction => {
+                return Err(VectorDbError::InvalidConfig(format!(
+                    "collection '{}' uses late interaction, token_embeddings are required",
+                    self.config.name
+                )))
+            }
+            None => None,
+        };
+        let embeddings = match tokens {
+            Some(ref tokens) if embeddings.is_empty() => tokens.iter().map(|t| pool(t)).collect(),
+            _ => embeddings,
+        };
+        if n != embeddings.len() {
+            return Err(VectorDbError::InvalidConfig(
+                "ids and embeddings must have the same length".to_string(),
+            ));
+        }
+
+        if let Some(ref metas) = metadatas {
+            if metas.len() != n {
+                return Err(VectorDbError::InvalidConfig(
+                    "metadatas must have the same length as ids".to_string(),
+                ));
+            }
+            metas.iter().try_for_each(check_reserved_keys)?;
+        }
+        let metadatas = match options.on_conflict {
+            OnConflict::Merge => Some(self.merge_metadatas(&ids, metadatas)),
+            _ => metadatas,
+        };
+        if let Some(ref metas) = metadatas {
+            for (id, meta) in ids.iter().zip(metas.iter()) {
+                self.config.metadata_limits.check(id, meta)?;
+            }
+        }
+
+        if let Some(ref hashes) = options.content_hashes {
+            if hashes.len() != n {
+                return Err(VectorDbError::InvalidConfig(
+                    "content_hashes must have the same length as ids".to_string(),
+                ));
+            }
+        }
+
+        // collection binaire : tout le lot vérifié avant la première écriture
+        if self.config.is_binary() {
+            let not_bits = |e: &Vec<f32>| e.iter().any(|&v| v != 0.0 && v != 1.0);
+            if let Some(id) = ids.iter().zip(&embeddings).find(|(_, e)| not_bits(e)).map(|(id, _)| id) {
+                return Err(VectorDbError::InvalidConfig(format!(
+                    "collection '{}' is binary: embedding of '{}' must contain only 0 and 1",
+                    self.config.name, id
+                )));
+            }
+        }
+
+        let on_conflict = options.on_conflict;
+        if on_conflict == OnConflict::Error {
+            if let Some(id) = ids.iter().find(|id| self.vectors.contains_key(*id)) {
+                return Err(VectorDbError::VectorAlreadyExists(id.clone()));
+            }
+        }
+
+        let count_before = self.vectors.len();
+        if self.max_vectors > 0 {
+            let new_ids: HashSet<&String> = ids.iter().filter(|id| !self.vectors.contains_key(*id)).collect();
+            if count_before + new_ids.len() > self.max_vectors {
+                return Err(VectorDbError::LimitExceeded(format!(
+                    "collection '{}' would hold {} vectors, limit is {}",
+                    self.config.name,
+                    count_before + new_ids.len(),
+                    self.max_vectors
+                )));
+            }
+        }
+
+        // référence de dérive : les données présentes avant le premier lot
+        if !self.drift.is_seeded() {
+            // vecteurs en mémoire seulement : pas de relecture complète des listes sur disque
+            let in_memory: Vec<Cow<[f32]>> = self
+                .vectors
+                .values()
+                .filter(|e| !e.embedding.is_empty() || self.quantized.contains_key(&e.id) || self.half.contains_key(&e.id))
+                .map(|e| memory_embedding(&self.quantized, &self.half, e))
+                .collect();
+            self.drift.seed(self.config.dimension, in_memory.iter().map(|e| e.as_ref()));
+        }
+
+        let now = MetadataValue::now();
+        let mut outcome = AddOutcome::default();
+        let mut sample = BatchSample::new(self.config.dimension);
+
+        // pre-reserve capacity si nécessaire
+        if self.vectors.capacity() < self.vectors.len() + n {
+            self.vectors.reserve(n);
+        }
+
+        for idx in 0..n {
+            if options.written_at.is_some_and(|at| self.tombstones.blocks(&ids[idx], at)) {
+                outcome.tombstoned += 1;
+                continue;
+            }
+            let exists = self.vectors.contains_key(&ids[idx]);
+            if exists && on_conflict == OnConflict::Skip {
+                outcome.skipped += 1;
+                continue;
+            }
+
+            let mut embedding = embeddings[idx].clone();
+
+            if embedding.len() != self.config.dimension {
+                return Err(VectorDbError::DimensionMismatch {
+                    expected: self.config.dimension,
+                    actual: embedding.len(),
+                });
+            }
+
+            // empreinte calculée avant normalisation, reproductible côté client
+            let content_hash = match options.content_hashes {
+                Some(ref hashes) => Some(hashes[idx].clone()),
+                None if self.config.content_hash => Some(embedding_hash(&embedding)),
+                None => None,
+            };
+
+            // collection binaire : composantes 0/1 empaquetées, ni normalisation ni dérive
+            if self.config.is_binary() {
+                // composantes 0/1 vérifiées avant la boucle
+                self.binary.insert(ids[idx].clone(), BinaryVector::threshold(&embedding));
+                embedding = Vec::new();
+            } else {
+                if self.config.store_raw {
+                    self.raw.insert(ids[idx].clone(), embedding.clone());
+                }
+                let raw_norm = embedding.iter().map(|x| x * x).sum::<f32>().sqrt();
+                normalize_l2(&mut embedding);
+                sample.push(raw_norm, &embedding);
+            }
+
+            let mut metadata = metadatas
+                .as_ref()
+                .and_then(|m| m.get(idx))
+                .cloned()
+                .unwrap_or_default();
+
+            // conserver la date de création si l'ID existe déjà
+            let created_at = self.vectors
+                .get(&ids[idx])
+                .and_then(|e| e.metadata.get(SYSTEM_CREATED_AT))
+                .cloned()
+                .unwrap_or_else(|| now.clone());
+            metadata.insert(SYSTEM_CREATED_AT.to_string(), created_at);
+            metadata.insert(SYSTEM_UPDATED_AT.to_string(), now.clone());
+            metadata.insert(SYSTEM_SOURCE.to_string(), MetadataValue::from(options.source.as_str()));
+            if let Some(hash) = content_hash {
+                metadata.insert(SYSTEM_CONTENT_HASH.to_string(), MetadataValue::String(hash.into()));
+            }
+            self.dictionary.intern(&mut metadata);
+
+            if let Some(sq) = self.config.scalar_quantization {
+                self.quantized.insert(ids[idx].clone(), Int8Vector::quantize(&embedding));
+                if sq.rescore.is_none() {
+                    embedding = Vec::new();
+                }
+            }
+            if self.config.precision.is_half() {
+                self.half.insert(ids[idx].clone(), HalfVector::encode(&embedding, self.config.precision));
+                embedding = Vec::new();
+            }
+            let entry = VectorEntry {
+                id: ids[idx].clone(),
+                embedding,
+                metadata,
+            };
+            // listes sur disque : pas de rebuild automatique, l'index suit les écritures
+            if let (Some(disk), Some(ivf)) = (self.disk.as_ref(), self.ivf_index.as_mut()) {
+                if let Some(previous) = self.vectors.get(&ids[idx]) {
+                    unlist(ivf, Some(disk), previous);
+                }
+                ivf.insert(ids[idx].clone(), &entry.embedding);
+            }
+            self.record_pending(&ids[idx], false);
+            if self.is_segmented() {
+                self.release_segment_row(&ids[idx]);
+                self.memtable.insert(ids[idx].clone());
+            }
+            self.columns.upsert(&ids[idx], &entry.metadata);
+            self.record_writes([&ids[idx]]);
+            self.vectors.insert(ids[idx].clone(), entry);
+            self.tombstones.clear(&ids[idx]);
+            if let Some(ref mut tokens) = tokens {
+                self.tokens.insert(ids[idx].clone(), std::mem::take(&mut tokens[idx]));
+            }
+            if exists {
+                outcome.overwritten += 1;
+            } else {
+                outcome.added += 1;
+            }
+        }
+
+        let written = outcome.added + outcome.overwritten;
+        if written == 0 {
+            self.emit_add(&ids, &outcome, &options.source, start);
+            return Ok(outcome);
+        }
+
+        if self.soft_max_vectors > 0 && count_before < self.soft_max_vectors && self.vectors.len() >= self.soft_max_vectors {
+            tracing::warn!(
+                collection = %self.config.name,
+                count = self.vectors.len(),
+                max_vectors = self.max_vectors,
+                "Collection is approaching its vector limit"
+            );
+        }
+
+        self.embeddings_dirty = true;
+        self.key_types.get_mut().unwrap().clear();
+
+        let threshold = self.drift_threshold();
+        let drift = self.drift.record(sample, &options.source, threshold);
+        if drift.alert {
+            tracing::warn!(
+                collection = %self.config.name,
+                source = %drift.source,
+                score = drift.score,
+                centroid_shift = ?drift.centroid_shift,
+                norm_change = ?drift.norm_change,
+                "Embedding drift detected"
+            );
+        }
+
+        // marquer qu'on doit rebuild l'IVF (sauf en batch mode)
+        if self.config.use_ivf && !self.is_segmented() {
+            self.modifications_count += written;
+            if !self.batch_mode {
+                self.needs_rebuild = true;
+            }
+        }
+
+        self.emit_add(&ids, &outcome, &options.source, start);
+        Ok(outcome)
+    }
+
+    // version d'un vecteur : date de sa dernière écriture (_system.updated_at), en
+    // microsecondes ; None s'il n'existe pas ou n'a pas de date (collections migrées)
+    pub fn vector_version(&self, id: &str) -> Option<i64> {
+        match self.vectors.get(id)?.metadata.get(SYSTEM_UPDATED_AT)? {
+            MetadataValue::DateTime(micros) => Some(*micros),
+            _ => None,
+        }
+    }
+
+    pub fn get(
+        &self,
+        ids: Option<Vec<String>>,
+        include: Option<Vec<String>>,
+    ) -> Result<GetResult> {
+        let default_include = vec!["metadatas".to_string(), "embeddings".to_string()];
+        let include_set: HashSet<String> = include
+            .unwrap_or(default_include)
+            .into_iter()
+            .collect();
+
+        let entries: Vec<&VectorEntry> = match ids {
+            Some(id_list) => id_list
+                .iter()
+                .filter_map(|id| self.vectors.get(id))
+                .collect(),
+            None => self.vectors.values().collect(),
+        };
+
+        let result_ids = entries.iter().map(|e| e.id.clone()).collect();
+
+        let embeddings = if include_set.contains("embeddings") {
+            Some(
+                entries
+                    .iter()
+                    .map(|e| self.stored_embedding_of(e))
+                    .collect::<Result<_>>()?,
+            )
+        } else {
+            None
+        };
+
+        let metadatas = if include_set.contains("metadatas") {
+            Some(entries.iter().map(|e| e.metadata.clone()).collect())
+        } else {
+            None
+        };
+
+        Ok(GetResult {
+            ids: result_ids,
+            embeddings,
+            metadatas,
+        })
+    }
+
+    // ids d'une page de get sans ids, par ordre croissant d'id (stable d'un appel à
+    // l'autre, contrairement à l'ordre de la table) : ids strictement supérieurs à
+    // `after` satisfaisant le filtre, `offset` sautés puis au plus `limit`. Le booléen
+    // indique qu'il en reste après la page ; seuls les ids jusqu'à la fin de la page
+    // sont triés
+    pub fn page_ids(
+        &self,
+        filter: Option<&WhereFilter>,
+        after: Option<&str>,
+        offset: usize,
+        limit: Option<usize>,
+    ) -> Result<(Vec<String>, bool)> {
+        let mut ids: Vec<&str> = match filter {
+            Some(f) => {
+                self.validate_filter(f)?;
+                self.select_ids(f)
+            }
+            None => self.vectors.keys().map(String::as_str).collect(),
+        };
+        if let Some(after) = after {
+            ids.retain(|id| *id > after);
+        }
+        let end = limit.map_or(ids.len(), |limit| offset.saturating_add(limit));
+        let has_more = end < ids.len();
+        if has_more {
+            ids.select_nth_unstable(end);
+            ids.truncate(end);
+        }
+        ids.sort_unstable();
+        Ok((ids.into_iter().skip(offset).map(str::to_string).collect(), has_more))
+    }
+
+    pub fn update(
+        &mut self,
+        ids: Vec<String>,
+        metadatas: Vec<HashMap<String, MetadataValue>>,
+    ) -> Result<()> {
+        self.ensure_writable()?;
+        if ids.len() != metadatas.len() {
+            return Err(VectorDbError::InvalidConfig(
+                "ids and metadatas must have the same length".to_string(),
+            ));
+        }
+
+        metadatas.iter().try_for_each(check_reserved_keys)?;
+
+        // vérifier les limites sur le résultat fusionné avant toute modification
+        for (id, patch) in ids.iter().zip(metadatas.iter()) {
+            let entry = self.vectors
+                .get(id)
+                .ok_or_else(|| VectorDbError::VectorNotFound(id.clone()))?;
+            let mut merged = entry.metadata.clone();
+            merged.extend(patch.iter().map(|(k, v)| (k.clone(), v.clone())));
+            self.config.metadata_limits.check(id, &merged)?;
+        }
+
+        let now = MetadataValue::now();
+        for (idx, id) in ids.iter().enumerate() {
+            self.materialize(id)?;
+            let entry = self.vectors
+                .get_mut(id)
+                .ok_or_else(|| VectorDbError::VectorNotFound(id.clone()))?;
+
+            // merge metadata au lieu de remplacer
+            for (k, v) in &metadatas[idx] {
+                entry.metadata.insert(k.clone(), v.clone());
+            }
+            entry.metadata.insert(SYSTEM_UPDATED_AT.to_string(), now.clone());
+            self.dictionary.intern(&mut entry.metadata);
+            self.columns.upsert(id, &entry.metadata);
+        }
+        self.record_writes(&ids);
+        self.key_types.get_mut().unwrap().clear();
+
+        Ok(())
+    }
+
+    // fusionne `patch` dans les métadonnées de tous les vecteurs correspondant au filtre ;
+    // renvoie le nombre de vecteurs modifiés
+    pub fn update_where(
+        &mut self,
+        where_filter: &WhereFilter,
+        patch: HashMap<String, MetadataValue>,
+    ) -> Result<usize> {
+        self.ensure_writable()?;
+        check_reserved_keys(&patch)?;
+        self.validate_filter(where_filter)?;
+
+        let ids: Vec<String> = self.select_ids(where_filter).into_iter().map(str::to_string).collect();
+
+        // vérifier les limites sur tous les résultats fusionnés avant toute modification
+        for id in &ids {
+            let mut merged = self.vectors[id].metadata.clone();
+            merged.extend(patch.iter().map(|(k, v)| (k.clone(), v.clone())));
+            self.config.metadata_limits.check(id, &merged)?;
+        }
+
+        let now = MetadataValue::now();
+        for id in &ids {
+            self.materialize(id)?;
+            if let Some(entry) = self.vectors.get_mut(id) {
+                entry.metadata.extend(patch.iter().map(|(k, v)| (k.clone(), v.clone())));
+                entry.metadata.insert(SYSTEM_UPDATED_AT.to_string(), now.clone());
+                self.dictionary.intern(&mut entry.metadata);
+                self.columns.upsert(id, &entry.metadata);
+            }
+        }
+        self.record_writes(&ids);
+        self.key_types.get_mut().unwrap().clear();
+
+        Ok(ids.len())
+    }
+
+    pub fn delete(&mut self, ids: Vec<String>) -> Result<()> {
+        self.ensure_writable()?;
+        self.remove_ids(&ids);
+        Ok(())
+    }
+
+    // supprime tous les vecteurs correspondant au filtre ; renvoie le nombre supprimé
+    pub fn delete_where(&mut self, where_filter: &WhereFilter) -> Result<usize> {
+        self.ensure_writable()?;
+        self.validate_filter(where_filter)?;
+
+        let ids: Vec<String> = self.select_ids(where_filter).into_iter().map(str::to_string).collect();
+        Ok(self.remove_ids(&ids))
+    }
+
+    // suppression définitive (droit à l'effacement) : en plus de delete_where, ni
+    // pierre tombale ni ligne de segment ne gardent les ids effacés. `dir` est le
+    // répertoire de la collection, où les segments concernés sont réécrits
+    pub fn erase_where(&mut self, where_filter: &WhereFilter, dir: &Path) -> Result<Erasure> {
+        self.ensure_writable()?;
+        self.validate_filter(where_filter)?;
+
+        let ids: Vec<String> = self.select_ids(where_filter).into_iter().map(str::to_string).collect();
+        let content_hashes = ids
+            .iter()
+            .filter_map(|id| match self.vectors[id].metadata.get(SYSTEM_CONTENT_HASH) {
+                Some(MetadataValue::String(hash)) => Some(hash.to_string()),
+                _ => None,
+            })
+            .collect();
+        let deleted = self.remove_ids(&ids);
+        for id in &ids {
+            self.tombstones.clear(id);
+        }
+        let erased: HashSet<String> = ids.into_iter().collect();
+        let segments_rewritten = self.rewrite_segments(dir, &erased)?;
+        Ok(Erasure { deleted, segments_rewritten, content_hashes })
+    }
+
+    // les ids supprimés sont retirés tout de suite des listes inversées, la recherche
+    // IVF ne parcourt donc pas d'ids morts ; les suppressions comptent quand même
+    // dans le seuil de rebuild (centroïdes de moins en moins représentatifs)
+    fn remove_ids(&mut self, ids: &[String]) -> usize {
+        let start = Instant::now();
+        let deleted_at = chrono::Utc::now().timestamp_micros();
+        let mut removed = 0;
+        for id in ids {
+            if let Some(entry) = self.vectors.remove(id) {
+                if self.is_segmented() {
+                    self.release_segment_row(id);
+                    self.memtable.remove(id);
+                }
+                self.columns.remove(id);
+                self.record_writes([id]);
+                if self.config.tombstones {
+                    self.tombstones.record(id, deleted_at);
+                }
+                if let Some(ref mut ivf) = self.ivf_index {
+                    unlist(ivf, self.disk.as_ref(), &entry);
+                }
+                self.tokens.remove(id);
+                self.quantized.remove(id);
+                self.binary.remove(id);
+                self.half.remove(id);
+                self.raw.remove(id);
+                removed += 1;
+            }
+            self.record_pending(id, true);
+        }
+        self.hooks.emit(|h| {
+            h.on_delete(&DeleteEvent {
+                collection: &self.config.name,
+                ids,
+                removed,
+                duration: start.elapsed(),
+            })
+        });
+        if removed == 0 {
+            return 0;
+        }
+        self.dictionary.prune();
+        let policy = self.tombstone_policy;
+        if policy.max_tombstones > 0 && self.tombstones.stats().count > policy.max_tombstones {
+            self.tombstones.purge(&policy, deleted_at);
+        }
+
+        self.embeddings_dirty = true;
+        self.key_types.get_mut().unwrap().clear();
+
+        if self.config.use_ivf && !self.is_segmented() {
+            self.modifications_count += removed;
+            if !self.batch_mode {
+                self.needs_rebuild = true;
+            }
+        }
+
+        removed
+    }
+
+    pub fn count(&self) -> usize {
+        self.vectors.len()
+    }
+
+    // compare des empreintes (id -> hash) à celles stockées : un id absent est nouveau,
+    // un id présent sans empreinte stockée est considéré comme modifié
+    pub fn diff(&self, hashes: &HashMap<String, String>) -> DiffResult {
+        let mut result = DiffResult::default();
+        for (id, hash) in hashes {
+            let bucket = match self.vectors.get(id) {
+                None => &mut result.new,
+                Some(entry) => match entry.metadata.get(SYSTEM_CONTENT_HASH) {
+                    Some(MetadataValue::String(stored)) if **stored == *hash => &mut result.unchanged,
+                    _ => &mut result.changed,
+                },
+            };
+            bucket.push(id.clone());
+        }
+        result.new.sort();
+        result.changed.sort();
+        result.unchanged.sort();
+        result
+    }
+
+    // rejette un filtre mal formé ou dont les valeurs ne peuvent correspondre au type
+    // des métadonnées stockées ; les types observés sont mis en cache par clé
+    pub fn validate_filter(&self, where_filter: &WhereFilter) -> Result<()> {
+        filter::validate_filter(where_filter)?;
+
+        let mut key_types = self.key_types.lock().unwrap();
+        let missing: Vec<&str> = where_filter
+            .keys()
+            .map(String::as_str)
+            .filter(|k| !key_types.contains_key(*k))
+            .collect();
+        if !missing.is_empty() {
+            key_types.extend(filter::observed_types(&missing, self.vectors.values().map(|e| &e.metadata)));
+        }
+        filter::check_filter_types(where_filter, &key_types)
+    }
+
+    pub fn count_where(&self, filter: &WhereFilter) -> usize {
+        self.select_ids(filter).len()
+    }
+
+    // vecteurs satisfaisant le filtre, tous sans filtre. En estimation, le filtre est
+    // évalué sur les COUNT_SAMPLE_SIZE premiers vecteurs (ordre de la table, sans lien
+    // avec les métadonnées) et la proportion extrapolée ; exact en dessous
+    pub fn total_matched(&self, filter: Option<&WhereFilter>, mode: CountMode) -> MatchCount {
+        let total = self.vectors.len();
+        let Some(filter) = filter else {
+            return MatchCount { total_matched: total, exact: true };
+        };
+        if mode == CountMode::Exact || total <= COUNT_SAMPLE_SIZE {
+            return MatchCount { total_matched: self.count_where(filter), exact: true };
+        }
+        let matched = self
+            .vectors
+            .values()
+            .take(COUNT_SAMPLE_SIZE)
+            .filter(|e| matches_filter(&e.metadata, filter))
+            .count();
+        let estimate = (matched as f64 * total as f64 / COUNT_SAMPLE_SIZE as f64).round() as usize;
+        MatchCount { total_matched: estimate, exact: false }
+    }
+
+    // ids satisfaisant le filtre, par balayage des colonnes de métadonnées
+    fn select_ids(&self, filter: &WhereFilter) -> Vec<&str> {
+        self.columns.select(filter, |id| self.vectors.get(id).map(|e| &e.metadata))
+    }
+
+    pub fn metadata_stats(&self) -> MetadataStats {
+        metadata::compute_stats(self.vectors.values().map(|e| &e.metadata))
+    }
+
+    // vide la fenêtre de latences (instantanés périodiques)
+    pub fn take_latencies(&self) -> LatencyWindow {
+        std::mem::take(&mut *self.latencies.lock().unwrap())
+    }
+
+    pub fn drift_threshold(&self) -> f32 {
+        self.config.drift_threshold.unwrap_or(DEFAULT_DRIFT_THRESHOLD)
+    }
+
+    pub fn drift_report(&self) -> DriftReport {
+        self.drift.report(self.drift_threshold())
+    }
+
+    pub fn stats(&self) -> CollectionStats {
+        let index_info = if self.config.use_ivf {
+            self.ivf_index.as_ref().map(|ivf| IndexInfo {
+                is_built: ivf.is_built(),
+                n_clusters: self.config.n_clusters,
+                n_centroids: ivf.centroids.len(),
+                needs_rebuild: self.needs_rebuild,
+                rebuilding: self.rebuild_in_progress(),
+                n_probe: self.n_probe(ivf),
+                refine_factor: self.config.index_params.refine_factor,
+                coarse_groups: ivf.coarse.as_ref().map(|coarse| coarse.groups.len()),
+                disk: self.disk.as_ref().map(|disk| DiskListsInfo {
+                    path: disk.path().display().to_string(),
+                    vectors: self.vectors.values().filter(|e| e.embedding.is_empty()).count(),
+                    file_bytes: disk.file_bytes(),
+                    cache: disk.cache_stats(),
+                }),
+                pq: ivf.pq.as_ref().map(|pq| PqInfo {
+                    subspaces: pq.code_len(),
+                    n_centroids: pq.n_centroids(),
+                    code_bytes: ivf.pq_code_bytes(),
+                    codebook_bytes: pq.codebook_bytes(),
+                    rerank: self.config.index_params.pq_rerank,
+                }),
+            })
+        } else {
+            None
+        };
+
+        // estimation mémoire approximative, hors vecteurs sur disque ou dans un segment
+        let in_memory =
+            if self.disk.is_some()
+                || self.is_segmented()
+                || !self.quantized.is_empty()
+                || !self.binary.is_empty()
+                || !self.half.is_empty()
+            {
+                self.vectors.values().filter(|e| !e.embedding.is_empty()).count()
+            } else {
+                self.vectors.len()
+            };
+        let list_cache = self.disk.as_ref().map_or(0, |disk| disk.cache_stats().used_bytes);
+        let quantized: usize = self.quantized.values().map(Int8Vector::bytes).sum::<usize>()
+            + self.binary.values().map(BinaryVector::bytes).sum::<usize>()
+            + self.half.values().map(HalfVector::bytes).sum::<usize>()
+            + self.raw.len() * self.config.dimension * 4;
+        let embedding_bytes = in_memory * self.config.dimension * 4 + quantized;
+        let vec_size = embedding_bytes + self.vectors.len() * 64 + list_cache; // f32 + overhead
+        let index_size = if let Some(ref ivf) = self.ivf_index {
+            ivf.centroids.len() * self.config.dimension * 4
+                + ivf.pq_code_bytes()
+                + ivf.pq.as_ref().map_or(0, |pq| pq.codebook_bytes())
+        } else {
+            0
+        };
+
+        CollectionStats {
+            name: self.config.name.clone(),
+            dimension: self.config.dimension,
+            count: self.vectors.len(),
+            use_ivf: self.config.use_ivf,
+            index_info,
+            estimated_memory_bytes: vec_size + index_size,
+            precision: self.config.precision,
+            embedding_bytes,
+            last_query_time_ms: self.last_query_time_ms,
+            total_queries: self.total_queries,
+            loaded: true,
+            drift: Some(self.drift_report()),
+            index_status: self.index_status(),
+            vector_limit: (self.max_vectors > 0).then_some(LimitUsage {
+                used: self.vectors.len(),
+                max: self.max_vectors,
+                soft_limit: self.soft_max_vectors,
+                soft_exceeded: self.vectors.len() >= self.soft_max_vectors,
+            }),
+            rebuild_policy: self.config.rebuild_policy.clone(),
+            tombstones: Some(self.tombstone_stats()),
+            metadata_dictionary: Some(self.dictionary_stats()),
+            segments: self.segment_infos(),
+        }
+    }
+
+    pub fn dictionary_stats(&self) -> DictionaryStats {
+        self.dictionary.stats()
+    }
+
+    // rebuilder l'index IVF si nécessaire
+    pub fn rebuild_index(&mut self) {
+        if !self.config.use_ivf || !self.needs_rebuild || self.rebuild_in_progress() || self.is_segmented() {
+            return;
+        }
+
+        // listes sur disque : réentraîner, c'est réécrire le fichier
+        if let Some(dir) = self.disk_dir() {
+            if let Err(e) = self.offload_to_disk(&dir) {
+                tracing::error!(collection = %self.config.name, error = %e, "Failed to rebuild disk lists");
+            }
+            return;
+        }
+
+        if let Some(ref mut ivf) = self.ivf_index {
+            let data: Vec<(String, Vec<f32>)> = self.vectors.iter()
+                .map(|(id, v)| (id.clone(), memory_embedding(&self.quantized, &self.half, v).into_owned()))
+                .collect();
+
+            if !data.is_empty() {
+                let start = Instant::now();
+                ivf.rebuild(&data);
+                self.needs_rebuild = false;
+                self.modifications_count = 0;
+                self.emit_rebuild(RebuildKind::InPlace, start);
+            }
+        }
+    }
+
+    // contrôle de cohérence de l'index IVF : ids des listes inversées absents des
+    // vecteurs (suppressions), vecteurs non indexés ou indexés plusieurs fois,
+    // centroïdes incompatibles avec la config
+    pub fn verify_index(&self) -> Result<IndexReport> {
+        if !self.config.use_ivf {
+            return Err(VectorDbError::InvalidConfig(
+                "Collection does not use IVF index".to_string(),
+            ));
+        }
+
+        let mut report = IndexReport {
+            vectors: self.vectors.len(),
+            needs_rebuild: self.needs_rebuild,
+            consistent: true,
+            ..Default::default()
+        };
+        let ivf = match self.ivf_index() {
+            Some(ivf) => ivf,
+            None => return Ok(report),
+        };
+        report.built = true;
+
+        if ivf.centroids.len() != ivf.inverted_lists.len() {
+            report.centroid_errors.push(format!(
+                "{} centroids for {} inverted lists",
+                ivf.centroids.len(),
+                ivf.inverted_lists.len()
+            ));
+        }
+        if ivf.centroids.len() > self.config.n_clusters {
+            report.centroid_errors.push(format!(
+                "{} centroids but n_clusters is {}",
+                ivf.centroids.len(),
+                self.config.n_clusters
+            ));
+        }
+        if let Some(c) = ivf.centroids.iter().find(|c| c.len() != self.config.dimension) {
+            report.centroid_errors.push(format!(
+                "centroid of dimension {} in a collection of dimension {}",
+                c.len(),
+                self.config.dimension
+            ));
+        }
+
+        let mut occurrences: HashMap<&str, usize> = HashMap::new();
+        for id in ivf.inverted_lists.iter().flatten() {
+            *occurrences.entry(id.as_str()).or_default() += 1;
+        }
+        report.indexed_ids = occurrences.len();
+
+        let mut stale = Vec::new();
+        let mut duplicates = Vec::new();
+        for (id, n) in &occurrences {
+            if !self.vectors.contains_key(*id) {
+                stale.push(id.to_string());
+            } else if *n > 1 {
+                duplicates.push(id.to_string());
+            }
+        }
+        let missing: Vec<String> = self.vectors
+            .keys()
+            .filter(|id| !occurrences.contains_key(id.as_str()))
+            .cloned()
+            .collect();
+
+        (report.n_stale, report.stale_ids) = report_sample(stale);
+        (report.n_missing, report.missing_ids) = report_sample(missing);
+        (report.n_duplicates, report.duplicate_ids) = report_sample(duplicates);
+        report.consistent = report.centroid_errors.is_empty()
+            && report.n_stale == 0
+            && report.n_missing == 0
+            && report.n_duplicates == 0;
+
+        Ok(report)
+    }
+
+    // embeddings f32 en mémoire dont la norme s'écarte de 1 de plus de `tolerance`
+    // (fichiers importés, formats anciens) ; les vecteurs des segments, des listes sur
+    // disque, quantifiés sans f32 ou binaires ne sont pas contrôlés (skipped)
+    pub fn verify_norms(&self, tolerance: f32) -> Result<NormReport> {
+        if !(tolerance.is_finite() && tolerance > 0.0) {
+            return Err(VectorDbError::InvalidConfig("tolerance must be > 0".to_string()));
+        }
+        let mut report = NormReport { tolerance, ..Default::default() };
+        let mut denormalized = Vec::new();
+        let mut zero = Vec::new();
+        for entry in self.vectors.values() {
+            if entry.embedding.is_empty() {
+                report.skipped += 1;
+                continue;
+            }
+            report.checked += 1;
+            let norm = entry.embedding.iter().map(|x| x * x).sum::<f32>().sqrt();
+            let deviation = (norm - 1.0).abs();
+            if norm <= 1e-10 {
+                zero.push(entry.id.clone());
+            } else if deviation > tolerance {
+                report.max_deviation = report.max_deviation.max(deviation);
+                denormalized.push(entry.id.clone());
+            }
+        }
+        (report.n_denormalized, report.denormalized_ids) = report_sample(denormalized);
+        (report.n_zero, report.zero_ids) = report_sample(zero);
+        Ok(report)
+    }
+
+    // verify_norms puis re-normalisation des vecteurs en écart (les vecteurs nuls
+    // restent tels quels). Le sens d'un vecteur ne change pas, ses listes IVF non plus ;
+    // les codes PQ et int8 dépendent de la norme : vecteurs requantifiés, rebuild dû
+    pub fn renormalize(&mut self, tolerance: f32) -> Result<NormReport> {
+        self.ensure_writable()?;
+        let mut report = self.verify_norms(tolerance)?;
+        if report.n_denormalized == 0 {
+            return Ok(report);
+        }
+
+        let ids: Vec<String> = self
+            .vectors
+            .values()
+            .filter(|e| !e.embedding.is_empty())
+            .filter(|e| {
+                let norm = e.embedding.iter().map(|x| x * x).sum::<f32>().sqrt();
+                norm > 1e-10 && (norm - 1.0).abs() > tolerance
+            })
+            .map(|e| e.id.clone())
+            .collect();
+        // vecteur rendu par get modifié : nouvelle version (ETag de GET /vectors/:id)
+        let now = MetadataValue::now();
+        for id in &ids {
+            let entry = self.vectors.get_mut(id).unwrap();
+            normalize_l2(&mut entry.embedding);
+            entry.metadata.insert(SYSTEM_UPDATED_AT.to_string(), now.clone());
+            self.columns.upsert(id, &entry.metadata);
+            if self.quantized.contains_key(id) {
+                self.quantized.insert(id.clone(), Int8Vector::quantize(&entry.embedding));
+            }
+            self.record_pending(id, false);
+        }
+        self.record_writes(&ids);
+        report.renormalized = ids.len();
+        self.embeddings_dirty = true;
+        if self.config.use_ivf && !self.is_segmented() {
+            self.modifications_count += ids.len();
+            if !self.batch_mode {
+                self.needs_rebuild = true;
+            }
+        }
+
+        tracing::info!(
+            collection = %self.config.name,
+            renormalized = report.renormalized,
+            zero = report.n_zero,
+            max_deviation = report.max_deviation,
+            "Embeddings renormalized"
+        );
+        Ok(report)
+    }
+
+    // verify_index puis correction : centroïdes incohérents -> rebuild complet ;
+    // sinon retrait des ids obsolètes et des doublons, vecteurs manquants placés
+    // dans le cluster le plus proche (sans ré-entraîner les centroïdes)
+    pub fn repair_index(&mut self) -> Result<IndexReport> {
+        if self.rebuild_in_progress() {
+            return Err(VectorDbError::RebuildInProgress(self.config.name.clone()));
+        }
+
+        let mut report = self.verify_index()?;
+        if report.consistent {
+            return Ok(report);
+        }
+        if self.is_frozen() {
+            return Err(VectorDbError::Frozen(self.config.name.clone()));
+        }
+
+        if let Some(dir) = self.disk_dir().filter(|_| !report.centroid_errors.is_empty()) {
+            self.offload_to_disk(&dir)?;
+        } else if !report.centroid_errors.is_empty() {
+            let data: Vec<(String, Vec<f32>)> = self.vectors.iter()
+                .map(|(id, v)| (id.clone(), memory_embedding(&self.quantized, &self.half, v).into_owned()))
+                .collect();
+            let n_probe = self.ivf_index.as_ref().map(|ivf| ivf.n_probe);
+            let mut ivf = IVFIndex::new(self.config.n_clusters)
+                .with_coarse_groups(self.config.index_params.coarse_groups)
+                .with_pq(self.config.pq_subspaces);
+            if let Some(n_probe) = n_probe {
+                ivf = ivf.with_n_probe(n_probe);
+            }
+            ivf.build(&data);
+            self.ivf_index = Some(ivf);
+            self.needs_rebuild = false;
+            self.modifications_count = 0;
+        } else if let Some(mut ivf) = self.ivf_index.take() {
+            let mut seen: HashSet<String> = HashSet::with_capacity(report.indexed_ids);
+            ivf.retain_ids(|id| self.vectors.contains_key(id) && seen.insert(id.clone()));
+            let placed: Result<()> = self.vectors.values().filter(|e| !seen.contains(&e.id)).try_for_each(|entry| {
+                // vecteur sur disque : sa liste est celle du fichier, relu pour son code PQ
+                match self.disk.as_ref().and_then(|d| d.location(&entry.id)) {
+                    Some((cluster, _)) if entry.embedding.is_empty() => {
+                        ivf.push_to(cluster, entry.id.clone(), &self.embedding_of(entry)?)
+                    }
+                    _ => ivf.insert(entry.id.clone(), &memory_embedding(&self.quantized, &self.half, entry)),
+                }
+                Ok(())
+            });
+            self.ivf_index = Some(ivf);
+            placed?;
+        }
+
+        tracing::info!(
+            collection = %self.config.name,
+            stale = report.n_stale,
+            missing = report.n_missing,
+            duplicates = report.n_duplicates,
+            centroid_errors = report.centroid_errors.len(),
+            "IVF index repaired"
+        );
+        report.repaired = true;
+        Ok(report)
+    }
+
+    // clusters IVF les plus proches d'un embedding, avec la taille de leur liste :
+    // pour comprendre pourquoi un vecteur n'est pas retrouvé par les requêtes IVF.
+    // Sans embedding, celui du vecteur `id` ; avec `id`, les listes qui le contiennent
+    pub fn assign_diagnostics(&self, embedding: Option<&[f32]>, id: Option<&str>, n: usize) -> Result<AssignReport> {
+        if !self.config.use_ivf {
+            return Err(VectorDbError::InvalidConfig(
+                "Collection does not use IVF index".to_string(),
+            ));
+        }
+        let ivf = self.ivf_index().ok_or_else(|| {
+            VectorDbError::InvalidConfig(format!("IVF index of '{}' is not built", self.config.name))
+        })?;
+
+        let mut normalized = match (embedding, id) {
+            (Some(embedding), _) => embedding.to_vec(),
+            (None, Some(id)) => {
+                let entry = self.vectors
+                    .get(id)
+                    .ok_or_else(|| VectorDbError::InvalidConfig(format!("unknown id '{}'", id)))?;
+                self.embedding_of(entry)?.into_owned()
+            }
+            (None, None) => {
+                return Err(VectorDbError::InvalidConfig("embedding or id is required".to_string()))
+            }
+        };
+        if normalized.len() != self.config.dimension {
+            return Err(VectorDbError::DimensionMismatch {
+                expected: self.config.dimension,
+                actual: normalized.len(),
+            });
+        }
+        normalize_l2(&mut normalized);
+
+        let n_probe = self.n_probe(ivf);
+        let nearest = ivf
+            .ranked_clusters(&normalized, n)
+            .into_iter()
+            .enumerate()
+            .map(|(rank, cluster)| ClusterAssignment {
+                cluster,
+                rank,
+                distance: cosine_distance(&normalized, &ivf.centroids[cluster]),
+                list_size: ivf.inverted_lists[cluster].len(),
+                probed: rank < n_probe,
+            })
+            .collect();
+        let listed_in = id.map(|id| {
+            ivf.inverted_lists
+                .iter()
+                .enumerate()
+                .filter(|(_, list)| list.iter().any(|listed| listed == id))
+                .map(|(cluster, _)| cluster)
+                .collect()
+        });
+
+        Ok(AssignReport {
+            n_probe,
+            n_centroids: ivf.centroids.len(),
+            nearest,
+            id: id.map(str::to_string),
+            listed_in,
+        })
+    }
+
+    pub fn index_status(&self) -> IndexStatus {
+        let modifications = self.modifications_count;
+        if self.is_segmented() {
+            IndexStatus::Segmented { segments: self.segments.len(), memtable: self.memtable.len() }
+        } else if !self.config.use_ivf {
+            IndexStatus::Disabled
+        } else if self.rebuild_in_progress() {
+            IndexStatus::Building { modifications }
+        } else if self.ivf_index().is_none() {
+            IndexStatus::NotBuilt { modifications }
+        } else if self.needs_rebuild && modifications > 0 {
+            IndexStatus::Stale { modifications, rebuild_due: self.rebuild_due() }
+        } else {
+            IndexStatus::Fresh
+        }
+    }
+
+    // rebuild automatique selon config.rebuild_policy (par défaut 10 % de modifications),
+    // sauf listes sur disque
+    pub fn rebuild_due(&self) -> bool {
+        let policy = &self.config.rebuild_policy;
+        if !policy.auto || !self.config.use_ivf || !self.needs_rebuild || self.rebuild_in_progress() || self.is_segmented() {
+            return false;
+        }
+        // listes sur disque : réécrire le fichier n'est jamais déclenché par une requête
+        if self.disk.is_some() {
+            return false;
+        }
+
+        let total = self.vectors.len();
+        if total == 0 {
+            return false;
+        }
+
+        self.modifications_count >= policy.threshold(total)
+    }
+
+    // prend effet à la prochaine requête, sans rebuild ; n_probe est aussi reporté
+    // sur l'index pour les exports (faiss)
+    pub fn set_index_params(&mut self, params: IndexParams) -> Result<()> {
+        self.ensure_writable()?;
+        params.validate()?;
+        if let Some(ivf) = self.ivf_index.as_mut() {
+            if let Some(n_probe) = params.n_probe {
+                ivf.n_probe = n_probe.min(ivf.n_clusters);
+            }
+            // second niveau reconstruit sur les centroïdes courants, listes inchangées
+            if params.coarse_groups != ivf.coarse_groups {
+                ivf.set_coarse_groups(params.coarse_groups);
+            }
+        }
+        self.config.index_params = params;
+        Ok(())
+    }
+
+    // effectif au prochain rebuild de l'index ; false si la valeur ne change pas
+    pub fn set_n_clusters(&mut self, n_clusters: usize) -> Result<bool> {
+        self.ensure_writable()?;
+        if !self.config.use_ivf {
+            return Err(VectorDbError::InvalidConfig("Collection does not use IVF index".to_string()));
+        }
+        if n_clusters == 0 {
+            return Err(VectorDbError::InvalidConfig("n_clusters must be > 0".to_string()));
+        }
+        if n_clusters == self.config.n_clusters {
+            return Ok(false);
+        }
+        self.config.n_clusters = n_clusters;
+        self.needs_rebuild = true;
+        Ok(true)
+    }
+
+    // copie des embeddings normalisés, pour entraîner des index hors verrou (autotune)
+    pub fn index_data(&self) -> Result<Vec<(String, Vec<f32>)>> {
+        self.vectors
+            .values()
+            .map(|entry| Ok((entry.id.clone(), self.embedding_of(entry)?.into_owned())))
+            .collect()
+    }
+
+    // clusters sondés par requête
+    fn n_probe(&self, ivf: &IVFIndex) -> usize {
+        self.config.index_params.n_probe.map_or(ivf.n_probe, |n| n.min(ivf.n_clusters))
+    }
+
+    // prend effet à la prochaine requête, sans rebuild
+    pub fn set_rebuild_policy(&mut self, policy: RebuildPolicy) -> Result<()> {
+        self.ensure_writable()?;
+        policy.validate()?;
+        self.config.rebuild_policy = policy;
+        Ok(())
+    }
+
+    fn maybe_rebuild(&mut self) {
+        if !self.deferred_rebuild && self.rebuild_due() {
+            self.rebuild_index();
+        }
+    }
+
+    // en mode différé, query ne reconstruit jamais l'index : l'appelant
+    // consulte rebuild_due() et lance un rebuild en arrière-plan
+    pub fn set_deferred_rebuild(&mut self, deferred: bool) {
+        self.deferred_rebuild = deferred;
+    }
+
+    // collection servie par un processus lecteur : les données ne changent que
+    // par rechargement depuis le disque (l'index reste reconstructible en mémoire)
+    pub fn set_read_only(&mut self, read_only: bool) {
+        self.read_only = read_only;
+    }
+
+    // fixée par le client (Limits::max_vectors_per_collection) ; un ajout qui la
+    // dépasserait est refusé en entier, seuls les nouveaux ids comptent
+    pub fn set_vector_limit(&mut self, max_vectors: usize, soft_max_vectors: usize) {
+        self.max_vectors = max_vectors;
+        self.soft_max_vectors = soft_max_vectors;
+    }
+
+    // fixé par le client (VECTORDB_LIST_CACHE_BYTES), pour les listes sur disque
+    pub fn set_list_cache_bytes(&mut self, bytes: usize) {
+        self.list_cache_bytes = bytes;
+        if let Some(ref disk) = self.disk {
+            disk.set_cache_capacity(bytes);
+        }
+    }
+
+    // politique appliquée tout de suite, puis à chaque purge_tombstones
+    pub fn set_tombstone_policy(&mut self, policy: TombstonePolicy) {
+        self.tombstone_policy = policy;
+        self.purge_tombstones();
+    }
+
+    pub fn purge_tombstones(&mut self) -> usize {
+        self.tombstones.purge(&self.tombstone_policy, chrono::Utc::now().timestamp_micros())
+    }
+
+    pub fn tombstones_need_purge(&self) -> bool {
+        self.tombstones.needs_purge(&self.tombstone_policy, chrono::Utc::now().timestamp_micros())
+    }
+
+    pub fn tombstone_stats(&self) -> TombstoneStats {
+        self.tombstones.stats()
+    }
+
+    pub(crate) fn tombstones(&self) -> &TombstoneSet {
+        &self.tombstones
+    }
+
+    pub(crate) fn set_tombstones(&mut self, tombstones: TombstoneSet) {
+        self.tombstones = tombstones;
+    }
+
+    pub fn set_hooks(&mut self, hooks: HookSet) {
+        self.hooks = hooks;
+    }
+
+    fn emit_add(&self, ids: &[String], outcome: &AddOutcome, source: &str, start: Instant) {
+        self.hooks.emit(|h| {
+            h.on_add(&AddEvent {
+                collection: &self.config.name,
+                ids,
+                outcome,
+                source,
+                duration: start.elapsed(),
+            })
+        });
+    }
+
+    fn emit_rebuild(&self, kind: RebuildKind, start: Instant) {
+        self.hooks.emit(|h| {
+            h.on_rebuild(&RebuildEvent {
+                collection: &self.config.name,
+                kind,
+                vectors: self.vectors.len(),
+                n_clusters: self.config.n_clusters,
+                duration: start.elapsed(),
+            })
+        });
+    }
+
+    fn ensure_writable(&self) -> Result<()> {
+        if self.read_only {
+            return Err(VectorDbError::ReadOnly(self.config.name.clone()));
+        }
+        if self.frozen {
+            return Err(VectorDbError::Frozen(self.config.name.clone()));
+        }
+        Ok(())
+    }
+
+    pub fn rebuild_in_progress(&self) -> bool {
+        self.pending_rebuild.is_some()
+    }
+
+    // rebuild en deux temps : begin_rebuild capture les données sous verrou,
+    // le k-means tourne hors verrou (RebuildJob::run), finish_rebuild échange l'index.
+    // Les écritures reçues entre-temps s'appliquent tout de suite aux vecteurs
+    // (visibles en recherche linéaire et par get) et sont rejouées sur le nouvel
+    // index au moment de l'échange ; l'ancien index continue de servir les requêtes.
+    pub fn begin_rebuild(&mut self) -> Result<RebuildJob> {
+        if !self.config.use_ivf {
+            return Err(VectorDbError::InvalidConfig(
+                "Collection does not use IVF index".to_string(),
+            ));
+        }
+        if self.rebuild_in_progress() {
+            return Err(VectorDbError::RebuildInProgress(self.config.name.clone()));
+        }
+        if self.is_frozen() {
+            return Err(VectorDbError::Frozen(self.config.name.clone()));
+        }
+        if self.is_segmented() {
+            return Err(VectorDbError::InvalidConfig(format!(
+                "collection '{}' is segmented: each segment has its own index",
+                self.config.name
+            )));
+        }
+        if self.disk.is_some() {
+            return Err(VectorDbError::InvalidConfig(format!(
+                "lists of '{}' are on disk: offload it again to retrain the index",
+                self.config.name
+            )));
+        }
+
+        let n_probe = self.ivf_index.as_ref().map(|ivf| ivf.n_probe);
+        let data = self.vectors.iter()
+            .map(|(id, v)| (id.clone(), memory_embedding(&self.quantized, &self.half, v).into_owned()))
+            .collect();
+
+        self.pending_rebuild = Some(PendingRebuild {
+            added: HashSet::new(),
+            removed: HashSet::new(),
+            started: Instant::now(),
+        });
+        self.modifications_count = 0;
+
+        Ok(RebuildJob {
+            data,
+            n_clusters: self.config.n_clusters,
+            n_probe,
+            coarse_groups: self.config.index_params.coarse_groups,
+            pq_subspaces: self.config.pq_subspaces,
+        })
+    }
+
+    // false si aucun rebuild n'était en cours (collection rechargée entre-temps) :
+    // l'index fourni est alors ignoré
+    pub fn finish_rebuild(&mut self, mut index: IVFIndex) -> bool {
+        let pending = match self.pending_rebuild.take() {
+            Some(pending) => pending,
+            None => return false,
+        };
+
+        for id in &pending.removed {
+            index.remove(id);
+        }
+        for id in &pending.added {
+            if let Some(entry) = self.vectors.get(id) {
+                index.remove(id);
+                index.insert(id.clone(), &memory_embedding(&self.quantized, &self.half, entry));
+            }
+        }
+
+        self.ivf_index = Some(index);
+        self.needs_rebuild = self.modifications_count > 0;
+        self.emit_rebuild(RebuildKind::Background, pending.started);
+        true
+    }
+
+    pub fn abort_rebuild(&mut self) {
+        if self.pending_rebuild.take().is_some() {
+            self.needs_rebuild = true;
+        }
+    }
+
+    fn record_pending(&mut self, id: &str, removed: bool) {
+        if let Some(ref mut pending) = self.pending_rebuild {
+            if removed {
+                pending.added.remove(id);
+                pending.removed.insert(id.to_string());
+            } else {
+                pending.removed.remove(id);
+                pending.added.insert(id.to_string());
+            }
+        }
+    }
+
+    // requête par appels chaînés : coll.search(&v).k(10).filter(f).run()
+    pub fn search(&mut self, embedding: &[f32]) -> QueryBuilder<'_> {
+        QueryBuilder::new(self, embedding.to_vec())
+    }
+
+    // embedding stocké pour un id : normalisé, ou tel qu'ajouté avec store_raw
+    pub(crate) fn embedding(&self, id: &str) -> Result<Option<Vec<f32>>> {
+        self.vectors.get(id).map(|e| self.stored_embedding_of(e)).transpose()
+    }
+
+    pub fn query(
+        &mut self,
+        query_embedding: &[f32],
+        n_results: usize,
+        where_filter: Option<&WhereFilter>,
+    ) -> Result<Vec<SearchResult>> {
+        self.query_with_options(query_embedding, n_results, where_filter, &QueryOptions::default())
+    }
+
+    pub fn query_with_options(
+        &mut self,
+        query_embedding: &[f32],
+        n_results: usize,
+        where_filter: Option<&WhereFilter>,
+        options: &QueryOptions,
+    ) -> Result<Vec<SearchResult>> {
+        let start = Instant::now();
+        self.check_query(query_embedding, where_filter)?;
+
+        if !options.exact {
+            self.maybe_rebuild();
+        }
+
+        let results = self.run_query(query_embedding, n_results, where_filter, options)?;
+
+        self.record_query(start.elapsed(), n_results, results.len(), where_filter.is_some(), options.exact);
+        Ok(results)
+    }
+
+    // latence, compteurs et hook on_query d'une requête exécutée, quel que soit le chemin
+    fn record_query(&mut self, elapsed: Duration, n_results: usize, returned: usize, filtered: bool, exact: bool) {
+        self.latencies.get_mut().unwrap().record(elapsed);
+        self.last_query_time_ms = elapsed.as_secs_f64() * 1000.0;
+        self.total_queries += 1;
+        self.hooks.emit(|h| {
+            h.on_query(&QueryEvent {
+                collection: &self.config.name,
+                n_results,
+                returned,
+                filtered,
+                exact,
+                duration: elapsed,
+            })
+        });
+    }
+
+    // plusieurs requêtes en un appel, exécutées en parallèle ; une liste de résultats
+    // par requête, dans l'ordre. Rebuild éventuel une seule fois, avant la première
+    pub fn query_batch(
+        &mut self,
+        query_embeddings: &[Vec<f32>],
+        n_results: usize,
+        where_filter: Option<&WhereFilter>,
+        options: &QueryOptions,
+    ) -> Result<Vec<Vec<SearchResult>>> {
+        let start = Instant::now();
+        for query_embedding in query_embeddings {
+            self.check_query(query_embedding, where_filter)?;
+        }
+        if !options.exact {
+            self.maybe_rebuild();
+        }
+
+        let results = query_embeddings
+            .par_iter()
+            .map(|query_embedding| self.run_query(query_embedding, n_results, where_filter, options))
+            .collect::<Result<Vec<_>>>()?;
+
+        // latence moyenne par requête, comparable à celle d'une requête seule
+        let elapsed = start.elapsed() / query_embeddings.len().max(1) as u32;
+        for batch_results in &results {
+            self.record_query(elapsed, n_results, batch_results.len(), where_filter.is_some(), options.exact);
+        }
+        Ok(results)
+    }
+
+    // tous les vecteurs à distance <= max_distance, les plus proches d'abord, au lieu
+    // d'un top-k. Toujours exhaustif : l'IVF ne sonde que les clusters les plus proches
+    // et manquerait une partie du rayon. `max_results` borne la mémoire des résultats
+    pub fn range_search(
+        &mut self,
+        query_embedding: &[f32],
+        max_distance: f32,
+        where_filter: Option<&WhereFilter>,
+        max_results: usize,
+    ) -> Result<RangeResults> {
+        let options = QueryOptions::default().exact().max_distance(max_distance);
+        let mut results =
+            self.query_with_options(query_embedding, max_results.saturating_add(1), where_filter, &options)?;
+        let truncated = results.len() > max_results;
+        results.truncate(max_results);
+        Ok(RangeResults { results, truncated })
+    }
+
+    // résultats exhaustifs de référence, sans rebuild ni effet sur les statistiques
+    // de requêtes : mesure du rappel de l'index
+    pub fn exact_results(
+        &self,
+        query_embedding: &[f32],
+        n_results: usize,
+        where_filter: Option<&WhereFilter>,
+        options: &QueryOptions,
+    ) -> Result<Vec<SearchResult>> {
+        self.check_query(query_embedding, where_filter)?;
+        let options = QueryOptions { exact: true, ..options.clone() };
+        self.run_query(query_embedding, n_results, where_filter, &options)
+    }
+
+    // la même requête par l'index et en exhaustif : les deux listes, leur recouvrement
+    // et le rappel de l'index
+    pub fn compare_query(
+        &mut self,
+        query_embedding: &[f32],
+        n_results: usize,
+        where_filter: Option<&WhereFilter>,
+        options: &QueryOptions,
+    ) -> Result<QueryComparison> {
+        let options = QueryOptions { exact: false, ..options.clone() };
+        let start = Instant::now();
+        let results = self.query_with_options(query_embedding, n_results, where_filter, &options)?;
+        let index_ms = start.elapsed().as_secs_f64() * 1000.0;
+
+        let start = Instant::now();
+        let exact = self.exact_results(query_embedding, n_results, where_filter, &options)?;
+        let exact_ms = start.elapsed().as_secs_f64() * 1000.0;
+
+        let expected: HashSet<&str> = exact.iter().map(|r| r.id.as_str()).collect();
+        let overlap = results.iter().filter(|r| expected.contains(r.id.as_str())).count();
+        Ok(QueryComparison {
+            recall: recall(&results, &exact),
+            overlap,
+            index_ms,
+            exact_ms,
+            results,
+            exact,
+        })
+    }
+
+    fn check_query(&self, query_embedding: &[f32], where_filter: Option<&WhereFilter>) -> Result<()> {
+        if query_embedding.len() != self.config.dimension {
+            return Err(VectorDbError::DimensionMismatch {
+                expected: self.config.dimension,
+                actual: query_embedding.len(),
+            });
+        }
+        if self.config.is_binary() && BinaryVector::from_bits(query_embedding).is_none() {
+            return Err(VectorDbError::InvalidConfig(format!(
+                "collection '{}' is binary: the query must contain only 0 and 1",
+                self.config.name
+            )));
+        }
+
+        if let Some(filter) = where_filter {
+            self.validate_filter(filter)?;
+        }
+        Ok(())
+    }
+
+    // recherche proprement dite, requête déjà validée
+    fn run_query(
+        &self,
+        query_embedding: &[f32],
+        n_results: usize,
+        where_filter: Option<&WhereFilter>,
+        options: &QueryOptions,
+    ) -> Result<Vec<SearchResult>> {
+        let mut normalized_query = query_embedding.to_vec();
+        normalize_l2(&mut normalized_query);
+
+        let (plan, selected) = self.plan_query(n_results, where_filter, options);
+        tracing::trace!(
+            collection = %self.config.name,
+            strategy = ?plan.strategy,
+            reason = ?plan.reason,
+            "Query planned"
+        );
+        let mut results = match plan.strategy {
+            QueryStrategy::Segments => self.query_segments(&normalized_query, n_results, where_filter, options)?,
+            QueryStrategy::Ivf => self.query_with_ivf(&normalized_query, n_results, where_filter, options)?,
+            QueryStrategy::Linear => {
+                self.query_linear(&normalized_query, n_results, where_filter, selected, options)?
+            }
+        };
+
+        // appliquer filtre si présent
+        if let Some(filter) = where_filter {
+            results.retain(|r| matches_filter(&r.metadata, filter));
+            results.truncate(n_results);
+        }
+        results.retain(|r| options.within(r.distance));
+
+        if options.explain_hits {
+            self.explain_hits(&mut results, query_embedding, &normalized_query, plan.strategy);
+        }
+
+        Ok(results)
+    }
+
+    // plan que suivrait la requête dans l'état courant : à appeler après
+    // query_with_options, qui peut reconstruire l'index avant de planifier
+    pub fn explain_query(
+        &self,
+        n_results: usize,
+        where_filter: Option<&WhereFilter>,
+        options: &QueryOptions,
+    ) -> QueryExplanation {
+        let (plan, _) = self.plan_query(n_results, where_filter, options);
+        let filter_stage = where_filter.map(|_| match plan.strategy {
+            QueryStrategy::Linear => FilterStage::PreSelection,
+            QueryStrategy::Ivf | QueryStrategy::Segments => FilterStage::Probed,
+        });
+        QueryExplanation { plan, filter_stage }
+    }
+
+    // choix du chemin de recherche par coût estimé, en distances calculées : exhaustif
+    // sur les vecteurs qui passent le filtre, ou centroïdes + clusters sondés par
+    // l'index. Sélection du filtre rendue pour être reprise par la recherche exhaustive
+    fn plan_query(
+        &self,
+        n_results: usize,
+        where_filter: Option<&WhereFilter>,
+        options: &QueryOptions,
+    ) -> (QueryPlan, Option<Vec<&str>>) {
+        let linear = |reason, linear_cost, index_cost| {
+            QueryPlan { strategy: QueryStrategy::Linear, reason, linear_cost, index_cost }
+        };
+        if options.exact {
+            return (linear(PlanReason::Exact, self.vectors.len(), None), None);
+        }
+        if options.direction == SortDirection::Desc {
+            return (linear(PlanReason::Descending, self.vectors.len(), None), None);
+        }
+        if self.is_segmented() {
+            let plan = QueryPlan {
+                strategy: QueryStrategy::Segments,
+                reason: PlanReason::Segmented,
+                linear_cost: self.vectors.len(),
+                index_cost: None,
+            };
+            return (plan, None);
+        }
+        let Some(ivf) = self.ivf_index().filter(|_| self.config.use_ivf) else {
+            return (linear(PlanReason::NoIndex, self.vectors.len(), None), None);
+        };
+
+        let selected = where_filter.map(|filter| self.select_ids(filter));
+        let linear_cost = selected.as_ref().map_or(self.vectors.len(), Vec::len);
+        let index_cost = self.index_cost(ivf, n_results);
+        let n_centroids = ivf.centroids.len();
+        let reason = if self.rebuild_due() {
+            // écritures au-delà du seuil de rebuild absentes des listes
+            PlanReason::StaleIndex
+        } else if linear_cost <= index_cost {
+            PlanReason::CheaperThanIndex
+        } else if selected.is_some()
+            && self.config.index_params.refine_factor.is_none()
+            && linear_cost.saturating_mul(self.n_probe(ivf).min(n_centroids)) < n_results.saturating_mul(n_centroids)
+        {
+            // moins de n_results vecteurs attendus dans les clusters sondés
+            PlanReason::SelectiveFilter
+        } else {
+            let plan = QueryPlan {
+                strategy: QueryStrategy::Ivf,
+                reason: PlanReason::CheaperThanLinear,
+                linear_cost,
+                index_cost: Some(index_cost),
+            };
+            return (plan, None);
+        };
+        (linear(reason, linear_cost, Some(index_cost)), selected)
+    }
+
+    // distances calculées par une recherche IVF : centroïdes, puis candidats des
+    // clusters sondés (taille moyenne des listes) ; un code PQ coûte code_len lectures
+    // de table au lieu de `dimension` produits, plus le reclassement exact
+    fn index_cost(&self, ivf: &IVFIndex, n_results: usize) -> usize {
+        let n_centroids = ivf.centroids.len().max(1);
+        let probed = (self.vectors.len() * self.n_probe(ivf).min(n_centroids)).div_ceil(n_centroids);
+        let scored = match ivf.pq {
+            Some(ref pq) => {
+                let rerank = self.config.index_params.pq_rerank.map_or(0, |f| n_results.saturating_mul(f));
+                (probed * pq.code_len()).div_ceil(self.config.dimension) + rerank.min(probed)
+            }
+            None => probed,
+        };
+        n_centroids + scored
+    }
+
+    // recherche late_interaction : les `candidates` plus proches de la moyenne des
+    // tokens de la requête, reclassés par max-sim ; distance = 1 - max-sim
+    pub fn query_tokens(
+        &mut self,
+        query_tokens: &[Vec<f32>],
+        n_results: usize,
+        candidates: usize,
+        where_filter: Option<&WhereFilter>,
+        options: &QueryOptions,
+    ) -> Result<Vec<SearchResult>> {
+        if !self.config.late_interaction {
+            return Err(VectorDbError::InvalidConfig(format!(
+                "collection '{}' does not use late interaction",
+                self.config.name
+            )));
+        }
+        let query = normalize_tokens(query_tokens.to_vec(), self.config.dimension)?;
+        // seuil appliqué au score max-sim, pas à la distance du premier étage
+        let first_stage = QueryOptions { max_distance: None, ..options.clone() };
+        let mut results =
+            self.query_with_options(&pool(&query), candidates.max(n_results), where_filter, &first_stage)?;
+        for result in results.iter_mut() {
+            let score = match self.tokens.get(&result.id) {
+                Some(document) => max_sim(&query, document),
+                // sans matrice (fichier de tokens absent) : score du premier étage
+                None => 1.0 - result.distance,
+            };
+            result.distance = 1.0 - score;
+        }
+        // tri stable : à égalité, l'ordre du premier étage
+        results.sort_by(|a, b| a.distance.total_cmp(&b.distance));
+        results.retain(|r| options.within(r.distance));
+        results.truncate(n_results);
+        Ok(results)
+    }
+
+    // entrées multi-vecteurs (matrice par id d'une collection late_interaction)
+    // interrogées avec un seul embedding, un résultat par id noté selon `aggregation` ;
+    // distance = 1 - score. En exhaustif ou sans index IVF, toutes les entrées filtrées
+    // sont notées ; sinon les `candidates` que l'index trouve les plus proches de la
+    // moyenne de leurs vecteurs sont reclassés
+    pub fn query_multi_vector(
+        &mut self,
+        query_embedding: &[f32],
+        n_results: usize,
+        candidates: usize,
+        aggregation: VectorAggregation,
+        where_filter: Option<&WhereFilter>,
+        options: &QueryOptions,
+    ) -> Result<Vec<SearchResult>> {
+        if !self.config.late_interaction {
+            return Err(VectorDbError::InvalidConfig(format!(
+                "collection '{}' has no multi-vector entries (late_interaction)",
+                self.config.name
+            )));
+        }
+        let start = Instant::now();
+        self.check_query(query_embedding, where_filter)?;
+        let mut query = query_embedding.to_vec();
+        normalize_l2(&mut query);
+
+        let results = if options.exact || self.ivf_index.is_none() {
+            let entries: Vec<&VectorEntry> = match where_filter {
+                Some(filter) => self.select_ids(filter).into_iter().filter_map(|id| self.vectors.get(id)).collect(),
+                None => self.vectors.values().collect(),
+            };
+            let scored = entries
+                .into_par_iter()
+                .map(|entry| {
+                    let score = match self.tokens.get(&entry.id) {
+                        Some(vectors) => aggregation.score(&query, vectors),
+                        // sans matrice : similarité à l'embedding de l'entrée
+                        None => self.embedding_of(entry).map_or(0.0, |e| dot_product(&query, &e)),
+                    };
+                    (1.0 - score, entry)
+                })
+                .collect();
+            rank_candidates(&query, &[], scored, n_results, options)
+        } else {
+            // premier étage par l'index, sans compter de requête : une seule pour l'appel
+            self.maybe_rebuild();
+            let first_stage = QueryOptions { max_distance: None, ..options.clone() };
+            let mut results = self.run_query(&query, candidates.max(n_results), where_filter, &first_stage)?;
+            for result in results.iter_mut() {
+                if let Some(vectors) = self.tokens.get(&result.id) {
+                    result.distance = 1.0 - aggregation.score(&query, vectors);
+                }
+            }
+            results.sort_by(|a, b| options.compare((a.distance, &a.metadata), (b.distance, &b.metadata)));
+            results.retain(|r| options.within(r.distance));
+            results.truncate(n_results);
+            results
+        };
+
+        self.record_query(start.elapsed(), n_results, results.len(), where_filter.is_some(), options.exact);
+        Ok(results)
+    }
+
+    pub(crate) fn token_embeddings(&self) -> &HashMap<String, TokenMatrix> {
+        &self.tokens
+    }
+
+    // matrices relues du stockage, ids inconnus ignorés
+    pub(crate) fn set_token_embeddings(&mut self, tokens: Vec<(String, TokenMatrix)>) {
+        self.tokens = tokens.into_iter().filter(|(id, _)| self.vectors.contains_key(id)).collect();
+    }
+
+    // distances pour une liste d'ids explicite, sans passer par l'index : re-ranking
+    // de candidats venus d'ailleurs (recherche plein texte...). Les ids inconnus
+    // sont ignorés, les doublons comptés une fois
+    pub fn score_ids(&self, query_embedding: &[f32], ids: &[String]) -> Result<Vec<SearchResult>> {
+        if query_embedding.len() != self.config.dimension {
+            return Err(VectorDbError::DimensionMismatch {
+                expected: self.config.dimension,
+                actual: query_embedding.len(),
+            });
+        }
+
+        let mut normalized_query = query_embedding.to_vec();
+        normalize_l2(&mut normalized_query);
+
+        let mut seen = HashSet::new();
+        let candidates: Vec<&VectorEntry> = ids
+            .iter()
+            .filter(|id| seen.insert(id.as_str()))
+            .filter_map(|id| self.vectors.get(id))
+            .collect();
+
+        let n = candidates.len();
+        self.rank(&normalized_query, candidates, n, &QueryOptions::default())
+    }
+
+    // pour chaque résultat : chemin qui l'a produit, cluster IVF et rang de sondage,
+    // produit scalaire avec la requête non normalisée
+    fn explain_hits(
+        &self,
+        results: &mut [SearchResult],
+        raw_query: &[f32],
+        normalized_query: &[f32],
+        strategy: QueryStrategy,
+    ) {
+        let via_ivf = strategy == QueryStrategy::Ivf;
+        // id -> (cluster, rang du cluster par distance à la requête) ; tous les clusters,
+        // le sondage pouvant être élargi par refine_factor
+        let mut probed: HashMap<&str, (usize, usize)> = HashMap::new();
+        if let Some(ivf) = self.ivf_index().filter(|_| via_ivf) {
+            for (rank, cluster) in ivf.ranked_clusters(normalized_query, usize::MAX).into_iter().enumerate() {
+                for id in &ivf.inverted_lists[cluster] {
+                    probed.insert(id.as_str(), (cluster, rank));
+                }
+            }
+        }
+
+        for result in results.iter_mut() {
+            let (source, cluster, probe_rank) = match probed.get(result.id.as_str()) {
+                Some(&(cluster, rank)) => (HitSource::Ivf, Some(cluster), Some(rank)),
+                // écrit pendant un rebuild, parcouru hors index
+                None if via_ivf => (HitSource::Pending, None, None),
+                None => (HitSource::Linear, None, None),
+            };
+            let dot = self.vectors
+                .get(&result.id)
+                .and_then(|e| self.embedding_of(e).ok())
+                .map(|embedding| dot_product(raw_query, &embedding))
+                .unwrap_or(0.0);
+            result.explain = Some(HitExplanation {
+                source,
+                cluster,
+                probe_rank,
+                dot_product: dot,
+            });
+        }
+    }
+
+    fn query_linear(
+        &self,
+        normalized_query: &[f32],
+        n_results: usize,
+        where_filter: Option<&WhereFilter>,
+        selected: Option<Vec<&str>>,
+        options: &QueryOptions,
+    ) -> Result<Vec<SearchResult>> {
+        // filtrer d'abord si nécessaire, sauf sélection déjà faite par le planificateur
+        let selected = selected.or_else(|| where_filter.map(|filter| self.select_ids(filter)));
+        let entries_to_search: Vec<&VectorEntry> = match selected {
+            Some(ids) => ids.into_iter().filter_map(|id| self.vectors.get(id)).collect(),
+            None => self.vectors.values().collect(),
+        };
+
+        self.rank(normalized_query, entries_to_search, n_results, options)
+    }
+
+    fn query_with_ivf(
+        &self,
+        normalized_query: &[f32],
+        n_results: usize,
+        where_filter: Option<&WhereFilter>,
+        options: &QueryOptions,
+    ) -> Result<Vec<SearchResult>> {
+        let ivf = self.ivf_index.as_ref().unwrap();
+        let passes = |entry: &&VectorEntry| where_filter.is_none_or(|f| matches_filter(&entry.metadata, f));
+        let n_probe = self.n_probe(ivf);
+        let min_candidates = self.config.index_params.refine_factor.map_or(0, |f| n_results.saturating_mul(f));
+        let limit = if min_candidates > 0 { usize::MAX } else { n_probe };
+        // IVF-PQ : distances approchées calculées sur les codes, sans lire les vecteurs
+        let table = ivf.pq.as_ref().map(|pq| pq.distance_table(normalized_query));
+
+        let mut candidates: Vec<&VectorEntry> = Vec::new();
+        let mut approximate: Vec<(f32, &VectorEntry)> = Vec::new();
+        for (rank, cluster) in ivf.ranked_clusters(normalized_query, limit).into_iter().enumerate() {
+            if rank >= n_probe && candidates.len() + approximate.len() >= min_candidates {
+                break;
+            }
+            match (&table, ivf.coded_list(cluster)) {
+                (Some(table), Some(coded)) => approximate.extend(coded.filter_map(|(id, code)| {
+                    self.vectors.get(id).filter(passes).map(|entry| (table.distance(code), entry))
+                })),
+                _ => candidates
+                    .extend(ivf.inverted_lists[cluster].iter().filter_map(|id| self.vectors.get(id)).filter(passes)),
+            }
+        }
+
+        // écritures reçues pendant un rebuild : absentes de l'ancien index,
+        // parcourues linéairement jusqu'à l'échange
+        if let Some(ref pending) = self.pending_rebuild {
+            if !pending.added.is_empty() {
+                let listed: HashSet<&str> =
+                    candidates.iter().chain(approximate.iter().map(|(_, e)| e)).map(|e| e.id.as_str()).collect();
+                let extra: Vec<&VectorEntry> = pending.added
+                    .iter()
+                    .filter(|id| !listed.contains(id.as_str()))
+                    .filter_map(|id| self.vectors.get(id))
+                    .filter(passes)
+                    .collect();
+                candidates.extend(extra);
+            }
+        }
+
+        if approximate.is_empty() {
+            return self.rank(normalized_query, candidates, n_results, options);
+        }
+        match self.config.index_params.pq_rerank {
+            // les meilleurs par distance approchée reclassés en distance exacte
+            Some(factor) => {
+                let shortlist =
+                    top_k_by(approximate, n_results.saturating_mul(factor), |a, b| a.0.total_cmp(&b.0));
+                candidates.extend(shortlist.into_iter().map(|(_, entry)| entry));
+                self.rank(normalized_query, candidates, n_results, options)
+            }
+            // distances approchées renvoyées telles quelles ; les écritures en attente,
+            // toujours en mémoire, en distance exacte
+            None => Ok(rank_candidates(normalized_query, &candidates, approximate, n_results, options)),
+        }
+    }
+
+    // rank_candidates, les vecteurs sur disque étant lus par listes entières et ceux
+    // des segments en place
+    fn rank(
+        &self,
+        normalized_query: &[f32],
+        candidates: Vec<&VectorEntry>,
+        n_results: usize,
+        options: &QueryOptions,
+    ) -> Result<Vec<SearchResult>> {
+        if self.config.is_binary() {
+            return Ok(self.rank_binary(normalized_query, candidates, n_results, options));
+        }
+        if self.config.precision.is_half() {
+            return Ok(self.rank_half(normalized_query, candidates, n_results, options));
+        }
+        if self.is_segmented() {
+            let (in_segments, in_memory): (Vec<&VectorEntry>, Vec<&VectorEntry>) =
+                candidates.into_iter().partition(|e| e.embedding.is_empty());
+            let scored = self.score_in_segments(normalized_query, &in_segments);
+            return Ok(rank_candidates(normalized_query, &in_memory, scored, n_results, options));
+        }
+        // exact avec les f32 gardés : pas de distance approchée
+        if let Some(sq) = self.config.scalar_quantization.filter(|sq| !(options.exact && sq.rescore.is_some())) {
+            return Ok(self.rank_quantized(normalized_query, candidates, n_results, sq.rescore, options));
+        }
+        let Some(ref disk) = self.disk else {
+            return Ok(rank_candidates(normalized_query, &candidates, Vec::new(), n_results, options));
+        };
+        let (on_disk, in_memory): (Vec<&VectorEntry>, Vec<&VectorEntry>) =
+            candidates.into_iter().partition(|e| e.embedding.is_empty());
+        let scored = score_on_disk(disk, normalized_query, &on_disk)?;
+        Ok(rank_candidates(normalized_query, &in_memory, scored, n_results, options))
+    }
+
+    // distances en entiers sur les vecteurs quantifiés, requête quantifiée une fois ;
+    // avec rescore, les n_results × rescore plus proches reclassés sur leurs f32
+    fn rank_quantized<'a>(
+        &self,
+        normalized_query: &[f32],
+        candidates: Vec<&'a VectorEntry>,
+        n_results: usize,
+        rescore: Option<usize>,
+        options: &QueryOptions,
+    ) -> Vec<SearchResult> {
+        let query = Int8Vector::quantize(normalized_query);
+        let score = |entry: &&'a VectorEntry| self.quantized.get(&entry.id).map(|v| (query.distance(v), *entry));
+        let approximate: Vec<(f32, &'a VectorEntry)> = if candidates.len() >= PARALLEL_MIN_CANDIDATES {
+            candidates.par_iter().filter_map(score).collect()
+        } else {
+            candidates.iter().filter_map(score).collect()
+        };
+        match rescore {
+            Some(factor) if options.direction == SortDirection::Asc => {
+                let cmp = |a: &(f32, &VectorEntry), b: &(f32, &VectorEntry)| a.0.total_cmp(&b.0);
+                let shortlist: Vec<&'a VectorEntry> = top_k_by(approximate, n_results.saturating_mul(factor), cmp)
+                    .into_iter()
+                    .map(|(_, entry)| entry)
+                    .collect();
+                rank_candidates(normalized_query, &shortlist, Vec::new(), n_results, options)
+            }
+            _ => rank_candidates(normalized_query, &[], approximate, n_results, options),
+        }
+    }
+
+    // collection binaire : distance de Hamming (nombre de bits différents) ; la
+    // normalisation ne change pas le signe des composantes, la requête est
+    // empaquetée depuis sa forme normalisée
+    fn rank_binary<'a>(
+        &self,
+        normalized_query: &[f32],
+        candidates: Vec<&'a VectorEntry>,
+        n_results: usize,
+        options: &QueryOptions,
+    ) -> Vec<SearchResult> {
+        let query = BinaryVector::threshold(normalized_query);
+        let score =
+            |entry: &&'a VectorEntry| self.binary.get(&entry.id).map(|v| (query.hamming(v) as f32, *entry));
+        let scored: Vec<(f32, &'a VectorEntry)> = if candidates.len() >= PARALLEL_MIN_CANDIDATES {
+            candidates.par_iter().filter_map(score).collect()
+        } else {
+            candidates.iter().filter_map(score).collect()
+        };
+        rank_candidates(normalized_query, &[], scored, n_results, options)
+    }
+
+    // demi-précision : composantes reconverties en f32 par blocs pendant le produit
+    // scalaire, sans reconstruire les vecteurs
+    fn rank_half<'a>(
+        &self,
+        normalized_query: &[f32],
+        candidates: Vec<&'a VectorEntry>,
+        n_results: usize,
+        options: &QueryOptions,
+    ) -> Vec<SearchResult> {
+        let score = |entry: &&'a VectorEntry| self.half.get(&entry.id).map(|v| (v.distance(normalized_query), *entry));
+        let scored: Vec<(f32, &'a VectorEntry)> = if candidates.len() >= PARALLEL_MIN_CANDIDATES {
+            candidates.par_iter().filter_map(score).collect()
+        } else {
+            candidates.iter().filter_map(score).collect()
+        };
+        rank_candidates(normalized_query, &[], scored, n_results, options)
+    }
+
+    // collection segmentée : les n_probe listes les plus proches de chaque segment,
+    // lignes vivantes seulement, puis toute la memtable
+    fn query_segments(
+        &self,
+        normalized_query: &[f32],
+        n_results: usize,
+        where_filter: Option<&WhereFilter>,
+        options: &QueryOptions,
+    ) -> Result<Vec<SearchResult>> {
+        let passes = |entry: &&VectorEntry| where_filter.is_none_or(|f| matches_filter(&entry.metadata, f));
+        let n_probe = self.config.index_params.n_probe.unwrap_or(DEFAULT_SEGMENT_N_PROBE);
+
+        let scored: Vec<(f32, &VectorEntry)> = self
+            .segments
+            .par_iter()
+            .flat_map_iter(|live| {
+                let segment = &live.segment;
+                segment
+                    .probed_rows(normalized_query, n_probe)
+                    .into_iter()
+                    .flatten()
+                    .filter_map(move |row| {
+                        let id = &segment.ids()[row];
+                        if live.deleted.contains(id) {
+                            return None;
+                        }
+                        self.vectors
+                            .get(id)
+                            .filter(|e| e.embedding.is_empty())
+                            .filter(passes)
+                            .map(|entry| (cosine_distance(normalized_query, segment.vector(row)), entry))
+                    })
+            })
+            .collect();
+        let memtable: Vec<&VectorEntry> =
+            self.memtable.iter().filter_map(|id| self.vectors.get(id)).filter(passes).collect();
+        Ok(rank_candidates(normalized_query, &memtable, scored, n_results, options))
+    }
+
+    // distances des entrées rangées dans un segment, lues en place dans le fichier projeté
+    fn score_in_segments<'a>(&self, normalized_query: &[f32], entries: &[&'a VectorEntry]) -> Vec<(f32, &'a VectorEntry)> {
+        let score = |entry: &&'a VectorEntry| {
+            self.segment_row(&entry.id)
+                .map(|(segment, row)| (cosine_distance(normalized_query, segment.vector(row)), *entry))
+        };
+        if entries.len() >= PARALLEL_MIN_CANDIDATES {
+            entries.par_iter().filter_map(score).collect()
+        } else {
+            entries.iter().filter_map(score).collect()
+        }
+    }
+
+    fn disk_dir(&self) -> Option<PathBuf> {
+        self.disk.as_ref().and_then(|d| d.path().parent()).map(Path::to_path_buf)
+    }
+
+    // range les embeddings dans des listes IVF sur disque (lists.dat de `dir`) et les
+    // libère de la mémoire : seuls centroïdes, ids et métadonnées y restent. Centroïdes
+    // entraînés sur un échantillon, vecteurs écrits cluster par cluster. Sur une
+    // collection déjà sur disque, réentraîne l'index et réécrit le fichier
+    pub fn offload_to_disk(&mut self, dir: &Path) -> Result<()> {
+        let start = Instant::now();
+        self.ensure_writable()?;
+        if !self.config.use_ivf {
+            return Err(VectorDbError::InvalidConfig(
+                "Collection does not use IVF index".to_string(),
+            ));
+        }
+        if self.rebuild_in_progress() {
+            return Err(VectorDbError::RebuildInProgress(self.config.name.clone()));
+        }
+        if self.is_segmented() {
+            return Err(VectorDbError::InvalidConfig(format!(
+                "collection '{}' is segmented: thaw it before moving its lists to disk",
+                self.config.name
+            )));
+        }
+        if self.config.scalar_quantization.is_some() {
+            return Err(VectorDbError::InvalidConfig(format!(
+                "embeddings of '{}' are quantized: lists cannot move to disk",
+                self.config.name
+            )));
+        }
+        if self.config.precision.is_half() {
+            return Err(VectorDbError::InvalidConfig(format!(
+                "embeddings of '{}' are stored in {}: lists cannot move to disk",
+                self.config.name,
+                self.config.precision.name()
+            )));
+        }
+        if self.vectors.is_empty() {
+            return Err(VectorDbError::InvalidConfig(format!("collection '{}' is empty", self.config.name)));
+        }
+
+        // seul l'échantillon d'entraînement est chargé en entier
+        let ids: Vec<&String> = self.vectors.keys().collect();
+        let sample_len = self.config.n_clusters.saturating_mul(OFFLOAD_SAMPLE_PER_CLUSTER).min(ids.len());
+        let sample = rand::seq::index::sample(&mut rand::thread_rng(), ids.len(), sample_len)
+            .into_iter()
+            .map(|i| {
+                let entry = &self.vectors[ids[i]];
+                Ok((entry.id.clone(), self.embedding_of(entry)?.into_owned()))
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        let mut ivf = IVFIndex::new(self.config.n_clusters)
+            .with_coarse_groups(self.config.index_params.coarse_groups)
+            .with_pq(self.config.pq_subspaces);
+        if let Some(n_probe) = self.ivf_index.as_ref().map(|ivf| ivf.n_probe) {
+            ivf = ivf.with_n_probe(n_probe);
+        }
+        ivf.build(&sample);
+        drop(sample);
+
+        // affectation de tous les vecteurs, listes sur disque parcourues bloc par bloc
+        ivf.clear_lists();
+        for entry in self.vectors.values().filter(|e| !e.embedding.is_empty()) {
+            ivf.insert(entry.id.clone(), &entry.embedding);
+        }
+        if let Some(ref disk) = self.disk {
+            let dimension = disk.dimension();
+            for cluster in 0..disk.n_lists() {
+                let block = disk.read_list(cluster)?;
+                for (pos, id) in disk.ids(cluster).iter().enumerate() {
+                    if self.vectors.get(id).is_some_and(|e| e.embedding.is_empty()) {
+                        ivf.insert(id.clone(), &block[pos * dimension..(pos + 1) * dimension]);
+                    }
+                }
+            }
+        }
+
+        let mut writer = DiskListsWriter::create(&dir.join(DISK_LISTS_FILE), self.config.dimension, ivf.centroids.clone())?
+            .with_pq(ivf.pq.clone());
+        for list in &ivf.inverted_lists {
+            let entries = list
+                .iter()
+                .map(|id| Ok((id.clone(), self.embedding_of(&self.vectors[id])?.into_owned())))
+                .collect::<Result<Vec<_>>>()?;
+            writer.write_list(&entries)?;
+        }
+        let disk = writer.finish()?;
+
+        for entry in self.vectors.values_mut() {
+            entry.embedding = Vec::new();
+        }
+        tracing::info!(
+            collection = %self.config.name,
+            vectors = disk.len(),
+            file_bytes = disk.file_bytes(),
+            "IVF lists written to disk"
+        );
+        self.ivf_index = Some(ivf);
+        disk.set_cache_capacity(self.list_cache_bytes);
+        self.disk = Some(disk);
+        self.needs_rebuild = false;
+        self.modifications_count = 0;
+        self.embeddings_dirty = true;
+        self.emit_rebuild(RebuildKind::Disk, start);
+        Ok(())
+    }
+
+    pub fn is_frozen(&self) -> bool {
+        self.frozen
+    }
+
+    // vrai si les vecteurs sont rangés dans des segments (flush_memtable, freeze)
+    pub fn is_segmented(&self) -> bool {
+        !self.segments.is_empty()
+    }
+
+    pub fn segment_infos(&self) -> Vec<SegmentInfo> {
+        self.segments.iter().map(LiveSegment::info).collect()
+    }
+
+    // relit tout le bloc de vecteurs de chaque segment et le compare à sa somme de
+    // contrôle
+    pub fn verify_segments(&self) -> Vec<SegmentInfo> {
+        self.segments
+            .iter()
+            .map(|live| SegmentInfo { checksum_ok: Some(live.segment.verify()), ..live.info() })
+            .collect()
+    }
+
+    // vecteurs hors segments : la memtable, toute la collection avant le premier vidage
+    pub fn memtable_len(&self) -> usize {
+        if self.is_segmented() {
+            self.memtable.len()
+        } else {
+            self.vectors.len()
+        }
+    }
+
+    pub(crate) fn segment_manifest(&self) -> SegmentManifest {
+        SegmentManifest {
+            frozen: self.frozen,
+            segments: self.segments.iter().map(|s| (s.seq, s.deleted.iter().cloned().collect())).collect(),
+        }
+    }
+
+    // fichiers à garder : segments vivants et fusion en cours
+    pub(crate) fn segment_files(&self) -> Vec<String> {
+        self.segments.iter().map(|s| s.seq).chain(self.pending_merge).map(segment_file).collect()
+    }
+
+    // les segments rangent des f32
+    fn ensure_unquantized(&self) -> Result<()> {
+        if self.config.scalar_quantization.is_some() {
+            return Err(VectorDbError::InvalidConfig(format!(
+                "embeddings of '{}' are quantized: segments are not available",
+                self.config.name
+            )));
+        }
+        if self.config.is_binary() {
+            return Err(VectorDbError::InvalidConfig(format!(
+                "embeddings of '{}' are binary: segments are not available",
+                self.config.name
+            )));
+        }
+        if self.config.precision.is_half() {
+            return Err(VectorDbError::InvalidConfig(format!(
+                "embeddings of '{}' are stored in {}: segments are not available",
+                self.config.name,
+                self.config.precision.name()
+            )));
+        }
+        Ok(())
+    }
+
+    // ligne vivante d'un id, cherchée du segment le plus récent au plus ancien
+    fn segment_row(&self, id: &str) -> Option<(&Segment, usize)> {
+        self.segments.iter().rev().find_map(|s| s.live_row(id).map(|row| (&*s.segment, row)))
+    }
+
+    // l'id est réécrit ou supprimé : sa ligne de segment n'est plus vivante
+    fn release_segment_row(&mut self, id: &str) {
+        if let Some(live) = self.segments.iter_mut().rev().find(|s| s.live_row(id).is_some()) {
+            live.deleted.insert(id.to_string());
+        }
+    }
+
+    // ramène dans la memtable une entrée rangée dans un segment, avant de modifier
+    // ses métadonnées : le segment n'est jamais réécrit
+    fn materialize(&mut self, id: &str) -> Result<()> {
+        if !self.is_segmented() || self.vectors.get(id).is_none_or(|e| !e.embedding.is_empty()) {
+            return Ok(());
+        }
+        let embedding = self.embedding_of(&self.vectors[id])?.into_owned();
+        self.release_segment_row(id);
+        self.vectors.get_mut(id).unwrap().embedding = embedding;
+        self.memtable.insert(id.to_string());
+        Ok(())
+    }
+
+    // écrit la memtable dans un nouveau segment de `dir` (segment-NNNNNN.vdb) : index
+    // IVF propre au segment, vecteurs rangés liste par liste. Le premier vidage range
+    // toute la collection, qui passe en mode segmenté : plus d'index global, chaque
+    // requête interroge les segments puis la memtable. None si la memtable est vide
+    pub fn flush_memtable(&mut self, dir: &Path) -> Result<Option<SegmentInfo>> {
+        let start = Instant::now();
+        self.ensure_writable()?;
+        if self.disk.is_some() {
+            return Err(VectorDbError::InvalidConfig(format!(
+                "lists of '{}' are on disk: segments are not available",
+                self.config.name
+            )));
+        }
+        self.ensure_unquantized()?;
+        if self.rebuild_in_progress() {
+            return Err(VectorDbError::RebuildInProgress(self.config.name.clone()));
+        }
+        let ids: Vec<String> = if self.is_segmented() {
+            self.memtable.iter().cloned().collect()
+        } else {
+            self.vectors.keys().cloned().collect()
+        };
+        if ids.is_empty() {
+            return Ok(None);
+        }
+
+        let seq = self.next_segment_seq;
+        let segment = self.write_segment(dir, seq, ids.clone())?;
+        self.next_segment_seq += 1;
+        for id in &ids {
+            if let Some(entry) = self.vectors.get_mut(id) {
+                entry.embedding = Vec::new();
+            }
+        }
+        let live = LiveSegment { seq, segment: Arc::new(segment), deleted: HashSet::new() };
+        let info = live.info();
+        tracing::info!(
+            collection = %self.config.name,
+            segment = seq,
+            vectors = info.vectors,
+            elapsed_ms = start.elapsed().as_secs_f64() * 1000.0,
+            "Memtable flushed to segment"
+        );
+        self.segments.push(live);
+        self.memtable.clear();
+        self.enter_segmented_mode();
+        self.columns.compact();
+        self.embeddings_dirty = true;
+        Ok(Some(info))
+    }
+
+    // segment `seq` de `dir` avec les entrées `ids`, tokens compris
+    fn write_segment(&self, dir: &Path, seq: u64, ids: Vec<String>) -> Result<Segment> {
+        let n_clusters = if self.config.use_ivf { self.config.n_clusters } else { 0 };
+        let tokens = ids.iter().filter_map(|id| self.tokens.get(id).map(|t| (id.clone(), t.clone()))).collect();
+        write_segment(
+            &dir.join(segment_file(seq)),
+            self.config.dimension,
+            n_clusters,
+            ids,
+            |id| self.embedding_of(&self.vectors[id]),
+            |id| &self.vectors[id].metadata,
+            tokens,
+        )
+    }
+
+    // fige la collection dans un seul segment immuable de `dir` : les embeddings
+    // quittent la mémoire, lus ensuite dans le fichier projeté ; ajouts, mises à jour
+    // et suppressions sont refusés (Frozen) jusqu'à thaw
+    pub fn freeze(&mut self, dir: &Path) -> Result<()> {
+        let start = Instant::now();
+        self.ensure_writable()?;
+        self.ensure_unquantized()?;
+        if self.rebuild_in_progress() {
+            return Err(VectorDbError::RebuildInProgress(self.config.name.clone()));
+        }
+
+        let seq = self.next_segment_seq;
+        let segment = self.write_segment(dir, seq, self.vectors.keys().cloned().collect())?;
+        self.next_segment_seq += 1;
+        for entry in self.vectors.values_mut() {
+            entry.embedding = Vec::new();
+        }
+        tracing::info!(
+            collection = %self.config.name,
+            vectors = segment.len(),
+            file_bytes = segment.file_bytes(),
+            elapsed_ms = start.elapsed().as_secs_f64() * 1000.0,
+            "Collection frozen into segment"
+        );
+        // les segments précédents et les listes sur disque sont remplacés
+        self.segments = vec![LiveSegment { seq, segment: Arc::new(segment), deleted: HashSet::new() }];
+        self.memtable.clear();
+        self.disk = None;
+        self.frozen = true;
+        self.enter_segmented_mode();
+        self.columns.compact();
+        self.embeddings_dirty = true;
+        Ok(())
+    }
+
+    // ramène tous les vecteurs en mémoire et quitte le mode segmenté (collection figée
+    // ou non) ; les fichiers sont retirés à la sauvegarde suivante et l'index IVF
+    // global est reconstruit
+    pub fn thaw(&mut self) -> Result<()> {
+        if self.read_only {
+            return Err(VectorDbError::ReadOnly(self.config.name.clone()));
+        }
+        if !self.is_segmented() {
+            return Err(VectorDbError::InvalidConfig(format!("collection '{}' has no segments", self.config.name)));
+        }
+        let embeddings = self
+            .vectors
+            .values()
+            .filter(|e| e.embedding.is_empty())
+            .map(|e| Ok((e.id.clone(), self.embedding_of(e)?.into_owned())))
+            .collect::<Result<Vec<_>>>()?;
+        for (id, embedding) in embeddings {
+            self.vectors.get_mut(&id).unwrap().embedding = embedding;
+        }
+        self.segments.clear();
+        self.memtable.clear();
+        self.frozen = false;
+        if self.config.use_ivf {
+            self.ivf_index = Some(
+                IVFIndex::new(self.config.n_clusters)
+                    .with_coarse_groups(self.config.index_params.coarse_groups)
+                    .with_pq(self.config.pq_subspaces),
+            );
+            self.needs_rebuild = true;
+        }
+        self.embeddings_dirty = true;
+        Ok(())
+    }
+
+    // fusion en deux temps, comme les rebuilds : plan_merge choisit les segments
+    // (merge_candidates) et capture leurs lignes vivantes sous verrou, MergeJob::run
+    // écrit le segment fusionné hors verrou, finish_merge le met à la place des sources
+    pub fn plan_merge(&mut self, dir: &Path) -> Option<MergeJob> {
+        if self.frozen || self.read_only || self.pending_merge.is_some() {
+            return None;
+        }
+        let picked = merge_candidates(&self.segments);
+        if picked.is_empty() {
+            return None;
+        }
+        Some(self.merge_job(dir, picked))
+    }
+
+    // lignes vivantes des segments `picked`, capturées pour MergeJob::run
+    fn merge_job(&mut self, dir: &Path, picked: Vec<usize>) -> MergeJob {
+        let sources: Vec<LiveSegment> = picked.into_iter().map(|idx| self.segments[idx].clone()).collect();
+        let mut metadata = HashMap::new();
+        let mut tokens = Vec::new();
+        for source in &sources {
+            for id in source.segment.ids().iter().filter(|id| !source.deleted.contains(*id)) {
+                if let Some(entry) = self.vectors.get(id) {
+                    metadata.insert(id.clone(), entry.metadata.clone());
+                }
+                if let Some(matrix) = self.tokens.get(id) {
+                    tokens.push((id.clone(), matrix.clone()));
+                }
+            }
+        }
+        let seq = self.next_segment_seq;
+        self.next_segment_seq += 1;
+        self.pending_merge = Some(seq);
+        MergeJob {
+            path: dir.join(segment_file(seq)),
+            seq,
+            dimension: self.config.dimension,
+            n_clusters: if self.config.use_ivf { self.config.n_clusters } else { 0 },
+            sources,
+            metadata,
+            tokens,
+        }
+    }
+
+    // réécrit tout de suite, sans leurs lignes supprimées, les segments où l'un des
+    // `ids` a une ligne morte : sans cela ses vecteurs et métadonnées restent lisibles
+    // dans le fichier jusqu'à une fusion. Une fusion en cours sur ces segments sera
+    // écartée par finish_merge ; renvoie le nombre de segments réécrits
+    fn rewrite_segments(&mut self, dir: &Path, ids: &HashSet<String>) -> Result<usize> {
+        let picked: Vec<usize> = self
+            .segments
+            .iter()
+            .enumerate()
+            .filter(|(_, live)| live.deleted.iter().any(|id| ids.contains(id)))
+            .map(|(idx, _)| idx)
+            .collect();
+        if picked.is_empty() {
+            return Ok(0);
+        }
+        let rewritten = picked.len();
+        let merged = match self.merge_job(dir, picked).run() {
+            Ok(merged) => merged,
+            Err(e) => {
+                self.abort_merge();
+                return Err(e);
+            }
+        };
+        self.finish_merge(merged);
+        Ok(rewritten)
+    }
+
+    // false si les sources ont changé entre-temps (thaw, freeze, rechargement) : le
+    // segment fusionné est alors ignoré, son fichier retiré à la sauvegarde suivante
+    pub fn finish_merge(&mut self, merged: MergedSegment) -> bool {
+        // collection rechargée entre-temps : la fusion ne lui appartient pas
+        if self.pending_merge.take() != Some(merged.seq) {
+            return false;
+        }
+        let positions: Option<Vec<usize>> = merged
+            .sources
+            .iter()
+            .map(|source| self.segments.iter().position(|s| s.seq == source.seq))
+            .collect();
+        let Some(positions) = positions.filter(|_| !self.frozen) else {
+            return false;
+        };
+
+        // lignes supprimées ou réécrites pendant la fusion
+        let mut deleted = HashSet::new();
+        for (source, &pos) in merged.sources.iter().zip(&positions) {
+            deleted.extend(self.segments[pos].deleted.difference(&source.deleted).cloned());
+        }
+        deleted.retain(|id| merged.segment.row(id).is_some());
+
+        let at = *positions.iter().min().unwrap();
+        let live = LiveSegment { seq: merged.seq, segment: Arc::new(merged.segment), deleted };
+        tracing::info!(
+            collection = %self.config.name,
+            segment = live.seq,
+            sources = positions.len(),
+            vectors = live.live_len(),
+            "Segments merged"
+        );
+        // un segment fusionné vide disparaît, sauf s'il est le dernier
+        let keep = live.live_len() > 0 || positions.len() == self.segments.len();
+        let mut kept = Vec::with_capacity(self.segments.len());
+        for (pos, segment) in std::mem::take(&mut self.segments).into_iter().enumerate() {
+            if pos == at && keep {
+                kept.push(live.clone());
+            }
+            if !positions.contains(&pos) {
+                kept.push(segment);
+            }
+        }
+        self.segments = kept;
+        self.embeddings_dirty = true;
+        true
+    }
+
+    pub fn abort_merge(&mut self) {
+        self.pending_merge = None;
+    }
+}
+
+// représentations compactes relues du stockage, passées à from_parts
+#[derive(Default)]
+pub(crate) struct EncodedVectors {
+    pub quantized: Vec<(String, Int8Vector)>,
+    pub binary: Vec<(String, BinaryVector)>,
+    pub half: Vec<(String, HalfVector)>,
+}
+
+// vecteurs par cluster pour entraîner les centroïdes d'offload_to_disk
+const OFFLOAD_SAMPLE_PER_CLUSTER: usize = 256;
+
+// embedding d'une entrée hors disque et hors segment : f32 en mémoire, ou
+// reconstruit depuis sa version quantifiée ou en demi-précision
+fn memory_embedding<'a>(
+    quantized: &'a HashMap<String, Int8Vector>,
+    half: &'a HashMap<String, HalfVector>,
+    entry: &'a VectorEntry,
+) -> Cow<'a, [f32]> {
+    if !entry.embedding.is_empty() {
+        return Cow::Borrowed(&entry.embedding);
+    }
+    match (quantized.get(&entry.id), half.get(&entry.id)) {
+        (Some(vector), _) => Cow::Owned(vector.dequantize()),
+        (None, Some(vector)) => Cow::Owned(vector.decode()),
+        (None, None) => Cow::Borrowed(&entry.embedding),
+    }
+}
+
+// retrait d'une entrée des listes inversées ; une entrée sur disque est dans la
+// liste de son cluster dans le fichier
+fn unlist(ivf: &mut IVFIndex, disk: Option<&DiskLists>, entry: &VectorEntry) {
+    match disk.and_then(|d| d.location(&entry.id)) {
+        Some((cluster, _)) if entry.embedding.is_empty() => ivf.remove_from(cluster, &entry.id),
+        // embedding quantifié seulement : parcours de toutes les listes
+        _ if entry.embedding.is_empty() => ivf.remove(&entry.id),
+        _ => ivf.remove_near(&entry.id, &entry.embedding),
+    }
+}
+
+// distances des entrées dont l'embedding est sur disque : une lecture par liste
+// concernée, listes lues en parallèle
+fn score_on_disk<'a>(
+    disk: &DiskLists,
+    normalized_query: &[f32],
+    entries: &[&'a VectorEntry],
+) -> Result<Vec<(f32, &'a VectorEntry)>> {
+    let mut by_cluster: HashMap<usize, Vec<(usize, &'a VectorEntry)>> = HashMap::new();
+    for entry in entries {
+        if let Some((cluster, pos)) = disk.location(&entry.id) {
+            by_cluster.entry(cluster).or_default().push((pos, *entry));
+        }
+    }
+
+    let dimension = disk.dimension();
+    let scored = by_cluster
+        .into_par_iter()
+        .map(|(cluster, wanted)| {
+            let block = disk.cached_list(cluster)?;
+            Ok(wanted
+                .into_iter()
+                .map(|(pos, entry)| {
+                    (cosine_distance(normalized_query, &block[pos * dimension..(pos + 1) * dimension]), entry)
+                })
+                .collect::<Vec<_>>())
+        })
+        .collect::<Result<Vec<_>>>()?;
+    Ok(scored.into_iter().flatten().collect())
+}
+
+// en dessous, le coût de rayon dépasse le gain
+const PARALLEL_MIN_CANDIDATES: usize = 64;
+
+// candidats par appel de cosine_distances dans rank_candidates
+const DISTANCE_BATCH: usize = 32;
+
+// vecteurs échantillonnés par total_matched en CountMode::Estimate
+const COUNT_SAMPLE_SIZE: usize = 2_000;
+
+// listes sondées par segment sans index_params.n_probe
+const DEFAULT_SEGMENT_N_PROBE: usize = 4;
+
+// classement commun à tous les chemins de recherche : distances, sélection des
+// n_results meilleurs par tas borné, puis construction des SearchResult pour les
+// seuls retenus (pas de clone de métadonnées pour les candidats écartés).
+// `scored` : candidats dont la distance est déjà calculée (vecteurs sur disque)
+pub(crate) fn rank_candidates<'a>(
+    normalized_query: &[f32],
+    candidates: &[&'a VectorEntry],
+    scored: Vec<(f32, &'a VectorEntry)>,
+    n_results: usize,
+    options: &QueryOptions,
+) -> Vec<SearchResult> {
+    let cmp = |a: &(f32, &VectorEntry), b: &(f32, &VectorEntry)| {
+        options.compare((a.0, &a.1.metadata), (b.0, &b.1.metadata))
+    };
+
+    // distances par lots : la requête est chargée une fois pour plusieurs vecteurs
+    let push_chunk = |top: &mut TopK<(f32, &'a VectorEntry), _>, chunk: &[&'a VectorEntry]| {
+        let mut vectors: [&[f32]; DISTANCE_BATCH] = [&[]; DISTANCE_BATCH];
+        let mut distances = [0.0; DISTANCE_BATCH];
+        for (v, entry) in vectors.iter_mut().zip(chunk) {
+            *v = &entry.embedding;
+        }
+        let n = chunk.len();
+        cosine_distances(normalized_query, &vectors[..n], &mut distances[..n]);
+        for (distance, entry) in distances.into_iter().zip(chunk) {
+            if options.within(distance) {
+                top.push((distance, *entry));
+            }
+        }
+    };
+    let mut top = if candidates.len() >= PARALLEL_MIN_CANDIDATES {
+        candidates
+            .par_chunks(DISTANCE_BATCH)
+            .fold(
+                || TopK::new(n_results, cmp),
+                |mut top, chunk| {
+                    push_chunk(&mut top, chunk);
+                    top
+                },
+            )
+            .reduce(|| TopK::new(n_results, cmp), TopK::merge)
+    } else {
+        let mut top = TopK::new(n_results, cmp);
+        for chunk in candidates.chunks(DISTANCE_BATCH) {
+            push_chunk(&mut top, chunk);
+        }
+        top
+    };
+    for item in scored.into_iter().filter(|(distance, _)| options.within(*distance)) {
+        top.push(item);
+    }
+
+    top.into_sorted_vec()
+        .into_iter()
+        .map(|(distance, entry)| SearchResult {
+            id: entry.id.clone(),
+            distance,
+            metadata: entry.metadata.clone(),
+            explain: None,
+            rerank_score: None,
+        })
+        .collect()
+}
+
+// nombre d'ids d'une anomalie de verify_index gardés dans le rapport
+const REPORT_SAMPLE_LEN: usize = 100;
+
+fn report_sample(mut ids: Vec<String>) -> (usize, Vec<String>) {
+    let n = ids.len();
+    ids.sort();
+    ids.truncate(REPORT_SAMPLE_LEN);
+    (n, ids)
+}
+
+// le namespace _system est alimenté par la collection, jamais par l'utilisateur
+fn check_reserved_keys(metadata: &HashMap<String, MetadataValue>) -> Result<()> {
+    match metadata.keys().find(|k| is_reserved_key(k)) {
+        Some(key) => Err(VectorDbError::ReservedMetadataKey(key.clone())),
+        None => Ok(()),
+    }
+}
+
+// ce qu'un effacement a retiré d'une collection (voir erase_where)
+#[derive(Debug, Default)]
+pub struct Erasure {
+    pub deleted: usize,
+    // segments réécrits sans les lignes effacées
+    pub segments_rewritten: usize,
+    // _system.content_hash des entrées effacées, pour purger le journal des requêtes
+    pub content_hashes: HashSet<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GetResult {
+    pub ids: Vec<String>,
+    pub embeddings: Option<Vec<Vec<f32>>>,
+    #[serde(with = "crate::vector::json_metadata_list")]
+    pub metadatas: Option<Vec<HashMap<String, MetadataValue>>>,
+}
+
+// chemin de recherche ayant produit un résultat
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HitSource {
+    Ivf,
+    Linear,
+    // écrit pendant un rebuild, absent de l'index courant
+    Pending,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HitExplanation {
+    pub source: HitSource,
+    pub cluster: Option<usize>,
+    // 0 = cluster le plus proche de la requête
+    pub probe_rank: Option<usize>,
+    // requête non normalisée · embedding stocké (normalisé)
+    pub dot_product: f32,
+}
+
+// moment où le filtre écarte les documents
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FilterStage {
+    // recherche exhaustive : ids qui passent le filtre sélectionnés avant toute
+    // distance, tous les documents correspondants sont classés
+    PreSelection,
+    // index ou segments : filtre évalué sur les candidats des clusters sondés, un
+    // document correspondant hors de ces clusters n'est pas vu
+    Probed,
+}
+
+// décision du planificateur pour une requête, rendue une fois avec ses résultats
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QueryExplanation {
+    pub plan: QueryPlan,
+    // None sans filtre
+    pub filter_stage: Option<FilterStage>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum QueryStrategy {
+    Linear,
+    Ivf,
+    Segments,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PlanReason {
+    // QueryOptions::exact
+    Exact,
+    // plus lointains d'abord : l'index ne sonde que les clusters proches
+    Descending,
+    Segmented,
+    // pas d'IVF ou index pas encore construit
+    NoIndex,
+    // rebuild dû, les dernières écritures manquent aux listes
+    StaleIndex,
+    // pas plus de vecteurs (filtrés) que de distances à calculer par l'index
+    CheaperThanIndex,
+    // filtre assez sélectif pour laisser les clusters sondés presque vides
+    SelectiveFilter,
+    CheaperThanLinear,
+}
+
+// choix entre recherche exhaustive et index pour une requête, coûts en distances
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QueryPlan {
+    pub strategy: QueryStrategy,
+    pub reason: PlanReason,
+    // vecteurs qui passent le filtre (tous sans filtre)
+    pub linear_cost: usize,
+    // centroïdes + candidats estimés des clusters sondés ; None si l'index n'est pas envisagé
+    pub index_cost: Option<usize>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SearchResult {
+    pub id: String,
+    pub distance: f32,
+    #[serde(with = "crate::vector::json_metadata")]
+    pub metadata: HashMap<String, MetadataValue>,
+    // renseigné avec QueryOptions::explain_hits
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub explain: Option<HitExplanation>,
+    // score du reranker (voir rerank), plus grand = plus pertinent
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rerank_score: Option<f32>,
+}
+
+// part des résultats exhaustifs `truth` retrouvés dans `results` ; 1 si `truth` est vide
+pub fn recall(results: &[SearchResult], truth: &[SearchResult]) -> f64 {
+    if truth.is_empty() {
+        return 1.0;
+    }
+    let expected: HashSet<&str> = truth.iter().map(|r| r.id.as_str()).collect();
+    let found = results.iter().filter(|r| expected.contains(r.id.as_str())).count();
+    found as f64 / expected.len() as f64
+}
+
+// résultat de Collection::compare_query
+#[derive(Debug, Clone, Serialize)]
+pub struct QueryComparison {
+    // par l'index (IVF), ou exhaustifs si la collection n'en a pas
+    pub results: Vec<SearchResult>,
+    pub exact: Vec<SearchResult>,
+    // ids présents dans les deux listes
+    pub overlap: usize,
+    pub recall: f64,
+    pub index_ms: f64,
+    pub exact_ms: f64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct IndexInfo {
+    pub is_built: bool,
+    pub n_clusters: usize,
+    pub n_centroids: usize,
+    pub needs_rebuild: bool,
+    pub rebuilding: bool,
+    // valeur effective : config.index_params.n_probe, sinon celle de l'index
+    #[serde(default)]
+    pub n_probe: usize,
+    #[serde(default)]
+    pub refine_factor: Option<usize>,
+    // groupes effectivement construits du second niveau (voir IndexParams::coarse_groups)
+    #[serde(default)]
+    pub coarse_groups: Option<usize>,
+    #[serde(default)]
+    pub disk: Option<DiskListsInfo>,
+    // quantificateur entraîné (IVF-PQ) ; None tant que l'index n'est pas construit
+    #[serde(default)]
+    pub pq: Option<PqInfo>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PqInfo {
+    pub subspaces: usize,
+    // centroïdes par sous-espace, moins de 256 sur un petit échantillon
+    pub n_centroids: usize,
+    pub code_bytes: usize,
+    pub codebook_bytes: usize,
+    pub rerank: Option<usize>,
+}
+
+// listes IVF sur disque (Collection::offload_to_disk)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DiskListsInfo {
+    pub path: String,
+    // vecteurs dont l'embedding n'est que sur disque
+    pub vectors: usize,
+    pub file_bytes: u64,
+    // listes gardées en mémoire et taux de succès depuis le chargement
+    #[serde(default)]
+    pub cache: ListCacheStats,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ClusterAssignment {
+    pub cluster: usize,
+    // rang par distance au vecteur, 0 = plus proche
+    pub rank: usize,
+    pub distance: f32,
+    pub list_size: usize,
+    // sondé par une requête avec ce vecteur (rang < n_probe), hors refine_factor
+    pub probed: bool,
+}
+
+// rapport de assign_diagnostics
+#[derive(Debug, Clone, Serialize)]
+pub struct AssignReport {
+    pub n_probe: usize,
+    pub n_centroids: usize,
+    pub nearest: Vec<ClusterAssignment>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    // clusters dont la liste contient `id` ; vide : vecteur non indexé
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub listed_in: Option<Vec<usize>>,
+}
+
+// rapport de verify_index / repair_index ; les listes d'ids sont tronquées
+// à REPORT_SAMPLE_LEN, les compteurs n_* sont exacts
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct IndexReport {
+    // false si l'index n'a jamais été construit : rien à vérifier
+    pub built: bool,
+    pub vectors: usize,
+    pub indexed_ids: usize,
+    pub n_stale: usize,
+    pub stale_ids: Vec<String>,
+    pub n_missing: usize,
+    pub missing_ids: Vec<String>,
+    pub n_duplicates: usize,
+    pub duplicate_ids: Vec<String>,
+    pub centroid_errors: Vec<String>,
+    pub needs_rebuild: bool,
+    pub consistent: bool,
+    pub repaired: bool,
+}
+
+// écart de norme toléré par verify_norms / renormalize sans tolérance explicite
+pub const DEFAULT_NORM_TOLERANCE: f32 = 1e-3;
+
+// rapport de verify_norms / renormalize ; listes d'ids tronquées à REPORT_SAMPLE_LEN
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct NormReport {
+    pub tolerance: f32,
+    pub checked: usize,
+    // embeddings hors mémoire (segments, listes sur disque), quantifiés ou binaires
+    pub skipped: usize,
+    pub n_denormalized: usize,
+    pub denormalized_ids: Vec<String>,
+    // plus grand écart |norme - 1| parmi les vecteurs en écart
+    pub max_deviation: f32,
+    // vecteurs nuls, non normalisables
+    pub n_zero: usize,
+    pub zero_ids: Vec<String>,
+    pub renormalized: usize,
+}
+
+// état de l'index IVF, pour décider quand appeler /rebuild
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case", tag = "state")]
+pub enum IndexStatus {
+    // collection sans IVF
+    #[default]
+    Disabled,
+    // jamais construit ; l'index n'est pas persisté, une collection non chargée est dans cet état
+    NotBuilt { modifications: usize },
+    Fresh,
+    // modifications depuis le dernier build ; rebuild_due : seuil de rebuild automatique atteint
+    Stale { modifications: usize, rebuild_due: bool },
+    // rebuild en arrière-plan, modifications reçues depuis son début
+    Building { modifications: usize },
+    // un index par segment (Collection::flush_memtable), plus la memtable parcourue en entier
+    Segmented { segments: usize, memtable: usize },
+}
+
+impl IndexStatus {
+    pub fn unloaded(config: &CollectionConfig, modifications_count: usize) -> Self {
+        if config.use_ivf {
+            IndexStatus::NotBuilt { modifications: modifications_count }
+        } else {
+            IndexStatus::Disabled
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CollectionStats {
+    pub name: String,
+    pub dimension: usize,
+    pub count: usize,
+    pub use_ivf: bool,
+    pub index_info: Option<IndexInfo>,
+    pub estimated_memory_bytes: usize,
+    #[serde(default)]
+    pub precision: Precision,
+    // octets des vecteurs en mémoire (f32, quantifiés, binaires, demi-précision,
+    // store_raw), hors métadonnées et index
+    #[serde(default)]
+    pub embedding_bytes: usize,
+    pub last_query_time_ms: f64,
+    pub total_queries: usize,
+    pub loaded: bool,
+    // None si la collection n'est pas chargée
+    #[serde(default)]
+    pub drift: Option<DriftReport>,
+    #[serde(default)]
+    pub index_status: IndexStatus,
+    // None sans limite de vecteurs
+    #[serde(default)]
+    pub vector_limit: Option<LimitUsage>,
+    #[serde(default)]
+    pub rebuild_policy: RebuildPolicy,
+    // None si la collection n'est pas chargée
+    #[serde(default)]
+    pub tombstones: Option<TombstoneStats>,
+    // None si la collection n'est pas chargée
+    #[serde(default)]
+    pub metadata_dictionary: Option<DictionaryStats>,
+    // segments d'une collection segmentée ou figée (voir segment)
+    #[serde(default)]
+    pub segments: Vec<SegmentInfo>,
+}
+
+impl CollectionStats {
+    // statistiques d'une collection non chargée, à partir de son en-tête sur disque
+    pub fn unloaded(config: &CollectionConfig, count: usize, modifications_count: usize) -> Self {
+        Self {
+            name: config.name.clone(),
+            dimension: config.dimension,
+            count,
+            use_ivf: config.use_ivf,
+            index_info: None,
+            estimated_memory_bytes: count * (config.dimension * config.precision.component_bytes() + 64),
+            precision: config.precision,
+            embedding_bytes: count * config.dimension * config.precision.component_bytes(),
+            last_query_time_ms: 0.0,
+            total_queries: 0,
+            loaded: false,
+            drift: None,
+            index_status: IndexStatus::unloaded(config, modifications_count),
+            vector_limit: None,
+            rebuild_policy: config.rebuild_policy.clone(),
+            tombstones: None,
+            metadata_dictionary: None,
+            segments: Vec::new(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::filter::FilterBuilder;
+    use crate::testing::TempDir;
+
+    #[test]
+    fn test_on_conflict() {
+        let mut coll = Collection::new_with_ivf("docs".to_string(), 2, 2);
+        let ids: Vec<String> = (0..30).map(|i| format!("v{}", i)).collect();
+        let embeddings = (0..30).map(|i| vec![(i as f32 * 0.2).cos(), (i as f32 * 0.2).sin()]).collect();
+        let metadatas = (0..30).map(|i| HashMap::from([("n".to_string(), MetadataValue::Int(i))])).collect();
+        coll.add(ids, embeddings, Some(metadatas)).unwrap();
+        coll.rebuild_index();
+        let get = |coll: &Collection, id: &str| {
+            let got = coll.get(Some(vec![id.to_string()]), None).unwrap();
+            (got.metadatas.unwrap()[0].get("n").cloned(), got.embeddings.unwrap()[0].clone())
+        };
+        let modifications = coll.modifications_count();
+
+        // ids présents ignorés : ni données ni index modifiés
+        let batch = |id: &str| (vec!["v0".to_string(), id.to_string()], vec![vec![0.0, -1.0], vec![-1.0, 0.0]]);
+        let (ids, embeddings) = batch("new");
+        let metadatas = vec![HashMap::from([("n".to_string(), MetadataValue::Int(-1))]); 2];
+        let outcome = coll.add_with_options(ids, embeddings, Some(metadatas.clone()), AddOptions { on_conflict: OnConflict::Skip, ..AddOptions::new("test") }).unwrap();
+        assert_eq!((outcome.added, outcome.skipped, outcome.overwritten), (1, 1, 0));
+        assert_eq!(get(&coll, "v0"), (Some(MetadataValue::Int(0)), vec![1.0, 0.0]));
+        assert_eq!(coll.modifications_count(), modifications + 1);
+
+        // un seul id présent fait échouer tout le lot
+        let (ids, embeddings) = batch("other");
+        let err = coll.add_with_options(ids, embeddings, Some(metadatas.clone()), AddOptions { on_conflict: OnConflict::Error, ..AddOptions::new("test") }).unwrap_err();
+        assert!(matches!(err, VectorDbError::VectorAlreadyExists(ref id) if id == "v0"));
+        assert_eq!(coll.count(), 31);
+
+        // défaut : embedding et métadonnées remplacés
+        let (ids, embeddings) = batch("other");
+        let outcome = coll.add_with_options(ids, embeddings, Some(metadatas), AddOptions { on_conflict: OnConflict::Overwrite, ..AddOptions::new("test") }).unwrap();
+        assert_eq!((outcome.added, outcome.overwritten), (1, 1));
+        assert_eq!(get(&coll, "v0"), (Some(MetadataValue::Int(-1)), vec![0.0, -1.0]));
+        assert_eq!(coll.verify_index().unwrap().n_duplicates, 0);
+    }
+
+    #[test]
+    fn test_verify_and_repair_index() {
+        let mut coll = Collection::new_with_ivf("docs".to_string(), 2, 4);
+        assert!(Collection::new("flat".to_string(), 2).verify_index().is_err());
+        let embeddings: Vec<Vec<f32>> = (0..40).map(|i| vec![(i as f32 * 0.15).cos(), (i as f32 * 0.15).sin()]).collect();
+        coll.add((0..40).map(|i| format!("v{}", i)).collect(), embeddings.clone(), None).unwrap();
+        assert!(!coll.verify_index().unwrap().built);
+        coll.rebuild_index();
+        assert!(coll.verify_index().unwrap().consistent);
+
+        // id supprimé resté listé, doublon, vecteur absent des listes
+        let ivf = coll.ivf_index.as_mut().unwrap();
+        ivf.inverted_lists[0].push("ghost".to_string());
+        ivf.inverted_lists[1].push("v1".to_string());
+        ivf.remove("v2");
+        let report = coll.verify_index().unwrap();
+        assert!(!report.consistent);
+        assert_eq!((report.stale_ids, report.duplicate_ids, report.missing_ids), (vec!["ghost".to_string()], vec!["v1".to_string()], vec!["v2".to_string()]));
+        assert!(report.centroid_errors.is_empty());
+
+        let repaired = coll.repair_index().unwrap();
+        assert!(repaired.repaired && !repaired.consistent);
+        let after = coll.verify_index().unwrap();
+        assert!(after.consistent && after.indexed_ids == 40);
+        assert!(!coll.repair_index().unwrap().repaired);
+        assert_eq!(coll.search(&embeddings[2]).k(1).run().unwrap()[0].id, "v2");
+
+        // centroïde de mauvaise dimension : index reconstruit
+        coll.ivf_index.as_mut().unwrap().centroids[0] = vec![1.0];
+        let report = coll.repair_index().unwrap();
+        assert_eq!(report.centroid_errors.len(), 1);
+        assert!(coll.verify_index().unwrap().consistent);
+    }
+
+    #[test]
+    fn test_delete_unlists_ids() {
+        let mut coll = Collection::new_with_ivf("docs".to_string(), 2, 4);
+        let embeddings: Vec<Vec<f32>> = (0..40).map(|i| vec![(i as f32 * 0.15).cos(), (i as f32 * 0.15).sin()]).collect();
+        coll.add((0..40).map(|i| format!("v{}", i)).collect(), embeddings.clone(), None).unwrap();
+        coll.rebuild_index();
+
+        coll.delete((0..10).map(|i| format!("v{}", i)).collect()).unwrap();
+        assert_eq!(coll.count(), 30);
+        let report = coll.verify_index().unwrap();
+        assert_eq!((report.n_stale, report.indexed_ids), (0, 30));
+        assert!(report.consistent);
+        let lists: usize = coll.ivf_index.as_ref().unwrap().inverted_lists.iter().map(Vec::len).sum();
+        assert_eq!(lists, 30);
+        // listes sondées seules : aucun id supprimé parmi les candidats
+        let hits = coll.search(&embeddings[0]).k(10).run().unwrap();
+        assert!(!hits.is_empty());
+        assert!(hits.iter().all(|h| coll.vectors.contains_key(&h.id)));
+    }
+
+    #[test]
+    fn test_binary_batch_rejected_whole() {
+        let mut coll = Collection::from_config(CollectionConfig::new("hashes".to_string(), 4).with_binary());
+        coll.add(vec!["a".into()], vec![vec![1.0, 0.0, 0.0, 1.0]], None).unwrap();
+        let epoch = coll.data_version();
+        // vecteur non binaire en milieu de lot : rien d'écrit, pas même les précédents
+        let err = coll
+            .add(
+                vec!["b".into(), "c".into(), "d".into()],
+                vec![vec![0.0, 1.0, 1.0, 0.0], vec![0.0, 0.5, 1.0, 0.0], vec![1.0; 4]],
+                None,
+            )
+            .unwrap_err();
+        assert!(matches!(err, VectorDbError::InvalidConfig(ref msg) if msg.contains("'c'")));
+        assert_eq!(coll.count(), 1);
+        assert_eq!(coll.binary.len(), 1);
+        assert_eq!(coll.data_version(), epoch);
+        assert!(coll.get(Some(vec!["b".into()]), None).unwrap().ids.is_empty());
+    }
+
+    #[test]
+    fn test_rebuild_policy() {
+        let mut coll = Collection::new_with_ivf("docs".to_string(), 2, 2);
+        let ids = (0..100).map(|i| format!("v{}", i)).collect();
+        let embeddings = (0..100).map(|i| vec![(i as f32).cos(), (i as f32).sin()]).collect();
+        coll.add(ids, embeddings, None).unwrap();
+        coll.rebuild_index();
 
-        if let Some(ref metas) = metadatas {
-            if metas.len() != n {
-                return Err(VectorDbError::InvalidConfig(
-                    "metadatas must have the same length as ids".to_string(),
-                ));
-            }
-        }
+        let ids = (0..5).map(|i| format!("late{}", i)).collect();
+        coll.add(ids, vec![vec![0.6, 0.8]; 5], None).unwrap();
+        // politique par défaut : max(10 % de 105, 10) modifications
+        assert!(!coll.rebuild_due());
 
-        // pre-reserve capacity si nécessaire
-        if self.vectors.capacity() < self.vectors.len() + n {
-            self.vectors.reserve(n);
-        }
+        let update = RebuildPolicyUpdate { threshold_percent: Some(5.0), min_modifications: Some(1), ..Default::default() };
+        coll.set_rebuild_policy(update.apply(&coll.config.rebuild_policy)).unwrap();
+        assert!(coll.rebuild_due());
 
-        for idx in 0..n {
-            let mut embedding = embeddings[idx].clone();
+        let manual = RebuildPolicyUpdate { auto: Some(false), ..Default::default() };
+        coll.set_rebuild_policy(manual.apply(&coll.config.rebuild_policy)).unwrap();
+        assert!(!coll.rebuild_due());
+        assert_eq!(coll.stats().rebuild_policy.min_modifications, 1);
 
-            if embedding.len() != self.config.dimension {
-                return Err(VectorDbError::DimensionMismatch {
-                    expected: self.config.dimension,
-                    actual: embedding.len(),
-                });
-            }
+        let invalid = RebuildPolicyUpdate { threshold_percent: Some(0.0), ..Default::default() };
+        assert!(coll.set_rebuild_policy(invalid.apply(&coll.config.rebuild_policy)).is_err());
+    }
 
-            normalize_l2(&mut embedding);
+    #[test]
+    fn test_index_params_refine() {
+        let mut coll = Collection::new_with_ivf("docs".to_string(), 2, 4);
+        let angles: Vec<f32> = (0..80).map(|i| i as f32 * std::f32::consts::TAU / 80.0).collect();
+        let ids = (0..80).map(|i| format!("v{}", i)).collect();
+        let embeddings = angles.iter().map(|a| vec![a.cos(), a.sin()]).collect();
+        // 40 % des vecteurs, assez pour que le planificateur garde l'index
+        let metadatas = angles
+            .iter()
+            .map(|a| HashMap::from([("far".to_string(), MetadataValue::from(a.cos() < -0.3))]))
+            .collect();
+        coll.add(ids, embeddings, Some(metadatas)).unwrap();
+        coll.rebuild_index();
 
-            let metadata = metadatas
-                .as_ref()
-                .and_then(|m| m.get(idx))
-                .cloned()
-                .unwrap_or_default();
+        let far = || FilterBuilder::new().eq("far", true).build().unwrap();
+        coll.set_index_params(IndexParams { n_probe: Some(1), ..Default::default() }).unwrap();
+        assert_eq!(coll.stats().index_info.unwrap().n_probe, 1);
+        // cluster le plus proche de la requête : aucun vecteur à l'opposé du cercle
+        assert!(coll.search(&[1.0, 0.0]).k(3).filter(far()).run().unwrap().is_empty());
+        let explanation = coll.explain_query(3, Some(&far()), &QueryOptions::default());
+        assert_eq!(explanation.filter_stage, Some(FilterStage::Probed));
 
-            let entry = VectorEntry {
-                id: ids[idx].clone(),
-                embedding,
-                metadata,
-            };
-            self.vectors.insert(ids[idx].clone(), entry);
-        }
+        let update = IndexParamsUpdate { refine_factor: Some(Some(1)), ..Default::default() };
+        coll.set_index_params(update.apply(&coll.config.index_params)).unwrap();
+        let hits = coll.search(&[1.0, 0.0]).k(3).filter(far()).explain_hits().run().unwrap();
+        assert_eq!(hits.len(), 3);
+        assert!(hits.iter().all(|h| h.explain.as_ref().unwrap().probe_rank > Some(0)));
 
-        // marquer qu'on doit rebuild l'IVF (sauf en batch mode)
-        if self.config.use_ivf {
-            self.modifications_count += n;
-            if !self.batch_mode {
-                self.needs_rebuild = true;
-            }
-        }
+        // champ absent inchangé, null : retour à la valeur automatique
+        let update: IndexParamsUpdate = serde_json::from_str(r#"{"n_probe": null}"#).unwrap();
+        let params = update.apply(&coll.config.index_params);
+        assert_eq!((params.n_probe, params.refine_factor), (None, Some(1)));
+        let update: IndexParamsUpdate = serde_json::from_str("{}").unwrap();
+        assert_eq!(update.apply(&coll.config.index_params), coll.config.index_params);
 
-        Ok(())
+        assert!(coll.set_index_params(IndexParams { n_probe: Some(0), ..Default::default() }).is_err());
     }
 
-    pub fn get(
-        &self,
-        ids: Option<Vec<String>>,
-        include: Option<Vec<String>>,
-    ) -> Result<GetResult> {
-        use std::collections::HashSet;
+    #[test]
+    fn test_ivf_pq_rerank() {
+        use rand::{Rng, SeedableRng};
 
-        let default_include = vec!["metadatas".to_string(), "embeddings".to_string()];
-        let include_set: HashSet<String> = include
-            .unwrap_or(default_include)
-            .into_iter()
-            .collect();
+        let mut rng = rand::rngs::StdRng::seed_from_u64(11);
+        let config = CollectionConfig::new("docs".to_string(), 16).with_ivf(4).with_pq(4);
+        let mut coll = Collection::from_config(config);
+        let ids: Vec<String> = (0..400).map(|i| format!("v{}", i)).collect();
+        let embeddings: Vec<Vec<f32>> = (0..400).map(|_| (0..16).map(|_| rng.gen_range(-1.0..1.0)).collect()).collect();
+        coll.add(ids.clone(), embeddings.clone(), None).unwrap();
+        coll.rebuild_index();
+        let pq = coll.stats().index_info.unwrap().pq.unwrap();
+        assert_eq!((pq.subspaces, pq.code_bytes), (4, 400 * 4));
 
-        let entries: Vec<&VectorEntry> = match ids {
-            Some(id_list) => id_list
-                .iter()
-                .filter_map(|id| self.vectors.get(id))
-                .collect(),
-            None => self.vectors.values().collect(),
-        };
+        // distances approchées : calculées sur les codes, pas sur les vecteurs
+        let query = &embeddings[7];
+        let approximate = coll.search(query).k(5).run().unwrap();
+        let exact = coll.search(query).k(5).exact().run().unwrap();
+        assert!(approximate.iter().zip(&exact).any(|(a, e)| (a.distance - e.distance).abs() > 1e-4));
 
-        let result_ids = entries.iter().map(|e| e.id.clone()).collect();
+        let update = IndexParamsUpdate { pq_rerank: Some(Some(8)), n_probe: Some(Some(4)), ..Default::default() };
+        coll.set_index_params(update.apply(&coll.config.index_params)).unwrap();
+        let reranked = coll.search(query).k(5).run().unwrap();
+        assert_eq!(reranked[0].id, "v7");
+        assert!(reranked.iter().zip(&exact).all(|(r, e)| r.id == e.id && (r.distance - e.distance).abs() < 1e-6));
 
-        let embeddings = if include_set.contains("embeddings") {
-            Some(entries.iter().map(|e| e.embedding.clone()).collect())
-        } else {
-            None
-        };
+        // suppressions sous le seuil de rebuild : codes retirés avec leurs ids
+        coll.delete(ids[..5].to_vec()).unwrap();
+        assert_eq!(coll.stats().index_info.unwrap().pq.unwrap().code_bytes, 395 * 4);
+        let exact = coll.search(query).k(5).exact().run().unwrap();
+        let reranked = coll.search(query).k(5).run().unwrap();
+        assert!(reranked.iter().zip(&exact).all(|(r, e)| r.id == e.id));
 
-        let metadatas = if include_set.contains("metadatas") {
-            Some(entries.iter().map(|e| e.metadata.clone()).collect())
-        } else {
-            None
+        assert!(coll.set_index_params(IndexParams { pq_rerank: Some(0), ..Default::default() }).is_err());
+    }
+
+    #[test]
+    fn test_scalar_quantization() {
+        use crate::storage::Storage;
+        use rand::{Rng, SeedableRng};
+
+        let mut rng = rand::rngs::StdRng::seed_from_u64(13);
+        let ids: Vec<String> = (0..500).map(|i| format!("v{}", i)).collect();
+        let embeddings: Vec<Vec<f32>> = (0..500).map(|_| (0..32).map(|_| rng.gen_range(-1.0..1.0)).collect()).collect();
+        let collection = |config: CollectionConfig| {
+            let mut coll = Collection::from_config(config);
+            coll.add(ids.clone(), embeddings.clone(), None).unwrap();
+            coll
         };
+        let mut plain = collection(CollectionConfig::new("plain".to_string(), 32));
+        let mut quantized = collection(CollectionConfig::new("docs".to_string(), 32).with_scalar_quantization(None));
+        let mut rescored = collection(CollectionConfig::new("rescored".to_string(), 32).with_scalar_quantization(Some(4)));
+        // 32 octets + échelle, décalage et somme des codes au lieu de 128 octets
+        assert_eq!(quantized.stats().estimated_memory_bytes, 500 * (32 + 12 + 64));
 
-        Ok(GetResult {
-            ids: result_ids,
-            embeddings,
-            metadatas,
-        })
+        let query = &embeddings[3];
+        let exact = plain.search(query).k(10).run().unwrap();
+        let approximate = quantized.search(query).k(10).run().unwrap();
+        assert_eq!(approximate[0].id, "v3");
+        let overlap = approximate.iter().filter(|a| exact.iter().any(|e| e.id == a.id)).count();
+        assert!(overlap >= 8, "overlap {}", overlap);
+        assert!(approximate.iter().zip(&exact).all(|(a, e)| (a.distance - e.distance).abs() < 0.02));
+        // reclassement sur les f32 gardés : mêmes résultats que sans quantification
+        let reranked = rescored.search(query).k(10).run().unwrap();
+        assert!(reranked.iter().zip(&exact).all(|(r, e)| r.id == e.id && (r.distance - e.distance).abs() < 1e-6));
+
+        // embedding rendu : le vecteur normalisé, à un demi-pas de quantification près
+        let hit = &quantized.search(query).k(1).include_embeddings().run().unwrap()[0];
+        let norm = query.iter().map(|x| x * x).sum::<f32>().sqrt();
+        let returned = hit.embedding.as_ref().unwrap();
+        assert!(returned.iter().zip(query).all(|(r, q)| (r - q / norm).abs() < 0.01));
+
+        // vecteurs quantifiés relus de quantized.bin, sans f32 sur disque
+        let dir = TempDir::new("sq");
+        let storage = Storage::new(&dir).unwrap();
+        storage.save_collection(&quantized).unwrap();
+        let mut loaded = storage.load_collection("docs").unwrap();
+        let distances = |hits: Vec<crate::query::SearchHit>| hits.iter().map(|h| h.distance).collect::<Vec<_>>();
+        assert_eq!(distances(loaded.search(query).k(10).run().unwrap()), distances(approximate));
+        loaded.delete(vec!["v3".into()]).unwrap();
+        assert_ne!(loaded.search(query).k(1).run().unwrap()[0].id, "v3");
+        assert!(loaded.freeze(&dir).is_err());
     }
 
-    pub fn update(
-        &mut self,
-        ids: Vec<String>,
-        metadatas: Vec<HashMap<String, MetadataValue>>,
-    ) -> Result<()> {
-        if ids.len() != metadatas.len() {
-            return Err(VectorDbError::InvalidConfig(
-                "ids and metadatas must have the same length".to_string(),
-            ));
-        }
+    #[test]
+    fn test_binary_hamming_search() {
+        use crate::storage::Storage;
+        use crate::vector::BinaryVector;
 
-        for (idx, id) in ids.iter().enumerate() {
-            let entry = self.vectors
-                .get_mut(id)
-                .ok_or_else(|| VectorDbError::VectorNotFound(id.clone()))?;
+        // 70 bits : deux mots, le second partiel
+        let bits = |ones: &[usize]| (0..70).map(|i| if ones.contains(&i) { 1.0 } else { 0.0 }).collect::<Vec<f32>>();
+        let mut coll = Collection::from_config(CollectionConfig::new("hashes".to_string(), 70).with_binary());
+        coll.add(
+            vec!["a".into(), "b".into(), "c".into()],
+            vec![bits(&[0, 1, 2]), bits(&[0, 1, 65]), bits(&[10, 20, 30, 69])],
+            None,
+        )
+        .unwrap();
+        assert!(coll.add(vec!["d".into()], vec![vec![0.5; 70]], None).is_err());
+        assert!(coll.search(&vec![2.0; 70]).k(1).run().is_err());
 
-            // merge metadata au lieu de remplacer
-            for (k, v) in &metadatas[idx] {
-                entry.metadata.insert(k.clone(), v.clone());
-            }
-        }
+        let hits = coll.search(&bits(&[0, 1, 2])).k(3).include_embeddings().run().unwrap();
+        let ranked: Vec<(&str, f32)> = hits.iter().map(|h| (h.id.as_str(), h.distance)).collect();
+        assert_eq!(ranked, vec![("a", 0.0), ("b", 2.0), ("c", 7.0)]);
+        assert_eq!(hits[0].embedding.as_deref(), Some(bits(&[0, 1, 2]).as_slice()));
+        let packed = BinaryVector::from_words(vec![0b11, 1 << 1], 70).unwrap();
+        assert_eq!(packed.to_floats(70), bits(&[0, 1, 65]));
+        assert!(BinaryVector::from_words(vec![0, 1 << 6], 70).is_none());
 
-        Ok(())
+        let dir = TempDir::new("binary");
+        let storage = Storage::new(&dir).unwrap();
+        storage.save_collection(&coll).unwrap();
+        let mut loaded = storage.load_collection("hashes").unwrap();
+        assert!(loaded.config.is_binary());
+        assert_eq!(loaded.search(&bits(&[0, 1, 65])).k(1).run().unwrap()[0].id, "b");
+        loaded.delete(vec!["b".into()]).unwrap();
+        assert_eq!(loaded.search(&bits(&[0, 1, 65])).k(1).run().unwrap()[0].id, "a");
+        assert!(loaded.freeze(&dir).is_err());
     }
 
-    pub fn delete(&mut self, ids: Vec<String>) -> Result<()> {
-        let n = ids.len();
-        ids.iter().for_each(|id| {
-            self.vectors.remove(id);
-        });
+    #[test]
+    fn test_total_matched() {
+        let mut coll = Collection::new("docs".to_string(), 4);
+        let ids: Vec<String> = (0..10_000).map(|i| format!("v{}", i)).collect();
+        let embeddings: Vec<Vec<f32>> = (0..10_000).map(|i| vec![1.0, i as f32, 0.5, 0.0]).collect();
+        let metadatas = (0..10_000)
+            .map(|i| HashMap::from([("lang".to_string(), MetadataValue::from(if i % 4 == 0 { "fr" } else { "en" }))]))
+            .collect();
+        coll.add(ids, embeddings, Some(metadatas)).unwrap();
+        let filter = FilterBuilder::new().eq("lang", "fr").build().unwrap();
 
-        if self.config.use_ivf {
-            self.modifications_count += n;
-            if !self.batch_mode {
-                self.needs_rebuild = true;
-            }
-        }
+        let exact = coll.total_matched(Some(&filter), CountMode::Exact);
+        assert_eq!((exact.total_matched, exact.exact), (2_500, true));
+        let estimate = coll.total_matched(Some(&filter), CountMode::Estimate);
+        assert!(!estimate.exact);
+        assert!((2_000..3_000).contains(&estimate.total_matched), "{}", estimate.total_matched);
+        let all = coll.total_matched(None, CountMode::Estimate);
+        assert_eq!((all.total_matched, all.exact), (10_000, true));
 
-        Ok(())
+        coll.delete((0..9_000).map(|i| format!("v{}", i)).collect()).unwrap();
+        let small = coll.total_matched(Some(&filter), CountMode::Estimate);
+        assert_eq!((small.total_matched, small.exact), (250, true));
     }
 
-    pub fn count(&self) -> usize {
-        self.vectors.len()
+    #[test]
+    fn test_verify_and_renormalize() {
+        // embeddings relus tels quels du stockage : deux hors norme, un nul
+        let embeddings = vec![
+            ("ok".to_string(), vec![0.6, 0.8]),
+            ("long".to_string(), vec![3.0, 4.0]),
+            ("short".to_string(), vec![0.0, 0.5]),
+            ("zero".to_string(), vec![0.0, 0.0]),
+        ];
+        let metadatas = embeddings.iter().map(|(id, _)| (id.clone(), HashMap::new())).collect();
+        let config = CollectionConfig::new("imported".to_string(), 2);
+        let mut coll = Collection::from_parts(config, 0, 0, metadatas, embeddings, Default::default(), None).unwrap();
+
+        let report = coll.verify_norms(1e-3).unwrap();
+        assert_eq!((report.checked, report.n_denormalized, report.n_zero), (4, 2, 1));
+        assert_eq!(report.denormalized_ids, vec!["long", "short"]);
+        assert!((report.max_deviation - 4.0).abs() < 1e-6);
+        assert!(coll.verify_norms(0.0).is_err());
+        // tolérance large : rien à corriger
+        assert_eq!(coll.renormalize(10.0).unwrap().renormalized, 0);
+        assert!(!coll.embeddings_dirty());
+
+        // métadonnées relues sans _system.updated_at : pas de version avant correction
+        assert_eq!(coll.vector_version("long"), None);
+        assert_eq!(coll.renormalize(1e-3).unwrap().renormalized, 2);
+        assert!(coll.embeddings_dirty());
+        assert_eq!(coll.embedding("long").unwrap().unwrap(), vec![0.6, 0.8]);
+        assert!(coll.vector_version("long").is_some());
+        assert_eq!(coll.vector_version("ok"), None);
+        let after = coll.verify_norms(1e-3).unwrap();
+        assert_eq!((after.n_denormalized, after.n_zero), (0, 1));
+        let hit = &coll.search(&[0.0, 1.0]).k(1).run().unwrap()[0];
+        assert_eq!(hit.id, "short");
+        assert!(hit.distance.abs() < 1e-6);
     }
 
-    pub fn stats(&self) -> CollectionStats {
-        let index_info = if self.config.use_ivf {
-            if let Some(ref ivf) = self.ivf_index {
-                Some(IndexInfo {
-                    is_built: ivf.is_built(),
-                    n_clusters: self.config.n_clusters,
-                    n_centroids: ivf.centroids.len(),
-                    needs_rebuild: self.needs_rebuild,
-                })
-            } else {
-                None
-            }
-        } else {
-            None
-        };
+    #[test]
+    fn test_store_raw() {
+        use crate::storage::Storage;
 
-        // estimation mémoire approximative
-        let vec_size = self.vectors.len() * (self.config.dimension * 4 + 64); // f32 + overhead
-        let index_size = if let Some(ref ivf) = self.ivf_index {
-            ivf.centroids.len() * self.config.dimension * 4
-        } else {
-            0
-        };
+        let mut coll = Collection::from_config(CollectionConfig::new("raw".to_string(), 2).with_store_raw());
+        coll.add(vec!["a".into(), "b".into()], vec![vec![3.0, 4.0], vec![0.0, -2.5]], None).unwrap();
+        let raw = |coll: &Collection| coll.get(None, None).unwrap();
+        assert_eq!(coll.get(Some(vec!["a".into()]), None).unwrap().embeddings.unwrap()[0], vec![3.0, 4.0]);
 
-        CollectionStats {
-            name: self.config.name.clone(),
-            dimension: self.config.dimension,
-            count: self.vectors.len(),
-            use_ivf: self.config.use_ivf,
-            index_info,
-            estimated_memory_bytes: vec_size + index_size,
-            last_query_time_ms: self.last_query_time_ms,
-            total_queries: self.total_queries,
-        }
+        // recherche sur la copie normalisée, vecteur d'origine rendu
+        let hit = &coll.search(&[0.6, 0.8]).k(1).include_embeddings().run().unwrap()[0];
+        assert_eq!(hit.id, "a");
+        assert!(hit.distance.abs() < 1e-6);
+        assert_eq!(hit.embedding.as_deref(), Some([3.0, 4.0].as_slice()));
+        assert_eq!(coll.verify_norms(1e-3).unwrap().n_denormalized, 0);
+
+        let dir = TempDir::new("raw");
+        let storage = Storage::new(&dir).unwrap();
+        storage.save_collection(&coll).unwrap();
+        let mut loaded = storage.load_collection("raw").unwrap();
+        assert_eq!(loaded.get(Some(vec!["b".into()]), None).unwrap().embeddings.unwrap()[0], vec![0.0, -2.5]);
+        loaded.delete(vec!["b".into()]).unwrap();
+        assert_eq!(raw(&loaded).ids, vec!["a"]);
+        assert_eq!(loaded.search(&[0.0, -1.0]).k(1).run().unwrap()[0].id, "a");
+
+        // fichier .vdb : vecteurs d'origine dans le paquet
+        storage.save_collection(&loaded).unwrap();
+        storage.export_packed(&dir.join("raw.vdb")).unwrap();
+        let imported = Storage::new(dir.join("imported")).unwrap();
+        imported.import_packed(&dir.join("raw.vdb")).unwrap();
+        let unpacked = imported.load_collection("raw").unwrap();
+        assert_eq!(raw(&unpacked).embeddings.unwrap(), vec![vec![3.0, 4.0]]);
     }
 
-    // rebuilder l'index IVF si nécessaire
-    pub fn rebuild_index(&mut self) {
-        if !self.config.use_ivf || !self.needs_rebuild {
-            return;
-        }
+    #[test]
+    fn test_half_precision() {
+        use crate::half::Precision;
+        use crate::storage::Storage;
 
-        if let Some(ref mut ivf) = self.ivf_index {
-            let data: Vec<(String, Vec<f32>)> = self.vectors.iter()
-                .map(|(id, v)| (id.clone(), v.embedding.clone()))
-                .collect();
+        let ids: Vec<String> = (0..200).map(|i| format!("v{}", i)).collect();
+        let embeddings: Vec<Vec<f32>> =
+            (0..200).map(|i| (0..32).map(|d| ((i * 32 + d) as f32 * 0.7).sin()).collect()).collect();
+        let mut full = Collection::new("full".to_string(), 32);
+        full.add(ids.clone(), embeddings.clone(), None).unwrap();
 
-            if !data.is_empty() {
-                ivf.rebuild(&data);
-                self.needs_rebuild = false;
-                self.modifications_count = 0;
-            }
+        for precision in [Precision::F16, Precision::Bf16] {
+            let config = CollectionConfig::new("half".to_string(), 32).with_ivf(4).with_precision(precision);
+            let mut coll = Collection::from_config(config);
+            coll.add(ids.clone(), embeddings.clone(), None).unwrap();
+
+            // 2 octets par composante, mêmes voisins qu'en f32
+            let stats = coll.stats();
+            assert_eq!((stats.precision, stats.embedding_bytes), (precision, 200 * 32 * 2));
+            assert_eq!(full.stats().embedding_bytes, 200 * 32 * 4);
+            let query = &embeddings[17];
+            let hits = coll.search(query).k(5).exact().run().unwrap();
+            let expected = full.search(query).k(5).run().unwrap();
+            assert_eq!(hits[0].id, "v17");
+            assert!((hits[0].distance - expected[0].distance).abs() < 1e-2);
+            coll.rebuild_index();
+            assert_eq!(coll.search(query).k(1).run().unwrap()[0].id, "v17");
+            let stored = coll.get(Some(vec!["v3".into()]), None).unwrap().embeddings.unwrap();
+            let original = full.get(Some(vec!["v3".into()]), None).unwrap().embeddings.unwrap();
+            assert!(stored[0].iter().zip(&original[0]).all(|(a, b)| (a - b).abs() < 1e-2));
+
+            let dir = TempDir::new(&format!("half-{}", precision.name()));
+            let storage = Storage::new(&dir).unwrap();
+            storage.save_collection(&coll).unwrap();
+            let loaded = storage.load_collection("half").unwrap();
+            assert_eq!(loaded.get(Some(vec!["v3".into()]), None).unwrap().embeddings.unwrap(), stored);
+            assert_eq!(loaded.stats().embedding_bytes, 200 * 32 * 2);
         }
     }
 
-    // rebuild automatique si trop de modifications (seuil : 10%)
-    fn maybe_rebuild(&mut self) {
-        if !self.config.use_ivf || !self.needs_rebuild {
-            return;
-        }
+    #[test]
+    fn test_assign_diagnostics() {
+        let mut coll = Collection::new_with_ivf("docs".to_string(), 2, 4);
+        let ids = (0..40).map(|i| format!("v{}", i)).collect();
+        let embeddings = (0..40).map(|i| vec![(i as f32).cos(), (i as f32).sin()]).collect();
+        coll.add(ids, embeddings, None).unwrap();
+        assert!(coll.assign_diagnostics(Some(&[1.0, 0.0]), None, 2).is_err());
+        coll.rebuild_index();
 
-        let total = self.vectors.len();
-        if total == 0 {
-            return;
-        }
+        let report = coll.assign_diagnostics(None, Some("v7"), 10).unwrap();
+        assert_eq!(report.nearest.len(), report.n_centroids);
+        assert_eq!(report.nearest.iter().map(|c| c.list_size).sum::<usize>(), 40);
+        assert!(report.nearest.windows(2).all(|w| w[0].distance <= w[1].distance));
+        // placé dans le cluster le plus proche de son propre embedding
+        assert_eq!(report.listed_in, Some(vec![report.nearest[0].cluster]));
+        assert!(report.nearest[0].probed);
 
-        // rebuild si plus de 10% de modifications
-        let threshold = (total as f64 * 0.1).max(10.0) as usize;
-        if self.modifications_count >= threshold {
-            self.rebuild_index();
-        }
+        assert!(coll.assign_diagnostics(None, Some("missing"), 2).is_err());
+        assert!(coll.assign_diagnostics(Some(&[1.0]), None, 2).is_err());
     }
 
-    pub fn query(
-        &mut self,
-        query_embedding: &[f32],
-        n_results: usize,
-        where_filter: Option<&WhereFilter>,
-    ) -> Result<Vec<SearchResult>> {
-        use std::time::Instant;
+    #[test]
+    fn test_compare_query() {
+        let mut coll = Collection::new_with_ivf("docs".to_string(), 2, 2);
+        let ids = (0..40).map(|i| format!("v{}", i)).collect();
+        let embeddings = (0..40).map(|i| vec![(i as f32).cos(), (i as f32).sin()]).collect();
+        coll.add(ids, embeddings, None).unwrap();
+        coll.rebuild_index();
+        coll.set_index_params(IndexParams { n_probe: Some(1), ..Default::default() }).unwrap();
+        coll.add(vec!["late".into()], vec![vec![0.6, 0.8]], None).unwrap();
+        let queries = coll.stats().total_queries;
 
-        let start = Instant::now();
+        let comparison = coll.compare_query(&[0.6, 0.8], 1, None, &Default::default()).unwrap();
+        assert_eq!(comparison.exact[0].id, "late");
+        assert_ne!(comparison.results[0].id, "late");
+        assert_eq!((comparison.overlap, comparison.recall), (0, 0.0));
+        // la recherche exhaustive de référence n'est pas comptée
+        assert_eq!(coll.stats().total_queries, queries + 1);
 
-        if query_embedding.len() != self.config.dimension {
-            return Err(VectorDbError::DimensionMismatch {
-                expected: self.config.dimension,
-                actual: query_embedding.len(),
-            });
-        }
+        // tous les clusters sondés : plus coûteux que la recherche exhaustive, choisie
+        // par le planificateur
+        coll.set_index_params(IndexParams { n_probe: Some(2), ..Default::default() }).unwrap();
+        let comparison = coll.compare_query(&[0.6, 0.8], 10, None, &Default::default()).unwrap();
+        assert_eq!(comparison.overlap, 10);
+        assert!((comparison.recall - 1.0).abs() < 1e-9);
+    }
 
-        self.maybe_rebuild();
+    #[test]
+    fn test_score_ids_skips_index() {
+        let mut coll = Collection::new_with_ivf("docs".to_string(), 2, 2);
+        let ids = (0..40).map(|i| format!("v{}", i)).collect();
+        let embeddings = (0..40).map(|i| vec![(i as f32).cos(), (i as f32).sin()]).collect();
+        coll.add(ids, embeddings, None).unwrap();
+        coll.rebuild_index();
+        coll.add(vec!["late".into()], vec![vec![0.6, 0.8]], None).unwrap();
 
-        let mut normalized_query = query_embedding.to_vec();
-        normalize_l2(&mut normalized_query);
+        let candidates = ["v0", "late", "missing", "v0"].map(String::from);
+        let results = coll.score_ids(&[0.6, 0.8], &candidates).unwrap();
+        let ids: Vec<_> = results.iter().map(|r| r.id.as_str()).collect();
+        assert_eq!(ids, ["late", "v0"]);
+        assert!(results[0].distance.abs() < 1e-6);
 
-        let mut results = if self.config.use_ivf {
-            if let Some(ref ivf) = self.ivf_index {
-                if ivf.is_built() {
-                    self.query_with_ivf(&normalized_query, n_results, where_filter)?
-                } else {
-                    self.query_linear(&normalized_query, n_results, where_filter)?
-                }
-            } else {
-                self.query_linear(&normalized_query, n_results, where_filter)?
-            }
-        } else {
-            self.query_linear(&normalized_query, n_results, where_filter)?
-        };
+        assert!(coll.score_ids(&[1.0], &candidates).is_err());
+    }
 
-        // appliquer filtre si présent
-        if let Some(filter) = where_filter {
-            results.retain(|r| matches_filter(&r.metadata, filter));
-            results.truncate(n_results);
-        }
+    #[test]
+    fn test_upsert_merges_metadata() {
+        use crate::vector::SYSTEM_CREATED_AT;
 
-        self.last_query_time_ms = start.elapsed().as_secs_f64() * 1000.0;
-        self.total_queries += 1;
+        let mut coll = Collection::new("docs".to_string(), 2);
+        let metadata = HashMap::from([
+            ("lang".to_string(), MetadataValue::from("fr")),
+            ("year".to_string(), MetadataValue::Int(2020)),
+        ]);
+        coll.add(vec!["a".into()], vec![vec![1.0, 0.0]], Some(vec![metadata])).unwrap();
+        let created_at = coll.get(Some(vec!["a".into()]), None).unwrap().metadatas.unwrap()[0][SYSTEM_CREATED_AT].clone();
 
-        Ok(results)
-    }
+        let outcome = coll
+            .upsert(
+                vec!["a".into(), "b".into()],
+                vec![vec![0.0, 1.0], vec![1.0, 1.0]],
+                Some(vec![HashMap::from([("year".to_string(), MetadataValue::Int(2024))]), HashMap::new()]),
+            )
+            .unwrap();
+        assert_eq!((outcome.inserted, outcome.updated), (1, 1));
 
-    fn query_linear(&self, normalized_query: &[f32], n_results: usize, where_filter: Option<&WhereFilter>) -> Result<Vec<SearchResult>> {
-        // filtrer d'abord si nécessaire
-        let entries_to_search: Vec<&VectorEntry> = if let Some(filter) = where_filter {
-            self.vectors.values()
-                .filter(|entry| matches_filter(&entry.metadata, filter))
-                .collect()
-        } else {
-            self.vectors.values().collect()
-        };
+        let got = coll.get(Some(vec!["a".into()]), None).unwrap();
+        let metadata = &got.metadatas.unwrap()[0];
+        assert_eq!(metadata["lang"], MetadataValue::from("fr"));
+        assert_eq!(metadata["year"], MetadataValue::Int(2024));
+        assert_eq!(metadata[SYSTEM_CREATED_AT], created_at);
+        // embedding remplacé
+        assert_eq!(coll.search(&[0.0, 1.0]).k(1).run().unwrap()[0].id, "a");
 
-        // paralléliser si suffisamment de vecteurs
-        let mut results: Vec<SearchResult> = if entries_to_search.len() > 100 {
-            entries_to_search.par_iter()
-                .map(|entry| {
-                    let dist = cosine_distance(normalized_query, &entry.embedding);
-                    SearchResult {
-                        id: entry.id.clone(),
-                        distance: dist,
-                        metadata: entry.metadata.clone(),
-                    }
-                })
-                .collect()
-        } else {
-            entries_to_search.iter()
-                .map(|entry| {
-                    let dist = cosine_distance(normalized_query, &entry.embedding);
-                    SearchResult {
-                        id: entry.id.clone(),
-                        distance: dist,
-                        metadata: entry.metadata.clone(),
-                    }
-                })
-                .collect()
-        };
+        // sans métadonnées : celles de l'id sont gardées telles quelles
+        let outcome = coll.upsert(vec!["a".into()], vec![vec![1.0, 0.0]], None).unwrap();
+        assert_eq!((outcome.inserted, outcome.updated), (0, 1));
+        assert_eq!(coll.count_where(&FilterBuilder::new().eq("lang", "fr").build().unwrap()), 1);
+    }
 
-        // tri partiel suffit pour n_results << total
-        if n_results < results.len() / 4 {
-            results.select_nth_unstable_by(n_results, |a, b| {
-                a.distance.partial_cmp(&b.distance).unwrap_or(std::cmp::Ordering::Equal)
-            });
-            results.truncate(n_results);
-            results.sort_by(|a, b| a.distance.partial_cmp(&b.distance).unwrap());
-        } else {
-            results.sort_by(|a, b| a.distance.partial_cmp(&b.distance).unwrap());
-            results.truncate(n_results);
+    #[test]
+    fn test_query_batch() {
+        let mut coll = Collection::new("docs".to_string(), 2);
+        let ids: Vec<String> = (0..20).map(|i| format!("v{}", i)).collect();
+        let embeddings: Vec<Vec<f32>> =
+            (0..20).map(|i| vec![(i as f32 * 0.3).cos(), (i as f32 * 0.3).sin()]).collect();
+        coll.add(ids, embeddings, None).unwrap();
+
+        let queries = vec![vec![1.0, 0.0], vec![0.0, 1.0], vec![-1.0, 0.2]];
+        let options = QueryOptions::default();
+        let batch = coll.query_batch(&queries, 3, None, &options).unwrap();
+        assert_eq!(batch.len(), 3);
+        for (query, results) in queries.iter().zip(&batch) {
+            let single = coll.query_with_options(query, 3, None, &options).unwrap();
+            let ids = |r: &[SearchResult]| r.iter().map(|r| r.id.clone()).collect::<Vec<_>>();
+            assert_eq!(ids(results), ids(&single));
         }
+        assert_eq!(coll.stats().total_queries, 6);
 
-        Ok(results)
+        // une requête de mauvaise dimension fait échouer tout le lot
+        assert!(coll.query_batch(&[vec![1.0, 0.0], vec![1.0]], 3, None, &options).is_err());
     }
 
-    fn query_with_ivf(&self, normalized_query: &[f32], n_results: usize, where_filter: Option<&WhereFilter>) -> Result<Vec<SearchResult>> {
-        let ivf = self.ivf_index.as_ref().unwrap();
-        let candidate_ids = ivf.search_candidates(normalized_query);
-
-        // paralléliser le calcul des distances sur les candidats
-        let mut results: Vec<SearchResult> = if candidate_ids.len() > 50 {
-            candidate_ids.par_iter()
-                .filter_map(|id| self.vectors.get(id))
-                .filter(|entry| {
-                    where_filter.map_or(true, |f| matches_filter(&entry.metadata, f))
-                })
-                .map(|entry| {
-                    let dist = cosine_distance(normalized_query, &entry.embedding);
-                    SearchResult {
-                        id: entry.id.clone(),
-                        distance: dist,
-                        metadata: entry.metadata.clone(),
-                    }
-                })
-                .collect()
-        } else {
-            candidate_ids.iter()
-                .filter_map(|id| self.vectors.get(id))
-                .filter(|entry| {
-                    where_filter.map_or(true, |f| matches_filter(&entry.metadata, f))
-                })
-                .map(|entry| {
-                    let dist = cosine_distance(normalized_query, &entry.embedding);
-                    SearchResult {
-                        id: entry.id.clone(),
-                        distance: dist,
-                        metadata: entry.metadata.clone(),
-                    }
-                })
-                .collect()
-        };
+    #[test]
+    fn test_score_threshold() {
+        let mut coll = Collection::new("docs".to_string(), 2);
+        let ids: Vec<String> = (0..10).map(|i| format!("v{}", i)).collect();
+        // angles de 0 à 90° par pas de 10°
+        let embeddings: Vec<Vec<f32>> =
+            (0..10).map(|i| i as f32 * 10f32.to_radians()).map(|a| vec![a.cos(), a.sin()]).collect();
+        coll.add(ids, embeddings, None).unwrap();
 
-        results.sort_by(|a, b| a.distance.partial_cmp(&b.distance).unwrap());
-        results.truncate(n_results);
+        // cos(30°) ≈ 0.866 : v0 à v3 passent
+        let options = QueryOptions::default().score_threshold(0.85);
+        let results = coll.query_with_options(&[1.0, 0.0], 10, None, &options).unwrap();
+        assert_eq!(results.iter().map(|r| r.id.as_str()).collect::<Vec<_>>(), ["v0", "v1", "v2", "v3"]);
+        // aucun voisin assez proche : liste vide
+        let far = QueryOptions::default().max_distance(0.01);
+        assert!(coll.query_with_options(&[-1.0, 0.0], 10, None, &far).unwrap().is_empty());
 
-        Ok(results)
+        // seuil appliqué avant de couper à n_results : les plus lointains sous le seuil
+        let options = options.direction(SortDirection::Desc);
+        let results = coll.query_with_options(&[1.0, 0.0], 2, None, &options).unwrap();
+        assert_eq!(results.iter().map(|r| r.id.as_str()).collect::<Vec<_>>(), ["v3", "v2"]);
     }
-}
 
-#[derive(Debug, Serialize, Deserialize)]
-pub struct GetResult {
-    pub ids: Vec<String>,
-    pub embeddings: Option<Vec<Vec<f32>>>,
-    pub metadatas: Option<Vec<HashMap<String, MetadataValue>>>,
-}
+    #[test]
+    fn test_range_search() {
+        let mut coll = Collection::new_with_ivf("docs".to_string(), 2, 2);
+        let ids: Vec<String> = (0..10).map(|i| format!("v{}", i)).collect();
+        let embeddings: Vec<Vec<f32>> =
+            (0..10).map(|i| i as f32 * 10f32.to_radians()).map(|a| vec![a.cos(), a.sin()]).collect();
+        let metadatas = (0..10).map(|i| HashMap::from([("even".to_string(), MetadataValue::Bool(i % 2 == 0))])).collect();
+        coll.add(ids, embeddings, Some(metadatas)).unwrap();
+        coll.rebuild_index();
+        coll.set_index_params(IndexParams { n_probe: Some(1), ..Default::default() }).unwrap();
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct SearchResult {
-    pub id: String,
-    pub distance: f32,
-    pub metadata: HashMap<String, MetadataValue>,
-}
+        // 1 - cos(30°) ≈ 0.134 : v0 à v3, même avec l'index construit
+        let range = coll.range_search(&[1.0, 0.0], 0.14, None, 100).unwrap();
+        assert_eq!(range.results.iter().map(|r| r.id.as_str()).collect::<Vec<_>>(), ["v0", "v1", "v2", "v3"]);
+        assert!(!range.truncated);
 
-#[derive(Debug, Serialize, Deserialize)]
-pub struct IndexInfo {
-    pub is_built: bool,
-    pub n_clusters: usize,
-    pub n_centroids: usize,
-    pub needs_rebuild: bool,
-}
+        let range = coll.range_search(&[1.0, 0.0], 0.14, None, 2).unwrap();
+        assert_eq!(range.results.len(), 2);
+        assert!(range.truncated);
 
-#[derive(Debug, Serialize, Deserialize)]
-pub struct CollectionStats {
-    pub name: String,
-    pub dimension: usize,
-    pub count: usize,
-    pub use_ivf: bool,
-    pub index_info: Option<IndexInfo>,
-    pub estimated_memory_bytes: usize,
-    pub last_query_time_ms: f64,
-    pub total_queries: usize,
+        let even = FilterBuilder::new().eq("even", true).build().unwrap();
+        let range = coll.range_search(&[1.0, 0.0], 0.14, Some(&even), 100).unwrap();
+        assert_eq!(range.results.iter().map(|r| r.id.as_str()).collect::<Vec<_>>(), ["v0", "v2"]);
+        assert!(coll.range_search(&[-1.0, 0.0], 0.1, None, 100).unwrap().results.is_empty());
+    }
 }
@@ -0,0 +1,359 @@
+// représentation en colonnes des métadonnées d'une collection, tenue à jour à chaque
+// écriture à côté des métadonnées par vecteur : une colonne par clé, une ligne par
+// vecteur. Un filtre s'y évalue par balayage de tableaux contigus au lieu d'une
+// recherche dans la HashMap de chaque vecteur ; les clauses qu'aucune colonne ne
+// couvre (valeurs objet, chaînes trop nombreuses, clés imbriquées) sont vérifiées
+// ligne par ligne sur les seuls vecteurs restants
+use crate::dictionary::MAX_INTERNED_LEN;
+use crate::filter::{matches_filter, matches_value, FilterValue, WhereFilter};
+use crate::vector::MetadataValue;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+// valeurs distinctes d'une colonne de chaînes ; au-delà (ids, empreintes, textes),
+// la clé est évaluée ligne par ligne
+pub const MAX_STRING_CODES: usize = 4096;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Slot<T> {
+    Absent,
+    Null,
+    Value(T),
+}
+
+// valeurs sans allocation, comparées entre elles comme leurs MetadataValue
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Scalar {
+    Int(i64),
+    Float(f64),
+    Bool(bool),
+    DateTime(i64),
+    GeoPoint { lat: f64, lon: f64 },
+}
+
+impl Scalar {
+    fn from_value(value: &MetadataValue) -> Option<Self> {
+        match *value {
+            MetadataValue::Int(x) => Some(Scalar::Int(x)),
+            MetadataValue::Float(x) => Some(Scalar::Float(x)),
+            MetadataValue::Bool(x) => Some(Scalar::Bool(x)),
+            MetadataValue::DateTime(x) => Some(Scalar::DateTime(x)),
+            MetadataValue::GeoPoint { lat, lon } => Some(Scalar::GeoPoint { lat, lon }),
+            _ => None,
+        }
+    }
+
+    fn to_value(self) -> MetadataValue {
+        match self {
+            Scalar::Int(x) => MetadataValue::Int(x),
+            Scalar::Float(x) => MetadataValue::Float(x),
+            Scalar::Bool(x) => MetadataValue::Bool(x),
+            Scalar::DateTime(x) => MetadataValue::DateTime(x),
+            Scalar::GeoPoint { lat, lon } => MetadataValue::GeoPoint { lat, lon },
+        }
+    }
+}
+
+// chaînes codées par un indice dans `values`, copies propres à la colonne
+#[derive(Debug, Clone, Default)]
+struct StringColumn {
+    codes: Vec<Slot<u32>>,
+    values: Vec<Arc<str>>,
+    lookup: HashMap<Arc<str>, u32>,
+}
+
+impl StringColumn {
+    fn code(&mut self, value: &str) -> Option<u32> {
+        if let Some(&code) = self.lookup.get(value) {
+            return Some(code);
+        }
+        if value.len() > MAX_INTERNED_LEN || self.values.len() >= MAX_STRING_CODES {
+            return None;
+        }
+        let code = self.values.len() as u32;
+        let value: Arc<str> = Arc::from(value);
+        self.values.push(value.clone());
+        self.lookup.insert(value, code);
+        Some(code)
+    }
+}
+
+#[derive(Debug, Clone)]
+enum Column {
+    Scalar(Vec<Slot<Scalar>>),
+    String(StringColumn),
+    // types mélangés, objets ou chaînes trop nombreuses : évaluée ligne par ligne
+    Unindexed,
+}
+
+impl Column {
+    fn for_value(value: &MetadataValue, rows: usize) -> Self {
+        match value {
+            MetadataValue::String(_) => {
+                Column::String(StringColumn { codes: vec![Slot::Absent; rows], ..Default::default() })
+            }
+            MetadataValue::Object(_) => Column::Unindexed,
+            _ => Column::Scalar(vec![Slot::Absent; rows]),
+        }
+    }
+
+    fn push_absent(&mut self) {
+        match self {
+            Column::Scalar(slots) => slots.push(Slot::Absent),
+            Column::String(strings) => strings.codes.push(Slot::Absent),
+            Column::Unindexed => {}
+        }
+    }
+
+    fn swap_remove(&mut self, row: usize) {
+        match self {
+            Column::Scalar(slots) => {
+                slots.swap_remove(row);
+            }
+            Column::String(strings) => {
+                strings.codes.swap_remove(row);
+            }
+            Column::Unindexed => {}
+        }
+    }
+
+    fn set(&mut self, row: usize, value: Option<&MetadataValue>) {
+        let stored = match (&mut *self, value) {
+            (Column::Unindexed, _) => true,
+            (Column::Scalar(slots), None) => {
+                slots[row] = Slot::Absent;
+                true
+            }
+            (Column::String(strings), None) => {
+                strings.codes[row] = Slot::Absent;
+                true
+            }
+            (Column::Scalar(slots), Some(MetadataValue::Null)) => {
+                slots[row] = Slot::Null;
+                true
+            }
+            (Column::String(strings), Some(MetadataValue::Null)) => {
+                strings.codes[row] = Slot::Null;
+                true
+            }
+            (Column::Scalar(slots), Some(value)) => match Scalar::from_value(value) {
+                Some(scalar) => {
+                    slots[row] = Slot::Value(scalar);
+                    true
+                }
+                None => false,
+            },
+            (Column::String(strings), Some(MetadataValue::String(s))) => match strings.code(s) {
+                Some(code) => {
+                    strings.codes[row] = Slot::Value(code);
+                    true
+                }
+                None => false,
+            },
+            (Column::String(_), Some(_)) => false,
+        };
+        if !stored {
+            *self = Column::Unindexed;
+        }
+    }
+
+    // `matches[row]` reste vrai si la ligne satisfait aussi cette clause
+    fn evaluate(&self, filter_value: &FilterValue, matches: &mut [bool]) {
+        let absent = matches_value(None, filter_value);
+        let null = matches_value(Some(&MetadataValue::Null), filter_value);
+        match self {
+            Column::Scalar(slots) => {
+                for (matched, slot) in matches.iter_mut().zip(slots) {
+                    if *matched {
+                        *matched = match *slot {
+                            Slot::Absent => absent,
+                            Slot::Null => null,
+                            Slot::Value(scalar) => matches_value(Some(&scalar.to_value()), filter_value),
+                        };
+                    }
+                }
+            }
+            // chaque valeur distincte n'est comparée qu'une fois
+            Column::String(strings) => {
+                let by_code: Vec<bool> = strings
+                    .values
+                    .iter()
+                    .map(|s| matches_value(Some(&MetadataValue::String(s.clone())), filter_value))
+                    .collect();
+                for (matched, slot) in matches.iter_mut().zip(&strings.codes) {
+                    if *matched {
+                        *matched = match *slot {
+                            Slot::Absent => absent,
+                            Slot::Null => null,
+                            Slot::Value(code) => by_code[code as usize],
+                        };
+                    }
+                }
+            }
+            Column::Unindexed => unreachable!("unindexed columns are evaluated row by row"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct MetadataColumns {
+    // ligne -> id, et l'inverse
+    ids: Vec<String>,
+    rows: HashMap<String, usize>,
+    columns: HashMap<String, Column>,
+}
+
+impl MetadataColumns {
+    // nouvelle ligne, ou remplacement de toutes les valeurs d'une ligne existante
+    pub fn upsert(&mut self, id: &str, metadata: &HashMap<String, MetadataValue>) {
+        let row = match self.rows.get(id) {
+            Some(&row) => row,
+            None => {
+                let row = self.ids.len();
+                self.ids.push(id.to_string());
+                self.rows.insert(id.to_string(), row);
+                self.columns.values_mut().for_each(Column::push_absent);
+                row
+            }
+        };
+        for (key, column) in self.columns.iter_mut() {
+            if !metadata.contains_key(key) {
+                column.set(row, None);
+            }
+        }
+        for (key, value) in metadata {
+            let rows = self.ids.len();
+            self.columns
+                .entry(key.clone())
+                .or_insert_with(|| Column::for_value(value, rows))
+                .set(row, Some(value));
+        }
+    }
+
+    // libère la capacité excédentaire des colonnes, pour une collection qui ne
+    // changera plus (Collection::freeze)
+    pub fn compact(&mut self) {
+        self.ids.shrink_to_fit();
+        self.rows.shrink_to_fit();
+        for column in self.columns.values_mut() {
+            match column {
+                Column::Scalar(slots) => slots.shrink_to_fit(),
+                Column::String(strings) => {
+                    strings.codes.shrink_to_fit();
+                    strings.values.shrink_to_fit();
+                    strings.lookup.shrink_to_fit();
+                }
+                Column::Unindexed => {}
+            }
+        }
+        self.columns.shrink_to_fit();
+    }
+
+    // la dernière ligne prend la place de la ligne supprimée
+    pub fn remove(&mut self, id: &str) {
+        let Some(row) = self.rows.remove(id) else {
+            return;
+        };
+        self.ids.swap_remove(row);
+        self.columns.values_mut().for_each(|column| column.swap_remove(row));
+        if let Some(moved) = self.ids.get(row) {
+            self.rows.insert(moved.clone(), row);
+        }
+    }
+
+    // une clé à points ("author.country") peut désigner un champ d'un objet : seule
+    // une clé dont aucun préfixe n'est une colonne est évaluée par colonne
+    fn may_be_nested(&self, key: &str) -> bool {
+        key.match_indices('.').any(|(i, _)| self.columns.contains_key(&key[..i]))
+    }
+
+    // ids des vecteurs satisfaisant le filtre, même résultat que matches_filter sur
+    // chaque ligne ; `metadata_of` fournit les métadonnées des clauses hors colonnes
+    pub fn select<'a, F>(&'a self, filter: &WhereFilter, metadata_of: F) -> Vec<&'a str>
+    where
+        F: Fn(&str) -> Option<&'a HashMap<String, MetadataValue>>,
+    {
+        let mut matches = vec![true; self.ids.len()];
+        let mut residual = WhereFilter::new();
+        for (key, filter_value) in filter {
+            match self.columns.get(key) {
+                _ if self.may_be_nested(key) => {
+                    residual.insert(key.clone(), filter_value.clone());
+                }
+                Some(Column::Unindexed) => {
+                    residual.insert(key.clone(), filter_value.clone());
+                }
+                Some(column) => column.evaluate(filter_value, &mut matches),
+                // clé absente de tous les vecteurs
+                None if !matches_value(None, filter_value) => matches.fill(false),
+                None => {}
+            }
+        }
+
+        self.ids
+            .iter()
+            .zip(matches)
+            .filter(|(_, matched)| *matched)
+            .map(|(id, _)| id.as_str())
+            .filter(|id| residual.is_empty() || metadata_of(id).is_some_and(|m| matches_filter(m, &residual)))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::filter::parse_filter;
+
+    #[test]
+    fn test_column_scan_matches_row_filter() {
+        let rows: Vec<(String, HashMap<String, MetadataValue>)> = [
+            serde_json::json!({"lang": "en", "year": 2021, "score": 0.5, "tags": {"a": 1}}),
+            serde_json::json!({"lang": "fr", "year": 2023.5, "draft": true}),
+            serde_json::json!({"lang": null, "year": 2019, "author": {"country": "fr"}}),
+            serde_json::json!({"year": "unknown", "author.country": "en"}),
+            serde_json::json!({"lang": "en", "published": "2024-01-01T00:00:00Z"}),
+        ]
+        .into_iter()
+        .enumerate()
+        .map(|(i, json)| {
+            let serde_json::Value::Object(obj) = json else { unreachable!() };
+            (format!("v{}", i), obj.into_iter().map(|(k, v)| (k, MetadataValue::from_json(v))).collect())
+        })
+        .collect();
+
+        let mut columns = MetadataColumns::default();
+        rows.iter().for_each(|(id, metadata)| columns.upsert(id, metadata));
+        columns.remove("v1");
+        columns.upsert("v1", &rows[1].1);
+        let metadata_of = |id: &str| rows.iter().find(|(i, _)| i == id).map(|(_, m)| m);
+
+        let filters = [
+            serde_json::json!({"lang": "en"}),
+            serde_json::json!({"lang": null}),
+            serde_json::json!({"lang": {"$ne": null}}),
+            serde_json::json!({"lang": {"$in": ["fr", null]}}),
+            serde_json::json!({"lang": {"$exists": true}}),
+            serde_json::json!({"year": {"$gte": 2020}}),
+            serde_json::json!({"year": {"$nin": [2019]}, "lang": {"$exists": true}}),
+            serde_json::json!({"draft": true}),
+            serde_json::json!({"missing": {"$exists": false}}),
+            serde_json::json!({"missing": 1}),
+            serde_json::json!({"author.country": "fr"}),
+            serde_json::json!({"author.country": {"$exists": true}}),
+            serde_json::json!({"tags": {"a": 1}}),
+            serde_json::json!({"published": {"$lt": "2025-01-01T00:00:00Z"}}),
+        ];
+        for json in filters {
+            let filter = parse_filter(&json).unwrap();
+            let mut selected = columns.select(&filter, metadata_of);
+            selected.sort_unstable();
+            let expected: Vec<&str> =
+                rows.iter().filter(|(_, m)| matches_filter(m, &filter)).map(|(id, _)| id.as_str()).collect();
+            assert_eq!(selected, expected, "{}", json);
+        }
+        // "year" mélange nombres et chaîne : évaluée ligne par ligne
+        assert!(matches!(columns.columns["year"], Column::Unindexed));
+        assert!(matches!(columns.columns["lang"], Column::String(_)));
+    }
+}
@@ -0,0 +1,150 @@
+// curseurs de pagination signés par le serveur : position (dernier id servi), filtre
+// et version des données de la collection, encodés en bincode puis signés en
+// HMAC-SHA256. Le client ne peut ni fabriquer un curseur ni le rejouer avec un autre
+// filtre ; après une écriture dans la collection le curseur est refusé (StaleCursor)
+// au lieu de sauter ou répéter des vecteurs
+use crate::error::{Result, VectorDbError};
+use crate::filter::WhereFilter;
+use hmac_sha256::{Hash, HMAC};
+use serde::{Deserialize, Serialize};
+
+// octets de signature gardés dans le jeton
+const MAC_LEN: usize = 16;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PageCursor {
+    pub collection: String,
+    // dernier id servi ; la page suivante commence à l'id strictement supérieur
+    pub after: String,
+    pub filter_hash: u64,
+    // Collection::data_version à l'émission
+    pub data_version: u64,
+}
+
+impl PageCursor {
+    pub fn new(collection: &str, after: String, filter: Option<&WhereFilter>, data_version: u64) -> Self {
+        Self { collection: collection.to_string(), after, filter_hash: filter_hash(filter), data_version }
+    }
+}
+
+// clé de signature : VECTORDB_CURSOR_KEY pour que les curseurs survivent à un
+// redémarrage ou passent d'une instance à l'autre, aléatoire par processus sinon
+#[derive(Clone)]
+pub struct CursorKey([u8; 32]);
+
+impl CursorKey {
+    pub fn from_secret(secret: &str) -> Self {
+        Self(Hash::hash(secret.as_bytes()))
+    }
+
+    pub fn random() -> Self {
+        Self(rand::random())
+    }
+
+    // jeton opaque en hexadécimal : bincode du curseur puis signature tronquée
+    pub fn sign(&self, cursor: &PageCursor) -> String {
+        let mut bytes = bincode::serialize(cursor).expect("cursor serialization cannot fail");
+        let mac = HMAC::mac(&bytes, self.0);
+        bytes.extend_from_slice(&mac[..MAC_LEN]);
+        bytes.iter().map(|b| format!("{:02x}", b)).collect()
+    }
+
+    // curseur vérifié pour cette collection, ce filtre et cette version des données
+    pub fn open(&self, token: &str, collection: &str, filter: Option<&WhereFilter>, data_version: u64) -> Result<PageCursor> {
+        let invalid = |reason: &str| VectorDbError::InvalidCursor(reason.to_string());
+        let bytes = decode_hex(token).ok_or_else(|| invalid("cursor is not a token issued by this server"))?;
+        if bytes.len() <= MAC_LEN {
+            return Err(invalid("cursor is not a token issued by this server"));
+        }
+        let (payload, mac) = bytes.split_at(bytes.len() - MAC_LEN);
+        let expected = HMAC::mac(payload, self.0);
+        if !constant_time_eq(&expected[..MAC_LEN], mac) {
+            return Err(invalid("cursor signature does not match"));
+        }
+        let cursor: PageCursor = bincode::deserialize(payload).map_err(|_| invalid("cursor payload is unreadable"))?;
+
+        if cursor.collection != collection {
+            return Err(invalid(&format!("cursor was issued for collection '{}'", cursor.collection)));
+        }
+        if cursor.filter_hash != filter_hash(filter) {
+            return Err(invalid("cursor was issued for a different filter"));
+        }
+        if cursor.data_version != data_version {
+            return Err(VectorDbError::StaleCursor(collection.to_string()));
+        }
+        Ok(cursor)
+    }
+}
+
+impl std::fmt::Debug for CursorKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("CursorKey(..)")
+    }
+}
+
+// empreinte du filtre, indépendante de l'ordre des clés (objets JSON triés)
+pub fn filter_hash(filter: Option<&WhereFilter>) -> u64 {
+    let Some(filter) = filter else {
+        return 0;
+    };
+    let canonical = serde_json::to_value(filter).map(|v| v.to_string()).unwrap_or_default();
+    let digest = Hash::hash(canonical.as_bytes());
+    u64::from_le_bytes(digest[..8].try_into().unwrap())
+}
+
+fn decode_hex(s: &str) -> Option<Vec<u8>> {
+    if !s.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..s.len()).step_by(2).map(|i| u8::from_str_radix(s.get(i..i + 2)?, 16).ok()).collect()
+}
+
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::collection::Collection;
+    use crate::filter::FilterBuilder;
+
+    #[test]
+    fn test_signed_cursor() {
+        let key = CursorKey::from_secret("secret");
+        let filter = FilterBuilder::new().eq("lang", "fr").gte("year", 2020).build().unwrap();
+        let cursor = PageCursor::new("docs", "v41".to_string(), Some(&filter), 7);
+        let token = key.sign(&cursor);
+        assert_eq!(key.open(&token, "docs", Some(&filter), 7).unwrap(), cursor);
+        // même filtre construit dans un autre ordre
+        let reordered = FilterBuilder::new().gte("year", 2020).eq("lang", "fr").build().unwrap();
+        assert!(key.open(&token, "docs", Some(&reordered), 7).is_ok());
+
+        let invalid = |r: Result<PageCursor>| matches!(r, Err(VectorDbError::InvalidCursor(_)));
+        assert!(invalid(key.open(&token, "other", Some(&filter), 7)));
+        assert!(invalid(key.open(&token, "docs", None, 7)));
+        assert!(invalid(CursorKey::from_secret("other").open(&token, "docs", Some(&filter), 7)));
+        assert!(invalid(key.open("zz", "docs", None, 7)));
+        // position modifiée par le client : signature invalide
+        let mut tampered = token.clone().into_bytes();
+        tampered[20] = if tampered[20] == b'0' { b'1' } else { b'0' };
+        assert!(invalid(key.open(std::str::from_utf8(&tampered).unwrap(), "docs", Some(&filter), 7)));
+
+        assert!(matches!(key.open(&token, "docs", Some(&filter), 8), Err(VectorDbError::StaleCursor(_))));
+    }
+
+    #[test]
+    fn test_data_version() {
+        let mut coll = Collection::new("docs".to_string(), 2);
+        let version = coll.data_version();
+        coll.add(vec!["a".into(), "b".into()], vec![vec![1.0, 0.0], vec![0.0, 1.0]], None).unwrap();
+        assert!(coll.data_version() > version);
+        // aucune écriture effective : même version
+        let version = coll.data_version();
+        let filter = FilterBuilder::new().eq("lang", "fr").build().unwrap();
+        assert_eq!(coll.delete_where(&filter).unwrap(), 0);
+        assert_eq!(coll.data_version(), version);
+        coll.delete(vec!["a".into()]).unwrap();
+        assert!(coll.data_version() > version);
+    }
+}
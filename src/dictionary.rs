@@ -0,0 +1,136 @@
+// dictionnaire des valeurs de métadonnées d'une collection : une chaîne répétée sur
+// des millions de vecteurs ("lang": "en") n'est allouée qu'une fois, chaque vecteur
+// en garde une référence partagée. Transparent pour les lectures, les filtres et le
+// stockage (une Arc<str> se sérialise comme une String)
+use crate::vector::{MetadataValue, SYSTEM_CONTENT_HASH};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+
+// au-delà, une chaîne (document, description) est rarement répétée : non internée
+pub const MAX_INTERNED_LEN: usize = 128;
+// valeurs distinctes par collection ; dictionnaire plein, les nouvelles valeurs
+// restent propres à leur vecteur
+pub const DEFAULT_MAX_DICTIONARY_ENTRIES: usize = 65_536;
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DictionaryStats {
+    // valeurs référencées par au moins un vecteur
+    pub distinct_values: usize,
+    pub references: usize,
+    pub dictionary_bytes: usize,
+    // octets des copies évitées
+    pub saved_bytes: usize,
+    // valeurs laissées hors du dictionnaire plein, depuis le chargement
+    pub skipped: usize,
+}
+
+#[derive(Debug)]
+pub struct MetadataDictionary {
+    values: HashSet<Arc<str>>,
+    max_entries: usize,
+    skipped: usize,
+}
+
+impl Default for MetadataDictionary {
+    fn default() -> Self {
+        Self::new(DEFAULT_MAX_DICTIONARY_ENTRIES)
+    }
+}
+
+impl MetadataDictionary {
+    pub fn new(max_entries: usize) -> Self {
+        Self { values: HashSet::new(), max_entries, skipped: 0 }
+    }
+
+    // remplace les chaînes de `metadata` par leur exemplaire partagé ; les empreintes
+    // de contenu, uniques par construction, n'y entrent pas
+    pub fn intern(&mut self, metadata: &mut HashMap<String, MetadataValue>) {
+        for (key, value) in metadata.iter_mut() {
+            if key != SYSTEM_CONTENT_HASH {
+                self.intern_value(value);
+            }
+        }
+    }
+
+    fn intern_value(&mut self, value: &mut MetadataValue) {
+        match value {
+            MetadataValue::String(s) if s.len() <= MAX_INTERNED_LEN => {
+                if let Some(shared) = self.values.get(&**s) {
+                    *s = shared.clone();
+                    return;
+                }
+                if self.values.len() >= self.max_entries {
+                    self.prune();
+                }
+                if self.values.len() < self.max_entries {
+                    self.values.insert(s.clone());
+                } else {
+                    self.skipped += 1;
+                }
+            }
+            MetadataValue::Object(inner) => inner.values_mut().for_each(|v| self.intern_value(v)),
+            _ => {}
+        }
+    }
+
+    // retire les valeurs qui ne sont plus référencées par aucun vecteur
+    pub fn prune(&mut self) -> usize {
+        let before = self.values.len();
+        self.values.retain(|v| Arc::strong_count(v) > 1);
+        before - self.values.len()
+    }
+
+    pub fn stats(&self) -> DictionaryStats {
+        let mut stats = DictionaryStats { skipped: self.skipped, ..Default::default() };
+        for value in &self.values {
+            let references = Arc::strong_count(value) - 1;
+            if references == 0 {
+                continue;
+            }
+            stats.distinct_values += 1;
+            stats.references += references;
+            stats.dictionary_bytes += value.len();
+            stats.saved_bytes += (references - 1) * value.len();
+        }
+        stats
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::collection::Collection;
+
+    #[test]
+    fn test_repeated_values_are_shared() {
+        let mut coll = Collection::new("docs".to_string(), 2);
+        let metadata = |lang: &str| HashMap::from([("lang".to_string(), MetadataValue::from(lang))]);
+        let ids: Vec<String> = (0..4).map(|i| format!("v{}", i)).collect();
+        let metadatas = vec![metadata("en"), metadata("en"), metadata("en"), metadata("fr")];
+        coll.add(ids, vec![vec![1.0, 0.0]; 4], Some(metadatas)).unwrap();
+
+        // "en" x3, "fr" x1 et la source "library" x4 ; les dates ne sont pas des chaînes
+        let stats = coll.dictionary_stats();
+        assert_eq!((stats.distinct_values, stats.references), (3, 8));
+        assert_eq!(stats.saved_bytes, 2 * "en".len() + 3 * "library".len());
+
+        coll.delete(vec!["v3".into()]).unwrap();
+        assert_eq!(coll.dictionary_stats().distinct_values, 2);
+
+        let mut dictionary = MetadataDictionary::new(1);
+        let mut a = metadata("en");
+        let mut b = metadata("en");
+        dictionary.intern(&mut a);
+        dictionary.intern(&mut b);
+        let mut c = metadata("fr");
+        dictionary.intern(&mut c);
+        match (&a["lang"], &b["lang"]) {
+            (MetadataValue::String(x), MetadataValue::String(y)) => assert!(Arc::ptr_eq(x, y)),
+            _ => unreachable!(),
+        }
+        // dictionnaire plein : "fr" reste hors du dictionnaire, mais lisible
+        assert_eq!(dictionary.stats().skipped, 1);
+        assert_eq!(c["lang"], MetadataValue::from("fr"));
+    }
+}
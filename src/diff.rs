@@ -0,0 +1,242 @@
+// comparaison complète de deux collections de même dimension : deux collections de
+// la base, ou une collection et son état dans un instantané .vdb. Ids ajoutés,
+// supprimés et modifiés, avec la dérive de l'embedding de chaque id modifié (distance
+// cosinus entre l'ancien et le nouveau vecteur) : de quoi valider un ré-embedding
+// avant de basculer un alias. Les métadonnées _system.* (dates, source) sont ignorées
+use crate::collection::Collection;
+use crate::distance::cosine_distance;
+use crate::error::{Result, VectorDbError};
+use crate::vector::{MetadataValue, SYSTEM_NAMESPACE};
+use rayon::prelude::*;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+// collection de la base, ou sa copie dans un instantané .vdb si `snapshot` est donné
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DiffSource {
+    pub collection: String,
+    pub snapshot: Option<PathBuf>,
+}
+
+impl DiffSource {
+    pub fn collection(name: &str) -> Self {
+        Self { collection: name.to_string(), snapshot: None }
+    }
+
+    pub fn snapshot<P: Into<PathBuf>>(path: P, name: &str) -> Self {
+        Self { collection: name.to_string(), snapshot: Some(path.into()) }
+    }
+
+    // nom affiché dans le rapport : "docs" ou "docs@snapshot-20240501T120000000Z.vdb"
+    pub fn label(&self) -> String {
+        match self.snapshot {
+            Some(ref path) => {
+                let file = path.file_name().map_or_else(|| path.display().to_string(), |f| f.to_string_lossy().into());
+                format!("{}@{}", self.collection, file)
+            }
+            None => self.collection.clone(),
+        }
+    }
+}
+
+// en deçà, deux embeddings sont considérés identiques (arrondis d'une resauvegarde)
+pub const DEFAULT_DIFF_TOLERANCE: f32 = 1e-6;
+
+#[derive(Debug, Clone, Copy)]
+pub struct DiffOptions {
+    pub tolerance: f32,
+    // ids détaillés par liste, 0 = tous ; les compteurs portent toujours sur tout
+    pub max_ids: usize,
+}
+
+impl Default for DiffOptions {
+    fn default() -> Self {
+        Self { tolerance: DEFAULT_DIFF_TOLERANCE, max_ids: 0 }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ChangedId {
+    pub id: String,
+    // distance cosinus entre les deux embeddings, 0 si seules les métadonnées changent
+    pub distance: f32,
+    pub metadata_changed: bool,
+}
+
+// distribution des distances des ids dont l'embedding a changé
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize)]
+pub struct DriftSummary {
+    pub mean: f32,
+    pub p50: f32,
+    pub p95: f32,
+    pub max: f32,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct CollectionDiff {
+    pub left: String,
+    pub right: String,
+    pub dimension: usize,
+    // présents à droite seulement, triés
+    pub added: Vec<String>,
+    // présents à gauche seulement, triés
+    pub removed: Vec<String>,
+    // plus forte dérive d'abord
+    pub changed: Vec<ChangedId>,
+    pub total_added: usize,
+    pub total_removed: usize,
+    pub total_changed: usize,
+    pub unchanged: usize,
+    pub drift: Option<DriftSummary>,
+}
+
+// `left` : l'état de référence (avant ré-embedding), `right` : le nouvel état
+pub fn diff_collections(left: &Collection, right: &Collection, options: &DiffOptions) -> Result<CollectionDiff> {
+    if left.config.dimension != right.config.dimension {
+        return Err(VectorDbError::DimensionMismatch {
+            expected: left.config.dimension,
+            actual: right.config.dimension,
+        });
+    }
+
+    let left_entries: Vec<_> = left.entries().collect();
+    let compared = left_entries
+        .par_iter()
+        .filter_map(|entry| right.entry(&entry.id).map(|other| (entry, other)))
+        .map(|(entry, other)| {
+            let distance = cosine_distance(&left.embedding_of(entry)?, &right.embedding_of(other)?).max(0.0);
+            let distance = if distance > options.tolerance { distance } else { 0.0 };
+            let metadata_changed = user_metadata(&entry.metadata) != user_metadata(&other.metadata);
+            Ok(ChangedId { id: entry.id.clone(), distance, metadata_changed })
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    let mut added: Vec<String> = right.entries().filter(|e| left.entry(&e.id).is_none()).map(|e| e.id.clone()).collect();
+    let mut removed: Vec<String> =
+        left_entries.iter().filter(|e| right.entry(&e.id).is_none()).map(|e| e.id.clone()).collect();
+    let total = compared.len();
+    let mut changed: Vec<ChangedId> = compared.into_iter().filter(|c| c.distance > 0.0 || c.metadata_changed).collect();
+    added.sort();
+    removed.sort();
+    changed.sort_by(|a, b| b.distance.total_cmp(&a.distance).then_with(|| a.id.cmp(&b.id)));
+
+    let mut distances: Vec<f32> = changed.iter().map(|c| c.distance).filter(|d| *d > 0.0).collect();
+    distances.sort_by(f32::total_cmp);
+    let drift = (!distances.is_empty()).then(|| {
+        let at = |p: f64| distances[((distances.len() - 1) as f64 * p).round() as usize];
+        DriftSummary {
+            mean: distances.iter().sum::<f32>() / distances.len() as f32,
+            p50: at(0.5),
+            p95: at(0.95),
+            max: distances[distances.len() - 1],
+        }
+    });
+
+    let mut diff = CollectionDiff {
+        left: left.config.name.clone(),
+        right: right.config.name.clone(),
+        dimension: left.config.dimension,
+        total_added: added.len(),
+        total_removed: removed.len(),
+        total_changed: changed.len(),
+        unchanged: total - changed.len(),
+        added,
+        removed,
+        changed,
+        drift,
+    };
+    if options.max_ids > 0 {
+        diff.added.truncate(options.max_ids);
+        diff.removed.truncate(options.max_ids);
+        diff.changed.truncate(options.max_ids);
+    }
+    Ok(diff)
+}
+
+fn user_metadata(metadata: &HashMap<String, MetadataValue>) -> HashMap<&str, &MetadataValue> {
+    metadata
+        .iter()
+        .filter(|(key, _)| key.split('.').next() != Some(SYSTEM_NAMESPACE))
+        .map(|(key, value)| (key.as_str(), value))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::TempDir;
+
+    #[test]
+    fn test_diff_collections() {
+        let mut before = Collection::new("docs".to_string(), 2);
+        let tag = |t: &str| HashMap::from([("tag".to_string(), MetadataValue::from(t))]);
+        before
+            .add(
+                vec!["a".into(), "b".into(), "c".into(), "d".into()],
+                vec![vec![1.0, 0.0], vec![0.0, 1.0], vec![1.0, 1.0], vec![1.0, -1.0]],
+                Some(vec![tag("x"), tag("x"), tag("x"), tag("x")]),
+            )
+            .unwrap();
+        let mut after = Collection::new("docs_v2".to_string(), 2);
+        // a identique (dates _system différentes), b ré-embeddé, c métadonnées seules,
+        // d supprimé, e ajouté
+        after
+            .add(
+                vec!["a".into(), "b".into(), "c".into(), "e".into()],
+                vec![vec![2.0, 0.0], vec![0.6, 0.8], vec![1.0, 1.0], vec![0.0, -1.0]],
+                Some(vec![tag("x"), tag("x"), tag("y"), tag("x")]),
+            )
+            .unwrap();
+
+        let diff = diff_collections(&before, &after, &DiffOptions::default()).unwrap();
+        assert_eq!(diff.added, ["e"]);
+        assert_eq!(diff.removed, ["d"]);
+        assert_eq!(diff.unchanged, 1);
+        assert_eq!(diff.changed.len(), 2);
+        assert_eq!(diff.changed[0].id, "b");
+        assert!((diff.changed[0].distance - 0.2).abs() < 1e-5);
+        assert!(!diff.changed[0].metadata_changed);
+        assert_eq!(diff.changed[1], ChangedId { id: "c".into(), distance: 0.0, metadata_changed: true });
+        let drift = diff.drift.unwrap();
+        assert!((drift.max - 0.2).abs() < 1e-5 && (drift.mean - 0.2).abs() < 1e-5);
+
+        let diff = diff_collections(&before, &after, &DiffOptions { max_ids: 1, ..Default::default() }).unwrap();
+        assert_eq!((diff.changed.len(), diff.total_changed), (1, 2));
+
+        let other = Collection::new("other".to_string(), 3);
+        assert!(matches!(
+            diff_collections(&before, &other, &DiffOptions::default()),
+            Err(VectorDbError::DimensionMismatch { .. })
+        ));
+    }
+
+    #[test]
+    fn test_diff_against_snapshot() {
+        use crate::client::VectorDbClient;
+
+        let root = TempDir::new("diff");
+        let client = VectorDbClient::new(root.join("db")).unwrap();
+        client.create_collection("docs".into(), 2).unwrap();
+        client
+            .with_collection_mut("docs", |c| c.add(vec!["a".into(), "b".into()], vec![vec![1.0, 0.0], vec![0.0, 1.0]], None))
+            .unwrap();
+        let snapshot = root.join("before.vdb");
+        client.export_file(&snapshot).unwrap();
+
+        client
+            .with_collection_mut("docs", |c| c.add(vec!["b".into()], vec![vec![1.0, 1.0]], None))
+            .unwrap();
+        let before = DiffSource::snapshot(&snapshot, "docs");
+        let diff = client.diff_collections(&before, &DiffSource::collection("docs"), &DiffOptions::default()).unwrap();
+        assert_eq!((diff.left.as_str(), diff.right.as_str()), ("docs@before.vdb", "docs"));
+        assert_eq!(diff.changed.iter().map(|c| c.id.as_str()).collect::<Vec<_>>(), ["b"]);
+        assert_eq!(diff.unchanged, 1);
+
+        let missing = DiffSource::snapshot(&snapshot, "other");
+        assert!(matches!(
+            client.diff_collections(&missing, &DiffSource::collection("docs"), &DiffOptions::default()),
+            Err(VectorDbError::CollectionNotFound(_))
+        ));
+    }
+}
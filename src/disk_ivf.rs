@@ -0,0 +1,509 @@
+// listes inversées IVF sur disque : les embeddings d'une collection "offloadée" sont
+// rangés cluster par cluster dans lists.dat et relus par lectures positionnelles
+// pendant les requêtes ; seuls centroïdes, ids et métadonnées restent en mémoire
+//
+// format : blocs de vecteurs f32 little-endian (un bloc contigu par cluster), puis
+// l'index bincode (dimension, centroïdes, position, ids et codes PQ de chaque liste,
+// quantificateur), puis un pied de 16 octets : position de l'index (u64 LE) et
+// DISK_LISTS_MAGIC. Les fichiers VDBLIST1, sans codes PQ, restent lisibles
+use crate::error::{Result, VectorDbError};
+use crate::pq::ProductQuantizer;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+pub const DISK_LISTS_FILE: &str = "lists.dat";
+const DISK_LISTS_MAGIC: &[u8; 8] = b"VDBLIST2";
+const DISK_LISTS_MAGIC_V1: &[u8; 8] = b"VDBLIST1";
+const FOOTER_LEN: u64 = 16;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct DiskList {
+    offset: u64,
+    ids: Vec<String>,
+    // codes PQ des vecteurs de la liste, dans l'ordre des ids ; vide sans quantificateur
+    codes: Vec<u8>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct DiskListsIndex {
+    dimension: usize,
+    centroids: Vec<Vec<f32>>,
+    lists: Vec<DiskList>,
+    pq: Option<ProductQuantizer>,
+}
+
+// index des fichiers VDBLIST1
+#[derive(Deserialize)]
+struct DiskListV1 {
+    offset: u64,
+    ids: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct DiskListsIndexV1 {
+    dimension: usize,
+    centroids: Vec<Vec<f32>>,
+    lists: Vec<DiskListV1>,
+}
+
+impl From<DiskListsIndexV1> for DiskListsIndex {
+    fn from(v1: DiskListsIndexV1) -> Self {
+        Self {
+            dimension: v1.dimension,
+            centroids: v1.centroids,
+            lists: v1.lists.into_iter().map(|l| DiskList { offset: l.offset, ids: l.ids, codes: Vec::new() }).collect(),
+            pq: None,
+        }
+    }
+}
+
+// listes les plus sondées gardées en mémoire, éviction de la moins récemment lue ;
+// le fichier n'étant jamais modifié en place, pas d'invalidation
+#[derive(Debug, Default)]
+struct ListCache {
+    capacity_bytes: usize,
+    used_bytes: usize,
+    tick: u64,
+    // cluster -> (bloc, dernier accès)
+    lists: HashMap<usize, (Arc<Vec<f32>>, u64)>,
+}
+
+impl ListCache {
+    fn get(&mut self, cluster: usize) -> Option<Arc<Vec<f32>>> {
+        self.tick += 1;
+        let tick = self.tick;
+        self.lists.get_mut(&cluster).map(|(block, last)| {
+            *last = tick;
+            block.clone()
+        })
+    }
+
+    fn insert(&mut self, cluster: usize, block: Arc<Vec<f32>>) {
+        let size = block.len() * 4;
+        if size > self.capacity_bytes || self.lists.contains_key(&cluster) {
+            return;
+        }
+        self.evict_until(size);
+        self.tick += 1;
+        self.used_bytes += size;
+        self.lists.insert(cluster, (block, self.tick));
+    }
+
+    // évince jusqu'à ce que `incoming` octets tiennent dans le budget
+    fn evict_until(&mut self, incoming: usize) {
+        while self.used_bytes + incoming > self.capacity_bytes {
+            let Some(oldest) = self.lists.iter().min_by_key(|(_, (_, last))| *last).map(|(c, _)| *c) else {
+                break;
+            };
+            self.remove(oldest);
+        }
+    }
+
+    fn remove(&mut self, cluster: usize) {
+        if let Some((block, _)) = self.lists.remove(&cluster) {
+            self.used_bytes -= block.len() * 4;
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ListCacheStats {
+    pub capacity_bytes: usize,
+    pub used_bytes: usize,
+    pub cached_lists: usize,
+    pub hits: u64,
+    pub misses: u64,
+    pub hit_rate: f64,
+}
+
+#[derive(Debug)]
+pub struct DiskLists {
+    path: PathBuf,
+    file: File,
+    file_bytes: u64,
+    index: DiskListsIndex,
+    // id -> (cluster, position dans la liste)
+    locations: HashMap<String, (usize, usize)>,
+    cache: Mutex<ListCache>,
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+impl DiskLists {
+    pub fn open(path: &Path) -> Result<Self> {
+        let corrupt = |reason: &str| VectorDbError::CorruptData {
+            path: path.display().to_string(),
+            reason: reason.to_string(),
+        };
+        let file = File::open(path)?;
+        let file_bytes = file.metadata()?.len();
+        if file_bytes < FOOTER_LEN {
+            return Err(corrupt("file too short"));
+        }
+
+        let mut footer = [0u8; FOOTER_LEN as usize];
+        read_at(&file, &mut footer, file_bytes - FOOTER_LEN)?;
+        let v1 = &footer[8..] == DISK_LISTS_MAGIC_V1;
+        if &footer[8..] != DISK_LISTS_MAGIC && !v1 {
+            return Err(corrupt("not a disk lists file"));
+        }
+        let index_offset = u64::from_le_bytes(footer[..8].try_into().unwrap());
+        if index_offset > file_bytes - FOOTER_LEN {
+            return Err(corrupt("index offset out of range"));
+        }
+
+        let mut raw = vec![0u8; (file_bytes - FOOTER_LEN - index_offset) as usize];
+        read_at(&file, &mut raw, index_offset)?;
+        let index: DiskListsIndex = if v1 {
+            bincode::deserialize::<DiskListsIndexV1>(&raw)?.into()
+        } else {
+            bincode::deserialize(&raw)?
+        };
+        if let Some(ref pq) = index.pq {
+            if index.lists.iter().any(|l| l.codes.len() != l.ids.len() * pq.code_len()) {
+                return Err(corrupt("PQ codes do not match list lengths"));
+            }
+        }
+
+        let mut locations = HashMap::new();
+        for (cluster, list) in index.lists.iter().enumerate() {
+            let end = list.offset + (list.ids.len() * index.dimension * 4) as u64;
+            if end > index_offset {
+                return Err(corrupt("list block out of range"));
+            }
+            for (pos, id) in list.ids.iter().enumerate() {
+                locations.insert(id.clone(), (cluster, pos));
+            }
+        }
+
+        Ok(Self {
+            path: path.to_path_buf(),
+            file,
+            file_bytes,
+            index,
+            locations,
+            cache: Mutex::default(),
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+        })
+    }
+
+    // budget du cache de listes, 0 = désactivé ; réduit, évince les plus anciennes
+    pub fn set_cache_capacity(&self, capacity_bytes: usize) {
+        let mut cache = self.cache.lock().unwrap();
+        cache.capacity_bytes = capacity_bytes;
+        cache.evict_until(0);
+    }
+
+    pub fn cache_stats(&self) -> ListCacheStats {
+        let cache = self.cache.lock().unwrap();
+        let hits = self.hits.load(Ordering::Relaxed);
+        let misses = self.misses.load(Ordering::Relaxed);
+        ListCacheStats {
+            capacity_bytes: cache.capacity_bytes,
+            used_bytes: cache.used_bytes,
+            cached_lists: cache.lists.len(),
+            hits,
+            misses,
+            hit_rate: if hits + misses > 0 { hits as f64 / (hits + misses) as f64 } else { 0.0 },
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn file_bytes(&self) -> u64 {
+        self.file_bytes
+    }
+
+    pub fn dimension(&self) -> usize {
+        self.index.dimension
+    }
+
+    pub fn centroids(&self) -> &[Vec<f32>] {
+        &self.index.centroids
+    }
+
+    pub fn n_lists(&self) -> usize {
+        self.index.lists.len()
+    }
+
+    pub fn ids(&self, cluster: usize) -> &[String] {
+        &self.index.lists[cluster].ids
+    }
+
+    // quantificateur de l'offload, None sans IVF-PQ ou pour un fichier VDBLIST1
+    pub fn pq(&self) -> Option<&ProductQuantizer> {
+        self.index.pq.as_ref()
+    }
+
+    // codes PQ de la liste `cluster`, alignés sur ids(cluster)
+    pub fn codes(&self, cluster: usize) -> &[u8] {
+        &self.index.lists[cluster].codes
+    }
+
+    // vecteurs écrits dans le fichier, y compris ceux supprimés depuis
+    pub fn len(&self) -> usize {
+        self.locations.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.locations.is_empty()
+    }
+
+    pub fn contains(&self, id: &str) -> bool {
+        self.locations.contains_key(id)
+    }
+
+    // (cluster, position dans sa liste)
+    pub fn location(&self, id: &str) -> Option<(usize, usize)> {
+        self.locations.get(id).copied()
+    }
+
+    // bloc d'une liste, servi par le cache s'il y est ; les lectures concurrentes
+    // d'une même liste absente peuvent toutes aller au disque
+    pub fn cached_list(&self, cluster: usize) -> Result<Arc<Vec<f32>>> {
+        let cached = {
+            let mut cache = self.cache.lock().unwrap();
+            if cache.capacity_bytes == 0 {
+                None
+            } else {
+                Some(cache.get(cluster))
+            }
+        };
+        match cached {
+            None => self.read_list(cluster).map(Arc::new),
+            Some(Some(block)) => {
+                self.hits.fetch_add(1, Ordering::Relaxed);
+                Ok(block)
+            }
+            Some(None) => {
+                self.misses.fetch_add(1, Ordering::Relaxed);
+                let block = Arc::new(self.read_list(cluster)?);
+                self.cache.lock().unwrap().insert(cluster, block.clone());
+                Ok(block)
+            }
+        }
+    }
+
+    // bloc d'une liste : ids(cluster).len() vecteurs à la suite, en une lecture
+    pub fn read_list(&self, cluster: usize) -> Result<Vec<f32>> {
+        let list = &self.index.lists[cluster];
+        self.read_floats(list.offset, list.ids.len() * self.index.dimension)
+    }
+
+    pub fn read_vector(&self, id: &str) -> Result<Option<Vec<f32>>> {
+        let Some(&(cluster, pos)) = self.locations.get(id) else {
+            return Ok(None);
+        };
+        let dimension = self.index.dimension;
+        let offset = self.index.lists[cluster].offset + (pos * dimension * 4) as u64;
+        self.read_floats(offset, dimension).map(Some)
+    }
+
+    fn read_floats(&self, offset: u64, count: usize) -> Result<Vec<f32>> {
+        let mut raw = vec![0u8; count * 4];
+        read_at(&self.file, &mut raw, offset)?;
+        Ok(raw
+            .chunks_exact(4)
+            .map(|b| f32::from_le_bytes(b.try_into().unwrap()))
+            .collect())
+    }
+}
+
+// écriture d'un fichier de listes, une liste après l'autre dans l'ordre des clusters :
+// seule la liste en cours est en mémoire. Le fichier n'apparaît qu'à finish()
+pub struct DiskListsWriter {
+    path: PathBuf,
+    tmp_path: PathBuf,
+    writer: BufWriter<File>,
+    offset: u64,
+    index: DiskListsIndex,
+}
+
+impl DiskListsWriter {
+    pub fn create(path: &Path, dimension: usize, centroids: Vec<Vec<f32>>) -> Result<Self> {
+        let tmp_path = path.with_extension("dat.tmp");
+        let writer = BufWriter::with_capacity(512 * 1024, File::create(&tmp_path)?);
+        Ok(Self {
+            path: path.to_path_buf(),
+            tmp_path,
+            writer,
+            offset: 0,
+            index: DiskListsIndex { dimension, centroids, lists: Vec::new(), pq: None },
+        })
+    }
+
+    // chaque liste écrite est aussi encodée par `pq`
+    pub fn with_pq(mut self, pq: Option<ProductQuantizer>) -> Self {
+        self.index.pq = pq;
+        self
+    }
+
+    // liste du cluster suivant
+    pub fn write_list(&mut self, entries: &[(String, Vec<f32>)]) -> Result<()> {
+        let dimension = self.index.dimension;
+        let mut ids = Vec::with_capacity(entries.len());
+        let mut codes = Vec::new();
+        for (id, embedding) in entries {
+            if embedding.len() != dimension {
+                return Err(VectorDbError::DimensionMismatch { expected: dimension, actual: embedding.len() });
+            }
+            for value in embedding {
+                self.writer.write_all(&value.to_le_bytes())?;
+            }
+            ids.push(id.clone());
+            if let Some(ref pq) = self.index.pq {
+                pq.encode_into(embedding, &mut codes);
+            }
+        }
+        self.index.lists.push(DiskList { offset: self.offset, ids, codes });
+        self.offset += (entries.len() * dimension * 4) as u64;
+        Ok(())
+    }
+
+    pub fn finish(mut self) -> Result<DiskLists> {
+        if self.index.lists.len() != self.index.centroids.len() {
+            return Err(VectorDbError::InvalidConfig(format!(
+                "disk lists: {} lists written for {} centroids",
+                self.index.lists.len(),
+                self.index.centroids.len()
+            )));
+        }
+        bincode::serialize_into(&mut self.writer, &self.index)?;
+        self.writer.write_all(&self.offset.to_le_bytes())?;
+        self.writer.write_all(DISK_LISTS_MAGIC)?;
+        let file = self.writer.into_inner().map_err(|e| e.into_error())?;
+        file.sync_all()?;
+        fs::rename(&self.tmp_path, &self.path)?;
+        DiskLists::open(&self.path)
+    }
+}
+
+#[cfg(unix)]
+pub(crate) fn read_at(file: &File, buf: &mut [u8], offset: u64) -> std::io::Result<()> {
+    use std::os::unix::fs::FileExt;
+    file.read_exact_at(buf, offset)
+}
+
+#[cfg(windows)]
+pub(crate) fn read_at(file: &File, buf: &mut [u8], offset: u64) -> std::io::Result<()> {
+    use std::os::windows::fs::FileExt;
+    let mut done = 0;
+    while done < buf.len() {
+        match file.seek_read(&mut buf[done..], offset + done as u64)? {
+            0 => return Err(std::io::ErrorKind::UnexpectedEof.into()),
+            n => done += n,
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::TempDir;
+
+    #[test]
+    fn test_disk_lists_roundtrip() {
+        let dir = TempDir::new("disk-ivf");
+        let path = dir.join(DISK_LISTS_FILE);
+
+        let mut writer = DiskListsWriter::create(&path, 2, vec![vec![1.0, 0.0], vec![0.0, 1.0]]).unwrap();
+        writer.write_list(&[("a".into(), vec![1.0, 0.0]), ("b".into(), vec![0.9, 0.1])]).unwrap();
+        writer.write_list(&[("c".into(), vec![0.0, 1.0])]).unwrap();
+        let lists = writer.finish().unwrap();
+
+        let lists = DiskLists::open(lists.path()).unwrap();
+        assert_eq!((lists.len(), lists.n_lists(), lists.dimension()), (3, 2, 2));
+        assert_eq!(lists.ids(0), ["a", "b"]);
+        assert_eq!(lists.read_list(0).unwrap(), vec![1.0, 0.0, 0.9, 0.1]);
+        assert_eq!(lists.read_vector("c").unwrap(), Some(vec![0.0, 1.0]));
+        assert_eq!(lists.read_vector("missing").unwrap(), None);
+
+        // cache d'une seule liste de 2 vecteurs : la liste 1 évince la liste 0
+        lists.set_cache_capacity(16);
+        lists.cached_list(0).unwrap();
+        assert_eq!(*lists.cached_list(0).unwrap(), vec![1.0, 0.0, 0.9, 0.1]);
+        lists.cached_list(1).unwrap();
+        lists.cached_list(0).unwrap();
+        let stats = lists.cache_stats();
+        assert_eq!((stats.hits, stats.misses, stats.cached_lists, stats.used_bytes), (1, 3, 1, 16));
+        lists.set_cache_capacity(0);
+        assert_eq!(lists.cache_stats().used_bytes, 0);
+
+        // codes PQ écrits avec les listes et relus tels quels
+        let sample: Vec<Vec<f32>> = (0..20).map(|i| vec![(i as f32).cos(), (i as f32).sin()]).collect();
+        let pq = crate::pq::ProductQuantizer::train(&sample, 2);
+        let mut writer = DiskListsWriter::create(&path, 2, vec![vec![1.0, 0.0]]).unwrap().with_pq(Some(pq.clone()));
+        writer.write_list(&[("a".into(), sample[0].clone()), ("b".into(), sample[5].clone())]).unwrap();
+        let lists = writer.finish().unwrap();
+        assert_eq!(lists.pq().unwrap().code_len(), 2);
+        assert_eq!(lists.codes(0), [pq.encode(&sample[0]), pq.encode(&sample[5])].concat());
+
+        fs::write(&path, b"garbage").unwrap();
+        assert!(matches!(DiskLists::open(&path), Err(VectorDbError::CorruptData { .. })));
+    }
+
+    #[test]
+    fn test_offloaded_collection() {
+        use crate::client::VectorDbClient;
+        use crate::collection::QueryOptions;
+
+        let dir = TempDir::new("offload");
+        let mut client = VectorDbClient::new(&dir).unwrap();
+        client.set_list_cache_bytes(1 << 20);
+        client.create_collection_with_ivf("docs".into(), 3, 4).unwrap();
+        let ids = (0..200).map(|i| format!("v{}", i)).collect();
+        let embeddings = (0..200)
+            .map(|i| vec![(i as f32 * 0.7).cos(), (i as f32 * 0.3).sin(), (i % 7) as f32 / 7.0])
+            .collect();
+        client.with_collection_mut("docs", |c| c.add(ids, embeddings, None)).unwrap();
+
+        let query = [0.3, 0.8, 0.2];
+        let exact = QueryOptions { exact: true, ..Default::default() };
+        let before = client.with_collection("docs", |c| c.exact_results(&query, 5, None, &exact)).unwrap().unwrap();
+        let memory = client.stats("docs").unwrap().estimated_memory_bytes;
+
+        let stats = client.offload_collection("docs").unwrap();
+        let disk = stats.index_info.unwrap().disk.unwrap();
+        assert_eq!(disk.vectors, 200);
+        assert!(stats.estimated_memory_bytes < memory);
+
+        // écritures après l'offload : en mémoire, et tout de suite dans l'index
+        client.with_collection_mut("docs", |c| c.delete(vec![before[1].id.clone()])).unwrap();
+        client
+            .with_collection_mut("docs", |c| c.add(vec!["late".into()], vec![query.to_vec()], None))
+            .unwrap();
+        client.reload_collection("docs").unwrap();
+
+        client
+            .with_collection_mut("docs", |c| {
+                assert!(c.is_offloaded());
+                let results = c.exact_results(&query, 5, None, &exact)?;
+                assert_eq!(results[0].id, "late");
+                assert_eq!(results[1].id, before[0].id);
+                assert!((results[1].distance - before[0].distance).abs() < 1e-6);
+                assert!(results.iter().all(|r| r.id != before[1].id));
+
+                let via_index = c.query(&query, 2, None)?;
+                assert_eq!((via_index[0].id.as_str(), via_index[1].id.as_str()), ("late", before[0].id.as_str()));
+
+                let stored = c.get(Some(vec![before[0].id.clone()]), None)?.embeddings.unwrap();
+                assert_eq!(stored[0].len(), 3);
+
+                // listes déjà lues par la recherche exhaustive : servies par le cache
+                let cache = c.stats().index_info.unwrap().disk.unwrap().cache;
+                assert_eq!((cache.cached_lists, cache.misses), (4, 4));
+                assert!(cache.hit_rate >= 0.5);
+                Ok(())
+            })
+            .unwrap();
+    }
+}
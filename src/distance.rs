@@ -1,6 +1,76 @@
-#[inline(always)]
+use std::sync::OnceLock;
+
+// noyaux de produit scalaire, choisis une fois au premier appel selon le CPU :
+// AVX-512 puis AVX2 + FMA sur x86_64, NEON sur aarch64, boucle déroulée sinon.
+// VECTORDB_SIMD=off force la boucle déroulée (comparaisons, débogage)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SimdKernel {
+    Scalar,
+    Avx2,
+    Avx512,
+    Neon,
+}
+
+impl SimdKernel {
+    pub fn name(self) -> &'static str {
+        match self {
+            SimdKernel::Scalar => "scalar",
+            SimdKernel::Avx2 => "avx2",
+            SimdKernel::Avx512 => "avx512",
+            SimdKernel::Neon => "neon",
+        }
+    }
+
+    // ce CPU sait-il exécuter ce noyau ?
+    pub fn is_available(self) -> bool {
+        match self {
+            SimdKernel::Scalar => true,
+            #[cfg(target_arch = "x86_64")]
+            SimdKernel::Avx512 => is_x86_feature_detected!("avx512f"),
+            #[cfg(target_arch = "x86_64")]
+            SimdKernel::Avx2 => is_x86_feature_detected!("avx2") && is_x86_feature_detected!("fma"),
+            #[cfg(target_arch = "aarch64")]
+            SimdKernel::Neon => true,
+            #[allow(unreachable_patterns)]
+            _ => false,
+        }
+    }
+
+    // noyaux utilisables sur ce CPU, du plus rapide au plus lent
+    pub fn available() -> Vec<SimdKernel> {
+        [SimdKernel::Avx512, SimdKernel::Avx2, SimdKernel::Neon, SimdKernel::Scalar]
+            .into_iter()
+            .filter(|kernel| kernel.is_available())
+            .collect()
+    }
+
+    // lui-même s'il est disponible, la boucle déroulée sinon
+    fn or_scalar(self) -> SimdKernel {
+        if self.is_available() {
+            self
+        } else {
+            SimdKernel::Scalar
+        }
+    }
+}
+
+static KERNEL: OnceLock<SimdKernel> = OnceLock::new();
+
+pub fn simd_kernel() -> SimdKernel {
+    *KERNEL.get_or_init(|| {
+        if std::env::var("VECTORDB_SIMD").is_ok_and(|v| v == "off") {
+            return SimdKernel::Scalar;
+        }
+        SimdKernel::available()[0]
+    })
+}
+
+// en dessous, l'appel indirect coûte plus que la boucle
+const SIMD_MIN_LEN: usize = 16;
+
+#[inline]
 pub fn dot_product(a: &[f32], b: &[f32]) -> f32 {
-    let len = a.len();
+    let len = a.len().min(b.len());
 
     // optimisation pour petits vecteurs
     if len < 8 {
@@ -10,6 +80,83 @@ pub fn dot_product(a: &[f32], b: &[f32]) -> f32 {
         }
         return sum;
     }
+    if len < SIMD_MIN_LEN {
+        return dot_product_scalar(a, b);
+    }
+    dot_product_kernel(simd_kernel(), a, b)
+}
+
+// produit scalaire avec un noyau donné ; un noyau que ce CPU ne sait pas exécuter
+// retombe sur la boucle déroulée. Longueurs différentes : calcul sur la plus courte
+pub fn dot_product_with(kernel: SimdKernel, a: &[f32], b: &[f32]) -> f32 {
+    dot_product_kernel(kernel.or_scalar(), a, b)
+}
+
+// `kernel` doit être disponible : simd_kernel() l'est, les autres passent par or_scalar
+fn dot_product_kernel(kernel: SimdKernel, a: &[f32], b: &[f32]) -> f32 {
+    let len = a.len().min(b.len());
+    let (a, b) = (&a[..len], &b[..len]);
+    match kernel {
+        // SAFETY : noyau disponible sur ce CPU (voir plus haut), longueurs égales
+        #[cfg(target_arch = "x86_64")]
+        SimdKernel::Avx512 => unsafe { x86::dot_avx512(a, b) },
+        #[cfg(target_arch = "x86_64")]
+        SimdKernel::Avx2 => unsafe { x86::dot_avx2(a, b) },
+        #[cfg(target_arch = "aarch64")]
+        SimdKernel::Neon => unsafe { neon::dot(a, b) },
+        _ => dot_product_scalar(a, b),
+    }
+}
+
+// une requête contre plusieurs vecteurs (recherche exhaustive) : par groupes de
+// quatre, chaque bloc de la requête chargé une fois pour les quatre vecteurs.
+// out[i] = dot_product(query, vectors[i])
+pub fn dot_products(query: &[f32], vectors: &[&[f32]], out: &mut [f32]) {
+    dot_products_with(simd_kernel(), query, vectors, out)
+}
+
+// un noyau indisponible sur ce CPU retombe sur la boucle déroulée
+pub fn dot_products_with(kernel: SimdKernel, query: &[f32], vectors: &[&[f32]], out: &mut [f32]) {
+    assert_eq!(vectors.len(), out.len());
+    let kernel = kernel.or_scalar();
+    let mut groups = vectors.chunks_exact(4);
+    let mut outs = out.chunks_exact_mut(4);
+    for (group, out) in (&mut groups).zip(&mut outs) {
+        let group = [group[0], group[1], group[2], group[3]];
+        // vecteur d'une autre longueur : un par un
+        if query.len() < SIMD_MIN_LEN || group.iter().any(|v| v.len() != query.len()) {
+            for (v, o) in group.iter().zip(out.iter_mut()) {
+                *o = dot_product(query, v);
+            }
+            continue;
+        }
+        let dots = match kernel {
+            // SAFETY : noyau disponible (or_scalar), longueurs vérifiées
+            #[cfg(target_arch = "x86_64")]
+            SimdKernel::Avx512 => unsafe { x86::dot4_avx512(query, group) },
+            #[cfg(target_arch = "x86_64")]
+            SimdKernel::Avx2 => unsafe { x86::dot4_avx2(query, group) },
+            #[cfg(target_arch = "aarch64")]
+            SimdKernel::Neon => unsafe { neon::dot4(query, group) },
+            _ => group.map(|v| dot_product_scalar(query, v)),
+        };
+        out.copy_from_slice(&dots);
+    }
+    for (v, o) in groups.remainder().iter().zip(outs.into_remainder()) {
+        *o = dot_product_kernel(kernel, query, v);
+    }
+}
+
+// out[i] = cosine_distance(query, vectors[i])
+pub fn cosine_distances(query: &[f32], vectors: &[&[f32]], out: &mut [f32]) {
+    dot_products(query, vectors, out);
+    for d in out.iter_mut() {
+        *d = 1.0 - *d;
+    }
+}
+
+fn dot_product_scalar(a: &[f32], b: &[f32]) -> f32 {
+    let len = a.len().min(b.len());
 
     // déroulement de boucle pour vectorisation auto
     let chunks = len / 4;
@@ -39,11 +186,188 @@ pub fn dot_product(a: &[f32], b: &[f32]) -> f32 {
     sum
 }
 
+#[cfg(target_arch = "x86_64")]
+mod x86 {
+    use std::arch::x86_64::*;
+
+    #[target_feature(enable = "avx2,fma")]
+    unsafe fn hsum256(v: __m256) -> f32 {
+        let pair = _mm_add_ps(_mm256_castps256_ps128(v), _mm256_extractf128_ps(v, 1));
+        let quad = _mm_add_ps(pair, _mm_movehl_ps(pair, pair));
+        _mm_cvtss_f32(_mm_add_ss(quad, _mm_movehdup_ps(quad)))
+    }
+
+    // 4 accumulateurs de 8 lanes, pour couvrir la latence des FMA
+    #[target_feature(enable = "avx2,fma")]
+    pub(super) unsafe fn dot_avx2(a: &[f32], b: &[f32]) -> f32 {
+        let n = a.len().min(b.len());
+        let (pa, pb) = (a.as_ptr(), b.as_ptr());
+        let mut acc = [_mm256_setzero_ps(); 4];
+        let mut i = 0;
+        while i + 32 <= n {
+            for (k, acc) in acc.iter_mut().enumerate() {
+                let j = i + 8 * k;
+                *acc = _mm256_fmadd_ps(_mm256_loadu_ps(pa.add(j)), _mm256_loadu_ps(pb.add(j)), *acc);
+            }
+            i += 32;
+        }
+        while i + 8 <= n {
+            acc[0] = _mm256_fmadd_ps(_mm256_loadu_ps(pa.add(i)), _mm256_loadu_ps(pb.add(i)), acc[0]);
+            i += 8;
+        }
+        let sum = _mm256_add_ps(_mm256_add_ps(acc[0], acc[1]), _mm256_add_ps(acc[2], acc[3]));
+        hsum256(sum) + (i..n).map(|j| a[j] * b[j]).sum::<f32>()
+    }
+
+    #[target_feature(enable = "avx2,fma")]
+    pub(super) unsafe fn dot4_avx2(q: &[f32], v: [&[f32]; 4]) -> [f32; 4] {
+        let n = q.len();
+        let mut acc = [_mm256_setzero_ps(); 4];
+        let mut i = 0;
+        while i + 8 <= n {
+            let x = _mm256_loadu_ps(q.as_ptr().add(i));
+            for (acc, v) in acc.iter_mut().zip(v) {
+                *acc = _mm256_fmadd_ps(x, _mm256_loadu_ps(v.as_ptr().add(i)), *acc);
+            }
+            i += 8;
+        }
+        let mut dots = [0.0; 4];
+        for ((dot, acc), v) in dots.iter_mut().zip(acc).zip(v) {
+            *dot = hsum256(acc) + (i..n).map(|j| q[j] * v[j]).sum::<f32>();
+        }
+        dots
+    }
+
+    #[target_feature(enable = "avx512f")]
+    pub(super) unsafe fn dot_avx512(a: &[f32], b: &[f32]) -> f32 {
+        let n = a.len().min(b.len());
+        let (pa, pb) = (a.as_ptr(), b.as_ptr());
+        let mut acc = [_mm512_setzero_ps(); 4];
+        let mut i = 0;
+        while i + 64 <= n {
+            for (k, acc) in acc.iter_mut().enumerate() {
+                let j = i + 16 * k;
+                *acc = _mm512_fmadd_ps(_mm512_loadu_ps(pa.add(j)), _mm512_loadu_ps(pb.add(j)), *acc);
+            }
+            i += 64;
+        }
+        while i + 16 <= n {
+            acc[0] = _mm512_fmadd_ps(_mm512_loadu_ps(pa.add(i)), _mm512_loadu_ps(pb.add(i)), acc[0]);
+            i += 16;
+        }
+        // reste : chargement masqué, sans lecture au-delà des tranches
+        if i < n {
+            let mask: __mmask16 = (1 << (n - i)) - 1;
+            let x = _mm512_maskz_loadu_ps(mask, pa.add(i));
+            acc[1] = _mm512_fmadd_ps(x, _mm512_maskz_loadu_ps(mask, pb.add(i)), acc[1]);
+        }
+        _mm512_reduce_add_ps(_mm512_add_ps(_mm512_add_ps(acc[0], acc[1]), _mm512_add_ps(acc[2], acc[3])))
+    }
+
+    #[target_feature(enable = "avx512f")]
+    pub(super) unsafe fn dot4_avx512(q: &[f32], v: [&[f32]; 4]) -> [f32; 4] {
+        let n = q.len();
+        let mut acc = [_mm512_setzero_ps(); 4];
+        let mut i = 0;
+        while i + 16 <= n {
+            let x = _mm512_loadu_ps(q.as_ptr().add(i));
+            for (acc, v) in acc.iter_mut().zip(v) {
+                *acc = _mm512_fmadd_ps(x, _mm512_loadu_ps(v.as_ptr().add(i)), *acc);
+            }
+            i += 16;
+        }
+        if i < n {
+            let mask: __mmask16 = (1 << (n - i)) - 1;
+            let x = _mm512_maskz_loadu_ps(mask, q.as_ptr().add(i));
+            for (acc, v) in acc.iter_mut().zip(v) {
+                *acc = _mm512_fmadd_ps(x, _mm512_maskz_loadu_ps(mask, v.as_ptr().add(i)), *acc);
+            }
+        }
+        acc.map(|acc| _mm512_reduce_add_ps(acc))
+    }
+}
+
+#[cfg(target_arch = "aarch64")]
+mod neon {
+    use std::arch::aarch64::*;
+
+    #[target_feature(enable = "neon")]
+    pub(super) unsafe fn dot(a: &[f32], b: &[f32]) -> f32 {
+        let n = a.len().min(b.len());
+        let (pa, pb) = (a.as_ptr(), b.as_ptr());
+        let mut acc = [vdupq_n_f32(0.0); 4];
+        let mut i = 0;
+        while i + 16 <= n {
+            for (k, acc) in acc.iter_mut().enumerate() {
+                let j = i + 4 * k;
+                *acc = vfmaq_f32(*acc, vld1q_f32(pa.add(j)), vld1q_f32(pb.add(j)));
+            }
+            i += 16;
+        }
+        while i + 4 <= n {
+            acc[0] = vfmaq_f32(acc[0], vld1q_f32(pa.add(i)), vld1q_f32(pb.add(i)));
+            i += 4;
+        }
+        let sum = vaddq_f32(vaddq_f32(acc[0], acc[1]), vaddq_f32(acc[2], acc[3]));
+        vaddvq_f32(sum) + (i..n).map(|j| a[j] * b[j]).sum::<f32>()
+    }
+
+    #[target_feature(enable = "neon")]
+    pub(super) unsafe fn dot4(q: &[f32], v: [&[f32]; 4]) -> [f32; 4] {
+        let n = q.len();
+        let mut acc = [vdupq_n_f32(0.0); 4];
+        let mut i = 0;
+        while i + 4 <= n {
+            let x = vld1q_f32(q.as_ptr().add(i));
+            for (acc, v) in acc.iter_mut().zip(v) {
+                *acc = vfmaq_f32(*acc, x, vld1q_f32(v.as_ptr().add(i)));
+            }
+            i += 4;
+        }
+        let mut dots = [0.0; 4];
+        for ((dot, acc), v) in dots.iter_mut().zip(acc).zip(v) {
+            *dot = vaddvq_f32(acc) + (i..n).map(|j| q[j] * v[j]).sum::<f32>();
+        }
+        dots
+    }
+}
+
+// produit scalaire de codes int8 (quantification scalaire, voir sq), accumulé en
+// i32 : exact jusqu'à 2^31 / 128² = 131 072 composantes
+#[inline]
+pub fn dot_product_i8(a: &[i8], b: &[i8]) -> i32 {
+    // 4 accumulateurs, comme dot_product, pour la vectorisation auto
+    let mut sums = [0i32; 4];
+    let mut a_chunks = a.chunks_exact(4);
+    let mut b_chunks = b.chunks_exact(4);
+    for (x, y) in (&mut a_chunks).zip(&mut b_chunks) {
+        for lane in 0..4 {
+            sums[lane] += x[lane] as i32 * y[lane] as i32;
+        }
+    }
+    let rest: i32 = a_chunks.remainder().iter().zip(b_chunks.remainder()).map(|(&x, &y)| x as i32 * y as i32).sum();
+    sums.iter().sum::<i32>() + rest
+}
+
+// distance de Hamming de vecteurs binaires empaquetés (voir vector::BinaryVector) :
+// bits différents, un popcount par mot de 64 bits
+#[inline]
+pub fn hamming_distance(a: &[u64], b: &[u64]) -> u32 {
+    a.iter().zip(b).map(|(x, y)| (x ^ y).count_ones()).sum()
+}
+
 #[inline]
 pub fn cosine_distance(a: &[f32], b: &[f32]) -> f32 {
     1.0 - dot_product(a, b)
 }
 
+// distance euclidienne au carré, pour les vecteurs non normalisés (sous-vecteurs
+// de la quantification produit)
+#[inline]
+pub fn squared_l2(a: &[f32], b: &[f32]) -> f32 {
+    a.iter().zip(b).map(|(x, y)| (x - y) * (x - y)).sum()
+}
+
 #[inline]
 pub fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
     dot_product(a, b)
@@ -67,6 +391,19 @@ pub fn normalized_l2(vector: &[f32]) -> Vec<f32> {
     result
 }
 
+const EARTH_RADIUS_KM: f64 = 6371.0088;
+
+// distance orthodromique entre deux points (lat/lon en degrés)
+pub fn haversine_km(lat1: f64, lon1: f64, lat2: f64, lon2: f64) -> f64 {
+    let d_lat = (lat2 - lat1).to_radians();
+    let d_lon = (lon2 - lon1).to_radians();
+
+    let a = (d_lat / 2.0).sin().powi(2)
+        + lat1.to_radians().cos() * lat2.to_radians().cos() * (d_lon / 2.0).sin().powi(2);
+
+    2.0 * EARTH_RADIUS_KM * a.sqrt().asin()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -79,6 +416,88 @@ mod tests {
         assert_eq!(result, 32.0); // 1*4 + 2*5 + 3*6 = 32
     }
 
+    #[test]
+    fn test_dot_product_i8() {
+        let a: Vec<i8> = (-64..-55).collect();
+        let b: Vec<i8> = (0..9).map(|i| if i % 2 == 0 { 127 } else { -128 }).collect();
+        let expected: i32 = a.iter().zip(&b).map(|(&x, &y)| x as i32 * y as i32).sum();
+        assert_eq!(dot_product_i8(&a, &b), expected);
+        assert_eq!(dot_product_i8(&[-128; 8], &[-128; 8]), 8 * 16384);
+    }
+
+    // chaque noyau disponible sur ce CPU contre la boucle déroulée, restes compris
+    #[test]
+    fn test_simd_kernels_match_scalar() {
+        let value = |i: usize, seed: usize| ((i * 7919 + seed) % 1000) as f32 / 500.0 - 1.0;
+        for len in (1..140).chain([255, 256, 257, 1023, 1280, 1537]) {
+            let a: Vec<f32> = (0..len).map(|i| value(i, 1)).collect();
+            let b: Vec<f32> = (0..len).map(|i| value(i, 17)).collect();
+            let expected = dot_product_scalar(&a, &b);
+            for kernel in SimdKernel::available() {
+                let got = dot_product_with(kernel, &a, &b);
+                assert!((got - expected).abs() <= 1e-4 * (len as f32), "{} len={}", kernel.name(), len);
+            }
+        }
+        assert!(SimdKernel::available().contains(&simd_kernel()));
+    }
+
+    // noyau absent de ce CPU demandé explicitement : boucle déroulée, pas d'instruction illégale
+    #[test]
+    fn test_unavailable_kernel_falls_back() {
+        let a: Vec<f32> = (0..100).map(|i| (i as f32).sin()).collect();
+        let b: Vec<f32> = (0..100).map(|i| (i as f32).cos()).collect();
+        let refs = [b.as_slice(); 5];
+        for kernel in [SimdKernel::Avx512, SimdKernel::Avx2, SimdKernel::Neon, SimdKernel::Scalar] {
+            assert!((dot_product_with(kernel, &a, &b) - dot_product_scalar(&a, &b)).abs() < 1e-3);
+            let mut out = [0.0; 5];
+            dot_products_with(kernel, &a, &refs, &mut out);
+            assert!(out.iter().all(|d| (d - dot_product_scalar(&a, &b)).abs() < 1e-3), "{}", kernel.name());
+        }
+        assert_eq!(SimdKernel::Scalar.or_scalar(), SimdKernel::Scalar);
+    }
+
+    // vecteur plus court que la requête : préfixe commun, pas de panique
+    #[test]
+    fn test_dot_product_length_mismatch() {
+        let query: Vec<f32> = (0..100).map(|i| (i as f32).sin()).collect();
+        let short: Vec<f32> = (0..40).map(|i| (i as f32).cos()).collect();
+        let expected = dot_product_scalar(&query[..40], &short);
+        for kernel in SimdKernel::available() {
+            assert!((dot_product_with(kernel, &query, &short) - expected).abs() < 1e-3, "{}", kernel.name());
+            assert!((dot_product_with(kernel, &short, &query) - expected).abs() < 1e-3, "{}", kernel.name());
+            let mut out = [0.0; 5];
+            dot_products_with(kernel, &query, &[short.as_slice(); 5], &mut out);
+            assert!(out.iter().all(|d| (d - expected).abs() < 1e-3), "{}", kernel.name());
+        }
+        assert!((dot_product(&query, &short[..5]) - dot_product_scalar(&query[..5], &short[..5])).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_dot_products_batched() {
+        let query: Vec<f32> = (0..100).map(|i| (i as f32).sin()).collect();
+        let mut vectors: Vec<Vec<f32>> = (0..11).map(|k| (0..100).map(|i| ((i * k) as f32).cos()).collect()).collect();
+        // longueur différente dans un groupe de quatre : calcul un par un
+        vectors[5].push(1.0);
+        let refs: Vec<&[f32]> = vectors.iter().map(Vec::as_slice).collect();
+        for kernel in SimdKernel::available() {
+            let mut out = vec![0.0; refs.len()];
+            dot_products_with(kernel, &query, &refs, &mut out);
+            for (v, got) in refs.iter().zip(&out) {
+                assert!((got - dot_product_scalar(&query, v)).abs() < 1e-3, "{}", kernel.name());
+            }
+        }
+        let mut distances = vec![0.0; refs.len()];
+        cosine_distances(&query, &refs, &mut distances);
+        assert!((distances[0] - cosine_distance(&query, refs[0])).abs() < 1e-3);
+    }
+
+    #[test]
+    fn test_hamming_distance() {
+        assert_eq!(hamming_distance(&[0b1011, 0], &[0b0110, 0]), 3);
+        assert_eq!(hamming_distance(&[u64::MAX, 1], &[0, 0]), 65);
+        assert_eq!(hamming_distance(&[42, 7], &[42, 7]), 0);
+    }
+
     #[test]
     fn test_normalize_l2() {
         let mut v = vec![3.0, 4.0];
@@ -97,4 +516,12 @@ mod tests {
         let dist = cosine_distance(&a, &b);
         assert!((dist - 1.0).abs() < 1e-6); // Vecteurs orthogonaux
     }
+
+    #[test]
+    fn test_haversine_paris_lyon() {
+        // Paris -> Lyon, environ 392 km
+        let d = haversine_km(48.8566, 2.3522, 45.7640, 4.8357);
+        assert!((d - 392.0).abs() < 5.0);
+        assert!(haversine_km(10.0, 20.0, 10.0, 20.0).abs() < 1e-9);
+    }
 }
@@ -0,0 +1,213 @@
+// suivi de dérive des embeddings entre lots d'ingestion : un changement silencieux de
+// modèle en amont déplace le centroïde des nouveaux lots et/ou leur norme moyenne
+use crate::distance::{cosine_distance, normalized_l2};
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+
+pub const DEFAULT_DRIFT_THRESHOLD: f32 = 0.3;
+// nombre de lots gardés dans l'historique
+const HISTORY_LEN: usize = 20;
+
+// somme des embeddings normalisés et des normes brutes d'un ensemble de vecteurs
+#[derive(Debug, Clone)]
+struct Aggregate {
+    count: usize,
+    centroid_sum: Vec<f32>,
+    // les normes brutes ne sont connues que pour les lots vus par le moniteur
+    norm_count: usize,
+    norm_sum: f64,
+}
+
+impl Aggregate {
+    fn new(dimension: usize) -> Self {
+        Self { count: 0, centroid_sum: vec![0.0; dimension], norm_count: 0, norm_sum: 0.0 }
+    }
+
+    fn mean_norm(&self) -> Option<f64> {
+        (self.norm_count > 0).then(|| self.norm_sum / self.norm_count as f64)
+    }
+
+    fn merge(&mut self, other: &Aggregate) {
+        self.count += other.count;
+        self.norm_count += other.norm_count;
+        self.norm_sum += other.norm_sum;
+        for (a, b) in self.centroid_sum.iter_mut().zip(&other.centroid_sum) {
+            *a += b;
+        }
+    }
+}
+
+// statistiques d'un lot en cours d'ajout
+#[derive(Debug, Clone)]
+pub struct BatchSample(Aggregate);
+
+impl BatchSample {
+    pub fn new(dimension: usize) -> Self {
+        Self(Aggregate::new(dimension))
+    }
+
+    // raw_norm : norme avant normalisation, normalized : embedding stocké
+    pub fn push(&mut self, raw_norm: f32, normalized: &[f32]) {
+        let agg = &mut self.0;
+        agg.count += 1;
+        agg.norm_count += 1;
+        agg.norm_sum += raw_norm as f64;
+        for (a, v) in agg.centroid_sum.iter_mut().zip(normalized) {
+            *a += v;
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BatchDrift {
+    pub at: String,
+    pub source: String,
+    pub size: usize,
+    pub mean_norm: f32,
+    // distance cosinus entre le centroïde du lot et celui des données déjà présentes
+    pub centroid_shift: Option<f32>,
+    // écart relatif de la norme moyenne par rapport aux lots précédents
+    pub norm_change: Option<f32>,
+    // max(centroid_shift, norm_change), comparé au seuil d'alerte
+    pub score: f32,
+    pub alert: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DriftReport {
+    pub threshold: f32,
+    pub batches: u64,
+    pub alerts: u64,
+    pub max_recent_score: f32,
+    pub last_batch: Option<BatchDrift>,
+    pub recent: Vec<BatchDrift>,
+}
+
+#[derive(Debug, Default)]
+pub struct DriftMonitor {
+    // référence : données présentes au chargement puis lots successifs
+    reference: Option<Aggregate>,
+    history: VecDeque<BatchDrift>,
+    batches: u64,
+    alerts: u64,
+}
+
+impl DriftMonitor {
+    pub fn is_seeded(&self) -> bool {
+        self.reference.is_some()
+    }
+
+    // initialise la référence avec les embeddings déjà stockés (normes brutes inconnues)
+    pub fn seed<'a>(&mut self, dimension: usize, embeddings: impl Iterator<Item = &'a [f32]>) {
+        let mut reference = Aggregate::new(dimension);
+        for embedding in embeddings {
+            reference.count += 1;
+            for (a, v) in reference.centroid_sum.iter_mut().zip(embedding) {
+                *a += v;
+            }
+        }
+        self.reference = Some(reference);
+    }
+
+    // compare le lot à la référence puis l'y intègre
+    pub fn record(&mut self, sample: BatchSample, source: &str, threshold: f32) -> &BatchDrift {
+        let batch = sample.0;
+        let reference = self
+            .reference
+            .get_or_insert_with(|| Aggregate::new(batch.centroid_sum.len()));
+
+        let centroid_shift = (reference.count > 0).then(|| {
+            cosine_distance(&normalized_l2(&batch.centroid_sum), &normalized_l2(&reference.centroid_sum))
+                .max(0.0)
+        });
+        let mean_norm = batch.mean_norm().unwrap_or(0.0);
+        let norm_change = reference
+            .mean_norm()
+            .filter(|r| *r > 1e-10)
+            .map(|r| ((mean_norm - r).abs() / r) as f32);
+        let score = centroid_shift.unwrap_or(0.0).max(norm_change.unwrap_or(0.0));
+
+        reference.merge(&batch);
+
+        let drift = BatchDrift {
+            at: chrono::Utc::now().to_rfc3339(),
+            source: source.to_string(),
+            size: batch.count,
+            mean_norm: mean_norm as f32,
+            centroid_shift,
+            norm_change,
+            score,
+            alert: score > threshold,
+        };
+
+        self.batches += 1;
+        if drift.alert {
+            self.alerts += 1;
+        }
+        if self.history.len() == HISTORY_LEN {
+            self.history.pop_front();
+        }
+        self.history.push_back(drift);
+        self.history.back().unwrap()
+    }
+
+    pub fn report(&self, threshold: f32) -> DriftReport {
+        DriftReport {
+            threshold,
+            batches: self.batches,
+            alerts: self.alerts,
+            max_recent_score: self.history.iter().map(|d| d.score).fold(0.0, f32::max),
+            last_batch: self.history.back().cloned(),
+            recent: self.history.iter().cloned().collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample(vectors: &[Vec<f32>]) -> BatchSample {
+        let mut sample = BatchSample::new(vectors[0].len());
+        for v in vectors {
+            let norm = v.iter().map(|x| x * x).sum::<f32>().sqrt();
+            sample.push(norm, &normalized_l2(v));
+        }
+        sample
+    }
+
+    #[test]
+    fn test_drift_detects_model_change() {
+        let mut monitor = DriftMonitor::default();
+
+        let first = monitor.record(sample(&[vec![1.0, 0.1, 0.0], vec![0.9, 0.2, 0.0]]), "api", 0.3);
+        assert_eq!(first.centroid_shift, None);
+        assert!(!first.alert);
+
+        // même distribution : pas d'alerte
+        let same = monitor.record(sample(&[vec![1.0, 0.15, 0.0], vec![0.95, 0.1, 0.0]]), "api", 0.3);
+        assert!(same.score < 0.05, "score {}", same.score);
+        assert!(!same.alert);
+
+        // autre espace et autre échelle de norme
+        let shifted = monitor.record(sample(&[vec![0.0, 0.1, 8.0], vec![0.0, 0.2, 9.0]]), "api", 0.3);
+        assert!(shifted.centroid_shift.unwrap() > 0.9);
+        assert!(shifted.norm_change.unwrap() > 5.0);
+        assert!(shifted.alert);
+
+        let report = monitor.report(0.3);
+        assert_eq!((report.batches, report.alerts), (3, 1));
+        assert_eq!(report.recent.len(), 3);
+    }
+
+    #[test]
+    fn test_seeded_reference_has_no_norm() {
+        let mut monitor = DriftMonitor::default();
+        let stored = [normalized_l2(&[1.0, 0.0]), normalized_l2(&[0.9, 0.1])];
+        monitor.seed(2, stored.iter().map(|v| v.as_slice()));
+
+        let drift = monitor.record(sample(&[vec![5.0, 0.2]]), "api", 0.3);
+        assert!(drift.centroid_shift.unwrap() < 0.01);
+        assert_eq!(drift.norm_change, None);
+    }
+}
@@ -0,0 +1,170 @@
+use crate::error::{Result, VectorDbError};
+use std::collections::HashMap;
+
+// modèles d'embedding courants et leur dimension
+const KNOWN_MODELS: &[(&str, usize)] = &[
+    ("text-embedding-ada-002", 1536),
+    ("text-embedding-3-small", 1536),
+    ("text-embedding-3-large", 3072),
+    ("all-MiniLM-L6-v2", 384),
+    ("all-mpnet-base-v2", 768),
+    ("nomic-embed-text", 768),
+    ("bge-base-en-v1.5", 768),
+    ("bge-large-en-v1.5", 1024),
+    ("mistral-embed", 1024),
+];
+
+// calcule les embeddings d'un lot de textes (API distante, modèle local...) ;
+// `model` doit correspondre au embedding_model de la collection cible
+pub trait EmbeddingProvider: Send + Sync {
+    fn model(&self) -> &str;
+    fn dimension(&self) -> usize;
+    fn embed(&self, texts: &[String]) -> Result<Vec<Vec<f32>>>;
+}
+
+// modèle intégré réservé aux tests et démos : accepté pour toute dimension
+pub const HASH_EMBEDDING_MODEL: &str = "hash-ngram-v1";
+
+// embeddings déterministes sans API externe : mots et trigrammes de caractères
+// hachés (FNV-1a) dans un vecteur de dimension fixe, signe tiré du hash, puis
+// normalisation L2. Mêmes vecteurs d'une exécution et d'une machine à l'autre ;
+// proximité lexicale seulement, aucune sémantique
+#[derive(Debug, Clone)]
+pub struct HashEmbedding {
+    dimension: usize,
+}
+
+impl HashEmbedding {
+    pub fn new(dimension: usize) -> Self {
+        Self { dimension: dimension.max(1) }
+    }
+
+    // fournisseur intégré d'une collection, si son modèle est HASH_EMBEDDING_MODEL
+    pub fn for_model(model: Option<&str>, dimension: usize) -> Option<Self> {
+        (model == Some(HASH_EMBEDDING_MODEL)).then(|| Self::new(dimension))
+    }
+
+    fn fnv1a(bytes: &[u8]) -> u64 {
+        bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, b| {
+            (hash ^ *b as u64).wrapping_mul(0x0000_0100_0000_01b3)
+        })
+    }
+
+    fn add_feature(&self, vector: &mut [f32], feature: &str) {
+        let hash = Self::fnv1a(feature.as_bytes());
+        let idx = (hash % self.dimension as u64) as usize;
+        vector[idx] += if hash >> 63 == 0 { 1.0 } else { -1.0 };
+    }
+
+    pub fn embed_text(&self, text: &str) -> Vec<f32> {
+        let mut vector = vec![0.0; self.dimension];
+        let lower = text.to_lowercase();
+        for word in lower.split(|c: char| !c.is_alphanumeric()).filter(|w| !w.is_empty()) {
+            self.add_feature(&mut vector, &format!("w:{}", word));
+            let chars: Vec<char> = format!("#{}#", word).chars().collect();
+            for gram in chars.windows(3) {
+                self.add_feature(&mut vector, &format!("c:{}", gram.iter().collect::<String>()));
+            }
+        }
+        crate::distance::normalize_l2(&mut vector);
+        vector
+    }
+}
+
+impl EmbeddingProvider for HashEmbedding {
+    fn model(&self) -> &str {
+        HASH_EMBEDDING_MODEL
+    }
+
+    fn dimension(&self) -> usize {
+        self.dimension
+    }
+
+    fn embed(&self, texts: &[String]) -> Result<Vec<Vec<f32>>> {
+        Ok(texts.iter().map(|t| self.embed_text(t)).collect())
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ModelRegistry {
+    models: HashMap<String, usize>,
+}
+
+impl Default for ModelRegistry {
+    fn default() -> Self {
+        let models = KNOWN_MODELS
+            .iter()
+            .map(|(name, dim)| (name.to_string(), *dim))
+            .collect();
+        Self { models }
+    }
+}
+
+impl ModelRegistry {
+    pub fn empty() -> Self {
+        Self { models: HashMap::new() }
+    }
+
+    pub fn register(&mut self, name: String, dimension: usize) {
+        self.models.insert(name, dimension);
+    }
+
+    pub fn dimension(&self, name: &str) -> Option<usize> {
+        self.models.get(name).copied()
+    }
+
+    // un modèle inconnu est accepté tel quel, seul un modèle connu impose sa dimension
+    pub fn check(&self, name: &str, dimension: usize) -> Result<()> {
+        match self.dimension(name) {
+            Some(expected) if expected != dimension => Err(VectorDbError::InvalidConfig(format!(
+                "embedding model '{}' produces {}-dimensional vectors, got dimension {}",
+                name, expected, dimension
+            ))),
+            _ => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_known_model_dimension() {
+        let registry = ModelRegistry::default();
+        assert!(registry.check("text-embedding-3-small", 1536).is_ok());
+        assert!(registry.check("text-embedding-3-small", 768).is_err());
+        assert!(registry.check("my-custom-model", 42).is_ok());
+    }
+
+    #[test]
+    fn test_register_model() {
+        let mut registry = ModelRegistry::empty();
+        registry.register("custom".to_string(), 128);
+        assert_eq!(registry.dimension("custom"), Some(128));
+        assert!(registry.check("custom", 256).is_err());
+    }
+
+    #[test]
+    fn test_hash_embedding_is_deterministic() {
+        let provider = HashEmbedding::new(64);
+        let texts = vec![
+            "Le chat dort sur le canapé".to_string(),
+            "le CHAT dort sur le canapé !".to_string(),
+            "Rust compile vers du code natif".to_string(),
+        ];
+        let vectors = provider.embed(&texts).unwrap();
+
+        assert!(vectors.iter().all(|v| v.len() == 64));
+        // casse et ponctuation ignorées
+        assert_eq!(vectors[0], vectors[1]);
+        assert_eq!(vectors[0], HashEmbedding::new(64).embed_text(&texts[0]));
+
+        let sim = |a: &[f32], b: &[f32]| crate::distance::dot_product(a, b);
+        let close = HashEmbedding::new(64).embed_text("le chat dort");
+        assert!(sim(&close, &vectors[0]) > sim(&close, &vectors[2]));
+
+        assert!(HashEmbedding::for_model(Some(HASH_EMBEDDING_MODEL), 8).is_some());
+        assert!(HashEmbedding::for_model(Some("text-embedding-3-small"), 8).is_none());
+    }
+}
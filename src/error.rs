@@ -11,9 +11,42 @@ pub enum VectorDbError {
     #[error("Vector dimension mismatch: expected {expected}, got {actual}")]
     DimensionMismatch { expected: usize, actual: usize },
 
+    #[error("Embedding model mismatch: collection uses {expected}, got {actual}")]
+    EmbeddingModelMismatch { expected: String, actual: String },
+
+    #[error("Reserved metadata key: {0}")]
+    ReservedMetadataKey(String),
+
+    #[error("Metadata limit exceeded: {0}")]
+    MetadataLimitExceeded(String),
+
+    #[error("Limit exceeded: {0}")]
+    LimitExceeded(String),
+
+    #[error("Index rebuild already in progress for collection: {0}")]
+    RebuildInProgress(String),
+
+    #[error("Storage is opened read-only: {0}")]
+    ReadOnly(String),
+
+    #[error("Collection is frozen: {0}")]
+    Frozen(String),
+
+    #[error("Storage directory is locked by another writer: {0}")]
+    StorageLocked(String),
+
+    #[error("Invalid filter: {0}")]
+    InvalidFilter(String),
+
     #[error("Vector not found: {0}")]
     VectorNotFound(String),
 
+    #[error("Vector already exists: {0}")]
+    VectorAlreadyExists(String),
+
+    #[error("Corrupt data in {path}: {reason}")]
+    CorruptData { path: String, reason: String },
+
     #[error("IO error: {0}")]
     Io(#[from] std::io::Error),
 
@@ -22,6 +55,21 @@ pub enum VectorDbError {
 
     #[error("Invalid configuration: {0}")]
     InvalidConfig(String),
+
+    #[error("Unsupported API version: {0}")]
+    UnsupportedApiVersion(String),
+
+    #[error("Reranker failed: {0}")]
+    Reranker(String),
+
+    #[error("Invalid cursor: {0}")]
+    InvalidCursor(String),
+
+    #[error("Cursor is stale, collection {0} was modified since it was issued")]
+    StaleCursor(String),
+
+    #[error("Unauthorized: {0}")]
+    Unauthorized(String),
 }
 
 pub type Result<T> = std::result::Result<T, VectorDbError>;
@@ -37,3 +85,17 @@ impl From<bincode::Error> for VectorDbError {
         VectorDbError::Serialization(err.to_string())
     }
 }
+
+#[cfg(feature = "chroma-import")]
+impl From<rusqlite::Error> for VectorDbError {
+    fn from(err: rusqlite::Error) -> Self {
+        VectorDbError::Serialization(err.to_string())
+    }
+}
+
+#[cfg(feature = "chroma-import")]
+impl From<parquet::errors::ParquetError> for VectorDbError {
+    fn from(err: parquet::errors::ParquetError) -> Self {
+        VectorDbError::Serialization(err.to_string())
+    }
+}
@@ -0,0 +1,393 @@
+use crate::error::{Result, VectorDbError};
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+
+// format binaire de faiss::write_index (index_write.cpp) : entiers little-endian,
+// chaque index commence par un fourcc de 4 caractères
+const FLAT_IP: &[u8; 4] = b"IxFI";
+const FLAT_L2: &[u8; 4] = b"IxF2";
+const FLAT_OTHER: &[u8; 4] = b"IxFl";
+const ID_MAP: &[u8; 4] = b"IxMp";
+const ID_MAP2: &[u8; 4] = b"IxM2";
+const IVF_FLAT: &[u8; 4] = b"IwFl";
+const ARRAY_INVLISTS: &[u8; 4] = b"ilar";
+const NULL_INVLISTS: &[u8; 4] = b"il00";
+
+pub const METRIC_INNER_PRODUCT: i32 = 0;
+pub const METRIC_L2: i32 = 1;
+
+// valeur écrite par faiss dans les deux champs historiques de l'en-tête
+const HEADER_DUMMY: i64 = 1 << 20;
+
+#[derive(Debug, Clone)]
+pub struct FaissIvf {
+    pub centroids: Vec<Vec<f32>>,
+    pub n_probe: usize,
+    // labels de chaque liste inversée
+    pub lists: Vec<Vec<i64>>,
+}
+
+// contenu d'un index faiss plat ou IVF-Flat, avec les labels de chaque vecteur
+#[derive(Debug, Clone)]
+pub struct FaissIndex {
+    pub dimension: usize,
+    pub metric: i32,
+    pub labels: Vec<i64>,
+    pub vectors: Vec<Vec<f32>>,
+    pub ivf: Option<FaissIvf>,
+}
+
+// fichier d'ids à côté de l'index : les labels faiss sont des entiers,
+// la position dans ce tableau JSON donne l'id vectordb
+pub fn ids_path(index_path: &Path) -> PathBuf {
+    let mut name = index_path.as_os_str().to_owned();
+    name.push(".ids.json");
+    PathBuf::from(name)
+}
+
+struct FaissReader<R: Read> {
+    inner: R,
+}
+
+impl<R: Read> FaissReader<R> {
+    fn bytes<const N: usize>(&mut self) -> Result<[u8; N]> {
+        let mut buf = [0u8; N];
+        self.inner.read_exact(&mut buf)?;
+        Ok(buf)
+    }
+
+    fn u8(&mut self) -> Result<u8> {
+        Ok(self.bytes::<1>()?[0])
+    }
+
+    fn i32(&mut self) -> Result<i32> {
+        Ok(i32::from_le_bytes(self.bytes()?))
+    }
+
+    fn i64(&mut self) -> Result<i64> {
+        Ok(i64::from_le_bytes(self.bytes()?))
+    }
+
+    fn usize(&mut self) -> Result<usize> {
+        Ok(u64::from_le_bytes(self.bytes()?) as usize)
+    }
+
+    fn f32s(&mut self, n: usize) -> Result<Vec<f32>> {
+        let mut buf = vec![0u8; n * 4];
+        self.inner.read_exact(&mut buf)?;
+        Ok(buf
+            .chunks_exact(4)
+            .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+            .collect())
+    }
+
+    fn i64s(&mut self, n: usize) -> Result<Vec<i64>> {
+        (0..n).map(|_| self.i64()).collect()
+    }
+
+    fn usize_vec(&mut self) -> Result<Vec<usize>> {
+        let n = self.usize()?;
+        (0..n).map(|_| self.usize()).collect()
+    }
+
+    // d, ntotal, 2 champs ignorés, is_trained, metric_type (+ metric_arg)
+    fn header(&mut self) -> Result<(usize, usize, i32)> {
+        let d = self.i32()? as usize;
+        let ntotal = self.i64()? as usize;
+        self.i64()?;
+        self.i64()?;
+        self.u8()?;
+        let metric = self.i32()?;
+        if metric > 1 {
+            self.f32s(1)?;
+        }
+        Ok((d, ntotal, metric))
+    }
+
+    fn index(&mut self) -> Result<FaissIndex> {
+        let fourcc: [u8; 4] = self.bytes()?;
+        match &fourcc {
+            FLAT_IP | FLAT_L2 | FLAT_OTHER => {
+                let (dimension, ntotal, metric) = self.header()?;
+                let n_floats = self.usize()?;
+                if n_floats != ntotal * dimension {
+                    return Err(corrupt("flat index size does not match its header"));
+                }
+                let data = self.f32s(n_floats)?;
+                Ok(FaissIndex {
+                    dimension,
+                    metric,
+                    labels: (0..ntotal as i64).collect(),
+                    vectors: data.chunks(dimension.max(1)).map(|c| c.to_vec()).collect(),
+                    ivf: None,
+                })
+            }
+            ID_MAP | ID_MAP2 => {
+                self.header()?;
+                let mut index = self.index()?;
+                let id_map_len = self.usize()?;
+                let id_map = self.i64s(id_map_len)?;
+                if id_map.len() != index.labels.len() {
+                    return Err(corrupt("id map size does not match the wrapped index"));
+                }
+                // les labels internes sont des positions dans id_map
+                index.labels = index.labels.iter().map(|&l| id_map[l as usize]).collect();
+                if let Some(ref mut ivf) = index.ivf {
+                    for list in ivf.lists.iter_mut() {
+                        for label in list.iter_mut() {
+                            *label = id_map[*label as usize];
+                        }
+                    }
+                }
+                Ok(index)
+            }
+            IVF_FLAT => self.ivf_flat(),
+            other => Err(VectorDbError::Serialization(format!(
+                "unsupported Faiss index type '{}' (supported: IndexFlat, IndexIDMap, IndexIVFFlat)",
+                String::from_utf8_lossy(other)
+            ))),
+        }
+    }
+
+    fn ivf_flat(&mut self) -> Result<FaissIndex> {
+        let (dimension, _ntotal, metric) = self.header()?;
+        let nlist = self.usize()?;
+        let n_probe = self.usize()?;
+        let quantizer = self.index()?;
+        if quantizer.ivf.is_some() || quantizer.vectors.len() != nlist {
+            return Err(corrupt("IVF quantizer must be a flat index with nlist centroids"));
+        }
+
+        // direct map : type (0 = aucune, 1 = tableau) puis tableau d'entiers
+        let direct_map_type = self.u8()?;
+        if direct_map_type > 1 {
+            return Err(VectorDbError::Serialization(
+                "Faiss IVF indexes with a hashtable direct map are not supported".to_string(),
+            ));
+        }
+        let n = self.usize()?;
+        self.i64s(n)?;
+
+        let fourcc: [u8; 4] = self.bytes()?;
+        let mut lists = vec![Vec::new(); nlist];
+        let mut labels = Vec::new();
+        let mut vectors = Vec::new();
+        match &fourcc {
+            NULL_INVLISTS => {}
+            ARRAY_INVLISTS => {
+                let stored_nlist = self.usize()?;
+                let code_size = self.usize()?;
+                if stored_nlist != nlist || code_size != dimension * 4 {
+                    return Err(corrupt("inverted lists do not match the IVF header"));
+                }
+
+                let list_type: [u8; 4] = self.bytes()?;
+                let raw = self.usize_vec()?;
+                let sizes: Vec<usize> = match &list_type {
+                    b"full" => raw,
+                    b"sprs" => {
+                        let mut sizes = vec![0; nlist];
+                        for pair in raw.chunks_exact(2) {
+                            sizes[pair[0]] = pair[1];
+                        }
+                        sizes
+                    }
+                    _ => return Err(corrupt("unknown inverted list layout")),
+                };
+
+                for (list_no, &size) in sizes.iter().enumerate() {
+                    if size == 0 {
+                        continue;
+                    }
+                    let codes = self.f32s(size * dimension)?;
+                    let ids = self.i64s(size)?;
+                    for (code, id) in codes.chunks(dimension).zip(ids) {
+                        lists[list_no].push(id);
+                        labels.push(id);
+                        vectors.push(code.to_vec());
+                    }
+                }
+            }
+            other => {
+                return Err(VectorDbError::Serialization(format!(
+                    "unsupported Faiss inverted lists '{}'",
+                    String::from_utf8_lossy(other)
+                )))
+            }
+        }
+
+        Ok(FaissIndex {
+            dimension,
+            metric,
+            labels,
+            vectors,
+            ivf: Some(FaissIvf {
+                centroids: quantizer.vectors,
+                n_probe,
+                lists,
+            }),
+        })
+    }
+}
+
+fn corrupt(msg: &str) -> VectorDbError {
+    VectorDbError::Serialization(format!("invalid Faiss index file: {}", msg))
+}
+
+pub fn read_index(path: &Path) -> Result<FaissIndex> {
+    let mut reader = FaissReader {
+        inner: BufReader::with_capacity(512 * 1024, File::open(path)?),
+    };
+    reader.index()
+}
+
+struct FaissWriter<W: Write> {
+    inner: W,
+}
+
+impl<W: Write> FaissWriter<W> {
+    fn raw(&mut self, bytes: &[u8]) -> Result<()> {
+        self.inner.write_all(bytes)?;
+        Ok(())
+    }
+
+    fn usize(&mut self, v: usize) -> Result<()> {
+        self.raw(&(v as u64).to_le_bytes())
+    }
+
+    fn header(&mut self, dimension: usize, ntotal: usize, metric: i32) -> Result<()> {
+        self.raw(&(dimension as i32).to_le_bytes())?;
+        self.raw(&(ntotal as i64).to_le_bytes())?;
+        self.raw(&HEADER_DUMMY.to_le_bytes())?;
+        self.raw(&HEADER_DUMMY.to_le_bytes())?;
+        self.raw(&[1])?;
+        self.raw(&metric.to_le_bytes())
+    }
+
+    fn f32s(&mut self, values: &[f32]) -> Result<()> {
+        for v in values {
+            self.raw(&v.to_le_bytes())?;
+        }
+        Ok(())
+    }
+
+    fn flat_ip(&mut self, dimension: usize, vectors: &[&[f32]]) -> Result<()> {
+        self.raw(FLAT_IP)?;
+        self.header(dimension, vectors.len(), METRIC_INNER_PRODUCT)?;
+        self.usize(vectors.len() * dimension)?;
+        for v in vectors {
+            self.f32s(v)?;
+        }
+        Ok(())
+    }
+}
+
+// IndexIDMap(IndexFlatIP) : les embeddings étant normalisés, le produit
+// scalaire de faiss correspond à la similarité cosinus de vectordb
+pub fn write_flat(path: &Path, dimension: usize, vectors: &[&[f32]]) -> Result<()> {
+    let mut w = FaissWriter {
+        inner: BufWriter::with_capacity(512 * 1024, File::create(path)?),
+    };
+    w.raw(ID_MAP)?;
+    w.header(dimension, vectors.len(), METRIC_INNER_PRODUCT)?;
+    w.flat_ip(dimension, vectors)?;
+    w.usize(vectors.len())?;
+    for label in 0..vectors.len() as i64 {
+        w.raw(&label.to_le_bytes())?;
+    }
+    w.inner.flush()?;
+    Ok(())
+}
+
+// IndexIVFFlat avec quantizer IndexFlatIP ; `assignments[i]` est la liste du vecteur i,
+// son label faiss est sa position
+pub fn write_ivf_flat(
+    path: &Path,
+    dimension: usize,
+    centroids: &[&[f32]],
+    n_probe: usize,
+    vectors: &[&[f32]],
+    assignments: &[usize],
+) -> Result<()> {
+    let nlist = centroids.len();
+    let mut lists: Vec<Vec<usize>> = vec![Vec::new(); nlist];
+    for (i, &list_no) in assignments.iter().enumerate() {
+        lists[list_no].push(i);
+    }
+
+    let mut w = FaissWriter {
+        inner: BufWriter::with_capacity(512 * 1024, File::create(path)?),
+    };
+    w.raw(IVF_FLAT)?;
+    w.header(dimension, vectors.len(), METRIC_INNER_PRODUCT)?;
+    w.usize(nlist)?;
+    w.usize(n_probe)?;
+    w.flat_ip(dimension, centroids)?;
+
+    // pas de direct map
+    w.raw(&[0])?;
+    w.usize(0)?;
+
+    w.raw(ARRAY_INVLISTS)?;
+    w.usize(nlist)?;
+    w.usize(dimension * 4)?;
+    w.raw(b"full")?;
+    w.usize(nlist)?;
+    for list in &lists {
+        w.usize(list.len())?;
+    }
+    for list in &lists {
+        for &i in list {
+            w.f32s(vectors[i])?;
+        }
+        for &i in list {
+            w.raw(&(i as i64).to_le_bytes())?;
+        }
+    }
+    w.inner.flush()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::TempDir;
+
+    #[test]
+    fn test_flat_roundtrip() {
+        let dir = TempDir::new("faiss-flat");
+        let path = dir.join("flat.index");
+        let a = [1.0, 0.0];
+        let b = [0.0, 1.0];
+        write_flat(&path, 2, &[&a, &b]).unwrap();
+
+        let index = read_index(&path).unwrap();
+
+        assert_eq!(index.dimension, 2);
+        assert_eq!(index.metric, METRIC_INNER_PRODUCT);
+        assert_eq!(index.labels, vec![0, 1]);
+        assert_eq!(index.vectors, vec![vec![1.0, 0.0], vec![0.0, 1.0]]);
+        assert!(index.ivf.is_none());
+    }
+
+    #[test]
+    fn test_ivf_flat_roundtrip() {
+        let dir = TempDir::new("faiss-ivf");
+        let path = dir.join("ivf.index");
+        let c0 = [1.0, 0.0];
+        let c1 = [0.0, 1.0];
+        let v0 = [0.9, 0.1];
+        let v1 = [0.1, 0.9];
+        let v2 = [0.8, 0.2];
+        write_ivf_flat(&path, 2, &[&c0, &c1], 1, &[&v0, &v1, &v2], &[0, 1, 0]).unwrap();
+
+        let index = read_index(&path).unwrap();
+
+        let ivf = index.ivf.unwrap();
+        assert_eq!(ivf.centroids.len(), 2);
+        assert_eq!(ivf.lists, vec![vec![0, 2], vec![1]]);
+        assert_eq!(index.labels, vec![0, 2, 1]);
+        assert_eq!(index.vectors[1], vec![0.8, 0.2]);
+    }
+}
@@ -0,0 +1,425 @@
+// interface C stable (feature `ffi`) pour embarquer le moteur depuis C++, Go (cgo) ou
+// Swift ; l'en-tête include/vectordb.h est généré par cbindgen au build
+use crate::client::VectorDbClient;
+use crate::collection::CollectionConfig;
+use crate::error::VectorDbError;
+use crate::filter::{parse_filter, WhereFilter};
+use crate::vector::MetadataValue;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::ffi::{c_char, CStr};
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::ptr;
+
+/// Codes de retour de toutes les fonctions `vdb_*`.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VdbStatus {
+    Ok = 0,
+    NullPointer = 1,
+    InvalidArgument = 2,
+    NotFound = 3,
+    AlreadyExists = 4,
+    DimensionMismatch = 5,
+    ReadOnly = 6,
+    Locked = 7,
+    Io = 8,
+    BufferTooSmall = 9,
+    Internal = 10,
+}
+
+/// Client opaque, créé par `vdb_client_open` et libéré par `vdb_client_free`.
+pub struct VdbClient {
+    client: VectorDbClient,
+}
+
+thread_local! {
+    static LAST_ERROR: RefCell<String> = const { RefCell::new(String::new()) };
+}
+
+struct FfiError(VdbStatus, String);
+
+impl From<VectorDbError> for FfiError {
+    fn from(e: VectorDbError) -> Self {
+        let status = match e {
+            VectorDbError::CollectionNotFound(_) | VectorDbError::VectorNotFound(_) => VdbStatus::NotFound,
+            VectorDbError::CollectionAlreadyExists(_) | VectorDbError::VectorAlreadyExists(_) => {
+                VdbStatus::AlreadyExists
+            }
+            VectorDbError::DimensionMismatch { .. } => VdbStatus::DimensionMismatch,
+            VectorDbError::ReadOnly(_) => VdbStatus::ReadOnly,
+            VectorDbError::StorageLocked(_) | VectorDbError::RebuildInProgress(_) => VdbStatus::Locked,
+            VectorDbError::Io(_) | VectorDbError::CorruptData { .. } => VdbStatus::Io,
+            _ => VdbStatus::InvalidArgument,
+        };
+        FfiError(status, e.to_string())
+    }
+}
+
+fn invalid(msg: impl Into<String>) -> FfiError {
+    FfiError(VdbStatus::InvalidArgument, msg.into())
+}
+
+// exécute `f` en convertissant erreurs et paniques en code de retour ;
+// le message est conservé pour vdb_last_error
+fn guard(f: impl FnOnce() -> Result<(), FfiError>) -> VdbStatus {
+    let (status, message) = match catch_unwind(AssertUnwindSafe(f)) {
+        Ok(Ok(())) => (VdbStatus::Ok, String::new()),
+        Ok(Err(FfiError(status, message))) => (status, message),
+        Err(_) => (VdbStatus::Internal, "panic in vectordb".to_string()),
+    };
+    LAST_ERROR.with(|last| *last.borrow_mut() = message);
+    status
+}
+
+unsafe fn str_arg<'a>(ptr: *const c_char, what: &str) -> Result<&'a str, FfiError> {
+    if ptr.is_null() {
+        return Err(FfiError(VdbStatus::NullPointer, format!("{} is null", what)));
+    }
+    CStr::from_ptr(ptr)
+        .to_str()
+        .map_err(|_| invalid(format!("{} is not valid UTF-8", what)))
+}
+
+unsafe fn client_arg<'a>(client: *const VdbClient) -> Result<&'a VectorDbClient, FfiError> {
+    client
+        .as_ref()
+        .map(|c| &c.client)
+        .ok_or_else(|| FfiError(VdbStatus::NullPointer, "client is null".to_string()))
+}
+
+unsafe fn string_array(ptr: *const *const c_char, len: usize, what: &str) -> Result<Vec<String>, FfiError> {
+    if len == 0 {
+        return Ok(Vec::new());
+    }
+    if ptr.is_null() {
+        return Err(FfiError(VdbStatus::NullPointer, format!("{} is null", what)));
+    }
+    std::slice::from_raw_parts(ptr, len)
+        .iter()
+        .map(|&s| str_arg(s, what).map(str::to_string))
+        .collect()
+}
+
+// filtre optionnel au format JSON de l'API REST
+unsafe fn filter_arg(where_json: *const c_char) -> Result<Option<WhereFilter>, FfiError> {
+    if where_json.is_null() {
+        return Ok(None);
+    }
+    let value: serde_json::Value =
+        serde_json::from_str(str_arg(where_json, "where_json")?).map_err(|e| invalid(e.to_string()))?;
+    Ok(Some(parse_filter(&value)?))
+}
+
+unsafe fn open_with(
+    path: *const c_char,
+    out_client: *mut *mut VdbClient,
+    open: fn(&str) -> crate::error::Result<VectorDbClient>,
+) -> VdbStatus {
+    guard(|| {
+        if out_client.is_null() {
+            return Err(FfiError(VdbStatus::NullPointer, "out_client is null".to_string()));
+        }
+        let client = open(str_arg(path, "path")?)?;
+        *out_client = Box::into_raw(Box::new(VdbClient { client }));
+        Ok(())
+    })
+}
+
+/// Ouvre (ou crée) une base en répertoire et écrit le client dans `*out_client`.
+///
+/// # Safety
+/// `path` doit être une chaîne C terminée par NUL et `out_client` un pointeur valide.
+#[no_mangle]
+pub unsafe extern "C" fn vdb_client_open(path: *const c_char, out_client: *mut *mut VdbClient) -> VdbStatus {
+    open_with(path, out_client, |p| VectorDbClient::new(p))
+}
+
+/// Comme `vdb_client_open`, en lecture seule à côté d'un processus écrivain.
+///
+/// # Safety
+/// Mêmes contraintes que `vdb_client_open`.
+#[no_mangle]
+pub unsafe extern "C" fn vdb_client_open_read_only(
+    path: *const c_char,
+    out_client: *mut *mut VdbClient,
+) -> VdbStatus {
+    open_with(path, out_client, |p| VectorDbClient::open_read_only(p))
+}
+
+/// Sauvegarde et libère le client ; `NULL` est accepté.
+///
+/// # Safety
+/// `client` doit provenir de `vdb_client_open*` et ne plus être utilisé ensuite.
+#[no_mangle]
+pub unsafe extern "C" fn vdb_client_free(client: *mut VdbClient) {
+    if !client.is_null() {
+        let _ = catch_unwind(AssertUnwindSafe(|| drop(Box::from_raw(client))));
+    }
+}
+
+/// Copie le message de la dernière erreur du thread courant dans `buf` (tronqué, terminé
+/// par NUL) et renvoie sa longueur complète hors NUL.
+///
+/// # Safety
+/// `buf` doit pointer sur au moins `buf_len` octets, ou être `NULL` avec `buf_len == 0`.
+#[no_mangle]
+pub unsafe extern "C" fn vdb_last_error(buf: *mut c_char, buf_len: usize) -> usize {
+    LAST_ERROR.with(|last| {
+        let message = last.borrow();
+        if !buf.is_null() && buf_len > 0 {
+            let n = message.len().min(buf_len - 1);
+            ptr::copy_nonoverlapping(message.as_ptr() as *const c_char, buf, n);
+            *buf.add(n) = 0;
+        }
+        message.len()
+    })
+}
+
+/// Crée une collection ; `n_clusters == 0` pour une recherche linéaire sans IVF.
+///
+/// # Safety
+/// `client` doit être valide et `name` une chaîne C terminée par NUL.
+#[no_mangle]
+pub unsafe extern "C" fn vdb_collection_create(
+    client: *const VdbClient,
+    name: *const c_char,
+    dimension: usize,
+    n_clusters: usize,
+) -> VdbStatus {
+    guard(|| {
+        let mut config = CollectionConfig::new(str_arg(name, "name")?.to_string(), dimension);
+        if n_clusters > 0 {
+            config = config.with_ivf(n_clusters);
+        }
+        Ok(client_arg(client)?.create_collection_with_config(config)?)
+    })
+}
+
+/// Supprime une collection et ses fichiers.
+///
+/// # Safety
+/// `client` doit être valide et `name` une chaîne C terminée par NUL.
+#[no_mangle]
+pub unsafe extern "C" fn vdb_collection_delete(client: *const VdbClient, name: *const c_char) -> VdbStatus {
+    guard(|| Ok(client_arg(client)?.delete_collection(str_arg(name, "name")?)?))
+}
+
+/// Ajoute `n_ids` vecteurs. `embeddings` contient `embeddings_len` flottants, soit
+/// `n_ids × dimension` à la suite ; `metadatas_json` (optionnel) contient `n_ids` objets
+/// JSON, un élément `NULL` valant une métadonnée vide.
+///
+/// # Safety
+/// `ids` (et `metadatas_json` s'il est non nul) doivent pointer sur `n_ids` chaînes C,
+/// `embeddings` sur `embeddings_len` flottants.
+#[no_mangle]
+pub unsafe extern "C" fn vdb_add(
+    client: *const VdbClient,
+    collection: *const c_char,
+    ids: *const *const c_char,
+    n_ids: usize,
+    embeddings: *const f32,
+    embeddings_len: usize,
+    metadatas_json: *const *const c_char,
+) -> VdbStatus {
+    guard(|| {
+        let client = client_arg(client)?;
+        let collection = str_arg(collection, "collection")?;
+        let ids = string_array(ids, n_ids, "ids")?;
+        if n_ids == 0 {
+            return Ok(());
+        }
+        if embeddings.is_null() {
+            return Err(FfiError(VdbStatus::NullPointer, "embeddings is null".to_string()));
+        }
+        if !embeddings_len.is_multiple_of(n_ids) {
+            return Err(invalid(format!(
+                "{} values cannot be split into {} embeddings",
+                embeddings_len, n_ids
+            )));
+        }
+        let dimension = embeddings_len / n_ids;
+        let embeddings: Vec<Vec<f32>> = std::slice::from_raw_parts(embeddings, embeddings_len)
+            .chunks(dimension.max(1))
+            .map(|e| e.to_vec())
+            .collect();
+
+        let metadatas = if metadatas_json.is_null() {
+            None
+        } else {
+            let parsed = std::slice::from_raw_parts(metadatas_json, n_ids)
+                .iter()
+                .map(|&json| -> Result<HashMap<String, MetadataValue>, FfiError> {
+                    if json.is_null() {
+                        return Ok(HashMap::new());
+                    }
+                    match serde_json::from_str(str_arg(json, "metadatas_json")?) {
+                        Ok(serde_json::Value::Object(map)) => Ok(map
+                            .into_iter()
+                            .map(|(k, v)| (k, MetadataValue::from_json(v)))
+                            .collect()),
+                        Ok(_) => Err(invalid("metadata must be a JSON object")),
+                        Err(e) => Err(invalid(e.to_string())),
+                    }
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            Some(parsed)
+        };
+
+        Ok(client.with_collection_mut(collection, |coll| {
+            coll.add_with_source(ids, embeddings, metadatas, "ffi")
+        })?)
+    })
+}
+
+/// Recherche les `n_results` plus proches voisins de `query` (`dimension` flottants),
+/// avec un filtre JSON optionnel (`where_json`, syntaxe de l'API REST).
+///
+/// Les résultats sont écrits dans les tampons de l'appelant : `out_distances` reçoit
+/// `n_results` flottants, et `out_ids` `n_results` emplacements de `id_stride` octets
+/// contenant chacun un id terminé par NUL. `*out_count` reçoit le nombre de résultats.
+/// Renvoie `VDB_STATUS_BUFFER_TOO_SMALL` si un id ne tient pas dans `id_stride` octets.
+///
+/// # Safety
+/// `query` doit pointer sur `dimension` flottants, `out_distances` sur `n_results`
+/// flottants, `out_ids` sur `n_results * id_stride` octets, `out_count` être valide.
+#[no_mangle]
+pub unsafe extern "C" fn vdb_query(
+    client: *const VdbClient,
+    collection: *const c_char,
+    query: *const f32,
+    dimension: usize,
+    n_results: usize,
+    where_json: *const c_char,
+    out_ids: *mut c_char,
+    id_stride: usize,
+    out_distances: *mut f32,
+    out_count: *mut usize,
+) -> VdbStatus {
+    guard(|| {
+        let client = client_arg(client)?;
+        let collection = str_arg(collection, "collection")?;
+        if query.is_null() || out_ids.is_null() || out_distances.is_null() || out_count.is_null() {
+            return Err(FfiError(VdbStatus::NullPointer, "query buffers must not be null".to_string()));
+        }
+        *out_count = 0;
+        let filter = filter_arg(where_json)?;
+        let query = std::slice::from_raw_parts(query, dimension);
+
+        let results = client.with_collection_mut(collection, |coll| coll.query(query, n_results, filter.as_ref()))?;
+        if let Some(r) = results.iter().find(|r| r.id.len() >= id_stride) {
+            return Err(FfiError(
+                VdbStatus::BufferTooSmall,
+                format!("id '{}' needs {} bytes, id_stride is {}", r.id, r.id.len() + 1, id_stride),
+            ));
+        }
+
+        for (i, r) in results.iter().take(n_results).enumerate() {
+            let slot = out_ids.add(i * id_stride);
+            ptr::copy_nonoverlapping(r.id.as_ptr() as *const c_char, slot, r.id.len());
+            *slot.add(r.id.len()) = 0;
+            *out_distances.add(i) = r.distance;
+        }
+        *out_count = results.len().min(n_results);
+        Ok(())
+    })
+}
+
+/// Nombre de vecteurs de la collection, éventuellement filtré (`where_json` nul sinon).
+///
+/// # Safety
+/// `client` et `out_count` doivent être valides, les chaînes terminées par NUL.
+#[no_mangle]
+pub unsafe extern "C" fn vdb_count(
+    client: *const VdbClient,
+    collection: *const c_char,
+    where_json: *const c_char,
+    out_count: *mut usize,
+) -> VdbStatus {
+    guard(|| {
+        let client = client_arg(client)?;
+        let collection = str_arg(collection, "collection")?;
+        if out_count.is_null() {
+            return Err(FfiError(VdbStatus::NullPointer, "out_count is null".to_string()));
+        }
+        let filter = filter_arg(where_json)?;
+        *out_count = client.count(collection, filter.as_ref())?;
+        Ok(())
+    })
+}
+
+/// Supprime `n_ids` vecteurs.
+///
+/// # Safety
+/// `ids` doit pointer sur `n_ids` chaînes C terminées par NUL.
+#[no_mangle]
+pub unsafe extern "C" fn vdb_delete(
+    client: *const VdbClient,
+    collection: *const c_char,
+    ids: *const *const c_char,
+    n_ids: usize,
+) -> VdbStatus {
+    guard(|| {
+        let client = client_arg(client)?;
+        let collection = str_arg(collection, "collection")?;
+        let ids = string_array(ids, n_ids, "ids")?;
+        Ok(client.with_collection_mut(collection, |coll| coll.delete(ids))?)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::TempDir;
+    use std::ffi::CString;
+
+    #[test]
+    fn test_c_api_roundtrip() {
+        let dir = TempDir::new("ffi");
+        let path = CString::new(dir.to_str().unwrap()).unwrap();
+        let name = CString::new("docs").unwrap();
+
+        unsafe {
+            let mut client: *mut VdbClient = ptr::null_mut();
+            assert_eq!(vdb_client_open(path.as_ptr(), &mut client), VdbStatus::Ok);
+            assert_eq!(vdb_collection_create(client, name.as_ptr(), 2, 0), VdbStatus::Ok);
+
+            let ids = [CString::new("a").unwrap(), CString::new("bb").unwrap()];
+            let id_ptrs: Vec<*const c_char> = ids.iter().map(|s| s.as_ptr()).collect();
+            let metas = [CString::new(r#"{"lang": "fr"}"#).unwrap()];
+            let meta_ptrs = [metas[0].as_ptr(), ptr::null()];
+            let embeddings = [1.0f32, 0.0, 0.0, 1.0];
+            let status = vdb_add(client, name.as_ptr(), id_ptrs.as_ptr(), 2, embeddings.as_ptr(), 4, meta_ptrs.as_ptr());
+            assert_eq!(status, VdbStatus::Ok);
+
+            let mut out_ids = [0 as c_char; 2 * 8];
+            let mut distances = [0f32; 2];
+            let mut count = 0usize;
+            let query = [0.0f32, 1.0];
+            let status = vdb_query(
+                client, name.as_ptr(), query.as_ptr(), 2, 2, ptr::null(),
+                out_ids.as_mut_ptr(), 8, distances.as_mut_ptr(), &mut count,
+            );
+            assert_eq!(status, VdbStatus::Ok);
+            assert_eq!(count, 2);
+            assert_eq!(CStr::from_ptr(out_ids.as_ptr()).to_str().unwrap(), "bb");
+
+            // id plus long que l'emplacement
+            let status = vdb_query(
+                client, name.as_ptr(), query.as_ptr(), 2, 2, ptr::null(),
+                out_ids.as_mut_ptr(), 2, distances.as_mut_ptr(), &mut count,
+            );
+            assert_eq!(status, VdbStatus::BufferTooSmall);
+
+            let filter = CString::new(r#"{"lang": "fr"}"#).unwrap();
+            assert_eq!(vdb_count(client, name.as_ptr(), filter.as_ptr(), &mut count), VdbStatus::Ok);
+            assert_eq!(count, 1);
+
+            let status = vdb_add(client, name.as_ptr(), id_ptrs.as_ptr(), 1, embeddings.as_ptr(), 3, ptr::null());
+            assert_eq!(status, VdbStatus::DimensionMismatch);
+            assert!(vdb_last_error(ptr::null_mut(), 0) > 0);
+
+            vdb_client_free(client);
+        }
+    }
+}
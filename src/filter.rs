@@ -1,7 +1,12 @@
-use crate::vector::MetadataValue;
+use crate::distance::haversine_km;
+use crate::error::{Result, VectorDbError};
+use crate::vector::{lookup_path, MetadataValue};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::cmp::Ordering;
+use std::collections::{HashMap, HashSet};
 
+// filtres peu nombreux et de courte durée, la taille de l'enum n'a pas d'importance
+#[allow(clippy::large_enum_variant)]
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(untagged)]
 pub enum FilterValue {
@@ -9,7 +14,9 @@ pub enum FilterValue {
     Operator(FilterOperator),
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+// opérateur inconnu : la variante Operator échoue au lieu d'être ignorée
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
 pub struct FilterOperator {
     #[serde(rename = "$ne", skip_serializing_if = "Option::is_none")]
     pub ne: Option<MetadataValue>,
@@ -17,47 +24,722 @@ pub struct FilterOperator {
     pub in_values: Option<Vec<MetadataValue>>,
     #[serde(rename = "$nin", skip_serializing_if = "Option::is_none")]
     pub nin: Option<Vec<MetadataValue>>,
+    #[serde(rename = "$gt", skip_serializing_if = "Option::is_none")]
+    pub gt: Option<MetadataValue>,
+    #[serde(rename = "$gte", skip_serializing_if = "Option::is_none")]
+    pub gte: Option<MetadataValue>,
+    #[serde(rename = "$lt", skip_serializing_if = "Option::is_none")]
+    pub lt: Option<MetadataValue>,
+    #[serde(rename = "$lte", skip_serializing_if = "Option::is_none")]
+    pub lte: Option<MetadataValue>,
+    #[serde(rename = "$exists", skip_serializing_if = "Option::is_none")]
+    pub exists: Option<bool>,
+    #[serde(rename = "$geo_radius", skip_serializing_if = "Option::is_none")]
+    pub geo_radius: Option<GeoRadius>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GeoRadius {
+    pub lat: f64,
+    pub lon: f64,
+    pub radius_km: f64,
+}
+
+impl GeoRadius {
+    fn contains(&self, value: &MetadataValue) -> bool {
+        match value {
+            MetadataValue::GeoPoint { lat, lon } => {
+                haversine_km(self.lat, self.lon, *lat, *lon) <= self.radius_km
+            }
+            _ => false,
+        }
+    }
 }
 
 pub type WhereFilter = HashMap<String, FilterValue>;
 
-pub fn matches_filter(metadata: &HashMap<String, MetadataValue>, filter: &WhereFilter) -> bool {
-    for (key, filter_value) in filter {
-        let meta_val = metadata.get(key);
-
-        match filter_value {
-            FilterValue::Direct(expected) => {
-                // égalité simple
-                match meta_val {
-                    Some(val) if val == expected => continue,
-                    _ => return false,
+// ordre entre deux valeurs comparables (nombres entre eux, chaînes entre elles)
+pub(crate) fn compare_values(a: &MetadataValue, b: &MetadataValue) -> Option<Ordering> {
+    match (a, b) {
+        (MetadataValue::Int(x), MetadataValue::Int(y)) => Some(x.cmp(y)),
+        (MetadataValue::Int(x), MetadataValue::Float(y)) => (*x as f64).partial_cmp(y),
+        (MetadataValue::Float(x), MetadataValue::Int(y)) => x.partial_cmp(&(*y as f64)),
+        (MetadataValue::Float(x), MetadataValue::Float(y)) => x.partial_cmp(y),
+        (MetadataValue::String(x), MetadataValue::String(y)) => Some(x.cmp(y)),
+        (MetadataValue::DateTime(x), MetadataValue::DateTime(y)) => Some(x.cmp(y)),
+        _ => None,
+    }
+}
+
+fn matches_range(meta_val: Option<&MetadataValue>, op: &FilterOperator) -> bool {
+    let bounds = [
+        (&op.gt, [Ordering::Greater].as_slice()),
+        (&op.gte, [Ordering::Greater, Ordering::Equal].as_slice()),
+        (&op.lt, [Ordering::Less].as_slice()),
+        (&op.lte, [Ordering::Less, Ordering::Equal].as_slice()),
+    ];
+
+    for (bound, accepted) in bounds {
+        if let Some(ref bound_val) = bound {
+            let ord = meta_val.and_then(|val| compare_values(val, bound_val));
+            match ord {
+                Some(o) if accepted.contains(&o) => continue,
+                _ => return false,
+            }
+        }
+    }
+
+    true
+}
+
+// construire un WhereFilter depuis le JSON de l'API (valeurs simples, pas de tag serde)
+pub fn parse_filter(value: &serde_json::Value) -> Result<WhereFilter> {
+    let obj = value.as_object().ok_or_else(|| {
+        VectorDbError::InvalidFilter("where filter must be a JSON object".to_string())
+    })?;
+
+    let mut filter = WhereFilter::with_capacity(obj.len());
+    for (key, val) in obj {
+        let filter_value = match val {
+            serde_json::Value::Object(ops) if ops.keys().all(|k| k.starts_with('$')) => {
+                FilterValue::Operator(parse_operator(key, ops)?)
+            }
+            _ => FilterValue::Direct(MetadataValue::from_json(val.clone())),
+        };
+        filter.insert(key.clone(), filter_value);
+    }
+
+    Ok(filter)
+}
+
+fn parse_operator(
+    key: &str,
+    ops: &serde_json::Map<String, serde_json::Value>,
+) -> Result<FilterOperator> {
+    let mut op = FilterOperator::default();
+
+    for (name, val) in ops {
+        let single = || MetadataValue::from_json(val.clone());
+        let list = || -> Result<Vec<MetadataValue>> {
+            val.as_array()
+                .map(|items| items.iter().cloned().map(MetadataValue::from_json).collect())
+                .ok_or_else(|| {
+                    VectorDbError::InvalidFilter(format!("{} on '{}' expects an array", name, key))
+                })
+        };
+
+        match name.as_str() {
+            "$ne" => op.ne = Some(single()),
+            "$in" => op.in_values = Some(list()?),
+            "$nin" => op.nin = Some(list()?),
+            "$gt" => op.gt = Some(single()),
+            "$gte" => op.gte = Some(single()),
+            "$lt" => op.lt = Some(single()),
+            "$lte" => op.lte = Some(single()),
+            "$exists" => {
+                let exists = val.as_bool().ok_or_else(|| {
+                    VectorDbError::InvalidFilter(format!("$exists on '{}' expects a boolean", key))
+                })?;
+                op.exists = Some(exists);
+            }
+            "$geo_radius" => {
+                let geo = serde_json::from_value(val.clone()).map_err(|_| {
+                    VectorDbError::InvalidFilter(format!(
+                        "$geo_radius on '{}' expects {{\"lat\", \"lon\", \"radius_km\"}}",
+                        key
+                    ))
+                })?;
+                op.geo_radius = Some(geo);
+            }
+            _ => {
+                return Err(VectorDbError::InvalidFilter(format!(
+                    "unknown operator {} on '{}'",
+                    name, key
+                )))
+            }
+        }
+    }
+
+    Ok(op)
+}
+
+// syntaxe compacte des filtres (`where_str`), pratique dans un tableau de bord ou
+// avec curl : "lang = 'fr' AND price >= 10 AND tag IN ('a', 'b')". Conditions
+// `clé opérateur valeur` reliées par AND, traduites dans le JSON des opérateurs puis
+// analysées par parse_filter (mêmes types de valeurs, dates RFC3339 comprises).
+// Opérateurs = != > >= < <=, IN (...), NOT IN (...), EXISTS, NOT EXISTS ; valeurs
+// 'chaîne' ('' pour une apostrophe), nombres, true, false, null ; clé entre
+// guillemets doubles si elle contient autre chose que lettres, chiffres, _ et '.'.
+// Pas de OR : les clés d'un filtre sont toujours combinées par ET
+pub fn parse_where_str(input: &str) -> Result<WhereFilter> {
+    parse_filter(&where_str_to_json(input)?)
+}
+
+// filtre JSON équivalent à une expression where_str (journal des requêtes)
+pub fn where_str_to_json(input: &str) -> Result<serde_json::Value> {
+    let mut parser = WhereParser { tokens: tokenize(input)?, pos: 0 };
+    let mut filter = serde_json::Map::new();
+    loop {
+        let (key, op, value) = parser.condition()?;
+        add_condition(&mut filter, key, op, value)?;
+        match parser.next() {
+            None => break,
+            Some((Token::Word(w), _)) if w.eq_ignore_ascii_case("and") => {}
+            Some((Token::Word(w), _)) if w.eq_ignore_ascii_case("or") => {
+                return Err(dsl_error("OR is not supported, conditions are combined with AND".to_string()))
+            }
+            Some((token, at)) => return Err(dsl_error(format!("expected AND at position {}, found {}", at, token))),
+        }
+    }
+    Ok(serde_json::Value::Object(filter))
+}
+
+fn dsl_error(msg: String) -> VectorDbError {
+    VectorDbError::InvalidFilter(format!("where_str: {}", msg))
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    // identifiant ou mot-clé (AND, IN, NOT, EXISTS, true, false, null)
+    Word(String),
+    QuotedKey(String),
+    Str(String),
+    Number(serde_json::Number),
+    Op(&'static str),
+    Open,
+    Close,
+    Comma,
+}
+
+impl std::fmt::Display for Token {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Token::Word(w) => write!(f, "'{}'", w),
+            Token::QuotedKey(k) => write!(f, "\"{}\"", k),
+            Token::Str(s) => write!(f, "string '{}'", s),
+            Token::Number(n) => write!(f, "{}", n),
+            Token::Op(op) => write!(f, "'{}'", op),
+            Token::Open => write!(f, "'('"),
+            Token::Close => write!(f, "')'"),
+            Token::Comma => write!(f, "','"),
+        }
+    }
+}
+
+// jetons et leur position (en caractères) dans l'expression
+fn tokenize(input: &str) -> Result<Vec<(Token, usize)>> {
+    let chars: Vec<char> = input.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let start = i;
+        let c = chars[i];
+        let token = match c {
+            _ if c.is_whitespace() => {
+                i += 1;
+                continue;
+            }
+            '(' | ')' | ',' => {
+                i += 1;
+                match c {
+                    '(' => Token::Open,
+                    ')' => Token::Close,
+                    _ => Token::Comma,
                 }
             }
-            FilterValue::Operator(op) => {
-                // opérateurs
-                if let Some(ref ne_val) = op.ne {
-                    match meta_val {
-                        Some(val) if val == ne_val => return false,
-                        None => return false,
-                        _ => continue,
+            '=' | '!' | '<' | '>' => {
+                let two: String = chars[i..(i + 2).min(chars.len())].iter().collect();
+                let op = match two.as_str() {
+                    "==" => "=",
+                    "!=" | "<>" => "!=",
+                    ">=" => ">=",
+                    "<=" => "<=",
+                    _ => match c {
+                        '=' => "=",
+                        '<' => "<",
+                        '>' => ">",
+                        _ => return Err(dsl_error(format!("unexpected '!' at position {}", start))),
+                    },
+                };
+                i += if matches!(two.as_str(), "==" | "!=" | "<>" | ">=" | "<=") { 2 } else { 1 };
+                Token::Op(op)
+            }
+            '\'' | '"' => {
+                let mut text = String::new();
+                i += 1;
+                loop {
+                    match chars.get(i) {
+                        None => return Err(dsl_error(format!("unterminated quote at position {}", start))),
+                        // quote doublée : le caractère lui-même
+                        Some(&q) if q == c && chars.get(i + 1) == Some(&c) => {
+                            text.push(c);
+                            i += 2;
+                        }
+                        Some(&q) if q == c => {
+                            i += 1;
+                            break;
+                        }
+                        Some(&other) => {
+                            text.push(other);
+                            i += 1;
+                        }
                     }
                 }
-
-                if let Some(ref in_vals) = op.in_values {
-                    match meta_val {
-                        Some(val) if in_vals.contains(val) => continue,
-                        _ => return false,
+                if c == '\'' { Token::Str(text) } else { Token::QuotedKey(text) }
+            }
+            _ if c.is_ascii_digit() || (c == '-' && chars.get(i + 1).is_some_and(|d| d.is_ascii_digit())) => {
+                i += 1;
+                while i < chars.len() && (chars[i].is_ascii_alphanumeric() || matches!(chars[i], '.' | '+' | '-')) {
+                    // signe d'un exposant seulement
+                    if matches!(chars[i], '+' | '-') && !matches!(chars[i - 1], 'e' | 'E') {
+                        break;
                     }
+                    i += 1;
                 }
-
-                if let Some(ref nin_vals) = op.nin {
-                    match meta_val {
-                        Some(val) if nin_vals.contains(val) => return false,
-                        None => continue,
-                        _ => continue,
-                    }
+                let text: String = chars[start..i].iter().collect();
+                let number = match text.parse::<i64>() {
+                    Ok(n) => Some(serde_json::Number::from(n)),
+                    Err(_) => text.parse::<f64>().ok().and_then(serde_json::Number::from_f64),
+                };
+                Token::Number(number.ok_or_else(|| dsl_error(format!("invalid number '{}' at position {}", text, start)))?)
+            }
+            _ if c.is_alphanumeric() || c == '_' => {
+                while i < chars.len() && (chars[i].is_alphanumeric() || matches!(chars[i], '_' | '.')) {
+                    i += 1;
                 }
+                Token::Word(chars[start..i].iter().collect())
+            }
+            _ => return Err(dsl_error(format!("unexpected '{}' at position {}", c, start))),
+        };
+        tokens.push((token, start));
+    }
+    Ok(tokens)
+}
+
+struct WhereParser {
+    tokens: Vec<(Token, usize)>,
+    pos: usize,
+}
+
+impl WhereParser {
+    fn next(&mut self) -> Option<(Token, usize)> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    // jeton suivant, erreur en fin d'expression
+    fn expect(&mut self, what: &str) -> Result<(Token, usize)> {
+        self.next().ok_or_else(|| dsl_error(format!("expected {} at the end of the expression", what)))
+    }
+
+    // clé, nom d'opérateur JSON ("=" pour l'égalité) et valeur
+    fn condition(&mut self) -> Result<(String, &'static str, serde_json::Value)> {
+        let key = match self.expect("a key")? {
+            (Token::QuotedKey(key), _) => key,
+            (Token::Word(w), _) if !is_keyword(&w) => w,
+            (token, at) => return Err(dsl_error(format!("expected a key at position {}, found {}", at, token))),
+        };
+        match self.expect("an operator")? {
+            (Token::Op(op), _) => {
+                let value = self.value()?;
+                let op = match op {
+                    "=" => "=",
+                    "!=" => "$ne",
+                    ">" => "$gt",
+                    ">=" => "$gte",
+                    "<" => "$lt",
+                    _ => "$lte",
+                };
+                Ok((key, op, value))
             }
+            (Token::Word(w), _) if w.eq_ignore_ascii_case("in") => Ok((key, "$in", self.list()?)),
+            (Token::Word(w), _) if w.eq_ignore_ascii_case("exists") => Ok((key, "$exists", true.into())),
+            (Token::Word(w), _) if w.eq_ignore_ascii_case("not") => match self.expect("IN or EXISTS")? {
+                (Token::Word(w), _) if w.eq_ignore_ascii_case("in") => Ok((key, "$nin", self.list()?)),
+                (Token::Word(w), _) if w.eq_ignore_ascii_case("exists") => Ok((key, "$exists", false.into())),
+                (token, at) => Err(dsl_error(format!("expected IN or EXISTS at position {}, found {}", at, token))),
+            },
+            (token, at) => Err(dsl_error(format!("expected an operator at position {}, found {}", at, token))),
+        }
+    }
+
+    fn value(&mut self) -> Result<serde_json::Value> {
+        match self.expect("a value")? {
+            (Token::Str(s), _) => Ok(s.into()),
+            (Token::Number(n), _) => Ok(n.into()),
+            (Token::Word(w), _) if w.eq_ignore_ascii_case("true") => Ok(true.into()),
+            (Token::Word(w), _) if w.eq_ignore_ascii_case("false") => Ok(false.into()),
+            (Token::Word(w), _) if w.eq_ignore_ascii_case("null") => Ok(serde_json::Value::Null),
+            (token, at) => Err(dsl_error(format!("expected a value at position {}, found {}", at, token))),
+        }
+    }
+
+    // (v1, v2, ...)
+    fn list(&mut self) -> Result<serde_json::Value> {
+        match self.expect("'('")? {
+            (Token::Open, _) => {}
+            (token, at) => return Err(dsl_error(format!("expected '(' at position {}, found {}", at, token))),
+        }
+        let mut values = Vec::new();
+        loop {
+            values.push(self.value()?);
+            match self.expect("')'")? {
+                (Token::Comma, _) => {}
+                (Token::Close, _) => return Ok(values.into()),
+                (token, at) => return Err(dsl_error(format!("expected ',' or ')' at position {}, found {}", at, token))),
+            }
+        }
+    }
+}
+
+fn is_keyword(word: &str) -> bool {
+    ["and", "or", "not", "in", "exists", "true", "false", "null"].iter().any(|k| word.eq_ignore_ascii_case(k))
+}
+
+// plusieurs conditions sur une même clé se combinent en un objet d'opérateurs,
+// sauf l'égalité qui reste seule
+fn add_condition(
+    filter: &mut serde_json::Map<String, serde_json::Value>,
+    key: String,
+    op: &'static str,
+    value: serde_json::Value,
+) -> Result<()> {
+    use serde_json::Value;
+    let existing = filter.get_mut(&key);
+    match (existing, op) {
+        (None, "=") => {
+            filter.insert(key, value);
+        }
+        (None, _) => {
+            filter.insert(key, Value::Object([(op.to_string(), value)].into_iter().collect()));
+        }
+        (Some(Value::Object(ops)), _) if op != "=" && ops.keys().all(|k| k.starts_with('$')) => {
+            if ops.contains_key(op) {
+                return Err(dsl_error(format!("'{}' has {} twice", key, op)));
+            }
+            ops.insert(op.to_string(), value);
+        }
+        (Some(_), _) => {
+            return Err(dsl_error(format!("'{}' cannot combine an equality with other conditions", key)));
+        }
+    }
+    Ok(())
+}
+
+// famille de type utilisée pour la validation (int et float sont comparables entre eux)
+fn type_family(value: &MetadataValue) -> &'static str {
+    match value {
+        MetadataValue::Int(_) | MetadataValue::Float(_) => "number",
+        other => crate::metadata::type_name(other),
+    }
+}
+
+// valeurs comparées par chaque opérateur d'un filtre, Null exclu
+fn compared_values(filter_value: &FilterValue) -> Vec<(&'static str, &MetadataValue)> {
+    let mut values = Vec::new();
+    match filter_value {
+        FilterValue::Direct(v) => values.push(("equality", v)),
+        FilterValue::Operator(op) => {
+            let singles = [("$ne", &op.ne), ("$gt", &op.gt), ("$gte", &op.gte), ("$lt", &op.lt), ("$lte", &op.lte)];
+            for (name, v) in singles {
+                values.extend(v.iter().map(|v| (name, v)));
+            }
+            for (name, list) in [("$in", &op.in_values), ("$nin", &op.nin)] {
+                values.extend(list.iter().flatten().map(|v| (name, v)));
+            }
+        }
+    }
+    values.retain(|(_, v)| **v != MetadataValue::Null);
+    values
+}
+
+fn invalid(key: &str, msg: String) -> VectorDbError {
+    VectorDbError::InvalidFilter(format!("'{}': {}", key, msg))
+}
+
+// vérifications indépendantes des données : opérateurs vides, bornes non comparables,
+// listes $in/$nin hétérogènes, coordonnées $geo_radius hors limites
+pub fn validate_filter(filter: &WhereFilter) -> Result<()> {
+    for (key, filter_value) in filter {
+        if key.is_empty() {
+            return Err(VectorDbError::InvalidFilter("empty key".to_string()));
+        }
+        let op = match filter_value {
+            FilterValue::Direct(MetadataValue::Object(obj)) if obj.keys().any(|k| k.starts_with('$')) => {
+                return Err(invalid(key, "operators cannot be mixed with plain values".to_string()))
+            }
+            FilterValue::Direct(_) => continue,
+            FilterValue::Operator(op) => op,
+        };
+
+        let bounds: Vec<(&str, &MetadataValue)> = [("$gt", &op.gt), ("$gte", &op.gte), ("$lt", &op.lt), ("$lte", &op.lte)]
+            .into_iter()
+            .filter_map(|(name, v)| v.as_ref().map(|v| (name, v)))
+            .collect();
+        let has_list = op.in_values.is_some() || op.nin.is_some();
+        if bounds.is_empty() && !has_list && op.ne.is_none() && op.exists.is_none() && op.geo_radius.is_none() {
+            return Err(invalid(key, "empty operator object".to_string()));
+        }
+
+        for (name, bound) in &bounds {
+            if !matches!(type_family(bound), "number" | "string" | "datetime") {
+                return Err(invalid(
+                    key,
+                    format!("{} expects a number, string or datetime, got {}", name, type_family(bound)),
+                ));
+            }
+        }
+        if let [(first_name, first), rest @ ..] = bounds.as_slice() {
+            if let Some((name, other)) = rest.iter().find(|(_, v)| compare_values(first, v).is_none()) {
+                return Err(invalid(
+                    key,
+                    format!(
+                        "{} and {} compare different types ({}, {})",
+                        first_name, name, type_family(first), type_family(other)
+                    ),
+                ));
+            }
+        }
+
+        for (name, list) in [("$in", &op.in_values), ("$nin", &op.nin)] {
+            let families: HashSet<&str> = list
+                .iter()
+                .flatten()
+                .filter(|v| **v != MetadataValue::Null)
+                .map(type_family)
+                .collect();
+            if families.len() > 1 {
+                let mut families: Vec<&str> = families.into_iter().collect();
+                families.sort_unstable();
+                return Err(invalid(key, format!("{} mixes {} values", name, families.join(" and "))));
+            }
+        }
+
+        if let Some(ref geo) = op.geo_radius {
+            let valid = (-90.0..=90.0).contains(&geo.lat)
+                && (-180.0..=180.0).contains(&geo.lon)
+                && geo.radius_km.is_finite()
+                && geo.radius_km >= 0.0;
+            if !valid {
+                return Err(invalid(
+                    key,
+                    "$geo_radius expects lat in [-90, 90], lon in [-180, 180] and radius_km >= 0".to_string(),
+                ));
+            }
+        }
+    }
+    Ok(())
+}
+
+pub type ObservedTypes = HashMap<String, HashSet<&'static str>>;
+
+// familles de types présentes dans les données pour chacune des clés du filtre
+pub fn observed_types<'a, I>(keys: &[&str], metadatas: I) -> ObservedTypes
+where
+    I: Iterator<Item = &'a HashMap<String, MetadataValue>>,
+{
+    let mut observed: ObservedTypes = keys.iter().map(|k| (k.to_string(), HashSet::new())).collect();
+    for metadata in metadatas {
+        for key in keys {
+            if let Some(value) = lookup_path(metadata, key).filter(|v| **v != MetadataValue::Null) {
+                observed.get_mut(*key).unwrap().insert(type_family(value));
+            }
+        }
+    }
+    observed
+}
+
+// une valeur de filtre d'un type absent des données ne peut jamais correspondre :
+// erreur plutôt que résultat vide (clés inconnues ou toujours Null acceptées)
+pub fn check_filter_types(filter: &WhereFilter, observed: &ObservedTypes) -> Result<()> {
+    for (key, filter_value) in filter {
+        let types = match observed.get(key) {
+            Some(types) if !types.is_empty() => types,
+            _ => continue,
+        };
+        let describe = || {
+            let mut types: Vec<&str> = types.iter().copied().collect();
+            types.sort_unstable();
+            types.join("/")
+        };
+
+        for (name, value) in compared_values(filter_value) {
+            if !types.contains(type_family(value)) {
+                return Err(invalid(
+                    key,
+                    format!("{} compares {} values but the key holds {}", name, type_family(value), describe()),
+                ));
+            }
+        }
+        if let FilterValue::Operator(FilterOperator { geo_radius: Some(_), .. }) = filter_value {
+            if !types.contains("geo_point") {
+                return Err(invalid(key, format!("$geo_radius needs geo_point values but the key holds {}", describe())));
+            }
+        }
+    }
+    Ok(())
+}
+
+// construction typée d'un WhereFilter :
+//   FilterBuilder::new().eq("lang", "fr").gte("year", 2020).lt("year", 2024).build()?
+// plusieurs opérateurs sur une même clé sont combinés ; build() valide le résultat
+#[derive(Debug, Default, Clone)]
+pub struct FilterBuilder {
+    filter: WhereFilter,
+    error: Option<String>,
+}
+
+impl FilterBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn eq(mut self, key: &str, value: impl Into<MetadataValue>) -> Self {
+        if self.filter.insert(key.to_string(), FilterValue::Direct(value.into())).is_some() {
+            self.conflict(key);
+        }
+        self
+    }
+
+    pub fn ne(self, key: &str, value: impl Into<MetadataValue>) -> Self {
+        let value = value.into();
+        self.operator(key, |op| op.ne = Some(value))
+    }
+
+    pub fn gt(self, key: &str, value: impl Into<MetadataValue>) -> Self {
+        let value = value.into();
+        self.operator(key, |op| op.gt = Some(value))
+    }
+
+    pub fn gte(self, key: &str, value: impl Into<MetadataValue>) -> Self {
+        let value = value.into();
+        self.operator(key, |op| op.gte = Some(value))
+    }
+
+    pub fn lt(self, key: &str, value: impl Into<MetadataValue>) -> Self {
+        let value = value.into();
+        self.operator(key, |op| op.lt = Some(value))
+    }
+
+    pub fn lte(self, key: &str, value: impl Into<MetadataValue>) -> Self {
+        let value = value.into();
+        self.operator(key, |op| op.lte = Some(value))
+    }
+
+    pub fn is_in<V: Into<MetadataValue>>(self, key: &str, values: impl IntoIterator<Item = V>) -> Self {
+        let values = values.into_iter().map(Into::into).collect();
+        self.operator(key, |op| op.in_values = Some(values))
+    }
+
+    pub fn not_in<V: Into<MetadataValue>>(self, key: &str, values: impl IntoIterator<Item = V>) -> Self {
+        let values = values.into_iter().map(Into::into).collect();
+        self.operator(key, |op| op.nin = Some(values))
+    }
+
+    pub fn exists(self, key: &str, exists: bool) -> Self {
+        self.operator(key, |op| op.exists = Some(exists))
+    }
+
+    pub fn geo_radius(self, key: &str, lat: f64, lon: f64, radius_km: f64) -> Self {
+        self.operator(key, |op| op.geo_radius = Some(GeoRadius { lat, lon, radius_km }))
+    }
+
+    pub fn build(self) -> Result<WhereFilter> {
+        if let Some(error) = self.error {
+            return Err(VectorDbError::InvalidFilter(error));
+        }
+        validate_filter(&self.filter)?;
+        Ok(self.filter)
+    }
+
+    fn operator(mut self, key: &str, set: impl FnOnce(&mut FilterOperator)) -> Self {
+        match self
+            .filter
+            .entry(key.to_string())
+            .or_insert_with(|| FilterValue::Operator(FilterOperator::default()))
+        {
+            FilterValue::Operator(op) => set(op),
+            FilterValue::Direct(_) => self.conflict(key),
+        }
+        self
+    }
+
+    // première erreur conservée, remontée par build()
+    fn conflict(&mut self, key: &str) {
+        self.error.get_or_insert_with(|| {
+            format!("'{}': equality cannot be combined with another condition", key)
+        });
+    }
+}
+
+// les clés peuvent désigner un champ imbriqué ("author.country").
+// sémantique de Null : une clé absente et une valeur Null sont équivalentes pour
+// tous les opérateurs sauf $exists, qui teste seulement la présence de la clé.
+//   {"k": null}          -> k absente ou Null
+//   {"k": v}             -> k == v (Null n'est jamais égal à une valeur)
+//   {"$ne": null}        -> k présente et non Null
+//   {"$ne": v}           -> k présente, non Null et != v
+//   {"$in": [...]}       -> k dans la liste, ou k absente/Null si la liste contient null
+//   {"$nin": [...]}      -> k hors de la liste, k absente/Null sauf si la liste contient null
+//   {"$exists": b}       -> présence de la clé (une valeur Null compte comme présente)
+//   $gt/$gte/$lt/$lte, $geo_radius -> jamais vrais sur une clé absente ou Null
+pub fn matches_filter(metadata: &HashMap<String, MetadataValue>, filter: &WhereFilter) -> bool {
+    filter.iter().all(|(key, filter_value)| matches_value(lookup_path(metadata, key), filter_value))
+}
+
+// une clause du filtre sur la valeur d'une clé (None : clé absente), commune à
+// l'évaluation par ligne et par colonne (voir columns)
+pub(crate) fn matches_value(present: Option<&MetadataValue>, filter_value: &FilterValue) -> bool {
+    let value = present.filter(|v| **v != MetadataValue::Null);
+    match filter_value {
+        FilterValue::Direct(MetadataValue::Null) => value.is_none(),
+        FilterValue::Direct(expected) => value == Some(expected),
+        FilterValue::Operator(op) => matches_operator(present, value, op),
+    }
+}
+
+fn matches_operator(
+    present: Option<&MetadataValue>,
+    value: Option<&MetadataValue>,
+    op: &FilterOperator,
+) -> bool {
+    let as_listed = |list: &Vec<MetadataValue>| match value {
+        Some(val) => list.contains(val),
+        None => list.contains(&MetadataValue::Null),
+    };
+
+    if let Some(ref ne_val) = op.ne {
+        if value.is_none() || value == Some(ne_val) {
+            return false;
+        }
+    }
+
+    if let Some(ref in_vals) = op.in_values {
+        if !as_listed(in_vals) {
+            return false;
+        }
+    }
+
+    if let Some(ref nin_vals) = op.nin {
+        if as_listed(nin_vals) {
+            return false;
+        }
+    }
+
+    if let Some(exists) = op.exists {
+        if present.is_some() != exists {
+            return false;
+        }
+    }
+
+    if !matches_range(value, op) {
+        return false;
+    }
+
+    if let Some(ref geo) = op.geo_radius {
+        if !value.is_some_and(|val| geo.contains(val)) {
+            return false;
         }
     }
 
@@ -71,12 +753,12 @@ mod tests {
     #[test]
     fn test_direct_match() {
         let mut metadata = HashMap::new();
-        metadata.insert("status".to_string(), MetadataValue::String("active".to_string()));
+        metadata.insert("status".to_string(), MetadataValue::String("active".into()));
 
         let mut filter = HashMap::new();
         filter.insert(
             "status".to_string(),
-            FilterValue::Direct(MetadataValue::String("active".to_string())),
+            FilterValue::Direct(MetadataValue::String("active".into())),
         );
 
         assert!(matches_filter(&metadata, &filter));
@@ -85,18 +767,264 @@ mod tests {
     #[test]
     fn test_ne_operator() {
         let mut metadata = HashMap::new();
-        metadata.insert("status".to_string(), MetadataValue::String("active".to_string()));
+        metadata.insert("status".to_string(), MetadataValue::String("active".into()));
 
         let mut filter = HashMap::new();
         filter.insert(
             "status".to_string(),
             FilterValue::Operator(FilterOperator {
-                ne: Some(MetadataValue::String("inactive".to_string())),
-                in_values: None,
-                nin: None,
+                ne: Some(MetadataValue::String("inactive".into())),
+                ..Default::default()
+            }),
+        );
+
+        assert!(matches_filter(&metadata, &filter));
+    }
+
+    #[test]
+    fn test_range_operators() {
+        let mut metadata = HashMap::new();
+        metadata.insert("_system.created_at".to_string(), MetadataValue::Int(1_700_000_000));
+
+        let mut filter = HashMap::new();
+        filter.insert(
+            "_system.created_at".to_string(),
+            FilterValue::Operator(FilterOperator {
+                gte: Some(MetadataValue::Int(1_699_996_400)),
+                lt: Some(MetadataValue::Float(1_700_000_000.5)),
+                ..Default::default()
+            }),
+        );
+        assert!(matches_filter(&metadata, &filter));
+
+        filter.insert(
+            "_system.created_at".to_string(),
+            FilterValue::Operator(FilterOperator {
+                gt: Some(MetadataValue::Int(1_700_000_000)),
+                ..Default::default()
             }),
         );
+        assert!(!matches_filter(&metadata, &filter));
 
+        // clé absente : aucune comparaison possible
+        filter.clear();
+        filter.insert(
+            "price".to_string(),
+            FilterValue::Operator(FilterOperator {
+                lte: Some(MetadataValue::Int(10)),
+                ..Default::default()
+            }),
+        );
+        assert!(!matches_filter(&metadata, &filter));
+    }
+
+    #[test]
+    fn test_parse_filter_datetime_range() {
+        let mut metadata = HashMap::new();
+        metadata.insert(
+            "published".to_string(),
+            MetadataValue::parse_datetime("2024-06-15T10:00:00Z").unwrap(),
+        );
+        metadata.insert("lang".to_string(), MetadataValue::from("fr"));
+
+        let json = serde_json::json!({
+            "lang": "fr",
+            "published": {"$gte": "2024-06-01T00:00:00Z", "$lt": "2024-07-01T00:00:00+02:00"}
+        });
+        let filter = parse_filter(&json).unwrap();
+        assert!(matches_filter(&metadata, &filter));
+
+        let json = serde_json::json!({"published": {"$gt": "2024-06-15T10:00:00Z"}});
+        let filter = parse_filter(&json).unwrap();
+        assert!(!matches_filter(&metadata, &filter));
+    }
+
+    #[test]
+    fn test_parse_filter_unknown_operator() {
+        let json = serde_json::json!({"lang": {"$regex": "f.*"}});
+        assert!(parse_filter(&json).is_err());
+    }
+
+    #[test]
+    fn test_geo_radius() {
+        let mut metadata = HashMap::new();
+        let point = serde_json::json!({"lat": 48.8606, "lon": 2.3376});
+        metadata.insert("location".to_string(), MetadataValue::from_json(point));
+
+        // Louvre à ~1.2 km de Notre-Dame
+        let json = serde_json::json!({
+            "location": {"$geo_radius": {"lat": 48.8530, "lon": 2.3499, "radius_km": 5.0}}
+        });
+        assert!(matches_filter(&metadata, &parse_filter(&json).unwrap()));
+
+        let json = serde_json::json!({
+            "location": {"$geo_radius": {"lat": 48.8530, "lon": 2.3499, "radius_km": 1.0}}
+        });
+        assert!(!matches_filter(&metadata, &parse_filter(&json).unwrap()));
+    }
+
+    #[test]
+    fn test_null_semantics() {
+        let mut metadata = HashMap::new();
+        metadata.insert("author".to_string(), MetadataValue::Null);
+        metadata.insert("lang".to_string(), MetadataValue::from("fr"));
+
+        let check = |json: serde_json::Value| matches_filter(&metadata, &parse_filter(&json).unwrap());
+
+        // égalité
+        assert!(check(serde_json::json!({"author": null})));
+        assert!(check(serde_json::json!({"missing": null})));
+        assert!(!check(serde_json::json!({"lang": null})));
+        assert!(!check(serde_json::json!({"author": "null"})));
+
+        // $ne
+        assert!(!check(serde_json::json!({"author": {"$ne": null}})));
+        assert!(check(serde_json::json!({"lang": {"$ne": null}})));
+        assert!(!check(serde_json::json!({"author": {"$ne": "bob"}})));
+
+        // $in / $nin
+        assert!(check(serde_json::json!({"author": {"$in": [null, "bob"]}})));
+        assert!(!check(serde_json::json!({"author": {"$in": ["bob"]}})));
+        assert!(check(serde_json::json!({"author": {"$nin": ["bob"]}})));
+        assert!(!check(serde_json::json!({"missing": {"$nin": [null]}})));
+
+        // $exists : Null compte comme présent
+        assert!(check(serde_json::json!({"author": {"$exists": true}})));
+        assert!(check(serde_json::json!({"missing": {"$exists": false}})));
+        assert!(!check(serde_json::json!({"lang": {"$exists": false}})));
+
+        // comparaisons
+        assert!(!check(serde_json::json!({"author": {"$gte": ""}})));
+    }
+
+    #[test]
+    fn test_combined_operators_on_same_key() {
+        let mut metadata = HashMap::new();
+        metadata.insert("price".to_string(), MetadataValue::Int(50));
+
+        let json = serde_json::json!({"price": {"$ne": 10, "$lt": 20}});
+        assert!(!matches_filter(&metadata, &parse_filter(&json).unwrap()));
+    }
+
+    #[test]
+    fn test_where_str() {
+        let json = where_str_to_json(
+            "lang = 'fr' AND price >= 10 and price < 20.5 AND tag IN ('a', 'l''été') \
+             AND author.country != null AND \"note-x\" NOT EXISTS AND published >= '2024-06-01T00:00:00Z'",
+        )
+        .unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "lang": "fr",
+                "price": {"$gte": 10, "$lt": 20.5},
+                "tag": {"$in": ["a", "l'été"]},
+                "author.country": {"$ne": null},
+                "note-x": {"$exists": false},
+                "published": {"$gte": "2024-06-01T00:00:00Z"}
+            })
+        );
+
+        let mut metadata = HashMap::new();
+        metadata.insert("lang".to_string(), MetadataValue::from("fr"));
+        metadata.insert("price".to_string(), MetadataValue::Int(-3));
+        assert!(matches_filter(&metadata, &parse_where_str("lang == 'fr' AND price > -5e0").unwrap()));
+        assert!(!matches_filter(&metadata, &parse_where_str("price NOT IN (-3, 4)").unwrap()));
+
+        for (input, message) in [
+            ("lang = 'fr' OR lang = 'en'", "OR is not supported"),
+            ("lang = 'fr", "unterminated quote at position 7"),
+            ("lang = 'fr' price > 3", "expected AND at position 12, found 'price'"),
+            ("price >", "expected a value at the end"),
+            ("price > 3 AND price > 4", "'price' has $gt twice"),
+            ("price = 3 AND price < 4", "cannot combine an equality"),
+            ("tag IN ('a' 'b')", "expected ',' or ')' at position 12"),
+            ("AND = 3", "expected a key at position 0"),
+        ] {
+            let err = where_str_to_json(input).unwrap_err().to_string();
+            assert!(err.contains(message), "{}: {}", input, err);
+        }
+    }
+
+    #[test]
+    fn test_nested_dot_path() {
+        let json = serde_json::json!({"author": {"name": "Zola", "country": "FR"}});
+        let mut metadata = HashMap::new();
+        for (k, v) in json.as_object().unwrap() {
+            metadata.insert(k.clone(), MetadataValue::from_json(v.clone()));
+        }
+
+        let check = |json: serde_json::Value| matches_filter(&metadata, &parse_filter(&json).unwrap());
+        assert!(check(serde_json::json!({"author.country": "FR"})));
+        assert!(check(serde_json::json!({"author.country": {"$in": ["FR", "BE"]}})));
+        assert!(!check(serde_json::json!({"author.country": "US"})));
+        assert!(check(serde_json::json!({"author.birth": {"$exists": false}})));
+
+        // aller-retour JSON
+        assert_eq!(metadata["author"].to_json(), json["author"]);
+    }
+
+    #[test]
+    fn test_validate_filter_structure() {
+        let validate = |json: serde_json::Value| validate_filter(&parse_filter(&json).unwrap());
+
+        assert!(validate(serde_json::json!({"price": {"$gte": 10, "$lt": 20.5}})).is_ok());
+        assert!(validate(serde_json::json!({"lang": {"$in": ["fr", "en", null]}})).is_ok());
+
+        let err = validate(serde_json::json!({"active": {"$gt": true}})).unwrap_err();
+        assert!(err.to_string().contains("'active': $gt expects"));
+        assert!(validate(serde_json::json!({"price": {"$gte": 10, "$lt": "20"}})).is_err());
+        assert!(validate(serde_json::json!({"lang": {"$in": ["fr", true]}})).is_err());
+        assert!(validate(serde_json::json!({"loc": {"$geo_radius": {"lat": 95.0, "lon": 0.0, "radius_km": 1.0}}})).is_err());
+        assert!(validate(serde_json::json!({"price": {"$gt": 1, "unit": "eur"}})).is_err());
+
+        // opérateur inconnu via serde : plus ignoré silencieusement
+        let raw = serde_json::json!({"lang": {"$regex": "f.*"}});
+        assert!(serde_json::from_value::<WhereFilter>(raw).is_err());
+    }
+
+    #[test]
+    fn test_check_filter_types_against_data() {
+        let mut metadata = HashMap::new();
+        metadata.insert("active".to_string(), MetadataValue::Bool(true));
+        metadata.insert("price".to_string(), MetadataValue::Float(9.5));
+        let metadatas = [metadata];
+
+        let check = |json: serde_json::Value| {
+            let filter = parse_filter(&json).unwrap();
+            let keys: Vec<&str> = filter.keys().map(String::as_str).collect();
+            check_filter_types(&filter, &observed_types(&keys, metadatas.iter()))
+        };
+
+        let err = check(serde_json::json!({"active": {"$in": ["true", "false"]}})).unwrap_err();
+        assert!(err.to_string().contains("$in compares string values but the key holds bool"));
+        assert!(check(serde_json::json!({"active": {"$in": [true, null]}})).is_ok());
+        assert!(check(serde_json::json!({"price": {"$lt": 10}})).is_ok());
+        assert!(check(serde_json::json!({"price": "cheap"})).is_err());
+        // clé absente des données : pas de contrainte de type
+        assert!(check(serde_json::json!({"missing": "x"})).is_ok());
+    }
+
+    #[test]
+    fn test_filter_builder() {
+        let filter = FilterBuilder::new()
+            .eq("lang", "fr")
+            .gte("year", 2020)
+            .lt("year", 2024)
+            .is_in("tag", ["a", "b"])
+            .build()
+            .unwrap();
+
+        let mut metadata = HashMap::new();
+        metadata.insert("lang".to_string(), MetadataValue::from("fr"));
+        metadata.insert("year".to_string(), MetadataValue::Int(2022));
+        metadata.insert("tag".to_string(), MetadataValue::from("b"));
         assert!(matches_filter(&metadata, &filter));
+
+        metadata.insert("year".to_string(), MetadataValue::Int(2024));
+        assert!(!matches_filter(&metadata, &filter));
+
+        assert!(FilterBuilder::new().eq("lang", "fr").ne("lang", "en").build().is_err());
+        assert!(FilterBuilder::new().gt("active", true).build().is_err());
     }
 }
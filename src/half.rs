@@ -0,0 +1,237 @@
+// stockage des embeddings en demi-précision (config.precision) : 2 octets par
+// composante au lieu de 4. f16 (IEEE 754 binary16) garde 10 bits de mantisse, assez
+// pour des composantes normalisées ; bf16 garde l'exposant des f32 et 7 bits de
+// mantisse. Les distances reconvertissent en f32 par blocs de HALF_BLOCK composantes,
+// boucles de taille fixe que le compilateur vectorise
+use serde::{Deserialize, Serialize};
+
+// vecteurs en demi-précision, à côté de embeddings.bin
+pub const HALF_FILE: &str = "half.bin";
+
+// composantes reconverties à la fois par dot
+const HALF_BLOCK: usize = 16;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Precision {
+    #[default]
+    F32,
+    F16,
+    Bf16,
+}
+
+impl Precision {
+    pub fn is_half(self) -> bool {
+        self != Precision::F32
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Precision::F32 => "f32",
+            Precision::F16 => "f16",
+            Precision::Bf16 => "bf16",
+        }
+    }
+
+    pub fn component_bytes(self) -> usize {
+        if self.is_half() { 2 } else { 4 }
+    }
+
+    fn encode(self, x: f32) -> u16 {
+        match self {
+            Precision::Bf16 => f32_to_bf16(x),
+            _ => f32_to_f16(x),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HalfVector {
+    precision: Precision,
+    bits: Vec<u16>,
+}
+
+impl HalfVector {
+    // precision : F16 ou Bf16
+    pub fn encode(vector: &[f32], precision: Precision) -> Self {
+        Self { precision, bits: vector.iter().map(|&x| precision.encode(x)).collect() }
+    }
+
+    pub fn decode(&self) -> Vec<f32> {
+        let mut out = vec![0.0; self.bits.len()];
+        match self.precision {
+            Precision::Bf16 => decode_into(&self.bits, &mut out, bf16_to_f32),
+            _ => decode_into(&self.bits, &mut out, f16_to_f32),
+        }
+        out
+    }
+
+    pub fn precision(&self) -> Precision {
+        self.precision
+    }
+
+    pub fn len(&self) -> usize {
+        self.bits.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.bits.is_empty()
+    }
+
+    pub fn bytes(&self) -> usize {
+        self.bits.len() * 2
+    }
+
+    // produit scalaire avec une requête f32, sans reconstruire le vecteur
+    #[inline]
+    pub fn dot(&self, query: &[f32]) -> f32 {
+        match self.precision {
+            Precision::Bf16 => dot_blocks(&self.bits, query, bf16_to_f32),
+            _ => dot_blocks(&self.bits, query, f16_to_f32),
+        }
+    }
+
+    // distance cosinus à une requête normalisée
+    #[inline]
+    pub fn distance(&self, query: &[f32]) -> f32 {
+        1.0 - self.dot(query)
+    }
+}
+
+fn decode_into(bits: &[u16], out: &mut [f32], convert: impl Fn(u16) -> f32) {
+    for (o, &b) in out.iter_mut().zip(bits) {
+        *o = convert(b);
+    }
+}
+
+#[inline(always)]
+fn dot_blocks(bits: &[u16], query: &[f32], convert: impl Fn(u16) -> f32) -> f32 {
+    let n = bits.len().min(query.len());
+    let (bits, query) = (&bits[..n], &query[..n]);
+    let mut acc = [0.0f32; HALF_BLOCK];
+    let mut block = [0.0f32; HALF_BLOCK];
+    let mut bit_chunks = bits.chunks_exact(HALF_BLOCK);
+    let mut query_chunks = query.chunks_exact(HALF_BLOCK);
+    for (b, q) in (&mut bit_chunks).zip(&mut query_chunks) {
+        for (x, &h) in block.iter_mut().zip(b) {
+            *x = convert(h);
+        }
+        for ((a, x), y) in acc.iter_mut().zip(&block).zip(q) {
+            *a += x * y;
+        }
+    }
+    let tail: f32 = bit_chunks.remainder().iter().zip(query_chunks.remainder()).map(|(&h, y)| convert(h) * y).sum();
+    acc.iter().sum::<f32>() + tail
+}
+
+// arrondi au plus proche pair ; hors plage : ±inf, sous 2^-25 : ±0
+pub fn f32_to_f16(x: f32) -> u16 {
+    let bits = x.to_bits();
+    let sign = ((bits >> 16) & 0x8000) as u16;
+    let exp = ((bits >> 23) & 0xff) as i32;
+    let mant = bits & 0x7f_ffff;
+    if exp == 0xff {
+        return sign | 0x7c00 | if mant != 0 { 0x200 } else { 0 };
+    }
+    let e = exp - 127 + 15;
+    if e >= 31 {
+        return sign | 0x7c00;
+    }
+    let (mut half, rest, halfway) = if e <= 0 {
+        // sous-normal : mantisse implicite comprise, décalée de 14 - e bits
+        if e < -10 {
+            return sign;
+        }
+        let m = mant | 0x80_0000;
+        let shift = (14 - e) as u32;
+        (m >> shift, m & ((1 << shift) - 1), 1 << (shift - 1))
+    } else {
+        (((e as u32) << 10) | (mant >> 13), mant & 0x1fff, 0x1000)
+    };
+    // la retenue peut passer dans l'exposant : résultat toujours correct
+    if rest > halfway || (rest == halfway && half & 1 == 1) {
+        half += 1;
+    }
+    sign | half as u16
+}
+
+#[inline(always)]
+pub fn f16_to_f32(h: u16) -> f32 {
+    let sign = ((h & 0x8000) as u32) << 16;
+    let exp = ((h >> 10) & 0x1f) as u32;
+    let mant = (h & 0x3ff) as u32;
+    match exp {
+        // zéro et sous-normaux : mant × 2^-24
+        0 => {
+            let x = mant as f32 / 16_777_216.0;
+            if sign != 0 { -x } else { x }
+        }
+        31 => f32::from_bits(sign | 0x7f80_0000 | (mant << 13)),
+        _ => f32::from_bits(sign | ((exp + 112) << 23) | (mant << 13)),
+    }
+}
+
+// arrondi au plus proche pair sur les 16 bits de poids faible
+pub fn f32_to_bf16(x: f32) -> u16 {
+    let bits = x.to_bits();
+    if x.is_nan() {
+        return ((bits >> 16) | 0x40) as u16;
+    }
+    let rounding = 0x7fff + ((bits >> 16) & 1);
+    (bits.wrapping_add(rounding) >> 16) as u16
+}
+
+#[inline(always)]
+pub fn bf16_to_f32(h: u16) -> f32 {
+    f32::from_bits((h as u32) << 16)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_f16_conversions() {
+        for (x, h) in [(0.0, 0x0000), (-0.0, 0x8000), (1.0, 0x3c00), (-2.0, 0xc000), (65504.0, 0x7bff), (0.5, 0x3800)] {
+            assert_eq!(f32_to_f16(x), h, "{}", x);
+            assert_eq!(f16_to_f32(h), x);
+        }
+        // plus petit sous-normal, dépassement, arrondi au pair
+        assert_eq!(f32_to_f16(5.960_464_5e-8), 0x0001);
+        assert_eq!(f16_to_f32(0x0001), 5.960_464_5e-8);
+        assert_eq!(f16_to_f32(0x8001), -5.960_464_5e-8);
+        assert_eq!(f32_to_f16(1e6), 0x7c00);
+        assert_eq!(f32_to_f16(1.0 + 1.0 / 2048.0), 0x3c00);
+        assert_eq!(f32_to_f16(1.0 + 3.0 / 2048.0), 0x3c02);
+        assert!(f16_to_f32(f32_to_f16(f32::NAN)).is_nan());
+        // aller-retour sur les composantes d'un vecteur normalisé : erreur relative < 2^-11
+        for i in 0..2000 {
+            let x = (i as f32 - 1000.0) / 1000.0 * 0.37;
+            let y = f16_to_f32(f32_to_f16(x));
+            assert!((x - y).abs() <= x.abs() / 2048.0 + 6e-8, "{} {}", x, y);
+        }
+    }
+
+    #[test]
+    fn test_bf16_conversions() {
+        assert_eq!(f32_to_bf16(1.0), 0x3f80);
+        assert_eq!(bf16_to_f32(0x3f80), 1.0);
+        assert_eq!(f32_to_bf16(-3.0e38), 0xff62);
+        assert_eq!(bf16_to_f32(f32_to_bf16(1.0 + 1.0 / 256.0)), 1.0);
+        assert!(bf16_to_f32(f32_to_bf16(f32::NAN)).is_nan());
+    }
+
+    #[test]
+    fn test_half_vector_dot() {
+        let v: Vec<f32> = (0..37).map(|i| ((i * 31 % 17) as f32 - 8.0) / 20.0).collect();
+        let q: Vec<f32> = (0..37).map(|i| ((i * 7 % 11) as f32 - 5.0) / 10.0).collect();
+        let exact: f32 = v.iter().zip(&q).map(|(a, b)| a * b).sum();
+        for (precision, tolerance) in [(Precision::F16, 1e-3), (Precision::Bf16, 2e-2)] {
+            let half = HalfVector::encode(&v, precision);
+            assert_eq!(half.bytes(), 74);
+            assert!((half.dot(&q) - exact).abs() < tolerance);
+            let decoded: f32 = half.decode().iter().zip(&q).map(|(a, b)| a * b).sum();
+            assert!((half.dot(&q) - decoded).abs() < 1e-5);
+        }
+    }
+}
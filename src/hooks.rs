@@ -0,0 +1,149 @@
+// rappels sur le cycle de vie des collections (ajouts, suppressions, requêtes,
+// rebuilds), enregistrés avec VectorDbClient::add_hooks : métriques maison, cache
+// applicatif, effets de bord, sans modifier la crate.
+// Appelés de façon synchrone, verrou de la collection tenu : un rappel doit rester
+// court et ne pas rappeler le client sur la même collection
+use crate::collection::AddOutcome;
+use std::fmt;
+use std::sync::Arc;
+use std::time::Duration;
+
+// toutes les méthodes ont une implémentation vide : n'implémenter que les utiles
+pub trait Hooks: Send + Sync {
+    fn on_add(&self, _event: &AddEvent) {}
+    fn on_delete(&self, _event: &DeleteEvent) {}
+    fn on_query(&self, _event: &QueryEvent) {}
+    fn on_rebuild(&self, _event: &RebuildEvent) {}
+}
+
+#[derive(Debug)]
+pub struct AddEvent<'a> {
+    pub collection: &'a str,
+    // ids du lot, ignorés compris (voir outcome)
+    pub ids: &'a [String],
+    pub outcome: &'a AddOutcome,
+    pub source: &'a str,
+    pub duration: Duration,
+}
+
+#[derive(Debug)]
+pub struct DeleteEvent<'a> {
+    pub collection: &'a str,
+    // ids demandés, absents compris
+    pub ids: &'a [String],
+    pub removed: usize,
+    pub duration: Duration,
+}
+
+#[derive(Debug)]
+pub struct QueryEvent<'a> {
+    pub collection: &'a str,
+    pub n_results: usize,
+    pub returned: usize,
+    pub filtered: bool,
+    pub exact: bool,
+    pub duration: Duration,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RebuildKind {
+    // rebuild sous verrou, au fil des requêtes
+    InPlace,
+    // begin_rebuild / finish_rebuild : durée de la capture à l'échange
+    Background,
+    // réécriture des listes IVF sur disque (offload_to_disk)
+    Disk,
+}
+
+#[derive(Debug)]
+pub struct RebuildEvent<'a> {
+    pub collection: &'a str,
+    pub kind: RebuildKind,
+    pub vectors: usize,
+    pub n_clusters: usize,
+    pub duration: Duration,
+}
+
+// rappels enregistrés, partagés par le client et les collections qu'il charge
+#[derive(Clone, Default)]
+pub struct HookSet(Arc<Vec<Arc<dyn Hooks>>>);
+
+impl HookSet {
+    pub fn push(&mut self, hooks: Arc<dyn Hooks>) {
+        Arc::make_mut(&mut self.0).push(hooks);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn emit(&self, f: impl Fn(&dyn Hooks)) {
+        self.0.iter().for_each(|hooks| f(hooks.as_ref()));
+    }
+}
+
+impl fmt::Debug for HookSet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "HookSet({} registered)", self.0.len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::TempDir;
+    use crate::client::VectorDbClient;
+    use std::sync::Mutex;
+
+    #[derive(Default)]
+    struct Recorder(Mutex<Vec<String>>);
+
+    impl Hooks for Recorder {
+        fn on_add(&self, event: &AddEvent) {
+            self.0.lock().unwrap().push(format!("add {} {}", event.collection, event.outcome.added));
+        }
+
+        fn on_delete(&self, event: &DeleteEvent) {
+            self.0.lock().unwrap().push(format!("delete {} {}", event.collection, event.removed));
+        }
+
+        fn on_query(&self, event: &QueryEvent) {
+            self.0.lock().unwrap().push(format!("query {} {}", event.collection, event.returned));
+        }
+
+        fn on_rebuild(&self, event: &RebuildEvent) {
+            self.0.lock().unwrap().push(format!("rebuild {:?} {}", event.kind, event.vectors));
+        }
+    }
+
+    #[test]
+    fn test_hooks_see_lifecycle_events() {
+        let dir = TempDir::new("hooks");
+        let mut client = VectorDbClient::new(&dir).unwrap();
+        client.create_collection_with_ivf("docs".into(), 2, 2).unwrap();
+        let recorder = Arc::new(Recorder::default());
+        client.add_hooks(recorder.clone());
+
+        let ids: Vec<String> = (0..20).map(|i| format!("v{}", i)).collect();
+        let embeddings = (0..20).map(|i| vec![1.0, i as f32]).collect();
+        client.with_collection_mut("docs", |c| c.add(ids, embeddings, None)).unwrap();
+        // le rebuild IVF dû se fait à la première requête
+        let handle = client.collection("docs").unwrap();
+        handle.query(&[1.0, 0.0], 3, None).unwrap();
+        handle.delete(vec!["v1".into(), "missing".into()]).unwrap();
+        client.rebuild_index_background("docs").unwrap();
+
+        let events = recorder.0.lock().unwrap().clone();
+        assert_eq!(
+            events,
+            [
+                "add docs 20",
+                "rebuild InPlace 20",
+                "query docs 3",
+                "delete docs 1",
+                "rebuild Background 19",
+            ]
+        );
+        drop(handle);
+    }
+}
@@ -0,0 +1,342 @@
+use crate::client::VectorDbClient;
+use crate::embedding_model::EmbeddingProvider;
+use crate::error::{Result, VectorDbError};
+use crate::vector::MetadataValue;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SourceFormat {
+    Csv,
+    // une ligne JSON par enregistrement (export `Dataset.to_json` de HuggingFace datasets)
+    Jsonl,
+}
+
+impl SourceFormat {
+    pub fn from_path(path: &std::path::Path) -> Option<Self> {
+        match path.extension()?.to_str()? {
+            "csv" => Some(SourceFormat::Csv),
+            "jsonl" | "ndjson" | "json" => Some(SourceFormat::Jsonl),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct IngestConfig {
+    pub collection: String,
+    pub text_column: String,
+    // sans colonne d'id : <collection>-<numéro de ligne>
+    pub id_column: Option<String>,
+    // colonnes gardées en métadonnées, toutes les autres si vide
+    pub metadata_columns: Vec<String>,
+    // clé de métadonnée recevant le texte embeddé, None pour ne pas le stocker
+    pub document_key: Option<String>,
+    pub batch_size: usize,
+    // plafond d'appels au provider, None = pas de limite
+    pub max_requests_per_sec: Option<f64>,
+}
+
+impl IngestConfig {
+    pub fn new(collection: String, text_column: String) -> Self {
+        Self {
+            collection,
+            text_column,
+            id_column: None,
+            metadata_columns: Vec::new(),
+            document_key: Some("document".to_string()),
+            batch_size: 64,
+            max_requests_per_sec: None,
+        }
+    }
+
+    pub fn with_id_column(mut self, column: String) -> Self {
+        self.id_column = Some(column);
+        self
+    }
+
+    pub fn with_metadata_columns(mut self, columns: Vec<String>) -> Self {
+        self.metadata_columns = columns;
+        self
+    }
+
+    pub fn with_batch_size(mut self, batch_size: usize) -> Self {
+        self.batch_size = batch_size.max(1);
+        self
+    }
+
+    pub fn with_rate_limit(mut self, max_requests_per_sec: f64) -> Self {
+        self.max_requests_per_sec = Some(max_requests_per_sec);
+        self
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case", tag = "state", content = "error")]
+pub enum IngestState {
+    Running,
+    Completed,
+    Failed(String),
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct IngestStatus {
+    pub state: IngestState,
+    pub rows_read: usize,
+    pub rows_inserted: usize,
+    pub batches: usize,
+    pub elapsed_ms: f64,
+}
+
+// tâche d'ingestion en arrière-plan ; status() peut être consulté pendant l'exécution
+pub struct IngestJob {
+    status: Arc<Mutex<IngestStatus>>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl IngestJob {
+    pub fn status(&self) -> IngestStatus {
+        self.status.lock().unwrap().clone()
+    }
+
+    pub fn wait(mut self) -> IngestStatus {
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+        self.status()
+    }
+}
+
+type Row = HashMap<String, MetadataValue>;
+
+// lecture ligne à ligne, sans charger le fichier en mémoire
+fn read_rows(path: &PathBuf, format: SourceFormat) -> Result<Box<dyn Iterator<Item = Result<Row>>>> {
+    let file = File::open(path)?;
+    match format {
+        SourceFormat::Csv => {
+            let reader = csv::Reader::from_reader(BufReader::new(file));
+            Ok(Box::new(reader.into_deserialize::<HashMap<String, String>>().map(|record| {
+                record
+                    .map(|r| r.into_iter().map(|(k, v)| (k, MetadataValue::String(v.into()))).collect())
+                    .map_err(|e| VectorDbError::Serialization(e.to_string()))
+            })))
+        }
+        SourceFormat::Jsonl => Ok(Box::new(
+            BufReader::new(file)
+                .lines()
+                .filter(|line| !matches!(line, Ok(l) if l.trim().is_empty()))
+                .map(|line| {
+                    let value: serde_json::Value = serde_json::from_str(&line?)?;
+                    match value {
+                        serde_json::Value::Object(map) => Ok(map
+                            .into_iter()
+                            .map(|(k, v)| (k, MetadataValue::from_json(v)))
+                            .collect()),
+                        _ => Err(VectorDbError::Serialization(
+                            "each JSONL line must be an object".to_string(),
+                        )),
+                    }
+                }),
+        )),
+    }
+}
+
+struct Pending {
+    ids: Vec<String>,
+    texts: Vec<String>,
+    metadatas: Vec<HashMap<String, MetadataValue>>,
+}
+
+fn run(
+    client: &VectorDbClient,
+    path: &PathBuf,
+    format: SourceFormat,
+    config: &IngestConfig,
+    provider: &dyn EmbeddingProvider,
+    status: &Mutex<IngestStatus>,
+) -> Result<()> {
+    client.with_collection(&config.collection, |coll| {
+        coll.check_embedding_model(Some(provider.model()))?;
+        if coll.config.dimension != provider.dimension() {
+            return Err(VectorDbError::DimensionMismatch {
+                expected: coll.config.dimension,
+                actual: provider.dimension(),
+            });
+        }
+        Ok(())
+    })??;
+
+    let min_interval = config
+        .max_requests_per_sec
+        .filter(|r| *r > 0.0)
+        .map(|r| Duration::from_secs_f64(1.0 / r));
+    let mut last_call: Option<Instant> = None;
+
+    let mut flush = |pending: &mut Pending| -> Result<()> {
+        if pending.ids.is_empty() {
+            return Ok(());
+        }
+        // limitation de débit vers l'API d'embedding
+        if let (Some(interval), Some(last)) = (min_interval, last_call) {
+            let elapsed = last.elapsed();
+            if elapsed < interval {
+                std::thread::sleep(interval - elapsed);
+            }
+        }
+        last_call = Some(Instant::now());
+
+        let embeddings = provider.embed(&pending.texts)?;
+        let n = pending.ids.len();
+        client.with_collection_mut(&config.collection, |coll| {
+            coll.add_with_source(
+                std::mem::take(&mut pending.ids),
+                embeddings,
+                Some(std::mem::take(&mut pending.metadatas)),
+                "ingest",
+            )
+        })?;
+        pending.texts.clear();
+
+        let mut status = status.lock().unwrap();
+        status.rows_inserted += n;
+        status.batches += 1;
+        Ok(())
+    };
+
+    let mut pending = Pending { ids: Vec::new(), texts: Vec::new(), metadatas: Vec::new() };
+    for (line, row) in read_rows(path, format)?.enumerate() {
+        let mut row = row?;
+        status.lock().unwrap().rows_read += 1;
+
+        let text = match row.remove(&config.text_column) {
+            Some(MetadataValue::String(text)) => text.to_string(),
+            _ => {
+                return Err(VectorDbError::InvalidConfig(format!(
+                    "row {}: missing text column '{}'",
+                    line + 1,
+                    config.text_column
+                )))
+            }
+        };
+        let id = match config.id_column.as_ref().and_then(|c| row.remove(c)) {
+            Some(MetadataValue::String(id)) => id.to_string(),
+            Some(MetadataValue::Int(id)) => id.to_string(),
+            _ => format!("{}-{}", config.collection, line),
+        };
+
+        let mut metadata: HashMap<String, MetadataValue> = if config.metadata_columns.is_empty() {
+            row
+        } else {
+            row.into_iter()
+                .filter(|(k, _)| config.metadata_columns.contains(k))
+                .collect()
+        };
+        if let Some(ref key) = config.document_key {
+            metadata.insert(key.clone(), MetadataValue::String(text.as_str().into()));
+        }
+
+        pending.ids.push(id);
+        pending.texts.push(text);
+        pending.metadatas.push(metadata);
+        if pending.ids.len() >= config.batch_size {
+            flush(&mut pending)?;
+        }
+    }
+    flush(&mut pending)
+}
+
+// lance l'ingestion dans un thread dédié ; la collection doit exister
+pub fn spawn_ingest(
+    client: Arc<VectorDbClient>,
+    path: PathBuf,
+    format: SourceFormat,
+    config: IngestConfig,
+    provider: Arc<dyn EmbeddingProvider>,
+) -> IngestJob {
+    let status = Arc::new(Mutex::new(IngestStatus {
+        state: IngestState::Running,
+        rows_read: 0,
+        rows_inserted: 0,
+        batches: 0,
+        elapsed_ms: 0.0,
+    }));
+
+    let job_status = status.clone();
+    let handle = std::thread::spawn(move || {
+        let start = Instant::now();
+        let result = run(&client, &path, format, &config, provider.as_ref(), &job_status);
+
+        let mut status = job_status.lock().unwrap();
+        status.elapsed_ms = start.elapsed().as_secs_f64() * 1000.0;
+        status.state = match result {
+            Ok(()) => IngestState::Completed,
+            Err(e) => {
+                tracing::warn!(collection = %config.collection, error = %e, "Ingestion failed");
+                IngestState::Failed(e.to_string())
+            }
+        };
+    });
+
+    IngestJob { status, handle: Some(handle) }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::TempDir;
+
+    // embedding factice : longueur du texte et nombre d'espaces
+    struct LengthProvider;
+
+    impl EmbeddingProvider for LengthProvider {
+        fn model(&self) -> &str {
+            "length"
+        }
+
+        fn dimension(&self) -> usize {
+            2
+        }
+
+        fn embed(&self, texts: &[String]) -> Result<Vec<Vec<f32>>> {
+            Ok(texts
+                .iter()
+                .map(|t| vec![t.len() as f32, t.matches(' ').count() as f32 + 1.0])
+                .collect())
+        }
+    }
+
+    #[test]
+    fn test_ingest_csv_in_batches() {
+        let dir = TempDir::new("ingest");
+        let csv_path = dir.join("docs.csv");
+        std::fs::write(&csv_path, "id,text,lang\na,hello world,en\nb,\"bonjour, le monde\",fr\nc,hola,es\n").unwrap();
+
+        let client = Arc::new(VectorDbClient::new(dir.join("db")).unwrap());
+        client.create_collection("docs".to_string(), 2).unwrap();
+
+        let config = IngestConfig::new("docs".to_string(), "text".to_string())
+            .with_id_column("id".to_string())
+            .with_batch_size(2);
+        let status = spawn_ingest(client.clone(), csv_path, SourceFormat::Csv, config, Arc::new(LengthProvider)).wait();
+
+        assert_eq!(status.state, IngestState::Completed);
+        assert_eq!(status.rows_inserted, 3);
+        assert_eq!(status.batches, 2);
+
+        let result = client
+            .with_collection("docs", |c| c.get(Some(vec!["b".to_string()]), None))
+            .unwrap()
+            .unwrap();
+        let metadata = &result.metadatas.unwrap()[0];
+        assert_eq!(metadata.get("lang"), Some(&MetadataValue::String("fr".into())));
+        assert_eq!(metadata.get("document"), Some(&MetadataValue::String("bonjour, le monde".into())));
+
+        drop(client);
+    }
+}
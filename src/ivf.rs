@@ -1,13 +1,64 @@
-use crate::distance::cosine_distance;
+use crate::distance::{cosine_distance, normalize_l2};
 use crate::kmeans::KMeans;
+use crate::pq::{ProductQuantizer, PQ_TRAIN_SAMPLE};
+use crate::topk::top_k_by;
 use serde::{Deserialize, Serialize};
 
+// centroïdes examinés par cluster demandé avec le quantificateur à deux niveaux :
+// marge contre les centroïdes proches rangés dans un groupe voisin
+const COARSE_CANDIDATES_PER_CLUSTER: usize = 4;
+
+// second niveau du quantificateur grossier : les centroïdes sont eux-mêmes regroupés
+// par k-means, la requête n'est comparée qu'aux centroïdes des groupes les plus proches
+// (≈ 2·√n_clusters distances au lieu de n_clusters)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CoarseLevel {
+    pub group_centroids: Vec<Vec<f32>>,
+    // indices des centroïdes de chaque groupe
+    pub groups: Vec<Vec<usize>>,
+}
+
+impl CoarseLevel {
+    fn build(centroids: &[Vec<f32>], n_groups: usize) -> Self {
+        let mut kmeans = KMeans::new(n_groups.min(centroids.len()).max(1));
+        kmeans.fit(centroids);
+        let mut groups = vec![Vec::new(); kmeans.centroids.len()];
+        for (idx, centroid) in centroids.iter().enumerate() {
+            groups[kmeans.predict(centroid)].push(idx);
+        }
+        // distance cosine au query normalisé : directions seules
+        let mut group_centroids = kmeans.centroids;
+        group_centroids.iter_mut().for_each(|c| normalize_l2(c));
+        Self { group_centroids, groups }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct IVFIndex {
     pub centroids: Vec<Vec<f32>>,
     pub inverted_lists: Vec<Vec<String>>,  // stocke les IDs directement
     pub n_clusters: usize,
     pub n_probe: usize,
+    // nombre de groupes du second niveau ; None : recherche exhaustive des centroïdes
+    #[serde(default)]
+    pub coarse_groups: Option<usize>,
+    #[serde(default)]
+    pub coarse: Option<CoarseLevel>,
+    // quantification produit des listes (IVF-PQ) : nombre de sous-espaces ; None :
+    // pas de codes, distances exactes seulement
+    #[serde(default)]
+    pub pq_subspaces: Option<usize>,
+    #[serde(default)]
+    pub pq: Option<ProductQuantizer>,
+    // codes PQ alignés sur inverted_lists : l'entrée i de la liste c a pour code
+    // pq_codes[c][i·m..(i+1)·m], m = pq.code_len(). Vide sans quantificateur
+    #[serde(default)]
+    pub pq_codes: Vec<Vec<u8>>,
+}
+
+// clusters réellement entraînés par build : n_clusters réduit à un pour dix vecteurs
+pub fn trained_clusters(n_clusters: usize, n_vectors: usize) -> usize {
+    n_clusters.min(n_vectors / 10).max(1)
 }
 
 impl IVFIndex {
@@ -17,6 +68,28 @@ impl IVFIndex {
             inverted_lists: vec![Vec::new(); n_clusters],
             n_clusters,
             n_probe: 4,  // valeur par défaut, chercher dans 4 clusters les plus proches
+            coarse_groups: None,
+            coarse: None,
+            pq_subspaces: None,
+            pq: None,
+            pq_codes: Vec::new(),
+        }
+    }
+
+    // index entraîné ailleurs (faiss) : centroïdes normalisés pour la distance cosinus
+    pub fn from_trained(mut centroids: Vec<Vec<f32>>, inverted_lists: Vec<Vec<String>>, n_probe: usize) -> Self {
+        centroids.iter_mut().for_each(|c| normalize_l2(c));
+        let n_clusters = centroids.len();
+        Self {
+            centroids,
+            inverted_lists,
+            n_clusters,
+            n_probe: n_probe.min(n_clusters),
+            coarse_groups: None,
+            coarse: None,
+            pq_subspaces: None,
+            pq: None,
+            pq_codes: Vec::new(),
         }
     }
 
@@ -25,6 +98,27 @@ impl IVFIndex {
         self
     }
 
+    pub fn with_coarse_groups(mut self, n_groups: Option<usize>) -> Self {
+        self.set_coarse_groups(n_groups);
+        self
+    }
+
+    // quantificateur entraîné au prochain build
+    pub fn with_pq(mut self, subspaces: Option<usize>) -> Self {
+        self.pq_subspaces = subspaces;
+        self
+    }
+
+    // (re)construit le second niveau sur les centroïdes existants, sans toucher aux
+    // listes inversées : modifiable sans rebuild de l'index
+    pub fn set_coarse_groups(&mut self, n_groups: Option<usize>) {
+        self.coarse_groups = n_groups;
+        self.coarse = match n_groups {
+            Some(n) if self.is_built() => Some(CoarseLevel::build(&self.centroids, n)),
+            _ => None,
+        };
+    }
+
     // construire l'index à partir des vecteurs avec leurs IDs
     pub fn build(&mut self, data: &[(String, Vec<f32>)]) {
         if data.is_empty() {
@@ -33,20 +127,63 @@ impl IVFIndex {
 
         let embeddings: Vec<Vec<f32>> = data.iter().map(|(_, emb)| emb.clone()).collect();
 
-        // réduire n_clusters si pas assez de vecteurs
-        let actual_clusters = self.n_clusters.min(embeddings.len() / 10).max(1);
+        let actual_clusters = trained_clusters(self.n_clusters, embeddings.len());
 
         let mut kmeans = KMeans::new(actual_clusters);
         kmeans.fit(&embeddings);
 
         self.centroids = kmeans.centroids.clone();
         self.inverted_lists = vec![Vec::new(); actual_clusters];
+        self.pq = self.pq_subspaces.map(|subspaces| train_pq(&embeddings, subspaces));
+        self.pq_codes = match self.pq {
+            Some(_) => vec![Vec::new(); actual_clusters],
+            None => Vec::new(),
+        };
 
         // assigner chaque vecteur à son cluster
         for (id, emb) in data.iter() {
             let cluster = kmeans.predict(emb);
-            self.inverted_lists[cluster].push(id.clone());
+            self.push_to(cluster, id.clone(), emb);
         }
+
+        self.set_coarse_groups(self.coarse_groups);
+    }
+
+    // les n_probe clusters les plus proches du query, du plus proche au plus lointain
+    pub fn probed_clusters(&self, query: &[f32]) -> Vec<usize> {
+        self.ranked_clusters(query, self.n_probe)
+    }
+
+    // les `limit` clusters les plus proches du query (usize::MAX : tous), dans l'ordre.
+    // Avec le second niveau, approximatif : seuls les centroïdes des groupes les plus
+    // proches sont classés
+    pub fn ranked_clusters(&self, query: &[f32], limit: usize) -> Vec<usize> {
+        let wanted = limit.saturating_mul(COARSE_CANDIDATES_PER_CLUSTER);
+        let candidates: Vec<usize> = match self.coarse {
+            Some(ref coarse) if wanted < self.centroids.len() => {
+                let group_distances = coarse.group_centroids.iter()
+                    .enumerate()
+                    .map(|(idx, c)| (idx, cosine_distance(query, c)));
+                let mut candidates = Vec::with_capacity(wanted);
+                for (group, _) in top_k_by(group_distances, usize::MAX, |a, b| a.1.total_cmp(&b.1)) {
+                    if candidates.len() >= wanted {
+                        break;
+                    }
+                    candidates.extend_from_slice(&coarse.groups[group]);
+                }
+                candidates
+            }
+            _ => (0..self.centroids.len()).collect(),
+        };
+
+        let distances = candidates
+            .into_iter()
+            .map(|idx| (idx, cosine_distance(query, &self.centroids[idx])));
+
+        top_k_by(distances, limit, |a, b| a.1.total_cmp(&b.1))
+            .into_iter()
+            .map(|(idx, _)| idx)
+            .collect()
     }
 
     // chercher les n_probe clusters les plus proches du query
@@ -55,18 +192,8 @@ impl IVFIndex {
             return Vec::new();
         }
 
-        let mut distances: Vec<(usize, f32)> = self.centroids.iter()
-            .enumerate()
-            .map(|(idx, c)| (idx, cosine_distance(query, c)))
-            .collect();
-
-        distances.sort_by(|a, b| a.1.partial_cmp(&b.1).unwrap());
-
-        let probe_count = self.n_probe.min(distances.len());
         let mut candidates = Vec::new();
-
-        for i in 0..probe_count {
-            let cluster_idx = distances[i].0;
+        for cluster_idx in self.probed_clusters(query) {
             candidates.extend(self.inverted_lists[cluster_idx].iter().cloned());
         }
 
@@ -78,9 +205,111 @@ impl IVFIndex {
         self.build(data);
     }
 
+    // à n'appeler que sur un index construit
+    pub fn nearest_centroid(&self, embedding: &[f32]) -> usize {
+        self.centroids.iter()
+            .enumerate()
+            .map(|(idx, c)| (idx, cosine_distance(embedding, c)))
+            .min_by(|a, b| a.1.partial_cmp(&b.1).unwrap_or(std::cmp::Ordering::Equal))
+            .map(|(idx, _)| idx)
+            .unwrap_or(0)
+    }
+
+    // ajout incrémental dans le cluster le plus proche, sans ré-entraîner les centroïdes
+    // ni le quantificateur
+    pub fn insert(&mut self, id: String, embedding: &[f32]) {
+        if self.is_built() {
+            let cluster = self.nearest_centroid(embedding);
+            self.push_to(cluster, id, embedding);
+        }
+    }
+
+    // ajout dans la liste `cluster`, avec son code PQ
+    pub fn push_to(&mut self, cluster: usize, id: String, embedding: &[f32]) {
+        self.inverted_lists[cluster].push(id);
+        if let Some(ref pq) = self.pq {
+            pq.encode_into(embedding, &mut self.pq_codes[cluster]);
+        }
+    }
+
+    // vide les listes en gardant centroïdes et quantificateur
+    pub fn clear_lists(&mut self) {
+        self.inverted_lists.iter_mut().for_each(Vec::clear);
+        self.pq_codes.iter_mut().for_each(Vec::clear);
+    }
+
+    pub fn remove(&mut self, id: &str) {
+        self.retain_ids(|existing| existing != id);
+    }
+
+    // retire des listes les ids refusés par `keep`, codes PQ compris
+    pub fn retain_ids(&mut self, mut keep: impl FnMut(&String) -> bool) {
+        for cluster in 0..self.inverted_lists.len() {
+            // à rebours : l'entrée déplacée par swap_remove a déjà été examinée
+            for pos in (0..self.inverted_lists[cluster].len()).rev() {
+                if !keep(&self.inverted_lists[cluster][pos]) {
+                    self.remove_at(cluster, pos);
+                }
+            }
+        }
+    }
+
+    // ids de la liste `cluster` avec leur code PQ ; None sans quantificateur
+    pub fn coded_list(&self, cluster: usize) -> Option<impl Iterator<Item = (&String, &[u8])>> {
+        let pq = self.pq.as_ref()?;
+        Some(self.inverted_lists[cluster].iter().zip(self.pq_codes[cluster].chunks_exact(pq.code_len())))
+    }
+
+    // suppression d'un vecteur connu : cherché d'abord dans la liste de son centroïde
+    // le plus proche (celle où build/insert l'a placé), parcours complet sinon
+    pub fn remove_near(&mut self, id: &str, embedding: &[f32]) {
+        if !self.is_built() {
+            return;
+        }
+        let cluster = self.nearest_centroid(embedding);
+        self.remove_from(cluster, id);
+    }
+
+    // suppression d'un vecteur attendu dans la liste `cluster`, parcours complet sinon
+    pub fn remove_from(&mut self, cluster: usize, id: &str) {
+        match self.inverted_lists[cluster].iter().position(|existing| existing == id) {
+            Some(pos) => self.remove_at(cluster, pos),
+            None => self.remove(id),
+        }
+    }
+
+    // l'ordre d'une liste n'a pas d'importance : la dernière entrée prend la place
+    // de celle retirée, son code aussi
+    fn remove_at(&mut self, cluster: usize, pos: usize) {
+        self.inverted_lists[cluster].swap_remove(pos);
+        if let Some(ref pq) = self.pq {
+            let m = pq.code_len();
+            let codes = &mut self.pq_codes[cluster];
+            let last = codes.len() - m;
+            codes.copy_within(last.., pos * m);
+            codes.truncate(last);
+        }
+    }
+
     pub fn is_built(&self) -> bool {
         !self.centroids.is_empty()
     }
+
+    pub fn pq_code_bytes(&self) -> usize {
+        self.pq_codes.iter().map(Vec::len).sum()
+    }
+}
+
+// codebooks entraînés sur au plus PQ_TRAIN_SAMPLE vecteurs tirés au hasard
+pub fn train_pq(embeddings: &[Vec<f32>], subspaces: usize) -> ProductQuantizer {
+    if embeddings.len() <= PQ_TRAIN_SAMPLE {
+        return ProductQuantizer::train(embeddings, subspaces);
+    }
+    let sample: Vec<Vec<f32>> = rand::seq::index::sample(&mut rand::thread_rng(), embeddings.len(), PQ_TRAIN_SAMPLE)
+        .into_iter()
+        .map(|i| embeddings[i].clone())
+        .collect();
+    ProductQuantizer::train(&sample, subspaces)
 }
 
 #[cfg(test)]
@@ -105,6 +334,47 @@ mod tests {
         assert!(ivf.is_built());
     }
 
+    #[test]
+    fn test_coarse_level() {
+        use rand::{Rng, SeedableRng};
+        let mut rng = rand::rngs::StdRng::seed_from_u64(7);
+        let data: Vec<(String, Vec<f32>)> = (0..2000)
+            .map(|i| {
+                let mut v: Vec<f32> = (0..8).map(|_| rng.gen_range(-1.0..1.0)).collect();
+                normalize_l2(&mut v);
+                (format!("id{}", i), v)
+            })
+            .collect();
+        let mut ivf = IVFIndex::new(64).with_coarse_groups(Some(8));
+        ivf.build(&data);
+
+        let coarse = ivf.coarse.as_ref().unwrap();
+        let mut grouped: Vec<usize> = coarse.groups.iter().flatten().copied().collect();
+        grouped.sort_unstable();
+        assert_eq!(grouped, (0..ivf.centroids.len()).collect::<Vec<_>>());
+
+        // le cluster le plus proche (classement exhaustif) est le plus souvent parmi
+        // les 4 premiers du classement à deux niveaux
+        let found = data
+            .iter()
+            .filter(|(_, v)| {
+                let exact = ivf.centroids.iter()
+                    .enumerate()
+                    .min_by(|a, b| cosine_distance(v, a.1).total_cmp(&cosine_distance(v, b.1)))
+                    .map(|(idx, _)| idx)
+                    .unwrap();
+                ivf.ranked_clusters(v, 4).contains(&exact)
+            })
+            .count();
+        assert!(found >= data.len() * 3 / 4);
+        // assez de clusters demandés : classement exhaustif
+        let all = ivf.ranked_clusters(&data[0].1, usize::MAX);
+        assert_eq!(all.len(), ivf.centroids.len());
+
+        ivf.set_coarse_groups(None);
+        assert!(ivf.coarse.is_none());
+    }
+
     #[test]
     fn test_ivf_search() {
         let data = vec![
@@ -122,4 +392,28 @@ mod tests {
 
         assert!(!candidates.is_empty());
     }
+
+    #[test]
+    fn test_ivf_insert_remove() {
+        let data = vec![
+            ("id1".to_string(), vec![1.0, 0.0, 0.0]),
+            ("id2".to_string(), vec![0.0, 1.0, 0.0]),
+        ];
+
+        let mut ivf = IVFIndex::new(1);
+        ivf.build(&data);
+
+        ivf.insert("id3".to_string(), &[0.9, 0.1, 0.0]);
+        assert!(ivf.search_candidates(&[1.0, 0.0, 0.0]).contains(&"id3".to_string()));
+
+        ivf.remove("id1");
+        let candidates = ivf.search_candidates(&[1.0, 0.0, 0.0]);
+        assert!(!candidates.contains(&"id1".to_string()));
+        assert_eq!(candidates.len(), 2);
+
+        // embedding décalé : absent de la liste du centroïde le plus proche -> parcours complet
+        ivf.remove_near("id2", &[0.0, 1.0, 0.0]);
+        ivf.remove_near("id3", &[0.0, 0.0, 1.0]);
+        assert!(ivf.inverted_lists.iter().all(|l| l.is_empty()));
+    }
 }
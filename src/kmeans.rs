@@ -1,13 +1,33 @@
-use crate::distance::cosine_distance;
+use crate::distance::{cosine_distance, squared_l2};
 use rand::{Rng, seq::SliceRandom};
 use rayon::prelude::*;
 
+// distance des points aux centroïdes : cosinus pour les vecteurs normalisés (IVF),
+// euclidienne au carré pour les sous-vecteurs de la quantification produit
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Metric {
+    #[default]
+    Cosine,
+    SquaredL2,
+}
+
+impl Metric {
+    #[inline]
+    pub fn distance(self, a: &[f32], b: &[f32]) -> f32 {
+        match self {
+            Metric::Cosine => cosine_distance(a, b),
+            Metric::SquaredL2 => squared_l2(a, b),
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct KMeans {
     pub centroids: Vec<Vec<f32>>,
     pub n_clusters: usize,
     pub max_iter: usize,
     pub tolerance: f32,
+    pub metric: Metric,
 }
 
 impl KMeans {
@@ -17,9 +37,15 @@ impl KMeans {
             n_clusters,
             max_iter: 50,
             tolerance: 1e-4,
+            metric: Metric::Cosine,
         }
     }
 
+    pub fn with_metric(mut self, metric: Metric) -> Self {
+        self.metric = metric;
+        self
+    }
+
     pub fn with_max_iter(mut self, max_iter: usize) -> Self {
         self.max_iter = max_iter;
         self
@@ -43,17 +69,11 @@ impl KMeans {
             .unwrap();
         self.centroids.push(data[first_idx].clone());
 
-        // k-means++ pour les autres
+        // k-means++ pour les autres ; distance au plus proche centroïde tenue à jour
+        // avec le seul dernier ajouté (O(n·k) au lieu de O(n·k²))
+        let metric = self.metric;
+        let mut distances: Vec<f32> = data.par_iter().map(|point| metric.distance(point, &self.centroids[0])).collect();
         for _ in 1..self.n_clusters {
-            let distances: Vec<f32> = data.par_iter()
-                .map(|point| {
-                    self.centroids.iter()
-                        .map(|c| cosine_distance(point, c))
-                        .min_by(|a, b| a.partial_cmp(b).unwrap())
-                        .unwrap()
-                })
-                .collect();
-
             let total: f32 = distances.iter().sum();
             let mut r = rng.gen::<f32>() * total;
 
@@ -67,6 +87,10 @@ impl KMeans {
             }
 
             self.centroids.push(data[next_idx].clone());
+            let last = self.centroids.last().unwrap();
+            distances.par_iter_mut().zip(data.par_iter()).for_each(|(d, point)| {
+                *d = d.min(metric.distance(point, last));
+            });
         }
     }
 
@@ -76,7 +100,7 @@ impl KMeans {
             .map(|point| {
                 self.centroids.iter()
                     .enumerate()
-                    .map(|(idx, c)| (idx, cosine_distance(point, c)))
+                    .map(|(idx, c)| (idx, self.metric.distance(point, c)))
                     .min_by(|a, b| a.1.partial_cmp(&b.1).unwrap())
                     .map(|(idx, _)| idx)
                     .unwrap()
@@ -110,7 +134,7 @@ impl KMeans {
         // calculer le changement
         let mut total_shift = 0.0;
         for (old, new) in self.centroids.iter().zip(new_centroids.iter()) {
-            total_shift += cosine_distance(old, new);
+            total_shift += self.metric.distance(old, new);
         }
 
         self.centroids = new_centroids;
@@ -139,7 +163,7 @@ impl KMeans {
     pub fn predict(&self, point: &[f32]) -> usize {
         self.centroids.iter()
             .enumerate()
-            .map(|(idx, c)| (idx, cosine_distance(point, c)))
+            .map(|(idx, c)| (idx, self.metric.distance(point, c)))
             .min_by(|a, b| a.1.partial_cmp(&b.1).unwrap())
             .map(|(idx, _)| idx)
             .unwrap_or(0)
@@ -0,0 +1,173 @@
+// interaction tardive façon ColBERT : chaque document garde la matrice des embeddings
+// de ses tokens, la recherche vectorielle sur leur moyenne fournit les candidats,
+// reclassés par max-sim (pour chaque token de la requête, meilleure similarité parmi
+// les tokens du document, moyennée sur la requête). Les mêmes matrices servent aux
+// entrées multi-vecteurs (un vecteur par fragment d'un document, sous un seul id)
+// interrogées avec un embedding unique : voir VectorAggregation
+use crate::distance::{dot_product, normalize_l2};
+use crate::error::{Result, VectorDbError};
+use serde::{Deserialize, Serialize};
+
+// une ligne par token, normalisée L2 à l'ajout
+pub type TokenMatrix = Vec<Vec<f32>>;
+
+// fichier des matrices de tokens d'une collection late_interaction
+pub const TOKENS_FILE: &str = "tokens.bin";
+// candidats de la recherche vectorielle reclassés par défaut, en multiple de n_results
+pub const DEFAULT_RESCORE_FACTOR: usize = 4;
+
+// matrice vérifiée (au moins un token, dimension de la collection) et normalisée
+pub fn normalize_tokens(mut tokens: TokenMatrix, dimension: usize) -> Result<TokenMatrix> {
+    if tokens.is_empty() {
+        return Err(VectorDbError::InvalidConfig("token embeddings must hold at least one token".to_string()));
+    }
+    for token in tokens.iter_mut() {
+        if token.len() != dimension {
+            return Err(VectorDbError::DimensionMismatch { expected: dimension, actual: token.len() });
+        }
+        normalize_l2(token);
+    }
+    Ok(tokens)
+}
+
+// vecteur du premier étage : moyenne des tokens normalisés
+pub fn pool(tokens: &[Vec<f32>]) -> Vec<f32> {
+    let mut pooled = vec![0.0; tokens.first().map_or(0, Vec::len)];
+    for token in tokens {
+        for (p, x) in pooled.iter_mut().zip(token) {
+            *p += x;
+        }
+    }
+    let n = tokens.len().max(1) as f32;
+    pooled.iter_mut().for_each(|p| *p /= n);
+    pooled
+}
+
+// similarité cosinus moyenne entre chaque token de la requête et son meilleur
+// token du document, dans [-1, 1] ; les deux matrices sont normalisées
+pub fn max_sim(query: &[Vec<f32>], document: &[Vec<f32>]) -> f32 {
+    if query.is_empty() || document.is_empty() {
+        return 0.0;
+    }
+    let total: f32 = query
+        .iter()
+        .map(|q| document.iter().map(|d| dot_product(q, d)).fold(f32::NEG_INFINITY, f32::max))
+        .sum();
+    total / query.len() as f32
+}
+
+// similarité d'une entrée multi-vecteurs à un embedding de requête
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum VectorAggregation {
+    // meilleur vecteur de l'entrée
+    #[default]
+    MaxSim,
+    // moyenne sur les vecteurs de l'entrée
+    Mean,
+}
+
+impl VectorAggregation {
+    // requête et vecteurs normalisés, résultat dans [-1, 1]
+    pub fn score(self, query: &[f32], vectors: &[Vec<f32>]) -> f32 {
+        if vectors.is_empty() {
+            return 0.0;
+        }
+        let similarities = vectors.iter().map(|v| dot_product(query, v));
+        match self {
+            VectorAggregation::MaxSim => similarities.fold(f32::NEG_INFINITY, f32::max),
+            VectorAggregation::Mean => similarities.sum::<f32>() / vectors.len() as f32,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::TempDir;
+    use crate::collection::{AddOptions, Collection, CollectionConfig, QueryOptions};
+    use crate::storage::Storage;
+
+    #[test]
+    fn test_max_sim_rescoring() {
+        let config = CollectionConfig::new("docs".to_string(), 2).with_late_interaction();
+        let mut coll = Collection::from_config(config);
+        // "a" : un seul token proche de la moyenne de la requête ; "b" : un token
+        // exactement aligné sur chaque token de la requête
+        let tokens = vec![
+            vec![vec![1.0, 1.0]],
+            vec![vec![1.0, 0.0], vec![0.0, 1.0]],
+            vec![vec![-1.0, 0.2]],
+        ];
+        let options = AddOptions::new("test").with_token_embeddings(tokens);
+        coll.add_with_options(vec!["a".into(), "b".into(), "c".into()], Vec::new(), None, options)
+            .unwrap();
+
+        let query = vec![vec![1.0, 0.0], vec![0.0, 1.0]];
+        assert!((max_sim(&query, &query) - 1.0).abs() < 1e-6);
+        let results = coll.query_tokens(&query, 2, 3, None, &QueryOptions::default()).unwrap();
+        assert_eq!(results[0].id, "b");
+        assert!(results[0].distance.abs() < 1e-6);
+        assert_eq!(results[1].id, "a");
+
+        // un ajout sans tokens est refusé, comme des tokens hors dimension
+        assert!(coll.add(vec!["d".into()], vec![vec![1.0, 0.0]], None).is_err());
+        let options = AddOptions::new("test").with_token_embeddings(vec![vec![vec![1.0, 0.0, 0.0]]]);
+        assert!(coll.add_with_options(vec!["d".into()], Vec::new(), None, options).is_err());
+
+        coll.delete(vec!["b".into()]).unwrap();
+        let results = coll.query_tokens(&query, 1, 3, None, &QueryOptions::default()).unwrap();
+        assert_eq!(results[0].id, "a");
+
+        // matrices relues de tokens.bin
+        let dir = TempDir::new("late-interaction");
+        let storage = Storage::new(&dir).unwrap();
+        storage.save_collection(&coll).unwrap();
+        let mut loaded = storage.load_collection("docs").unwrap();
+        assert!(loaded.config.late_interaction);
+        let reloaded = loaded.query_tokens(&query, 2, 3, None, &QueryOptions::default()).unwrap();
+        let distances = |r: &[crate::collection::SearchResult]| r.iter().map(|r| r.distance).collect::<Vec<_>>();
+        assert_eq!(distances(&reloaded), distances(&coll.query_tokens(&query, 2, 3, None, &QueryOptions::default()).unwrap()));
+    }
+
+    #[test]
+    fn test_multi_vector_aggregation() {
+        let config = CollectionConfig::new("chunks".to_string(), 2).with_late_interaction();
+        let mut coll = Collection::from_config(config);
+        // "a" : un fragment exactement aligné sur la requête, l'autre opposé ; "b" :
+        // deux fragments proches ; "c" : la moyenne de ses fragments la plus proche
+        let chunks = vec![
+            vec![vec![1.0, 0.0], vec![-1.0, 0.0]],
+            vec![vec![0.9, 0.3], vec![0.9, -0.3]],
+            vec![vec![1.0, 0.05], vec![1.0, -0.05], vec![0.95, 0.1]],
+        ];
+        let options = AddOptions::new("test").with_token_embeddings(chunks);
+        coll.add_with_options(vec!["a".into(), "b".into(), "c".into()], Vec::new(), None, options)
+            .unwrap();
+
+        let ids = |hits: Vec<crate::query::SearchHit>| hits.into_iter().map(|h| h.id).collect::<Vec<_>>();
+        let hits = coll.search(&[1.0, 0.0]).k(3).aggregate(VectorAggregation::MaxSim).run().unwrap();
+        assert_eq!(ids(hits), ["a", "c", "b"]);
+        let hits = coll.search(&[1.0, 0.0]).k(3).aggregate(VectorAggregation::Mean).run().unwrap();
+        assert!(hits[2].distance > 0.99, "{:?}", hits[2]);
+        assert_eq!(ids(hits), ["c", "b", "a"]);
+        // recherche exhaustive comptée et chronométrée comme les autres requêtes
+        assert_eq!(coll.stats().total_queries, 2);
+        assert_eq!(coll.take_latencies().count(), 2);
+
+        // même classement par l'index IVF (candidats reclassés), un résultat par id
+        coll.config.use_ivf = true;
+        coll.config.n_clusters = 1;
+        coll.rebuild_index();
+        let results = coll
+            .query_multi_vector(&[1.0, 0.0], 3, 3, VectorAggregation::MaxSim, None, &QueryOptions::default())
+            .unwrap();
+        assert_eq!(results.iter().map(|r| r.id.as_str()).collect::<Vec<_>>(), ["a", "c", "b"]);
+        // premier étage par l'index et reclassement : une seule requête
+        assert_eq!(coll.stats().total_queries, 3);
+        assert_eq!(coll.take_latencies().count(), 1);
+
+        let mut plain = Collection::new("plain".to_string(), 2);
+        assert!(plain.search(&[1.0, 0.0]).aggregate(VectorAggregation::Mean).run().is_err());
+    }
+}
@@ -1,16 +1,74 @@
+// le scaffolding UniFFI généré déclenche ce lint
+#![cfg_attr(feature = "mobile", allow(clippy::empty_line_after_doc_comments))]
+
+pub mod api_version;
 pub mod collection;
 pub mod vector;
 pub mod distance;
+pub mod drift;
 pub mod storage;
+pub mod snapshot;
 pub mod error;
 pub mod client;
 pub mod kmeans;
 pub mod ivf;
+pub mod pq;
+pub mod sq;
+pub mod half;
+pub mod readview;
+pub mod late_interaction;
+pub mod columns;
+pub mod cursor;
+pub mod dictionary;
+pub mod diff;
+pub mod disk_ivf;
 pub mod filter;
+pub mod hooks;
+pub mod query;
+pub mod rerank;
+pub mod segment;
+pub mod bloom;
+pub mod querylog;
+pub mod tombstones;
+pub mod topk;
+pub mod embedding_model;
+pub mod metadata;
+pub mod cache;
+pub mod metrics;
+pub mod limits;
+pub mod loadtest;
+pub mod autotune;
+pub mod profiles;
+pub mod faiss;
+pub mod npy;
+pub mod numbers;
+pub mod ingest;
+pub mod batcher;
+pub mod priority;
+#[cfg(any(test, feature = "testing"))]
+pub mod testing;
+#[cfg(feature = "chroma-import")]
+pub mod chroma;
+#[cfg(feature = "node")]
+pub mod node;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+#[cfg(feature = "mobile")]
+pub mod mobile;
 
-pub use collection::Collection;
-pub use client::VectorDbClient;
+pub use collection::{Collection, CollectionConfig};
+pub use client::{CollectionHandle, VectorDbClient};
 pub use error::{VectorDbError, Result};
+pub use filter::FilterBuilder;
+pub use hooks::Hooks;
+pub use query::{QueryBuilder, SearchHit};
+pub use rerank::Reranker;
+pub use embedding_model::{EmbeddingProvider, HashEmbedding, ModelRegistry};
+
+#[cfg(feature = "mobile")]
+use mobile::{CollectionInfo, Condition, DbError, Entry, QueryHit, Value, VectorDb};
+#[cfg(feature = "mobile")]
+uniffi::include_scaffolding!("vectordb");
 
 // exposer pour les benchmarks
 pub use distance::dot_product;
@@ -0,0 +1,345 @@
+use crate::collection::CollectionConfig;
+use crate::readview::ConcurrencyMode;
+use crate::error::{Result, VectorDbError};
+use serde::{Deserialize, Serialize};
+
+// une liste IVF doit contenir en moyenne au moins ce nombre de vecteurs
+pub const MIN_VECTORS_PER_CLUSTER: usize = 10;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Limits {
+    pub max_dimension: usize,
+    pub max_n_clusters: usize,
+    // limites dures, 0 = illimité
+    #[serde(default)]
+    pub max_collections: usize,
+    #[serde(default)]
+    pub max_vectors_per_collection: usize,
+    // plafonds par requête, 0 = illimité : vecteurs renvoyés par un get, vecteurs
+    // touchés par un update / delete (par ids ou par filtre)
+    #[serde(default)]
+    pub max_get_results: usize,
+    #[serde(default)]
+    pub max_batch_size: usize,
+    // requêtes d'un même appel /query (query_embeddings), 0 = illimité
+    #[serde(default)]
+    pub max_query_batch: usize,
+    // résultats d'une recherche par rayon : tout le rayon peut couvrir la collection
+    #[serde(default = "default_max_range_results")]
+    pub max_range_results: usize,
+    // fraction d'une limite dure au-delà de laquelle un avertissement est émis
+    #[serde(default = "default_soft_limit_ratio")]
+    pub soft_limit_ratio: f64,
+}
+
+fn default_soft_limit_ratio() -> f64 {
+    0.8
+}
+
+fn default_max_range_results() -> usize {
+    10_000
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Self {
+            max_dimension: 65_536,
+            max_n_clusters: 65_536,
+            max_collections: 0,
+            max_vectors_per_collection: 0,
+            max_get_results: 0,
+            max_batch_size: 0,
+            max_query_batch: 0,
+            max_range_results: default_max_range_results(),
+            soft_limit_ratio: default_soft_limit_ratio(),
+        }
+    }
+}
+
+// utilisation d'une limite dure, exposée dans /health et /stats
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct LimitUsage {
+    pub used: usize,
+    pub max: usize,
+    pub soft_limit: usize,
+    pub soft_exceeded: bool,
+}
+
+impl Limits {
+    pub fn from_env() -> Self {
+        let default = Self::default();
+        let env_usize = |key: &str, fallback: usize| {
+            std::env::var(key)
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(fallback)
+        };
+
+        Self {
+            max_dimension: env_usize("VECTORDB_MAX_DIMENSION", default.max_dimension),
+            max_n_clusters: env_usize("VECTORDB_MAX_CLUSTERS", default.max_n_clusters),
+            max_collections: env_usize("VECTORDB_MAX_COLLECTIONS", default.max_collections),
+            max_vectors_per_collection: env_usize(
+                "VECTORDB_MAX_VECTORS_PER_COLLECTION",
+                default.max_vectors_per_collection,
+            ),
+            max_get_results: env_usize("VECTORDB_MAX_GET_RESULTS", default.max_get_results),
+            max_batch_size: env_usize("VECTORDB_MAX_BATCH_SIZE", default.max_batch_size),
+            max_query_batch: env_usize("VECTORDB_MAX_QUERY_BATCH", default.max_query_batch),
+            max_range_results: env_usize("VECTORDB_MAX_RANGE_RESULTS", default.max_range_results),
+            soft_limit_ratio: std::env::var("VECTORDB_SOFT_LIMIT_RATIO")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .filter(|r: &f64| *r > 0.0 && *r <= 1.0)
+                .unwrap_or(default.soft_limit_ratio),
+        }
+    }
+
+    // 0 si la limite est désactivée : pas d'avertissement sans limite dure
+    pub fn soft_limit(&self, max: usize) -> usize {
+        if max == 0 {
+            return 0;
+        }
+        ((max as f64 * self.soft_limit_ratio).floor() as usize).max(1)
+    }
+
+    // None si la limite est désactivée
+    pub fn usage(&self, used: usize, max: usize) -> Option<LimitUsage> {
+        (max > 0).then(|| {
+            let soft_limit = self.soft_limit(max);
+            LimitUsage { used, max, soft_limit, soft_exceeded: used >= soft_limit }
+        })
+    }
+
+    // `existing` collections déjà présentes, avant création d'une nouvelle
+    pub fn check_collection_count(&self, existing: usize) -> Result<()> {
+        if self.max_collections > 0 && existing >= self.max_collections {
+            return Err(VectorDbError::LimitExceeded(format!(
+                "cannot create more than {} collections",
+                self.max_collections
+            )));
+        }
+        Ok(())
+    }
+
+    // `requested` vecteurs demandés par un get (nombre d'ids, ou taille de la collection)
+    pub fn check_get_results(&self, requested: usize) -> Result<()> {
+        if self.max_get_results > 0 && requested > self.max_get_results {
+            return Err(VectorDbError::LimitExceeded(format!(
+                "get would return up to {} vectors, more than the limit of {}; request fewer ids per call",
+                requested, self.max_get_results
+            )));
+        }
+        Ok(())
+    }
+
+    // `affected` vecteurs visés par un update / delete ; `operation` pour le message
+    pub fn check_batch_size(&self, operation: &str, affected: usize) -> Result<()> {
+        if self.max_batch_size > 0 && affected > self.max_batch_size {
+            return Err(VectorDbError::LimitExceeded(format!(
+                "{} would touch {} vectors, more than the limit of {}; split it into smaller batches",
+                operation, affected, self.max_batch_size
+            )));
+        }
+        Ok(())
+    }
+
+    // `queries` requêtes envoyées en un seul appel
+    pub fn check_query_batch(&self, queries: usize) -> Result<()> {
+        if self.max_query_batch > 0 && queries > self.max_query_batch {
+            return Err(VectorDbError::LimitExceeded(format!(
+                "query batch has {} queries, more than the limit of {}; split it into smaller batches",
+                queries, self.max_query_batch
+            )));
+        }
+        Ok(())
+    }
+
+    // `requested` résultats demandés à une recherche par rayon
+    pub fn check_range_results(&self, requested: usize) -> Result<()> {
+        if self.max_range_results > 0 && requested > self.max_range_results {
+            return Err(VectorDbError::LimitExceeded(format!(
+                "range query would return up to {} vectors, more than the limit of {}",
+                requested, self.max_range_results
+            )));
+        }
+        Ok(())
+    }
+
+    pub fn validate_config(&self, config: &CollectionConfig) -> Result<()> {
+        if config.dimension == 0 || config.dimension > self.max_dimension {
+            return Err(VectorDbError::InvalidConfig(format!(
+                "dimension must be between 1 and {}, got {}",
+                self.max_dimension, config.dimension
+            )));
+        }
+
+        if let Some(subspaces) = config.pq_subspaces {
+            if !config.use_ivf {
+                return Err(VectorDbError::InvalidConfig("pq_subspaces requires an IVF index".to_string()));
+            }
+            if subspaces == 0 || subspaces > config.dimension {
+                return Err(VectorDbError::InvalidConfig(format!(
+                    "pq_subspaces must be between 1 and the dimension ({}), got {}",
+                    config.dimension, subspaces
+                )));
+            }
+        }
+
+        if config.scalar_quantization.is_some_and(|sq| sq.rescore == Some(0)) {
+            return Err(VectorDbError::InvalidConfig("scalar_quantization.rescore must be > 0".to_string()));
+        }
+
+        // collections binaires : recherche exhaustive en Hamming sur des bits fournis
+        if config.is_binary() {
+            let conflicting = [
+                (config.use_ivf, "use_ivf"),
+                (config.pq_subspaces.is_some(), "pq_subspaces"),
+                (config.scalar_quantization.is_some(), "scalar_quantization"),
+                (config.late_interaction, "late_interaction"),
+                (config.embedding_model.is_some(), "embedding_model"),
+                (config.store_raw, "store_raw"),
+            ];
+            if let Some((_, option)) = conflicting.iter().find(|(set, _)| *set) {
+                return Err(VectorDbError::InvalidConfig(format!(
+                    "binary collections do not support {}",
+                    option
+                )));
+            }
+        }
+
+        // demi-précision : les embeddings ne sont gardés qu'en f16 / bf16
+        if config.precision.is_half() {
+            let conflicting = [
+                (config.is_binary(), "binary vectors"),
+                (config.scalar_quantization.is_some(), "scalar_quantization"),
+            ];
+            if let Some((_, option)) = conflicting.iter().find(|(set, _)| *set) {
+                return Err(VectorDbError::InvalidConfig(format!(
+                    "{} storage does not support {}",
+                    config.precision.name(),
+                    option
+                )));
+            }
+        }
+
+        // instantanés : recherche cosinus exhaustive sur les embeddings normalisés
+        if config.concurrency == ConcurrencyMode::Snapshot {
+            let conflicting = [
+                (config.is_binary(), "binary vectors"),
+                (config.late_interaction, "late_interaction"),
+                (config.use_ivf, "use_ivf"),
+            ];
+            if let Some((_, option)) = conflicting.iter().find(|(set, _)| *set) {
+                return Err(VectorDbError::InvalidConfig(format!(
+                    "snapshot concurrency does not support {}",
+                    option
+                )));
+            }
+        }
+
+        if !config.use_ivf {
+            return Ok(());
+        }
+
+        if config.n_clusters == 0 || config.n_clusters > self.max_n_clusters {
+            return Err(VectorDbError::InvalidConfig(format!(
+                "n_clusters must be between 1 and {}, got {}",
+                self.max_n_clusters, config.n_clusters
+            )));
+        }
+
+        if let Some(expected) = config.expected_size {
+            let max_clusters = (expected / MIN_VECTORS_PER_CLUSTER).max(1);
+            if config.n_clusters > max_clusters {
+                return Err(VectorDbError::InvalidConfig(format!(
+                    "n_clusters {} is too large for an expected size of {} vectors (max {})",
+                    config.n_clusters, expected, max_clusters
+                )));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::collection::Collection;
+
+    #[test]
+    fn test_dimension_bounds() {
+        let limits = Limits::default();
+        assert!(limits.validate_config(&CollectionConfig::new("a".into(), 0)).is_err());
+        assert!(limits.validate_config(&CollectionConfig::new("a".into(), 10_000_000)).is_err());
+        assert!(limits.validate_config(&CollectionConfig::new("a".into(), 768)).is_ok());
+    }
+
+    #[test]
+    fn test_hard_and_soft_limits() {
+        let limits = Limits {
+            max_collections: 2,
+            max_vectors_per_collection: 10,
+            ..Limits::default()
+        };
+        assert!(limits.check_collection_count(1).is_ok());
+        assert!(matches!(limits.check_collection_count(2), Err(VectorDbError::LimitExceeded(_))));
+
+        assert_eq!(limits.usage(5, 0), None);
+        assert_eq!(limits.soft_limit(0), 0);
+        let usage = limits.usage(8, 10).unwrap();
+        assert_eq!(usage.soft_limit, 8);
+        assert!(usage.soft_exceeded);
+
+        let mut coll = Collection::new("a".into(), 2);
+        coll.set_vector_limit(limits.max_vectors_per_collection, limits.soft_limit(10));
+        let ids = |r: std::ops::Range<usize>| r.map(|i| i.to_string()).collect::<Vec<_>>();
+        coll.add(ids(0..8), vec![vec![1.0, 0.0]; 8], None).unwrap();
+        // écrasements : pas de nouvel id, accepté même plein
+        coll.add(ids(6..10), vec![vec![1.0, 0.0]; 4], None).unwrap();
+        let err = coll.add(ids(8..11), vec![vec![1.0, 0.0]; 3], None).unwrap_err();
+        assert!(matches!(err, VectorDbError::LimitExceeded(_)));
+        assert_eq!(coll.count(), 10);
+        assert!(coll.stats().vector_limit.unwrap().soft_exceeded);
+    }
+
+    #[test]
+    fn test_request_caps() {
+        assert!(Limits::default().check_get_results(usize::MAX).is_ok());
+        let limits = Limits { max_get_results: 100, max_batch_size: 10, ..Limits::default() };
+        assert!(limits.check_get_results(100).is_ok());
+        assert!(matches!(limits.check_get_results(101), Err(VectorDbError::LimitExceeded(_))));
+        assert!(limits.check_batch_size("delete", 10).is_ok());
+        let err = limits.check_batch_size("delete", 11).unwrap_err();
+        assert!(err.to_string().contains("delete would touch 11 vectors"));
+        let limits = Limits { max_query_batch: 2, ..limits };
+        assert!(limits.check_query_batch(2).is_ok());
+        assert!(matches!(limits.check_query_batch(3), Err(VectorDbError::LimitExceeded(_))));
+        assert!(limits.check_range_results(10_000).is_ok());
+        assert!(matches!(limits.check_range_results(10_001), Err(VectorDbError::LimitExceeded(_))));
+    }
+
+    #[test]
+    fn test_n_clusters_sanity() {
+        let limits = Limits::default();
+        let config = CollectionConfig::new("a".into(), 128).with_ivf(0);
+        assert!(limits.validate_config(&config).is_err());
+
+        let mut config = CollectionConfig::new("a".into(), 128).with_ivf(100);
+        config.expected_size = Some(500);
+        assert!(limits.validate_config(&config).is_err());
+
+        config.expected_size = Some(100_000);
+        assert!(limits.validate_config(&config).is_ok());
+
+        // IVF-PQ : au plus un sous-espace par composante, index IVF requis
+        assert!(limits.validate_config(&config.clone().with_pq(32)).is_ok());
+        assert!(limits.validate_config(&config.clone().with_pq(129)).is_err());
+        assert!(limits.validate_config(&CollectionConfig::new("a".into(), 128).with_pq(32)).is_err());
+
+        // instantanés : recherche exhaustive uniquement
+        let err = limits.validate_config(&config.with_concurrency(ConcurrencyMode::Snapshot)).unwrap_err();
+        assert!(err.to_string().contains("use_ivf"), "{}", err);
+    }
+}
@@ -0,0 +1,296 @@
+// générateur de charge intégré (POST /admin/loadtest) : requêtes et écritures
+// synthétiques sur une collection, à débit fixé, pour dimensionner un serveur sans
+// écrire de harnais externe
+use crate::client::VectorDbClient;
+use crate::error::{Result, VectorDbError};
+use crate::metrics::LatencyWindow;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+// au-delà, un test bloquerait trop longtemps un thread du serveur
+pub const MAX_LOADTEST_SECS: f64 = 600.0;
+// threads lancés par le test, et vecteurs écrits par lot : bornés pour qu'une
+// requête ne puisse pas épuiser la mémoire ou les threads du serveur
+pub const MAX_LOADTEST_CONCURRENCY: usize = 64;
+pub const MAX_LOADTEST_BATCH: usize = 10_000;
+
+// préfixe des ids écrits, supprimés en fin de test si cleanup
+pub const LOADTEST_ID_PREFIX: &str = "__loadtest";
+
+fn default_duration() -> f64 {
+    10.0
+}
+
+fn default_query_rate() -> f64 {
+    100.0
+}
+
+fn default_concurrency() -> usize {
+    4
+}
+
+fn default_batch_size() -> usize {
+    1
+}
+
+fn default_n_results() -> usize {
+    10
+}
+
+fn default_cleanup() -> bool {
+    true
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LoadTestConfig {
+    pub collection: String,
+    #[serde(default = "default_duration")]
+    pub duration_secs: f64,
+    // requêtes par seconde, tous workers confondus ; 0 = pas de requête
+    #[serde(default = "default_query_rate")]
+    pub query_rate: f64,
+    // lots d'écriture par seconde ; 0 = lecture seule
+    #[serde(default)]
+    pub write_rate: f64,
+    #[serde(default = "default_batch_size")]
+    pub batch_size: usize,
+    #[serde(default = "default_n_results")]
+    pub n_results: usize,
+    #[serde(default = "default_concurrency")]
+    pub concurrency: usize,
+    // supprime les vecteurs écrits à la fin du test
+    #[serde(default = "default_cleanup")]
+    pub cleanup: bool,
+}
+
+impl LoadTestConfig {
+    pub fn new(collection: String) -> Self {
+        Self {
+            collection,
+            duration_secs: default_duration(),
+            query_rate: default_query_rate(),
+            write_rate: 0.0,
+            batch_size: default_batch_size(),
+            n_results: default_n_results(),
+            concurrency: default_concurrency(),
+            cleanup: default_cleanup(),
+        }
+    }
+
+    fn validate(&self) -> Result<()> {
+        let invalid = |msg: &str| Err(VectorDbError::InvalidConfig(format!("load test: {}", msg)));
+        if !(self.duration_secs > 0.0 && self.duration_secs <= MAX_LOADTEST_SECS) {
+            return invalid(&format!("duration_secs must be in (0, {}]", MAX_LOADTEST_SECS));
+        }
+        if !(self.query_rate >= 0.0 && self.write_rate >= 0.0) || self.query_rate + self.write_rate == 0.0 {
+            return invalid("query_rate and write_rate must be >= 0, and not both 0");
+        }
+        if self.concurrency == 0 || self.batch_size == 0 || self.n_results == 0 {
+            return invalid("concurrency, batch_size and n_results must be > 0");
+        }
+        if self.concurrency > MAX_LOADTEST_CONCURRENCY {
+            return invalid(&format!("concurrency must be at most {}", MAX_LOADTEST_CONCURRENCY));
+        }
+        if self.batch_size > MAX_LOADTEST_BATCH {
+            return invalid(&format!("batch_size must be at most {}", MAX_LOADTEST_BATCH));
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct LatencySummary {
+    pub count: u64,
+    pub p50_ms: f64,
+    pub p95_ms: f64,
+    pub p99_ms: f64,
+    pub max_ms: f64,
+}
+
+impl LatencySummary {
+    fn from_window(window: &LatencyWindow) -> Self {
+        Self {
+            count: window.count(),
+            p50_ms: window.percentile_ms(50.0),
+            p95_ms: window.percentile_ms(95.0),
+            p99_ms: window.percentile_ms(99.0),
+            max_ms: window.percentile_ms(100.0),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct LoadTestReport {
+    pub config: LoadTestConfig,
+    pub elapsed_secs: f64,
+    pub queries: LatencySummary,
+    pub writes: LatencySummary,
+    // opérations par seconde effectivement tenues
+    pub query_throughput: f64,
+    pub write_throughput: f64,
+    pub vectors_written: usize,
+    pub errors: u64,
+    // opérations parties en retard sur le débit demandé (serveur saturé)
+    pub behind_schedule: u64,
+}
+
+// `rate` opérations par seconde réparties sur `workers` ; None si rate = 0
+fn interval(rate: f64, workers: usize) -> Option<Duration> {
+    (rate > 0.0).then(|| Duration::from_secs_f64(workers as f64 / rate))
+}
+
+fn random_vector(rng: &mut impl Rng, dimension: usize) -> Vec<f32> {
+    (0..dimension).map(|_| rng.gen_range(-1.0..1.0)).collect()
+}
+
+pub fn run_load_test(client: &VectorDbClient, config: LoadTestConfig) -> Result<LoadTestReport> {
+    config.validate()?;
+    if config.write_rate > 0.0 && client.is_read_only() {
+        return Err(VectorDbError::ReadOnly(config.collection.clone()));
+    }
+    let dimension = client.collection_info(&config.collection)?.config.dimension;
+
+    let query_interval = interval(config.query_rate, config.concurrency);
+    let write_interval = interval(config.write_rate, config.concurrency);
+    let deadline = Duration::from_secs_f64(config.duration_secs);
+
+    let query_latencies = Mutex::new(LatencyWindow::default());
+    let write_latencies = Mutex::new(LatencyWindow::default());
+    let errors = AtomicU64::new(0);
+    let behind = AtomicU64::new(0);
+    let next_id = AtomicUsize::new(0);
+
+    let start = Instant::now();
+    std::thread::scope(|scope| {
+        for _ in 0..config.concurrency {
+            scope.spawn(|| {
+                let mut rng = rand::thread_rng();
+                // échéances décalées pour ne pas synchroniser les workers
+                let mut next_query = query_interval.map(|every| every.mul_f64(rng.gen_range(0.0..1.0)));
+                let mut next_write = write_interval.map(|every| every.mul_f64(rng.gen_range(0.0..1.0)));
+
+                loop {
+                    // prochaine opération due : la plus proche des deux échéances
+                    let (due, is_query) = match (next_query, next_write) {
+                        (Some(q), Some(w)) if w < q => (w, false),
+                        (Some(q), _) => (q, true),
+                        (None, Some(w)) => (w, false),
+                        (None, None) => break,
+                    };
+                    if due >= deadline {
+                        break;
+                    }
+                    let now = start.elapsed();
+                    if now < due {
+                        std::thread::sleep(due - now);
+                    } else if now - due > Duration::from_millis(10) {
+                        behind.fetch_add(1, Ordering::Relaxed);
+                    }
+
+                    let op_start = Instant::now();
+                    let outcome = if is_query {
+                        let query = random_vector(&mut rng, dimension);
+                        client
+                            .with_collection_mut(&config.collection, |coll| coll.query(&query, config.n_results, None))
+                            .map(|_| ())
+                    } else {
+                        let ids = (0..config.batch_size)
+                            .map(|_| format!("{}-{}", LOADTEST_ID_PREFIX, next_id.fetch_add(1, Ordering::Relaxed)))
+                            .collect();
+                        let embeddings = (0..config.batch_size).map(|_| random_vector(&mut rng, dimension)).collect();
+                        client.with_collection_mut(&config.collection, |coll| {
+                            coll.add_with_source(ids, embeddings, None, "loadtest")
+                        })
+                    };
+                    let elapsed = op_start.elapsed();
+
+                    match outcome {
+                        Ok(()) if is_query => query_latencies.lock().unwrap().record(elapsed),
+                        Ok(()) => write_latencies.lock().unwrap().record(elapsed),
+                        Err(e) => {
+                            errors.fetch_add(1, Ordering::Relaxed);
+                            tracing::debug!(collection = %config.collection, error = %e, "Load test operation failed");
+                        }
+                    }
+
+                    // débit fixe : l'échéance suivante ne dépend pas de la durée de l'opération
+                    if is_query {
+                        next_query = next_query.zip(query_interval).map(|(at, every)| at + every);
+                    } else {
+                        next_write = next_write.zip(write_interval).map(|(at, every)| at + every);
+                    }
+                }
+            });
+        }
+    });
+    let elapsed_secs = start.elapsed().as_secs_f64();
+
+    // ids attribués, écrits ou non
+    let assigned = next_id.load(Ordering::Relaxed);
+    if config.cleanup && assigned > 0 {
+        let ids = (0..assigned).map(|i| format!("{}-{}", LOADTEST_ID_PREFIX, i)).collect();
+        client.with_collection_mut(&config.collection, |coll| coll.delete(ids))?;
+    }
+
+    let queries = LatencySummary::from_window(&query_latencies.into_inner().unwrap());
+    let writes = LatencySummary::from_window(&write_latencies.into_inner().unwrap());
+    tracing::info!(
+        collection = %config.collection,
+        queries = queries.count,
+        writes = writes.count,
+        p95_query_ms = queries.p95_ms,
+        "Load test finished"
+    );
+    Ok(LoadTestReport {
+        query_throughput: queries.count as f64 / elapsed_secs,
+        write_throughput: writes.count as f64 / elapsed_secs,
+        vectors_written: writes.count as usize * config.batch_size,
+        errors: errors.load(Ordering::Relaxed),
+        behind_schedule: behind.load(Ordering::Relaxed),
+        queries,
+        writes,
+        elapsed_secs,
+        config,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::TempDir;
+
+    #[test]
+    fn test_load_test_runs_and_cleans_up() {
+        let dir = TempDir::new("loadtest");
+        let client = VectorDbClient::new(&dir).unwrap();
+        client.create_collection("docs".into(), 8).unwrap();
+
+        let mut config = LoadTestConfig::new("docs".into());
+        config.duration_secs = 0.3;
+        config.query_rate = 200.0;
+        config.write_rate = 50.0;
+        config.batch_size = 2;
+        config.concurrency = 2;
+        let report = run_load_test(&client, config).unwrap();
+
+        assert_eq!(report.errors, 0);
+        assert!(report.queries.count > 0 && report.writes.count > 0);
+        assert_eq!(report.vectors_written, 2 * report.writes.count as usize);
+        // débit borné par la configuration (tolérance pour les échéances décalées)
+        assert!(report.queries.count <= 70);
+        assert_eq!(client.with_collection("docs", |c| c.count()).unwrap(), 0);
+
+        let mut invalid = LoadTestConfig::new("docs".into());
+        invalid.query_rate = 0.0;
+        assert!(matches!(run_load_test(&client, invalid), Err(VectorDbError::InvalidConfig(_))));
+        let mut invalid = LoadTestConfig::new("docs".into());
+        invalid.concurrency = MAX_LOADTEST_CONCURRENCY + 1;
+        assert!(matches!(run_load_test(&client, invalid), Err(VectorDbError::InvalidConfig(_))));
+        let mut invalid = LoadTestConfig::new("docs".into());
+        invalid.batch_size = MAX_LOADTEST_BATCH + 1;
+        assert!(matches!(run_load_test(&client, invalid), Err(VectorDbError::InvalidConfig(_))));
+    }
+}
@@ -1,15 +1,48 @@
 use axum::{
-    extract::{Path, State},
-    http::StatusCode,
+    body::Body,
+    async_trait,
+    body::Bytes,
+    extract::{FromRequest, Path, Query, Request, State},
+    http::{header, HeaderMap, HeaderValue, StatusCode},
+    middleware::{self, Next},
     response::{IntoResponse, Json, Response},
-    routing::{delete, get, post, put},
+    routing::{delete, get, patch, post, put},
     Router,
 };
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
-use std::sync::Arc;
+use std::sync::{Arc, OnceLock};
 use tower_http::cors::CorsLayer;
-use vectordb_rust::{VectorDbClient, VectorDbError};
+use vectordb_rust::api_version::{self, API_VERSION_HEADER};
+use vectordb_rust::autotune::{run_autotune, AutotuneConfig, AutotuneReport};
+use vectordb_rust::cache::{EmbeddingCache, DEFAULT_EMBEDDING_CACHE_ENTRIES, DEFAULT_EMBEDDING_CACHE_TTL};
+use vectordb_rust::client::{MultiGetRequest, ScrollRequest, ScrubReport, DEFAULT_STATS_HISTORY_LEN};
+use vectordb_rust::collection::{
+    AddOptions, AddOutcome, AssignReport, CountMode, DiffResult, GetResult, IndexParamsUpdate, IndexReport, IndexStatus,
+    NormReport, OnConflict, QueryOptions, RebuildPolicyUpdate, SortDirection, SortKey, DEFAULT_NORM_TOLERANCE,
+};
+use vectordb_rust::cursor::CursorKey;
+use vectordb_rust::diff::{DiffOptions, DiffSource, DEFAULT_DIFF_TOLERANCE};
+use vectordb_rust::distance::simd_kernel;
+use vectordb_rust::filter::{parse_filter, parse_where_str, where_str_to_json, WhereFilter};
+use vectordb_rust::half::Precision;
+use vectordb_rust::late_interaction::{pool, TokenMatrix, VectorAggregation, DEFAULT_RESCORE_FACTOR};
+use vectordb_rust::loadtest::{run_load_test, LoadTestConfig, LoadTestReport};
+use vectordb_rust::metadata::MetadataLimits;
+use vectordb_rust::numbers::{check_embedding_numbers, NumberHandling};
+use vectordb_rust::priority::{Priority, PriorityLanes, DEFAULT_BATCH_CONCURRENCY, DEFAULT_BATCH_THREADS};
+use vectordb_rust::profiles::ProfileRegistry;
+use vectordb_rust::querylog::{QueryLog, QueryLogMode};
+use vectordb_rust::readview::ConcurrencyMode;
+use vectordb_rust::rerank::{self, HttpReranker, DEFAULT_CANDIDATES_FACTOR, DEFAULT_RERANKER};
+use vectordb_rust::segment::SegmentInfo;
+use vectordb_rust::snapshot::{SnapshotShipper, Standby};
+use vectordb_rust::sq::ScalarQuantization;
+use vectordb_rust::storage::ScanReport;
+use vectordb_rust::tombstones::TombstonePolicy;
+use vectordb_rust::embedding_model::HASH_EMBEDDING_MODEL;
+use vectordb_rust::vector::{BinaryVector, MetadataValue, VectorType};
+use vectordb_rust::{Collection, CollectionConfig, EmbeddingProvider, HashEmbedding, VectorDbClient, VectorDbError};
 
 type SharedClient = Arc<VectorDbClient>;
 
@@ -28,10 +61,28 @@ impl IntoResponse for AppError {
                 (StatusCode::CONFLICT, self.0.to_string())
             }
             VectorDbError::VectorNotFound(_) => (StatusCode::NOT_FOUND, self.0.to_string()),
+            VectorDbError::VectorAlreadyExists(_) => (StatusCode::CONFLICT, self.0.to_string()),
             VectorDbError::DimensionMismatch { .. } => {
                 (StatusCode::BAD_REQUEST, self.0.to_string())
             }
             VectorDbError::InvalidConfig(_) => (StatusCode::BAD_REQUEST, self.0.to_string()),
+            VectorDbError::UnsupportedApiVersion(_) => (StatusCode::BAD_REQUEST, self.0.to_string()),
+            VectorDbError::InvalidFilter(_) => (StatusCode::BAD_REQUEST, self.0.to_string()),
+            VectorDbError::EmbeddingModelMismatch { .. } => {
+                (StatusCode::BAD_REQUEST, self.0.to_string())
+            }
+            VectorDbError::ReservedMetadataKey(_) => (StatusCode::BAD_REQUEST, self.0.to_string()),
+            VectorDbError::LimitExceeded(_) => (StatusCode::FORBIDDEN, self.0.to_string()),
+            VectorDbError::MetadataLimitExceeded(_) => {
+                (StatusCode::PAYLOAD_TOO_LARGE, self.0.to_string())
+            }
+            VectorDbError::RebuildInProgress(_) => (StatusCode::CONFLICT, self.0.to_string()),
+            VectorDbError::ReadOnly(_) => (StatusCode::FORBIDDEN, self.0.to_string()),
+            VectorDbError::Frozen(_) => (StatusCode::CONFLICT, self.0.to_string()),
+            VectorDbError::Reranker(_) => (StatusCode::BAD_GATEWAY, self.0.to_string()),
+            VectorDbError::InvalidCursor(_) => (StatusCode::BAD_REQUEST, self.0.to_string()),
+            VectorDbError::StaleCursor(_) => (StatusCode::GONE, self.0.to_string()),
+            VectorDbError::Unauthorized(_) => (StatusCode::UNAUTHORIZED, self.0.to_string()),
             _ => (StatusCode::INTERNAL_SERVER_ERROR, self.0.to_string()),
         };
 
@@ -47,14 +98,103 @@ impl From<VectorDbError> for AppError {
 
 type AppResult<T> = Result<T, AppError>;
 
+// contrôle des nombres des embeddings par défaut (VECTORDB_NUMBER_HANDLING),
+// remplacé par requête avec l'en-tête X-Number-Handling
+static NUMBER_HANDLING: OnceLock<NumberHandling> = OnceLock::new();
+const NUMBER_HANDLING_HEADER: &str = "x-number-handling";
+
+// voies d'exécution par classe de priorité, choisie par requête avec l'en-tête
+// X-Priority (interactive par défaut)
+static PRIORITY_LANES: OnceLock<PriorityLanes> = OnceLock::new();
+const PRIORITY_HEADER: &str = "x-priority";
+
+// routes /admin/* : désactivées sans VECTORDB_ADMIN_TOKEN, réservées sinon aux
+// requêtes qui portent "Authorization: Bearer <jeton>"
+static ADMIN_TOKEN: OnceLock<Option<String>> = OnceLock::new();
+
+// Json des requêtes qui portent des embeddings : en mode strict, les valeurs qui ne
+// tiennent pas exactement dans un f32 fini sont refusées en 400 avec leur position
+// ({"error", "invalid": [{"field", "index", "component", "value", "reason"}], "total_invalid"})
+struct EmbeddingJson<T>(T);
+
+#[async_trait]
+impl<T, S> FromRequest<S> for EmbeddingJson<T>
+where
+    T: serde::de::DeserializeOwned,
+    S: Send + Sync,
+{
+    type Rejection = Response;
+
+    async fn from_request(request: Request, state: &S) -> Result<Self, Self::Rejection> {
+        let handling = match request.headers().get(NUMBER_HANDLING_HEADER) {
+            Some(value) => value.to_str().ok().and_then(NumberHandling::parse).ok_or_else(|| {
+                AppError(VectorDbError::InvalidConfig(format!(
+                    "invalid {} header: expected strict or lenient",
+                    NUMBER_HANDLING_HEADER
+                )))
+                .into_response()
+            })?,
+            None => NUMBER_HANDLING.get().copied().unwrap_or_default(),
+        };
+        if handling == NumberHandling::Lenient {
+            let Json(value) = Json::<T>::from_request(request, state).await.map_err(IntoResponse::into_response)?;
+            return Ok(EmbeddingJson(value));
+        }
+        let bytes = Bytes::from_request(request, state).await.map_err(IntoResponse::into_response)?;
+        let report = check_embedding_numbers(&bytes);
+        if !report.is_empty() {
+            let body = serde_json::json!({
+                "error": report.to_string(),
+                "invalid": report.invalid,
+                "total_invalid": report.total_invalid,
+            });
+            return Err((StatusCode::BAD_REQUEST, Json(body)).into_response());
+        }
+        let Json(value) = Json::<T>::from_bytes(&bytes).map_err(IntoResponse::into_response)?;
+        Ok(EmbeddingJson(value))
+    }
+}
+
 #[derive(Deserialize)]
 struct CreateCollectionRequest {
     name: String,
-    dimension: usize,
+    // profil de VECTORDB_PROFILES ; les autres champs fournis le surchargent
+    profile: Option<String>,
+    // obligatoire sans profil
+    dimension: Option<usize>,
+    use_ivf: Option<bool>,
+    n_clusters: Option<usize>,
+    embedding_model: Option<String>,
+    metadata_limits: Option<MetadataLimits>,
+    expected_size: Option<usize>,
+    content_hash: Option<bool>,
+    drift_threshold: Option<f32>,
+    // champs absents : ceux du profil, sinon les valeurs par défaut
+    rebuild_policy: Option<RebuildPolicyUpdate>,
+    // entrées ajoutées avec token_embeddings, requêtes par query_tokens (ColBERT)
+    #[serde(default)]
+    late_interaction: bool,
+    // IVF-PQ : octets par vecteur dans les listes IVF
+    pq_subspaces: Option<usize>,
+    // embeddings en int8 : {} ou {"rescore": 4}
+    scalar_quantization: Option<ScalarQuantization>,
+    // "float" (défaut) ou "binary" : `dimension` bits, distance de Hamming
+    vector_type: Option<VectorType>,
+    // garde les vecteurs tels qu'ajoutés, rendus par get, à côté de leur copie normalisée
     #[serde(default)]
-    use_ivf: bool,
-    #[serde(default = "default_n_clusters")]
-    n_clusters: usize,
+    store_raw: bool,
+    // "f32" (défaut), "f16" ou "bf16" : octets par composante des embeddings stockés
+    precision: Option<Precision>,
+    // "locked" (défaut) ou "snapshot" : requêtes sur un instantané copié à l'écriture
+    concurrency: Option<ConcurrencyMode>,
+    // pierres tombales des ids supprimés, pour les ajouts rejoués avec written_at
+    #[serde(default)]
+    tombstones: bool,
+}
+
+#[derive(Deserialize)]
+struct UpdateCollectionRequest {
+    rebuild_policy: Option<RebuildPolicyUpdate>,
 }
 
 fn default_n_clusters() -> usize {
@@ -64,14 +204,78 @@ fn default_n_clusters() -> usize {
 #[derive(Deserialize)]
 struct AddRequest {
     ids: Vec<String>,
+    // optionnel si la collection utilise le modèle intégré (hash-ngram-v1) et que
+    // `documents` est fourni
+    #[serde(default)]
     embeddings: Vec<Vec<f32>>,
+    // texte brut, stocké dans la métadonnée "document"
+    documents: Option<Vec<String>>,
     metadatas: Option<Vec<HashMap<String, serde_json::Value>>>,
+    embedding_model: Option<String>,
+    source: Option<String>,
+    // skip | overwrite (défaut) | merge | error
+    #[serde(default)]
+    on_conflict: OnConflict,
+    // empreintes fournies par le client, une par id
+    content_hashes: Option<Vec<String>>,
+    // collection late_interaction : matrice [token][dimension] par id, embeddings
+    // alors optionnels (moyenne des tokens)
+    token_embeddings: Option<Vec<TokenMatrix>>,
+    // collection binaire : vecteurs empaquetés en mots de 64 bits (bit i dans le bit
+    // i % 64 du mot i / 64), à la place d'embeddings en 0/1
+    binary_embeddings: Option<Vec<Vec<u64>>>,
+    // date d'écriture d'origine (RFC3339) d'un ajout rejoué par une réplique : les ids
+    // supprimés depuis ne sont pas ressuscités
+    written_at: Option<String>,
 }
 
 #[derive(Deserialize)]
 struct GetRequest {
     ids: Option<Vec<String>>,
     include: Option<Vec<String>>,
+    // pagination d'un get sans ids (voir VectorDbClient::scroll) : réponse avec
+    // next_cursor, à renvoyer dans cursor pour la page suivante
+    #[serde(flatten)]
+    filter: FilterFields,
+    limit: Option<usize>,
+    #[serde(default)]
+    offset: usize,
+    cursor: Option<String>,
+}
+
+// filtre d'une requête : objet JSON "where" ou syntaxe compacte "where_str"
+// ("lang = 'fr' AND price >= 10", voir filter::parse_where_str), exclusifs
+#[derive(Deserialize, Default)]
+struct FilterFields {
+    #[serde(rename = "where")]
+    where_filter: Option<serde_json::Value>,
+    where_str: Option<String>,
+}
+
+impl FilterFields {
+    // filtre sous sa forme JSON, celle du journal des requêtes ; None sans filtre
+    fn json(&self) -> Result<Option<serde_json::Value>, VectorDbError> {
+        match (&self.where_filter, &self.where_str) {
+            (Some(_), Some(_)) => Err(VectorDbError::InvalidFilter("where and where_str cannot be combined".into())),
+            (Some(json), None) => Ok(Some(json.clone())),
+            (None, Some(text)) => where_str_to_json(text).map(Some),
+            (None, None) => Ok(None),
+        }
+    }
+
+    fn parse(&self) -> Result<Option<WhereFilter>, VectorDbError> {
+        self.json()?.as_ref().map(parse_filter).transpose()
+    }
+
+    fn required(&self) -> Result<WhereFilter, VectorDbError> {
+        self.parse()?.ok_or_else(|| VectorDbError::InvalidFilter("where or where_str is required".into()))
+    }
+}
+
+#[derive(Deserialize)]
+struct CountRequest {
+    #[serde(flatten)]
+    filter: FilterFields,
 }
 
 #[derive(Deserialize)]
@@ -80,6 +284,33 @@ struct UpdateRequest {
     metadatas: Vec<HashMap<String, serde_json::Value>>,
 }
 
+#[derive(Deserialize)]
+struct UpdateWhereRequest {
+    #[serde(flatten)]
+    filter: FilterFields,
+    metadata: HashMap<String, serde_json::Value>,
+}
+
+#[derive(Deserialize)]
+struct DeleteWhereRequest {
+    #[serde(flatten)]
+    filter: FilterFields,
+}
+
+#[derive(Deserialize)]
+struct ScrubRequest {
+    #[serde(flatten)]
+    filter: FilterFields,
+    #[serde(default)]
+    reason: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct DiffRequest {
+    // id -> empreinte du contenu côté source
+    hashes: HashMap<String, String>,
+}
+
 #[derive(Deserialize)]
 struct DeleteRequest {
     ids: Vec<String>,
@@ -87,76 +318,337 @@ struct DeleteRequest {
 
 #[derive(Deserialize)]
 struct QueryRequest {
+    #[serde(default)]
     query_embedding: Vec<f32>,
+    // plusieurs requêtes en un appel : réponse [[résultats de la 1re], [de la 2e], ...]
+    query_embeddings: Option<Vec<Vec<f32>>>,
+    // alternative à query_embedding avec le modèle intégré
+    query_text: Option<String>,
     n_results: usize,
-    #[serde(rename = "where")]
-    where_filter: Option<vectordb_rust::filter::WhereFilter>,
+    #[serde(flatten)]
+    filter: FilterFields,
+    embedding_model: Option<String>,
+    // recherche exhaustive même si l'index IVF est construit
+    #[serde(default)]
+    exact: bool,
+    // ajoute "explain" à chaque résultat (cluster, produit scalaire, étape du filtre)
+    #[serde(default)]
+    explain_hits: bool,
+    // "asc" (défaut, plus proches d'abord) ou "desc"
+    #[serde(default)]
+    direction: SortDirection,
+    // départage des distances égales : [{"key": "timestamp", "direction": "desc"}]
+    #[serde(default)]
+    sort_by: Vec<SortKey>,
+    // similarité minimale (1 - distance) ou distance maximale, l'une ou l'autre :
+    // résultats écartés avant de couper à n_results, liste vide sans voisin assez proche
+    score_threshold: Option<f32>,
+    max_distance: Option<f32>,
+    // exécute aussi la recherche exhaustive et renvoie les deux listes avec le rappel
+    #[serde(default)]
+    compare: bool,
+    // second classement des meilleurs candidats : {"reranker": "default", "query": "...", "candidates": 40}
+    rerank: Option<RerankRequest>,
+    // collection late_interaction : tokens de la requête, remplacent query_embedding
+    query_tokens: Option<Vec<Vec<f32>>>,
+    // candidats du premier étage reclassés par max-sim, 4 × n_results par défaut
+    rescore_candidates: Option<usize>,
+    // collection late_interaction interrogée avec query_embedding : "max_sim" (meilleur
+    // vecteur de chaque id) ou "mean", un résultat par id
+    aggregation: Option<VectorAggregation>,
+    // collection binaire : requête empaquetée, remplace query_embedding
+    query_binary: Option<Vec<u64>>,
+    // "exact" ou "estimate" : réponse {"results": [...], "total_matched": n, "total_exact": bool},
+    // nombre de vecteurs qui passent le filtre
+    total_matched: Option<CountMode>,
 }
 
-fn convert_metadata(value: serde_json::Value) -> vectordb_rust::vector::MetadataValue {
-    use vectordb_rust::vector::MetadataValue;
-    match value {
-        serde_json::Value::String(s) => MetadataValue::String(s),
-        serde_json::Value::Number(n) => {
-            if let Some(i) = n.as_i64() {
-                MetadataValue::Int(i)
-            } else {
-                MetadataValue::Float(n.as_f64().unwrap_or(0.0))
-            }
-        }
-        serde_json::Value::Bool(b) => MetadataValue::Bool(b),
-        _ => MetadataValue::String(value.to_string()),
+#[derive(Deserialize)]
+struct RerankRequest {
+    #[serde(default = "default_reranker")]
+    reranker: String,
+    // texte passé au reranker, query_text par défaut
+    query: Option<String>,
+    // candidats de la recherche vectorielle reclassés, 4 × n_results par défaut
+    candidates: Option<usize>,
+}
+
+fn default_reranker() -> String {
+    DEFAULT_RERANKER.to_string()
+}
+
+#[derive(Deserialize)]
+struct CollectionDiffSide {
+    collection: String,
+    // nom d'un instantané de VECTORDB_SNAPSHOT_DIR (GET /admin/snapshots)
+    snapshot: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct CollectionDiffRequest {
+    left: CollectionDiffSide,
+    right: CollectionDiffSide,
+    tolerance: Option<f32>,
+    // ids détaillés par liste, 0 pour tous
+    max_ids: Option<usize>,
+}
+
+// ids détaillés par liste d'un diff sans max_ids
+const DEFAULT_DIFF_MAX_IDS: usize = 1000;
+
+#[derive(Deserialize)]
+struct RangeQueryRequest {
+    #[serde(default)]
+    query_embedding: Vec<f32>,
+    query_text: Option<String>,
+    // rayon, ou similarité minimale (1 - distance)
+    max_distance: Option<f32>,
+    score_threshold: Option<f32>,
+    // VECTORDB_MAX_RANGE_RESULTS par défaut et au plus
+    max_results: Option<usize>,
+    #[serde(flatten)]
+    filter: FilterFields,
+    embedding_model: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct ScoreIdsRequest {
+    #[serde(default)]
+    query_embedding: Vec<f32>,
+    query_text: Option<String>,
+    ids: Vec<String>,
+    embedding_model: Option<String>,
+}
+
+// embeddings calculés par le fournisseur intégré quand le client n'envoie que du texte
+fn embed_texts(coll: &Collection, texts: &[String]) -> Result<Vec<Vec<f32>>, VectorDbError> {
+    let provider = HashEmbedding::for_model(coll.config.embedding_model.as_deref(), coll.config.dimension)
+        .ok_or_else(|| {
+            VectorDbError::InvalidConfig(format!(
+                "collection '{}' has no built-in embedding model ('{}'), embeddings are required",
+                coll.config.name, HASH_EMBEDDING_MODEL
+            ))
+        })?;
+    provider.embed(texts)
+}
+
+// embedding d'un texte de requête, repris du cache du client s'il a déjà été calculé
+fn embed_query(client: &VectorDbClient, coll: &Collection, text: &str) -> Result<Vec<f32>, VectorDbError> {
+    let model = coll.config.embedding_model.as_deref().unwrap_or_default();
+    client.embedding_cache().get_or_embed(model, coll.config.dimension, text, || {
+        Ok(embed_texts(coll, &[text.to_string()])?.remove(0))
+    })
+}
+
+// vecteur empaqueté d'une collection binaire, déplié en composantes 0/1
+fn unpack_binary(coll: &Collection, words: Vec<u64>) -> Result<Vec<f32>, VectorDbError> {
+    if !coll.config.is_binary() {
+        return Err(VectorDbError::InvalidConfig(format!(
+            "collection '{}' is not binary: packed vectors are not accepted",
+            coll.config.name
+        )));
     }
+    let dimension = coll.config.dimension;
+    BinaryVector::from_words(words, dimension).map(|v| v.to_floats(dimension)).ok_or_else(|| {
+        VectorDbError::InvalidConfig(format!(
+            "packed vectors of '{}' must hold {} words of 64 bits, with no bit set past bit {}",
+            coll.config.name,
+            BinaryVector::n_words(dimension),
+            dimension - 1
+        ))
+    })
+}
+
+fn convert_metadata(value: serde_json::Value) -> vectordb_rust::vector::MetadataValue {
+    vectordb_rust::vector::MetadataValue::from_json(value)
 }
 
 async fn create_collection(
     State(client): State<SharedClient>,
     Json(req): Json<CreateCollectionRequest>,
 ) -> AppResult<Json<serde_json::Value>> {
+    let mut config = match req.profile {
+        Some(ref profile) => client.profile(profile)?.config(profile, req.name.clone()),
+        None => {
+            let dimension = req.dimension.ok_or_else(|| {
+                VectorDbError::InvalidConfig("dimension is required when no profile is given".to_string())
+            })?;
+            CollectionConfig::new(req.name.clone(), dimension)
+        }
+    };
+    if let Some(dimension) = req.dimension {
+        config.dimension = dimension;
+    }
+    if req.use_ivf.unwrap_or(config.use_ivf) {
+        let profile_clusters = (config.n_clusters > 0).then_some(config.n_clusters);
+        config = config.with_ivf(req.n_clusters.or(profile_clusters).unwrap_or_else(default_n_clusters));
+    } else {
+        config.use_ivf = false;
+        config.n_clusters = 0;
+    }
+    if let Some(model) = req.embedding_model {
+        config = config.with_embedding_model(model);
+    }
+    if let Some(limits) = req.metadata_limits {
+        config = config.with_metadata_limits(limits);
+    }
+    if req.expected_size.is_some() {
+        config.expected_size = req.expected_size;
+    }
+    if let Some(content_hash) = req.content_hash {
+        config.content_hash = content_hash;
+    }
+    if req.drift_threshold.is_some() {
+        config.drift_threshold = req.drift_threshold;
+    }
+    if let Some(update) = req.rebuild_policy {
+        config.rebuild_policy = update.apply(&config.rebuild_policy);
+    }
+    if req.late_interaction {
+        config = config.with_late_interaction();
+    }
+    if let Some(subspaces) = req.pq_subspaces {
+        config = config.with_pq(subspaces);
+    }
+    if let Some(sq) = req.scalar_quantization {
+        config = config.with_scalar_quantization(sq.rescore);
+    }
+    if let Some(vector_type) = req.vector_type {
+        config.vector_type = vector_type;
+    }
+    if req.store_raw {
+        config = config.with_store_raw();
+    }
+    if let Some(precision) = req.precision {
+        config = config.with_precision(precision);
+    }
+    if let Some(concurrency) = req.concurrency {
+        config = config.with_concurrency(concurrency);
+    }
+    if req.tombstones {
+        config = config.with_tombstones();
+    }
+
     tracing::info!(
-        name = %req.name,
-        dimension = req.dimension,
-        use_ivf = req.use_ivf,
+        name = %config.name,
+        dimension = config.dimension,
+        use_ivf = config.use_ivf,
+        profile = config.profile.as_deref().unwrap_or("-"),
         "Creating collection"
     );
+    let response = serde_json::json!({
+        "status": "created",
+        "name": config.name,
+        "use_ivf": config.use_ivf,
+        "n_clusters": config.n_clusters,
+        "embedding_model": config.embedding_model,
+        "profile": config.profile,
+        "late_interaction": config.late_interaction,
+        "pq_subspaces": config.pq_subspaces,
+        "scalar_quantization": config.scalar_quantization,
+        "vector_type": config.vector_type,
+        "store_raw": config.store_raw,
+        "precision": config.precision,
+        "concurrency": config.concurrency,
+        "tombstones": config.tombstones,
+    });
+    client.create_collection_with_config(config)?;
 
-    if req.use_ivf {
-        client.create_collection_with_ivf(req.name.clone(), req.dimension, req.n_clusters)?;
-    } else {
-        client.create_collection(req.name.clone(), req.dimension)?;
-    }
+    Ok(Json(response))
+}
 
-    Ok(Json(serde_json::json!({
-        "status": "created",
-        "name": req.name,
-        "use_ivf": req.use_ivf,
-        "n_clusters": if req.use_ivf { req.n_clusters } else { 0 }
-    })))
+async fn list_profiles(State(client): State<SharedClient>) -> Json<serde_json::Value> {
+    let profiles: serde_json::Map<String, serde_json::Value> = client
+        .list_profiles()
+        .into_iter()
+        .map(|(name, profile)| (name, serde_json::to_value(profile).unwrap()))
+        .collect();
+    Json(serde_json::Value::Object(profiles))
+}
+
+#[derive(Deserialize)]
+struct ListCollectionsParams {
+    #[serde(default)]
+    details: bool,
 }
 
 async fn list_collections(
     State(client): State<SharedClient>,
-) -> AppResult<Json<Vec<String>>> {
+    Query(params): Query<ListCollectionsParams>,
+) -> AppResult<Json<serde_json::Value>> {
+    if params.details {
+        let details = client.list_collections_details()?;
+        return Ok(Json(serde_json::to_value(&details).unwrap()));
+    }
     let collections = client.list_collections()?;
-    Ok(Json(collections))
+    Ok(Json(serde_json::json!(collections)))
+}
+
+#[derive(Deserialize)]
+struct GetCollectionParams {
+    #[serde(default)]
+    load: bool,
 }
 
+// ne charge la collection en mémoire que si ?load=true
 async fn get_collection(
     State(client): State<SharedClient>,
     Path(name): Path<String>,
+    Query(params): Query<GetCollectionParams>,
 ) -> AppResult<Json<serde_json::Value>> {
-    client.get_collection(&name)?;
+    if params.load {
+        client.get_collection(&name)?;
+    }
+    let info = client.collection_info(&name)?;
     Ok(Json(serde_json::json!({
-        "status": "loaded",
-        "name": name
+        "status": if client.is_loaded(&name) { "loaded" } else { "available" },
+        "name": name,
+        "dimension": info.config.dimension,
+        "count": info.count,
+        "use_ivf": info.config.use_ivf,
+        "embedding_model": info.config.embedding_model
     })))
 }
 
+// les stats bougent aussi avec les requêtes et les rebuilds : ETag calculé sur la
+// réponse, sans Last-Modified
 async fn get_collection_stats(
     State(client): State<SharedClient>,
     Path(name): Path<String>,
+    headers: HeaderMap,
+) -> AppResult<Response> {
+    let stats = client.stats(&name)?;
+    let body = serde_json::to_vec(&stats).unwrap();
+    let etag = content_etag(&body);
+    if not_modified(&headers, &etag, None) {
+        return Ok(with_validators(StatusCode::NOT_MODIFIED.into_response(), &etag, None));
+    }
+    let response = ([(header::CONTENT_TYPE, "application/json")], body).into_response();
+    Ok(with_validators(response, &etag, None))
+}
+
+// léger : ne charge pas la collection
+async fn get_stats_history(
+    State(client): State<SharedClient>,
+    Path(name): Path<String>,
+) -> AppResult<Json<serde_json::Value>> {
+    let snapshots = client.stats_history(&name)?;
+    Ok(Json(serde_json::json!({"name": name, "snapshots": snapshots})))
+}
+
+// léger : ne charge pas la collection
+async fn get_index_status(
+    State(client): State<SharedClient>,
+    Path(name): Path<String>,
+) -> AppResult<Json<IndexStatus>> {
+    Ok(Json(client.index_status(&name)?))
+}
+
+async fn get_metadata_stats(
+    State(client): State<SharedClient>,
+    Path(name): Path<String>,
 ) -> AppResult<Json<serde_json::Value>> {
-    let stats = client.with_collection(&name, |coll| coll.stats())?;
+    let stats = client.with_collection(&name, |coll| coll.metadata_stats())?;
     Ok(Json(serde_json::to_value(&stats).unwrap()))
 }
 
@@ -190,16 +682,15 @@ async fn rebuild_index(
 
     tracing::info!(collection = %name, "Rebuilding IVF index");
 
+    // le k-means tourne hors verrou, les écritures restent acceptées pendant le rebuild
     let start = Instant::now();
-    let stats = client.with_collection_mut(&name, |coll| {
-        if !coll.config.use_ivf {
-            return Err(VectorDbError::InvalidConfig(
-                "Collection does not use IVF index".to_string()
-            ));
-        }
-        coll.rebuild_index();
-        Ok(coll.stats())
-    })?;
+    let task_client = client.clone();
+    let task_name = name.clone();
+    let stats = tokio::task::spawn_blocking(move || {
+        task_client.rebuild_index_background(&task_name)
+    })
+    .await
+    .map_err(|e| VectorDbError::Io(std::io::Error::other(e)))??;
 
     let elapsed_ms = start.elapsed().as_secs_f64() * 1000.0;
     tracing::info!(
@@ -215,124 +706,1241 @@ async fn rebuild_index(
     })))
 }
 
-async fn health_check(State(client): State<SharedClient>) -> Json<serde_json::Value> {
-    let collections = client.list_collections().unwrap_or_default();
-    Json(serde_json::json!({
-        "status": "healthy",
-        "version": env!("CARGO_PKG_VERSION"),
-        "collections_count": collections.len(),
-    }))
-}
-
-async fn delete_collection(
+// listes IVF sur disque ; relancé sur une collection déjà sur disque, réentraîne l'index
+async fn offload_index(
     State(client): State<SharedClient>,
     Path(name): Path<String>,
 ) -> AppResult<Json<serde_json::Value>> {
-    tracing::info!(collection = %name, "Deleting collection");
-    client.delete_collection(&name)?;
+    use std::time::Instant;
+
+    let start = Instant::now();
+    let task_client = client.clone();
+    let task_name = name.clone();
+    let stats = tokio::task::spawn_blocking(move || task_client.offload_collection(&task_name))
+        .await
+        .map_err(|e| VectorDbError::Io(std::io::Error::other(e)))??;
+
+    let elapsed_ms = start.elapsed().as_secs_f64() * 1000.0;
+    tracing::info!(collection = %name, elapsed_ms = elapsed_ms, "IVF lists offloaded to disk");
+
     Ok(Json(serde_json::json!({
-        "status": "deleted",
-        "name": name
+        "status": "offloaded",
+        "elapsed_ms": elapsed_ms,
+        "collection_stats": stats
     })))
 }
 
-async fn add_vectors(
+// segment immuable projeté en mémoire ; écritures refusées (409) jusqu'à /thaw
+async fn freeze_collection(
     State(client): State<SharedClient>,
-    Path(collection_name): Path<String>,
-    Json(req): Json<AddRequest>,
+    Path(name): Path<String>,
 ) -> AppResult<Json<serde_json::Value>> {
-    let count = req.ids.len();
-    tracing::debug!(
-        collection = %collection_name,
-        count = count,
-        "Adding vectors"
-    );
-
-    let metas = req.metadatas.map(|ms| {
-        ms.into_iter()
-            .map(|m| m.into_iter().map(|(k, v)| (k, convert_metadata(v))).collect())
-            .collect()
-    });
+    use std::time::Instant;
 
-    client.with_collection_mut(&collection_name, |coll| {
-        coll.add(req.ids.clone(), req.embeddings, metas)
-    })?;
+    let start = Instant::now();
+    let task_client = client.clone();
+    let task_name = name.clone();
+    let stats = tokio::task::spawn_blocking(move || task_client.freeze_collection(&task_name))
+        .await
+        .map_err(|e| VectorDbError::Io(std::io::Error::other(e)))??;
 
-    Ok(Json(serde_json::json!({"status": "added", "count": count})))
+    Ok(Json(serde_json::json!({
+        "status": "frozen",
+        "elapsed_ms": start.elapsed().as_secs_f64() * 1000.0,
+        "collection_stats": stats
+    })))
 }
 
-async fn get_vectors(
+async fn thaw_collection(
     State(client): State<SharedClient>,
-    Path(collection_name): Path<String>,
-    Json(req): Json<GetRequest>,
+    Path(name): Path<String>,
 ) -> AppResult<Json<serde_json::Value>> {
-    let result = client.with_collection(&collection_name, |coll| -> Result<_, VectorDbError> {
-        coll.get(req.ids, req.include)
-    })??;
+    let task_client = client.clone();
+    let stats = tokio::task::spawn_blocking(move || task_client.thaw_collection(&name))
+        .await
+        .map_err(|e| VectorDbError::Io(std::io::Error::other(e)))??;
 
-    Ok(Json(serde_json::to_value(&result).unwrap()))
+    Ok(Json(serde_json::json!({
+        "status": "thawed",
+        "collection_stats": stats
+    })))
 }
 
-async fn update_vectors(
+// segments de la collection, blocs de vecteurs relus pour contrôler leurs sommes de contrôle
+async fn verify_segments(
     State(client): State<SharedClient>,
-    Path(collection_name): Path<String>,
-    Json(req): Json<UpdateRequest>,
-) -> AppResult<Json<serde_json::Value>> {
-    let metas: Vec<HashMap<String, _>> = req
-        .metadatas
-        .into_iter()
-        .map(|meta| meta.into_iter().map(|(k, v)| (k, convert_metadata(v))).collect())
-        .collect();
-
-    let n = req.ids.len();
-    client.with_collection_mut(&collection_name, |coll| coll.update(req.ids.clone(), metas))?;
-
-    Ok(Json(serde_json::json!({"status": "updated", "count": n})))
+    Path(name): Path<String>,
+) -> AppResult<Json<Vec<SegmentInfo>>> {
+    let infos = tokio::task::spawn_blocking(move || client.verify_segments(&name))
+        .await
+        .map_err(|e| VectorDbError::Io(std::io::Error::other(e)))??;
+    Ok(Json(infos))
 }
 
-async fn delete_vectors(
+// memtable écrite dans un nouveau segment ; le premier vidage range toute la collection
+async fn flush_collection(
     State(client): State<SharedClient>,
-    Path(collection_name): Path<String>,
-    Json(req): Json<DeleteRequest>,
+    Path(name): Path<String>,
 ) -> AppResult<Json<serde_json::Value>> {
-    let count = req.ids.len();
-    client.with_collection_mut(&collection_name, |coll| coll.delete(req.ids))?;
-    Ok(Json(serde_json::json!({"status": "deleted", "count": count})))
+    let segment = tokio::task::spawn_blocking(move || client.flush_collection(&name))
+        .await
+        .map_err(|e| VectorDbError::Io(std::io::Error::other(e)))??;
+    Ok(Json(serde_json::json!({
+        "status": if segment.is_some() { "flushed" } else { "empty" },
+        "segment": segment
+    })))
 }
 
-async fn query_vectors(
+// une fusion de segments, sans attendre la tâche périodique
+async fn merge_segments(
     State(client): State<SharedClient>,
-    Path(coll_name): Path<String>,
-    Json(req): Json<QueryRequest>,
+    Path(name): Path<String>,
 ) -> AppResult<Json<serde_json::Value>> {
-    tracing::debug!(
-        collection = %coll_name,
-        n_results = req.n_results,
-        has_filter = req.where_filter.is_some(),
-        "Querying vectors"
-    );
+    let segment = tokio::task::spawn_blocking(move || client.merge_segments(&name))
+        .await
+        .map_err(|e| VectorDbError::Io(std::io::Error::other(e)))??;
+    Ok(Json(serde_json::json!({
+        "status": if segment.is_some() { "merged" } else { "nothing_to_merge" },
+        "segment": segment
+    })))
+}
 
-    let results = client.with_collection_mut(&coll_name, |coll| {
-        coll.query(&req.query_embedding, req.n_results, req.where_filter.as_ref())
-    })?;
+async fn verify_index(
+    State(client): State<SharedClient>,
+    Path(name): Path<String>,
+) -> AppResult<Json<IndexReport>> {
+    let report = client.with_collection(&name, |coll| coll.verify_index())??;
+    Ok(Json(report))
+}
 
-    tracing::debug!(
-        collection = %coll_name,
-        results_count = results.len(),
-        "Query completed"
-    );
+#[derive(Deserialize)]
+struct AssignRequest {
+    embedding: Option<Vec<f32>>,
+    id: Option<String>,
+    #[serde(default = "default_assign_n")]
+    n: usize,
+}
 
-    Ok(Json(serde_json::to_value(&results).unwrap()))
+fn default_assign_n() -> usize {
+    5
 }
 
-#[tokio::main]
-async fn main() {
-    // initialiser tracing
-    use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
-    tracing_subscriber::registry()
-        .with(
-            tracing_subscriber::EnvFilter::try_from_default_env()
-                .unwrap_or_else(|_| "vectordb_rust=info,tower_http=info".into()),
+async fn assign_index(
+    State(client): State<SharedClient>,
+    Path(name): Path<String>,
+    Json(req): Json<AssignRequest>,
+) -> AppResult<Json<AssignReport>> {
+    let report = client.with_collection(&name, |coll| {
+        coll.assign_diagnostics(req.embedding.as_deref(), req.id.as_deref(), req.n)
+    })??;
+    Ok(Json(report))
+}
+
+async fn repair_index(
+    State(client): State<SharedClient>,
+    Path(name): Path<String>,
+) -> AppResult<Json<IndexReport>> {
+    let report = client.with_collection_mut(&name, |coll| coll.repair_index())?;
+    Ok(Json(report))
+}
+
+#[derive(Deserialize)]
+struct NormParams {
+    // écart toléré |norme - 1|
+    tolerance: Option<f32>,
+}
+
+async fn verify_norms(
+    State(client): State<SharedClient>,
+    Path(name): Path<String>,
+    Query(params): Query<NormParams>,
+) -> AppResult<Json<NormReport>> {
+    let tolerance = params.tolerance.unwrap_or(DEFAULT_NORM_TOLERANCE);
+    let report = client.with_collection(&name, |coll| coll.verify_norms(tolerance))??;
+    Ok(Json(report))
+}
+
+async fn renormalize(
+    State(client): State<SharedClient>,
+    Path(name): Path<String>,
+    Query(params): Query<NormParams>,
+) -> AppResult<Json<NormReport>> {
+    let tolerance = params.tolerance.unwrap_or(DEFAULT_NORM_TOLERANCE);
+    let report = client.with_collection_mut(&name, |coll| coll.renormalize(tolerance))?;
+    Ok(Json(report))
+}
+
+async fn health_check(State(client): State<SharedClient>) -> Json<serde_json::Value> {
+    let collections = client.list_collections().unwrap_or_default();
+    let limits = client.limits();
+    Json(serde_json::json!({
+        "status": "healthy",
+        "version": env!("CARGO_PKG_VERSION"),
+        "collections_count": collections.len(),
+        "simd": simd_kernel().name(),
+        "limits": {
+            "max_dimension": limits.max_dimension,
+            "max_n_clusters": limits.max_n_clusters,
+            "max_vectors_per_collection": limits.max_vectors_per_collection,
+            "max_get_results": limits.max_get_results,
+            "max_batch_size": limits.max_batch_size,
+            "max_query_batch": limits.max_query_batch,
+            "max_range_results": limits.max_range_results,
+            "soft_limit_ratio": limits.soft_limit_ratio,
+            "collections": client.collection_limit_usage().unwrap_or_default(),
+        },
+    }))
+}
+
+async fn cache_stats(State(client): State<SharedClient>) -> Json<serde_json::Value> {
+    Json(serde_json::to_value(client.cache_metrics()).unwrap())
+}
+
+async fn clear_cache(State(client): State<SharedClient>) -> Json<serde_json::Value> {
+    let evicted = client.clear_cache();
+    Json(serde_json::json!({"status": "cleared", "evicted": evicted}))
+}
+
+async fn embedding_cache_stats(State(client): State<SharedClient>) -> Json<serde_json::Value> {
+    Json(serde_json::to_value(client.embedding_cache().metrics()).unwrap())
+}
+
+async fn flush_embedding_cache(State(client): State<SharedClient>) -> Json<serde_json::Value> {
+    let flushed = client.embedding_cache().flush();
+    Json(serde_json::json!({"status": "flushed", "flushed": flushed}))
+}
+
+async fn purge_tombstones(State(client): State<SharedClient>) -> AppResult<Json<serde_json::Value>> {
+    let purged = tokio::task::spawn_blocking(move || client.purge_tombstones())
+        .await
+        .map_err(|e| VectorDbError::Io(std::io::Error::other(e)))??;
+    Ok(Json(serde_json::json!({"purged": purged})))
+}
+
+async fn list_snapshots(State(client): State<SharedClient>) -> AppResult<Json<serde_json::Value>> {
+    let snapshots = client.list_snapshots()?;
+    Ok(Json(serde_json::json!({"snapshots": snapshots})))
+}
+
+async fn ship_snapshot(State(client): State<SharedClient>) -> AppResult<Json<serde_json::Value>> {
+    let shipped = tokio::task::spawn_blocking(move || client.ship_snapshot())
+        .await
+        .map_err(|e| VectorDbError::Io(std::io::Error::other(e)))??;
+    Ok(Json(serde_json::to_value(&shipped).unwrap()))
+}
+
+#[derive(Deserialize)]
+struct PruneSnapshotsParams {
+    #[serde(default)]
+    dry_run: bool,
+}
+
+async fn prune_snapshots(
+    State(client): State<SharedClient>,
+    Query(params): Query<PruneSnapshotsParams>,
+) -> AppResult<Json<serde_json::Value>> {
+    let report = tokio::task::spawn_blocking(move || client.prune_snapshots(params.dry_run))
+        .await
+        .map_err(|e| VectorDbError::Io(std::io::Error::other(e)))??;
+    Ok(Json(serde_json::to_value(&report).unwrap()))
+}
+
+async fn reload_collection(
+    State(client): State<SharedClient>,
+    Path(name): Path<String>,
+) -> AppResult<Json<serde_json::Value>> {
+    let stats = client.reload_collection(&name)?;
+    Ok(Json(serde_json::json!({"status": "reloaded", "stats": stats})))
+}
+
+#[derive(Deserialize)]
+struct DryRunParams {
+    #[serde(default)]
+    dry_run: bool,
+}
+
+async fn migrate_storage(
+    State(client): State<SharedClient>,
+    Query(params): Query<DryRunParams>,
+) -> AppResult<Json<serde_json::Value>> {
+    let reports = client.migrate_storage(params.dry_run)?;
+    Ok(Json(serde_json::json!({
+        "dry_run": params.dry_run,
+        "migrated": reports
+    })))
+}
+
+async fn scan_storage(
+    State(client): State<SharedClient>,
+    Query(params): Query<DryRunParams>,
+) -> AppResult<Json<ScanReport>> {
+    Ok(Json(client.scan_storage(params.dry_run)?))
+}
+
+#[cfg(feature = "chroma-import")]
+#[derive(Deserialize)]
+struct ImportChromaRequest {
+    path: String,
+}
+
+#[cfg(feature = "chroma-import")]
+async fn import_chroma(
+    State(client): State<SharedClient>,
+    Json(req): Json<ImportChromaRequest>,
+) -> AppResult<Json<serde_json::Value>> {
+    tracing::info!(path = %req.path, "Importing Chroma directory");
+    let task_client = client.clone();
+    let imported = tokio::task::spawn_blocking(move || task_client.import_chroma(&req.path))
+        .await
+        .map_err(|e| VectorDbError::Io(std::io::Error::other(e)))??;
+
+    let collections: Vec<serde_json::Value> = imported
+        .into_iter()
+        .map(|(name, count)| serde_json::json!({"name": name, "count": count}))
+        .collect();
+    Ok(Json(serde_json::json!({"status": "imported", "collections": collections})))
+}
+
+#[derive(Deserialize)]
+struct ImportFaissRequest {
+    path: String,
+    name: String,
+}
+
+async fn import_faiss(
+    State(client): State<SharedClient>,
+    Json(req): Json<ImportFaissRequest>,
+) -> AppResult<Json<serde_json::Value>> {
+    tracing::info!(path = %req.path, collection = %req.name, "Importing Faiss index");
+    let task_client = client.clone();
+    let name = req.name.clone();
+    let count = tokio::task::spawn_blocking(move || task_client.import_faiss(&req.path, &req.name))
+        .await
+        .map_err(|e| VectorDbError::Io(std::io::Error::other(e)))??;
+    Ok(Json(serde_json::json!({"status": "imported", "name": name, "count": count})))
+}
+
+#[derive(Deserialize)]
+struct ExportRequest {
+    path: String,
+}
+
+async fn export_faiss(
+    State(client): State<SharedClient>,
+    Path(name): Path<String>,
+    Json(req): Json<ExportRequest>,
+) -> AppResult<Json<serde_json::Value>> {
+    let count = client.export_faiss(&name, &req.path)?;
+    Ok(Json(serde_json::json!({"status": "exported", "path": req.path, "count": count})))
+}
+
+async fn debug_collections(State(client): State<SharedClient>) -> Json<serde_json::Value> {
+    Json(serde_json::to_value(client.collection_op_stats()).unwrap())
+}
+
+async fn delete_collection(
+    State(client): State<SharedClient>,
+    Path(name): Path<String>,
+) -> AppResult<Json<serde_json::Value>> {
+    tracing::info!(collection = %name, "Deleting collection");
+    client.delete_collection(&name)?;
+    Ok(Json(serde_json::json!({
+        "status": "deleted",
+        "name": name
+    })))
+}
+
+async fn update_collection(
+    State(client): State<SharedClient>,
+    Path(name): Path<String>,
+    Json(req): Json<UpdateCollectionRequest>,
+) -> AppResult<Json<serde_json::Value>> {
+    let rebuild_policy = client.with_collection_mut(&name, |coll| {
+        if let Some(update) = req.rebuild_policy {
+            coll.set_rebuild_policy(update.apply(&coll.config.rebuild_policy))?;
+        }
+        Ok(coll.config.rebuild_policy.clone())
+    })?;
+    tracing::info!(collection = %name, ?rebuild_policy, "Collection updated");
+    Ok(Json(serde_json::json!({
+        "name": name,
+        "rebuild_policy": rebuild_policy
+    })))
+}
+
+// paramètres de recherche et politique de rebuild, sans rebuild de l'index
+async fn update_index_params(
+    State(client): State<SharedClient>,
+    Path(name): Path<String>,
+    Json(update): Json<IndexParamsUpdate>,
+) -> AppResult<Json<serde_json::Value>> {
+    let (params, policy, index_info) = client.with_collection_mut(&name, |coll| {
+        if !coll.config.use_ivf {
+            return Err(VectorDbError::InvalidConfig("Collection does not use IVF index".to_string()));
+        }
+        // tout validé avant d'appliquer quoi que ce soit
+        let params = update.apply(&coll.config.index_params);
+        params.validate()?;
+        let policy = update.rebuild_policy.as_ref().map(|u| u.apply(&coll.config.rebuild_policy));
+        if let Some(ref policy) = policy {
+            policy.validate()?;
+        }
+
+        coll.set_index_params(params)?;
+        if let Some(policy) = policy {
+            coll.set_rebuild_policy(policy)?;
+        }
+        Ok((coll.config.index_params.clone(), coll.config.rebuild_policy.clone(), coll.stats().index_info))
+    })?;
+    tracing::info!(collection = %name, ?params, ?policy, "Index parameters updated");
+    Ok(Json(serde_json::json!({
+        "name": name,
+        "index_params": params,
+        "rebuild_policy": policy,
+        "index_info": index_info
+    })))
+}
+
+async fn add_vectors(
+    State(client): State<SharedClient>,
+    Path(collection_name): Path<String>,
+    EmbeddingJson(req): EmbeddingJson<AddRequest>,
+) -> AppResult<Json<serde_json::Value>> {
+    let outcome = add_request(&client, &collection_name, req)?;
+    Ok(Json(serde_json::json!({
+        "status": "added",
+        "count": outcome.added + outcome.overwritten,
+        "added": outcome.added,
+        "overwritten": outcome.overwritten,
+        "skipped": outcome.skipped,
+        "tombstoned": outcome.tombstoned,
+    })))
+}
+
+// ids présents : embedding remplacé, métadonnées fusionnées (on_conflict du corps ignoré)
+async fn upsert_vectors(
+    State(client): State<SharedClient>,
+    Path(collection_name): Path<String>,
+    EmbeddingJson(mut req): EmbeddingJson<AddRequest>,
+) -> AppResult<Json<serde_json::Value>> {
+    req.on_conflict = OnConflict::Merge;
+    let outcome = add_request(&client, &collection_name, req)?;
+    Ok(Json(serde_json::json!({
+        "status": "upserted",
+        "inserted": outcome.added,
+        "updated": outcome.overwritten,
+        "tombstoned": outcome.tombstoned,
+    })))
+}
+
+// corps commun à /add et /upsert
+fn add_request(client: &VectorDbClient, collection_name: &str, req: AddRequest) -> AppResult<AddOutcome> {
+    let count = req.ids.len();
+    tracing::debug!(
+        collection = %collection_name,
+        count = count,
+        "Adding vectors"
+    );
+
+    let mut metas: Option<Vec<HashMap<String, _>>> = req.metadatas.map(|ms| {
+        ms.into_iter()
+            .map(|m| m.into_iter().map(|(k, v)| (k, convert_metadata(v))).collect())
+            .collect()
+    });
+    if let Some(ref documents) = req.documents {
+        if documents.len() != count {
+            return Err(VectorDbError::InvalidConfig(
+                "documents must have the same length as ids".to_string(),
+            )
+            .into());
+        }
+        let metas = metas.get_or_insert_with(|| vec![HashMap::new(); count]);
+        for (meta, document) in metas.iter_mut().zip(documents) {
+            meta.entry("document".to_string())
+                .or_insert_with(|| MetadataValue::String(document.as_str().into()));
+        }
+    }
+
+    let outcome = client.with_collection_mut(collection_name, |coll| {
+        coll.check_embedding_model(req.embedding_model.as_deref())?;
+        let embeddings = match (req.binary_embeddings, req.documents) {
+            (Some(_), _) if !req.embeddings.is_empty() => {
+                return Err(VectorDbError::InvalidConfig(
+                    "embeddings and binary_embeddings cannot be combined".to_string(),
+                ))
+            }
+            (Some(packed), _) => packed.into_iter().map(|words| unpack_binary(coll, words)).collect::<Result<_, _>>()?,
+            (None, Some(ref documents)) if req.embeddings.is_empty() => embed_texts(coll, documents)?,
+            _ => req.embeddings,
+        };
+        let mut options = AddOptions::new(req.source.as_deref().unwrap_or("api"))
+            .with_on_conflict(req.on_conflict);
+        if let Some(hashes) = req.content_hashes {
+            options = options.with_content_hashes(hashes);
+        }
+        if let Some(tokens) = req.token_embeddings {
+            options = options.with_token_embeddings(tokens);
+        }
+        if let Some(ref written_at) = req.written_at {
+            let at = chrono::DateTime::parse_from_rfc3339(written_at).map_err(|e| {
+                VectorDbError::InvalidConfig(format!("invalid written_at '{}': {}", written_at, e))
+            })?;
+            options = options.with_written_at(at.timestamp_micros());
+        }
+        coll.add_with_options(req.ids, embeddings, metas, options)
+    })?;
+    Ok(outcome)
+}
+
+// avec embeddings, réponse sérialisée en flux depuis un thread bloquant : seul un
+// bloc d'embeddings est en mémoire à la fois (voir VectorDbClient::get_stream). Les
+// pages (limit, offset, cursor, where) sont bornées et rendues d'un bloc
+async fn get_vectors(
+    State(client): State<SharedClient>,
+    Path(collection_name): Path<String>,
+    Json(req): Json<GetRequest>,
+) -> AppResult<Response> {
+    let filter = req.filter.parse()?;
+    if filter.is_some() || req.limit.is_some() || req.offset > 0 || req.cursor.is_some() {
+        if req.ids.is_some() {
+            return Err(AppError(VectorDbError::InvalidConfig(
+                "where, limit, offset and cursor apply to get without ids".to_string(),
+            )));
+        }
+        let request = ScrollRequest {
+            filter,
+            limit: req.limit,
+            offset: req.offset,
+            cursor: req.cursor,
+            include: req.include,
+        };
+        return Ok(Json(client.scroll(&collection_name, request)?).into_response());
+    }
+
+    let wants_embeddings = req.include.as_ref().is_none_or(|inc| inc.iter().any(|i| i == "embeddings"));
+    if !wants_embeddings {
+        let result = client.get(&collection_name, req.ids, req.include)?;
+        return Ok(Json(result).into_response());
+    }
+
+    // erreurs d'ouverture (collection absente, plafond) renvoyées avant le premier octet
+    let (opened_tx, opened_rx) = tokio::sync::oneshot::channel();
+    let (chunk_tx, chunk_rx) = tokio::sync::mpsc::channel::<Result<Vec<u8>, VectorDbError>>(4);
+    tokio::task::spawn_blocking(move || {
+        let stream = match client.get_stream(&collection_name, req.ids, req.include) {
+            Ok(stream) => {
+                let _ = opened_tx.send(Ok(()));
+                stream
+            }
+            Err(e) => {
+                let _ = opened_tx.send(Err(e));
+                return;
+            }
+        };
+        for chunk in stream {
+            // client déconnecté : inutile de lire la suite
+            if chunk_tx.blocking_send(chunk).is_err() {
+                break;
+            }
+        }
+    });
+    opened_rx
+        .await
+        .map_err(|e| VectorDbError::Io(std::io::Error::other(e)))??;
+
+    let body = futures_util::stream::unfold(chunk_rx, |mut rx| async move {
+        rx.recv().await.map(|chunk| (chunk, rx))
+    });
+    Ok(([(header::CONTENT_TYPE, "application/json")], Body::from_stream(body)).into_response())
+}
+
+#[derive(Deserialize)]
+struct MultiGetBody {
+    requests: Vec<MultiGetRequest>,
+}
+
+async fn get_multi(
+    State(client): State<SharedClient>,
+    Json(req): Json<MultiGetBody>,
+) -> AppResult<Json<serde_json::Value>> {
+    let results = client.get_multi(req.requests)?;
+    Ok(Json(serde_json::json!({"results": results})))
+}
+
+async fn count_vectors(
+    State(client): State<SharedClient>,
+    Path(collection_name): Path<String>,
+    Json(req): Json<CountRequest>,
+) -> AppResult<Json<serde_json::Value>> {
+    let where_filter = req.filter.parse()?;
+    let count = client.count(&collection_name, where_filter.as_ref())?;
+    Ok(Json(serde_json::json!({"count": count})))
+}
+
+#[derive(Deserialize)]
+struct GetVectorQuery {
+    // "embeddings", "metadatas" ou les deux séparés par une virgule (défaut)
+    include: Option<String>,
+}
+
+// GET /collections/{name}/vectors/{id} : un vecteur, avec ETag et Last-Modified tirés
+// de sa version (_system.updated_at) ; 304 sans corps si la copie du client est à jour
+async fn get_vector(
+    State(client): State<SharedClient>,
+    Path((collection_name, id)): Path<(String, String)>,
+    Query(query): Query<GetVectorQuery>,
+    headers: HeaderMap,
+) -> AppResult<Response> {
+    let include: Option<Vec<String>> =
+        query.include.map(|inc| inc.split(',').map(|i| i.trim().to_string()).collect());
+    let parts = include.clone();
+    let (version, result) = client.with_collection(&collection_name, |coll| {
+        let version = coll.vector_version(&id);
+        coll.get(Some(vec![id.clone()]), include).map(|result| (version, result))
+    })??;
+    if result.ids.is_empty() {
+        return Err(VectorDbError::VectorNotFound(id).into());
+    }
+
+    // sans version (collection migrée d'un ancien format) : pas de validateurs
+    let Some(version) = version else {
+        return Ok(Json(single_vector(result)).into_response());
+    };
+    let etag = vector_etag(version, parts.as_deref());
+    if not_modified(&headers, &etag, Some(version)) {
+        return Ok(with_validators(StatusCode::NOT_MODIFIED.into_response(), &etag, Some(version)));
+    }
+    Ok(with_validators(Json(single_vector(result)).into_response(), &etag, Some(version)))
+}
+
+fn single_vector(result: GetResult) -> serde_json::Value {
+    let mut value = serde_json::to_value(&result).unwrap();
+    let mut vector = serde_json::json!({"id": result.ids[0]});
+    if let Some(embedding) = value.pointer_mut("/embeddings/0") {
+        vector["embedding"] = embedding.take();
+    }
+    if let Some(metadata) = value.pointer_mut("/metadatas/0") {
+        vector["metadata"] = metadata.take();
+    }
+    vector
+}
+
+// ETag d'un vecteur : sa version et les parties rendues (include trié, sans doublon),
+// pour qu'une réponse sans embedding ne valide pas la copie complète, et inversement
+fn vector_etag(version: i64, include: Option<&[String]>) -> String {
+    let mut parts: Vec<&str> = match include {
+        Some(include) => include.iter().map(String::as_str).collect(),
+        None => vec!["embeddings", "metadatas"],
+    };
+    parts.sort_unstable();
+    parts.dedup();
+    format!("\"{:x}-{}\"", version, parts.join("+"))
+}
+
+// ETag fort d'une réponse : FNV-1a 64 bits du corps
+fn content_etag(body: &[u8]) -> String {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for &byte in body {
+        hash ^= byte as u64;
+        hash = hash.wrapping_mul(0x0000_0100_0000_01b3);
+    }
+    format!("\"{:016x}\"", hash)
+}
+
+fn http_date(micros: i64) -> Option<String> {
+    chrono::DateTime::from_timestamp_micros(micros).map(|t| t.format("%a, %d %b %Y %H:%M:%S GMT").to_string())
+}
+
+// requête conditionnelle satisfaite : If-None-Match contient l'ETag (ou *), ou à
+// défaut If-Modified-Since n'est pas antérieur à la dernière modification (à la
+// seconde, précision des dates HTTP)
+fn not_modified(headers: &HeaderMap, etag: &str, last_modified: Option<i64>) -> bool {
+    if let Some(if_none_match) = headers.get(header::IF_NONE_MATCH).and_then(|v| v.to_str().ok()) {
+        return if_none_match
+            .split(',')
+            .map(|tag| tag.trim())
+            .any(|tag| tag == "*" || tag.strip_prefix("W/").unwrap_or(tag) == etag);
+    }
+    let since = headers
+        .get(header::IF_MODIFIED_SINCE)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| chrono::DateTime::parse_from_rfc2822(&v.replace("GMT", "+0000")).ok());
+    match (since, last_modified) {
+        (Some(since), Some(micros)) => micros.div_euclid(1_000_000) <= since.timestamp(),
+        _ => false,
+    }
+}
+
+fn with_validators(mut response: Response, etag: &str, last_modified: Option<i64>) -> Response {
+    let headers = response.headers_mut();
+    if let Ok(value) = HeaderValue::from_str(etag) {
+        headers.insert(header::ETAG, value);
+    }
+    if let Some(value) = last_modified.and_then(http_date).and_then(|d| HeaderValue::from_str(&d).ok()) {
+        headers.insert(header::LAST_MODIFIED, value);
+    }
+    // revalidation à chaque usage : les caches gardent la copie mais la vérifient
+    headers.insert(header::CACHE_CONTROL, HeaderValue::from_static("no-cache"));
+    response
+}
+
+#[derive(Deserialize)]
+struct CountQuery {
+    where_str: Option<String>,
+}
+
+// GET /collections/{name}/count?where_str=lang%20%3D%20'fr' : comptage depuis un
+// navigateur ou un tableau de bord, sans corps JSON
+async fn count_vectors_get(
+    State(client): State<SharedClient>,
+    Path(collection_name): Path<String>,
+    Query(query): Query<CountQuery>,
+) -> AppResult<Json<serde_json::Value>> {
+    let where_filter = query.where_str.as_deref().map(parse_where_str).transpose()?;
+    let count = client.count(&collection_name, where_filter.as_ref())?;
+    Ok(Json(serde_json::json!({"count": count})))
+}
+
+async fn update_vectors(
+    State(client): State<SharedClient>,
+    Path(collection_name): Path<String>,
+    Json(req): Json<UpdateRequest>,
+) -> AppResult<Json<serde_json::Value>> {
+    let metas: Vec<HashMap<String, _>> = req
+        .metadatas
+        .into_iter()
+        .map(|meta| meta.into_iter().map(|(k, v)| (k, convert_metadata(v))).collect())
+        .collect();
+
+    let n = req.ids.len();
+    client.limits().check_batch_size("update", n)?;
+    client.with_collection_mut(&collection_name, |coll| coll.update(req.ids.clone(), metas))?;
+
+    Ok(Json(serde_json::json!({"status": "updated", "count": n})))
+}
+
+async fn update_vectors_where(
+    State(client): State<SharedClient>,
+    Path(collection_name): Path<String>,
+    Json(req): Json<UpdateWhereRequest>,
+) -> AppResult<Json<serde_json::Value>> {
+    let where_filter = req.filter.required()?;
+    let patch: HashMap<String, _> = req
+        .metadata
+        .into_iter()
+        .map(|(k, v)| (k, convert_metadata(v)))
+        .collect();
+
+    let limits = client.limits();
+    let count = client.with_collection_mut(&collection_name, |coll| {
+        limits.check_batch_size("update_where", coll.count_where(&where_filter))?;
+        coll.update_where(&where_filter, patch)
+    })?;
+
+    Ok(Json(serde_json::json!({"status": "updated", "count": count})))
+}
+
+async fn delete_vectors_where(
+    State(client): State<SharedClient>,
+    Path(collection_name): Path<String>,
+    Json(req): Json<DeleteWhereRequest>,
+) -> AppResult<Json<serde_json::Value>> {
+    let where_filter = req.filter.required()?;
+    let limits = client.limits();
+    let count = client.with_collection_mut(&collection_name, |coll| {
+        limits.check_batch_size("delete_where", coll.count_where(&where_filter))?;
+        coll.delete_where(&where_filter)
+    })?;
+    Ok(Json(serde_json::json!({"status": "deleted", "count": count})))
+}
+
+async fn scrub(
+    State(client): State<SharedClient>,
+    Json(req): Json<ScrubRequest>,
+) -> AppResult<Json<ScrubReport>> {
+    let where_filter = req.filter.required()?;
+    let report = tokio::task::spawn_blocking(move || client.scrub(&where_filter, req.reason))
+        .await
+        .map_err(|e| VectorDbError::Io(std::io::Error::other(e)))??;
+    Ok(Json(report))
+}
+
+// bloque un thread du pool spawn_blocking pendant toute la durée du test
+async fn load_test(
+    State(client): State<SharedClient>,
+    Json(config): Json<LoadTestConfig>,
+) -> AppResult<Json<LoadTestReport>> {
+    tracing::info!(collection = %config.collection, duration_secs = config.duration_secs, "Starting load test");
+    let report = tokio::task::spawn_blocking(move || run_load_test(&client, config))
+        .await
+        .map_err(|e| VectorDbError::Io(std::io::Error::other(e)))??;
+    Ok(Json(report))
+}
+
+// réponse à la fin du réglage : un index entraîné par valeur de n_clusters essayée
+async fn autotune(
+    State(client): State<SharedClient>,
+    Json(config): Json<AutotuneConfig>,
+) -> AppResult<Json<AutotuneReport>> {
+    tracing::info!(collection = %config.collection, strategy = ?config.strategy, "Starting autotune");
+    let report = tokio::task::spawn_blocking(move || run_autotune(&client, config))
+        .await
+        .map_err(|e| VectorDbError::Io(std::io::Error::other(e)))??;
+    Ok(Json(report))
+}
+
+async fn diff_vectors(
+    State(client): State<SharedClient>,
+    Path(collection_name): Path<String>,
+    Json(req): Json<DiffRequest>,
+) -> AppResult<Json<DiffResult>> {
+    let diff = client.with_collection(&collection_name, |coll| coll.diff(&req.hashes))?;
+    Ok(Json(diff))
+}
+
+async fn delete_vectors(
+    State(client): State<SharedClient>,
+    Path(collection_name): Path<String>,
+    Json(req): Json<DeleteRequest>,
+) -> AppResult<Json<serde_json::Value>> {
+    let count = req.ids.len();
+    client.limits().check_batch_size("delete", count)?;
+    client.with_collection_mut(&collection_name, |coll| coll.delete(req.ids))?;
+    Ok(Json(serde_json::json!({"status": "deleted", "count": count})))
+}
+
+async fn query_vectors(
+    State(client): State<SharedClient>,
+    Path(coll_name): Path<String>,
+    EmbeddingJson(req): EmbeddingJson<QueryRequest>,
+) -> AppResult<Json<serde_json::Value>> {
+    tracing::debug!(
+        collection = %coll_name,
+        n_results = req.n_results,
+        has_filter = req.filter.where_filter.is_some() || req.filter.where_str.is_some(),
+        "Querying vectors"
+    );
+
+    let filter_json = req.filter.json()?;
+    let where_filter = filter_json.as_ref().map(parse_filter).transpose()?;
+    // reranker résolu avant la recherche : un nom inconnu échoue sans la coûter
+    let rerank = match req.rerank {
+        Some(_) if req.compare => {
+            return Err(VectorDbError::InvalidConfig("rerank cannot be combined with compare".into()).into());
+        }
+        Some(ref rerank) => {
+            let query = rerank.query.clone().or_else(|| req.query_text.clone()).ok_or_else(|| {
+                VectorDbError::InvalidConfig("rerank requires a query or query_text".into())
+            })?;
+            let candidates = rerank.candidates.unwrap_or(req.n_results * DEFAULT_CANDIDATES_FACTOR);
+            Some((client.reranker(&rerank.reranker)?, query, candidates.max(req.n_results)))
+        }
+        None => None,
+    };
+    let n_candidates = rerank.as_ref().map_or(req.n_results, |(_, _, candidates)| *candidates);
+    if req.query_tokens.is_some() && req.compare {
+        return Err(VectorDbError::InvalidConfig("query_tokens cannot be combined with compare".into()).into());
+    }
+    if req.aggregation.is_some() && (req.query_tokens.is_some() || req.compare) {
+        return Err(VectorDbError::InvalidConfig("aggregation cannot be combined with query_tokens or compare".into()).into());
+    }
+
+    let max_distance = match (req.score_threshold, req.max_distance) {
+        (Some(_), Some(_)) => {
+            return Err(VectorDbError::InvalidConfig("use either score_threshold or max_distance, not both".into()).into());
+        }
+        (Some(threshold), None) => Some(1.0 - threshold),
+        (None, max_distance) => max_distance,
+    };
+    let options = QueryOptions {
+        exact: req.exact,
+        explain_hits: req.explain_hits,
+        direction: req.direction,
+        sort_by: req.sort_by,
+        max_distance,
+    };
+
+    if let Some(query_embeddings) = req.query_embeddings {
+        let single = !req.query_embedding.is_empty()
+            || req.query_text.is_some()
+            || req.query_tokens.is_some()
+            || req.query_binary.is_some();
+        if single || req.compare || rerank.is_some() || req.aggregation.is_some() || req.total_matched.is_some() {
+            return Err(VectorDbError::InvalidConfig(
+                "query_embeddings cannot be combined with another query, compare, rerank, aggregation or total_matched"
+                    .into(),
+            )
+            .into());
+        }
+        client.limits().check_query_batch(query_embeddings.len())?;
+        let (results, elapsed) = client.with_collection_mut(&coll_name, |coll| {
+            coll.check_embedding_model(req.embedding_model.as_deref())?;
+            let start = std::time::Instant::now();
+            let results = coll.query_batch(&query_embeddings, req.n_results, where_filter.as_ref(), &options)?;
+            Ok((results, start.elapsed()))
+        })?;
+        let elapsed = elapsed / query_embeddings.len().max(1) as u32;
+        for query_embedding in &query_embeddings {
+            client.log_query(&coll_name, query_embedding, filter_json.as_ref(), req.n_results, req.exact, elapsed);
+        }
+        return Ok(Json(serde_json::to_value(&results).unwrap()));
+    }
+
+    // collection en mode snapshot : requête simple servie par l'instantané publié, sans
+    // verrou ; les autres formes de requête passent par la collection
+    let plain = !req.compare
+        && !req.explain_hits
+        && req.query_tokens.is_none()
+        && req.aggregation.is_none()
+        && req.query_binary.is_none()
+        && req.query_text.is_none()
+        && req.total_matched.is_none();
+    let snapshot = if plain {
+        let start = std::time::Instant::now();
+        client
+            .query_snapshot(
+                &coll_name,
+                &req.query_embedding,
+                n_candidates,
+                where_filter.as_ref(),
+                &options,
+                req.embedding_model.as_deref(),
+            )?
+            .map(|results| (results, start.elapsed()))
+    } else {
+        None
+    };
+    let (results, comparison, matched, explanation, rebuild_due, use_ivf, query_embedding, elapsed) = match snapshot {
+        Some((results, elapsed)) => (results, None, None, None, false, false, req.query_embedding, elapsed),
+        None => client.with_collection_mut(&coll_name, |coll| {
+            coll.check_embedding_model(req.embedding_model.as_deref())?;
+            let query_embedding = match (&req.query_tokens, &req.query_text) {
+                _ if req.query_binary.is_some() => unpack_binary(coll, req.query_binary.unwrap())?,
+                // journal et rappel : la moyenne des tokens, embedding du premier étage
+                (Some(tokens), _) => pool(tokens),
+                (None, Some(text)) if req.query_embedding.is_empty() => {
+                    embed_query(&client, coll, text)?
+                }
+                _ => req.query_embedding,
+            };
+            // durée de la recherche seule, comparable à celle mesurée par vectordb_replay
+            let start = std::time::Instant::now();
+            let (results, comparison, explanation, elapsed) = if req.compare {
+                let comparison =
+                    coll.compare_query(&query_embedding, req.n_results, where_filter.as_ref(), &options)?;
+                let elapsed = std::time::Duration::from_secs_f64(comparison.index_ms / 1000.0);
+                (Vec::new(), Some(comparison), None, elapsed)
+            } else if let Some(ref tokens) = req.query_tokens {
+                let rescore = req.rescore_candidates.unwrap_or(n_candidates * DEFAULT_RESCORE_FACTOR);
+                let results = coll.query_tokens(tokens, n_candidates, rescore, where_filter.as_ref(), &options)?;
+                (results, None, None, start.elapsed())
+            } else if let Some(aggregation) = req.aggregation {
+                let rescore = req.rescore_candidates.unwrap_or(n_candidates * DEFAULT_RESCORE_FACTOR);
+                let results = coll.query_multi_vector(
+                    &query_embedding,
+                    n_candidates,
+                    rescore,
+                    aggregation,
+                    where_filter.as_ref(),
+                    &options,
+                )?;
+                (results, None, None, start.elapsed())
+            } else {
+                let results =
+                    coll.query_with_options(&query_embedding, n_candidates, where_filter.as_ref(), &options)?;
+                let elapsed = start.elapsed();
+                // après la requête : plan suivi, index éventuellement reconstruit compris
+                let explanation = req
+                    .explain_hits
+                    .then(|| coll.explain_query(n_candidates, where_filter.as_ref(), &options));
+                (results, None, explanation, elapsed)
+            };
+            let matched = req.total_matched.map(|mode| coll.total_matched(where_filter.as_ref(), mode));
+            Ok((
+                results,
+                comparison,
+                matched,
+                explanation,
+                coll.rebuild_due(),
+                coll.config.use_ivf,
+                query_embedding,
+                elapsed,
+            ))
+        })?,
+    };
+    client.log_query(
+        &coll_name,
+        &query_embedding,
+        filter_json.as_ref(),
+        req.n_results,
+        req.exact,
+        elapsed,
+    );
+
+    // vérification de rappel échantillonnée (VECTORDB_RECALL_SAMPLE_RATE), hors requête
+    if use_ivf
+        && !req.exact
+        && comparison.is_none()
+        && req.query_tokens.is_none()
+        && req.aggregation.is_none()
+        && client.sample_recall()
+    {
+        let task_client = client.clone();
+        let task_name = coll_name.clone();
+        let (task_embedding, task_filter, task_results) = (query_embedding, where_filter, results.clone());
+        tokio::task::spawn_blocking(move || {
+            let checked = task_client.check_recall(
+                &task_name,
+                &task_embedding,
+                req.n_results,
+                task_filter.as_ref(),
+                &options,
+                &task_results,
+            );
+            if let Err(e) = checked {
+                tracing::debug!(collection = %task_name, error = %e, "Recall check failed");
+            }
+        });
+    }
+
+    // seuil de modifications atteint : rebuild en arrière-plan, la requête
+    // a été servie en exhaustif par le planificateur (index périmé)
+    if rebuild_due {
+        let task_client = client.clone();
+        let task_name = coll_name.clone();
+        tokio::task::spawn_blocking(move || {
+            match task_client.rebuild_index_background(&task_name) {
+                Ok(_) | Err(VectorDbError::RebuildInProgress(_)) => {}
+                Err(e) => tracing::warn!(collection = %task_name, error = %e, "Background rebuild failed"),
+            }
+        });
+    }
+
+    // reranking hors verrou de collection : un appel distant ne bloque pas les écritures
+    let results = match rerank {
+        Some((reranker, query, _)) => {
+            let n_results = req.n_results;
+            tokio::task::spawn_blocking(move || rerank::rerank(reranker.as_ref(), &query, results, n_results))
+                .await
+                .map_err(|e| VectorDbError::Io(std::io::Error::other(e)))??
+        }
+        None => results,
+    };
+
+    tracing::debug!(
+        collection = %coll_name,
+        results_count = results.len(),
+        "Query completed"
+    );
+
+    if let Some(comparison) = comparison {
+        return Ok(Json(serde_json::to_value(&comparison).unwrap()));
+    }
+    if matched.is_none() && explanation.is_none() {
+        return Ok(Json(serde_json::to_value(&results).unwrap()));
+    }
+    let mut response = serde_json::json!({ "results": results });
+    if let Some(matched) = matched {
+        response["total_matched"] = matched.total_matched.into();
+        response["total_exact"] = matched.exact.into();
+    }
+    if let Some(explanation) = explanation {
+        response["plan"] = serde_json::to_value(&explanation.plan).unwrap();
+        response["filter_stage"] = serde_json::to_value(explanation.filter_stage).unwrap();
+    }
+    Ok(Json(response))
+}
+
+// distances pour des candidats fournis par le client, sans ANN
+// comparaison de deux collections, ou d'une collection et de son état dans un instantané
+async fn diff_collections(
+    State(client): State<SharedClient>,
+    Json(req): Json<CollectionDiffRequest>,
+) -> AppResult<Json<serde_json::Value>> {
+    let options = DiffOptions {
+        tolerance: req.tolerance.unwrap_or(DEFAULT_DIFF_TOLERANCE),
+        max_ids: req.max_ids.unwrap_or(DEFAULT_DIFF_MAX_IDS),
+    };
+    let diff = tokio::task::spawn_blocking(move || {
+        let left = diff_source(&client, req.left)?;
+        let right = diff_source(&client, req.right)?;
+        client.diff_collections(&left, &right, &options)
+    })
+    .await
+    .map_err(|e| VectorDbError::Io(std::io::Error::other(e)))??;
+    Ok(Json(serde_json::to_value(&diff).unwrap()))
+}
+
+// instantané désigné par son nom, jamais par un chemin arbitraire
+fn diff_source(client: &VectorDbClient, side: CollectionDiffSide) -> Result<DiffSource, VectorDbError> {
+    let Some(name) = side.snapshot else {
+        return Ok(DiffSource::collection(&side.collection));
+    };
+    let snapshot = client
+        .list_snapshots()?
+        .into_iter()
+        .find(|s| s.name == name)
+        .ok_or_else(|| VectorDbError::InvalidConfig(format!("unknown snapshot '{}'", name)))?;
+    Ok(DiffSource::snapshot(snapshot.path, &side.collection))
+}
+
+// vecteurs dans un rayon plutôt qu'un top-k : {"results": [...], "count": n, "truncated": bool}
+async fn range_query(
+    State(client): State<SharedClient>,
+    Path(coll_name): Path<String>,
+    EmbeddingJson(req): EmbeddingJson<RangeQueryRequest>,
+) -> AppResult<Json<serde_json::Value>> {
+    let max_distance = match (req.max_distance, req.score_threshold) {
+        (Some(max_distance), None) => max_distance,
+        (None, Some(threshold)) => 1.0 - threshold,
+        _ => {
+            return Err(VectorDbError::InvalidConfig("range_query requires either max_distance or score_threshold".into())
+                .into());
+        }
+    };
+    let limits = client.limits();
+    let max_results = match req.max_results {
+        Some(max_results) => {
+            limits.check_range_results(max_results)?;
+            max_results
+        }
+        None if limits.max_range_results > 0 => limits.max_range_results,
+        None => usize::MAX,
+    };
+    let where_filter = req.filter.parse()?;
+
+    let range = client.with_collection_mut(&coll_name, |coll| {
+        coll.check_embedding_model(req.embedding_model.as_deref())?;
+        let query_embedding = match req.query_text {
+            Some(ref text) if req.query_embedding.is_empty() => embed_query(&client, coll, text)?,
+            _ => req.query_embedding.clone(),
+        };
+        coll.range_search(&query_embedding, max_distance, where_filter.as_ref(), max_results)
+    })?;
+    Ok(Json(serde_json::json!({
+        "count": range.results.len(),
+        "truncated": range.truncated,
+        "results": range.results,
+    })))
+}
+
+async fn score_ids(
+    State(client): State<SharedClient>,
+    Path(coll_name): Path<String>,
+    EmbeddingJson(req): EmbeddingJson<ScoreIdsRequest>,
+) -> AppResult<Json<serde_json::Value>> {
+    let results = client.with_collection(&coll_name, |coll| {
+        coll.check_embedding_model(req.embedding_model.as_deref())?;
+        let query_embedding = match req.query_text {
+            Some(ref text) if req.query_embedding.is_empty() => {
+                embed_query(&client, coll, text)?
+            }
+            _ => req.query_embedding.clone(),
+        };
+        coll.score_ids(&query_embedding, &req.ids)
+    })??;
+    Ok(Json(serde_json::to_value(&results).unwrap()))
+}
+
+// --standby <répertoire> ou --standby=<répertoire>
+fn standby_arg() -> Option<String> {
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        if arg == "--standby" {
+            return args.next();
+        }
+        if let Some(dir) = arg.strip_prefix("--standby=") {
+            return Some(dir.to_string());
+        }
+    }
+    None
+}
+
+// version d'API de chaque requête (préfixe /api/v{n}, sinon en-tête), exposée aux
+// handlers via Extension<ApiVersion> ; chemins sans préfixe et versions dépréciées
+// signalés par Deprecation (RFC 9745) / Sunset / Link (RFC 8594)
+async fn negotiate_api_version(mut request: Request, next: Next) -> Response {
+    let requested = request.headers().get(API_VERSION_HEADER).and_then(|v| v.to_str().ok());
+    let negotiated = match api_version::negotiate(request.uri().path(), requested) {
+        Ok(negotiated) => negotiated,
+        Err(e) => return AppError(e).into_response(),
+    };
+    request.extensions_mut().insert(negotiated.version);
+
+    let mut response = next.run(request).await;
+    let headers = response.headers_mut();
+    headers.insert(API_VERSION_HEADER, HeaderValue::from(negotiated.version.0));
+    if let Some(deprecation) = negotiated.deprecation {
+        if let Ok(since) = HeaderValue::from_str(&format!("@{}", deprecation.since)) {
+            headers.insert("deprecation", since);
+        }
+        if let Some(sunset) = deprecation.sunset {
+            headers.insert("sunset", HeaderValue::from_static(sunset));
+        }
+        if let Ok(link) = HeaderValue::from_str(&format!("<{}>; rel=\"successor-version\"", deprecation.successor)) {
+            headers.insert("link", link);
+        }
+    }
+    response
+}
+
+// X-Priority: batch : requête exécutée dans la voie batch, après les imports déjà en cours
+async fn route_priority(request: Request, next: Next) -> Response {
+    let priority = match request.headers().get(PRIORITY_HEADER) {
+        Some(value) => match value.to_str().ok().and_then(Priority::parse) {
+            Some(priority) => priority,
+            None => {
+                return AppError(VectorDbError::InvalidConfig(format!(
+                    "invalid {} header: expected interactive or batch",
+                    PRIORITY_HEADER
+                )))
+                .into_response();
+            }
+        },
+        None => Priority::Interactive,
+    };
+    match PRIORITY_LANES.get() {
+        Some(lanes) => lanes.run(priority, next.run(request)).await,
+        None => next.run(request).await,
+    }
+}
+
+async fn require_admin_token(request: Request, next: Next) -> Response {
+    let Some(expected) = ADMIN_TOKEN.get().and_then(Option::as_deref) else {
+        return AppError(VectorDbError::Unauthorized(
+            "admin routes are disabled, set VECTORDB_ADMIN_TOKEN to enable them".to_string(),
+        ))
+        .into_response();
+    };
+    let provided = request
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "));
+    if !provided.is_some_and(|token| token_matches(token, expected)) {
+        return AppError(VectorDbError::Unauthorized("missing or invalid admin token".to_string())).into_response();
+    }
+    next.run(request).await
+}
+
+// comparaison en temps constant, pour ne pas révéler le préfixe commun
+fn token_matches(provided: &str, expected: &str) -> bool {
+    provided.len() == expected.len()
+        && provided.bytes().zip(expected.bytes()).fold(0, |diff, (a, b)| diff | (a ^ b)) == 0
+}
+
+async fn lane_stats() -> Json<serde_json::Value> {
+    Json(serde_json::to_value(PRIORITY_LANES.get().map(PriorityLanes::metrics)).unwrap())
+}
+
+#[tokio::main]
+async fn main() {
+    // initialiser tracing
+    use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
+    tracing_subscriber::registry()
+        .with(
+            tracing_subscriber::EnvFilter::try_from_default_env()
+                .unwrap_or_else(|_| "vectordb_rust=info,tower_http=info".into()),
         )
         .with(tracing_subscriber::fmt::layer())
         .init();
@@ -343,22 +1951,345 @@ async fn main() {
         .and_then(|p| p.parse().ok())
         .unwrap_or(8080);
 
-    let client = Arc::new(VectorDbClient::new(&db_path).expect("Failed to create client"));
-    tracing::info!("VectorDB client initialized at {}", db_path);
+    let env_flag = |key: &str, default: bool| {
+        std::env::var(key)
+            .map(|v| v != "false" && v != "0")
+            .unwrap_or(default)
+    };
+
+    // secours : restaure en continu le dernier instantané expédié par le serveur
+    // principal dans VECTORDB_PATH et sert ce répertoire en lecture seule
+    let mut standby = standby_arg().or_else(|| std::env::var("VECTORDB_STANDBY").ok()).map(|source| {
+        let mut standby = Standby::new(&source, &db_path).expect("Failed to open standby directory");
+        match standby.poll() {
+            Ok(Some(report)) => tracing::info!(snapshot = %report.snapshot, "Restored standby snapshot"),
+            Ok(None) => tracing::warn!("No snapshot shipped to {} yet", source),
+            Err(e) => tracing::error!("Standby restore failed: {}", e),
+        }
+        standby
+    });
+
+    // lecture seule : processus secondaire à côté d'un serveur qui détient l'écriture
+    let read_only = standby.is_some() || env_flag("VECTORDB_READ_ONLY", false);
+    let mut client = if read_only {
+        VectorDbClient::open_read_only(&db_path)
+    } else {
+        VectorDbClient::new(&db_path)
+    }
+    .expect("Failed to create client");
+    client.set_deferred_rebuild(true);
+    // strict : embeddings refusés plutôt que convertis avec perte (entiers, hors plage f32, NaN)
+    if let Ok(value) = std::env::var("VECTORDB_NUMBER_HANDLING") {
+        let handling = NumberHandling::parse(&value).expect("Invalid VECTORDB_NUMBER_HANDLING");
+        NUMBER_HANDLING.set(handling).expect("number handling is set once");
+    }
+    // curseurs de pagination valables d'un redémarrage et d'une instance à l'autre
+    if let Ok(secret) = std::env::var("VECTORDB_CURSOR_KEY") {
+        client.set_cursor_key(CursorKey::from_secret(&secret));
+    }
+    let env_number = |key: &str, default: u64| {
+        std::env::var(key).ok().and_then(|v| v.parse().ok()).unwrap_or(default)
+    };
+    // voie batch : imports et ajouts en masse hors des threads des requêtes interactives
+    let lanes = PriorityLanes::new(
+        env_number("VECTORDB_BATCH_CONCURRENCY", DEFAULT_BATCH_CONCURRENCY as u64) as usize,
+        env_number("VECTORDB_BATCH_THREADS", DEFAULT_BATCH_THREADS as u64) as usize,
+    )
+    .expect("Failed to start the batch lane runtime");
+    PRIORITY_LANES.set(lanes).expect("priority lanes are set once");
+    let admin_token = std::env::var("VECTORDB_ADMIN_TOKEN").ok().filter(|token| !token.is_empty());
+    if admin_token.is_none() {
+        tracing::info!("Admin routes disabled, set VECTORDB_ADMIN_TOKEN to enable them");
+    }
+    ADMIN_TOKEN.set(admin_token).expect("admin token is set once");
+    // part des requêtes IVF rejouées en exhaustif pour le rappel de /debug/collections
+    if let Some(rate) = std::env::var("VECTORDB_RECALL_SAMPLE_RATE").ok().and_then(|v| v.parse::<f64>().ok()) {
+        client.set_recall_sample_rate(rate);
+    }
+    // listes IVF sur disque les plus sondées gardées en mémoire, par collection
+    client.set_list_cache_bytes(env_number("VECTORDB_LIST_CACHE_BYTES", 0) as usize);
+    // profils de collection nommés, fichier JSON {"nom": {"dimension": .., ...}}
+    if let Ok(path) = std::env::var("VECTORDB_PROFILES") {
+        let profiles = ProfileRegistry::from_json_file(&path).expect("Failed to read collection profiles");
+        for (name, profile) in profiles {
+            client
+                .register_profile(name.clone(), profile)
+                .unwrap_or_else(|e| panic!("Invalid collection profile '{}': {}", name, e));
+        }
+        tracing::info!("Loaded collection profiles from {}", path);
+    }
+    // journal des requêtes pour vectordb_replay ; embeddings complets avec
+    // VECTORDB_QUERY_LOG_EMBEDDINGS=full (empreinte seule par défaut)
+    if let Ok(path) = std::env::var("VECTORDB_QUERY_LOG") {
+        let mode = std::env::var("VECTORDB_QUERY_LOG_EMBEDDINGS").unwrap_or("hash".into());
+        let mode = QueryLogMode::parse(&mode).expect("Invalid VECTORDB_QUERY_LOG_EMBEDDINGS");
+        client.set_query_log(QueryLog::open(&path, mode).expect("Failed to open query log"));
+        tracing::info!(mode = ?mode, "Logging queries to {}", path);
+    }
+    // ids supprimés gardés pour écarter les ajouts répliqués en retard
+    let tombstone_defaults = TombstonePolicy::default();
+    client.set_tombstone_policy(TombstonePolicy {
+        retention: std::time::Duration::from_secs(env_number(
+            "VECTORDB_TOMBSTONE_RETENTION_SECS",
+            tombstone_defaults.retention.as_secs(),
+        )),
+        max_tombstones: env_number("VECTORDB_MAX_TOMBSTONES", tombstone_defaults.max_tombstones as u64) as usize,
+    });
+    let tombstone_purge_interval = env_number("VECTORDB_TOMBSTONE_PURGE_INTERVAL_SECS", 3600);
+    // memtable vidée en segment au-delà de ce nombre de vecteurs (0 = vidage manuel)
+    client.set_memtable_max_vectors(env_number("VECTORDB_MEMTABLE_MAX_VECTORS", 0) as usize);
+    let segment_merge_interval = env_number("VECTORDB_SEGMENT_MERGE_INTERVAL_SECS", 30);
+    // embeddings des query_text en cache (0 entrée = désactivé)
+    client.set_embedding_cache(EmbeddingCache::new(
+        env_number("VECTORDB_EMBEDDING_CACHE_SIZE", DEFAULT_EMBEDDING_CACHE_ENTRIES as u64) as usize,
+        std::time::Duration::from_secs(env_number("VECTORDB_EMBEDDING_CACHE_TTL_SECS", DEFAULT_EMBEDDING_CACHE_TTL.as_secs())),
+    ));
+    // service de reranking HTTP utilisé par défaut ("rerank" des requêtes)
+    if let Ok(url) = std::env::var("VECTORDB_RERANKER_URL") {
+        let timeout = std::time::Duration::from_millis(env_number("VECTORDB_RERANKER_TIMEOUT_MS", 10_000));
+        let reranker = HttpReranker::new(&url).expect("Invalid VECTORDB_RERANKER_URL").with_timeout(timeout);
+        client.register_reranker(DEFAULT_RERANKER.to_string(), Arc::new(reranker));
+        tracing::info!("Reranking with {}", url);
+    }
+    // instantanés expédiés vers un répertoire secondaire pour un serveur --standby
+    if let Ok(dir) = std::env::var("VECTORDB_SNAPSHOT_DIR") {
+        let shipper = SnapshotShipper::new(&dir, env_number("VECTORDB_SNAPSHOT_KEEP", 3) as usize)
+            .with_daily_retention(env_number("VECTORDB_SNAPSHOT_KEEP_DAILY_DAYS", 7) as u32);
+        let retention = shipper.retention();
+        client.set_snapshot_shipper(shipper);
+        tracing::info!(
+            keep_last = retention.keep_last,
+            keep_daily_days = retention.keep_daily_days,
+            "Shipping snapshots to {}",
+            dir
+        );
+    }
+    let snapshot_interval = env_number("VECTORDB_SNAPSHOT_INTERVAL_SECS", 3600);
+    let snapshot_prune_interval = env_number("VECTORDB_SNAPSHOT_PRUNE_INTERVAL_SECS", 3600);
+    let stats_interval = env_number("VECTORDB_STATS_INTERVAL_SECS", 60);
+    client.set_stats_history_len(env_number("VECTORDB_STATS_HISTORY_LEN", DEFAULT_STATS_HISTORY_LEN as u64) as usize);
+    let client = Arc::new(client);
+    tracing::info!(read_only = read_only, "VectorDB client initialized at {}", db_path);
+
+    // mise à niveau des anciens répertoires au démarrage (VECTORDB_AUTO_MIGRATE=false pour désactiver)
+    if !read_only && env_flag("VECTORDB_AUTO_MIGRATE", true) {
+        match client.migrate_storage(false) {
+            Ok(reports) if !reports.is_empty() => {
+                tracing::info!("Migrated {} collection(s) to the current storage format", reports.len());
+            }
+            Ok(_) => {}
+            Err(e) => tracing::error!("Storage migration failed: {}", e),
+        }
+    }
+
+    // instantanés périodiques pour /collections/:name/stats/history (0 pour désactiver)
+    if stats_interval > 0 {
+        let stats_client = client.clone();
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(std::time::Duration::from_secs(stats_interval));
+            ticker.tick().await;
+            loop {
+                ticker.tick().await;
+                let task_client = stats_client.clone();
+                let _ = tokio::task::spawn_blocking(move || task_client.snapshot_stats()).await;
+            }
+        });
+    }
+
+    // purge périodique des pierres tombales échues (0 pour POST /admin/tombstones/purge seul)
+    if tombstone_purge_interval > 0 && !read_only {
+        let purge_client = client.clone();
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(std::time::Duration::from_secs(tombstone_purge_interval));
+            ticker.tick().await;
+            loop {
+                ticker.tick().await;
+                let task_client = purge_client.clone();
+                match tokio::task::spawn_blocking(move || task_client.purge_tombstones()).await {
+                    Ok(Ok(purged)) if !purged.is_empty() => tracing::info!(?purged, "Purged tombstones"),
+                    Ok(Ok(_)) => {}
+                    Ok(Err(e)) => tracing::error!("Tombstone purge failed: {}", e),
+                    Err(e) => tracing::error!("Tombstone purge task failed: {}", e),
+                }
+            }
+        });
+    }
+
+    // vidage des memtables pleines et fusion des petits segments (0 pour désactiver)
+    if segment_merge_interval > 0 && !read_only {
+        let compact_client = client.clone();
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(std::time::Duration::from_secs(segment_merge_interval));
+            ticker.tick().await;
+            loop {
+                ticker.tick().await;
+                let task_client = compact_client.clone();
+                match tokio::task::spawn_blocking(move || task_client.compact_segments()).await {
+                    Ok(Ok(written)) if !written.is_empty() => tracing::info!(?written, "Compacted segments"),
+                    Ok(Ok(_)) => {}
+                    Ok(Err(e)) => tracing::error!("Segment compaction failed: {}", e),
+                    Err(e) => tracing::error!("Segment compaction task failed: {}", e),
+                }
+            }
+        });
+    }
+
+    // expédition périodique (0 pour n'expédier que via POST /admin/snapshots)
+    if snapshot_interval > 0 && client.list_snapshots().is_ok() {
+        let snapshot_client = client.clone();
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(std::time::Duration::from_secs(snapshot_interval));
+            ticker.tick().await;
+            loop {
+                ticker.tick().await;
+                let task_client = snapshot_client.clone();
+                match tokio::task::spawn_blocking(move || task_client.ship_snapshot()).await {
+                    Ok(Ok(shipped)) => tracing::info!(
+                        snapshot = %shipped.snapshot.name,
+                        collections = shipped.collections,
+                        bytes = shipped.snapshot.bytes,
+                        "Shipped snapshot"
+                    ),
+                    Ok(Err(e)) => tracing::error!("Snapshot shipping failed: {}", e),
+                    Err(e) => tracing::error!("Snapshot shipping task failed: {}", e),
+                }
+            }
+        });
+    }
+
+    // purge périodique des instantanés sortis de la rétention quotidienne, même
+    // sans nouvelle expédition (0 pour POST /admin/snapshots/prune seul)
+    if snapshot_prune_interval > 0 && client.list_snapshots().is_ok() {
+        let prune_client = client.clone();
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(std::time::Duration::from_secs(snapshot_prune_interval));
+            loop {
+                ticker.tick().await;
+                let task_client = prune_client.clone();
+                match tokio::task::spawn_blocking(move || task_client.prune_snapshots(false)).await {
+                    Ok(Ok(report)) if !report.pruned.is_empty() => tracing::info!(
+                        kept = report.kept.len(),
+                        pruned = report.pruned.len(),
+                        freed_bytes = report.freed_bytes,
+                        "Pruned snapshots"
+                    ),
+                    Ok(Ok(_)) => {}
+                    Ok(Err(e)) => tracing::error!("Snapshot pruning failed: {}", e),
+                    Err(e) => tracing::error!("Snapshot pruning task failed: {}", e),
+                }
+            }
+        });
+    }
 
-    let app = Router::new()
+    // secours : nouvel instantané restauré toutes les VECTORDB_STANDBY_POLL_SECS
+    if let Some(mut standby) = standby.take() {
+        let poll_interval = env_number("VECTORDB_STANDBY_POLL_SECS", 10).max(1);
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(std::time::Duration::from_secs(poll_interval));
+            ticker.tick().await;
+            loop {
+                ticker.tick().await;
+                let (returned, result) = match tokio::task::spawn_blocking(move || {
+                    let result = standby.poll();
+                    (standby, result)
+                })
+                .await
+                {
+                    Ok(polled) => polled,
+                    Err(e) => {
+                        tracing::error!("Standby restore task failed: {}", e);
+                        break;
+                    }
+                };
+                standby = returned;
+                match result {
+                    Ok(Some(report)) => tracing::info!(
+                        snapshot = %report.snapshot,
+                        collections = report.restored.len(),
+                        removed = report.removed.len(),
+                        "Restored standby snapshot"
+                    ),
+                    Ok(None) => {}
+                    Err(e) => tracing::error!("Standby restore failed: {}", e),
+                }
+            }
+        });
+    }
+
+    let admin = Router::new()
+        .route("/admin/cache", get(cache_stats))
+        .route("/admin/cache/clear", post(clear_cache))
+        .route("/admin/lanes", get(lane_stats))
+        .route("/admin/embedding_cache", get(embedding_cache_stats))
+        .route("/admin/embedding_cache/flush", post(flush_embedding_cache))
+        .route("/admin/migrate", post(migrate_storage))
+        .route("/admin/scan", post(scan_storage))
+        .route("/admin/scrub", post(scrub))
+        .route("/admin/snapshots", get(list_snapshots).post(ship_snapshot))
+        .route("/admin/snapshots/prune", post(prune_snapshots))
+        .route("/admin/tombstones/purge", post(purge_tombstones))
+        .route("/admin/loadtest", post(load_test))
+        .route("/admin/autotune", post(autotune))
+        .route("/admin/import/faiss", post(import_faiss))
+        .route("/admin/diff", post(diff_collections))
+        .route("/admin/collections/:name/verify_index", get(verify_index).post(repair_index))
+        .route("/admin/collections/:name/verify_norms", get(verify_norms).post(renormalize));
+
+    #[cfg(feature = "chroma-import")]
+    let admin = admin.route("/admin/import/chroma", post(import_chroma));
+    let admin = admin.route_layer(middleware::from_fn(require_admin_token));
+
+    let api = Router::new()
         .route("/health", get(health_check))
+        .route("/get_multi", post(get_multi))
+        .route("/profiles", get(list_profiles))
+        .route("/debug/collections", get(debug_collections))
         .route("/collections", post(create_collection).get(list_collections))
-        .route("/collections/:name", get(get_collection).delete(delete_collection))
+        .route(
+            "/collections/:name",
+            get(get_collection).delete(delete_collection).patch(update_collection),
+        )
         .route("/collections/:name/stats", get(get_collection_stats))
+        .route("/collections/:name/stats/history", get(get_stats_history))
+        .route("/collections/:name/index", patch(update_index_params))
+        .route("/collections/:name/index/assign", post(assign_index))
+        .route("/collections/:name/index/offload", post(offload_index))
+        .route("/collections/:name/index/status", get(get_index_status))
+        .route("/collections/:name/metadata/stats", get(get_metadata_stats))
         .route("/collections/:name/batch/begin", post(begin_batch))
         .route("/collections/:name/batch/end", post(end_batch))
         .route("/collections/:name/rebuild", post(rebuild_index))
+        .route("/collections/:name/reload", post(reload_collection))
+        .route("/collections/:name/freeze", post(freeze_collection))
+        .route("/collections/:name/thaw", post(thaw_collection))
+        .route("/collections/:name/segments", get(verify_segments))
+        .route("/collections/:name/segments/flush", post(flush_collection))
+        .route("/collections/:name/segments/merge", post(merge_segments))
+        .route("/collections/:name/export/faiss", post(export_faiss))
         .route("/collections/:name/add", post(add_vectors))
+        .route("/collections/:name/upsert", post(upsert_vectors))
         .route("/collections/:name/get", post(get_vectors))
+        .route("/collections/:name/vectors/:id", get(get_vector))
+        .route("/collections/:name/count", post(count_vectors).get(count_vectors_get))
         .route("/collections/:name/update", put(update_vectors))
+        .route("/collections/:name/update_where", post(update_vectors_where))
+        .route("/collections/:name/diff", post(diff_vectors))
         .route("/collections/:name/delete", delete(delete_vectors))
+        .route("/collections/:name/delete_where", post(delete_vectors_where))
         .route("/collections/:name/query", post(query_vectors))
+        .route("/collections/:name/range_query", post(range_query))
+        .route("/collections/:name/score_ids", post(score_ids))
+        .merge(admin);
+
+    // /api/v{n}/... pour chaque version servie, anciens chemins sans préfixe en alias
+    let mut app = Router::new();
+    for &version in api_version::SUPPORTED_API_VERSIONS {
+        app = app.nest(&api_version::prefix(version), api.clone());
+    }
+    let app = app
+        .merge(api)
+        .layer(middleware::from_fn(route_priority))
+        .layer(middleware::from_fn(negotiate_api_version))
         .layer(CorsLayer::permissive())
         .with_state(client);
 
@@ -383,3 +2314,61 @@ async fn main() {
 
     axum::serve(listener, app).await.unwrap();
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn headers(pairs: &[(header::HeaderName, &str)]) -> HeaderMap {
+        pairs.iter().map(|(name, value)| (name.clone(), HeaderValue::from_str(value).unwrap())).collect()
+    }
+
+    #[test]
+    fn test_token_matches() {
+        assert!(token_matches("s3cret-token", "s3cret-token"));
+        assert!(!token_matches("s3cret-tokem", "s3cret-token"));
+        assert!(!token_matches("s3cret", "s3cret-token"));
+        assert!(!token_matches("", "s3cret-token"));
+    }
+
+    #[test]
+    fn test_vector_etag_depends_on_include() {
+        let parts = |list: &[&str]| list.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+        let full = vector_etag(0x1f, None);
+        assert_eq!(full, vector_etag(0x1f, Some(&parts(&["metadatas", "embeddings"]))));
+        assert_eq!(full, vector_etag(0x1f, Some(&parts(&["embeddings", "metadatas", "embeddings"]))));
+        let embeddings = vector_etag(0x1f, Some(&parts(&["embeddings"])));
+        let metadatas = vector_etag(0x1f, Some(&parts(&["metadatas"])));
+        assert!(embeddings != metadatas && embeddings != full && metadatas != full);
+        assert_ne!(full, vector_etag(0x20, None));
+        assert!(HeaderValue::from_str(&full).is_ok());
+    }
+
+    #[test]
+    fn test_not_modified() {
+        let etag = "\"1f-embeddings+metadatas\"";
+        // 2024-01-02 03:04:05.5 UTC
+        let modified = 1_704_164_645_500_000;
+        assert!(!not_modified(&HeaderMap::new(), etag, Some(modified)));
+
+        assert!(not_modified(&headers(&[(header::IF_NONE_MATCH, etag)]), etag, None));
+        assert!(not_modified(&headers(&[(header::IF_NONE_MATCH, "\"x\", W/\"1f-embeddings+metadatas\"")]), etag, None));
+        assert!(not_modified(&headers(&[(header::IF_NONE_MATCH, "*")]), etag, None));
+        assert!(!not_modified(&headers(&[(header::IF_NONE_MATCH, "\"1f-metadatas\"")]), etag, None));
+
+        let since = |date: &str| headers(&[(header::IF_MODIFIED_SINCE, date)]);
+        assert!(not_modified(&since("Tue, 02 Jan 2024 03:04:05 GMT"), etag, Some(modified)));
+        assert!(not_modified(&since("Wed, 03 Jan 2024 00:00:00 GMT"), etag, Some(modified)));
+        assert!(!not_modified(&since("Tue, 02 Jan 2024 03:04:04 GMT"), etag, Some(modified)));
+        assert!(!not_modified(&since("not a date"), etag, Some(modified)));
+        assert!(!not_modified(&since("Tue, 02 Jan 2024 03:04:05 GMT"), etag, None));
+        assert_eq!(http_date(modified).as_deref(), Some("Tue, 02 Jan 2024 03:04:05 GMT"));
+
+        // If-None-Match prioritaire sur If-Modified-Since
+        let both = headers(&[
+            (header::IF_NONE_MATCH, "\"other\""),
+            (header::IF_MODIFIED_SINCE, "Wed, 03 Jan 2024 00:00:00 GMT"),
+        ]);
+        assert!(!not_modified(&both, etag, Some(modified)));
+    }
+}
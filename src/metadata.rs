@@ -0,0 +1,177 @@
+use crate::error::{Result, VectorDbError};
+use crate::vector::{is_reserved_key, MetadataValue};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MetadataLimits {
+    pub max_keys: usize,
+    pub max_string_len: usize,
+    pub max_total_bytes: usize,
+}
+
+impl Default for MetadataLimits {
+    fn default() -> Self {
+        Self {
+            max_keys: 64,
+            max_string_len: 4096,
+            max_total_bytes: 64 * 1024,
+        }
+    }
+}
+
+impl MetadataLimits {
+    // les clés _system ne comptent pas dans les limites utilisateur
+    pub fn check(&self, id: &str, metadata: &HashMap<String, MetadataValue>) -> Result<()> {
+        let user_entries = || metadata.iter().filter(|(k, _)| !is_reserved_key(k));
+
+        let n_keys = user_entries().count();
+        if n_keys > self.max_keys {
+            return Err(VectorDbError::MetadataLimitExceeded(format!(
+                "vector '{}' has {} metadata keys, max is {}",
+                id, n_keys, self.max_keys
+            )));
+        }
+
+        let mut total = 0;
+        for (key, value) in user_entries() {
+            if let Some(len) = longest_string(value) {
+                if len > self.max_string_len {
+                    return Err(VectorDbError::MetadataLimitExceeded(format!(
+                        "vector '{}': value of '{}' is {} bytes long, max is {}",
+                        id, key, len, self.max_string_len
+                    )));
+                }
+            }
+            total += key.len() + estimated_size(value);
+        }
+
+        if total > self.max_total_bytes {
+            return Err(VectorDbError::MetadataLimitExceeded(format!(
+                "vector '{}' has {} bytes of metadata, max is {}",
+                id, total, self.max_total_bytes
+            )));
+        }
+
+        Ok(())
+    }
+}
+
+fn longest_string(value: &MetadataValue) -> Option<usize> {
+    match value {
+        MetadataValue::String(s) => Some(s.len()),
+        MetadataValue::Object(obj) => obj.values().filter_map(longest_string).max(),
+        _ => None,
+    }
+}
+
+// taille approximative en mémoire d'une valeur
+pub fn estimated_size(value: &MetadataValue) -> usize {
+    match value {
+        MetadataValue::String(s) => s.len(),
+        MetadataValue::GeoPoint { .. } => 16,
+        MetadataValue::Object(obj) => obj.iter().map(|(k, v)| k.len() + estimated_size(v)).sum(),
+        MetadataValue::Null => 0,
+        _ => 8,
+    }
+}
+
+pub fn type_name(value: &MetadataValue) -> &'static str {
+    match value {
+        MetadataValue::String(_) => "string",
+        MetadataValue::Int(_) => "int",
+        MetadataValue::Float(_) => "float",
+        MetadataValue::Bool(_) => "bool",
+        MetadataValue::DateTime(_) => "datetime",
+        MetadataValue::GeoPoint { .. } => "geo_point",
+        MetadataValue::Null => "null",
+        MetadataValue::Object(_) => "object",
+    }
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct KeyStats {
+    pub count: usize,
+    pub cardinality: usize,
+    pub types: HashMap<String, usize>,
+    pub estimated_bytes: usize,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct MetadataStats {
+    pub total_vectors: usize,
+    pub keys: HashMap<String, KeyStats>,
+}
+
+// cardinalité exacte par clé, les valeurs sont comparées via leur forme JSON
+pub fn compute_stats<'a, I>(metadatas: I) -> MetadataStats
+where
+    I: Iterator<Item = &'a HashMap<String, MetadataValue>>,
+{
+    let mut keys: HashMap<String, KeyStats> = HashMap::new();
+    let mut distinct: HashMap<String, HashSet<String>> = HashMap::new();
+    let mut total_vectors = 0;
+
+    for metadata in metadatas {
+        total_vectors += 1;
+        for (key, value) in metadata {
+            let stats = keys.entry(key.clone()).or_default();
+            stats.count += 1;
+            stats.estimated_bytes += estimated_size(value);
+            *stats.types.entry(type_name(value).to_string()).or_default() += 1;
+            distinct
+                .entry(key.clone())
+                .or_default()
+                .insert(value.to_json().to_string());
+        }
+    }
+
+    for (key, values) in distinct {
+        if let Some(stats) = keys.get_mut(&key) {
+            stats.cardinality = values.len();
+        }
+    }
+
+    MetadataStats { total_vectors, keys }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_limits() {
+        let limits = MetadataLimits {
+            max_keys: 2,
+            max_string_len: 5,
+            max_total_bytes: 100,
+        };
+
+        let mut metadata = HashMap::new();
+        metadata.insert("a".to_string(), MetadataValue::from("short"));
+        metadata.insert("_system.source".to_string(), MetadataValue::from("ignored-here"));
+        assert!(limits.check("id1", &metadata).is_ok());
+
+        metadata.insert("b".to_string(), MetadataValue::from("too long"));
+        assert!(limits.check("id1", &metadata).is_err());
+
+        metadata.insert("b".to_string(), MetadataValue::Int(1));
+        metadata.insert("c".to_string(), MetadataValue::Int(2));
+        assert!(limits.check("id1", &metadata).is_err());
+    }
+
+    #[test]
+    fn test_cardinality() {
+        let metas: Vec<HashMap<String, MetadataValue>> = ["en", "fr", "en"]
+            .iter()
+            .map(|lang| HashMap::from([("lang".to_string(), MetadataValue::from(*lang))]))
+            .collect();
+
+        let stats = compute_stats(metas.iter());
+        assert_eq!(stats.total_vectors, 3);
+        assert_eq!(stats.keys["lang"].count, 3);
+        assert_eq!(stats.keys["lang"].cardinality, 2);
+        assert_eq!(stats.keys["lang"].types["string"], 3);
+    }
+}
@@ -0,0 +1,171 @@
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct DurationStats {
+    pub count: u64,
+    pub total_ms: f64,
+    pub max_ms: f64,
+    pub last_ms: f64,
+}
+
+impl DurationStats {
+    pub fn record(&mut self, elapsed: Duration) {
+        let ms = elapsed.as_secs_f64() * 1000.0;
+        self.count += 1;
+        self.total_ms += ms;
+        self.max_ms = self.max_ms.max(ms);
+        self.last_ms = ms;
+    }
+
+    pub fn mean_ms(&self) -> f64 {
+        if self.count == 0 {
+            0.0
+        } else {
+            self.total_ms / self.count as f64
+        }
+    }
+}
+
+// rappel de l'index mesuré sur un échantillon de requêtes
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct RecallStats {
+    pub samples: u64,
+    pub total: f64,
+    pub min: f64,
+    pub last: f64,
+}
+
+impl RecallStats {
+    pub fn record(&mut self, recall: f64) {
+        self.min = if self.samples == 0 { recall } else { self.min.min(recall) };
+        self.samples += 1;
+        self.total += recall;
+        self.last = recall;
+    }
+
+    pub fn mean(&self) -> Option<f64> {
+        (self.samples > 0).then(|| self.total / self.samples as f64)
+    }
+}
+
+// compteurs d'accès d'une collection côté client (verrous, lectures/écritures, sauvegardes)
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct CollectionOpStats {
+    pub reads: u64,
+    pub writes: u64,
+    pub read_lock_wait: DurationStats,
+    pub write_lock_wait: DurationStats,
+    pub saves: DurationStats,
+    pub loads: DurationStats,
+    pub recall: RecallStats,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CollectionOpReport {
+    pub name: String,
+    pub loaded: bool,
+    pub reads: u64,
+    pub writes: u64,
+    pub mean_read_lock_wait_ms: f64,
+    pub mean_write_lock_wait_ms: f64,
+    pub mean_save_ms: f64,
+    pub mean_recall: Option<f64>,
+    pub stats: CollectionOpStats,
+}
+
+impl CollectionOpReport {
+    pub fn new(name: String, loaded: bool, stats: CollectionOpStats) -> Self {
+        Self {
+            name,
+            loaded,
+            reads: stats.reads,
+            writes: stats.writes,
+            mean_read_lock_wait_ms: stats.read_lock_wait.mean_ms(),
+            mean_write_lock_wait_ms: stats.write_lock_wait.mean_ms(),
+            mean_save_ms: stats.saves.mean_ms(),
+            mean_recall: stats.recall.mean(),
+            stats,
+        }
+    }
+}
+
+// latences de requête depuis le dernier instantané ; au-delà de MAX_LATENCY_SAMPLES
+// les plus anciens échantillons sont écrasés
+const MAX_LATENCY_SAMPLES: usize = 10_000;
+
+#[derive(Debug, Clone, Default)]
+pub struct LatencyWindow {
+    samples_ms: Vec<f64>,
+    count: u64,
+}
+
+impl LatencyWindow {
+    pub fn record(&mut self, elapsed: Duration) {
+        let ms = elapsed.as_secs_f64() * 1000.0;
+        if self.samples_ms.len() < MAX_LATENCY_SAMPLES {
+            self.samples_ms.push(ms);
+        } else {
+            self.samples_ms[self.count as usize % MAX_LATENCY_SAMPLES] = ms;
+        }
+        self.count += 1;
+    }
+
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+
+    // percentile par rang le plus proche, 0 sans requête
+    pub fn percentile_ms(&self, p: f64) -> f64 {
+        if self.samples_ms.is_empty() {
+            return 0.0;
+        }
+        let mut sorted = self.samples_ms.clone();
+        sorted.sort_by(|a, b| a.total_cmp(b));
+        let rank = ((p / 100.0) * sorted.len() as f64).ceil() as usize;
+        sorted[rank.clamp(1, sorted.len()) - 1]
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct StatsSnapshot {
+    pub at: String,
+    pub count: usize,
+    pub estimated_memory_bytes: usize,
+    // requêtes depuis l'instantané précédent
+    pub queries: u64,
+    pub qps: f64,
+    pub p95_latency_ms: f64,
+}
+
+// instantanés périodiques d'une collection, les plus anciens sont retirés
+#[derive(Debug, Clone)]
+pub struct StatsHistory {
+    pub snapshots: VecDeque<StatsSnapshot>,
+    last_at: Instant,
+}
+
+impl StatsHistory {
+    pub fn new(since: Instant) -> Self {
+        Self { snapshots: VecDeque::new(), last_at: since }
+    }
+
+    pub fn push(&mut self, count: usize, memory_bytes: usize, latencies: LatencyWindow, capacity: usize) {
+        let now = Instant::now();
+        let elapsed = now.duration_since(self.last_at).as_secs_f64();
+        self.last_at = now;
+
+        if self.snapshots.len() >= capacity.max(1) {
+            self.snapshots.pop_front();
+        }
+        self.snapshots.push_back(StatsSnapshot {
+            at: chrono::Utc::now().to_rfc3339(),
+            count,
+            estimated_memory_bytes: memory_bytes,
+            queries: latencies.count(),
+            qps: if elapsed > 0.0 { latencies.count() as f64 / elapsed } else { 0.0 },
+            p95_latency_ms: latencies.percentile_ms(95.0),
+        });
+    }
+}
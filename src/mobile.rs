@@ -0,0 +1,283 @@
+// bindings UniFFI (feature `mobile`), interface décrite dans src/vectordb.udl ;
+// le scaffolding est inclus à la racine de la crate (lib.rs)
+use crate::client::VectorDbClient;
+use crate::collection::{CollectionConfig, SearchResult};
+use crate::error::VectorDbError;
+use crate::filter::{FilterBuilder, WhereFilter};
+use crate::vector::MetadataValue;
+use std::collections::HashMap;
+
+#[derive(Debug, thiserror::Error)]
+pub enum DbError {
+    #[error("{message}")]
+    NotFound { message: String },
+    #[error("{message}")]
+    AlreadyExists { message: String },
+    #[error("{message}")]
+    InvalidArgument { message: String },
+    #[error("{message}")]
+    ReadOnly { message: String },
+    #[error("{message}")]
+    Storage { message: String },
+}
+
+impl From<VectorDbError> for DbError {
+    fn from(e: VectorDbError) -> Self {
+        let message = e.to_string();
+        match e {
+            VectorDbError::CollectionNotFound(_) | VectorDbError::VectorNotFound(_) => DbError::NotFound { message },
+            VectorDbError::CollectionAlreadyExists(_) | VectorDbError::VectorAlreadyExists(_) => {
+                DbError::AlreadyExists { message }
+            }
+            VectorDbError::ReadOnly(_) => DbError::ReadOnly { message },
+            VectorDbError::Io(_)
+            | VectorDbError::Serialization(_)
+            | VectorDbError::CorruptData { .. }
+            | VectorDbError::StorageLocked(_)
+            | VectorDbError::RebuildInProgress(_) => DbError::Storage { message },
+            _ => DbError::InvalidArgument { message },
+        }
+    }
+}
+
+type DbResult<T> = std::result::Result<T, DbError>;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Text { value: String },
+    Int { value: i64 },
+    Float { value: f64 },
+    Bool { value: bool },
+    DateTime { micros: i64 },
+    GeoPoint { lat: f64, lon: f64 },
+    Null,
+}
+
+impl From<Value> for MetadataValue {
+    fn from(v: Value) -> Self {
+        match v {
+            Value::Text { value } => MetadataValue::String(value.into()),
+            Value::Int { value } => MetadataValue::Int(value),
+            Value::Float { value } => MetadataValue::Float(value),
+            Value::Bool { value } => MetadataValue::Bool(value),
+            Value::DateTime { micros } => MetadataValue::DateTime(micros),
+            Value::GeoPoint { lat, lon } => MetadataValue::GeoPoint { lat, lon },
+            Value::Null => MetadataValue::Null,
+        }
+    }
+}
+
+impl From<MetadataValue> for Value {
+    fn from(v: MetadataValue) -> Self {
+        match v {
+            MetadataValue::String(value) => Value::Text { value: value.to_string() },
+            MetadataValue::Int(value) => Value::Int { value },
+            MetadataValue::Float(value) => Value::Float { value },
+            MetadataValue::Bool(value) => Value::Bool { value },
+            MetadataValue::DateTime(micros) => Value::DateTime { micros },
+            MetadataValue::GeoPoint { lat, lon } => Value::GeoPoint { lat, lon },
+            MetadataValue::Null => Value::Null,
+            // pas de type imbriqué côté mobile : l'objet est rendu en JSON
+            obj @ MetadataValue::Object(_) => Value::Text { value: obj.to_json().to_string() },
+        }
+    }
+}
+
+fn metadata_in(metadata: HashMap<String, Value>) -> HashMap<String, MetadataValue> {
+    metadata.into_iter().map(|(k, v)| (k, v.into())).collect()
+}
+
+fn metadata_out(metadata: HashMap<String, MetadataValue>) -> HashMap<String, Value> {
+    metadata.into_iter().map(|(k, v)| (k, v.into())).collect()
+}
+
+#[derive(Debug, Clone)]
+pub enum Condition {
+    Eq { key: String, value: Value },
+    Ne { key: String, value: Value },
+    Gt { key: String, value: Value },
+    Gte { key: String, value: Value },
+    Lt { key: String, value: Value },
+    Lte { key: String, value: Value },
+    In { key: String, values: Vec<Value> },
+    NotIn { key: String, values: Vec<Value> },
+    Exists { key: String, exists: bool },
+    GeoRadius { key: String, lat: f64, lon: f64, radius_km: f64 },
+}
+
+// conditions combinées par clé via FilterBuilder (Eq ne se combine pas avec un opérateur)
+fn to_where_filter(conditions: Vec<Condition>) -> DbResult<Option<WhereFilter>> {
+    if conditions.is_empty() {
+        return Ok(None);
+    }
+
+    let builder = conditions.into_iter().fold(FilterBuilder::new(), |b, condition| match condition {
+        Condition::Eq { key, value } => b.eq(&key, value),
+        Condition::Ne { key, value } => b.ne(&key, value),
+        Condition::Gt { key, value } => b.gt(&key, value),
+        Condition::Gte { key, value } => b.gte(&key, value),
+        Condition::Lt { key, value } => b.lt(&key, value),
+        Condition::Lte { key, value } => b.lte(&key, value),
+        Condition::In { key, values } => b.is_in(&key, values),
+        Condition::NotIn { key, values } => b.not_in(&key, values),
+        Condition::Exists { key, exists } => b.exists(&key, exists),
+        Condition::GeoRadius { key, lat, lon, radius_km } => b.geo_radius(&key, lat, lon, radius_km),
+    });
+    Ok(Some(builder.build()?))
+}
+
+#[derive(Debug, Clone)]
+pub struct Entry {
+    pub id: String,
+    pub embedding: Vec<f32>,
+    pub metadata: HashMap<String, Value>,
+}
+
+#[derive(Debug, Clone)]
+pub struct QueryHit {
+    pub id: String,
+    pub distance: f32,
+    pub metadata: HashMap<String, Value>,
+}
+
+impl From<SearchResult> for QueryHit {
+    fn from(r: SearchResult) -> Self {
+        Self { id: r.id, distance: r.distance, metadata: metadata_out(r.metadata) }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct CollectionInfo {
+    pub name: String,
+    pub dimension: u32,
+    pub count: u64,
+    pub use_ivf: bool,
+}
+
+pub struct VectorDb {
+    client: VectorDbClient,
+}
+
+impl VectorDb {
+    pub fn new(path: String) -> DbResult<Self> {
+        Ok(Self { client: VectorDbClient::new(path)? })
+    }
+
+    pub fn open_read_only(path: String) -> DbResult<Self> {
+        Ok(Self { client: VectorDbClient::open_read_only(path)? })
+    }
+
+    pub fn create_collection(&self, name: String, dimension: u32, n_clusters: Option<u32>) -> DbResult<()> {
+        let mut config = CollectionConfig::new(name, dimension as usize);
+        if let Some(n_clusters) = n_clusters {
+            config = config.with_ivf(n_clusters as usize);
+        }
+        Ok(self.client.create_collection_with_config(config)?)
+    }
+
+    pub fn list_collections(&self) -> DbResult<Vec<String>> {
+        Ok(self.client.list_collections()?)
+    }
+
+    pub fn collection_info(&self, name: String) -> DbResult<CollectionInfo> {
+        let header = self.client.collection_info(&name)?;
+        Ok(CollectionInfo {
+            name,
+            dimension: header.config.dimension as u32,
+            count: header.count as u64,
+            use_ivf: header.config.use_ivf,
+        })
+    }
+
+    pub fn delete_collection(&self, name: String) -> DbResult<()> {
+        Ok(self.client.delete_collection(&name)?)
+    }
+
+    pub fn add(&self, collection: String, entries: Vec<Entry>) -> DbResult<()> {
+        let mut ids = Vec::with_capacity(entries.len());
+        let mut embeddings = Vec::with_capacity(entries.len());
+        let mut metadatas = Vec::with_capacity(entries.len());
+        for entry in entries {
+            ids.push(entry.id);
+            embeddings.push(entry.embedding);
+            metadatas.push(metadata_in(entry.metadata));
+        }
+        Ok(self.client.with_collection_mut(&collection, |coll| {
+            coll.add_with_source(ids, embeddings, Some(metadatas), "mobile")
+        })?)
+    }
+
+    pub fn query(
+        &self,
+        collection: String,
+        embedding: Vec<f32>,
+        n_results: u32,
+        filter: Vec<Condition>,
+    ) -> DbResult<Vec<QueryHit>> {
+        let filter = to_where_filter(filter)?;
+        let results = self.client.with_collection_mut(&collection, |coll| {
+            coll.query(&embedding, n_results as usize, filter.as_ref())
+        })?;
+        Ok(results.into_iter().map(QueryHit::from).collect())
+    }
+
+    pub fn get(&self, collection: String, ids: Vec<String>) -> DbResult<Vec<Entry>> {
+        let result = self.client.with_collection(&collection, |coll| coll.get(Some(ids), None))??;
+        let embeddings = result.embeddings.unwrap_or_default();
+        let metadatas = result.metadatas.unwrap_or_default();
+        Ok(result
+            .ids
+            .into_iter()
+            .zip(embeddings)
+            .zip(metadatas)
+            .map(|((id, embedding), metadata)| Entry { id, embedding, metadata: metadata_out(metadata) })
+            .collect())
+    }
+
+    pub fn update_metadata(&self, collection: String, id: String, metadata: HashMap<String, Value>) -> DbResult<()> {
+        Ok(self
+            .client
+            .with_collection_mut(&collection, |coll| coll.update(vec![id], vec![metadata_in(metadata)]))?)
+    }
+
+    pub fn count(&self, collection: String, filter: Vec<Condition>) -> DbResult<u64> {
+        let filter = to_where_filter(filter)?;
+        Ok(self.client.count(&collection, filter.as_ref())? as u64)
+    }
+
+    pub fn delete(&self, collection: String, ids: Vec<String>) -> DbResult<()> {
+        Ok(self.client.with_collection_mut(&collection, |coll| coll.delete(ids))?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::filter::FilterValue;
+
+    #[test]
+    fn test_conditions_grouped_by_key() {
+        let filter = to_where_filter(vec![
+            Condition::Gte { key: "year".into(), value: Value::Int { value: 2020 } },
+            Condition::Lt { key: "year".into(), value: Value::Int { value: 2024 } },
+            Condition::Eq { key: "lang".into(), value: Value::Text { value: "fr".into() } },
+        ])
+        .unwrap()
+        .unwrap();
+
+        match &filter["year"] {
+            FilterValue::Operator(op) => {
+                assert_eq!(op.gte, Some(MetadataValue::Int(2020)));
+                assert_eq!(op.lt, Some(MetadataValue::Int(2024)));
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+        assert!(matches!(filter["lang"], FilterValue::Direct(MetadataValue::String(_))));
+
+        let conflict = to_where_filter(vec![
+            Condition::Eq { key: "lang".into(), value: Value::Text { value: "fr".into() } },
+            Condition::Ne { key: "lang".into(), value: Value::Null },
+        ]);
+        assert!(matches!(conflict, Err(DbError::InvalidArgument { .. })));
+    }
+}
@@ -0,0 +1,178 @@
+// binding Node.js (napi-rs) : le moteur tourne dans le processus Node, sur les mêmes
+// fichiers de stockage que le serveur (un seul processus écrivain par répertoire)
+use crate::client::VectorDbClient;
+use crate::collection::{CollectionConfig, SearchResult};
+use crate::error::VectorDbError;
+use crate::filter::parse_filter;
+use crate::vector::{metadata_to_json, MetadataValue};
+use napi::bindgen_prelude::*;
+use napi_derive::napi;
+use std::collections::HashMap;
+
+impl From<VectorDbError> for napi::Error {
+    fn from(e: VectorDbError) -> Self {
+        let status = match e {
+            VectorDbError::CollectionNotFound(_)
+            | VectorDbError::DimensionMismatch { .. }
+            | VectorDbError::InvalidConfig(_)
+            | VectorDbError::InvalidFilter(_) => Status::InvalidArg,
+            _ => Status::GenericFailure,
+        };
+        napi::Error::new(status, e.to_string())
+    }
+}
+
+fn metadata_from_json(value: serde_json::Value) -> Result<HashMap<String, MetadataValue>> {
+    match value {
+        serde_json::Value::Object(map) => Ok(map
+            .into_iter()
+            .map(|(k, v)| (k, MetadataValue::from_json(v)))
+            .collect()),
+        serde_json::Value::Null => Ok(HashMap::new()),
+        _ => Err(napi::Error::new(Status::InvalidArg, "metadata must be an object")),
+    }
+}
+
+#[napi(object)]
+pub struct QueryHit {
+    pub id: String,
+    pub distance: f64,
+    pub metadata: serde_json::Value,
+}
+
+impl From<SearchResult> for QueryHit {
+    fn from(r: SearchResult) -> Self {
+        Self {
+            id: r.id,
+            distance: r.distance as f64,
+            metadata: metadata_to_json(&r.metadata),
+        }
+    }
+}
+
+#[napi(object)]
+pub struct Record {
+    pub id: String,
+    pub embedding: Option<Float32Array>,
+    pub metadata: serde_json::Value,
+}
+
+#[napi]
+pub struct VectorDb {
+    client: VectorDbClient,
+}
+
+#[napi]
+impl VectorDb {
+    #[napi(constructor)]
+    pub fn new(path: String) -> Result<Self> {
+        Ok(Self { client: VectorDbClient::new(path)? })
+    }
+
+    #[napi(factory)]
+    pub fn open_read_only(path: String) -> Result<Self> {
+        Ok(Self { client: VectorDbClient::open_read_only(path)? })
+    }
+
+    #[napi]
+    pub fn create_collection(&self, name: String, dimension: u32, n_clusters: Option<u32>) -> Result<()> {
+        let mut config = CollectionConfig::new(name, dimension as usize);
+        if let Some(n_clusters) = n_clusters {
+            config = config.with_ivf(n_clusters as usize);
+        }
+        Ok(self.client.create_collection_with_config(config)?)
+    }
+
+    #[napi]
+    pub fn delete_collection(&self, name: String) -> Result<()> {
+        Ok(self.client.delete_collection(&name)?)
+    }
+
+    #[napi]
+    pub fn list_collections(&self) -> Result<Vec<String>> {
+        Ok(self.client.list_collections()?)
+    }
+
+    // embeddings : un Float32Array plat de ids.length * dimension valeurs
+    #[napi]
+    pub fn add(
+        &self,
+        collection: String,
+        ids: Vec<String>,
+        embeddings: Float32Array,
+        metadatas: Option<Vec<serde_json::Value>>,
+    ) -> Result<()> {
+        if ids.is_empty() {
+            return Ok(());
+        }
+        if !embeddings.len().is_multiple_of(ids.len()) {
+            return Err(napi::Error::new(
+                Status::InvalidArg,
+                format!("{} values cannot be split into {} embeddings", embeddings.len(), ids.len()),
+            ));
+        }
+        let dimension = embeddings.len() / ids.len();
+        let embeddings: Vec<Vec<f32>> = embeddings.chunks(dimension).map(|e| e.to_vec()).collect();
+        let metadatas = metadatas
+            .map(|list| list.into_iter().map(metadata_from_json).collect::<Result<Vec<_>>>())
+            .transpose()?;
+
+        Ok(self
+            .client
+            .with_collection_mut(&collection, |coll| coll.add_with_source(ids, embeddings, metadatas, "node"))?)
+    }
+
+    #[napi]
+    pub fn query(
+        &self,
+        collection: String,
+        embedding: Float32Array,
+        n_results: u32,
+        r#where: Option<serde_json::Value>,
+    ) -> Result<Vec<QueryHit>> {
+        let filter = r#where.as_ref().map(parse_filter).transpose()?;
+        let results = self.client.with_collection_mut(&collection, |coll| {
+            coll.query(&embedding, n_results as usize, filter.as_ref())
+        })?;
+        Ok(results.into_iter().map(QueryHit::from).collect())
+    }
+
+    #[napi]
+    pub fn get(&self, collection: String, ids: Option<Vec<String>>, with_embeddings: Option<bool>) -> Result<Vec<Record>> {
+        let include = if with_embeddings.unwrap_or(false) {
+            vec!["metadatas".to_string(), "embeddings".to_string()]
+        } else {
+            vec!["metadatas".to_string()]
+        };
+        let result = self
+            .client
+            .with_collection(&collection, |coll| coll.get(ids, Some(include)))??;
+
+        let mut embeddings = result.embeddings.map(|e| e.into_iter());
+        let mut metadatas = result.metadatas.map(|m| m.into_iter());
+        Ok(result
+            .ids
+            .into_iter()
+            .map(|id| Record {
+                id,
+                embedding: embeddings.as_mut().and_then(|e| e.next()).map(Float32Array::new),
+                metadata: metadatas
+                    .as_mut()
+                    .and_then(|m| m.next())
+                    .map(|m| metadata_to_json(&m))
+                    .unwrap_or(serde_json::Value::Null),
+            })
+            .collect())
+    }
+
+    #[napi]
+    pub fn delete(&self, collection: String, ids: Vec<String>) -> Result<()> {
+        Ok(self.client.with_collection_mut(&collection, |coll| coll.delete(ids))?)
+    }
+
+    #[napi]
+    pub fn count(&self, collection: String, r#where: Option<serde_json::Value>) -> Result<u32> {
+        let filter = r#where.as_ref().map(parse_filter).transpose()?;
+        Ok(self.client.count(&collection, filter.as_ref())? as u32)
+    }
+}
@@ -0,0 +1,300 @@
+use crate::error::{Result, VectorDbError};
+use std::fs::File;
+use std::io::{BufRead, BufReader, Read};
+use std::path::Path;
+
+const NPY_MAGIC: &[u8; 6] = b"\x93NUMPY";
+
+// embeddings lus depuis un .npy/.npz, avec les ids éventuellement présents dans l'archive
+#[derive(Debug, Clone)]
+pub struct NpyEmbeddings {
+    pub dimension: usize,
+    pub embeddings: Vec<Vec<f32>>,
+    pub ids: Option<Vec<String>>,
+}
+
+#[derive(Debug)]
+struct NpyHeader {
+    descr: String,
+    fortran_order: bool,
+    shape: Vec<usize>,
+}
+
+fn invalid(msg: impl Into<String>) -> VectorDbError {
+    VectorDbError::Serialization(format!("invalid .npy data: {}", msg.into()))
+}
+
+fn read_header<R: Read>(reader: &mut R) -> Result<NpyHeader> {
+    let mut magic = [0u8; 6];
+    reader.read_exact(&mut magic)?;
+    if &magic != NPY_MAGIC {
+        return Err(invalid("missing NUMPY magic"));
+    }
+
+    let mut version = [0u8; 2];
+    reader.read_exact(&mut version)?;
+    let header_len = match version[0] {
+        1 => {
+            let mut len = [0u8; 2];
+            reader.read_exact(&mut len)?;
+            u16::from_le_bytes(len) as usize
+        }
+        2 | 3 => {
+            let mut len = [0u8; 4];
+            reader.read_exact(&mut len)?;
+            u32::from_le_bytes(len) as usize
+        }
+        v => return Err(invalid(format!("unsupported format version {}", v))),
+    };
+
+    let mut raw = vec![0u8; header_len];
+    reader.read_exact(&mut raw)?;
+    parse_header(&String::from_utf8_lossy(&raw))
+}
+
+// dictionnaire Python littéral : {'descr': '<f4', 'fortran_order': False, 'shape': (3, 4), }
+fn parse_header(header: &str) -> Result<NpyHeader> {
+    let value_after = |key: &str| -> Result<&str> {
+        let pattern = format!("'{}':", key);
+        let start = header
+            .find(&pattern)
+            .ok_or_else(|| invalid(format!("header has no '{}'", key)))?;
+        Ok(header[start + pattern.len()..].trim_start())
+    };
+
+    let descr = value_after("descr")?;
+    let descr = descr
+        .strip_prefix('\'')
+        .and_then(|d| d.split('\'').next())
+        .ok_or_else(|| invalid("malformed descr"))?
+        .to_string();
+
+    let fortran_order = value_after("fortran_order")?.starts_with("True");
+
+    let shape = value_after("shape")?;
+    let end = shape.find(')').ok_or_else(|| invalid("malformed shape"))?;
+    let shape = shape[1..end]
+        .split(',')
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .map(|s| s.parse().map_err(|_| invalid(format!("bad dimension '{}'", s))))
+        .collect::<Result<Vec<usize>>>()?;
+
+    Ok(NpyHeader { descr, fortran_order, shape })
+}
+
+// tableau 2D de flottants (f4, f8 ou f2) en lignes de f32
+fn read_matrix<R: Read>(reader: &mut R) -> Result<Vec<Vec<f32>>> {
+    let header = read_header(reader)?;
+    let (rows, cols) = match header.shape.as_slice() {
+        [rows, cols] => (*rows, *cols),
+        [cols] => (1, *cols),
+        shape => return Err(invalid(format!("expected a 2D array, got shape {:?}", shape))),
+    };
+
+    let little_endian = !header.descr.starts_with('>');
+    let values: Vec<f32> = match header.descr.get(1..).unwrap_or("") {
+        "f4" => read_values(reader, rows * cols, |b: [u8; 4]| {
+            if little_endian { f32::from_le_bytes(b) } else { f32::from_be_bytes(b) }
+        })?,
+        "f8" => read_values(reader, rows * cols, |b: [u8; 8]| {
+            (if little_endian { f64::from_le_bytes(b) } else { f64::from_be_bytes(b) }) as f32
+        })?,
+        "f2" => read_values(reader, rows * cols, |b: [u8; 2]| {
+            let bits = if little_endian { u16::from_le_bytes(b) } else { u16::from_be_bytes(b) };
+            half_to_f32(bits)
+        })?,
+        _ => {
+            return Err(invalid(format!(
+                "unsupported dtype '{}' (expected float16, float32 or float64)",
+                header.descr
+            )))
+        }
+    };
+
+    // ordre Fortran : colonne par colonne
+    let matrix = if header.fortran_order {
+        (0..rows)
+            .map(|r| (0..cols).map(|c| values[c * rows + r]).collect())
+            .collect()
+    } else {
+        values.chunks(cols.max(1)).map(|row| row.to_vec()).collect()
+    };
+    Ok(matrix)
+}
+
+fn read_values<R: Read, const N: usize, T>(
+    reader: &mut R,
+    count: usize,
+    convert: impl Fn([u8; N]) -> T,
+) -> Result<Vec<T>> {
+    let mut buf = vec![0u8; count * N];
+    reader.read_exact(&mut buf)?;
+    Ok(buf
+        .chunks_exact(N)
+        .map(|c| convert(c.try_into().unwrap()))
+        .collect())
+}
+
+fn half_to_f32(bits: u16) -> f32 {
+    let sign = if bits & 0x8000 != 0 { -1.0 } else { 1.0 };
+    let exponent = ((bits >> 10) & 0x1f) as i32;
+    let mantissa = (bits & 0x3ff) as f32;
+    match exponent {
+        0 => sign * mantissa * 2f32.powi(-24),
+        31 if mantissa == 0.0 => sign * f32::INFINITY,
+        31 => f32::NAN,
+        e => sign * (1.0 + mantissa / 1024.0) * 2f32.powi(e - 15),
+    }
+}
+
+// tableau 1D de chaînes ('<U..' UTF-32 ou '|S..' octets) ou d'entiers
+fn read_strings<R: Read>(reader: &mut R) -> Result<Vec<String>> {
+    let header = read_header(reader)?;
+    let count: usize = header.shape.iter().product();
+    let kind = header.descr.get(1..2).unwrap_or("");
+    let width: usize = header.descr.get(2..).and_then(|w| w.parse().ok()).unwrap_or(0);
+
+    match kind {
+        "U" => {
+            let chars = read_values(reader, count * width, u32::from_le_bytes)?;
+            Ok(chars
+                .chunks(width.max(1))
+                .map(|c| c.iter().filter(|&&ch| ch != 0).filter_map(|&ch| char::from_u32(ch)).collect())
+                .collect())
+        }
+        "S" => {
+            let mut buf = vec![0u8; count * width];
+            reader.read_exact(&mut buf)?;
+            Ok(buf
+                .chunks(width.max(1))
+                .map(|c| String::from_utf8_lossy(c).trim_end_matches('\0').to_string())
+                .collect())
+        }
+        "i" if width == 8 => Ok(read_values(reader, count, i64::from_le_bytes)?
+            .into_iter()
+            .map(|i| i.to_string())
+            .collect()),
+        _ => Err(invalid(format!("unsupported ids dtype '{}'", header.descr))),
+    }
+}
+
+// .npy : une matrice ; .npz : l'entrée `array` (par défaut "embeddings", sinon la
+// première entrée autre que "ids"), avec un éventuel tableau "ids"
+pub fn read_embeddings(path: &Path, array: Option<&str>) -> Result<NpyEmbeddings> {
+    let is_npz = path.extension().is_some_and(|e| e.eq_ignore_ascii_case("npz"));
+
+    let (embeddings, ids) = if is_npz {
+        let mut archive = zip::ZipArchive::new(BufReader::new(File::open(path)?))
+            .map_err(|e| VectorDbError::Serialization(e.to_string()))?;
+        let names: Vec<String> = archive.file_names().map(|n| n.trim_end_matches(".npy").to_string()).collect();
+
+        let wanted = match array {
+            Some(name) => name.to_string(),
+            None if names.iter().any(|n| n == "embeddings") => "embeddings".to_string(),
+            None => names
+                .iter()
+                .find(|n| n.as_str() != "ids")
+                .cloned()
+                .ok_or_else(|| invalid("empty .npz archive"))?,
+        };
+
+        let mut open = |name: &str| -> Result<Vec<u8>> {
+            let mut file = archive
+                .by_name(&format!("{}.npy", name))
+                .map_err(|_| invalid(format!("no array '{}' in archive (found {:?})", name, names)))?;
+            let mut bytes = Vec::with_capacity(file.size() as usize);
+            file.read_to_end(&mut bytes)?;
+            Ok(bytes)
+        };
+        let embeddings = read_matrix(&mut open(&wanted)?.as_slice())?;
+        let ids = match open("ids") {
+            Ok(bytes) => Some(read_strings(&mut bytes.as_slice())?),
+            Err(_) => None,
+        };
+        (embeddings, ids)
+    } else {
+        let mut reader = BufReader::with_capacity(512 * 1024, File::open(path)?);
+        (read_matrix(&mut reader)?, None)
+    };
+
+    let dimension = embeddings.first().map(|e| e.len()).unwrap_or(0);
+    Ok(NpyEmbeddings { dimension, embeddings, ids })
+}
+
+// fichier d'ids compagnon : tableau JSON, .npy de chaînes, ou une ligne par id
+pub fn read_ids_file(path: &Path) -> Result<Vec<String>> {
+    let extension = path.extension().and_then(|e| e.to_str()).unwrap_or("");
+    match extension {
+        "json" => Ok(serde_json::from_reader(BufReader::new(File::open(path)?))?),
+        "npy" => read_strings(&mut BufReader::new(File::open(path)?)),
+        _ => BufReader::new(File::open(path)?)
+            .lines()
+            .filter(|l| !matches!(l, Ok(l) if l.is_empty()))
+            .map(|l| l.map_err(VectorDbError::from))
+            .collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn npy_bytes(descr: &str, shape: &str, data: &[u8]) -> Vec<u8> {
+        let mut header = format!(
+            "{{'descr': '{}', 'fortran_order': False, 'shape': {}, }}",
+            descr, shape
+        );
+        while (10 + header.len() + 1) % 64 != 0 {
+            header.push(' ');
+        }
+        header.push('\n');
+
+        let mut bytes = NPY_MAGIC.to_vec();
+        bytes.extend([1, 0]);
+        bytes.extend((header.len() as u16).to_le_bytes());
+        bytes.extend(header.as_bytes());
+        bytes.extend(data);
+        bytes
+    }
+
+    #[test]
+    fn test_read_f4_matrix() {
+        let data: Vec<u8> = [1.0f32, 2.0, 3.0, 4.0, 5.0, 6.0]
+            .iter()
+            .flat_map(|v| v.to_le_bytes())
+            .collect();
+        let bytes = npy_bytes("<f4", "(2, 3)", &data);
+
+        let matrix = read_matrix(&mut bytes.as_slice()).unwrap();
+        assert_eq!(matrix, vec![vec![1.0, 2.0, 3.0], vec![4.0, 5.0, 6.0]]);
+    }
+
+    #[test]
+    fn test_read_f8_and_f2() {
+        let data: Vec<u8> = [0.5f64, -2.0].iter().flat_map(|v| v.to_le_bytes()).collect();
+        let bytes = npy_bytes("<f8", "(1, 2)", &data);
+        assert_eq!(read_matrix(&mut bytes.as_slice()).unwrap(), vec![vec![0.5, -2.0]]);
+
+        // 1.0 et -0.5 en float16
+        let data: Vec<u8> = [0x3c00u16, 0xb800].iter().flat_map(|v| v.to_le_bytes()).collect();
+        let bytes = npy_bytes("<f2", "(1, 2)", &data);
+        assert_eq!(read_matrix(&mut bytes.as_slice()).unwrap(), vec![vec![1.0, -0.5]]);
+    }
+
+    #[test]
+    fn test_read_unicode_ids() {
+        let data: Vec<u8> = ['a', 'b', '\0', 'c', 'd', 'e']
+            .iter()
+            .flat_map(|&c| (c as u32).to_le_bytes())
+            .collect();
+        let bytes = npy_bytes("<U3", "(2,)", &data);
+        assert_eq!(read_strings(&mut bytes.as_slice()).unwrap(), vec!["ab", "cde"]);
+    }
+
+    #[test]
+    fn test_rejects_integer_matrix() {
+        let bytes = npy_bytes("<i4", "(1, 1)", &[0, 0, 0, 0]);
+        assert!(read_matrix(&mut bytes.as_slice()).is_err());
+    }
+}
@@ -0,0 +1,258 @@
+// contrôle strict des nombres des embeddings reçus en JSON. Par défaut serde
+// convertit sans rien dire : 3 devient 3.0, 1e39 devient inf et les jetons NaN /
+// Infinity (hors JSON standard) font échouer tout le corps sans dire où. En mode
+// strict, le corps est relu une première fois en gardant le type de chaque nombre
+// et toute valeur qui ne tient pas exactement dans un f32 fini est rapportée avec sa
+// position, avant la désérialisation normale
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use std::fmt;
+
+// valeurs détaillées dans un rapport, les suivantes sont seulement comptées
+pub const MAX_REPORTED: usize = 100;
+
+const NON_FINITE_TOKENS: [&[u8]; 3] = [b"-Infinity", b"Infinity", b"NaN"];
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NumberHandling {
+    // conversion serde habituelle
+    #[default]
+    Lenient,
+    Strict,
+}
+
+impl NumberHandling {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "lenient" => Some(NumberHandling::Lenient),
+            "strict" => Some(NumberHandling::Strict),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NumberIssueKind {
+    // NaN, Infinity, -Infinity
+    NonFinite,
+    // au-delà de f32::MAX ou non nul mais arrondi à 0 en f32
+    OutOfRange,
+    // entier écrit sans partie décimale ni exposant
+    Integer,
+    // chaîne ou autre valeur à la place d'un nombre
+    NotANumber,
+}
+
+impl NumberIssueKind {
+    pub fn name(self) -> &'static str {
+        match self {
+            NumberIssueKind::NonFinite => "non_finite",
+            NumberIssueKind::OutOfRange => "out_of_range",
+            NumberIssueKind::Integer => "integer",
+            NumberIssueKind::NotANumber => "not_a_number",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct NumberIssue {
+    pub field: &'static str,
+    // vecteur dans le lot (absent pour query_embedding et query_tokens)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub index: Option<usize>,
+    // token des champs par token (token_embeddings, query_tokens)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub token: Option<usize>,
+    pub component: usize,
+    // valeur lue dans le corps
+    pub value: String,
+    pub reason: NumberIssueKind,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct NumberReport {
+    // MAX_REPORTED premières valeurs refusées, dans l'ordre du corps
+    pub invalid: Vec<NumberIssue>,
+    pub total_invalid: usize,
+}
+
+impl NumberReport {
+    pub fn is_empty(&self) -> bool {
+        self.total_invalid == 0
+    }
+
+    fn push(&mut self, issue: NumberIssue) {
+        if self.invalid.len() < MAX_REPORTED {
+            self.invalid.push(issue);
+        }
+        self.total_invalid += 1;
+    }
+}
+
+impl fmt::Display for NumberReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} invalid embedding value(s)", self.total_invalid)?;
+        if let Some(first) = self.invalid.first() {
+            write!(f, ", first: {}", first.field)?;
+            for position in [first.index, first.token, Some(first.component)].into_iter().flatten() {
+                write!(f, "[{}]", position)?;
+            }
+            write!(f, " = {} ({})", first.value, first.reason.name())?;
+        }
+        Ok(())
+    }
+}
+
+// nombre gardé tel qu'écrit : serde_json distingue les entiers des flottants
+#[derive(Debug, Clone, Deserialize)]
+#[serde(untagged)]
+enum RawNumber {
+    Number(serde_json::Number),
+    Other(serde_json::Value),
+}
+
+impl RawNumber {
+    fn issue(&self) -> Option<(String, NumberIssueKind)> {
+        match self {
+            RawNumber::Number(n) if !n.is_f64() => Some((n.to_string(), NumberIssueKind::Integer)),
+            RawNumber::Number(n) => {
+                let x = n.as_f64()?;
+                let y = x as f32;
+                (!y.is_finite() || (y == 0.0 && x != 0.0)).then(|| (n.to_string(), NumberIssueKind::OutOfRange))
+            }
+            // jetons non standard remis entre guillemets par quote_non_finite
+            RawNumber::Other(serde_json::Value::String(s)) if NON_FINITE_TOKENS.contains(&s.as_bytes()) => {
+                Some((s.clone(), NumberIssueKind::NonFinite))
+            }
+            RawNumber::Other(v) => Some((v.to_string(), NumberIssueKind::NotANumber)),
+        }
+    }
+}
+
+// seuls les champs d'embeddings sont relus, les autres sont ignorés
+#[derive(Debug, Default, Deserialize)]
+struct EmbeddingFields {
+    embeddings: Option<Vec<Vec<RawNumber>>>,
+    token_embeddings: Option<Vec<Vec<Vec<RawNumber>>>>,
+    query_embedding: Option<Vec<RawNumber>>,
+    query_embeddings: Option<Vec<Vec<RawNumber>>>,
+    query_tokens: Option<Vec<Vec<RawNumber>>>,
+}
+
+// valeurs des champs d'embeddings du corps qui ne tiennent pas exactement dans un
+// f32 fini. Un corps illisible donne un rapport vide : la désérialisation normale
+// renverra l'erreur de syntaxe
+pub fn check_embedding_numbers(body: &[u8]) -> NumberReport {
+    let mut report = NumberReport::default();
+    let Ok(fields) = serde_json::from_slice::<EmbeddingFields>(&quote_non_finite(body)) else {
+        return report;
+    };
+    let mut check = |field, index, token, values: &[RawNumber]| {
+        for (component, value) in values.iter().enumerate() {
+            if let Some((value, reason)) = value.issue() {
+                report.push(NumberIssue { field, index, token, component, value, reason });
+            }
+        }
+    };
+    for (i, row) in fields.embeddings.iter().flatten().enumerate() {
+        check("embeddings", Some(i), None, row);
+    }
+    for (i, matrix) in fields.token_embeddings.iter().flatten().enumerate() {
+        for (t, row) in matrix.iter().enumerate() {
+            check("token_embeddings", Some(i), Some(t), row);
+        }
+    }
+    if let Some(ref query) = fields.query_embedding {
+        check("query_embedding", None, None, query);
+    }
+    for (i, query) in fields.query_embeddings.iter().flatten().enumerate() {
+        check("query_embeddings", Some(i), None, query);
+    }
+    for (t, row) in fields.query_tokens.iter().flatten().enumerate() {
+        check("query_tokens", None, Some(t), row);
+    }
+    report
+}
+
+// met entre guillemets les jetons NaN, Infinity et -Infinity hors des chaînes pour
+// que serde_json les lise et qu'on puisse les situer
+pub fn quote_non_finite(body: &[u8]) -> Cow<'_, [u8]> {
+    let mut out: Option<Vec<u8>> = None;
+    let (mut in_string, mut escaped) = (false, false);
+    let mut i = 0;
+    while i < body.len() {
+        let c = body[i];
+        if in_string {
+            match c {
+                _ if escaped => escaped = false,
+                b'\\' => escaped = true,
+                b'"' => in_string = false,
+                _ => {}
+            }
+        } else if c == b'"' {
+            in_string = true;
+        } else if let Some(token) = NON_FINITE_TOKENS.iter().find(|t| body[i..].starts_with(t)) {
+            let out = out.get_or_insert_with(|| body[..i].to_vec());
+            out.push(b'"');
+            out.extend_from_slice(token);
+            out.push(b'"');
+            i += token.len();
+            continue;
+        }
+        if let Some(ref mut out) = out {
+            out.push(c);
+        }
+        i += 1;
+    }
+    match out {
+        Some(out) => Cow::Owned(out),
+        None => Cow::Borrowed(body),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_quote_non_finite() {
+        let body = br#"{"q": [NaN, -Infinity, 1.5], "s": "NaN \" Infinity"}"#;
+        let quoted = quote_non_finite(body);
+        assert_eq!(&*quoted, br#"{"q": ["NaN", "-Infinity", 1.5], "s": "NaN \" Infinity"}"#);
+        assert!(matches!(quote_non_finite(b"[1.0, 2.0]"), Cow::Borrowed(_)));
+    }
+
+    #[test]
+    fn test_check_embedding_numbers() {
+        let body = br#"{
+            "ids": ["a", "b"],
+            "embeddings": [[0.5, 3, 1e39], [NaN, 1e-46, "0.1"]],
+            "metadatas": [{"n": 3}, {"n": 4}],
+            "query_tokens": [[0.0, -Infinity]]
+        }"#;
+        let report = check_embedding_numbers(body);
+        let found: Vec<_> = report
+            .invalid
+            .iter()
+            .map(|i| (i.field, i.index, i.token, i.component, i.value.as_str(), i.reason))
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                ("embeddings", Some(0), None, 1, "3", NumberIssueKind::Integer),
+                ("embeddings", Some(0), None, 2, "1e+39", NumberIssueKind::OutOfRange),
+                ("embeddings", Some(1), None, 0, "NaN", NumberIssueKind::NonFinite),
+                ("embeddings", Some(1), None, 1, "1e-46", NumberIssueKind::OutOfRange),
+                ("embeddings", Some(1), None, 2, "\"0.1\"", NumberIssueKind::NotANumber),
+                ("query_tokens", None, Some(0), 1, "-Infinity", NumberIssueKind::NonFinite),
+            ]
+        );
+        assert_eq!(report.to_string(), "6 invalid embedding value(s), first: embeddings[0][1] = 3 (integer)");
+
+        assert!(check_embedding_numbers(br#"{"query_embedding": [0.0, -1.0, 2.5e-3, 3.4e38]}"#).is_empty());
+        // corps illisible : laissé à la désérialisation normale
+        assert!(check_embedding_numbers(b"{\"embeddings\": [[1.0,").is_empty());
+    }
+}
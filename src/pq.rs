@@ -0,0 +1,168 @@
+// quantification produit (PQ) des listes IVF : chaque vecteur est découpé en
+// `subspaces` sous-vecteurs, chacun remplacé par l'indice (un octet) du centroïde le
+// plus proche dans le codebook de son sous-espace. Un vecteur de 768 f32 (3 Ko) tient
+// en 96 octets avec 96 sous-espaces. La distance à une requête est estimée sans
+// décoder (ADC) : table des produits scalaires requête × centroïdes par sous-espace,
+// puis `subspaces` lectures par code
+use crate::distance::{dot_product, squared_l2};
+use crate::kmeans::{KMeans, Metric};
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+
+// un code par sous-espace tient dans un octet
+pub const PQ_CENTROIDS: usize = 256;
+// vecteurs tirés pour entraîner les codebooks
+pub const PQ_TRAIN_SAMPLE: usize = 8 * PQ_CENTROIDS;
+const PQ_MAX_ITER: usize = 15;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProductQuantizer {
+    dimension: usize,
+    // sous-espace s : composantes bounds[s]..bounds[s + 1], découpage aussi égal que
+    // possible quand subspaces ne divise pas la dimension
+    bounds: Vec<usize>,
+    // centroïdes par sous-espace, moins de PQ_CENTROIDS si l'échantillon est petit
+    n_centroids: usize,
+    // codebooks[s] : n_centroids centroïdes du sous-espace s, à la suite
+    codebooks: Vec<Vec<f32>>,
+}
+
+impl ProductQuantizer {
+    // k-means euclidien par sous-espace, sous-espaces entraînés en parallèle ;
+    // `sample` non vide
+    pub fn train(sample: &[Vec<f32>], subspaces: usize) -> Self {
+        let dimension = sample.first().map_or(0, Vec::len);
+        let subspaces = subspaces.clamp(1, dimension.max(1));
+        let bounds: Vec<usize> = (0..=subspaces).map(|s| s * dimension / subspaces).collect();
+
+        let trained: Vec<KMeans> = bounds
+            .par_windows(2)
+            .map(|w| {
+                let sub: Vec<Vec<f32>> = sample.iter().map(|v| v[w[0]..w[1]].to_vec()).collect();
+                let mut kmeans = KMeans::new(PQ_CENTROIDS).with_metric(Metric::SquaredL2).with_max_iter(PQ_MAX_ITER);
+                kmeans.fit(&sub);
+                kmeans
+            })
+            .collect();
+        let n_centroids = trained.iter().map(|k| k.centroids.len()).min().unwrap_or(0);
+        let codebooks = trained
+            .into_iter()
+            .map(|k| k.centroids.into_iter().take(n_centroids).flatten().collect())
+            .collect();
+        Self { dimension, bounds, n_centroids, codebooks }
+    }
+
+    pub fn dimension(&self) -> usize {
+        self.dimension
+    }
+
+    // octets par vecteur encodé
+    pub fn code_len(&self) -> usize {
+        self.codebooks.len()
+    }
+
+    pub fn n_centroids(&self) -> usize {
+        self.n_centroids
+    }
+
+    pub fn codebook_bytes(&self) -> usize {
+        self.codebooks.iter().map(|c| c.len() * 4).sum()
+    }
+
+    // ajoute à `out` le code de `vector` (code_len octets)
+    pub fn encode_into(&self, vector: &[f32], out: &mut Vec<u8>) {
+        for (s, codebook) in self.codebooks.iter().enumerate() {
+            let sub = &vector[self.bounds[s]..self.bounds[s + 1]];
+            let width = sub.len();
+            let nearest = (0..self.n_centroids)
+                .map(|c| (c, squared_l2(sub, &codebook[c * width..(c + 1) * width])))
+                .min_by(|a, b| a.1.total_cmp(&b.1))
+                .map_or(0, |(c, _)| c);
+            out.push(nearest as u8);
+        }
+    }
+
+    pub fn encode(&self, vector: &[f32]) -> Vec<u8> {
+        let mut code = Vec::with_capacity(self.code_len());
+        self.encode_into(vector, &mut code);
+        code
+    }
+
+    // vecteur reconstruit à partir de ses centroïdes
+    pub fn decode(&self, code: &[u8]) -> Vec<f32> {
+        let mut vector = Vec::with_capacity(self.dimension);
+        for (s, codebook) in self.codebooks.iter().enumerate() {
+            let width = self.bounds[s + 1] - self.bounds[s];
+            let c = code[s] as usize;
+            vector.extend_from_slice(&codebook[c * width..(c + 1) * width]);
+        }
+        vector
+    }
+
+    // table ADC d'une requête normalisée, calculée une fois par requête
+    pub fn distance_table(&self, query: &[f32]) -> DistanceTable {
+        let mut table = Vec::with_capacity(self.codebooks.len() * self.n_centroids);
+        for (s, codebook) in self.codebooks.iter().enumerate() {
+            let sub = &query[self.bounds[s]..self.bounds[s + 1]];
+            let width = sub.len();
+            table.extend((0..self.n_centroids).map(|c| dot_product(sub, &codebook[c * width..(c + 1) * width])));
+        }
+        DistanceTable { n_centroids: self.n_centroids, table }
+    }
+}
+
+// produits scalaires requête × centroïdes, sous-espace par sous-espace
+pub struct DistanceTable {
+    n_centroids: usize,
+    table: Vec<f32>,
+}
+
+impl DistanceTable {
+    // distance cosinus approchée entre la requête et le vecteur encodé par `code`
+    #[inline]
+    pub fn distance(&self, code: &[u8]) -> f32 {
+        let dot: f32 = code.iter().enumerate().map(|(s, &c)| self.table[s * self.n_centroids + c as usize]).sum();
+        1.0 - dot
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::distance::{cosine_distance, normalize_l2};
+    use rand::{Rng, SeedableRng};
+
+    #[test]
+    fn test_encode_and_asymmetric_distance() {
+        let mut rng = rand::rngs::StdRng::seed_from_u64(3);
+        let data: Vec<Vec<f32>> = (0..1000)
+            .map(|_| {
+                let mut v: Vec<f32> = (0..30).map(|_| rng.gen_range(-1.0..1.0)).collect();
+                normalize_l2(&mut v);
+                v
+            })
+            .collect();
+        // 30 composantes en 8 sous-espaces : largeurs 3 et 4
+        let pq = ProductQuantizer::train(&data, 8);
+        assert_eq!(pq.code_len(), 8);
+        assert_eq!(pq.n_centroids(), PQ_CENTROIDS);
+        assert_eq!(pq.decode(&pq.encode(&data[0])).len(), 30);
+
+        let query = &data[1];
+        let table = pq.distance_table(query);
+        let mut error = 0.0;
+        for v in &data {
+            let code = pq.encode(v);
+            // ADC : exactement la distance au vecteur reconstruit
+            let decoded = pq.decode(&code);
+            assert!((table.distance(&code) - cosine_distance(query, &decoded)).abs() < 1e-4);
+            error += (table.distance(&code) - cosine_distance(query, v)).abs();
+        }
+        assert!(error / (data.len() as f32) < 0.15, "mean error {}", error / data.len() as f32);
+
+        // échantillon plus petit que le codebook
+        let small = ProductQuantizer::train(&data[..10], 4);
+        assert_eq!(small.n_centroids(), 10);
+        assert!(small.encode(&data[3]).iter().all(|&c| (c as usize) < 10));
+    }
+}
@@ -0,0 +1,205 @@
+// classes de priorité des requêtes HTTP (en-tête X-Priority). Les requêtes
+// interactives restent sur le runtime du serveur ; les requêtes batch (imports,
+// ajouts en masse) passent par une voie à part : au plus `concurrency` à la fois,
+// exécutées sur un runtime dédié de `threads` threads. Un import massif ne prend
+// ainsi ni les threads qui servent les requêtes ni plus de `concurrency` écrivains.
+// Les verrous de collection restent communs : une requête sur la collection en
+// cours d'import attend toujours l'écriture, sauf en concurrency = snapshot
+use serde::{Deserialize, Serialize};
+use std::future::Future;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::Arc;
+use tokio::sync::Semaphore;
+
+pub const DEFAULT_BATCH_CONCURRENCY: usize = 2;
+pub const DEFAULT_BATCH_THREADS: usize = 2;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Priority {
+    #[default]
+    Interactive,
+    Batch,
+}
+
+impl Priority {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "interactive" => Some(Priority::Interactive),
+            "batch" => Some(Priority::Batch),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Priority::Interactive => "interactive",
+            Priority::Batch => "batch",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct LaneMetrics {
+    pub interactive_completed: u64,
+    // requêtes batch en attente d'une place
+    pub batch_queued: usize,
+    pub batch_running: usize,
+    pub batch_completed: u64,
+    pub batch_concurrency: usize,
+    pub batch_threads: usize,
+}
+
+#[derive(Debug, Default)]
+struct Counters {
+    interactive_completed: AtomicU64,
+    batch_queued: AtomicUsize,
+    batch_running: AtomicUsize,
+    batch_completed: AtomicU64,
+}
+
+// décrémente un compteur à la sortie, y compris si la requête est abandonnée
+struct Gauge<'a>(&'a AtomicUsize);
+
+impl<'a> Gauge<'a> {
+    fn enter(counter: &'a AtomicUsize) -> Self {
+        counter.fetch_add(1, Ordering::Relaxed);
+        Self(counter)
+    }
+}
+
+impl Drop for Gauge<'_> {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+pub struct PriorityLanes {
+    batch_permits: Arc<Semaphore>,
+    batch_runtime: tokio::runtime::Runtime,
+    concurrency: usize,
+    threads: usize,
+    counters: Counters,
+}
+
+impl PriorityLanes {
+    // le runtime batch ne doit pas être libéré depuis un contexte async : les voies
+    // du serveur vivent jusqu'à l'arrêt du processus
+    pub fn new(concurrency: usize, threads: usize) -> std::io::Result<Self> {
+        let (concurrency, threads) = (concurrency.max(1), threads.max(1));
+        let batch_runtime = tokio::runtime::Builder::new_multi_thread()
+            .worker_threads(threads)
+            .thread_name("vectordb-batch")
+            .enable_all()
+            .build()?;
+        Ok(Self {
+            batch_permits: Arc::new(Semaphore::new(concurrency)),
+            batch_runtime,
+            concurrency,
+            threads,
+            counters: Counters::default(),
+        })
+    }
+
+    pub async fn run<F>(&self, priority: Priority, work: F) -> F::Output
+    where
+        F: Future + Send + 'static,
+        F::Output: Send + 'static,
+    {
+        if priority == Priority::Interactive {
+            let output = work.await;
+            self.counters.interactive_completed.fetch_add(1, Ordering::Relaxed);
+            return output;
+        }
+
+        let permit = {
+            let _queued = Gauge::enter(&self.counters.batch_queued);
+            self.batch_permits.clone().acquire_owned().await.expect("batch lane semaphore is never closed")
+        };
+        let _running = Gauge::enter(&self.counters.batch_running);
+        let handle = self.batch_runtime.spawn(async move {
+            let output = work.await;
+            drop(permit);
+            output
+        });
+        let output = match handle.await {
+            Ok(output) => output,
+            Err(e) if e.is_panic() => std::panic::resume_unwind(e.into_panic()),
+            Err(e) => panic!("batch lane task cancelled: {}", e),
+        };
+        self.counters.batch_completed.fetch_add(1, Ordering::Relaxed);
+        output
+    }
+
+    pub fn metrics(&self) -> LaneMetrics {
+        LaneMetrics {
+            interactive_completed: self.counters.interactive_completed.load(Ordering::Relaxed),
+            batch_queued: self.counters.batch_queued.load(Ordering::Relaxed),
+            batch_running: self.counters.batch_running.load(Ordering::Relaxed),
+            batch_completed: self.counters.batch_completed.load(Ordering::Relaxed),
+            batch_concurrency: self.concurrency,
+            batch_threads: self.threads,
+        }
+    }
+}
+
+impl std::fmt::Debug for PriorityLanes {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PriorityLanes").field("metrics", &self.metrics()).finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::sync::oneshot;
+
+    #[test]
+    fn test_priority_lanes() {
+        assert_eq!(Priority::parse(" Batch"), Some(Priority::Batch));
+        assert_eq!(Priority::parse("urgent"), None);
+
+        let lanes = Arc::new(PriorityLanes::new(1, 1).unwrap());
+        let server = tokio::runtime::Builder::new_current_thread().enable_all().build().unwrap();
+        server.block_on(async {
+            // import qui signale son démarrage puis attend d'être relâché
+            let batch = |lanes: Arc<PriorityLanes>| {
+                let (started_tx, started_rx) = oneshot::channel();
+                let (release_tx, release_rx) = oneshot::channel::<()>();
+                let handle = tokio::spawn(async move {
+                    lanes
+                        .run(Priority::Batch, async move {
+                            started_tx.send(()).unwrap();
+                            release_rx.await.unwrap();
+                            std::thread::current().name().map(String::from)
+                        })
+                        .await
+                });
+                (handle, started_rx, release_tx)
+            };
+
+            // deux imports : le second attend la place du premier
+            let (first, first_started, release_first) = batch(lanes.clone());
+            first_started.await.unwrap();
+            let (second, mut second_started, release_second) = batch(lanes.clone());
+            while lanes.metrics().batch_queued == 0 {
+                tokio::task::yield_now().await;
+            }
+            let metrics = lanes.metrics();
+            assert_eq!((metrics.batch_running, metrics.batch_queued), (1, 1));
+            assert!(second_started.try_recv().is_err());
+
+            // la requête interactive n'attend pas les imports, tous deux bloqués
+            assert_eq!(lanes.run(Priority::Interactive, async { 7 }).await, 7);
+
+            release_first.send(()).unwrap();
+            assert_eq!(first.await.unwrap().as_deref(), Some("vectordb-batch"));
+            second_started.await.unwrap();
+            release_second.send(()).unwrap();
+            second.await.unwrap();
+        });
+        let metrics = lanes.metrics();
+        assert_eq!((metrics.batch_completed, metrics.interactive_completed), (2, 1));
+        assert_eq!((metrics.batch_running, metrics.batch_queued), (0, 0));
+    }
+}
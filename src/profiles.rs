@@ -0,0 +1,174 @@
+// profils de collection nommés (dimension, modèle, paramètres IVF, limites de
+// métadonnées) définis dans la config serveur, pour créer une collection par nom
+// de profil plutôt que de recopier les mêmes réglages
+use crate::collection::{CollectionConfig, RebuildPolicy};
+use crate::error::{Result, VectorDbError};
+use crate::half::Precision;
+use crate::metadata::MetadataLimits;
+use crate::readview::ConcurrencyMode;
+use crate::sq::ScalarQuantization;
+use crate::vector::VectorType;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::Path;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CollectionProfile {
+    pub dimension: usize,
+    #[serde(default)]
+    pub use_ivf: bool,
+    #[serde(default)]
+    pub n_clusters: usize,
+    #[serde(default)]
+    pub embedding_model: Option<String>,
+    #[serde(default)]
+    pub metadata_limits: Option<MetadataLimits>,
+    #[serde(default)]
+    pub expected_size: Option<usize>,
+    #[serde(default)]
+    pub content_hash: bool,
+    #[serde(default)]
+    pub drift_threshold: Option<f32>,
+    #[serde(default)]
+    pub rebuild_policy: Option<RebuildPolicy>,
+    #[serde(default)]
+    pub pq_subspaces: Option<usize>,
+    #[serde(default)]
+    pub scalar_quantization: Option<ScalarQuantization>,
+    #[serde(default)]
+    pub vector_type: VectorType,
+    #[serde(default)]
+    pub store_raw: bool,
+    #[serde(default)]
+    pub precision: Precision,
+    #[serde(default)]
+    pub concurrency: ConcurrencyMode,
+    #[serde(default)]
+    pub tombstones: bool,
+}
+
+impl CollectionProfile {
+    pub fn new(dimension: usize) -> Self {
+        Self {
+            dimension,
+            use_ivf: false,
+            n_clusters: 0,
+            embedding_model: None,
+            metadata_limits: None,
+            expected_size: None,
+            content_hash: false,
+            drift_threshold: None,
+            rebuild_policy: None,
+            pq_subspaces: None,
+            scalar_quantization: None,
+            vector_type: VectorType::Float,
+            store_raw: false,
+            precision: Precision::F32,
+            concurrency: ConcurrencyMode::Locked,
+            tombstones: false,
+        }
+    }
+
+    pub fn with_ivf(mut self, n_clusters: usize) -> Self {
+        self.use_ivf = true;
+        self.n_clusters = n_clusters;
+        self
+    }
+
+    pub fn with_embedding_model(mut self, model: String) -> Self {
+        self.embedding_model = Some(model);
+        self
+    }
+
+    // config d'une nouvelle collection `name`, qui garde le nom du profil
+    pub fn config(&self, profile: &str, name: String) -> CollectionConfig {
+        let mut config = CollectionConfig::new(name, self.dimension);
+        if self.use_ivf {
+            config = config.with_ivf(self.n_clusters);
+        }
+        if let Some(ref model) = self.embedding_model {
+            config = config.with_embedding_model(model.clone());
+        }
+        if let Some(ref limits) = self.metadata_limits {
+            config = config.with_metadata_limits(limits.clone());
+        }
+        config.expected_size = self.expected_size;
+        config.content_hash = self.content_hash;
+        config.drift_threshold = self.drift_threshold;
+        if let Some(ref policy) = self.rebuild_policy {
+            config.rebuild_policy = policy.clone();
+        }
+        config.pq_subspaces = self.pq_subspaces;
+        config.scalar_quantization = self.scalar_quantization;
+        config.vector_type = self.vector_type;
+        config.store_raw = self.store_raw;
+        config.precision = self.precision;
+        config.concurrency = self.concurrency;
+        config.tombstones = self.tombstones;
+        config.profile = Some(profile.to_string());
+        config
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct ProfileRegistry {
+    profiles: HashMap<String, CollectionProfile>,
+}
+
+impl ProfileRegistry {
+    // fichier JSON : {"openai-1536-ivf": {"dimension": 1536, "use_ivf": true, ...}, ...}
+    pub fn from_json_file<P: AsRef<Path>>(path: P) -> Result<HashMap<String, CollectionProfile>> {
+        let data = std::fs::read(path.as_ref())?;
+        serde_json::from_slice(&data).map_err(|e| {
+            VectorDbError::InvalidConfig(format!(
+                "invalid profiles file {}: {}",
+                path.as_ref().display(),
+                e
+            ))
+        })
+    }
+
+    pub fn register(&mut self, name: String, profile: CollectionProfile) {
+        self.profiles.insert(name, profile);
+    }
+
+    pub fn get(&self, name: &str) -> Result<&CollectionProfile> {
+        self.profiles
+            .get(name)
+            .ok_or_else(|| VectorDbError::InvalidConfig(format!("unknown collection profile '{}'", name)))
+    }
+
+    // triés par nom
+    pub fn list(&self) -> Vec<(String, CollectionProfile)> {
+        let mut profiles: Vec<_> = self.profiles.iter().map(|(n, p)| (n.clone(), p.clone())).collect();
+        profiles.sort_by(|a, b| a.0.cmp(&b.0));
+        profiles
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_profile_config() {
+        let profiles: HashMap<String, CollectionProfile> = serde_json::from_str(
+            r#"{"openai-1536-ivf": {"dimension": 1536, "use_ivf": true, "n_clusters": 256,
+                "embedding_model": "text-embedding-3-small", "content_hash": true}}"#,
+        )
+        .unwrap();
+        let mut registry = ProfileRegistry::default();
+        for (name, profile) in profiles {
+            registry.register(name, profile);
+        }
+
+        let config = registry.get("openai-1536-ivf").unwrap().config("openai-1536-ivf", "docs".into());
+        assert_eq!(config.name, "docs");
+        assert_eq!((config.dimension, config.use_ivf, config.n_clusters), (1536, true, 256));
+        assert_eq!(config.embedding_model.as_deref(), Some("text-embedding-3-small"));
+        assert!(config.content_hash);
+        assert_eq!(config.profile.as_deref(), Some("openai-1536-ivf"));
+
+        assert!(matches!(registry.get("missing"), Err(VectorDbError::InvalidConfig(_))));
+    }
+}
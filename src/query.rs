@@ -0,0 +1,309 @@
+use crate::collection::{Collection, HitExplanation, QueryExplanation, QueryOptions, SortDirection};
+use crate::error::Result;
+use crate::filter::WhereFilter;
+use crate::late_interaction::{VectorAggregation, DEFAULT_RESCORE_FACTOR};
+use crate::rerank::{self, Reranker};
+use crate::vector::MetadataValue;
+use std::collections::HashMap;
+
+#[derive(Debug, Clone)]
+pub struct SearchHit {
+    pub id: String,
+    pub distance: f32,
+    // similarité cosinus, 1 - distance
+    pub score: f32,
+    pub metadata: Option<HashMap<String, MetadataValue>>,
+    pub embedding: Option<Vec<f32>>,
+    pub explain: Option<HitExplanation>,
+    // renseigné avec QueryBuilder::rerank
+    pub rerank_score: Option<f32>,
+}
+
+// requête construite par appels chaînés, exécutée par run() :
+//   coll.search(&v).k(10).filter(f).score_threshold(0.3).include_embeddings().run()?
+pub struct QueryBuilder<'a> {
+    collection: &'a mut Collection,
+    embedding: Vec<f32>,
+    k: usize,
+    filter: Option<WhereFilter>,
+    include_metadata: bool,
+    include_embeddings: bool,
+    options: QueryOptions,
+    aggregation: Option<VectorAggregation>,
+    rerank: Option<(&'a dyn Reranker, String, usize)>,
+}
+
+impl<'a> QueryBuilder<'a> {
+    pub(crate) fn new(collection: &'a mut Collection, embedding: Vec<f32>) -> Self {
+        Self {
+            collection,
+            embedding,
+            k: 10,
+            filter: None,
+            include_metadata: true,
+            include_embeddings: false,
+            options: QueryOptions::default(),
+            aggregation: None,
+            rerank: None,
+        }
+    }
+
+    pub fn k(mut self, k: usize) -> Self {
+        self.k = k;
+        self
+    }
+
+    pub fn filter(mut self, filter: WhereFilter) -> Self {
+        self.filter = Some(filter);
+        self
+    }
+
+    // ne garde que les résultats de similarité >= threshold, avant de couper à k
+    pub fn score_threshold(mut self, threshold: f32) -> Self {
+        self.options = self.options.score_threshold(threshold);
+        self
+    }
+
+    // recherche exhaustive, sans l'index IVF
+    pub fn exact(mut self) -> Self {
+        self.options = self.options.exact();
+        self
+    }
+
+    // Desc : plus lointains d'abord (recherche exhaustive)
+    pub fn direction(mut self, direction: SortDirection) -> Self {
+        self.options = self.options.direction(direction);
+        self
+    }
+
+    // clé de métadonnée départageant les distances égales, appelable plusieurs fois
+    pub fn then_by(mut self, key: &str, direction: SortDirection) -> Self {
+        self.options = self.options.then_by(key, direction);
+        self
+    }
+
+    // SearchHit::explain : chemin de recherche, cluster d'origine, produit scalaire
+    pub fn explain_hits(mut self) -> Self {
+        self.options = self.options.explain_hits();
+        self
+    }
+
+    // collection late_interaction : chaque id noté d'après tous ses vecteurs (voir
+    // Collection::query_multi_vector)
+    pub fn aggregate(mut self, aggregation: VectorAggregation) -> Self {
+        self.aggregation = Some(aggregation);
+        self
+    }
+
+    // reclasse les `candidates` plus proches avec `reranker` avant de garder les k
+    // meilleurs ; le seuil de similarité s'applique avant le reranking
+    pub fn rerank(mut self, reranker: &'a dyn Reranker, query_text: &str, candidates: usize) -> Self {
+        self.rerank = Some((reranker, query_text.to_string(), candidates));
+        self
+    }
+
+    pub fn include_embeddings(mut self) -> Self {
+        self.include_embeddings = true;
+        self
+    }
+
+    pub fn without_metadata(mut self) -> Self {
+        self.include_metadata = false;
+        self
+    }
+
+    // plan et étape du filtre de la requête, sans l'exécuter (voir Collection::explain_query)
+    pub fn explain(&self) -> QueryExplanation {
+        self.collection.explain_query(self.k, self.filter.as_ref(), &self.options)
+    }
+
+    pub fn run(self) -> Result<Vec<SearchHit>> {
+        let n = match self.rerank {
+            Some((_, _, candidates)) => candidates.max(self.k),
+            None => self.k,
+        };
+        let filter = self.filter.as_ref();
+        let mut results = match self.aggregation {
+            Some(aggregation) => self.collection.query_multi_vector(
+                &self.embedding,
+                n,
+                n * DEFAULT_RESCORE_FACTOR,
+                aggregation,
+                filter,
+                &self.options,
+            )?,
+            None => self.collection.query_with_options(&self.embedding, n, filter, &self.options)?,
+        };
+        if let Some((reranker, query_text, _)) = self.rerank {
+            results = rerank::rerank(reranker, &query_text, results, self.k)?;
+        }
+
+        let collection = &*self.collection;
+        results
+            .into_iter()
+            .map(|r| (1.0 - r.distance, r))
+            .map(|(score, r)| {
+                Ok(SearchHit {
+                    embedding: if self.include_embeddings {
+                        collection.embedding(&r.id)?
+                    } else {
+                        None
+                    },
+                    metadata: self.include_metadata.then_some(r.metadata),
+                    explain: r.explain,
+                    rerank_score: r.rerank_score,
+                    id: r.id,
+                    distance: r.distance,
+                    score,
+                })
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::collection::{Collection, HitSource, IndexParams, SortDirection};
+    use crate::filter::FilterBuilder;
+    use crate::vector::MetadataValue;
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_query_builder() {
+        let mut coll = Collection::new("docs".to_string(), 2);
+        let metadatas: Vec<HashMap<String, MetadataValue>> = ["fr", "fr", "en"]
+            .iter()
+            .map(|lang| HashMap::from([("lang".to_string(), MetadataValue::from(*lang))]))
+            .collect();
+        coll.add(
+            vec!["a".into(), "b".into(), "c".into()],
+            vec![vec![1.0, 0.0], vec![0.0, 1.0], vec![1.0, 0.1]],
+            Some(metadatas),
+        )
+        .unwrap();
+
+        let hits = coll
+            .search(&[1.0, 0.0])
+            .k(10)
+            .filter(FilterBuilder::new().eq("lang", "fr").build().unwrap())
+            .score_threshold(0.5)
+            .include_embeddings()
+            .run()
+            .unwrap();
+
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].id, "a");
+        assert!((hits[0].score - 1.0).abs() < 1e-6);
+        assert_eq!(hits[0].embedding.as_deref(), Some([1.0, 0.0].as_slice()));
+
+        let hits = coll.search(&[1.0, 0.0]).k(2).without_metadata().run().unwrap();
+        assert_eq!(hits.len(), 2);
+        assert!(hits.iter().all(|h| h.metadata.is_none() && h.embedding.is_none()));
+    }
+
+    #[test]
+    fn test_exact_bypasses_ivf() {
+        let mut coll = Collection::new_with_ivf("docs".to_string(), 2, 2);
+        let ids = (0..40).map(|i| format!("v{}", i)).collect();
+        let embeddings = (0..40).map(|i| vec![(i as f32).cos(), (i as f32).sin()]).collect();
+        coll.add(ids, embeddings, None).unwrap();
+        coll.rebuild_index();
+        // un seul cluster sondé : l'index reste moins coûteux qu'un parcours complet
+        coll.set_index_params(IndexParams { n_probe: Some(1), ..Default::default() }).unwrap();
+
+        // ajouté après le build, sous le seuil de rebuild : absent des listes inversées
+        coll.add(vec!["late".into()], vec![vec![0.6, 0.8]], None).unwrap();
+
+        let hits = coll.search(&[0.6, 0.8]).k(1).run().unwrap();
+        assert_ne!(hits[0].id, "late");
+        let hits = coll.search(&[0.6, 0.8]).k(1).exact().explain_hits().run().unwrap();
+        assert_eq!(hits[0].id, "late");
+        assert_eq!(hits[0].explain.as_ref().unwrap().source, HitSource::Linear);
+
+        let hits = coll.search(&[1.2, 1.6]).k(3).explain_hits().run().unwrap();
+        for hit in &hits {
+            let explain = hit.explain.as_ref().unwrap();
+            assert_eq!(explain.source, HitSource::Ivf);
+            assert!(explain.cluster.is_some());
+            // requête de norme 2
+            assert!((explain.dot_product - 2.0 * hit.score).abs() < 1e-4);
+        }
+    }
+
+    #[test]
+    fn test_query_planner() {
+        use crate::collection::{FilterStage, PlanReason, QueryStrategy};
+
+        let mut coll = Collection::new_with_ivf("docs".to_string(), 2, 4);
+        let ids = (0..200).map(|i| format!("v{}", i)).collect();
+        let embeddings = (0..200).map(|i| vec![(i as f32).cos(), (i as f32).sin()]).collect();
+        let metadatas = (0..200)
+            .map(|i| {
+                HashMap::from([
+                    ("even".to_string(), MetadataValue::from(i % 2 == 0)),
+                    ("rare".to_string(), MetadataValue::from(i < 5)),
+                ])
+            })
+            .collect();
+        coll.add(ids, embeddings, Some(metadatas)).unwrap();
+        // pas de rebuild déclenché par les requêtes
+        coll.set_deferred_rebuild(true);
+        let plan = |search: super::QueryBuilder| {
+            let plan = search.k(5).explain().plan;
+            (plan.strategy, plan.reason, plan.linear_cost, plan.index_cost)
+        };
+        assert_eq!(plan(coll.search(&[1.0, 0.0])).1, PlanReason::NoIndex);
+
+        coll.rebuild_index();
+        // 4 clusters sur 4 sondés : centroïdes + tous les vecteurs
+        let linear = (QueryStrategy::Linear, PlanReason::CheaperThanIndex);
+        assert_eq!(plan(coll.search(&[1.0, 0.0])), (linear.0, linear.1, 200, Some(204)));
+        coll.set_index_params(IndexParams { n_probe: Some(1), ..Default::default() }).unwrap();
+        assert_eq!(plan(coll.search(&[1.0, 0.0])), (QueryStrategy::Ivf, PlanReason::CheaperThanLinear, 200, Some(54)));
+
+        let even = FilterBuilder::new().eq("even", true).build().unwrap();
+        assert_eq!(plan(coll.search(&[1.0, 0.0]).filter(even.clone())).0, QueryStrategy::Ivf);
+        assert_eq!(coll.search(&[1.0, 0.0]).filter(even).explain().filter_stage, Some(FilterStage::Probed));
+        let rare = FilterBuilder::new().eq("rare", true).build().unwrap();
+        assert_eq!(plan(coll.search(&[1.0, 0.0]).filter(rare.clone())), (linear.0, linear.1, 5, Some(54)));
+        let explanation = coll.search(&[1.0, 0.0]).filter(rare.clone()).explain();
+        assert_eq!(explanation.filter_stage, Some(FilterStage::PreSelection));
+        assert_eq!(coll.search(&[1.0, 0.0]).explain().filter_stage, None);
+        assert_eq!(coll.search(&[1.0, 0.0]).k(10).filter(rare).run().unwrap().len(), 5);
+
+        assert_eq!(plan(coll.search(&[1.0, 0.0]).exact()).1, PlanReason::Exact);
+        assert_eq!(plan(coll.search(&[1.0, 0.0]).direction(SortDirection::Desc)).1, PlanReason::Descending);
+
+        // rebuild dû mais différé : les derniers ajouts manquent aux listes
+        let ids = (0..30).map(|i| format!("late{}", i)).collect();
+        coll.add(ids, vec![vec![0.6, 0.8]; 30], None).unwrap();
+        assert_eq!(plan(coll.search(&[0.6, 0.8])).1, PlanReason::StaleIndex);
+    }
+
+    #[test]
+    fn test_secondary_sort_keys() {
+        let mut coll = Collection::new("docs".to_string(), 2);
+        let metadatas: Vec<HashMap<String, MetadataValue>> = [3, 1, 2]
+            .iter()
+            .map(|ts| HashMap::from([("timestamp".to_string(), MetadataValue::from(*ts))]))
+            .collect();
+        // a, b, c à égale distance de la requête, d sans timestamp
+        coll.add(
+            vec!["a".into(), "b".into(), "c".into(), "d".into(), "far".into()],
+            vec![vec![1.0, 0.0], vec![1.0, 0.0], vec![1.0, 0.0], vec![1.0, 0.0], vec![0.0, 1.0]],
+            Some(metadatas.into_iter().chain([HashMap::new(), HashMap::new()]).collect()),
+        )
+        .unwrap();
+
+        let ids = |hits: Vec<super::SearchHit>| hits.into_iter().map(|h| h.id).collect::<Vec<_>>();
+
+        let hits = coll.search(&[1.0, 0.0]).k(4).then_by("timestamp", SortDirection::Desc).run().unwrap();
+        assert_eq!(ids(hits), ["a", "c", "b", "d"]);
+
+        let hits = coll.search(&[1.0, 0.0]).k(2).then_by("timestamp", SortDirection::Asc).run().unwrap();
+        assert_eq!(ids(hits), ["b", "c"]);
+
+        let hits = coll.search(&[1.0, 0.0]).k(1).direction(SortDirection::Desc).run().unwrap();
+        assert_eq!(ids(hits), ["far"]);
+    }
+}
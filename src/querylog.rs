@@ -0,0 +1,384 @@
+// journal des requêtes pour rejouer le trafic réel sur une collection (validation
+// d'un changement d'index, tests de charge). Fichier binaire : en-tête QUERY_LOG_MAGIC
+// puis un enregistrement bincode par requête, préfixé par sa longueur (u32 LE)
+use crate::client::VectorDbClient;
+use crate::collection::{recall, QueryOptions};
+use crate::error::{Result, VectorDbError};
+use crate::filter::{parse_filter, FilterValue, WhereFilter};
+use crate::metrics::LatencyWindow;
+use crate::vector::{embedding_hash, MetadataValue};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fs::{File, OpenOptions};
+use std::io::{BufReader, BufWriter, ErrorKind, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+pub const QUERY_LOG_MAGIC: &[u8; 8] = b"VDBQLOG1";
+
+// un enregistrement corrompu ne doit pas faire allouer des gigaoctets
+const MAX_RECORD_BYTES: u32 = 64 * 1024 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum QueryLogMode {
+    // empreinte de l'embedding seulement : volume réduit, pas de rejeu possible
+    Hash,
+    // embedding complet, nécessaire au rejeu
+    Full,
+}
+
+impl QueryLogMode {
+    pub fn parse(value: &str) -> Result<Self> {
+        match value {
+            "hash" => Ok(Self::Hash),
+            "full" => Ok(Self::Full),
+            other => Err(VectorDbError::InvalidConfig(format!(
+                "unknown query log mode '{}' (expected 'hash' or 'full')",
+                other
+            ))),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum LoggedEmbedding {
+    // même empreinte que _system.content_hash (vector::embedding_hash)
+    Hash(String),
+    Full(Vec<f32>),
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct QueryLogEntry {
+    pub at_ms: u64,
+    pub collection: String,
+    pub embedding: LoggedEmbedding,
+    // filtre en JSON : WhereFilter (untagged) ne se relit pas en bincode
+    pub where_filter: Option<String>,
+    pub n_results: usize,
+    pub exact: bool,
+    pub latency_us: u64,
+}
+
+pub struct QueryLog {
+    path: PathBuf,
+    mode: QueryLogMode,
+    file: Mutex<File>,
+}
+
+impl QueryLog {
+    // ouverture en ajout ; un fichier existant doit être un journal de requêtes
+    pub fn open<P: AsRef<Path>>(path: P, mode: QueryLogMode) -> Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let mut file = OpenOptions::new().read(true).append(true).create(true).open(&path)?;
+        if file.metadata()?.len() == 0 {
+            file.write_all(QUERY_LOG_MAGIC)?;
+        } else {
+            check_magic(&mut file, &path)?;
+        }
+        Ok(Self { path, mode, file: Mutex::new(file) })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn mode(&self) -> QueryLogMode {
+        self.mode
+    }
+
+    pub fn entry(
+        &self,
+        collection: &str,
+        query_embedding: &[f32],
+        where_filter: Option<&serde_json::Value>,
+        n_results: usize,
+        exact: bool,
+        latency: Duration,
+    ) -> QueryLogEntry {
+        let embedding = match self.mode {
+            QueryLogMode::Hash => LoggedEmbedding::Hash(embedding_hash(query_embedding)),
+            QueryLogMode::Full => LoggedEmbedding::Full(query_embedding.to_vec()),
+        };
+        QueryLogEntry {
+            at_ms: SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_millis() as u64,
+            collection: collection.to_string(),
+            embedding,
+            where_filter: where_filter.map(|f| f.to_string()),
+            n_results,
+            exact,
+            latency_us: latency.as_micros() as u64,
+        }
+    }
+
+    // un seul write par enregistrement : un arrêt brutal ne laisse au pire qu'un
+    // dernier enregistrement tronqué, ignoré à la lecture
+    pub fn record(&self, entry: &QueryLogEntry) -> Result<()> {
+        let record = encode_record(entry)?;
+        self.file.lock().unwrap().write_all(&record)?;
+        Ok(())
+    }
+
+    // réécrit le journal sans les enregistrements pour lesquels `erase` est vrai
+    // (effacement, voir concerns_erasure) ; les requêtes servies pendant la
+    // réécriture attendent le verrou du fichier. Renvoie le nombre d'enregistrements retirés
+    pub fn purge(&self, erase: impl Fn(&QueryLogEntry) -> bool) -> Result<usize> {
+        let mut file = self.file.lock().unwrap();
+        let tmp_path = self.path.with_extension("tmp");
+        let mut removed = 0;
+        let mut out = BufWriter::new(File::create(&tmp_path)?);
+        out.write_all(QUERY_LOG_MAGIC)?;
+        for entry in QueryLogReader::open(&self.path)? {
+            let entry = entry?;
+            if erase(&entry) {
+                removed += 1;
+            } else {
+                out.write_all(&encode_record(&entry)?)?;
+            }
+        }
+        out.into_inner().map_err(|e| e.into_error())?.sync_data()?;
+        std::fs::rename(&tmp_path, &self.path)?;
+        *file = OpenOptions::new().read(true).append(true).open(&self.path)?;
+        Ok(removed)
+    }
+}
+
+fn encode_record(entry: &QueryLogEntry) -> Result<Vec<u8>> {
+    let body = bincode::serialize(entry)?;
+    let mut record = Vec::with_capacity(4 + body.len());
+    record.extend_from_slice(&(body.len() as u32).to_le_bytes());
+    record.extend_from_slice(&body);
+    Ok(record)
+}
+
+// requête concernée par un effacement : embedding d'une entrée effacée (même
+// empreinte que son _system.content_hash), ou filtre qui désigne, par égalité ou
+// $in, une valeur que le filtre d'effacement désigne pour le même champ
+pub fn concerns_erasure(entry: &QueryLogEntry, filter: &WhereFilter, content_hashes: &HashSet<String>) -> bool {
+    let hash = match entry.embedding {
+        LoggedEmbedding::Hash(ref hash) => hash.clone(),
+        LoggedEmbedding::Full(ref embedding) => embedding_hash(embedding),
+    };
+    if content_hashes.contains(&hash) {
+        return true;
+    }
+    let logged = entry
+        .where_filter
+        .as_deref()
+        .and_then(|json| serde_json::from_str(json).ok())
+        .and_then(|value| parse_filter(&value).ok());
+    let Some(logged) = logged else {
+        return false;
+    };
+    let erased = filter_terms(filter);
+    filter_terms(&logged).iter().any(|term| erased.contains(term))
+}
+
+// couples (champ, valeur) désignés par égalité ou $in
+fn filter_terms(filter: &WhereFilter) -> Vec<(&str, &MetadataValue)> {
+    filter
+        .iter()
+        .flat_map(|(key, value)| {
+            let values: Vec<&MetadataValue> = match value {
+                FilterValue::Direct(value) => vec![value],
+                FilterValue::Operator(op) => op.in_values.iter().flatten().collect(),
+            };
+            values.into_iter().map(move |value| (key.as_str(), value))
+        })
+        .collect()
+}
+
+fn check_magic(file: &mut File, path: &Path) -> Result<()> {
+    let mut magic = [0u8; 8];
+    let valid = file.read_exact(&mut magic).is_ok() && &magic == QUERY_LOG_MAGIC;
+    if !valid {
+        return Err(VectorDbError::CorruptData {
+            path: path.display().to_string(),
+            reason: "not a query log".to_string(),
+        });
+    }
+    Ok(())
+}
+
+// lecture séquentielle d'un journal
+pub struct QueryLogReader {
+    path: PathBuf,
+    reader: BufReader<File>,
+}
+
+impl QueryLogReader {
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let mut file = File::open(&path)?;
+        check_magic(&mut file, &path)?;
+        Ok(Self { path, reader: BufReader::new(file) })
+    }
+
+    fn corrupt(&self, reason: String) -> VectorDbError {
+        VectorDbError::CorruptData { path: self.path.display().to_string(), reason }
+    }
+
+    fn read_entry(&mut self) -> Result<Option<QueryLogEntry>> {
+        let mut len = [0u8; 4];
+        match self.reader.read_exact(&mut len) {
+            Ok(()) => {}
+            Err(e) if e.kind() == ErrorKind::UnexpectedEof => return Ok(None),
+            Err(e) => return Err(e.into()),
+        }
+        let len = u32::from_le_bytes(len);
+        if len > MAX_RECORD_BYTES {
+            return Err(self.corrupt(format!("record length {} exceeds the maximum", len)));
+        }
+        let mut body = vec![0u8; len as usize];
+        match self.reader.read_exact(&mut body) {
+            Ok(()) => {}
+            // écriture interrompue en fin de fichier
+            Err(e) if e.kind() == ErrorKind::UnexpectedEof => return Ok(None),
+            Err(e) => return Err(e.into()),
+        }
+        bincode::deserialize(&body).map(Some).map_err(|e| self.corrupt(e.to_string()))
+    }
+}
+
+impl Iterator for QueryLogReader {
+    type Item = Result<QueryLogEntry>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.read_entry().transpose()
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ReplayOptions {
+    // rejoue sur cette collection plutôt que celle du journal
+    pub collection: Option<String>,
+    // compare chaque résultat à la recherche exhaustive (rappel de l'index)
+    pub check_recall: bool,
+    pub limit: Option<usize>,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ReplayReport {
+    pub replayed: usize,
+    // journalisées en mode hash, sans embedding à rejouer
+    pub skipped: usize,
+    pub failed: usize,
+    pub logged_p50_ms: f64,
+    pub logged_p95_ms: f64,
+    pub replay_p50_ms: f64,
+    pub replay_p95_ms: f64,
+    // rappel moyen par rapport à la recherche exhaustive (check_recall)
+    pub mean_recall: Option<f64>,
+}
+
+// réexécute les requêtes du journal ; une requête en erreur (collection absente,
+// dimension) est comptée dans failed sans interrompre le rejeu
+pub fn replay<P: AsRef<Path>>(client: &VectorDbClient, path: P, options: &ReplayOptions) -> Result<ReplayReport> {
+    let mut report = ReplayReport::default();
+    let mut logged = LatencyWindow::default();
+    let mut replayed = LatencyWindow::default();
+    let mut recall_sum = 0.0;
+
+    for entry in QueryLogReader::open(path)?.take(options.limit.unwrap_or(usize::MAX)) {
+        let entry = entry?;
+        let LoggedEmbedding::Full(ref embedding) = entry.embedding else {
+            report.skipped += 1;
+            continue;
+        };
+        let collection = options.collection.as_deref().unwrap_or(&entry.collection);
+
+        let outcome = client.with_collection_mut(collection, |coll| {
+            let filter = match entry.where_filter {
+                Some(ref json) => {
+                    let value: serde_json::Value = serde_json::from_str(json)
+                        .map_err(|e| VectorDbError::InvalidConfig(format!("invalid logged filter: {}", e)))?;
+                    Some(parse_filter(&value)?)
+                }
+                None => None,
+            };
+            let query_options = QueryOptions { exact: entry.exact, ..Default::default() };
+            let start = Instant::now();
+            let results = coll.query_with_options(embedding, entry.n_results, filter.as_ref(), &query_options)?;
+            let elapsed = start.elapsed();
+
+            let recall = if options.check_recall {
+                let truth = coll.exact_results(embedding, entry.n_results, filter.as_ref(), &QueryOptions::default())?;
+                Some(recall(&results, &truth))
+            } else {
+                None
+            };
+            Ok((elapsed, recall))
+        });
+
+        match outcome {
+            Ok((elapsed, recall)) => {
+                report.replayed += 1;
+                logged.record(Duration::from_micros(entry.latency_us));
+                replayed.record(elapsed);
+                recall_sum += recall.unwrap_or(0.0);
+            }
+            Err(e) => {
+                report.failed += 1;
+                tracing::debug!(collection = %collection, error = %e, "Replayed query failed");
+            }
+        }
+    }
+
+    report.logged_p50_ms = logged.percentile_ms(50.0);
+    report.logged_p95_ms = logged.percentile_ms(95.0);
+    report.replay_p50_ms = replayed.percentile_ms(50.0);
+    report.replay_p95_ms = replayed.percentile_ms(95.0);
+    if options.check_recall && report.replayed > 0 {
+        report.mean_recall = Some(recall_sum / report.replayed as f64);
+    }
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::TempDir;
+
+    #[test]
+    fn test_log_roundtrip_and_replay() {
+        let dir = TempDir::new("querylog");
+        let client = VectorDbClient::new(dir.join("db")).unwrap();
+        client.create_collection("docs".into(), 2).unwrap();
+        client
+            .with_collection_mut("docs", |coll| {
+                coll.add(vec!["a".into(), "b".into()], vec![vec![1.0, 0.0], vec![0.0, 1.0]], None)
+            })
+            .unwrap();
+
+        let log_path = dir.join("queries.log");
+        let filter = serde_json::json!({"lang": "fr"});
+        {
+            let log = QueryLog::open(&log_path, QueryLogMode::Full).unwrap();
+            let entry = log.entry("docs", &[1.0, 0.0], None, 1, false, Duration::from_micros(250));
+            log.record(&entry).unwrap();
+            log.record(&log.entry("missing", &[1.0, 0.0], Some(&filter), 1, false, Duration::ZERO)).unwrap();
+        }
+        // réouverture en ajout, mode hash
+        let log = QueryLog::open(&log_path, QueryLogMode::Hash).unwrap();
+        log.record(&log.entry("docs", &[1.0, 0.0], None, 1, false, Duration::ZERO)).unwrap();
+        drop(log);
+        // enregistrement tronqué par un arrêt brutal
+        OpenOptions::new().append(true).open(&log_path).unwrap().write_all(&[9, 0, 0, 0, 1]).unwrap();
+
+        let entries: Vec<QueryLogEntry> = QueryLogReader::open(&log_path).unwrap().map(|e| e.unwrap()).collect();
+        assert_eq!(entries.len(), 3);
+        assert_eq!(entries[0].embedding, LoggedEmbedding::Full(vec![1.0, 0.0]));
+        assert_eq!(entries[0].latency_us, 250);
+        assert_eq!(entries[1].where_filter.as_deref(), Some(r#"{"lang":"fr"}"#));
+        assert!(matches!(entries[2].embedding, LoggedEmbedding::Hash(_)));
+
+        let options = ReplayOptions { check_recall: true, ..Default::default() };
+        let report = replay(&client, &log_path, &options).unwrap();
+        assert_eq!((report.replayed, report.failed, report.skipped), (1, 1, 1));
+        assert_eq!(report.mean_recall, Some(1.0));
+
+        std::fs::write(dir.join("other"), b"not a log").unwrap();
+        assert!(QueryLogReader::open(dir.join("other")).is_err());
+    }
+}
@@ -0,0 +1,222 @@
+// instantanés de lecture copiés à l'écriture (config.concurrency = snapshot) : les
+// requêtes lisent la dernière version publiée, prise en un clone d'Arc, et ne
+// tiennent aucun verrou de collection pendant la recherche. L'unique écrivain
+// applique ses modifications sous le verrou de la collection puis publie une
+// nouvelle version. Les entrées sont réparties en VIEW_SHARDS tables partagées par
+// Arc : une écriture ne recopie que les tables des ids modifiés (des pointeurs), les
+// autres restent communes aux deux versions. Contrepartie : une copie f32 des
+// embeddings normalisés à côté de la collection, et une recherche toujours exhaustive
+use crate::collection::{rank_candidates, CollectionConfig, QueryOptions, SearchResult};
+use crate::distance::normalize_l2;
+use crate::error::{Result, VectorDbError};
+use crate::filter::{matches_filter, validate_filter, WhereFilter};
+use crate::vector::VectorEntry;
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::sync::{Arc, RwLock};
+
+// tables d'entrées d'une version ; une écriture en recopie au plus une par id modifié
+pub const VIEW_SHARDS: usize = 64;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConcurrencyMode {
+    // requêtes sous le verrou de la collection
+    #[default]
+    Locked,
+    Snapshot,
+}
+
+impl ConcurrencyMode {
+    pub fn name(self) -> &'static str {
+        match self {
+            ConcurrencyMode::Locked => "locked",
+            ConcurrencyMode::Snapshot => "snapshot",
+        }
+    }
+}
+
+// id modifié depuis la dernière publication, avec son embedding normalisé ; None : supprimé
+pub type ViewChange = (String, Option<VectorEntry>);
+
+type Shard = HashMap<String, Arc<VectorEntry>>;
+
+#[derive(Debug, Clone)]
+pub struct ReadView {
+    config: CollectionConfig,
+    // numéro de publication, 1 pour la première
+    version: u64,
+    len: usize,
+    shards: Vec<Arc<Shard>>,
+}
+
+impl ReadView {
+    pub(crate) fn new(config: &CollectionConfig, entries: impl IntoIterator<Item = VectorEntry>) -> Self {
+        let mut shards: Vec<Shard> = vec![HashMap::new(); VIEW_SHARDS];
+        for entry in entries {
+            shards[shard_of(&entry.id)].insert(entry.id.clone(), Arc::new(entry));
+        }
+        Self {
+            config: config.clone(),
+            version: 1,
+            len: shards.iter().map(HashMap::len).sum(),
+            shards: shards.into_iter().map(Arc::new).collect(),
+        }
+    }
+
+    // version suivante : seules les tables touchées par `changes` sont recopiées
+    pub(crate) fn apply(&self, config: &CollectionConfig, changes: Vec<ViewChange>) -> Self {
+        let mut next = Self { config: config.clone(), version: self.version + 1, ..self.clone() };
+        for (id, entry) in changes {
+            let shard = Arc::make_mut(&mut next.shards[shard_of(&id)]);
+            match entry {
+                Some(entry) => {
+                    if shard.insert(id, Arc::new(entry)).is_none() {
+                        next.len += 1;
+                    }
+                }
+                None => {
+                    if shard.remove(&id).is_some() {
+                        next.len -= 1;
+                    }
+                }
+            }
+        }
+        next
+    }
+
+    pub fn config(&self) -> &CollectionConfig {
+        &self.config
+    }
+
+    pub fn version(&self) -> u64 {
+        self.version
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    // tables communes avec `other` (même Arc) : mesure de ce qu'une publication a recopié
+    pub fn shared_shards(&self, other: &ReadView) -> usize {
+        self.shards.iter().zip(&other.shards).filter(|(a, b)| Arc::ptr_eq(a, b)).count()
+    }
+
+    // recherche exhaustive ; explain_hits n'est pas disponible sur un instantané
+    pub fn query(
+        &self,
+        query_embedding: &[f32],
+        n_results: usize,
+        where_filter: Option<&WhereFilter>,
+        options: &QueryOptions,
+    ) -> Result<Vec<SearchResult>> {
+        if query_embedding.len() != self.config.dimension {
+            return Err(VectorDbError::DimensionMismatch {
+                expected: self.config.dimension,
+                actual: query_embedding.len(),
+            });
+        }
+        if options.explain_hits {
+            return Err(VectorDbError::InvalidConfig("explain_hits is not available on snapshot reads".to_string()));
+        }
+        if let Some(filter) = where_filter {
+            validate_filter(filter)?;
+        }
+        let mut normalized_query = query_embedding.to_vec();
+        normalize_l2(&mut normalized_query);
+
+        let candidates: Vec<&VectorEntry> = self
+            .shards
+            .par_iter()
+            .flat_map_iter(|shard| shard.values())
+            .map(Arc::as_ref)
+            .filter(|entry| where_filter.is_none_or(|filter| matches_filter(&entry.metadata, filter)))
+            .collect();
+        Ok(rank_candidates(&normalized_query, &candidates, Vec::new(), n_results, options))
+    }
+}
+
+fn shard_of(id: &str) -> usize {
+    let mut hasher = DefaultHasher::new();
+    id.hash(&mut hasher);
+    (hasher.finish() % VIEW_SHARDS as u64) as usize
+}
+
+// dernière version publiée d'une collection
+#[derive(Debug)]
+pub struct PublishedView {
+    current: RwLock<Arc<ReadView>>,
+}
+
+impl PublishedView {
+    pub fn new(view: ReadView) -> Self {
+        Self { current: RwLock::new(Arc::new(view)) }
+    }
+
+    // verrou tenu le temps de cloner l'Arc : la version reste lisible après une publication
+    pub fn load(&self) -> Arc<ReadView> {
+        self.current.read().unwrap().clone()
+    }
+
+    pub fn publish(&self, view: ReadView) {
+        *self.current.write().unwrap() = Arc::new(view);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::client::VectorDbClient;
+    use crate::filter::FilterBuilder;
+    use crate::testing::TempDir;
+    use crate::vector::MetadataValue;
+
+    #[test]
+    fn test_snapshot_reads() {
+        let dir = TempDir::new("snapshot-reads");
+        let client = VectorDbClient::new(&dir).unwrap();
+        let config = CollectionConfig::new("docs".to_string(), 2).with_concurrency(ConcurrencyMode::Snapshot);
+        client.create_collection_with_config(config).unwrap();
+        let metadatas = (0..200).map(|i| HashMap::from([("even".to_string(), MetadataValue::Bool(i % 2 == 0))])).collect();
+        let embeddings = (0..200).map(|i| vec![(i as f32 * 0.1).cos(), (i as f32 * 0.1).sin()]).collect();
+        let ids = (0..200).map(|i| format!("v{}", i)).collect();
+        client.with_collection_mut("docs", |c| c.add(ids, embeddings, Some(metadatas))).unwrap();
+
+        let options = QueryOptions::default();
+        let filter = FilterBuilder::new().eq("even", true).build().unwrap();
+        let locked = client
+            .with_collection_mut("docs", |c| c.query_with_options(&[0.6, 0.8], 5, Some(&filter), &options))
+            .unwrap();
+        let snapshot = client.query_snapshot("docs", &[0.6, 0.8], 5, Some(&filter), &options, None).unwrap().unwrap();
+        let ids = |hits: &[SearchResult]| hits.iter().map(|h| h.id.clone()).collect::<Vec<_>>();
+        assert_eq!(ids(&snapshot), ids(&locked));
+
+        // une version tenue par un lecteur reste intacte ; la suivante ne recopie que
+        // les tables des ids écrits
+        let before = client.read_view("docs").unwrap();
+        client.with_collection_mut("docs", |c| c.delete(vec![snapshot[0].id.clone()])).unwrap();
+        let after = client.read_view("docs").unwrap();
+        assert_eq!((before.len(), after.len()), (200, 199));
+        assert_eq!(after.version(), before.version() + 1);
+        assert_eq!(after.shared_shards(&before), VIEW_SHARDS - 1);
+        assert_eq!(before.query(&[0.6, 0.8], 1, Some(&filter), &options).unwrap()[0].id, snapshot[0].id);
+        let hits = client.query_snapshot("docs", &[0.6, 0.8], 1, Some(&filter), &options, None).unwrap().unwrap();
+        assert_eq!(hits[0].id, snapshot[1].id);
+
+        // mode relu depuis le disque, instantané recréé au chargement
+        client.clear_cache();
+        assert!(client.read_view("docs").is_none());
+        assert_eq!(client.collection_info("docs").unwrap().config.concurrency, ConcurrencyMode::Snapshot);
+        client.get_collection("docs").unwrap();
+        assert_eq!(client.read_view("docs").unwrap().len(), 199);
+
+        drop(client);
+    }
+}
@@ -0,0 +1,349 @@
+// second classement des meilleurs candidats d'une requête (cross-encoder, API de
+// reranking...) avant la troncature finale. Les appels sont synchrones : le serveur
+// les exécute hors verrou de collection, dans spawn_blocking
+use crate::collection::SearchResult;
+use crate::error::{Result, VectorDbError};
+use crate::vector::MetadataValue;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::io::{Read, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::sync::Arc;
+use std::time::Duration;
+
+// nom du reranker utilisé quand la requête n'en désigne pas
+pub const DEFAULT_RERANKER: &str = "default";
+pub const NOOP_RERANKER: &str = "noop";
+// candidats reclassés par défaut, en multiple de n_results
+pub const DEFAULT_CANDIDATES_FACTOR: usize = 4;
+
+#[derive(Debug, Clone, Copy)]
+pub struct RerankCandidate<'a> {
+    pub id: &'a str,
+    pub distance: f32,
+    pub metadata: &'a HashMap<String, MetadataValue>,
+}
+
+pub trait Reranker: Send + Sync {
+    // un score par candidat, dans l'ordre reçu ; plus grand = plus pertinent
+    fn score(&self, query: &str, candidates: &[RerankCandidate]) -> Result<Vec<f32>>;
+}
+
+// garde l'ordre de la recherche vectorielle : score = similarité cosinus
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NoopReranker;
+
+impl Reranker for NoopReranker {
+    fn score(&self, _query: &str, candidates: &[RerankCandidate]) -> Result<Vec<f32>> {
+        Ok(candidates.iter().map(|c| 1.0 - c.distance).collect())
+    }
+}
+
+// reclasse `results` (ordre de la recherche vectorielle) et garde les `n` meilleurs,
+// SearchResult::rerank_score renseigné ; à score égal l'ordre initial est conservé
+pub fn rerank(reranker: &dyn Reranker, query: &str, results: Vec<SearchResult>, n: usize) -> Result<Vec<SearchResult>> {
+    let candidates: Vec<RerankCandidate> = results
+        .iter()
+        .map(|r| RerankCandidate { id: &r.id, distance: r.distance, metadata: &r.metadata })
+        .collect();
+    let scores = reranker.score(query, &candidates)?;
+    if scores.len() != results.len() {
+        return Err(VectorDbError::Reranker(format!(
+            "expected {} scores, got {}",
+            results.len(),
+            scores.len()
+        )));
+    }
+    let mut reranked: Vec<SearchResult> = results
+        .into_iter()
+        .zip(scores)
+        .map(|(mut r, score)| {
+            r.rerank_score = Some(score);
+            r
+        })
+        .collect();
+    reranked.sort_by(|a, b| b.rerank_score.partial_cmp(&a.rerank_score).unwrap_or(std::cmp::Ordering::Equal));
+    reranked.truncate(n);
+    Ok(reranked)
+}
+
+// rerankers nommés du serveur ; "default" et "noop" sont le reranker sans effet
+// tant que "default" n'est pas remplacé (VECTORDB_RERANKER_URL)
+#[derive(Clone)]
+pub struct RerankerRegistry {
+    rerankers: HashMap<String, Arc<dyn Reranker>>,
+}
+
+impl Default for RerankerRegistry {
+    fn default() -> Self {
+        let noop: Arc<dyn Reranker> = Arc::new(NoopReranker);
+        Self {
+            rerankers: HashMap::from([
+                (DEFAULT_RERANKER.to_string(), noop.clone()),
+                (NOOP_RERANKER.to_string(), noop),
+            ]),
+        }
+    }
+}
+
+impl RerankerRegistry {
+    pub fn register(&mut self, name: String, reranker: Arc<dyn Reranker>) {
+        self.rerankers.insert(name, reranker);
+    }
+
+    pub fn get(&self, name: &str) -> Result<Arc<dyn Reranker>> {
+        self.rerankers
+            .get(name)
+            .cloned()
+            .ok_or_else(|| VectorDbError::InvalidConfig(format!("unknown reranker '{}'", name)))
+    }
+
+    pub fn names(&self) -> Vec<String> {
+        let mut names: Vec<String> = self.rerankers.keys().cloned().collect();
+        names.sort();
+        names
+    }
+}
+
+// service de reranking HTTP (text-embeddings-inference, serveur cross-encoder maison) :
+// POST {"query": .., "texts": [..]} et réponse [{"index": 0, "score": 0.9}, ...] ou
+// {"results": [{"index": 0, "relevance_score": 0.9}, ...]} (format Cohere/Jina).
+// Texte d'un candidat : sa métadonnée `document_key`, chaîne vide si absente.
+// http:// seulement : un service distant en https passe par un proxy local
+#[derive(Debug, Clone)]
+pub struct HttpReranker {
+    host: String,
+    port: u16,
+    path: String,
+    timeout: Duration,
+    document_key: String,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum RerankResponse {
+    Plain(Vec<RankedIndex>),
+    Wrapped { results: Vec<RankedIndex> },
+}
+
+#[derive(Deserialize)]
+struct RankedIndex {
+    index: usize,
+    #[serde(alias = "relevance_score")]
+    score: f32,
+}
+
+impl HttpReranker {
+    pub fn new(url: &str) -> Result<Self> {
+        let invalid = |reason: &str| VectorDbError::InvalidConfig(format!("invalid reranker URL '{}': {}", url, reason));
+        let rest = url.strip_prefix("http://").ok_or_else(|| invalid("only http:// is supported"))?;
+        let (authority, path) = match rest.find('/') {
+            Some(i) => (&rest[..i], &rest[i..]),
+            None => (rest, "/rerank"),
+        };
+        let (host, port) = match authority.rsplit_once(':') {
+            Some((host, port)) => (host, port.parse().map_err(|_| invalid("bad port"))?),
+            None => (authority, 80),
+        };
+        if host.is_empty() {
+            return Err(invalid("missing host"));
+        }
+        Ok(Self {
+            host: host.to_string(),
+            port,
+            path: path.to_string(),
+            timeout: Duration::from_secs(10),
+            document_key: "document".to_string(),
+        })
+    }
+
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    pub fn with_document_key(mut self, key: &str) -> Self {
+        self.document_key = key.to_string();
+        self
+    }
+
+    fn post_json(&self, body: &[u8]) -> Result<Vec<u8>> {
+        let addr = (self.host.as_str(), self.port)
+            .to_socket_addrs()?
+            .next()
+            .ok_or_else(|| VectorDbError::Reranker(format!("cannot resolve {}", self.host)))?;
+        let mut stream = TcpStream::connect_timeout(&addr, self.timeout)?;
+        stream.set_read_timeout(Some(self.timeout))?;
+        stream.set_write_timeout(Some(self.timeout))?;
+        write!(
+            stream,
+            "POST {} HTTP/1.1\r\nHost: {}:{}\r\nContent-Type: application/json\r\nAccept: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+            self.path,
+            self.host,
+            self.port,
+            body.len()
+        )?;
+        stream.write_all(body)?;
+        let mut response = Vec::new();
+        stream.read_to_end(&mut response)?;
+        parse_http_response(&response)
+    }
+}
+
+impl Reranker for HttpReranker {
+    fn score(&self, query: &str, candidates: &[RerankCandidate]) -> Result<Vec<f32>> {
+        if candidates.is_empty() {
+            return Ok(Vec::new());
+        }
+        let texts: Vec<&str> = candidates
+            .iter()
+            .map(|c| match c.metadata.get(&self.document_key) {
+                Some(MetadataValue::String(text)) => &**text,
+                _ => "",
+            })
+            .collect();
+        let body = serde_json::to_vec(&serde_json::json!({ "query": query, "texts": texts }))?;
+        let response = self.post_json(&body)?;
+        let ranked = match serde_json::from_slice(&response)
+            .map_err(|e| VectorDbError::Reranker(format!("invalid response: {}", e)))?
+        {
+            RerankResponse::Plain(ranked) | RerankResponse::Wrapped { results: ranked } => ranked,
+        };
+
+        let mut scores = vec![None; candidates.len()];
+        for r in ranked {
+            match scores.get_mut(r.index) {
+                Some(slot) => *slot = Some(r.score),
+                None => return Err(VectorDbError::Reranker(format!("index {} out of range", r.index))),
+            }
+        }
+        scores
+            .into_iter()
+            .enumerate()
+            .map(|(i, s)| s.ok_or_else(|| VectorDbError::Reranker(format!("no score for candidate {}", i))))
+            .collect()
+    }
+}
+
+// corps d'une réponse HTTP/1.1 complète (Content-Length, chunked ou fin de connexion)
+fn parse_http_response(response: &[u8]) -> Result<Vec<u8>> {
+    let malformed = || VectorDbError::Reranker("malformed HTTP response".to_string());
+    let split = response.windows(4).position(|w| w == b"\r\n\r\n").ok_or_else(malformed)?;
+    let head = std::str::from_utf8(&response[..split]).map_err(|_| malformed())?;
+    let body = &response[split + 4..];
+    let mut lines = head.split("\r\n");
+    let status: u16 = lines
+        .next()
+        .and_then(|line| line.split_whitespace().nth(1))
+        .and_then(|code| code.parse().ok())
+        .ok_or_else(malformed)?;
+    let chunked = lines.any(|line| {
+        line.split_once(':').is_some_and(|(name, value)| {
+            name.trim().eq_ignore_ascii_case("transfer-encoding") && value.to_ascii_lowercase().contains("chunked")
+        })
+    });
+    let body = if chunked { decode_chunked(body).ok_or_else(malformed)? } else { body.to_vec() };
+    if !(200..300).contains(&status) {
+        let text = String::from_utf8_lossy(&body);
+        return Err(VectorDbError::Reranker(format!("HTTP {}: {}", status, text.chars().take(200).collect::<String>())));
+    }
+    Ok(body)
+}
+
+fn decode_chunked(mut data: &[u8]) -> Option<Vec<u8>> {
+    let mut body = Vec::new();
+    loop {
+        let line_end = data.windows(2).position(|w| w == b"\r\n")?;
+        let size_line = std::str::from_utf8(&data[..line_end]).ok()?;
+        let size = usize::from_str_radix(size_line.split(';').next()?.trim(), 16).ok()?;
+        data = &data[line_end + 2..];
+        if size == 0 {
+            return Some(body);
+        }
+        body.extend_from_slice(data.get(..size)?);
+        data = data.get(size + 2..)?;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::TcpListener;
+
+    fn result(id: &str, distance: f32, document: &str) -> SearchResult {
+        SearchResult {
+            id: id.to_string(),
+            distance,
+            metadata: HashMap::from([("document".to_string(), MetadataValue::from(document))]),
+            explain: None,
+            rerank_score: None,
+        }
+    }
+
+    #[test]
+    fn test_noop_keeps_vector_order() {
+        let results = vec![result("a", 0.1, ""), result("b", 0.2, ""), result("c", 0.3, "")];
+        let reranked = rerank(&NoopReranker, "q", results, 2).unwrap();
+        let ids: Vec<&str> = reranked.iter().map(|r| r.id.as_str()).collect();
+        assert_eq!(ids, ["a", "b"]);
+        assert_eq!(reranked[0].rerank_score, Some(0.9));
+        assert!(RerankerRegistry::default().get("missing").is_err());
+    }
+
+    #[test]
+    fn test_http_reranker() {
+        // faux service : le candidat contenant la requête passe en tête
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let server = std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut request = Vec::new();
+            let mut buf = [0u8; 4096];
+            let body = loop {
+                let n = stream.read(&mut buf).unwrap();
+                request.extend_from_slice(&buf[..n]);
+                let Some(split) = request.windows(4).position(|w| w == b"\r\n\r\n") else { continue };
+                let head = String::from_utf8_lossy(&request[..split]).to_ascii_lowercase();
+                let len: usize = head
+                    .lines()
+                    .find_map(|l| l.strip_prefix("content-length:"))
+                    .unwrap()
+                    .trim()
+                    .parse()
+                    .unwrap();
+                if request.len() >= split + 4 + len {
+                    break serde_json::from_slice::<serde_json::Value>(&request[split + 4..split + 4 + len]).unwrap();
+                }
+            };
+            let query = body["query"].as_str().unwrap();
+            let ranked: Vec<serde_json::Value> = body["texts"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .enumerate()
+                .map(|(i, t)| {
+                    let score = if t.as_str().unwrap().contains(query) { 0.9 } else { 0.1 };
+                    serde_json::json!({ "index": i, "relevance_score": score })
+                })
+                .collect();
+            let json = serde_json::to_string(&serde_json::json!({ "results": ranked })).unwrap();
+            // réponse en un seul chunk
+            write!(
+                stream,
+                "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nTransfer-Encoding: chunked\r\n\r\n{:x}\r\n{}\r\n0\r\n\r\n",
+                json.len(),
+                json
+            )
+            .unwrap();
+        });
+
+        let reranker = HttpReranker::new(&format!("http://127.0.0.1:{}/rerank", port)).unwrap();
+        let results = vec![result("a", 0.1, "chats"), result("b", 0.2, "chiens"), result("c", 0.3, "oiseaux")];
+        let reranked = rerank(&reranker, "chiens", results, 2).unwrap();
+        server.join().unwrap();
+        assert_eq!(reranked[0].id, "b");
+        assert_eq!(reranked[0].rerank_score, Some(0.9));
+        assert_eq!(reranked[1].id, "a");
+        assert!(HttpReranker::new("https://example.com").is_err());
+    }
+}
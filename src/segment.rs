@@ -0,0 +1,725 @@
+// segments immuables d'une collection : fichiers écrits une fois (vecteurs contigus
+// dans l'ordre des listes IVF, métadonnées, centroïdes et listes finalisés, sommes de
+// contrôle), projetés en mémoire à l'ouverture (mmap) ; seul l'index (ids,
+// métadonnées) est décodé, les vecteurs sont lus en place par les requêtes.
+//
+// Une collection segmentée (style LSM) garde ses écritures récentes dans une memtable
+// en mémoire, vidée dans un nouveau segment (Collection::flush_memtable) ; les
+// suppressions et remplacements marquent la ligne du segment comme supprimée, et une
+// tâche de fond fusionne les petits segments (Collection::plan_merge). Une collection
+// figée (Collection::freeze) tient dans un seul segment et refuse toute écriture.
+//
+// format d'un segment : en-tête de 64 octets (SEGMENT_MAGIC puis dimension en u32 LE),
+// vecteurs f32 little-endian ligne par ligne, index bincode, puis un pied de 24 octets :
+// position de l'index (u64 LE), CRC32 des vecteurs, CRC32 de l'index, SEGMENT_MAGIC.
+// L'index porte aussi un filtre de Bloom sur les ids et l'ordre des lignes par id :
+// une recherche par id n'ouvre que les segments qui le contiennent probablement,
+// sans table de hachage des ids en mémoire.
+// Le manifeste (segments.bin) liste les segments vivants et leurs lignes supprimées
+use crate::bloom::BloomFilter;
+use crate::disk_ivf::read_at;
+use crate::distance::cosine_distance;
+use crate::error::{Result, VectorDbError};
+use crate::ivf::IVFIndex;
+use crate::late_interaction::TokenMatrix;
+use crate::topk::top_k_by;
+use crate::vector::MetadataValue;
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs::{self, File};
+use std::io::{BufWriter, Write};
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+pub const SEGMENTS_MANIFEST: &str = "segments.bin";
+const SEGMENT_MAGIC: &[u8; 8] = b"VDBSEG01";
+// aligne le premier vecteur, la projection commençant sur une page
+const HEADER_LEN: u64 = 64;
+const FOOTER_LEN: u64 = 24;
+// vecteurs par cluster pour entraîner les centroïdes d'un segment
+const SAMPLE_PER_CLUSTER: usize = 256;
+// segments de taille comparable fusionnés par groupes de MERGE_FACTOR
+pub const MERGE_FACTOR: usize = 4;
+
+pub fn segment_file(seq: u64) -> String {
+    format!("segment-{:06}.vdb", seq)
+}
+
+pub fn is_segment_file(name: &str) -> bool {
+    name.starts_with("segment-") && name.ends_with(".vdb")
+}
+
+// segments vivants, du plus ancien au plus récent, avec leurs lignes supprimées
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct SegmentManifest {
+    pub frozen: bool,
+    pub segments: Vec<(u64, Vec<String>)>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct SegmentIndex {
+    dimension: usize,
+    // ligne -> id, métadonnées dans le même ordre
+    ids: Vec<String>,
+    metadata: Vec<HashMap<String, MetadataValue>>,
+    centroids: Vec<Vec<f32>>,
+    // (première ligne, nombre de lignes) de chaque liste IVF
+    lists: Vec<(usize, usize)>,
+    tokens: Vec<(String, TokenMatrix)>,
+    bloom: BloomFilter,
+    // lignes triées par id, pour la recherche dichotomique
+    by_id: Vec<u32>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SegmentInfo {
+    pub seq: u64,
+    pub path: String,
+    pub vectors: usize,
+    // lignes supprimées ou remplacées depuis l'écriture du segment
+    pub deleted: usize,
+    pub n_lists: usize,
+    pub file_bytes: u64,
+    // taille du filtre de Bloom des ids, en mémoire
+    pub bloom_bytes: usize,
+    // vecteurs lus en place dans le fichier projeté, sinon copiés en mémoire
+    pub mapped: bool,
+    // présent après une vérification (VectorDbClient::verify_segments)
+    #[serde(default)]
+    pub checksum_ok: Option<bool>,
+}
+
+#[derive(Debug)]
+pub struct Segment {
+    path: PathBuf,
+    file_bytes: u64,
+    dimension: usize,
+    vectors: Vectors,
+    vectors_crc: u32,
+    ids: Vec<String>,
+    bloom: BloomFilter,
+    by_id: Vec<u32>,
+    centroids: Vec<Vec<f32>>,
+    lists: Vec<(usize, usize)>,
+    // rendus une fois à la collection (take_metadata, take_tokens)
+    metadata: Vec<HashMap<String, MetadataValue>>,
+    tokens: Vec<(String, TokenMatrix)>,
+}
+
+impl Segment {
+    pub fn open(path: &Path) -> Result<Self> {
+        let corrupt = |reason: &str| VectorDbError::CorruptData {
+            path: path.display().to_string(),
+            reason: reason.to_string(),
+        };
+        let file = File::open(path)?;
+        let file_bytes = file.metadata()?.len();
+        if file_bytes < HEADER_LEN + FOOTER_LEN {
+            return Err(corrupt("file too short"));
+        }
+
+        let mut header = [0u8; HEADER_LEN as usize];
+        read_at(&file, &mut header, 0)?;
+        let mut footer = [0u8; FOOTER_LEN as usize];
+        read_at(&file, &mut footer, file_bytes - FOOTER_LEN)?;
+        if &header[..8] != SEGMENT_MAGIC || &footer[16..] != SEGMENT_MAGIC {
+            return Err(corrupt("not a segment file"));
+        }
+        let index_offset = u64::from_le_bytes(footer[..8].try_into().unwrap());
+        let vectors_crc = u32::from_le_bytes(footer[8..12].try_into().unwrap());
+        let index_crc = u32::from_le_bytes(footer[12..16].try_into().unwrap());
+        if index_offset < HEADER_LEN || index_offset > file_bytes - FOOTER_LEN {
+            return Err(corrupt("index offset out of range"));
+        }
+
+        let mut raw = vec![0u8; (file_bytes - FOOTER_LEN - index_offset) as usize];
+        read_at(&file, &mut raw, index_offset)?;
+        if crc32fast::hash(&raw) != index_crc {
+            return Err(corrupt("index checksum mismatch"));
+        }
+        let index: SegmentIndex = bincode::deserialize(&raw)?;
+        drop(raw);
+
+        let dimension = u32::from_le_bytes(header[8..12].try_into().unwrap()) as usize;
+        let n_floats = index.ids.len() * dimension;
+        if dimension != index.dimension || index.metadata.len() != index.ids.len() {
+            return Err(corrupt("inconsistent index"));
+        }
+        if HEADER_LEN + (n_floats * 4) as u64 != index_offset {
+            return Err(corrupt("vector block size mismatch"));
+        }
+        if index.lists.len() != index.centroids.len()
+            || index.lists.iter().any(|&(start, len)| start + len > index.ids.len())
+        {
+            return Err(corrupt("list out of range"));
+        }
+        if index.by_id.len() != index.ids.len() || index.by_id.iter().any(|&row| row as usize >= index.ids.len()) {
+            return Err(corrupt("id order out of range"));
+        }
+
+        let vectors = Vectors::load(&file, n_floats)?;
+        Ok(Self {
+            path: path.to_path_buf(),
+            file_bytes,
+            dimension,
+            vectors,
+            vectors_crc,
+            ids: index.ids,
+            bloom: index.bloom,
+            by_id: index.by_id,
+            centroids: index.centroids,
+            lists: index.lists,
+            metadata: index.metadata,
+            tokens: index.tokens,
+        })
+    }
+
+    // relit tout le bloc de vecteurs : l'ouverture ne contrôle que l'index
+    pub fn verify(&self) -> bool {
+        let mut hasher = crc32fast::Hasher::new();
+        let mut bytes = Vec::with_capacity(64 * 1024);
+        for chunk in self.vectors.as_slice().chunks(16 * 1024) {
+            bytes.clear();
+            bytes.extend(chunk.iter().flat_map(|v| v.to_le_bytes()));
+            hasher.update(&bytes);
+        }
+        hasher.finalize() == self.vectors_crc
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn file_bytes(&self) -> u64 {
+        self.file_bytes
+    }
+
+    pub fn dimension(&self) -> usize {
+        self.dimension
+    }
+
+    pub fn len(&self) -> usize {
+        self.ids.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ids.is_empty()
+    }
+
+    pub fn ids(&self) -> &[String] {
+        &self.ids
+    }
+
+    // filtre de Bloom d'abord : la plupart des segments sont écartés sans recherche
+    pub fn row(&self, id: &str) -> Option<usize> {
+        if !self.bloom.may_contain(id) {
+            return None;
+        }
+        self.by_id
+            .binary_search_by(|&row| self.ids[row as usize].as_str().cmp(id))
+            .ok()
+            .map(|pos| self.by_id[pos] as usize)
+    }
+
+    pub fn vector(&self, row: usize) -> &[f32] {
+        &self.vectors.as_slice()[row * self.dimension..(row + 1) * self.dimension]
+    }
+
+    pub fn n_lists(&self) -> usize {
+        self.lists.len()
+    }
+
+    // lignes des `limit` listes IVF les plus proches de la requête, contiguës dans le
+    // fichier ; toutes les lignes pour un segment sans index
+    pub fn probed_rows(&self, query: &[f32], limit: usize) -> Vec<Range<usize>> {
+        if self.lists.is_empty() {
+            return std::iter::once(0..self.ids.len()).collect();
+        }
+        let distances = self.centroids.iter().enumerate().map(|(idx, c)| (idx, cosine_distance(query, c)));
+        top_k_by(distances, limit, |a, b| a.1.total_cmp(&b.1))
+            .into_iter()
+            .map(|(list, _)| {
+                let (start, len) = self.lists[list];
+                start..start + len
+            })
+            .collect()
+    }
+
+    pub fn take_metadata(&mut self) -> Vec<(String, HashMap<String, MetadataValue>)> {
+        self.ids.iter().cloned().zip(std::mem::take(&mut self.metadata)).collect()
+    }
+
+    pub fn take_tokens(&mut self) -> Vec<(String, TokenMatrix)> {
+        std::mem::take(&mut self.tokens)
+    }
+}
+
+// segment d'une collection et ses lignes supprimées ou remplacées depuis : un id
+// n'a de ligne vivante que dans un seul segment au plus
+#[derive(Debug, Clone)]
+pub struct LiveSegment {
+    pub seq: u64,
+    pub segment: Arc<Segment>,
+    pub deleted: HashSet<String>,
+}
+
+impl LiveSegment {
+    pub fn live_row(&self, id: &str) -> Option<usize> {
+        self.segment.row(id).filter(|_| !self.deleted.contains(id))
+    }
+
+    pub fn live_len(&self) -> usize {
+        self.segment.len() - self.deleted.len()
+    }
+
+    pub fn info(&self) -> SegmentInfo {
+        SegmentInfo {
+            seq: self.seq,
+            path: self.segment.path.display().to_string(),
+            vectors: self.segment.len(),
+            deleted: self.deleted.len(),
+            n_lists: self.segment.n_lists(),
+            file_bytes: self.segment.file_bytes,
+            bloom_bytes: self.segment.bloom.bytes(),
+            mapped: self.segment.vectors.is_mapped(),
+            checksum_ok: None,
+        }
+    }
+}
+
+// segments à fusionner : un segment dont plus de la moitié des lignes sont supprimées
+// est réécrit seul ; sinon MERGE_FACTOR segments du plus petit palier de taille
+// (puissances de MERGE_FACTOR) qui en compte assez. Indices dans `segments`
+pub fn merge_candidates(segments: &[LiveSegment]) -> Vec<usize> {
+    if let Some(idx) = segments.iter().position(|s| s.deleted.len() * 2 > s.segment.len()) {
+        return vec![idx];
+    }
+    let mut tiers: BTreeMap<u32, Vec<usize>> = BTreeMap::new();
+    for (idx, segment) in segments.iter().enumerate() {
+        tiers.entry(segment.live_len().max(1).ilog(MERGE_FACTOR)).or_default().push(idx);
+    }
+    tiers
+        .into_values()
+        .find(|tier| tier.len() >= MERGE_FACTOR)
+        .map(|tier| tier[..MERGE_FACTOR].to_vec())
+        .unwrap_or_default()
+}
+
+// écrit un segment avec `ids` (vecteurs normalisés et métadonnées fournis par les
+// fonctions d'accès) : avec n_clusters > 0, centroïdes entraînés sur un échantillon
+// et lignes rangées liste par liste ; sans, dans l'ordre des ids
+pub fn write_segment<'a, V, M>(
+    path: &Path,
+    dimension: usize,
+    n_clusters: usize,
+    mut ids: Vec<String>,
+    vector_of: V,
+    metadata_of: M,
+    tokens: Vec<(String, TokenMatrix)>,
+) -> Result<Segment>
+where
+    V: Fn(&str) -> Result<Cow<'a, [f32]>> + Sync,
+    M: Fn(&str) -> &'a HashMap<String, MetadataValue>,
+{
+    ids.sort_unstable();
+    let (centroids, lists) = if n_clusters > 0 && !ids.is_empty() {
+        let sample_len = n_clusters.saturating_mul(SAMPLE_PER_CLUSTER).min(ids.len());
+        let sample = rand::seq::index::sample(&mut rand::thread_rng(), ids.len(), sample_len)
+            .into_iter()
+            .map(|i| Ok((ids[i].clone(), vector_of(&ids[i])?.into_owned())))
+            .collect::<Result<Vec<_>>>()?;
+        let mut ivf = IVFIndex::new(n_clusters);
+        ivf.build(&sample);
+        drop(sample);
+
+        let nearest = ids
+            .par_iter()
+            .map(|id| Ok(ivf.nearest_centroid(&vector_of(id)?)))
+            .collect::<Result<Vec<_>>>()?;
+        let mut lists = vec![Vec::new(); ivf.centroids.len()];
+        for (id, cluster) in ids.into_iter().zip(nearest) {
+            lists[cluster].push(id);
+        }
+        (ivf.centroids, lists)
+    } else {
+        (Vec::new(), vec![ids])
+    };
+
+    let with_lists = !centroids.is_empty();
+    let mut writer = SegmentWriter::create(path, dimension, centroids)?;
+    for list in &lists {
+        for id in list {
+            writer.write_row(id, &vector_of(id)?, metadata_of(id))?;
+        }
+        if with_lists {
+            writer.end_list();
+        }
+    }
+    writer.finish(tokens)
+}
+
+// fusion préparée sous verrou (Collection::plan_merge) : lignes vivantes des segments
+// source et leurs métadonnées ; run() écrit le segment fusionné hors verrou
+pub struct MergeJob {
+    pub(crate) path: PathBuf,
+    pub(crate) seq: u64,
+    pub(crate) dimension: usize,
+    pub(crate) n_clusters: usize,
+    pub(crate) sources: Vec<LiveSegment>,
+    pub(crate) metadata: HashMap<String, HashMap<String, MetadataValue>>,
+    pub(crate) tokens: Vec<(String, TokenMatrix)>,
+}
+
+pub struct MergedSegment {
+    pub(crate) seq: u64,
+    pub(crate) segment: Segment,
+    // état des sources au moment de la préparation : les suppressions reçues depuis
+    // sont reportées sur le segment fusionné (Collection::finish_merge)
+    pub(crate) sources: Vec<LiveSegment>,
+}
+
+impl MergeJob {
+    pub fn run(self) -> Result<MergedSegment> {
+        let location: HashMap<&str, (usize, usize)> = self
+            .metadata
+            .keys()
+            .filter_map(|id| {
+                self.sources
+                    .iter()
+                    .enumerate()
+                    .find_map(|(idx, source)| source.live_row(id).map(|row| (id.as_str(), (idx, row))))
+            })
+            .collect();
+        let ids = location.keys().map(|id| id.to_string()).collect();
+        let segment = write_segment(
+            &self.path,
+            self.dimension,
+            self.n_clusters,
+            ids,
+            |id| {
+                let (source, row) = location[id];
+                Ok(Cow::Borrowed(self.sources[source].segment.vector(row)))
+            },
+            |id| &self.metadata[id],
+            self.tokens,
+        )?;
+        Ok(MergedSegment { seq: self.seq, segment, sources: self.sources })
+    }
+}
+
+// écriture d'un segment ligne par ligne, liste IVF après liste IVF ; le fichier
+// n'apparaît qu'à finish()
+pub struct SegmentWriter {
+    path: PathBuf,
+    tmp_path: PathBuf,
+    writer: BufWriter<File>,
+    hasher: crc32fast::Hasher,
+    list_start: usize,
+    index: SegmentIndex,
+}
+
+impl SegmentWriter {
+    pub fn create(path: &Path, dimension: usize, centroids: Vec<Vec<f32>>) -> Result<Self> {
+        let tmp_path = path.with_extension("vdb.tmp");
+        let mut writer = BufWriter::with_capacity(512 * 1024, File::create(&tmp_path)?);
+        let mut header = [0u8; HEADER_LEN as usize];
+        header[..8].copy_from_slice(SEGMENT_MAGIC);
+        header[8..12].copy_from_slice(&(dimension as u32).to_le_bytes());
+        writer.write_all(&header)?;
+        Ok(Self {
+            path: path.to_path_buf(),
+            tmp_path,
+            writer,
+            hasher: crc32fast::Hasher::new(),
+            list_start: 0,
+            index: SegmentIndex {
+                dimension,
+                ids: Vec::new(),
+                metadata: Vec::new(),
+                centroids,
+                lists: Vec::new(),
+                tokens: Vec::new(),
+                bloom: BloomFilter::with_capacity(0),
+                by_id: Vec::new(),
+            },
+        })
+    }
+
+    pub fn write_row(&mut self, id: &str, embedding: &[f32], metadata: &HashMap<String, MetadataValue>) -> Result<()> {
+        if embedding.len() != self.index.dimension {
+            return Err(VectorDbError::DimensionMismatch { expected: self.index.dimension, actual: embedding.len() });
+        }
+        for value in embedding {
+            let bytes = value.to_le_bytes();
+            self.hasher.update(&bytes);
+            self.writer.write_all(&bytes)?;
+        }
+        self.index.ids.push(id.to_string());
+        self.index.metadata.push(metadata.clone());
+        Ok(())
+    }
+
+    // les lignes écrites depuis la liste précédente forment la liste du cluster suivant
+    pub fn end_list(&mut self) {
+        let end = self.index.ids.len();
+        self.index.lists.push((self.list_start, end - self.list_start));
+        self.list_start = end;
+    }
+
+    pub fn finish(mut self, tokens: Vec<(String, TokenMatrix)>) -> Result<Segment> {
+        if self.index.lists.len() != self.index.centroids.len() {
+            return Err(VectorDbError::InvalidConfig(format!(
+                "segment: {} lists written for {} centroids",
+                self.index.lists.len(),
+                self.index.centroids.len()
+            )));
+        }
+        self.index.tokens = tokens;
+        let mut bloom = BloomFilter::with_capacity(self.index.ids.len());
+        for id in &self.index.ids {
+            bloom.insert(id);
+        }
+        self.index.bloom = bloom;
+        let ids = &self.index.ids;
+        let mut by_id: Vec<u32> = (0..ids.len() as u32).collect();
+        by_id.sort_unstable_by(|&a, &b| ids[a as usize].cmp(&ids[b as usize]));
+        self.index.by_id = by_id;
+        let index_offset = HEADER_LEN + (self.index.ids.len() * self.index.dimension * 4) as u64;
+        let raw = bincode::serialize(&self.index)?;
+        self.writer.write_all(&raw)?;
+        self.writer.write_all(&index_offset.to_le_bytes())?;
+        self.writer.write_all(&self.hasher.finalize().to_le_bytes())?;
+        self.writer.write_all(&crc32fast::hash(&raw).to_le_bytes())?;
+        self.writer.write_all(SEGMENT_MAGIC)?;
+        let file = self.writer.into_inner().map_err(|e| e.into_error())?;
+        file.sync_all()?;
+        fs::rename(&self.tmp_path, &self.path)?;
+        let mut segment = Segment::open(&self.path)?;
+        // déjà en mémoire chez l'appelant
+        drop(segment.take_metadata());
+        drop(segment.take_tokens());
+        Ok(segment)
+    }
+}
+
+// bloc de vecteurs : projection du fichier là où les f32 du disque sont directement
+// lisibles (unix, little-endian), copie en mémoire ailleurs
+#[derive(Debug)]
+enum Vectors {
+    #[cfg(all(unix, target_endian = "little"))]
+    Mapped(Mapping),
+    Owned(Vec<f32>),
+}
+
+impl Vectors {
+    #[cfg(all(unix, target_endian = "little"))]
+    fn load(file: &File, n_floats: usize) -> Result<Self> {
+        if n_floats == 0 {
+            return Ok(Vectors::Owned(Vec::new()));
+        }
+        Mapping::new(file, n_floats).map(Vectors::Mapped)
+    }
+
+    #[cfg(not(all(unix, target_endian = "little")))]
+    fn load(file: &File, n_floats: usize) -> Result<Self> {
+        let mut raw = vec![0u8; n_floats * 4];
+        read_at(file, &mut raw, HEADER_LEN)?;
+        Ok(Vectors::Owned(raw.chunks_exact(4).map(|b| f32::from_le_bytes(b.try_into().unwrap())).collect()))
+    }
+
+    fn as_slice(&self) -> &[f32] {
+        match self {
+            #[cfg(all(unix, target_endian = "little"))]
+            Vectors::Mapped(mapping) => mapping.floats(),
+            Vectors::Owned(vectors) => vectors,
+        }
+    }
+
+    fn is_mapped(&self) -> bool {
+        !matches!(self, Vectors::Owned(_))
+    }
+}
+
+// projection en lecture seule du début du fichier, libérée avec le segment
+#[cfg(all(unix, target_endian = "little"))]
+#[derive(Debug)]
+struct Mapping {
+    ptr: *mut libc::c_void,
+    len: usize,
+    n_floats: usize,
+}
+
+// la projection n'est jamais modifiée : partageable entre threads
+#[cfg(all(unix, target_endian = "little"))]
+unsafe impl Send for Mapping {}
+#[cfg(all(unix, target_endian = "little"))]
+unsafe impl Sync for Mapping {}
+
+#[cfg(all(unix, target_endian = "little"))]
+impl Mapping {
+    fn new(file: &File, n_floats: usize) -> Result<Self> {
+        use std::os::unix::io::AsRawFd;
+        let len = HEADER_LEN as usize + n_floats * 4;
+        // SAFETY: projection privée en lecture seule d'un fichier ouvert, longueur
+        // non nulle vérifiée par l'appelant et bornée par la taille du fichier
+        let ptr = unsafe {
+            libc::mmap(std::ptr::null_mut(), len, libc::PROT_READ, libc::MAP_PRIVATE, file.as_raw_fd(), 0)
+        };
+        if ptr == libc::MAP_FAILED {
+            return Err(std::io::Error::last_os_error().into());
+        }
+        Ok(Self { ptr, len, n_floats })
+    }
+
+    fn floats(&self) -> &[f32] {
+        // SAFETY: les n_floats f32 suivent l'en-tête dans la projection (taille vérifiée
+        // à l'ouverture), alignés sur 4 octets puisque la projection commence sur
+        // une page et l'en-tête fait 64 octets
+        unsafe { std::slice::from_raw_parts((self.ptr as *const u8).add(HEADER_LEN as usize) as *const f32, self.n_floats) }
+    }
+}
+
+#[cfg(all(unix, target_endian = "little"))]
+impl Drop for Mapping {
+    fn drop(&mut self) {
+        // SAFETY: ptr et len viennent du mmap de Mapping::new
+        unsafe {
+            libc::munmap(self.ptr, self.len);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::TempDir;
+    use crate::collection::{Collection, CollectionConfig, SearchResult};
+    use crate::storage::Storage;
+
+    fn embedding(i: usize) -> Vec<f32> {
+        vec![1.0 + i as f32 * 0.01, (i * 37 % 101) as f32, (i * 53 % 89) as f32, 1.0]
+    }
+
+    fn add_range(coll: &mut Collection, range: Range<usize>) {
+        let ids = range.clone().map(|i| format!("v{}", i)).collect();
+        let metadatas = range.clone().map(|i| HashMap::from([("n".to_string(), MetadataValue::Int(i as i64))])).collect();
+        coll.add(ids, range.map(embedding).collect(), Some(metadatas)).unwrap();
+    }
+
+    fn ids(results: &[SearchResult]) -> Vec<String> {
+        results.iter().map(|r| r.id.clone()).collect()
+    }
+
+    // mêmes résultats, embeddings et métadonnées qu'une collection sans segments
+    fn check(coll: &mut Collection, reference: &mut Collection) {
+        assert_eq!(coll.count(), reference.count());
+        for query in [[3.0, 1.0, 2.0, 1.0], [1.0, 90.0, 5.0, 1.0], [2.0, 40.0, 80.0, 1.0]] {
+            assert_eq!(ids(&coll.query(&query, 10, None).unwrap()), ids(&reference.query(&query, 10, None).unwrap()));
+        }
+        let got = coll.get(Some(vec!["v5".into(), "v120".into()]), None).unwrap();
+        let expected = reference.get(Some(vec!["v5".into(), "v120".into()]), None).unwrap();
+        assert_eq!(got.embeddings, expected.embeddings);
+        assert_eq!(got.metadatas.unwrap()[1]["n"], MetadataValue::Int(-1));
+    }
+
+    #[test]
+    fn test_freeze_serves_queries_and_refuses_writes() {
+        let dir = TempDir::new("segment");
+        let storage = Storage::new(&dir).unwrap();
+
+        let mut coll = Collection::from_config(CollectionConfig::new("frozen".to_string(), 4).with_ivf(4));
+        add_range(&mut coll, 0..200);
+        coll.rebuild_index();
+        storage.save_collection(&coll).unwrap();
+        let query = [3.0, 1.0, 2.0, 1.0];
+        let before = coll.query(&query, 10, None).unwrap();
+
+        coll.freeze(&storage.collection_path("frozen")).unwrap();
+        assert!(coll.is_frozen());
+        let segments = coll.verify_segments();
+        assert_eq!(segments.len(), 1);
+        assert_eq!((segments[0].vectors, segments[0].checksum_ok), (200, Some(true)));
+        assert_eq!(ids(&coll.query(&query, 10, None).unwrap()), ids(&before));
+        assert!(matches!(
+            coll.add(vec!["x".into()], vec![vec![1.0; 4]], None),
+            Err(VectorDbError::Frozen(_))
+        ));
+
+        // rechargé depuis le segment seul
+        storage.save_collection(&coll).unwrap();
+        let mut reloaded = storage.load_collection("frozen").unwrap();
+        assert!(reloaded.is_frozen());
+        assert_eq!(reloaded.count(), 200);
+        assert_eq!(ids(&reloaded.query(&query, 10, None).unwrap()), ids(&before));
+
+        reloaded.thaw().unwrap();
+        reloaded.add(vec!["x".into()], vec![vec![1.0; 4]], None).unwrap();
+        storage.save_collection(&reloaded).unwrap();
+        assert!(!storage.collection_path("frozen").join(segment_file(1)).exists());
+        assert_eq!(storage.load_collection("frozen").unwrap().count(), 201);
+    }
+
+    #[test]
+    fn test_memtable_flush_and_merge() {
+        let dir = TempDir::new("lsm");
+        let storage = Storage::new(&dir).unwrap();
+        let coll_dir = storage.collection_path("lsm");
+        fs::create_dir_all(&coll_dir).unwrap();
+
+        // même historique d'écritures, sans segments
+        let mut coll = Collection::from_config(CollectionConfig::new("lsm".to_string(), 4).with_ivf(4));
+        let mut reference = Collection::new("reference".to_string(), 4);
+        for batch in 0..MERGE_FACTOR {
+            add_range(&mut coll, batch * 50..(batch + 1) * 50);
+            add_range(&mut reference, batch * 50..(batch + 1) * 50);
+            assert_eq!(coll.flush_memtable(&coll_dir).unwrap().unwrap().vectors, 50);
+        }
+        assert_eq!(coll.memtable_len(), 0);
+        for target in [&mut coll, &mut reference] {
+            add_range(target, 200..210);
+            target.add(vec!["v5".into()], vec![embedding(300)], None).unwrap();
+            target.delete(vec!["v60".into()]).unwrap();
+            target.update(vec!["v120".into()], vec![HashMap::from([("n".to_string(), MetadataValue::Int(-1))])]).unwrap();
+        }
+        assert_eq!(coll.memtable_len(), 12);
+
+        check(&mut coll, &mut reference);
+
+        // suppression reçue pendant la fusion, reportée sur le segment fusionné
+        let job = coll.plan_merge(&coll_dir).unwrap();
+        assert_eq!(job.sources.len(), MERGE_FACTOR);
+        let merged = job.run().unwrap();
+        coll.delete(vec!["v7".into()]).unwrap();
+        reference.delete(vec!["v7".into()]).unwrap();
+        assert!(coll.finish_merge(merged));
+        let segments = coll.segment_infos();
+        assert_eq!(segments.len(), 1);
+        assert_eq!(segments[0].vectors - segments[0].deleted, 200 - 4);
+        assert!(segments[0].bloom_bytes >= segments[0].vectors * crate::bloom::BITS_PER_KEY / 8);
+        let segment = Segment::open(Path::new(&segments[0].path)).unwrap();
+        // v5, v60 et v120 étaient déjà réécrits ou supprimés avant la fusion
+        for i in 0..210 {
+            let id = format!("v{}", i);
+            let expected = i < 200 && ![5, 60, 120].contains(&i);
+            assert_eq!(segment.row(&id).map(|row| segment.ids()[row] == id), expected.then_some(true));
+        }
+        assert!(coll.plan_merge(&coll_dir).is_none());
+        check(&mut coll, &mut reference);
+
+        // rechargé depuis le manifeste : un seul fichier de segment
+        storage.save_collection(&coll).unwrap();
+        let files = fs::read_dir(&coll_dir)
+            .unwrap()
+            .filter(|e| is_segment_file(&e.as_ref().unwrap().file_name().to_string_lossy()))
+            .count();
+        assert_eq!(files, 1);
+        let mut reloaded = storage.load_collection("lsm").unwrap();
+        assert_eq!(reloaded.memtable_len(), 12);
+        check(&mut reloaded, &mut reference);
+        let metadata = storage.load_metadata("lsm").unwrap();
+        assert_eq!(metadata.len(), reference.count());
+    }
+}
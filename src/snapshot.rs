@@ -0,0 +1,341 @@
+// instantanés de la base (fichier unique .vdb, voir VectorDbClient::export_file)
+// expédiés dans un répertoire secondaire, et restauration continue du dernier
+// d'entre eux par un serveur de secours en lecture seule (--standby)
+use crate::client::VectorDbClient;
+use crate::error::{Result, VectorDbError};
+use crate::storage::Storage;
+use serde::Serialize;
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+// nom du dernier instantané complet, réécrit après chaque expédition
+pub const LATEST_FILE: &str = "LATEST";
+const SNAPSHOT_PREFIX: &str = "snapshot-";
+const SNAPSHOT_EXTENSION: &str = ".vdb";
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SnapshotInfo {
+    pub name: String,
+    pub path: String,
+    pub bytes: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ShippedSnapshot {
+    pub snapshot: SnapshotInfo,
+    pub collections: usize,
+    // anciens instantanés supprimés par la politique de rétention
+    pub pruned: Vec<String>,
+}
+
+// instantanés gardés : les `keep_last` plus récents, plus le dernier de chacun des
+// `keep_daily_days` derniers jours (UTC, aujourd'hui compris) ; le reste est supprimé
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct RetentionPolicy {
+    pub keep_last: usize,
+    pub keep_daily_days: u32,
+}
+
+impl RetentionPolicy {
+    // noms à garder parmi `snapshots`, triés du plus ancien au plus récent ; un nom
+    // sans date lisible est gardé, faute de savoir le dater
+    fn retained(&self, snapshots: &[SnapshotInfo], today: chrono::NaiveDate) -> HashSet<String> {
+        let mut kept: HashSet<String> =
+            snapshots.iter().rev().take(self.keep_last.max(1)).map(|s| s.name.clone()).collect();
+        let mut days = HashSet::new();
+        for snapshot in snapshots.iter().rev() {
+            match snapshot_date(&snapshot.name) {
+                Some(date) => {
+                    let age = (today - date).num_days();
+                    if (0..self.keep_daily_days as i64).contains(&age) && days.insert(date) {
+                        kept.insert(snapshot.name.clone());
+                    }
+                }
+                None => {
+                    kept.insert(snapshot.name.clone());
+                }
+            }
+        }
+        kept
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PruneReport {
+    pub policy: RetentionPolicy,
+    pub kept: Vec<String>,
+    pub pruned: Vec<String>,
+    pub freed_bytes: u64,
+    // rien n'est supprimé, `pruned` liste ce qui le serait
+    pub dry_run: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RestoreReport {
+    pub snapshot: String,
+    pub restored: Vec<String>,
+    // collections absentes de l'instantané, supprimées du répertoire de secours
+    pub removed: Vec<String>,
+}
+
+// le répertoire peut être un montage d'object store (s3fs, gcsfuse...) : seuls un
+// rename et la réécriture de LATEST sont demandés au système de fichiers
+#[derive(Debug, Clone)]
+pub struct SnapshotShipper {
+    dir: PathBuf,
+    retention: RetentionPolicy,
+}
+
+impl SnapshotShipper {
+    // garde les `keep` derniers instantanés, le plus récent compris
+    pub fn new<P: AsRef<Path>>(dir: P, keep: usize) -> Self {
+        Self {
+            dir: dir.as_ref().to_path_buf(),
+            retention: RetentionPolicy { keep_last: keep.max(1), keep_daily_days: 0 },
+        }
+    }
+
+    // garde aussi le dernier instantané de chacun des `days` derniers jours
+    pub fn with_daily_retention(mut self, days: u32) -> Self {
+        self.retention.keep_daily_days = days;
+        self
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    pub fn retention(&self) -> RetentionPolicy {
+        self.retention
+    }
+
+    // exporte toutes les collections dans un nouvel instantané, puis le désigne
+    // comme dernier : un lecteur de LATEST ne voit jamais un fichier incomplet
+    pub fn ship(&self, client: &VectorDbClient) -> Result<ShippedSnapshot> {
+        fs::create_dir_all(&self.dir)?;
+        // noms horodatés à la milliseconde : deux expéditions rapprochées ne
+        // doivent pas écraser le même fichier
+        let (name, path) = loop {
+            let name = format!(
+                "{}{}{}",
+                SNAPSHOT_PREFIX,
+                chrono::Utc::now().format("%Y%m%dT%H%M%S%3fZ"),
+                SNAPSHOT_EXTENSION
+            );
+            let path = self.dir.join(&name);
+            if !path.exists() {
+                break (name, path);
+            }
+            std::thread::sleep(std::time::Duration::from_millis(1));
+        };
+        let collections = client.export_file(&path)?;
+
+        let latest_tmp = self.dir.join(format!("{}.tmp", LATEST_FILE));
+        fs::write(&latest_tmp, format!("{}\n", name))?;
+        fs::rename(&latest_tmp, self.dir.join(LATEST_FILE))?;
+
+        let pruned = self.prune(false)?.pruned;
+
+        Ok(ShippedSnapshot {
+            snapshot: SnapshotInfo {
+                bytes: fs::metadata(&path)?.len(),
+                path: path.display().to_string(),
+                name,
+            },
+            collections,
+            pruned,
+        })
+    }
+
+    // applique la politique de rétention ; appelé après chaque expédition et par la
+    // tâche de purge du serveur. Le dernier instantané (LATEST) n'est jamais supprimé
+    pub fn prune(&self, dry_run: bool) -> Result<PruneReport> {
+        let snapshots = self.list()?;
+        let mut retained = self.retention.retained(&snapshots, chrono::Utc::now().date_naive());
+        if let Some(latest) = latest_snapshot(&self.dir)? {
+            retained.insert(latest);
+        }
+
+        let mut report = PruneReport {
+            policy: self.retention,
+            kept: Vec::new(),
+            pruned: Vec::new(),
+            freed_bytes: 0,
+            dry_run,
+        };
+        for snapshot in snapshots {
+            if retained.contains(&snapshot.name) {
+                report.kept.push(snapshot.name);
+                continue;
+            }
+            if !dry_run {
+                fs::remove_file(&snapshot.path)?;
+            }
+            report.freed_bytes += snapshot.bytes;
+            report.pruned.push(snapshot.name);
+        }
+        Ok(report)
+    }
+
+    // instantanés présents, du plus ancien au plus récent
+    pub fn list(&self) -> Result<Vec<SnapshotInfo>> {
+        if !self.dir.exists() {
+            return Ok(Vec::new());
+        }
+        let mut snapshots = Vec::new();
+        for entry in fs::read_dir(&self.dir)?.flatten() {
+            let name = entry.file_name().to_string_lossy().into_owned();
+            if name.starts_with(SNAPSHOT_PREFIX) && name.ends_with(SNAPSHOT_EXTENSION) {
+                snapshots.push(SnapshotInfo {
+                    bytes: entry.metadata()?.len(),
+                    path: entry.path().display().to_string(),
+                    name,
+                });
+            }
+        }
+        // horodatage dans le nom : l'ordre lexical est l'ordre chronologique
+        snapshots.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(snapshots)
+    }
+}
+
+// jour UTC d'un instantané, lu dans son nom horodaté
+fn snapshot_date(name: &str) -> Option<chrono::NaiveDate> {
+    let stamp = name.strip_prefix(SNAPSHOT_PREFIX)?.get(..8)?;
+    chrono::NaiveDate::parse_from_str(stamp, "%Y%m%d").ok()
+}
+
+// nom du dernier instantané de `dir`, None si aucun n'a encore été expédié
+pub fn latest_snapshot(dir: &Path) -> Result<Option<String>> {
+    let name = match fs::read_to_string(dir.join(LATEST_FILE)) {
+        Ok(content) => content.trim().to_string(),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e.into()),
+    };
+    if name.is_empty() || name.contains(['/', '\\']) || name.starts_with('.') {
+        return Err(VectorDbError::CorruptData {
+            path: dir.join(LATEST_FILE).display().to_string(),
+            reason: format!("invalid snapshot name {:?}", name),
+        });
+    }
+    Ok(Some(name))
+}
+
+// écrivain du répertoire de secours : restaure chaque nouvel instantané de `source`,
+// les lectures passent par un VectorDbClient::open_read_only sur le même répertoire
+// qui recharge les collections réécrites
+pub struct Standby {
+    source: PathBuf,
+    storage: Storage,
+    restored: Option<String>,
+}
+
+impl Standby {
+    pub fn new<P: AsRef<Path>, Q: AsRef<Path>>(source: P, target: Q) -> Result<Self> {
+        Ok(Self {
+            source: source.as_ref().to_path_buf(),
+            storage: Storage::new(target)?,
+            restored: None,
+        })
+    }
+
+    pub fn source(&self) -> &Path {
+        &self.source
+    }
+
+    // dernier instantané restauré
+    pub fn restored(&self) -> Option<&str> {
+        self.restored.as_deref()
+    }
+
+    // restaure le dernier instantané s'il a changé depuis l'appel précédent ; les
+    // collections sont remplacées une à une, un lecteur peut donc voir brièvement
+    // des collections de deux instantanés successifs
+    pub fn poll(&mut self) -> Result<Option<RestoreReport>> {
+        let latest = match latest_snapshot(&self.source)? {
+            Some(name) if self.restored.as_deref() != Some(name.as_str()) => name,
+            _ => return Ok(None),
+        };
+
+        let restored = self.storage.import_packed(&self.source.join(&latest))?;
+        let mut removed = Vec::new();
+        for name in self.storage.list_collections()? {
+            if !restored.contains(&name) {
+                self.storage.delete_collection(&name)?;
+                removed.push(name);
+            }
+        }
+        self.restored = Some(latest.clone());
+        Ok(Some(RestoreReport { snapshot: latest, restored, removed }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::TempDir;
+
+    #[test]
+    fn test_ship_and_restore_standby() {
+        let root = TempDir::new("snapshot");
+        let primary = VectorDbClient::new(root.join("primary")).unwrap();
+        primary.create_collection("docs".into(), 2).unwrap();
+        primary.create_collection("old".into(), 2).unwrap();
+        primary
+            .with_collection_mut("docs", |c| c.add(vec!["a".into()], vec![vec![1.0, 0.0]], None))
+            .unwrap();
+
+        let shipper = SnapshotShipper::new(root.join("snapshots"), 2);
+        let mut standby = Standby::new(shipper.dir(), root.join("standby")).unwrap();
+        assert!(standby.poll().unwrap().is_none());
+
+        let first = shipper.ship(&primary).unwrap();
+        assert_eq!(first.collections, 2);
+        let report = standby.poll().unwrap().unwrap();
+        assert_eq!(report.snapshot, first.snapshot.name);
+        assert!(standby.poll().unwrap().is_none());
+
+        let reader = VectorDbClient::open_read_only(root.join("standby")).unwrap();
+        assert_eq!(reader.with_collection("docs", |c| c.count()).unwrap(), 1);
+        assert!(reader.with_collection_mut("docs", |c| c.delete(vec!["a".into()])).is_err());
+
+        // écritures suivantes et collection supprimée : le secours suit
+        primary
+            .with_collection_mut("docs", |c| c.add(vec!["b".into()], vec![vec![0.0, 1.0]], None))
+            .unwrap();
+        primary.delete_collection("old").unwrap();
+        shipper.ship(&primary).unwrap();
+        let third = shipper.ship(&primary).unwrap();
+        assert_eq!(third.pruned, [first.snapshot.name]);
+        assert_eq!(shipper.list().unwrap().len(), 2);
+
+        let report = standby.poll().unwrap().unwrap();
+        assert_eq!(report.removed, ["old"]);
+        assert_eq!(reader.with_collection("docs", |c| c.count()).unwrap(), 2);
+    }
+
+    #[test]
+    fn test_retention_keeps_last_and_daily() {
+        let snapshot = |stamp: &str| SnapshotInfo {
+            name: format!("{}{}{}", SNAPSHOT_PREFIX, stamp, SNAPSHOT_EXTENSION),
+            path: String::new(),
+            bytes: 1,
+        };
+        // du plus ancien au plus récent : deux par jour du 1er au 10
+        let snapshots: Vec<SnapshotInfo> = (1..=10)
+            .flat_map(|day| [format!("202405{:02}T010000000Z", day), format!("202405{:02}T130000000Z", day)])
+            .map(|stamp| snapshot(&stamp))
+            .collect();
+        let policy = RetentionPolicy { keep_last: 3, keep_daily_days: 7 };
+        let today = chrono::NaiveDate::from_ymd_opt(2024, 5, 10).unwrap();
+        let retained = policy.retained(&snapshots, today);
+
+        // 3 derniers (10 à 13 h et 1 h, 9 à 13 h) + dernier de chaque jour du 4 au 8
+        assert_eq!(retained.len(), 8);
+        assert!(retained.contains(&snapshot("20240510T010000000Z").name));
+        assert!(retained.contains(&snapshot("20240504T130000000Z").name));
+        assert!(!retained.contains(&snapshot("20240504T010000000Z").name));
+        assert!(!retained.contains(&snapshot("20240503T130000000Z").name));
+    }
+}
@@ -0,0 +1,113 @@
+// quantification scalaire int8 des embeddings (config.scalar_quantization) : chaque
+// composante ramenée sur 256 niveaux entre le min et le max du vecteur, avec échelle
+// et décalage propres au vecteur. Un vecteur de 768 f32 (3 Ko) tient en 768 octets
+// + 12. Le produit scalaire se calcule en entiers (distance::dot_product_i8) avec la
+// requête quantifiée de la même façon
+use crate::distance::dot_product_i8;
+use serde::{Deserialize, Serialize};
+
+// vecteurs quantifiés, à côté de embeddings.bin
+pub const QUANTIZED_FILE: &str = "quantized.bin";
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ScalarQuantization {
+    // f32 gardés en mémoire pour reclasser exactement les n_results × rescore plus
+    // proches en distance approchée ; None : f32 abandonnés après quantification
+    #[serde(default)]
+    pub rescore: Option<usize>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Int8Vector {
+    // composante i ≈ offset + scale * (codes[i] + 128)
+    codes: Vec<i8>,
+    scale: f32,
+    offset: f32,
+    // somme des codes, reprise par chaque produit scalaire
+    code_sum: i32,
+}
+
+impl Int8Vector {
+    pub fn quantize(vector: &[f32]) -> Self {
+        let (min, max) = vector
+            .iter()
+            .fold((f32::INFINITY, f32::NEG_INFINITY), |(min, max), &x| (min.min(x), max.max(x)));
+        let offset = if vector.is_empty() { 0.0 } else { min };
+        // vecteur constant : tous les codes à -128, la valeur est dans offset
+        let scale = if max > min { (max - min) / 255.0 } else { 0.0 };
+        let codes: Vec<i8> = vector
+            .iter()
+            .map(|&x| {
+                let level = if scale > 0.0 { ((x - offset) / scale).round().clamp(0.0, 255.0) } else { 0.0 };
+                (level as i32 - 128) as i8
+            })
+            .collect();
+        let code_sum = codes.iter().map(|&c| c as i32).sum();
+        Self { codes, scale, offset, code_sum }
+    }
+
+    pub fn dequantize(&self) -> Vec<f32> {
+        self.codes.iter().map(|&c| self.offset + self.scale * (c as i32 + 128) as f32).collect()
+    }
+
+    pub fn bytes(&self) -> usize {
+        self.codes.len() + 12
+    }
+
+    // produit scalaire approché des deux vecteurs reconstruits, sans les reconstruire :
+    // Σ (oa + sa·ua)(ob + sb·ub) avec u = code + 128, développé autour de Σ codes_a·codes_b
+    #[inline]
+    pub fn dot(&self, other: &Int8Vector) -> f32 {
+        let n = self.codes.len() as i64;
+        let (sum_a, sum_b) = (self.code_sum as i64, other.code_sum as i64);
+        let codes = dot_product_i8(&self.codes, &other.codes) as i64;
+        // Σ ua, Σ ub, Σ ua·ub
+        let (ua, ub) = (sum_a + 128 * n, sum_b + 128 * n);
+        let uu = codes + 128 * (sum_a + sum_b) + 128 * 128 * n;
+        let (sa, sb, oa, ob) = (self.scale as f64, other.scale as f64, self.offset as f64, other.offset as f64);
+        (n as f64 * oa * ob + oa * sb * ub as f64 + ob * sa * ua as f64 + sa * sb * uu as f64) as f32
+    }
+
+    // distance cosinus approchée entre deux vecteurs normalisés quantifiés
+    #[inline]
+    pub fn distance(&self, other: &Int8Vector) -> f32 {
+        1.0 - self.dot(other)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::distance::{cosine_distance, normalize_l2};
+    use rand::{Rng, SeedableRng};
+
+    #[test]
+    fn test_quantize_and_integer_dot_product() {
+        let mut rng = rand::rngs::StdRng::seed_from_u64(5);
+        let data: Vec<Vec<f32>> = (0..200)
+            .map(|_| {
+                let mut v: Vec<f32> = (0..64).map(|_| rng.gen_range(-1.0..1.0)).collect();
+                normalize_l2(&mut v);
+                v
+            })
+            .collect();
+        let quantized: Vec<Int8Vector> = data.iter().map(|v| Int8Vector::quantize(v)).collect();
+        assert_eq!(quantized[0].bytes(), 64 + 12);
+
+        for (v, q) in data.iter().zip(&quantized) {
+            let max_error = v.iter().zip(q.dequantize()).map(|(a, b)| (a - b).abs()).fold(0.0, f32::max);
+            // au plus un demi-pas de quantification
+            assert!(max_error <= q.scale / 2.0 + 1e-6);
+        }
+        for pair in quantized.windows(2).zip(data.windows(2)) {
+            let (q, v) = pair;
+            // calcul en entiers : exactement le produit des vecteurs reconstruits
+            let reconstructed = cosine_distance(&q[0].dequantize(), &q[1].dequantize());
+            assert!((q[0].distance(&q[1]) - reconstructed).abs() < 1e-4);
+            assert!((q[0].distance(&q[1]) - cosine_distance(&v[0], &v[1])).abs() < 0.02);
+        }
+
+        let constant = Int8Vector::quantize(&[0.5; 4]);
+        assert_eq!(constant.dequantize(), vec![0.5; 4]);
+    }
+}
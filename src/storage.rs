@@ -1,71 +1,847 @@
-use crate::collection::Collection;
+use crate::collection::{Collection, CollectionConfig, EncodedVectors};
+use crate::disk_ivf::{DiskLists, DISK_LISTS_FILE};
+use crate::half::{HalfVector, HALF_FILE};
+use crate::late_interaction::{TokenMatrix, TOKENS_FILE};
+use crate::sq::{Int8Vector, QUANTIZED_FILE};
+use crate::segment::{is_segment_file, segment_file, Segment, SegmentManifest, SEGMENTS_MANIFEST};
+use crate::tombstones::{TombstoneSet, TOMBSTONES_FILE};
 use crate::error::{Result, VectorDbError};
-use std::fs::{self, File};
-use std::io::{BufReader, BufWriter};
+use crate::vector::{BinaryVector, MetadataValue, VectorEntry, BINARY_FILE};
+use bincode::Options;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fs::{self, File, OpenOptions, TryLockError};
+use std::io::{BufReader, BufWriter, Read, Write};
 use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+const HEADER_FILE: &str = "header.bin";
+const METADATA_FILE: &str = "metadata.bin";
+const EMBEDDINGS_FILE: &str = "embeddings.bin";
+// vecteurs tels qu'ajoutés des collections store_raw
+const RAW_FILE: &str = "raw.bin";
+const LOCK_FILE: &str = "LOCK";
+// collections incomplètes ou illisibles écartées par le scan de cohérence
+const QUARANTINE_DIR: &str = "corrupted";
+// journal d'audit des opérations de suppression réglementaire, une ligne JSON par opération
+const AUDIT_FILE: &str = "audit.log";
+// un lecteur peut tomber au milieu d'une sauvegarde de l'écrivain
+const READ_ONLY_LOAD_ATTEMPTS: usize = 5;
+
+pub type StoredMetadata = Vec<(String, HashMap<String, MetadataValue>)>;
+
+// versions du format sur disque :
+// 0 : data.bin / data.json monolithique (0.1, 0.2)
+// 1 : header.bin + metadata.bin + embeddings.bin (+ fichiers annexes), en-tête
+//     versionné dont le contenu est en JSON (voir StoredHeader)
+pub const CURRENT_FORMAT_VERSION: u32 = 1;
+
+// en-tête léger : lisible sans charger métadonnées ni embeddings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CollectionHeader {
+    pub config: CollectionConfig,
+    pub count: usize,
+    pub modifications_count: usize,
+    // voir Collection::data_version ; absent des en-têtes écrits avant son ajout
+    #[serde(default)]
+    pub data_version: u64,
+    pub format_version: u32,
+}
+
+impl CollectionHeader {
+    // en-tête d'une collection en mémoire, au format courant
+    pub fn of(collection: &Collection) -> Self {
+        Self {
+            config: collection.config.clone(),
+            count: collection.count(),
+            modifications_count: collection.modifications_count(),
+            data_version: collection.data_version(),
+            format_version: CURRENT_FORMAT_VERSION,
+        }
+    }
+}
+
+// en-tête tel qu'écrit dans header.bin et les fichiers .vdb : la version d'abord,
+// puis l'en-tête en JSON préfixé par sa longueur. Un champ ajouté à la config avec
+// #[serde(default)] se relit dans les en-têtes plus anciens sans nouvelle version ;
+// seul un changement incompatible demande une version et une migration
+#[derive(Serialize, Deserialize)]
+struct StoredHeader {
+    format_version: u32,
+    header: String,
+}
+
+impl StoredHeader {
+    fn new(header: &CollectionHeader) -> Result<Self> {
+        Ok(Self { format_version: header.format_version, header: serde_json::to_string(header)? })
+    }
+
+    // la version est vérifiée avant le décodage du JSON, dont la forme lui appartient
+    fn decode(self, path: &Path) -> Result<CollectionHeader> {
+        Storage::check_supported(&path.display().to_string(), self.format_version)?;
+        let header: CollectionHeader = serde_json::from_str(&self.header).map_err(|e| VectorDbError::CorruptData {
+            path: path.display().to_string(),
+            reason: format!("invalid header: {}", e),
+        })?;
+        Ok(CollectionHeader { format_version: self.format_version, ..header })
+    }
+}
+
+// une étape de migration fait passer une collection de `from` à `to`
+struct Migration {
+    from: u32,
+    to: u32,
+    description: &'static str,
+    apply: fn(&Storage, &str) -> Result<()>,
+}
+
+// étapes ordonnées ; toute évolution du format ajoute une entrée ici
+const MIGRATIONS: &[Migration] = &[Migration {
+    from: 0,
+    to: 1,
+    description: "split legacy data file into header, metadata and embeddings, checked against the original",
+    apply: migrate_legacy_to_split,
+}];
+
+// fichiers du format 0, relus tant qu'aucun en-tête n'existe
+const LEGACY_FILES: [&str; 2] = ["data.bin", "data.json"];
+// copie de l'ancien fichier gardée le temps de vérifier la conversion
+const LEGACY_BACKUP_SUFFIX: &str = ".pre-migration";
+
+// la collection relue dans le nouveau format est comparée à l'ancienne (ids,
+// embeddings, métadonnées) avant la suppression de data.bin / data.json ; en cas
+// d'écart, les fichiers écrits sont retirés et l'ancien fichier remis en place
+fn migrate_legacy_to_split(storage: &Storage, name: &str) -> Result<()> {
+    let coll_path = storage.collection_path(name);
+    let legacy = storage.load_collection(name)?;
+    // save_collection supprime data.bin / data.json une fois l'en-tête écrit ; une
+    // copie plutôt qu'un renommage : interrompue avant l'en-tête, la migration laisse
+    // l'ancien fichier en place
+    let mut backups = Vec::new();
+    for file in LEGACY_FILES {
+        let path = coll_path.join(file);
+        if path.exists() {
+            let backup = coll_path.join(format!("{}{}", file, LEGACY_BACKUP_SUFFIX));
+            fs::copy(&path, &backup)?;
+            backups.push((path, backup));
+        }
+    }
+
+    let checked = storage
+        .save_collection(&legacy)
+        .and_then(|_| storage.load_collection(name))
+        .and_then(|migrated| {
+            same_contents(&legacy, &migrated).map_err(|reason| VectorDbError::CorruptData {
+                path: coll_path.display().to_string(),
+                reason: format!("migrated collection differs from the legacy file: {}", reason),
+            })
+        });
+    if let Err(e) = checked {
+        for file in [HEADER_FILE, METADATA_FILE, EMBEDDINGS_FILE] {
+            let _ = fs::remove_file(coll_path.join(file));
+        }
+        for (path, backup) in &backups {
+            fs::rename(backup, path)?;
+        }
+        return Err(e);
+    }
+    for (_, backup) in backups {
+        fs::remove_file(backup)?;
+    }
+    Ok(())
+}
+
+// premier écart entre deux versions d'une même collection
+fn same_contents(expected: &Collection, actual: &Collection) -> std::result::Result<(), String> {
+    if expected.count() != actual.count() {
+        return Err(format!("{} vectors instead of {}", actual.count(), expected.count()));
+    }
+    let actual_entries: HashMap<&str, &VectorEntry> = actual.entries().map(|e| (e.id.as_str(), e)).collect();
+    for entry in expected.entries() {
+        let other = actual_entries.get(entry.id.as_str()).ok_or_else(|| format!("vector '{}' is missing", entry.id))?;
+        if other.metadata != entry.metadata {
+            return Err(format!("metadata of '{}' changed", entry.id));
+        }
+        let (a, b) = (expected.embedding_of(entry).map_err(|e| e.to_string())?, actual.embedding_of(other).map_err(|e| e.to_string())?);
+        if a.len() != b.len() || a.iter().zip(b.iter()).any(|(x, y)| x.to_bits() != y.to_bits()) {
+            return Err(format!("embedding of '{}' changed", entry.id));
+        }
+    }
+    Ok(())
+}
+
+// fichier unique .vdb : magic, version, nombre de collections, puis chaque
+// collection sérialisée à la suite (une seule en mémoire à la fois)
+const PACKED_MAGIC: &[u8; 8] = b"VECTRDB\0";
+
+#[derive(Serialize, Deserialize)]
+struct PackedCollection {
+    header: StoredHeader,
+    metadata: StoredMetadata,
+    embeddings: Vec<(String, Vec<f32>)>,
+    tokens: Vec<(String, TokenMatrix)>,
+    // collection store_raw : vecteurs tels qu'ajoutés
+    raw: Vec<(String, Vec<f32>)>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct MigrationReport {
+    pub collection: String,
+    pub from_version: u32,
+    pub to_version: u32,
+    pub steps: Vec<String>,
+    pub dry_run: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct QuarantinedCollection {
+    pub collection: String,
+    pub reason: String,
+    // destination sous corrupted/, None en dry-run
+    pub moved_to: Option<String>,
+}
+
+// résultat du scan de cohérence ; en dry-run rien n'est modifié sur disque
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ScanReport {
+    pub scanned: usize,
+    // fichiers .tmp laissés par une écriture interrompue
+    pub removed_temp_files: Vec<String>,
+    // répertoires de collection vides
+    pub removed_empty_dirs: Vec<String>,
+    pub quarantined: Vec<QuarantinedCollection>,
+    pub dry_run: bool,
+}
+
+impl ScanReport {
+    pub fn is_clean(&self) -> bool {
+        self.removed_temp_files.is_empty()
+            && self.removed_empty_dirs.is_empty()
+            && self.quarantined.is_empty()
+    }
+}
 
 pub struct Storage {
     base_path: PathBuf,
+    read_only: bool,
+    // taille max d'un fichier relu, 0 = illimité (VECTORDB_MAX_FILE_BYTES)
+    max_file_bytes: u64,
+    // verrou exclusif de l'écrivain, relâché à la fermeture du fichier
+    _lock: Option<File>,
 }
 
 impl Storage {
+    // un seul écrivain par répertoire, d'autres processus peuvent l'ouvrir en lecture seule
     pub fn new<P: AsRef<Path>>(base_path: P) -> Result<Self> {
         let base_path = base_path.as_ref().to_path_buf();
         fs::create_dir_all(&base_path)?;
         fs::create_dir_all(base_path.join("collections"))?;
 
-        Ok(Self { base_path })
+        let lock = OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(base_path.join(LOCK_FILE))?;
+        match lock.try_lock() {
+            Ok(()) => {}
+            Err(TryLockError::WouldBlock) => {
+                return Err(VectorDbError::StorageLocked(base_path.display().to_string()));
+            }
+            Err(TryLockError::Error(e)) => return Err(e.into()),
+        }
+
+        Ok(Self {
+            base_path,
+            read_only: false,
+            max_file_bytes: Self::max_file_bytes_from_env(),
+            _lock: Some(lock),
+        })
+    }
+
+    // ouverture sans verrou ni écriture, à côté d'un processus écrivain
+    pub fn open_read_only<P: AsRef<Path>>(base_path: P) -> Result<Self> {
+        let base_path = base_path.as_ref().to_path_buf();
+        if !base_path.is_dir() {
+            return Err(VectorDbError::Io(std::io::Error::new(
+                std::io::ErrorKind::NotFound,
+                format!("storage directory not found: {}", base_path.display()),
+            )));
+        }
+
+        Ok(Self {
+            base_path,
+            read_only: true,
+            max_file_bytes: Self::max_file_bytes_from_env(),
+            _lock: None,
+        })
+    }
+
+    pub fn is_read_only(&self) -> bool {
+        self.read_only
+    }
+
+    fn max_file_bytes_from_env() -> u64 {
+        std::env::var("VECTORDB_MAX_FILE_BYTES")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(0)
+    }
+
+    pub fn set_max_file_bytes(&mut self, max_bytes: u64) {
+        self.max_file_bytes = max_bytes;
+    }
+
+    fn ensure_writable(&self) -> Result<()> {
+        if self.read_only {
+            return Err(VectorDbError::ReadOnly(self.base_path.display().to_string()));
+        }
+        Ok(())
+    }
+
+    // date de la dernière sauvegarde complète (l'en-tête est écrit en dernier)
+    pub fn header_stamp(&self, name: &str) -> Option<SystemTime> {
+        fs::metadata(self.collection_path(name).join(HEADER_FILE))
+            .and_then(|m| m.modified())
+            .ok()
     }
 
     pub fn collection_path(&self, name: &str) -> PathBuf {
         self.base_path.join("collections").join(name)
     }
 
+    // écriture dans un fichier temporaire puis rename, pour ne jamais laisser un fichier tronqué
+    fn write_atomic<T: Serialize + ?Sized>(path: &Path, value: &T) -> Result<()> {
+        let tmp_path = path.with_extension("bin.tmp");
+        {
+            let f = File::create(&tmp_path)?;
+            let mut writer = BufWriter::with_capacity(512 * 1024, f);
+            bincode::serialize_into(&mut writer, value)?;
+            writer.flush()?;
+        }
+        fs::rename(tmp_path, path)?;
+        Ok(())
+    }
+
+    // borne de décodage : la taille du fichier, qu'aucune donnée valide ne dépasse.
+    // Un préfixe de longueur corrompu échoue alors au lieu de réserver des Go
+    fn decode_limit(&self, path: &Path, file: &File) -> Result<u64> {
+        let len = file.metadata()?.len();
+        if self.max_file_bytes > 0 && len > self.max_file_bytes {
+            return Err(VectorDbError::CorruptData {
+                path: path.display().to_string(),
+                reason: format!(
+                    "file is {} bytes, above the {} bytes limit (VECTORDB_MAX_FILE_BYTES)",
+                    len, self.max_file_bytes
+                ),
+            });
+        }
+        Ok(len)
+    }
+
+    // même encodage que bincode::serialize_into, avec une limite d'octets lus
+    fn decode_from<T: for<'de> Deserialize<'de>, R: Read>(reader: R, limit: u64, path: &Path) -> Result<T> {
+        bincode::DefaultOptions::new()
+            .with_fixint_encoding()
+            .allow_trailing_bytes()
+            .with_limit(limit)
+            .deserialize_from(reader)
+            .map_err(|e| {
+                let reason = match *e {
+                    bincode::ErrorKind::Io(io) if io.kind() != std::io::ErrorKind::UnexpectedEof => {
+                        return VectorDbError::Io(io);
+                    }
+                    bincode::ErrorKind::SizeLimit => "declared length exceeds the file size".to_string(),
+                    other => other.to_string(),
+                };
+                VectorDbError::CorruptData { path: path.display().to_string(), reason }
+            })
+    }
+
+    fn read_bin<T: for<'de> Deserialize<'de>>(&self, path: &Path) -> Result<T> {
+        let file = File::open(path)?;
+        let limit = self.decode_limit(path, &file)?;
+        let reader = BufReader::with_capacity(512 * 1024, file);
+        Self::decode_from(reader, limit, path)
+    }
+
+    fn read_header(&self, path: &Path) -> Result<CollectionHeader> {
+        self.read_bin::<StoredHeader>(path)?.decode(path)
+    }
+
+    pub fn format_version(&self, name: &str) -> Result<u32> {
+        let coll_path = self.collection_path(name);
+        let header_path = coll_path.join(HEADER_FILE);
+        if header_path.exists() {
+            return Ok(self.read_header(&header_path)?.format_version);
+        }
+        if LEGACY_FILES.iter().any(|file| coll_path.join(file).exists()) {
+            return Ok(0);
+        }
+        Err(VectorDbError::CollectionNotFound(name.to_string()))
+    }
+
+    // un format plus récent que ce binaire ne doit être ni lu ni réécrit
+    fn check_supported(name: &str, version: u32) -> Result<()> {
+        if version > CURRENT_FORMAT_VERSION {
+            return Err(VectorDbError::Serialization(format!(
+                "Collection {} uses storage format {} but this build only supports up to {}",
+                name, version, CURRENT_FORMAT_VERSION
+            )));
+        }
+        Ok(())
+    }
+
+    fn migration_plan(name: &str, from: u32) -> Result<Vec<&'static Migration>> {
+        Self::check_supported(name, from)?;
+
+        let mut plan = Vec::new();
+        let mut version = from;
+        while version < CURRENT_FORMAT_VERSION {
+            let step = MIGRATIONS.iter().find(|m| m.from == version).ok_or_else(|| {
+                VectorDbError::Serialization(format!(
+                    "No migration path from storage format {} for collection {}",
+                    version, name
+                ))
+            })?;
+            plan.push(step);
+            version = step.to;
+        }
+        Ok(plan)
+    }
+
+    // amener une collection au format courant ; en dry-run, seules les étapes
+    // prévues sont rapportées et aucun fichier n'est modifié
+    pub fn migrate_collection(&self, name: &str, dry_run: bool) -> Result<MigrationReport> {
+        let from_version = self.format_version(name)?;
+        let plan = Self::migration_plan(name, from_version)?;
+
+        if !dry_run && !plan.is_empty() {
+            self.ensure_writable()?;
+        }
+
+        let mut steps = Vec::with_capacity(plan.len());
+        for step in plan {
+            if !dry_run {
+                (step.apply)(self, name)?;
+                tracing::info!(
+                    collection = %name,
+                    from = step.from,
+                    to = step.to,
+                    "Applied storage migration: {}",
+                    step.description
+                );
+            }
+            steps.push(format!("{} -> {}: {}", step.from, step.to, step.description));
+        }
+
+        Ok(MigrationReport {
+            collection: name.to_string(),
+            from_version,
+            to_version: CURRENT_FORMAT_VERSION,
+            steps,
+            dry_run,
+        })
+    }
+
+    // collections déjà au format courant exclues du rapport
+    pub fn migrate_all(&self, dry_run: bool) -> Result<Vec<MigrationReport>> {
+        let mut reports = Vec::new();
+        for name in self.list_collections()? {
+            if !self.collection_exists(&name) {
+                continue;
+            }
+            let report = self.migrate_collection(&name, dry_run)?;
+            if !report.steps.is_empty() {
+                reports.push(report);
+            }
+        }
+        Ok(reports)
+    }
+
+    // toutes les collections dans un seul fichier, écrit de façon atomique
+    pub fn export_packed(&self, path: &Path) -> Result<usize> {
+        let names: Vec<String> = self
+            .list_collections()?
+            .into_iter()
+            .filter(|name| self.collection_exists(name))
+            .collect();
+
+        let tmp_path = path.with_extension("vdb.tmp");
+        {
+            let f = File::create(&tmp_path)?;
+            let mut writer = BufWriter::with_capacity(512 * 1024, f);
+            writer.write_all(PACKED_MAGIC)?;
+            bincode::serialize_into(&mut writer, &CURRENT_FORMAT_VERSION)?;
+            bincode::serialize_into(&mut writer, &(names.len() as u64))?;
+
+            for name in &names {
+                let collection = self.load_collection(name)?;
+                let packed = PackedCollection {
+                    header: StoredHeader::new(&CollectionHeader::of(&collection))?,
+                    metadata: collection
+                        .entries()
+                        .map(|e| (e.id.clone(), e.metadata.clone()))
+                        .collect(),
+                    embeddings: collection
+                        .entries()
+                        .map(|e| Ok((e.id.clone(), collection.embedding_of(e)?.into_owned())))
+                        .collect::<Result<_>>()?,
+                    tokens: collection.token_embeddings().iter().map(|(id, t)| (id.clone(), t.clone())).collect(),
+                    raw: collection.raw_embeddings().iter().map(|(id, v)| (id.clone(), v.clone())).collect(),
+                };
+                bincode::serialize_into(&mut writer, &packed)?;
+            }
+            writer.flush()?;
+        }
+        fs::rename(tmp_path, path)?;
+
+        Ok(names.len())
+    }
+
+    // restaure les collections d'un fichier .vdb ; les collections existantes
+    // de même nom sont remplacées. Renvoie les noms restaurés
+    pub fn import_packed(&self, path: &Path) -> Result<Vec<String>> {
+        self.ensure_writable()?;
+
+        let mut names = Vec::new();
+        self.read_packed(path, |collection| {
+            self.save_collection(&collection)?;
+            names.push(collection.config.name.clone());
+            Ok(true)
+        })?;
+        Ok(names)
+    }
+
+    // une collection d'un fichier .vdb, sans rien écrire dans le répertoire ; les
+    // collections qui la précèdent dans le fichier sont décodées puis ignorées
+    pub fn load_packed_collection(&self, path: &Path, name: &str) -> Result<Collection> {
+        let mut found = None;
+        self.read_packed(path, |collection| {
+            if collection.config.name != name {
+                return Ok(true);
+            }
+            found = Some(collection);
+            Ok(false)
+        })?;
+        found.ok_or_else(|| VectorDbError::CollectionNotFound(format!("{} in {}", name, path.display())))
+    }
+
+    // collections d'un fichier .vdb dans l'ordre du fichier, tant que `visit` renvoie true
+    fn read_packed(&self, path: &Path, mut visit: impl FnMut(Collection) -> Result<bool>) -> Result<()> {
+        let file = File::open(path)?;
+        let limit = self.decode_limit(path, &file)?;
+        let mut reader = BufReader::with_capacity(512 * 1024, file);
+
+        let mut magic = [0u8; 8];
+        std::io::Read::read_exact(&mut reader, &mut magic)?;
+        if &magic != PACKED_MAGIC {
+            return Err(VectorDbError::Serialization(format!(
+                "{} is not a vectordb file",
+                path.display()
+            )));
+        }
+        let version: u32 = Self::decode_from(&mut reader, limit, path)?;
+        Self::check_supported(&path.display().to_string(), version)?;
+        let count: u64 = Self::decode_from(&mut reader, limit, path)?;
+
+        for _ in 0..count {
+            let packed: PackedCollection = Self::decode_from(&mut reader, limit, path)?;
+            let header = packed.header.decode(path)?;
+            let mut collection = Collection::from_parts(
+                header.config,
+                header.modifications_count,
+                header.data_version,
+                packed.metadata,
+                packed.embeddings,
+                EncodedVectors::default(),
+                None,
+            )?;
+            collection.set_token_embeddings(packed.tokens);
+            collection.set_raw_embeddings(packed.raw);
+            if !visit(collection)? {
+                break;
+            }
+        }
+
+        Ok(())
+    }
+
     pub fn save_collection(&self, collection: &Collection) -> Result<()> {
+        self.save_collection_parts(collection, true)
+    }
+
+    // les embeddings (souvent plusieurs Go) ne sont réécrits que s'ils ont changé
+    pub fn save_collection_parts(&self, collection: &Collection, with_embeddings: bool) -> Result<()> {
+        self.ensure_writable()?;
         let coll_path = self.collection_path(&collection.config.name);
         fs::create_dir_all(&coll_path)?;
 
-        // sauvegarder en bincode pour meilleure perf
-        let data_path = coll_path.join("data.bin");
-        let f = File::create(data_path)?;
-        let writer = BufWriter::with_capacity(512 * 1024, f);
-        bincode::serialize_into(writer, collection)?;
+        // collection segmentée : le manifeste d'abord, les fichiers séparés ne gardent
+        // que la memtable (une entrée de la memtable masque sa ligne de segment)
+        let segmented = collection.is_segmented();
+        if segmented {
+            Self::write_atomic(&coll_path.join(SEGMENTS_MANIFEST), &collection.segment_manifest())?;
+        }
+        let in_files = |e: &&VectorEntry| !segmented || !e.embedding.is_empty();
+
+        let embeddings_path = coll_path.join(EMBEDDINGS_FILE);
+        if with_embeddings || !embeddings_path.exists() {
+            // les vecteurs rangés dans lists.dat n'ont pas d'embedding en mémoire
+            let embeddings: Vec<(&String, &Vec<f32>)> = collection
+                .entries()
+                .filter(|e| !e.embedding.is_empty())
+                .map(|e| (&e.id, &e.embedding))
+                .collect();
+            Self::write_atomic(&embeddings_path, &embeddings)?;
+            if collection.config.scalar_quantization.is_some() {
+                let quantized: Vec<(&String, &Int8Vector)> = collection.quantized_vectors().iter().collect();
+                Self::write_atomic(&coll_path.join(QUANTIZED_FILE), &quantized)?;
+            }
+            if collection.config.is_binary() {
+                let binary: Vec<(&String, &BinaryVector)> = collection.binary_vectors().iter().collect();
+                Self::write_atomic(&coll_path.join(BINARY_FILE), &binary)?;
+            }
+            if collection.config.precision.is_half() {
+                let half: Vec<(&String, &HalfVector)> = collection.half_vectors().iter().collect();
+                Self::write_atomic(&coll_path.join(HALF_FILE), &half)?;
+            }
+            if collection.config.store_raw {
+                let raw: Vec<(&String, &Vec<f32>)> = collection.raw_embeddings().iter().collect();
+                Self::write_atomic(&coll_path.join(RAW_FILE), &raw)?;
+            }
+            if collection.config.late_interaction {
+                let tokens: Vec<(&String, &TokenMatrix)> = collection
+                    .entries()
+                    .filter(in_files)
+                    .filter_map(|e| collection.token_embeddings().get_key_value(&e.id))
+                    .collect();
+                Self::write_atomic(&coll_path.join(TOKENS_FILE), &tokens)?;
+            }
+        }
+
+        let metadatas: Vec<(&String, &HashMap<String, MetadataValue>)> = collection
+            .entries()
+            .filter(in_files)
+            .map(|e| (&e.id, &e.metadata))
+            .collect();
+        Self::write_atomic(&coll_path.join(METADATA_FILE), &metadatas)?;
+
+        // fichier annexe, sans effet sur le format : absent = aucune suppression suivie
+        let tombstones_path = coll_path.join(TOMBSTONES_FILE);
+        if !collection.tombstones().is_empty() || tombstones_path.exists() {
+            let tombstones: Vec<(&String, &i64)> = collection.tombstones().entries().collect();
+            Self::write_atomic(&tombstones_path, &tombstones)?;
+        }
+
+        // l'en-tête en dernier : sa présence indique une sauvegarde complète
+        let header = StoredHeader::new(&CollectionHeader::of(collection))?;
+        Self::write_atomic(&coll_path.join(HEADER_FILE), &header)?;
+
+        // listes d'une collection remplacée depuis (import), à ne pas rattacher au chargement
+        let disk_path = coll_path.join(DISK_LISTS_FILE);
+        if !collection.is_offloaded() && disk_path.exists() {
+            fs::remove_file(disk_path)?;
+        }
+
+        // segments fusionnés ou dégelés depuis ; celui d'une fusion en cours est gardé
+        let manifest_path = coll_path.join(SEGMENTS_MANIFEST);
+        if !segmented && manifest_path.exists() {
+            fs::remove_file(manifest_path)?;
+        }
+        let live: HashSet<String> = collection.segment_files().into_iter().collect();
+        for entry in fs::read_dir(&coll_path)?.flatten() {
+            let name = entry.file_name().to_string_lossy().into_owned();
+            if is_segment_file(&name) && !live.contains(&name) {
+                fs::remove_file(entry.path())?;
+            }
+        }
+
+        // l'ancien format n'est plus à jour, ne pas le relire par erreur
+        for legacy in LEGACY_FILES {
+            let legacy_path = coll_path.join(legacy);
+            if legacy_path.exists() {
+                fs::remove_file(legacy_path)?;
+            }
+        }
 
         Ok(())
     }
 
     pub fn load_collection(&self, name: &str) -> Result<Collection> {
+        if !self.read_only {
+            return self.load_collection_files(name);
+        }
+
+        // en lecture seule, recommencer si l'écrivain a sauvegardé pendant la lecture
+        let mut attempt = 1;
+        loop {
+            let before = self.header_stamp(name);
+            let result = self.load_collection_files(name);
+            if before == self.header_stamp(name) || attempt == READ_ONLY_LOAD_ATTEMPTS {
+                return result;
+            }
+            attempt += 1;
+            std::thread::sleep(Duration::from_millis(20));
+        }
+    }
+
+    fn load_collection_files(&self, name: &str) -> Result<Collection> {
         let coll_path = self.collection_path(name);
 
-        // essayer bincode d'abord (nouveau format)
+        // format séparé : en-tête + métadonnées + embeddings
+        let header_path = coll_path.join(HEADER_FILE);
+        if header_path.exists() {
+            let header = self.read_header(&header_path)?;
+            Self::check_supported(name, header.format_version)?;
+            let metadatas: StoredMetadata = self.read_bin(&coll_path.join(METADATA_FILE))?;
+            let embeddings: Vec<(String, Vec<f32>)> =
+                self.read_bin(&coll_path.join(EMBEDDINGS_FILE))?;
+            let disk_path = coll_path.join(DISK_LISTS_FILE);
+            let disk = if header.config.use_ivf && disk_path.exists() {
+                Some(DiskLists::open(&disk_path)?)
+            } else {
+                None
+            };
+            let tokens_path = coll_path.join(TOKENS_FILE);
+            let tokens: Vec<(String, TokenMatrix)> = if header.config.late_interaction && tokens_path.exists() {
+                self.read_bin(&tokens_path)?
+            } else {
+                Vec::new()
+            };
+            let quantized_path = coll_path.join(QUANTIZED_FILE);
+            let quantized: Vec<(String, Int8Vector)> =
+                if header.config.scalar_quantization.is_some() && quantized_path.exists() {
+                    self.read_bin(&quantized_path)?
+                } else {
+                    Vec::new()
+                };
+            let header_store_raw = header.config.store_raw;
+            let binary_path = coll_path.join(BINARY_FILE);
+            let binary: Vec<(String, BinaryVector)> = if header.config.is_binary() && binary_path.exists() {
+                self.read_bin(&binary_path)?
+            } else {
+                Vec::new()
+            };
+            let half_path = coll_path.join(HALF_FILE);
+            let half: Vec<(String, HalfVector)> = if header.config.precision.is_half() && half_path.exists() {
+                self.read_bin(&half_path)?
+            } else {
+                Vec::new()
+            };
+            let mut collection = Collection::from_parts(
+                header.config,
+                header.modifications_count,
+                header.data_version,
+                metadatas,
+                embeddings,
+                EncodedVectors { quantized, binary, half },
+                disk,
+            )?;
+            collection.set_token_embeddings(tokens);
+            let raw_path = coll_path.join(RAW_FILE);
+            if header_store_raw && raw_path.exists() {
+                collection.set_raw_embeddings(self.read_bin(&raw_path)?);
+            }
+            if let Some(manifest) = self.read_manifest(&coll_path)? {
+                let segments = self.open_segments(&coll_path, manifest.segments)?;
+                collection.attach_segments(segments, manifest.frozen)?;
+            }
+            self.attach_tombstones(&coll_path, &mut collection)?;
+            return Ok(collection);
+        }
+
+        // ancien format bincode monolithique, relu tel quel jusqu'à la migration
+        // (vectordb_migrate, POST /admin/migrate ou démarrage du serveur)
         let bin_path = coll_path.join("data.bin");
         if bin_path.exists() {
-            let file = File::open(bin_path)?;
-            let reader = BufReader::with_capacity(512 * 1024, file);
-            let mut collection: Collection = bincode::deserialize_from(reader)?;
+            tracing::warn!(collection = %name, "Loading legacy data.bin, run the storage migration to convert it");
+            let mut collection: Collection = self.read_bin(&bin_path)?;
             // reconstruire l'index IVF si nécessaire
-            if collection.config.use_ivf {
-                collection.needs_rebuild = true;
-            }
+            collection.restore_after_load();
             return Ok(collection);
         }
 
         // fallback sur JSON (ancien format)
         let json_path = coll_path.join("data.json");
         if json_path.exists() {
+            tracing::warn!(collection = %name, "Loading legacy data.json, run the storage migration to convert it");
             let file = File::open(json_path)?;
             let reader = BufReader::new(file);
             let mut collection: Collection = serde_json::from_reader(reader)?;
-            if collection.config.use_ivf {
-                collection.needs_rebuild = true;
-            }
+            collection.restore_after_load();
             return Ok(collection);
         }
 
         Err(VectorDbError::CollectionNotFound(name.to_string()))
     }
 
+    fn read_manifest(&self, coll_path: &Path) -> Result<Option<SegmentManifest>> {
+        let manifest_path = coll_path.join(SEGMENTS_MANIFEST);
+        if !manifest_path.exists() {
+            return Ok(None);
+        }
+        self.read_bin(&manifest_path).map(Some)
+    }
+
+    fn open_segments(
+        &self,
+        coll_path: &Path,
+        segments: Vec<(u64, Vec<String>)>,
+    ) -> Result<Vec<(u64, Segment, HashSet<String>)>> {
+        segments
+            .into_iter()
+            .map(|(seq, deleted)| Ok((seq, Segment::open(&coll_path.join(segment_file(seq)))?, deleted.into_iter().collect())))
+            .collect()
+    }
+
+    fn attach_tombstones(&self, coll_path: &Path, collection: &mut Collection) -> Result<()> {
+        let tombstones_path = coll_path.join(TOMBSTONES_FILE);
+        if tombstones_path.exists() {
+            let tombstones: Vec<(String, i64)> = self.read_bin(&tombstones_path)?;
+            collection.set_tombstones(TombstoneSet::from_entries(tombstones));
+        }
+        Ok(())
+    }
+
+    // lire config et compteurs sans charger les vecteurs
+    pub fn load_collection_meta(&self, name: &str) -> Result<CollectionHeader> {
+        let header_path = self.collection_path(name).join(HEADER_FILE);
+        if header_path.exists() {
+            return self.read_header(&header_path);
+        }
+
+        // ancien format : chargement complet inévitable
+        let collection = self.load_collection(name)?;
+        Ok(CollectionHeader { format_version: 0, ..CollectionHeader::of(&collection) })
+    }
+
+    // charger uniquement les métadonnées, sans toucher au fichier d'embeddings
+    pub fn load_metadata(&self, name: &str) -> Result<StoredMetadata> {
+        let coll_path = self.collection_path(name);
+        if coll_path.join(HEADER_FILE).exists() {
+            let mut metadatas: StoredMetadata = self.read_bin(&coll_path.join(METADATA_FILE))?;
+            // collection segmentée : metadata.bin ne couvre que la memtable
+            if let Some(manifest) = self.read_manifest(&coll_path)? {
+                let mut seen: HashSet<String> = metadatas.iter().map(|(id, _)| id.clone()).collect();
+                for (_, mut segment, deleted) in self.open_segments(&coll_path, manifest.segments)? {
+                    metadatas.extend(
+                        segment.take_metadata().into_iter().filter(|(id, _)| !deleted.contains(id) && seen.insert(id.clone())),
+                    );
+                }
+            }
+            return Ok(metadatas);
+        }
+
+        // ancien format : pas d'autre choix que de tout charger
+        let collection = self.load_collection(name)?;
+        Ok(collection
+            .entries()
+            .map(|e| (e.id.clone(), e.metadata.clone()))
+            .collect())
+    }
+
     pub fn delete_collection(&self, name: &str) -> Result<()> {
+        self.ensure_writable()?;
         let coll_path = self.collection_path(name);
         if coll_path.exists() {
             fs::remove_dir_all(coll_path)?;
@@ -82,12 +858,10 @@ impl Storage {
         let entries = fs::read_dir(coll_dir)?;
         let mut names = Vec::new();
 
-        for entry in entries {
-            if let Ok(e) = entry {
-                if e.path().is_dir() {
-                    if let Some(name) = e.file_name().to_str() {
-                        names.push(name.to_string());
-                    }
+        for e in entries.flatten() {
+            if e.path().is_dir() {
+                if let Some(name) = e.file_name().to_str() {
+                    names.push(name.to_string());
                 }
             }
         }
@@ -95,8 +869,325 @@ impl Storage {
         Ok(names)
     }
 
+    // raison pour laquelle le répertoire d'une collection est inutilisable, None s'il est sain
+    fn inspect_collection(&self, coll_path: &Path) -> Option<String> {
+        let header_path = coll_path.join(HEADER_FILE);
+        if header_path.exists() {
+            if let Err(e) = self.read_header(&header_path) {
+                return Some(format!("unreadable header: {}", e));
+            }
+            let missing: Vec<&str> = [METADATA_FILE, EMBEDDINGS_FILE]
+                .into_iter()
+                .filter(|f| !coll_path.join(f).exists())
+                .collect();
+            if !missing.is_empty() {
+                return Some(format!("header without data: missing {}", missing.join(", ")));
+            }
+            return None;
+        }
+        if LEGACY_FILES.iter().any(|file| coll_path.join(file).exists()) {
+            return None;
+        }
+        // l'en-tête est écrit en dernier : première sauvegarde interrompue
+        Some(format!("missing {}", HEADER_FILE))
+    }
+
+    // détecter les écritures incomplètes (fichiers temporaires, en-tête absent ou
+    // illisible, en-tête sans données) : les fichiers temporaires et les répertoires
+    // vides sont supprimés, les collections inutilisables déplacées sous corrupted/
+    pub fn scan_consistency(&self, dry_run: bool) -> Result<ScanReport> {
+        if !dry_run {
+            self.ensure_writable()?;
+        }
+        let mut report = ScanReport { dry_run, ..ScanReport::default() };
+        let mut names = self.list_collections()?;
+        names.sort();
+
+        for name in names {
+            report.scanned += 1;
+            let coll_path = self.collection_path(&name);
+
+            let mut files = Vec::new();
+            for entry in fs::read_dir(&coll_path)?.flatten() {
+                let file_name = entry.file_name().to_string_lossy().into_owned();
+                if file_name.ends_with(".tmp") {
+                    if !dry_run {
+                        fs::remove_file(entry.path())?;
+                    }
+                    report.removed_temp_files.push(format!("{}/{}", name, file_name));
+                } else {
+                    files.push(file_name);
+                }
+            }
+
+            if files.is_empty() {
+                if !dry_run {
+                    fs::remove_dir_all(&coll_path)?;
+                }
+                report.removed_empty_dirs.push(name);
+                continue;
+            }
+
+            let Some(reason) = self.inspect_collection(&coll_path) else {
+                continue;
+            };
+            let moved_to = if dry_run {
+                None
+            } else {
+                let quarantine = self.base_path.join(QUARANTINE_DIR);
+                fs::create_dir_all(&quarantine)?;
+                let mut target = quarantine.join(&name);
+                if target.exists() {
+                    let stamp = SystemTime::now()
+                        .duration_since(SystemTime::UNIX_EPOCH)
+                        .map(|d| d.as_secs())
+                        .unwrap_or(0);
+                    target = quarantine.join(format!("{}-{}", name, stamp));
+                }
+                fs::rename(&coll_path, &target)?;
+                Some(target.display().to_string())
+            };
+            report.quarantined.push(QuarantinedCollection { collection: name, reason, moved_to });
+        }
+
+        Ok(report)
+    }
+
+    // ajout synchronisé sur disque avant de rendre la main
+    pub fn append_audit<T: Serialize>(&self, record: &T) -> Result<()> {
+        self.ensure_writable()?;
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(self.base_path.join(AUDIT_FILE))?;
+        let mut line = serde_json::to_vec(record)?;
+        line.push(b'\n');
+        file.write_all(&line)?;
+        file.sync_data()?;
+        Ok(())
+    }
+
     pub fn collection_exists(&self, name: &str) -> bool {
         let path = self.collection_path(name);
-        path.join("data.bin").exists() || path.join("data.json").exists()
+        path.join(HEADER_FILE).exists() || LEGACY_FILES.iter().any(|file| path.join(file).exists())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::collection::IndexParams;
+    use crate::testing::TempDir;
+
+    #[test]
+    fn test_legacy_json_migration() {
+        let mut coll = Collection::new("legacy".to_string(), 3);
+        let metadatas = (0..20).map(|i| HashMap::from([("n".to_string(), MetadataValue::Int(i))])).collect();
+        let embeddings = (0..20).map(|i| vec![(i as f32).cos(), (i as f32).sin(), 0.5]).collect();
+        coll.add((0..20).map(|i| format!("v{}", i)).collect(), embeddings, Some(metadatas)).unwrap();
+
+        let dir = TempDir::new("legacy");
+        let storage = Storage::new(&dir).unwrap();
+        let coll_dir = storage.collection_path("legacy");
+        std::fs::create_dir_all(&coll_dir).unwrap();
+        serde_json::to_writer(std::fs::File::create(coll_dir.join("data.json")).unwrap(), &coll).unwrap();
+
+        assert_eq!(storage.format_version("legacy").unwrap(), 0);
+        let planned = storage.migrate_all(true).unwrap();
+        assert_eq!(planned[0].steps.len(), 1);
+        assert!(coll_dir.join("data.json").exists());
+
+        storage.migrate_all(false).unwrap();
+        assert_eq!(storage.format_version("legacy").unwrap(), CURRENT_FORMAT_VERSION);
+        let files: Vec<String> = std::fs::read_dir(&coll_dir)
+            .unwrap()
+            .map(|e| e.unwrap().file_name().to_string_lossy().into_owned())
+            .collect();
+        assert!(!files.iter().any(|f| f.starts_with("data.")), "{:?}", files);
+
+        let mut migrated = storage.load_collection("legacy").unwrap();
+        let hit = &migrated.search(&[1.0, 0.0, 0.5]).k(1).run().unwrap()[0];
+        assert_eq!((hit.id.as_str(), hit.metadata.as_ref().and_then(|m| m.get("n"))), ("v0", Some(&MetadataValue::Int(0))));
+        assert!(storage.migrate_all(false).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_header_config_fields_added_later() {
+        let dir = TempDir::new("header-json");
+        let storage = Storage::new(&dir).unwrap();
+        let config = CollectionConfig::new("docs".to_string(), 2).with_embedding_model("minilm".to_string());
+        let mut coll = Collection::from_config(config);
+        coll.add(vec!["a".into(), "b".into()], vec![vec![1.0, 0.0], vec![0.0, 1.0]], None).unwrap();
+        storage.save_collection(&coll).unwrap();
+
+        // en-tête écrit avant l'ajout de champs à la config : relu avec leurs défauts
+        let header_path = storage.collection_path("docs").join(HEADER_FILE);
+        let mut json = serde_json::to_value(CollectionHeader::of(&coll)).unwrap();
+        for field in ["concurrency", "precision", "store_raw", "index_params"] {
+            json["config"].as_object_mut().unwrap().remove(field);
+        }
+        let older = StoredHeader { format_version: CURRENT_FORMAT_VERSION, header: json.to_string() };
+        Storage::write_atomic(&header_path, &older).unwrap();
+        let header = storage.load_collection_meta("docs").unwrap();
+        assert_eq!((header.count, header.config.embedding_model.as_deref()), (2, Some("minilm")));
+        assert_eq!(header.config.index_params, IndexParams::default());
+        assert!(storage.migrate_collection("docs", false).unwrap().steps.is_empty());
+        assert_eq!(storage.load_collection("docs").unwrap().query(&[1.0, 0.0], 1, None).unwrap()[0].id, "a");
+
+        // JSON illisible au format courant : erreur de données, pas de panique
+        let corrupt = StoredHeader { format_version: CURRENT_FORMAT_VERSION, header: "{".to_string() };
+        Storage::write_atomic(&header_path, &corrupt).unwrap();
+        assert!(matches!(storage.load_collection("docs"), Err(VectorDbError::CorruptData { .. })));
+
+        // format plus récent que ce binaire : ni lu ni migré, quel que soit son contenu
+        let future = StoredHeader { format_version: CURRENT_FORMAT_VERSION + 1, header: String::new() };
+        Storage::write_atomic(&header_path, &future).unwrap();
+        assert!(storage.migrate_collection("docs", true).is_err());
+        assert!(storage.load_collection("docs").is_err());
+    }
+
+    #[test]
+    fn test_single_writer_lock() {
+        let dir = TempDir::new("writer-lock");
+        let writer = Storage::new(&dir).unwrap();
+        assert!(matches!(Storage::new(&dir), Err(VectorDbError::StorageLocked(_))));
+
+        // lecteurs sans verrou à côté de l'écrivain
+        let reader = Storage::open_read_only(&dir).unwrap();
+        assert!(reader.is_read_only());
+        let mut coll = Collection::new("docs".to_string(), 2);
+        coll.add(vec!["a".into()], vec![vec![1.0, 0.0]], None).unwrap();
+        writer.save_collection(&coll).unwrap();
+        assert_eq!(reader.load_collection("docs").unwrap().count(), 1);
+        assert!(matches!(reader.save_collection(&coll), Err(VectorDbError::ReadOnly(_))));
+        assert!(matches!(reader.delete_collection("docs"), Err(VectorDbError::ReadOnly(_))));
+        assert!(matches!(reader.migrate_all(false), Ok(reports) if reports.is_empty()));
+        assert!(Storage::open_read_only(dir.join("missing")).is_err());
+
+        // verrou relâché à la fermeture
+        drop(writer);
+        let writer = Storage::new(&dir).unwrap();
+        assert_eq!(writer.list_collections().unwrap(), vec!["docs"]);
+    }
+
+    #[test]
+    fn test_packed_roundtrip() {
+        let dir = TempDir::new("packed");
+        let storage = Storage::new(dir.join("source")).unwrap();
+        let mut docs = Collection::new_with_ivf("docs".to_string(), 2, 2);
+        let metadatas = (0..30).map(|i| HashMap::from([("n".to_string(), MetadataValue::Int(i))])).collect();
+        let embeddings = (0..30).map(|i| vec![(i as f32 * 0.2).cos(), (i as f32 * 0.2).sin()]).collect();
+        docs.add((0..30).map(|i| format!("v{}", i)).collect(), embeddings, Some(metadatas)).unwrap();
+        let mut notes = Collection::new("notes".to_string(), 3);
+        notes.add(vec!["n".into()], vec![vec![0.0, 0.0, 1.0]], None).unwrap();
+        storage.save_collection(&docs).unwrap();
+        storage.save_collection(&notes).unwrap();
+
+        let packed = dir.join("all.vdb");
+        assert_eq!(storage.export_packed(&packed).unwrap(), 2);
+
+        let rows = |coll: &Collection| {
+            let got = coll.get(None, None).unwrap();
+            let mut rows: Vec<_> = got.ids.into_iter().zip(got.embeddings.unwrap()).collect();
+            rows.sort_by(|a, b| a.0.cmp(&b.0));
+            rows
+        };
+
+        // lecture d'une seule collection sans rien écrire
+        let target = Storage::new(dir.join("target")).unwrap();
+        let loaded = target.load_packed_collection(&packed, "notes").unwrap();
+        assert_eq!(rows(&loaded), rows(&notes));
+        assert!(target.list_collections().unwrap().is_empty());
+        assert!(matches!(target.load_packed_collection(&packed, "missing"), Err(VectorDbError::CollectionNotFound(_))));
+
+        let mut names = target.import_packed(&packed).unwrap();
+        names.sort();
+        assert_eq!(names, ["docs", "notes"]);
+        let imported = target.load_collection("docs").unwrap();
+        assert_eq!(rows(&imported), rows(&docs));
+        assert_eq!(rows(&target.load_collection("notes").unwrap()), rows(&notes));
+        assert!(imported.config.use_ivf);
+
+        // fichier qui n'est pas un .vdb
+        fs::write(dir.join("bad.vdb"), b"not a packed file").unwrap();
+        assert!(matches!(target.import_packed(&dir.join("bad.vdb")), Err(VectorDbError::Serialization(_))));
+        let reader = Storage::open_read_only(dir.join("target")).unwrap();
+        assert!(matches!(reader.import_packed(&packed), Err(VectorDbError::ReadOnly(_))));
+    }
+
+    #[test]
+    fn test_scan_quarantines_broken_collections() {
+        let dir = TempDir::new("scan");
+        let storage = Storage::new(&dir).unwrap();
+        let mut coll = Collection::new("ok".to_string(), 2);
+        coll.add(vec!["a".into()], vec![vec![1.0, 0.0]], None).unwrap();
+        storage.save_collection(&coll).unwrap();
+        fs::write(storage.collection_path("ok").join("metadata.bin.tmp"), b"partial").unwrap();
+        // première sauvegarde interrompue avant l'en-tête
+        fs::create_dir_all(storage.collection_path("no-header")).unwrap();
+        fs::write(storage.collection_path("no-header").join(METADATA_FILE), b"").unwrap();
+        // en-tête sans données
+        let broken = Collection::new("no-data".to_string(), 2);
+        storage.save_collection(&broken).unwrap();
+        fs::remove_file(storage.collection_path("no-data").join(EMBEDDINGS_FILE)).unwrap();
+        fs::create_dir_all(storage.collection_path("empty")).unwrap();
+
+        let planned = storage.scan_consistency(true).unwrap();
+        assert_eq!(planned.scanned, 4);
+        assert_eq!(planned.removed_temp_files, ["ok/metadata.bin.tmp"]);
+        assert_eq!(planned.removed_empty_dirs, ["empty"]);
+        let quarantined: Vec<&str> = planned.quarantined.iter().map(|q| q.collection.as_str()).collect();
+        assert_eq!(quarantined, ["no-data", "no-header"]);
+        assert!(planned.quarantined.iter().all(|q| q.moved_to.is_none()));
+        assert_eq!(storage.list_collections().unwrap().len(), 4);
+
+        let report = storage.scan_consistency(false).unwrap();
+        assert_eq!(report.quarantined.len(), 2);
+        assert!(report.quarantined[1].reason.contains(HEADER_FILE));
+        assert!(dir.join(QUARANTINE_DIR).join("no-data").join(HEADER_FILE).exists());
+        assert!(dir.join(QUARANTINE_DIR).join("no-header").join(METADATA_FILE).exists());
+        assert!(!storage.collection_path("ok").join("metadata.bin.tmp").exists());
+        assert_eq!(storage.list_collections().unwrap(), vec!["ok"]);
+        assert_eq!(storage.load_collection("ok").unwrap().count(), 1);
+        assert!(storage.scan_consistency(false).unwrap().is_clean());
+
+        // même nom déjà en quarantaine : pas d'écrasement
+        fs::create_dir_all(storage.collection_path("no-header")).unwrap();
+        fs::write(storage.collection_path("no-header").join(METADATA_FILE), b"").unwrap();
+        let moved_to = storage.scan_consistency(false).unwrap().quarantined[0].moved_to.clone().unwrap();
+        assert!(moved_to.contains("no-header-"), "{}", moved_to);
+    }
+
+    #[test]
+    fn test_corrupt_files() {
+        let dir = TempDir::new("corrupt");
+        let mut storage = Storage::new(&dir).unwrap();
+        let mut coll = Collection::new("docs".to_string(), 8);
+        let embeddings = (0..50).map(|i| (0..8).map(|d| ((i * 8 + d) as f32).sin()).collect()).collect();
+        coll.add((0..50).map(|i| format!("v{}", i)).collect(), embeddings, None).unwrap();
+        storage.save_collection(&coll).unwrap();
+        let coll_path = storage.collection_path("docs");
+
+        // fichier tronqué : erreur de données, pas d'E/S
+        let embeddings_path = coll_path.join(EMBEDDINGS_FILE);
+        let bytes = fs::read(&embeddings_path).unwrap();
+        fs::write(&embeddings_path, &bytes[..bytes.len() / 2]).unwrap();
+        let err = storage.load_collection("docs").unwrap_err();
+        assert!(matches!(err, VectorDbError::CorruptData { ref path, .. } if path.ends_with(EMBEDDINGS_FILE)), "{:?}", err);
+
+        // préfixe de longueur corrompu : refusé sans allouer la taille annoncée
+        let mut bytes = fs::read(coll_path.join(METADATA_FILE)).unwrap();
+        bytes[..8].copy_from_slice(&u64::MAX.to_le_bytes());
+        fs::write(coll_path.join(METADATA_FILE), &bytes).unwrap();
+        let err = storage.load_metadata("docs").unwrap_err();
+        assert!(matches!(err, VectorDbError::CorruptData { ref reason, .. } if reason.contains("exceeds the file size")), "{:?}", err);
+
+        // fichier au-delà de la limite configurée
+        storage.save_collection(&coll).unwrap();
+        storage.set_max_file_bytes(64);
+        let err = storage.load_collection("docs").unwrap_err();
+        assert!(matches!(err, VectorDbError::CorruptData { ref reason, .. } if reason.contains("VECTORDB_MAX_FILE_BYTES")), "{:?}", err);
+        storage.set_max_file_bytes(0);
+        assert_eq!(storage.load_collection("docs").unwrap().count(), 50);
     }
 }
@@ -0,0 +1,216 @@
+// jeux de données synthétiques reproductibles (feature `testing`) : nuages gaussiens
+// autour de centres tirés sur la sphère unité, étiquette du nuage en métadonnée.
+// Même graine, mêmes données : une source commune aux tests, exemples et benchmarks.
+// Aussi les répertoires temporaires des tests (TempDir)
+use crate::collection::Collection;
+use crate::error::Result;
+use crate::vector::MetadataValue;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::collections::HashMap;
+use std::ops::Deref;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+// clé de métadonnée portant l'indice du nuage d'un vecteur
+pub const LABEL_KEY: &str = "label";
+
+#[derive(Debug, Clone)]
+pub struct BlobConfig {
+    pub n_vectors: usize,
+    pub dimension: usize,
+    pub n_blobs: usize,
+    // écart-type du bruit par composante autour du centre
+    pub spread: f32,
+    pub seed: u64,
+}
+
+impl BlobConfig {
+    pub fn new(n_vectors: usize, dimension: usize, n_blobs: usize) -> Self {
+        Self {
+            n_vectors,
+            dimension,
+            n_blobs: n_blobs.max(1),
+            spread: 0.05,
+            seed: 42,
+        }
+    }
+
+    pub fn with_spread(mut self, spread: f32) -> Self {
+        self.spread = spread;
+        self
+    }
+
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    pub fn generate(&self) -> Dataset {
+        let mut rng = StdRng::seed_from_u64(self.seed);
+        let centers: Vec<Vec<f32>> = (0..self.n_blobs).map(|_| unit_vector(&mut rng, self.dimension)).collect();
+
+        let mut ids = Vec::with_capacity(self.n_vectors);
+        let mut embeddings = Vec::with_capacity(self.n_vectors);
+        let mut labels = Vec::with_capacity(self.n_vectors);
+        for i in 0..self.n_vectors {
+            // nuages de tailles égales à une unité près
+            let label = i % self.n_blobs;
+            ids.push(format!("v{}", i));
+            embeddings.push(around(&mut rng, &centers[label], self.spread));
+            labels.push(label);
+        }
+
+        Dataset { ids, embeddings, labels, centers, seed: self.seed, spread: self.spread }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Dataset {
+    pub ids: Vec<String>,
+    pub embeddings: Vec<Vec<f32>>,
+    // nuage de chaque vecteur
+    pub labels: Vec<usize>,
+    pub centers: Vec<Vec<f32>>,
+    seed: u64,
+    spread: f32,
+}
+
+impl Dataset {
+    pub fn len(&self) -> usize {
+        self.ids.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ids.is_empty()
+    }
+
+    pub fn dimension(&self) -> usize {
+        self.centers.first().map_or(0, Vec::len)
+    }
+
+    // {"label": <nuage>} par vecteur
+    pub fn metadatas(&self) -> Vec<HashMap<String, MetadataValue>> {
+        self.labels
+            .iter()
+            .map(|&label| HashMap::from([(LABEL_KEY.to_string(), MetadataValue::Int(label as i64))]))
+            .collect()
+    }
+
+    // ajoute tous les vecteurs, étiquettes comprises, à `collection`
+    pub fn populate(&self, collection: &mut Collection) -> Result<()> {
+        collection.add_with_source(self.ids.clone(), self.embeddings.clone(), Some(self.metadatas()), "testing")
+    }
+
+    // `n` requêtes tirées dans les nuages (nuage i % n_blobs), avec leur étiquette ;
+    // indépendantes des vecteurs du jeu mais reproductibles
+    pub fn queries(&self, n: usize) -> Vec<(usize, Vec<f32>)> {
+        let mut rng = StdRng::seed_from_u64(self.seed.wrapping_add(1));
+        (0..n)
+            .map(|i| {
+                let label = i % self.centers.len();
+                (label, around(&mut rng, &self.centers[label], self.spread))
+            })
+            .collect()
+    }
+}
+
+static NEXT_TEMP_DIR: AtomicUsize = AtomicUsize::new(0);
+
+// répertoire temporaire d'un test, propre au test (nom) et à l'appel (processus,
+// compteur) : les tests lancés en parallèle ne se marchent pas dessus. Créé vide,
+// supprimé au drop, y compris quand une assertion échoue
+#[derive(Debug)]
+pub struct TempDir(PathBuf);
+
+impl TempDir {
+    pub fn new(name: &str) -> Self {
+        let n = NEXT_TEMP_DIR.fetch_add(1, Ordering::Relaxed);
+        let path = std::env::temp_dir().join(format!("vectordb-{}-test-{}-{}", name, std::process::id(), n));
+        let _ = std::fs::remove_dir_all(&path);
+        std::fs::create_dir_all(&path).expect("cannot create test directory");
+        Self(path)
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl Deref for TempDir {
+    type Target = Path;
+
+    fn deref(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl AsRef<Path> for TempDir {
+    fn as_ref(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
+
+// loi normale centrée réduite (Box-Muller)
+fn gaussian(rng: &mut StdRng) -> f32 {
+    let u1: f32 = rng.gen_range(f32::EPSILON..1.0);
+    let u2: f32 = rng.gen();
+    (-2.0 * u1.ln()).sqrt() * (2.0 * std::f32::consts::PI * u2).cos()
+}
+
+// direction uniforme sur la sphère unité
+fn unit_vector(rng: &mut StdRng, dimension: usize) -> Vec<f32> {
+    let mut v: Vec<f32> = (0..dimension).map(|_| gaussian(rng)).collect();
+    let norm = v.iter().map(|x| x * x).sum::<f32>().sqrt().max(f32::EPSILON);
+    v.iter_mut().for_each(|x| *x /= norm);
+    v
+}
+
+fn around(rng: &mut StdRng, center: &[f32], spread: f32) -> Vec<f32> {
+    center.iter().map(|c| c + spread * gaussian(rng)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::filter::FilterBuilder;
+
+    #[test]
+    fn test_temp_dir_is_unique_and_removed() {
+        let a = TempDir::new("tempdir");
+        let b = TempDir::new("tempdir");
+        assert_ne!(a.path(), b.path());
+        std::fs::write(a.join("file"), b"x").unwrap();
+        let path = a.to_path_buf();
+        drop(a);
+        assert!(!path.exists() && b.is_dir());
+    }
+
+    #[test]
+    fn test_blobs_are_reproducible_and_separable() {
+        let config = BlobConfig::new(300, 16, 5);
+        let dataset = config.generate();
+        assert_eq!((dataset.len(), dataset.dimension()), (300, 16));
+        assert_eq!(dataset.embeddings, config.generate().embeddings);
+        assert_ne!(dataset.embeddings, config.clone().with_seed(7).generate().embeddings);
+
+        let mut coll = Collection::new("blobs".to_string(), 16);
+        dataset.populate(&mut coll).unwrap();
+        assert_eq!(coll.count(), 300);
+
+        // nuages serrés : le plus proche voisin d'une requête est dans son nuage
+        for (label, query) in dataset.queries(10) {
+            let results = coll.query(&query, 1, None).unwrap();
+            assert_eq!(results[0].metadata[LABEL_KEY], MetadataValue::Int(label as i64));
+        }
+        let filter = FilterBuilder::new().eq(LABEL_KEY, 2i64).build().unwrap();
+        let expected = dataset.labels.iter().filter(|&&l| l == 2).count();
+        assert_eq!(coll.count_where(&filter), expected);
+    }
+}
@@ -0,0 +1,160 @@
+// ids supprimés et date de suppression : un ajout répliqué arrivé dans le désordre
+// (écrit avant la suppression, reçu après) ne ressuscite pas le vecteur. Seuls les
+// ajouts portant leur date d'origine (AddOptions::written_at) sont comparés ; un
+// ajout ordinaire recrée l'id et efface sa pierre tombale. Suivi activé par
+// collection (CollectionConfig::tombstones) : sans lui, written_at est sans effet
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::time::Duration;
+
+// fichier des pierres tombales d'une collection, à côté de metadata.bin
+pub const TOMBSTONES_FILE: &str = "tombstones.bin";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TombstonePolicy {
+    // durée de garde : au-delà, une réplique en retard n'est plus attendue
+    pub retention: Duration,
+    // nombre maximal par collection, les plus anciennes purgées d'abord ; 0 = illimité
+    pub max_tombstones: usize,
+}
+
+impl Default for TombstonePolicy {
+    fn default() -> Self {
+        Self { retention: Duration::from_secs(7 * 24 * 3600), max_tombstones: 1_000_000 }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TombstoneStats {
+    pub count: usize,
+    // date de la plus ancienne, epoch en microsecondes
+    pub oldest_micros: Option<i64>,
+    // purgées depuis le chargement
+    pub purged: usize,
+    // ajouts écartés car antérieurs à une suppression, depuis le chargement
+    pub blocked_adds: usize,
+}
+
+// id -> date de suppression, epoch en microsecondes
+#[derive(Debug, Clone, Default)]
+pub struct TombstoneSet {
+    deleted: HashMap<String, i64>,
+    purged: usize,
+    blocked_adds: usize,
+}
+
+impl TombstoneSet {
+    pub fn from_entries(entries: Vec<(String, i64)>) -> Self {
+        Self { deleted: entries.into_iter().collect(), ..Default::default() }
+    }
+
+    pub fn entries(&self) -> impl Iterator<Item = (&String, &i64)> {
+        self.deleted.iter()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.deleted.is_empty()
+    }
+
+    pub fn record(&mut self, id: &str, deleted_at: i64) {
+        let at = self.deleted.entry(id.to_string()).or_insert(deleted_at);
+        *at = (*at).max(deleted_at);
+    }
+
+    // un ajout écrit à `written_at` est-il antérieur à la suppression de `id` ?
+    pub fn blocks(&mut self, id: &str, written_at: i64) -> bool {
+        let blocked = self.deleted.get(id).is_some_and(|&deleted_at| written_at <= deleted_at);
+        if blocked {
+            self.blocked_adds += 1;
+        }
+        blocked
+    }
+
+    pub fn clear(&mut self, id: &str) -> bool {
+        self.deleted.remove(id).is_some()
+    }
+
+    fn cutoff(policy: &TombstonePolicy, now_micros: i64) -> i64 {
+        now_micros.saturating_sub(policy.retention.as_micros().min(i64::MAX as u128) as i64)
+    }
+
+    // purge utile : sans elle, la purge périodique n'écrit pas la collection
+    pub fn needs_purge(&self, policy: &TombstonePolicy, now_micros: i64) -> bool {
+        let cutoff = Self::cutoff(policy, now_micros);
+        (policy.max_tombstones > 0 && self.deleted.len() > policy.max_tombstones)
+            || self.deleted.values().any(|&at| at < cutoff)
+    }
+
+    // retire les pierres tombales plus anciennes que la durée de garde, puis les plus
+    // anciennes au-delà du maximum ; renvoie le nombre purgé
+    pub fn purge(&mut self, policy: &TombstonePolicy, now_micros: i64) -> usize {
+        let before = self.deleted.len();
+        let cutoff = Self::cutoff(policy, now_micros);
+        self.deleted.retain(|_, &mut at| at >= cutoff);
+        if policy.max_tombstones > 0 && self.deleted.len() > policy.max_tombstones {
+            let mut dates: Vec<i64> = self.deleted.values().copied().collect();
+            let excess = dates.len() - policy.max_tombstones;
+            dates.select_nth_unstable(excess - 1);
+            let threshold = dates[excess - 1];
+            // à date égale, quelques-unes de plus peuvent partir
+            self.deleted.retain(|_, &mut at| at > threshold);
+        }
+        let purged = before - self.deleted.len();
+        self.purged += purged;
+        purged
+    }
+
+    pub fn stats(&self) -> TombstoneStats {
+        TombstoneStats {
+            count: self.deleted.len(),
+            oldest_micros: self.deleted.values().min().copied(),
+            purged: self.purged,
+            blocked_adds: self.blocked_adds,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::collection::{AddOptions, Collection, CollectionConfig};
+
+    #[test]
+    fn test_out_of_order_add_does_not_resurrect() {
+        // sans pierres tombales (défaut) : rien n'est gardé des suppressions
+        let mut plain = Collection::new("plain".to_string(), 2);
+        plain.add(vec!["a".into()], vec![vec![1.0, 0.0]], None).unwrap();
+        let written_at = chrono::Utc::now().timestamp_micros();
+        plain.delete(vec!["a".into()]).unwrap();
+        assert_eq!(plain.tombstone_stats().count, 0);
+        let replayed = AddOptions::new("replica").with_written_at(written_at);
+        assert_eq!(plain.add_with_options(vec!["a".into()], vec![vec![1.0, 0.0]], None, replayed).unwrap().added, 1);
+
+        let mut coll = Collection::from_config(CollectionConfig::new("docs".to_string(), 2).with_tombstones());
+        coll.add(vec!["a".into(), "b".into()], vec![vec![1.0, 0.0], vec![0.0, 1.0]], None).unwrap();
+        let written_at = chrono::Utc::now().timestamp_micros();
+        coll.delete(vec!["a".into()]).unwrap();
+
+        // ajout répliqué écrit avant la suppression : écarté
+        let replayed = AddOptions::new("replica").with_written_at(written_at);
+        let outcome = coll.add_with_options(vec!["a".into()], vec![vec![1.0, 0.0]], None, replayed).unwrap();
+        assert_eq!((outcome.added, outcome.tombstoned), (0, 1));
+        assert_eq!(coll.count(), 1);
+        assert_eq!(coll.tombstone_stats().count, 1);
+
+        // écrit après la suppression, ou ajout ordinaire : l'id revient
+        let later = AddOptions::new("replica").with_written_at(chrono::Utc::now().timestamp_micros() + 1);
+        let outcome = coll.add_with_options(vec!["a".into()], vec![vec![1.0, 0.0]], None, later).unwrap();
+        assert_eq!(outcome.added, 1);
+        assert_eq!(coll.tombstone_stats().count, 0);
+
+        let mut set = TombstoneSet::default();
+        (0..5).for_each(|i| set.record(&format!("v{}", i), i * 1_000_000));
+        let policy = TombstonePolicy { retention: Duration::from_secs(3), max_tombstones: 2 };
+        // v0 trop ancienne, puis v1 et v2 au-delà du maximum
+        assert!(set.needs_purge(&policy, 4_000_000));
+        assert_eq!(set.purge(&policy, 4_000_000), 3);
+        assert!(!set.needs_purge(&policy, 4_000_000));
+        assert_eq!(set.stats().oldest_micros, Some(3_000_000));
+    }
+}
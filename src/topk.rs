@@ -0,0 +1,135 @@
+// sélection des k meilleurs éléments par tas borné : O(n log k) comparaisons et
+// au plus k éléments gardés, au lieu de trier tous les candidats. Partagé par
+// les chemins de recherche (linéaire, IVF) et le sondage des clusters
+use std::cmp::Ordering;
+
+pub struct TopK<T, F> {
+    k: usize,
+    // tas max selon `cmp` : le moins bon des retenus à la racine
+    heap: Vec<T>,
+    cmp: F,
+}
+
+impl<T, F> TopK<T, F>
+where
+    F: Fn(&T, &T) -> Ordering,
+{
+    // `cmp` classe les éléments, Less = meilleur
+    pub fn new(k: usize, cmp: F) -> Self {
+        // pas de réservation démesurée si k vaut usize::MAX (tout garder)
+        Self { k, heap: Vec::with_capacity(k.min(1024)), cmp }
+    }
+
+    pub fn len(&self) -> usize {
+        self.heap.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.heap.is_empty()
+    }
+
+    pub fn push(&mut self, item: T) {
+        if self.k == 0 {
+            return;
+        }
+        if self.heap.len() < self.k {
+            self.heap.push(item);
+            self.sift_up(self.heap.len() - 1);
+        } else if (self.cmp)(&item, &self.heap[0]) == Ordering::Less {
+            self.heap[0] = item;
+            self.sift_down(0);
+        }
+    }
+
+    // fusion de sélections partielles (un tas par thread rayon)
+    pub fn merge(mut self, other: Self) -> Self {
+        for item in other.heap {
+            self.push(item);
+        }
+        self
+    }
+
+    // retenus du meilleur au moins bon ; tri stable, les ex aequo gardent
+    // l'ordre du tas
+    pub fn into_sorted_vec(self) -> Vec<T> {
+        let cmp = self.cmp;
+        let mut items = self.heap;
+        items.sort_by(|a, b| cmp(a, b));
+        items
+    }
+
+    fn sift_up(&mut self, mut i: usize) {
+        while i > 0 {
+            let parent = (i - 1) / 2;
+            if (self.cmp)(&self.heap[i], &self.heap[parent]) != Ordering::Greater {
+                break;
+            }
+            self.heap.swap(i, parent);
+            i = parent;
+        }
+    }
+
+    fn sift_down(&mut self, mut i: usize) {
+        let len = self.heap.len();
+        loop {
+            let left = 2 * i + 1;
+            if left >= len {
+                break;
+            }
+            let right = left + 1;
+            let worst = if right < len && (self.cmp)(&self.heap[right], &self.heap[left]) == Ordering::Greater {
+                right
+            } else {
+                left
+            };
+            if (self.cmp)(&self.heap[worst], &self.heap[i]) != Ordering::Greater {
+                break;
+            }
+            self.heap.swap(i, worst);
+            i = worst;
+        }
+    }
+}
+
+// k plus petites valeurs de `items` selon `cmp`, triées
+pub fn top_k_by<T, I, F>(items: I, k: usize, cmp: F) -> Vec<T>
+where
+    I: IntoIterator<Item = T>,
+    F: Fn(&T, &T) -> Ordering,
+{
+    let mut top = TopK::new(k, cmp);
+    for item in items {
+        top.push(item);
+    }
+    top.into_sorted_vec()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_top_k_matches_full_sort() {
+        let values: Vec<f32> = (0..500).map(|i| ((i * 7919) % 503) as f32 / 3.0).collect();
+        let mut sorted = values.clone();
+        sorted.sort_by(|a, b| a.total_cmp(b));
+
+        for k in [0, 1, 5, 64, 500, 1000] {
+            let top = top_k_by(values.iter().copied(), k, |a, b| a.total_cmp(b));
+            assert_eq!(top, sorted[..k.min(values.len())]);
+        }
+
+        // fusion de tas partiels, comme avec rayon
+        let cmp = |a: &f32, b: &f32| a.total_cmp(b);
+        let merged = values
+            .chunks(37)
+            .map(|chunk| {
+                let mut top = TopK::new(10, cmp);
+                chunk.iter().for_each(|v| top.push(*v));
+                top
+            })
+            .fold(TopK::new(10, cmp), TopK::merge);
+        assert_eq!(merged.len(), 10);
+        assert_eq!(merged.into_sorted_vec(), sorted[..10]);
+    }
+}
@@ -1,23 +1,120 @@
+use crate::distance::hamming_distance;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::sync::Arc;
+
+// namespace réservé aux métadonnées système, non modifiable par l'utilisateur
+pub const SYSTEM_NAMESPACE: &str = "_system";
+pub const SYSTEM_CREATED_AT: &str = "_system.created_at";
+pub const SYSTEM_UPDATED_AT: &str = "_system.updated_at";
+pub const SYSTEM_SOURCE: &str = "_system.source";
+pub const SYSTEM_CONTENT_HASH: &str = "_system.content_hash";
+
+pub fn is_reserved_key(key: &str) -> bool {
+    key == SYSTEM_NAMESPACE
+        || key
+            .strip_prefix(SYSTEM_NAMESPACE)
+            .is_some_and(|rest| rest.starts_with('.'))
+}
+
+// empreinte par défaut d'un embedding : FNV-1a 64 bits sur les octets f32 little-endian
+// de l'embedding tel qu'envoyé (avant normalisation), en hexadécimal
+pub fn embedding_hash(embedding: &[f32]) -> String {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for byte in embedding.iter().flat_map(|v| v.to_le_bytes()) {
+        hash ^= byte as u64;
+        hash = hash.wrapping_mul(0x0000_0100_0000_01b3);
+    }
+    format!("{:016x}", hash)
+}
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub enum MetadataValue {
-    String(String),
+    // partagée entre les vecteurs d'une collection par son dictionnaire de valeurs
+    String(Arc<str>),
     Int(i64),
     Float(f64),
     Bool(bool),
+    // epoch en microsecondes (UTC)
+    DateTime(i64),
+    GeoPoint { lat: f64, lon: f64 },
+    Null,
+    Object(HashMap<String, MetadataValue>),
+}
+
+impl MetadataValue {
+    // parse une date RFC3339 ("2024-01-01T12:00:00Z") en DateTime
+    pub fn parse_datetime(s: &str) -> Option<Self> {
+        chrono::DateTime::parse_from_rfc3339(s)
+            .ok()
+            .map(|dt| MetadataValue::DateTime(dt.timestamp_micros()))
+    }
+
+    // un objet {"lat": .., "lon": ..} est interprété comme un point géographique
+    pub fn parse_geo_point(obj: &serde_json::Map<String, serde_json::Value>) -> Option<Self> {
+        if obj.len() != 2 {
+            return None;
+        }
+        let lat = obj.get("lat")?.as_f64()?;
+        let lon = obj.get("lon")?.as_f64()?;
+        Some(MetadataValue::GeoPoint { lat, lon })
+    }
+
+    pub fn now() -> Self {
+        MetadataValue::DateTime(chrono::Utc::now().timestamp_micros())
+    }
+
+    // conversion depuis le JSON de l'API, les chaînes RFC3339 deviennent des DateTime
+    pub fn from_json(value: serde_json::Value) -> Self {
+        match value {
+            serde_json::Value::String(s) => {
+                Self::parse_datetime(&s).unwrap_or(MetadataValue::String(s.into()))
+            }
+            serde_json::Value::Number(n) => {
+                if let Some(i) = n.as_i64() {
+                    MetadataValue::Int(i)
+                } else {
+                    MetadataValue::Float(n.as_f64().unwrap_or(0.0))
+                }
+            }
+            serde_json::Value::Bool(b) => MetadataValue::Bool(b),
+            serde_json::Value::Null => MetadataValue::Null,
+            serde_json::Value::Object(obj) => Self::parse_geo_point(&obj).unwrap_or_else(|| {
+                MetadataValue::Object(
+                    obj.into_iter().map(|(k, v)| (k, Self::from_json(v))).collect(),
+                )
+            }),
+            _ => MetadataValue::String(value.to_string().into()),
+        }
+    }
+
+    // représentation JSON "naturelle" (sans tag serde), symétrique de from_json
+    pub fn to_json(&self) -> serde_json::Value {
+        use serde_json::Value;
+        match self {
+            MetadataValue::String(s) => Value::String(s.to_string()),
+            MetadataValue::Int(i) => Value::from(*i),
+            MetadataValue::Float(f) => Value::from(*f),
+            MetadataValue::Bool(b) => Value::Bool(*b),
+            MetadataValue::DateTime(us) => chrono::DateTime::from_timestamp_micros(*us)
+                .map(|dt| Value::String(dt.to_rfc3339_opts(chrono::SecondsFormat::AutoSi, true)))
+                .unwrap_or(Value::Null),
+            MetadataValue::GeoPoint { lat, lon } => serde_json::json!({"lat": lat, "lon": lon}),
+            MetadataValue::Null => Value::Null,
+            MetadataValue::Object(obj) => metadata_to_json(obj),
+        }
+    }
 }
 
 impl From<String> for MetadataValue {
     fn from(s: String) -> Self {
-        MetadataValue::String(s)
+        MetadataValue::String(s.into())
     }
 }
 
 impl From<&str> for MetadataValue {
     fn from(s: &str) -> Self {
-        MetadataValue::String(s.to_string())
+        MetadataValue::String(s.into())
     }
 }
 
@@ -27,6 +124,13 @@ impl From<i64> for MetadataValue {
     }
 }
 
+// littéraux entiers sans suffixe (`gte("year", 2020)`)
+impl From<i32> for MetadataValue {
+    fn from(i: i32) -> Self {
+        MetadataValue::Int(i as i64)
+    }
+}
+
 impl From<f64> for MetadataValue {
     fn from(f: f64) -> Self {
         MetadataValue::Float(f)
@@ -39,6 +143,154 @@ impl From<bool> for MetadataValue {
     }
 }
 
+pub fn metadata_to_json(metadata: &HashMap<String, MetadataValue>) -> serde_json::Value {
+    serde_json::Value::Object(
+        metadata.iter().map(|(k, v)| (k.clone(), v.to_json())).collect(),
+    )
+}
+
+// résout "author.country" en descendant dans les objets imbriqués,
+// une clé plate contenant des points ("_system.created_at") reste prioritaire
+pub fn lookup_path<'a>(
+    metadata: &'a HashMap<String, MetadataValue>,
+    path: &str,
+) -> Option<&'a MetadataValue> {
+    if let Some(val) = metadata.get(path) {
+        return Some(val);
+    }
+
+    path.match_indices('.').find_map(|(i, _)| match metadata.get(&path[..i]) {
+        Some(MetadataValue::Object(inner)) => lookup_path(inner, &path[i + 1..]),
+        _ => None,
+    })
+}
+
+// (dé)sérialisation JSON des métadonnées dans les réponses de l'API
+pub mod json_metadata {
+    use super::MetadataValue;
+    use serde::{Deserialize, Deserializer, Serializer};
+    use std::collections::HashMap;
+
+    pub fn serialize<S: Serializer>(
+        metadata: &HashMap<String, MetadataValue>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.collect_map(metadata.iter().map(|(k, v)| (k, v.to_json())))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<HashMap<String, MetadataValue>, D::Error> {
+        let raw = HashMap::<String, serde_json::Value>::deserialize(deserializer)?;
+        Ok(raw.into_iter().map(|(k, v)| (k, MetadataValue::from_json(v))).collect())
+    }
+}
+
+pub mod json_metadata_list {
+    use super::MetadataValue;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+    use std::collections::HashMap;
+
+    pub fn serialize<S: Serializer>(
+        metadatas: &Option<Vec<HashMap<String, MetadataValue>>>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        metadatas
+            .as_ref()
+            .map(|list| list.iter().map(super::metadata_to_json).collect::<Vec<_>>())
+            .serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<Vec<HashMap<String, MetadataValue>>>, D::Error> {
+        let raw = Option::<Vec<HashMap<String, serde_json::Value>>>::deserialize(deserializer)?;
+        Ok(raw.map(|list| {
+            list.into_iter()
+                .map(|m| m.into_iter().map(|(k, v)| (k, MetadataValue::from_json(v))).collect())
+                .collect()
+        }))
+    }
+}
+
+// type des embeddings d'une collection : f32 comparés en cosinus, ou bits comparés
+// en distance de Hamming (empreintes binaires, hachages perceptuels, LSH)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum VectorType {
+    #[default]
+    Float,
+    Binary,
+}
+
+// vecteurs des collections binaires, une entrée (id, BinaryVector) par vecteur
+pub const BINARY_FILE: &str = "binary.bin";
+
+// vecteur binaire de `dimension` bits rangés par mots de 64 : bit i dans le bit
+// i % 64 du mot i / 64, bits au-delà de la dimension à zéro
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BinaryVector {
+    words: Vec<u64>,
+}
+
+impl BinaryVector {
+    pub fn n_words(dimension: usize) -> usize {
+        dimension.div_ceil(64)
+    }
+
+    // composantes 0.0 / 1.0 ; None si une autre valeur apparaît
+    pub fn from_bits(values: &[f32]) -> Option<Self> {
+        if values.iter().any(|&v| v != 0.0 && v != 1.0) {
+            return None;
+        }
+        Some(Self::threshold(values))
+    }
+
+    // bit à 1 pour chaque composante > 0 (requête normalisée d'un vecteur 0/1)
+    pub fn threshold(values: &[f32]) -> Self {
+        let mut words = vec![0u64; Self::n_words(values.len())];
+        for (i, _) in values.iter().enumerate().filter(|(_, &v)| v > 0.0) {
+            words[i / 64] |= 1 << (i % 64);
+        }
+        Self { words }
+    }
+
+    // mots déjà empaquetés ; None si leur nombre ne correspond pas à la dimension
+    // ou si un bit au-delà de la dimension est à 1
+    pub fn from_words(words: Vec<u64>, dimension: usize) -> Option<Self> {
+        if words.len() != Self::n_words(dimension) {
+            return None;
+        }
+        let tail = dimension % 64;
+        if tail > 0 && words.last().is_some_and(|w| w >> tail != 0) {
+            return None;
+        }
+        Some(Self { words })
+    }
+
+    pub fn words(&self) -> &[u64] {
+        &self.words
+    }
+
+    pub fn bytes(&self) -> usize {
+        self.words.len() * 8
+    }
+
+    pub fn count_ones(&self) -> u32 {
+        self.words.iter().map(|w| w.count_ones()).sum()
+    }
+
+    // composantes 0.0 / 1.0, pour les réponses et les chemins f32
+    pub fn to_floats(&self, dimension: usize) -> Vec<f32> {
+        (0..dimension).map(|i| ((self.words[i / 64] >> (i % 64)) & 1) as f32).collect()
+    }
+
+    #[inline]
+    pub fn hamming(&self, other: &BinaryVector) -> u32 {
+        hamming_distance(&self.words, &other.words)
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct VectorEntry {
     pub id: String,
@@ -16,17 +16,54 @@ class Collection:
     def add(
         self,
         ids: List[str],
-        embeddings: List[List[float]],
+        embeddings: Optional[List[List[float]]] = None,  # calculés par le serveur si modèle hash-ngram-v1
         metadatas: Optional[List[Dict[str, Any]]] = None,
-        documents: Optional[List[str]] = None,  # Ignoré, pour compatibilité ChromaDB
+        documents: Optional[List[str]] = None,  # stockés dans la métadonnée "document"
+        embedding_model: Optional[str] = None,
+        on_conflict: Optional[str] = None,  # "skip", "overwrite" (défaut), "merge" ou "error"
+        content_hashes: Optional[List[str]] = None,
+        priority: Optional[str] = None,  # "batch" : voie basse priorité pour les imports en masse
     ):
         url = f"{self.base_url}/collections/{self.name}/add"
-        data = {
-            "ids": ids,
-            "embeddings": embeddings,
-        }
+        data = {"ids": ids}
+        if embeddings is not None:
+            data["embeddings"] = embeddings
         if metadatas is not None:
             data["metadatas"] = metadatas
+        if documents is not None:
+            data["documents"] = documents
+        if embedding_model is not None:
+            data["embedding_model"] = embedding_model
+        if on_conflict is not None:
+            data["on_conflict"] = on_conflict
+        if content_hashes is not None:
+            data["content_hashes"] = content_hashes
+        headers = {"X-Priority": priority} if priority is not None else None
+
+        response = requests.post(url, json=data, headers=headers)
+        response.raise_for_status()
+        return response.json()
+
+    def upsert(
+        self,
+        ids: List[str],
+        embeddings: Optional[List[List[float]]] = None,
+        metadatas: Optional[List[Dict[str, Any]]] = None,
+        documents: Optional[List[str]] = None,
+        embedding_model: Optional[str] = None,
+    ):
+        """Ajoute les ids nouveaux, remplace l'embedding et fusionne les métadonnées des
+        ids existants ; renvoie {"inserted": .., "updated": ..}"""
+        url = f"{self.base_url}/collections/{self.name}/upsert"
+        data = {"ids": ids}
+        if embeddings is not None:
+            data["embeddings"] = embeddings
+        if metadatas is not None:
+            data["metadatas"] = metadatas
+        if documents is not None:
+            data["documents"] = documents
+        if embedding_model is not None:
+            data["embedding_model"] = embedding_model
 
         response = requests.post(url, json=data)
         response.raise_for_status()
@@ -36,6 +73,10 @@ class Collection:
         self,
         ids: Optional[List[str]] = None,
         include: Optional[List[str]] = None,
+        where: Optional[Dict[str, Any]] = None,
+        limit: Optional[int] = None,
+        offset: Optional[int] = None,
+        cursor: Optional[str] = None,
     ):
         url = f"{self.base_url}/collections/{self.name}/get"
         data = {}
@@ -43,11 +84,34 @@ class Collection:
             data["ids"] = ids
         if include is not None:
             data["include"] = include
+        if where is not None:
+            data["where"] = where
+        if limit is not None:
+            data["limit"] = limit
+        if offset is not None:
+            data["offset"] = offset
+        if cursor is not None:
+            data["cursor"] = cursor
 
         response = requests.post(url, json=data)
         response.raise_for_status()
         return response.json()
 
+    def scroll(
+        self,
+        batch_size: int = 1000,
+        where: Optional[Dict[str, Any]] = None,
+        include: Optional[List[str]] = None,
+    ):
+        """Parcourt la collection par pages (ordre des ids), une page à la fois"""
+        cursor = None
+        while True:
+            page = self.get(include=include, where=where, limit=batch_size, cursor=cursor)
+            yield page
+            cursor = page.get("next_cursor")
+            if cursor is None:
+                break
+
     def update(
         self,
         ids: List[str],
@@ -71,20 +135,94 @@ class Collection:
         response.raise_for_status()
         return response.json()
 
+    def delete_where(self, where: Dict[str, Any]):
+        """Supprime tous les éléments correspondant au filtre"""
+        url = f"{self.base_url}/collections/{self.name}/delete_where"
+        response = requests.post(url, json={"where": where})
+        response.raise_for_status()
+        return response.json()
+
     def query(
         self,
-        query_embedding: List[float],
+        query_embedding: Optional[List[float]] = None,
         n_results: int = 10,
         where: Optional[Dict[str, Any]] = None,
+        embedding_model: Optional[str] = None,
+        exact: bool = False,  # recherche exhaustive, sans l'index IVF
+        explain_hits: bool = False,  # "explain" par résultat, réponse {"results", "plan", "filter_stage"}
+        direction: Optional[str] = None,  # "asc" (défaut) ou "desc"
+        sort_by: Optional[List[Dict[str, str]]] = None,  # [{"key": "timestamp", "direction": "desc"}]
+        query_text: Optional[str] = None,  # à la place de query_embedding (modèle hash-ngram-v1)
+        compare: bool = False,  # résultats de l'index et exhaustifs, avec le rappel
+        query_embeddings: Optional[List[List[float]]] = None,  # lot : une liste de résultats par requête
+        score_threshold: Optional[float] = None,  # similarité minimale, [] sans voisin assez proche
     ):
         url = f"{self.base_url}/collections/{self.name}/query"
-        data = {
-            "query_embedding": query_embedding,
-            "n_results": n_results,
-        }
+        data = {"n_results": n_results}
+        if query_embedding is not None:
+            data["query_embedding"] = query_embedding
+        if query_embeddings is not None:
+            data["query_embeddings"] = query_embeddings
+        if query_text is not None:
+            data["query_text"] = query_text
+
+        if where is not None:
+            data["where"] = where
+        if embedding_model is not None:
+            data["embedding_model"] = embedding_model
+        if exact:
+            data["exact"] = True
+        if explain_hits:
+            data["explain_hits"] = True
+        if direction is not None:
+            data["direction"] = direction
+        if sort_by is not None:
+            data["sort_by"] = sort_by
+        if compare:
+            data["compare"] = True
+        if score_threshold is not None:
+            data["score_threshold"] = score_threshold
+
+        response = requests.post(url, json=data)
+        response.raise_for_status()
+        return response.json()
 
+    def range_query(
+        self,
+        query_embedding: List[float],
+        max_distance: float,
+        where: Optional[Dict[str, Any]] = None,
+        max_results: Optional[int] = None,
+    ):
+        """Tous les vecteurs à distance <= max_distance, les plus proches d'abord ;
+        renvoie {"count": .., "truncated": .., "results": [...]}"""
+        url = f"{self.base_url}/collections/{self.name}/range_query"
+        data = {"query_embedding": query_embedding, "max_distance": max_distance}
         if where is not None:
             data["where"] = where
+        if max_results is not None:
+            data["max_results"] = max_results
+
+        response = requests.post(url, json=data)
+        response.raise_for_status()
+        return response.json()
+
+    def score_ids(
+        self,
+        ids: List[str],
+        query_embedding: Optional[List[float]] = None,
+        query_text: Optional[str] = None,
+        embedding_model: Optional[str] = None,
+    ):
+        """Distances pour une liste d'ids donnée (re-ranking), sans passer par l'index"""
+        url = f"{self.base_url}/collections/{self.name}/score_ids"
+        data = {"ids": ids}
+        if query_embedding is not None:
+            data["query_embedding"] = query_embedding
+        if query_text is not None:
+            data["query_text"] = query_text
+        if embedding_model is not None:
+            data["embedding_model"] = embedding_model
 
         response = requests.post(url, json=data)
         response.raise_for_status()
@@ -95,6 +233,13 @@ class Collection:
         result = self.get(include=[])
         return len(result.get('ids', []))
 
+    def diff(self, hashes: Dict[str, str]):
+        """Classe des ids (id -> empreinte) en new / changed / unchanged"""
+        url = f"{self.base_url}/collections/{self.name}/diff"
+        response = requests.post(url, json={"hashes": hashes})
+        response.raise_for_status()
+        return response.json()
+
     def stats(self):
         """Retourne les statistiques de la collection"""
         url = f"{self.base_url}/collections/{self.name}/stats"
@@ -142,9 +287,12 @@ class BatchContext:
 
 
 class PersistentClient:
-    def __init__(self, path: str = "./vector_db", host: str = "localhost", port: int = 8080):
+    def __init__(self, path: str = "./vector_db", host: str = "localhost", port: int = 8080,
+                 admin_token: Optional[str] = None):
         self.path = path
-        self.base_url = f"http://{host}:{port}"
+        self.base_url = f"http://{host}:{port}/api/v1"
+        # jeton VECTORDB_ADMIN_TOKEN du serveur, envoyé sur les routes /admin/*
+        self.admin_headers = {"Authorization": f"Bearer {admin_token}"} if admin_token else {}
         # Vérifier que le serveur est accessible
         try:
             requests.get(f"{self.base_url}/collections", timeout=2)
@@ -157,19 +305,35 @@ class PersistentClient:
     def create_collection(
         self,
         name: str,
-        dimension: int = 1280,
+        dimension: Optional[int] = None,
         use_ivf: bool = False,
-        n_clusters: int = 100
+        n_clusters: int = 100,
+        embedding_model: Optional[str] = None,
+        profile: Optional[str] = None,
+        rebuild_policy: Optional[Dict] = None,
+        tombstones: bool = False,
     ):
+        """profile : profil défini côté serveur (GET /profiles), les autres paramètres le surchargent
+        rebuild_policy : {"auto": bool, "threshold_percent": float, "min_modifications": int}, champs optionnels
+        tombstones : garde les ids supprimés, un ajout rejoué avec un written_at antérieur ne les ressuscite pas"""
         url = f"{self.base_url}/collections"
-        data = {
-            "name": name,
-            "dimension": dimension,
-        }
-
+        data = {"name": name}
+
+        if profile is not None:
+            data["profile"] = profile
+            if dimension is not None:
+                data["dimension"] = dimension
+        else:
+            data["dimension"] = dimension if dimension is not None else 1280
         if use_ivf:
             data["use_ivf"] = True
             data["n_clusters"] = n_clusters
+        if embedding_model is not None:
+            data["embedding_model"] = embedding_model
+        if rebuild_policy is not None:
+            data["rebuild_policy"] = rebuild_policy
+        if tombstones:
+            data["tombstones"] = True
 
         try:
             response = requests.post(url, json=data)
@@ -187,6 +351,63 @@ class PersistentClient:
         response.raise_for_status()
         return Collection(name, self.base_url)
 
+    def update_collection(self, name: str, rebuild_policy: Dict):
+        """Modifie la politique de rebuild IVF ; les champs absents sont inchangés"""
+        url = f"{self.base_url}/collections/{name}"
+        response = requests.patch(url, json={"rebuild_policy": rebuild_policy})
+        response.raise_for_status()
+        return response.json()
+
+    def update_index(
+        self,
+        name: str,
+        n_probe: Optional[int] = None,
+        refine_factor: Optional[int] = None,
+        rebuild_policy: Optional[Dict] = None,
+        coarse_groups: Optional[int] = None,
+        reset: Optional[List[str]] = None,
+    ):
+        """Paramètres de recherche IVF et politique de rebuild, sans rebuild de l'index ;
+        reset : paramètres remis à leur valeur automatique (ex. ["n_probe"])"""
+        url = f"{self.base_url}/collections/{name}/index"
+        data = {field: None for field in reset or []}
+        if n_probe is not None:
+            data["n_probe"] = n_probe
+        if refine_factor is not None:
+            data["refine_factor"] = refine_factor
+        if coarse_groups is not None:
+            data["coarse_groups"] = coarse_groups
+        if rebuild_policy is not None:
+            data["rebuild_policy"] = rebuild_policy
+        response = requests.patch(url, json=data)
+        response.raise_for_status()
+        return response.json()
+
+    def assign_index(
+        self,
+        name: str,
+        embedding: Optional[List[float]] = None,
+        id: Optional[str] = None,
+        n: int = 5,
+    ):
+        """Clusters IVF les plus proches d'un embedding (ou de celui du vecteur id)"""
+        url = f"{self.base_url}/collections/{name}/index/assign"
+        data = {"n": n}
+        if embedding is not None:
+            data["embedding"] = embedding
+        if id is not None:
+            data["id"] = id
+        response = requests.post(url, json=data)
+        response.raise_for_status()
+        return response.json()
+
+    def offload_index(self, name: str):
+        """Listes IVF sur disque ; rappelé, réentraîne l'index et réécrit le fichier"""
+        url = f"{self.base_url}/collections/{name}/index/offload"
+        response = requests.post(url)
+        response.raise_for_status()
+        return response.json()
+
     def delete_collection(self, name: str):
         url = f"{self.base_url}/collections/{name}"
         response = requests.delete(url)
@@ -198,3 +419,46 @@ class PersistentClient:
         response = requests.get(url)
         response.raise_for_status()
         return response.json()
+
+    def get_multi(self, items: List[Dict[str, Any]]):
+        """Get par ids sur plusieurs collections : [{"collection": .., "ids": [..], "include": [..]}]"""
+        url = f"{self.base_url}/get_multi"
+        response = requests.post(url, json={"requests": items})
+        response.raise_for_status()
+        return response.json()["results"]
+
+    def load_test(self, collection: str, duration_secs: float = 10.0, query_rate: float = 100.0,
+                  write_rate: float = 0.0, **options):
+        """Charge synthétique sur une collection (POST /admin/loadtest), rend débit et latences"""
+        url = f"{self.base_url}/admin/loadtest"
+        data = {
+            "collection": collection,
+            "duration_secs": duration_secs,
+            "query_rate": query_rate,
+            "write_rate": write_rate,
+            **options,
+        }
+        response = requests.post(url, json=data, headers=self.admin_headers)
+        response.raise_for_status()
+        return response.json()
+
+    def autotune(self, collection: str, target_recall: float = 0.95, apply: bool = False, **options):
+        """Cherche n_clusters / n_probe atteignant le rappel visé (POST /admin/autotune)"""
+        url = f"{self.base_url}/admin/autotune"
+        data = {
+            "collection": collection,
+            "target_recall": target_recall,
+            "apply": apply,
+            **options,
+        }
+        response = requests.post(url, json=data, headers=self.admin_headers)
+        response.raise_for_status()
+        return response.json()
+
+    def scrub(self, where: Dict[str, Any], reason: Optional[str] = None):
+        """Supprime les éléments correspondant au filtre dans toutes les collections (audit.log) ;
+        les instantanés expédiés ne sont pas réécrits, voir "snapshots_not_scrubbed" """
+        url = f"{self.base_url}/admin/scrub"
+        response = requests.post(url, json={"where": where, "reason": reason}, headers=self.admin_headers)
+        response.raise_for_status()
+        return response.json()